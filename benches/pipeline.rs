@@ -0,0 +1,129 @@
+//! Decision-path benchmarks: `cargo bench --bench pipeline`
+//!
+//! Times the CPU-bound stages every listing goes through (decoding, conversion, scoring, and
+//! the entry decision) over a fixed batch of listings and prints nanoseconds per listing.
+//! `BENCH_SAVE=path` writes the results as a baseline; `BENCH_BASELINE=path` compares against
+//! one and exits non-zero when a stage got more than `BENCH_TOLERANCE_PCT` (default 20)
+//! slower.
+
+use solana_sniper_bot::models::PumpFunListing;
+use solana_sniper_bot::strategy::{TokenEvent, decide};
+use solana_sniper_bot::strategy_config::StrategyConfig;
+use std::hint::black_box;
+use std::time::Instant;
+
+const LISTINGS: usize = 1_000;
+const ROUNDS: usize = 50;
+
+fn listing_json(i: usize) -> String {
+    serde_json::json!({
+        "tokenAddress": format!("{:044}", i),
+        "name": format!("Token {}", i),
+        "symbol": format!("T{}", i),
+        "priceUsd": format!("{}", 0.000_03 + i as f64 * 1e-9),
+        "liquidity": format!("{}", 5_000 + i),
+        "fullyDilutedValuation": format!("{}", 30_000 + i * 10),
+        "createdAt": "1760599400",
+        "creator": "DevWa11et",
+        "initialBuy": format!("{}", 10_000_000 + i * 1_000),
+    })
+    .to_string()
+}
+
+/// Median nanoseconds per listing of `f` over `ROUNDS` passes
+fn bench<T>(inputs: &[T], mut f: impl FnMut(&T)) -> f64 {
+    let mut rounds: Vec<f64> = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for input in inputs {
+                f(input);
+            }
+            start.elapsed().as_nanos() as f64 / inputs.len() as f64
+        })
+        .collect();
+    rounds.sort_by(f64::total_cmp);
+    rounds[ROUNDS / 2]
+}
+
+fn main() {
+    let config = StrategyConfig::default();
+    let raw: Vec<String> = (0..LISTINGS).map(listing_json).collect();
+    let listings: Vec<PumpFunListing> = raw
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect();
+    let events: Vec<TokenEvent> = listings.iter().cloned().map(Into::into).collect();
+
+    let results = [
+        (
+            "decode",
+            bench(&raw, |json| {
+                black_box(serde_json::from_str::<PumpFunListing>(json).unwrap());
+            }),
+        ),
+        (
+            "convert",
+            bench(&listings, |l| {
+                black_box(TokenEvent::from(l.clone()));
+            }),
+        ),
+        (
+            "score",
+            bench(&events, |ev| {
+                black_box(ev.compute_score(&config));
+            }),
+        ),
+        (
+            "decide",
+            bench(&events, |ev| {
+                black_box(decide(ev, &config));
+            }),
+        ),
+    ];
+    for (stage, ns) in &results {
+        println!("{:<8} {:>10.1} ns/listing", stage, ns);
+    }
+
+    if let Ok(path) = std::env::var("BENCH_SAVE") {
+        let lines: Vec<String> = results
+            .iter()
+            .map(|(stage, ns)| format!("{} {}", stage, ns))
+            .collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        println!("saved baseline to {}", path);
+    }
+    if let Ok(path) = std::env::var("BENCH_BASELINE") {
+        let tolerance: f64 = std::env::var("BENCH_TOLERANCE_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20.0);
+        let baseline = std::fs::read_to_string(&path).unwrap();
+        let mut regressed = false;
+        for line in baseline.lines() {
+            let Some((stage, ns)) = line.split_once(' ') else {
+                continue;
+            };
+            let (Ok(before), Some((_, after))) = (
+                ns.parse::<f64>(),
+                results.iter().find(|(name, _)| *name == stage),
+            ) else {
+                continue;
+            };
+            let change_pct = (after - before) / before * 100.0;
+            if change_pct > tolerance {
+                regressed = true;
+                println!(
+                    "{} regressed: {:.1} -> {:.1} ns/listing ({:+.0}%)",
+                    stage, before, after, change_pct
+                );
+            }
+        }
+        if regressed {
+            std::process::exit(1);
+        }
+        println!(
+            "no stage regressed more than {}% against {}",
+            tolerance, path
+        );
+    }
+}
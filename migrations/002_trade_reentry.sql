@@ -0,0 +1 @@
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS is_reentry BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS position_marks (
+  id SERIAL PRIMARY KEY,
+  token_id TEXT,
+  marked_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  price_usd DOUBLE PRECISION,
+  value_usd DOUBLE PRECISION,
+  unrealized_pnl DOUBLE PRECISION,
+  equity_usd DOUBLE PRECISION,
+  drawdown_pct DOUBLE PRECISION
+);
+
+CREATE INDEX IF NOT EXISTS position_marks_token_id_idx ON position_marks (token_id, marked_at);
@@ -0,0 +1,3 @@
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS strategy_id TEXT NOT NULL DEFAULT 'default';
+
+ALTER TABLE position_marks ADD COLUMN IF NOT EXISTS strategy_id TEXT NOT NULL DEFAULT 'default';
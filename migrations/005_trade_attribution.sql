@@ -0,0 +1,3 @@
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS preset TEXT NOT NULL DEFAULT 'default';
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'pumpfun';
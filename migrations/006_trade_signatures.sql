@@ -0,0 +1,3 @@
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS entry_signature TEXT;
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS exit_signature TEXT;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS blacklist (
+  kind TEXT NOT NULL CHECK (kind IN ('wallet', 'mint')),
+  address TEXT NOT NULL,
+  reason TEXT,
+  added_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  PRIMARY KEY (kind, address)
+);
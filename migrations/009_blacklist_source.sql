@@ -0,0 +1 @@
+ALTER TABLE blacklist ADD COLUMN IF NOT EXISTS source TEXT;
@@ -0,0 +1 @@
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS schedule TEXT;
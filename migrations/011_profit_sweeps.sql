@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS profit_sweeps (
+  id SERIAL PRIMARY KEY,
+  strategy_id TEXT NOT NULL,
+  sol DOUBLE PRECISION NOT NULL,
+  destination TEXT NOT NULL,
+  -- NULL for simulated sweeps
+  signature TEXT,
+  swept_at TIMESTAMP WITH TIME ZONE DEFAULT now()
+);
@@ -0,0 +1 @@
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS wallet TEXT;
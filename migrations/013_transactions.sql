@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS transactions (
+  signature TEXT PRIMARY KEY,
+  -- buy, sell, or transfer
+  kind TEXT NOT NULL,
+  wallet TEXT NOT NULL,
+  cluster TEXT NOT NULL,
+  -- 1-based, and each attempt is re-signed with a fresh blockhash
+  attempt INTEGER NOT NULL,
+  -- sent, landed, failed, or expired
+  status TEXT NOT NULL,
+  slot BIGINT,
+  fee_lamports BIGINT,
+  error TEXT,
+  sent_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  finalized_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,2 @@
+-- Milliseconds from detecting a token to first sending its buy
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS detection_latency_ms BIGINT;
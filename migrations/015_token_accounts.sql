@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS token_accounts (
+  address TEXT PRIMARY KEY,
+  wallet TEXT NOT NULL,
+  mint TEXT NOT NULL,
+  -- open, emptied (position sold), or closed
+  status TEXT NOT NULL,
+  -- Reclaimed on close, NULL if the account was closed outside the bot
+  rent_lamports BIGINT,
+  close_signature TEXT,
+  opened_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  closed_at TIMESTAMP WITH TIME ZONE
+);
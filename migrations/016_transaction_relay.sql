@@ -0,0 +1,2 @@
+-- rpc, jito, bloxroute, or private
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS relay TEXT;
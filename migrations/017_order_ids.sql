@@ -0,0 +1,9 @@
+-- Client order IDs make trade recording and execution idempotent across retries
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS client_order_id TEXT;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS exit_order_id TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS trades_client_order_id ON trades (client_order_id);
+CREATE UNIQUE INDEX IF NOT EXISTS trades_exit_order_id ON trades (exit_order_id);
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS order_id TEXT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS last_valid_block_height BIGINT;
+CREATE INDEX IF NOT EXISTS transactions_order_id ON transactions (order_id);
@@ -0,0 +1,16 @@
+-- SOL committed to each position, so open positions can be checked against the ledger
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS sol_in DOUBLE PRECISION;
+
+CREATE TABLE IF NOT EXISTS balance_ledger (
+  id SERIAL PRIMARY KEY,
+  strategy_id TEXT NOT NULL,
+  -- start (a process began trading with balance_sol), buy, sell, or bank
+  kind TEXT NOT NULL,
+  -- Order of the buy or sell, NULL for start and bank entries
+  client_order_id TEXT UNIQUE,
+  delta_sol DOUBLE PRECISION NOT NULL,
+  -- Free balance and SOL in open positions after this entry
+  balance_sol DOUBLE PRECISION NOT NULL,
+  open_sol DOUBLE PRECISION NOT NULL,
+  recorded_at TIMESTAMP WITH TIME ZONE DEFAULT now()
+);
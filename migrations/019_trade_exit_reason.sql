@@ -0,0 +1,2 @@
+-- Why a position was sold (stop_loss, profit_target, lp_spike, graduation)
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS exit_reason TEXT;
@@ -0,0 +1,11 @@
+-- Launches downloaded by `backfill` rather than recorded live, and the provider they came from
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS backfill_source TEXT;
+
+-- Price observations of any token, downloaded by `backfill`, for replaying in backtests
+CREATE TABLE IF NOT EXISTS price_history (
+  token_id TEXT NOT NULL,
+  observed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  price_usd DOUBLE PRECISION NOT NULL,
+  source TEXT,
+  PRIMARY KEY (token_id, observed_at)
+);
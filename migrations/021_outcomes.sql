@@ -0,0 +1,13 @@
+-- What became of each recorded token, labeled a fixed delay after it was seen
+CREATE TABLE IF NOT EXISTS outcomes (
+  token_id TEXT PRIMARY KEY,
+  labeled_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  outcome TEXT NOT NULL,
+  final_price DOUBLE PRECISION,
+  peak_multiple DOUBLE PRECISION,
+  final_multiple DOUBLE PRECISION,
+  liquidity_usd DOUBLE PRECISION,
+  graduated BOOLEAN
+);
+
+CREATE INDEX IF NOT EXISTS price_history_token_id_idx ON price_history (token_id);
@@ -0,0 +1,14 @@
+-- Versions of the outcome-trained scoring model, of which the bot loads the active one
+CREATE TABLE IF NOT EXISTS score_models (
+  version SERIAL PRIMARY KEY,
+  trained_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+  kind TEXT NOT NULL,
+  params TEXT NOT NULL,
+  samples INTEGER,
+  positives INTEGER,
+  validation_auc DOUBLE PRECISION,
+  validation_log_loss DOUBLE PRECISION,
+  active BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS score_models_active_idx ON score_models (active) WHERE active;
@@ -0,0 +1,2 @@
+-- Hypothetical trades of strategies run with --shadow
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS shadow BOOLEAN NOT NULL DEFAULT FALSE;
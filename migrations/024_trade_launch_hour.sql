@@ -0,0 +1,2 @@
+-- UTC hour the traded token launched in, for per-hour analytics
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS launch_hour SMALLINT;
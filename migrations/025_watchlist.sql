@@ -0,0 +1,10 @@
+-- Mints tracked with price snapshots and threshold alerts, whether or not the bot holds them
+CREATE TABLE IF NOT EXISTS watchlist (
+  mint TEXT PRIMARY KEY,
+  added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  note TEXT,
+  alert_above_usd DOUBLE PRECISION,
+  alert_below_usd DOUBLE PRECISION,
+  last_price_usd DOUBLE PRECISION,
+  last_checked_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,11 @@
+-- Alert rules on watched mints (e.g. `liquidity_drop_pct=30`), with the value each is measured from
+CREATE TABLE IF NOT EXISTS watchlist_alerts (
+  id BIGSERIAL PRIMARY KEY,
+  mint TEXT NOT NULL REFERENCES watchlist (mint) ON DELETE CASCADE,
+  rule TEXT NOT NULL,
+  baseline DOUBLE PRECISION,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  last_fired_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS watchlist_alerts_mint_idx ON watchlist_alerts (mint);
@@ -0,0 +1,8 @@
+-- Free-text notes and tags reviewers attach to trades and tokens (e.g. `late_entry`, `social_pump`)
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS note TEXT;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS note TEXT;
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS trades_tags_idx ON trades USING GIN (tags);
+CREATE INDEX IF NOT EXISTS token_events_tags_idx ON token_events USING GIN (tags);
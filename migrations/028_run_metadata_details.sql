@@ -0,0 +1,7 @@
+-- What each run traded with and how much it did, so historical runs can be interpreted
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS config JSONB;
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS version TEXT;
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS git_hash TEXT;
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS args TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS events_seen BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE run_metadata ADD COLUMN IF NOT EXISTS trades_made BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- The creator wallet of each recorded token, and what became of each creator's tokens
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS dev_wallet TEXT;
+
+CREATE INDEX IF NOT EXISTS token_events_dev_wallet_idx ON token_events (dev_wallet);
+
+CREATE TABLE IF NOT EXISTS dev_wallets (
+    address TEXT PRIMARY KEY,
+    tokens_launched INTEGER NOT NULL DEFAULT 0,
+    graduated INTEGER NOT NULL DEFAULT 0,
+    rugged INTEGER NOT NULL DEFAULT 0,
+    -- Divided by tokens_launched for the average peak multiple
+    peak_multiple_sum DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
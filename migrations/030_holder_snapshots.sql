@@ -0,0 +1,10 @@
+-- The top 20 holders of held and watched tokens at each snapshot, for post-mortems
+CREATE TABLE IF NOT EXISTS holder_snapshots (
+  token_id TEXT NOT NULL,
+  snapshot_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  rank SMALLINT NOT NULL,
+  owner TEXT NOT NULL,
+  amount NUMERIC NOT NULL,
+  supply_pct DOUBLE PRECISION NOT NULL,
+  PRIMARY KEY (token_id, snapshot_at, rank)
+);
@@ -0,0 +1,8 @@
+-- Leases that let several instances share one database and wallet
+CREATE TABLE IF NOT EXISTS leases (
+  name TEXT PRIMARY KEY,
+  holder TEXT NOT NULL,
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS leases_holder_idx ON leases (holder);
@@ -0,0 +1,3 @@
+-- How each position was entered: at once, on a dip, or in time-weighted slices
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS entry_type TEXT NOT NULL DEFAULT 'market';
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS entry_slices INTEGER NOT NULL DEFAULT 1;
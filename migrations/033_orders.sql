@@ -0,0 +1,26 @@
+-- Every order the bot places, from the decision to its fill, failure, or cancellation
+CREATE TABLE IF NOT EXISTS orders (
+  client_order_id TEXT PRIMARY KEY,
+  strategy_id TEXT NOT NULL,
+  token_id TEXT NOT NULL,
+  -- buy or sell
+  side TEXT NOT NULL,
+  -- market, dip, twap, or exit
+  order_type TEXT NOT NULL,
+  -- pending, submitted, partially_filled, filled, failed, or cancelled
+  status TEXT NOT NULL,
+  -- SOL a buy is sized at, NULL for sells
+  sol_amount DOUBLE PRECISION,
+  -- SOL spent by a buy or received by a sell so far
+  filled_sol DOUBLE PRECISION NOT NULL DEFAULT 0,
+  signature TEXT,
+  error TEXT,
+  shadow BOOLEAN NOT NULL DEFAULT false,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  -- An order still open after this is closed as timed out
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS orders_open_idx ON orders (expires_at)
+  WHERE status IN ('pending', 'submitted', 'partially_filled');
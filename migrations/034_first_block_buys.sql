@@ -0,0 +1,3 @@
+-- Buys that landed in a token's launch slot besides the create transaction, which backtests
+-- model competing snipers from
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS first_block_buys INTEGER;
@@ -0,0 +1,3 @@
+-- Buys and sells seen on the trade stream after a listing, so backtests can measure momentum
+-- over each strategy's own window
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS trades JSONB;
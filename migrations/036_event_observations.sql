@@ -0,0 +1,7 @@
+-- A mint can be observed more than once, with its data moving on each time, so every
+-- observation is kept as its own row. `id` stays the mint, `generated_at` is when it was
+-- observed, and `event_id` tells observations apart.
+ALTER TABLE token_events DROP CONSTRAINT IF EXISTS token_events_pkey;
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS event_id BIGSERIAL;
+CREATE UNIQUE INDEX IF NOT EXISTS token_events_event_id_idx ON token_events (event_id);
+CREATE INDEX IF NOT EXISTS token_events_id_generated_at_idx ON token_events (id, generated_at DESC);
@@ -0,0 +1,10 @@
+-- The run that wrote each row, so simulations sharing a database can be told apart and
+-- reports scoped to one run. A trade or order belongs to the run that opened it.
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS run_id INTEGER REFERENCES run_metadata (id);
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS run_id INTEGER REFERENCES run_metadata (id);
+ALTER TABLE token_events ADD COLUMN IF NOT EXISTS run_id INTEGER REFERENCES run_metadata (id);
+ALTER TABLE position_marks ADD COLUMN IF NOT EXISTS run_id INTEGER REFERENCES run_metadata (id);
+
+CREATE INDEX IF NOT EXISTS trades_run_id_idx ON trades (run_id);
+CREATE INDEX IF NOT EXISTS token_events_run_id_idx ON token_events (run_id);
+CREATE INDEX IF NOT EXISTS position_marks_run_id_idx ON position_marks (run_id);
@@ -0,0 +1,14 @@
+-- Backtest results by what they were computed from, so identical runs aren't replayed again
+CREATE TABLE IF NOT EXISTS backtest_cache (
+  config_hash TEXT NOT NULL,
+  data_hash TEXT NOT NULL,
+  -- Crate version and commit of the binary that computed the result
+  code_version TEXT NOT NULL,
+  data_from TIMESTAMP WITH TIME ZONE,
+  data_to TIMESTAMP WITH TIME ZONE,
+  result JSONB NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  hits BIGINT NOT NULL DEFAULT 0,
+  last_hit_at TIMESTAMP WITH TIME ZONE,
+  PRIMARY KEY (config_hash, data_hash, code_version)
+);
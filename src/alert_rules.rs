@@ -0,0 +1,172 @@
+//! User-defined alert rules on watchlist tokens, written as `KIND=VALUE`:
+//! - `price_crosses=X`: the USD price moves from one side of X to the other
+//! - `liquidity_drop_pct=Y`: liquidity falls Y% below its high since the rule last fired
+//! - `holders_multiple=N`: the holder count reaches N times its count when the rule last fired
+//!
+//! Each rule keeps a baseline (the last price, the liquidity high, or the holder count) that
+//! the watchlist tracker stores between checks. A rule that fires starts over from the current
+//! value, so it reports each move once.
+
+use anyhow::{Result, anyhow, bail};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertRule {
+    PriceCrosses(f64),
+    LiquidityDropPct(f64),
+    HoldersMultiple(f64),
+}
+
+impl FromStr for AlertRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("alert rule '{}' must look like KIND=VALUE", s))?;
+        let value: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("alert rule '{}' needs a number", s))?;
+        if !(value > 0.0 && value.is_finite()) {
+            bail!("alert rule '{}' needs a positive number", s);
+        }
+        match kind.trim() {
+            "price_crosses" => Ok(AlertRule::PriceCrosses(value)),
+            "liquidity_drop_pct" if value < 100.0 => Ok(AlertRule::LiquidityDropPct(value)),
+            "liquidity_drop_pct" => bail!("liquidity_drop_pct must be below 100"),
+            "holders_multiple" if value > 1.0 => Ok(AlertRule::HoldersMultiple(value)),
+            "holders_multiple" => bail!("holders_multiple must be above 1"),
+            other => bail!(
+                "unknown alert rule '{}' (expected price_crosses, liquidity_drop_pct, or holders_multiple)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertRule::PriceCrosses(v) => write!(f, "price_crosses={}", v),
+            AlertRule::LiquidityDropPct(v) => write!(f, "liquidity_drop_pct={}", v),
+            AlertRule::HoldersMultiple(v) => write!(f, "holders_multiple={}", v),
+        }
+    }
+}
+
+/// A watched token's state at one check; fields are unset when the lookup failed
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub price_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    pub holders: Option<u64>,
+}
+
+impl AlertRule {
+    /// Whether checking the rule needs the holder count, which takes an RPC scan
+    pub fn needs_holders(&self) -> bool {
+        matches!(self, AlertRule::HoldersMultiple(_))
+    }
+
+    /// Check the rule against an observation; returns the new baseline and the alert, if any
+    pub fn check(&self, baseline: Option<f64>, obs: &Observation) -> (Option<f64>, Option<String>) {
+        let value = match self {
+            AlertRule::PriceCrosses(_) => obs.price_usd,
+            AlertRule::LiquidityDropPct(_) => obs.liquidity_usd,
+            AlertRule::HoldersMultiple(_) => obs.holders.map(|h| h as f64),
+        };
+        let Some(value) = value else {
+            return (baseline, None);
+        };
+        let Some(base) = baseline else {
+            return (Some(value), None);
+        };
+        let alert = match *self {
+            AlertRule::PriceCrosses(level) if (base < level) != (value < level) => Some(format!(
+                "price {} ${} (${} -> ${})",
+                if value >= level {
+                    "rose above"
+                } else {
+                    "fell below"
+                },
+                level,
+                base,
+                value
+            )),
+            AlertRule::LiquidityDropPct(pct)
+                if base > 0.0 && value <= base * (1.0 - pct / 100.0) =>
+            {
+                Some(format!(
+                    "liquidity down {:.1}% (${:.0} -> ${:.0})",
+                    (base - value) / base * 100.0,
+                    base,
+                    value
+                ))
+            }
+            AlertRule::HoldersMultiple(multiple) if base > 0.0 && value >= base * multiple => Some(
+                format!("holders up {:.1}x ({} -> {})", value / base, base, value),
+            ),
+            _ => None,
+        };
+        let baseline = match self {
+            AlertRule::LiquidityDropPct(_) if alert.is_none() => base.max(value),
+            AlertRule::HoldersMultiple(_) if alert.is_none() => base,
+            _ => value,
+        };
+        (Some(baseline), alert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_fire_once_per_move() {
+        assert!("price_above=1".parse::<AlertRule>().is_err());
+        assert!("holders_multiple=1".parse::<AlertRule>().is_err());
+        let rule: AlertRule = "liquidity_drop_pct=30".parse().unwrap();
+        assert_eq!(rule.to_string(), "liquidity_drop_pct=30");
+
+        let liquidity = |usd: f64| Observation {
+            liquidity_usd: Some(usd),
+            ..Observation::default()
+        };
+        // The high rises to 12k, so 9k is only 25% down; 8k is 33% down
+        let (base, alert) = rule.check(None, &liquidity(10_000.0));
+        assert_eq!((base, alert), (Some(10_000.0), None));
+        let (base, _) = rule.check(base, &liquidity(12_000.0));
+        assert_eq!(rule.check(base, &liquidity(9_000.0)).1, None);
+        let (base, alert) = rule.check(base, &liquidity(8_000.0));
+        assert_eq!(
+            alert.as_deref(),
+            Some("liquidity down 33.3% ($12000 -> $8000)")
+        );
+        assert_eq!(rule.check(base, &liquidity(7_000.0)).1, None);
+
+        let rule = AlertRule::PriceCrosses(0.002);
+        let price = |usd: f64| Observation {
+            price_usd: Some(usd),
+            ..Observation::default()
+        };
+        let (base, _) = rule.check(None, &price(0.0015));
+        let (base, alert) = rule.check(base, &price(0.0021));
+        assert!(alert.unwrap().starts_with("price rose above $0.002"));
+        assert_eq!(rule.check(base, &price(0.0025)).1, None);
+        // A failed lookup keeps the baseline
+        assert_eq!(rule.check(base, &Observation::default()), (base, None));
+
+        let rule = AlertRule::HoldersMultiple(2.0);
+        let holders = |n: u64| Observation {
+            holders: Some(n),
+            ..Observation::default()
+        };
+        let (base, _) = rule.check(None, &holders(150));
+        let (base, _) = rule.check(base, &holders(250));
+        let (base, alert) = rule.check(base, &holders(310));
+        assert_eq!(alert.as_deref(), Some("holders up 2.1x (150 -> 310)"));
+        assert_eq!(base, Some(310.0));
+    }
+}
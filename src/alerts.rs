@@ -0,0 +1,343 @@
+//! Watchdog for operational anomalies that would otherwise fail silently
+//!
+//! Every `ALERT_INTERVAL_SECS` the watchdog evaluates:
+//! - `no_tokens`: PumpPortal was polled for `ALERT_NO_TOKENS_MINS` without a single new token
+//! - `ws_reconnects`: `ALERT_WS_RECONNECTS` failed or dropped WebSocket connections within
+//!   `ALERT_WINDOW_MINS`
+//! - `db_write_failures`: `ALERT_DB_WRITE_FAILURES` failed batch writes within the window
+//! - `low_balance`: free SOL across strategies below `ALERT_MIN_BALANCE_SOL`
+//! - `stuck_position`: a position open longer than `ALERT_MAX_HOLD_MINS`
+//! - `strategy_paused`: a strategy's entries paused after a losing streak
+//!
+//! An alert is logged and sent through the notifier once when its condition starts, and once
+//! more when it clears.
+
+use crate::api::SharedSnapshot;
+use crate::config::Config;
+use crate::control::SharedControl;
+use crate::notifier::Notifier;
+use crate::pumpportal::MessageCounters;
+use crate::write_behind::WriteCounters;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// Thresholds of every rule; a zero count or duration disables its rule
+#[derive(Debug, Clone)]
+pub struct AlertRules {
+    pub no_tokens: Duration,
+    pub window: Duration,
+    pub ws_reconnects: u64,
+    pub db_write_failures: u64,
+    pub min_balance_sol: Option<f64>,
+    pub max_hold: chrono::Duration,
+}
+
+impl AlertRules {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            no_tokens: Duration::from_secs(cfg.alert_no_tokens_mins * 60),
+            window: Duration::from_secs(cfg.alert_window_mins * 60),
+            ws_reconnects: cfg.alert_ws_reconnects,
+            db_write_failures: cfg.alert_db_write_failures,
+            min_balance_sol: cfg.alert_min_balance_sol,
+            max_hold: chrono::Duration::minutes(cfg.alert_max_hold_mins as i64),
+        }
+    }
+}
+
+/// Running totals read at each check
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counts {
+    pub connects: u64,
+    pub new_tokens: u64,
+    pub ws_drops: u64,
+    pub write_failures: u64,
+}
+
+/// What one check observes
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    pub counts: Counts,
+    /// Unset until the simulator has published a portfolio snapshot
+    pub sol_balance: Option<f64>,
+    /// Open positions as `token [strategy]` with when they were opened
+    pub positions: Vec<(String, DateTime<Utc>)>,
+    /// Strategies with paused entries, with why
+    pub paused: Vec<(String, String)>,
+}
+
+pub struct AlertState {
+    rules: AlertRules,
+    /// Counts of earlier checks, oldest first, spanning at least the longest rule
+    history: VecDeque<(Instant, Counts)>,
+    /// Alerts currently raised, by name, with their message
+    firing: BTreeMap<String, String>,
+}
+
+impl AlertState {
+    pub fn new(rules: AlertRules) -> Self {
+        Self {
+            rules,
+            history: VecDeque::new(),
+            firing: BTreeMap::new(),
+        }
+    }
+
+    /// Latest recorded counts at least `span` before `now`
+    fn counts_before(&self, now: Instant, span: Duration) -> Option<Counts> {
+        let cutoff = now.checked_sub(span)?;
+        self.history
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= cutoff)
+            .map(|(_, counts)| *counts)
+    }
+
+    /// Record `sample` and return a message for every alert that started or cleared
+    pub fn evaluate(
+        &mut self,
+        sample: &Sample,
+        now: Instant,
+        now_utc: DateTime<Utc>,
+    ) -> Vec<String> {
+        let rules = &self.rules;
+        let counts = sample.counts;
+        let mut active = BTreeMap::new();
+
+        if !rules.no_tokens.is_zero()
+            && let Some(before) = self.counts_before(now, rules.no_tokens)
+            && counts.connects > before.connects
+            && counts.new_tokens == before.new_tokens
+        {
+            active.insert(
+                "no_tokens".to_string(),
+                format!(
+                    "no new tokens in {} min of polling",
+                    rules.no_tokens.as_secs() / 60
+                ),
+            );
+        }
+
+        // Rate rules count from the start of the window, or of the process if that is later
+        let since = self
+            .counts_before(now, rules.window)
+            .or_else(|| self.history.front().map(|(_, counts)| *counts))
+            .unwrap_or_default();
+        let window_mins = rules.window.as_secs() / 60;
+        let ws_drops = counts.ws_drops - since.ws_drops;
+        if rules.ws_reconnects > 0 && ws_drops >= rules.ws_reconnects {
+            active.insert(
+                "ws_reconnects".to_string(),
+                format!(
+                    "{} failed or dropped WebSocket connections in {} min",
+                    ws_drops, window_mins
+                ),
+            );
+        }
+        let write_failures = counts.write_failures - since.write_failures;
+        if rules.db_write_failures > 0 && write_failures >= rules.db_write_failures {
+            active.insert(
+                "db_write_failures".to_string(),
+                format!(
+                    "{} batched database writes failed in {} min",
+                    write_failures, window_mins
+                ),
+            );
+        }
+
+        if let (Some(min), Some(balance)) = (rules.min_balance_sol, sample.sol_balance)
+            && balance < min
+        {
+            active.insert(
+                "low_balance".to_string(),
+                format!("{:.4} SOL free, below {} SOL", balance, min),
+            );
+        }
+
+        if rules.max_hold > chrono::Duration::zero() {
+            for (position, opened_at) in &sample.positions {
+                let held = now_utc - *opened_at;
+                if held > rules.max_hold {
+                    active.insert(
+                        format!("stuck_position {}", position),
+                        format!("open for {} min", held.num_minutes()),
+                    );
+                }
+            }
+        }
+
+        for (strategy_id, reason) in &sample.paused {
+            active.insert(
+                format!("strategy_paused {}", strategy_id),
+                format!("entries paused after {}", reason),
+            );
+        }
+
+        self.history.push_back((now, counts));
+        let keep = rules.no_tokens.max(rules.window);
+        while self.history.len() > 1
+            && now
+                .checked_sub(keep)
+                .is_some_and(|cutoff| self.history[1].0 <= cutoff)
+        {
+            self.history.pop_front();
+        }
+
+        let mut messages = Vec::new();
+        for (name, message) in &active {
+            if !self.firing.contains_key(name) {
+                messages.push(format!("ALERT {}: {}", name, message));
+            }
+        }
+        for name in self.firing.keys() {
+            if !active.contains_key(name) {
+                messages.push(format!("RESOLVED {}", name));
+            }
+        }
+        self.firing = active;
+        messages
+    }
+}
+
+pub struct Watchdog {
+    state: AlertState,
+    ws: Arc<MessageCounters>,
+    writes: Arc<WriteCounters>,
+    snapshot: SharedSnapshot,
+    control: SharedControl,
+    notifier: Option<Arc<Notifier>>,
+    interval: Duration,
+}
+
+impl Watchdog {
+    pub fn new(
+        rules: AlertRules,
+        ws: Arc<MessageCounters>,
+        writes: Arc<WriteCounters>,
+        snapshot: SharedSnapshot,
+        control: SharedControl,
+        notifier: Option<Arc<Notifier>>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            state: AlertState::new(rules),
+            ws,
+            writes,
+            snapshot,
+            control,
+            notifier,
+            interval,
+        }
+    }
+
+    /// Evaluate the rules each interval until the process exits
+    pub async fn run(mut self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            let sample = self.sample().await;
+            for message in self.state.evaluate(&sample, Instant::now(), Utc::now()) {
+                println!("[alert] {}", message);
+                if let Some(notifier) = &self.notifier {
+                    notifier.send(&format!("memebot {}", message)).await;
+                }
+            }
+        }
+    }
+
+    async fn sample(&self) -> Sample {
+        let get = |c: &std::sync::atomic::AtomicU64| c.load(Ordering::Relaxed);
+        let counts = Counts {
+            connects: get(&self.ws.connects),
+            new_tokens: get(&self.ws.new_tokens),
+            ws_drops: get(&self.ws.connect_failures) + get(&self.ws.disconnects),
+            write_failures: get(&self.writes.failed),
+        };
+        let snapshot = self.snapshot.read().await;
+        Sample {
+            counts,
+            sol_balance: snapshot.updated_at.map(|_| snapshot.sol_balance),
+            positions: snapshot
+                .positions
+                .iter()
+                .map(|p| (format!("{} [{}]", p.token_id, p.strategy_id), p.opened_at))
+                .collect(),
+            paused: self
+                .control
+                .paused_strategies(Utc::now())
+                .into_iter()
+                .map(|p| (p.strategy_id, p.reason))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raises_alerts_once_and_resolves_them() {
+        let mut state = AlertState::new(AlertRules {
+            no_tokens: Duration::from_secs(600),
+            window: Duration::from_secs(600),
+            ws_reconnects: 3,
+            db_write_failures: 1,
+            min_balance_sol: Some(0.1),
+            max_hold: chrono::Duration::minutes(120),
+        });
+        let start = Instant::now() + Duration::from_secs(3_600);
+        let now_utc = Utc::now();
+        let at = |mins: u64| start + Duration::from_secs(mins * 60);
+        let mut sample = Sample {
+            sol_balance: Some(1.0),
+            ..Default::default()
+        };
+
+        assert!(state.evaluate(&sample, at(0), now_utc).is_empty());
+        // Polling keeps connecting but nothing is listed, and connections keep dropping
+        sample.counts.connects = 120;
+        sample.counts.ws_drops = 3;
+        assert_eq!(
+            state.evaluate(&sample, at(5), now_utc),
+            ["ALERT ws_reconnects: 3 failed or dropped WebSocket connections in 10 min"]
+        );
+        sample.counts.connects = 240;
+        assert_eq!(
+            state.evaluate(&sample, at(10), now_utc),
+            ["ALERT no_tokens: no new tokens in 10 min of polling"]
+        );
+
+        // Tokens arrive again, the drops age out of the window, and a position gets stuck
+        sample.counts.new_tokens = 4;
+        sample.sol_balance = Some(0.05);
+        sample.positions = vec![(
+            "MintA [default]".to_string(),
+            now_utc - chrono::Duration::minutes(150),
+        )];
+        assert_eq!(
+            state.evaluate(&sample, at(16), now_utc),
+            [
+                "ALERT low_balance: 0.0500 SOL free, below 0.1 SOL",
+                "ALERT stuck_position MintA [default]: open for 150 min",
+                "RESOLVED no_tokens",
+                "RESOLVED ws_reconnects",
+            ]
+        );
+        assert!(state.evaluate(&sample, at(17), now_utc).is_empty());
+
+        sample.paused = vec![("default".to_string(), "3 stop-losses in a row".to_string())];
+        assert_eq!(
+            state.evaluate(&sample, at(18), now_utc),
+            ["ALERT strategy_paused default: entries paused after 3 stop-losses in a row"]
+        );
+        sample.paused.clear();
+        assert_eq!(
+            state.evaluate(&sample, at(19), now_utc),
+            ["RESOLVED strategy_paused default"]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! `analyze <MINT>`: run the enrichment pipeline for one token and explain how each
+//! strategy would score and decide on it
+//!
+//! With the database reachable, the creator's history from `dev_wallets` is shown too. Top
+//! holders funded from the same place are merged into clusters (see `funding`). Every
+//! enrichment step runs, whatever the `ENABLE_*` settings.
+
+use crate::dev_wallets;
+use crate::funding::{HolderCluster, Tracer};
+use crate::portfolio_manager::{PortfolioManager, StrategySlot};
+use crate::scanner::TokenDataProvider;
+use crate::signals::ExternalSignal;
+use crate::simulator::{Enrichment, enrich_mint};
+use crate::strategy::{TokenEvent, decide, decide_with_score};
+use anyhow::Result;
+use sqlx::PgPool;
+
+pub async fn run<P: TokenDataProvider>(
+    scanner: &P,
+    mint: &str,
+    manager: &mut PortfolioManager,
+    pool: Option<&PgPool>,
+    funding: &Tracer,
+) -> Result<()> {
+    ExternalSignal {
+        mint: mint.to_string(),
+        confidence: None,
+        source: None,
+    }
+    .validate()?;
+
+    let mut ev = enrich_mint(scanner, mint, &Enrichment::default()).await;
+    let dev_history = match (pool, &ev.dev_wallet_address) {
+        (None, _) => "unknown (no database)".to_string(),
+        (Some(_), None) => "unknown".to_string(),
+        (Some(pool), Some(dev)) => match dev_wallets::lookup(pool, dev).await {
+            Ok(Some(stats)) => {
+                let summary = format!(
+                    "{} tokens, {:.0}% graduated, avg peak {:.1}x, {} rugged",
+                    stats.tokens_launched,
+                    stats.graduation_rate * 100.0,
+                    stats.avg_peak_multiple,
+                    stats.rug_count
+                );
+                ev.dev_stats = Some(stats);
+                summary
+            }
+            Ok(None) => "none recorded".to_string(),
+            Err(e) => format!("lookup failed: {}", e),
+        },
+    };
+    let clusters = funding.clusters(scanner, mint).await.unwrap_or_default();
+    ev.effective_top_holder_pct = clusters.first().map(|c| c.supply_pct);
+    print_token(&ev);
+    println!("  {:<18} {}", "Dev history:", dev_history);
+    print_clusters(&ev, &clusters);
+    for slot in &mut manager.slots {
+        println!();
+        print_decision(slot, &ev);
+    }
+    Ok(())
+}
+
+fn print_token(ev: &TokenEvent) {
+    let row = |label: &str, value: String| println!("  {:<18} {}", format!("{}:", label), value);
+    let or_unknown = |v: Option<String>| v.unwrap_or_else(|| "unknown".to_string());
+    let authority = |active: bool| if active { "ACTIVE" } else { "revoked" };
+
+    println!("Token {}", ev.id);
+    row(
+        "Age",
+        or_unknown(
+            ev.token_age_secs
+                .map(|s| format!("{}m {}s", s / 60, s % 60)),
+        ),
+    );
+    row("Holders", ev.holders.to_string());
+    row(
+        "Dev hold",
+        format!(
+            "{:.2}% ({})",
+            ev.dev_hold_pct,
+            or_unknown(ev.dev_wallet_address.clone())
+        ),
+    );
+    row(
+        "Dev initial buy",
+        or_unknown(ev.dev_initial_buy_pct.map(|p| format!("{:.2}%", p))),
+    );
+    row("Liquidity", format!("${:.0}", ev.liquidity_usd));
+    row("Market cap", format!("${:.0}", ev.market_cap_usd));
+    row("Price", format!("${}", ev.base_price));
+    row(
+        "Bonding curve",
+        or_unknown(
+            ev.bonding_curve_progress_pct
+                .map(|p| format!("{:.1}% sold", p)),
+        ),
+    );
+    row("Mint authority", authority(ev.upgradeable).to_string());
+    // Holders of a token with a live freeze authority can be blocked from selling
+    row(
+        "Freeze authority",
+        if ev.freeze_authority {
+            "ACTIVE (possible honeypot)".to_string()
+        } else {
+            authority(false).to_string()
+        },
+    );
+    row(
+        "Momentum",
+        format!("{}, graduation: {}", ev.momentum, ev.graduation),
+    );
+}
+
+fn print_clusters(ev: &TokenEvent, clusters: &[HolderCluster]) {
+    let Some(effective) = ev.effective_top_holder_pct else {
+        println!("  {:<18} unknown", "Top holder:");
+        return;
+    };
+    println!(
+        "  {:<18} {:.2}% after merging wallets with a common funder",
+        "Top holder:", effective
+    );
+    for cluster in clusters.iter().filter(|c| c.wallets.len() > 1) {
+        println!(
+            "    {} wallets hold {:.2}%, funded by {}",
+            cluster.wallets.len(),
+            cluster.supply_pct,
+            cluster.funder.as_deref().unwrap_or("?")
+        );
+        for wallet in &cluster.wallets {
+            println!("      {}", wallet);
+        }
+    }
+}
+
+fn print_decision(slot: &mut StrategySlot, ev: &TokenEvent) {
+    let (id, config) = (&slot.id, &slot.config);
+    println!("[{}] Score breakdown", id);
+    if ev.is_dev_known_rugger {
+        println!("  known_rugger     score forced to 0");
+    } else {
+        for (rule, points) in ev.score_breakdown(config) {
+            println!("  {:<16} {:+.1}", rule, points);
+        }
+    }
+    let mut decision = decide(ev, config);
+    if let Some(plugin) = slot.plugin.as_mut() {
+        match plugin.evaluate(ev, decision.score) {
+            Ok(verdict) => {
+                println!(
+                    "  {:<16} {:.1} -> {:.1}{}",
+                    "plugin",
+                    decision.score,
+                    verdict.score,
+                    if verdict.allow { "" } else { " (vetoed)" }
+                );
+                decision = decide_with_score(ev, config, verdict.score);
+                decision.should_buy &= verdict.allow;
+            }
+            Err(e) => {
+                println!("  {:<16} failed: {}", "plugin", e);
+                decision.should_buy = false;
+            }
+        }
+    }
+    println!(
+        "  {:<16} {:.1} (min {:.1})",
+        "score", decision.score, config.min_score_to_buy
+    );
+    if decision.should_buy {
+        println!(
+            "[{}] Decision: BUY {:.3} SOL ({} sizing, {:.0}%)",
+            id,
+            config.max_sol_per_trade * decision.size_fraction,
+            config.position_sizing,
+            decision.size_fraction * 100.0
+        );
+        return;
+    }
+    println!("[{}] Decision: SKIP", id);
+    for r in &decision.rejections {
+        println!("  {}: {}", r.filter, r.detail);
+    }
+    if decision.rejections.is_empty() && slot.plugin.is_none() {
+        if decision.score < config.min_score_to_buy {
+            println!("  score below minimum");
+        } else {
+            println!("  needs momentum or graduation");
+        }
+    }
+}
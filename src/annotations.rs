@@ -0,0 +1,172 @@
+//! Notes and tags on trades and tokens, for reviewing the trade journal
+//!
+//! Tags are lowercased with spaces turned into underscores, so "Social pump" and
+//! `social_pump` are the same tag. `report --tag` limits analytics to trades carrying a tag,
+//! themselves or through their token, and `backfill --export` writes tokens' notes and tags.
+
+use crate::db::LATEST_EVENTS;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub enum AnnotateCommand {
+    /// Annotate a trade by its id in the trades table
+    Trade {
+        id: i32,
+        #[structopt(flatten)]
+        edit: Annotation,
+    },
+    /// Annotate a token by its mint
+    Token {
+        mint: String,
+        #[structopt(flatten)]
+        edit: Annotation,
+    },
+    /// List annotated trades and tokens
+    List {
+        /// only those with this tag
+        #[structopt(long)]
+        tag: Option<String>,
+    },
+}
+
+/// A change to a trade's or token's annotations, as accepted by the API
+#[derive(StructOpt, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Annotation {
+    /// replace the note
+    #[structopt(long)]
+    pub note: Option<String>,
+    /// add a tag (repeatable)
+    #[structopt(long = "tag", number_of_values = 1)]
+    pub tags: Vec<String>,
+    /// remove a tag (repeatable)
+    #[structopt(long = "untag", number_of_values = 1)]
+    pub untag: Vec<String>,
+}
+
+/// Normalize a tag; errors on an empty one
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag
+        .trim()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_");
+    if tag.is_empty() {
+        bail!("empty tag");
+    }
+    Ok(tag)
+}
+
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    tags.iter().map(|t| normalize_tag(t)).collect()
+}
+
+/// A trade or token with a note or tags
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Annotated {
+    /// `trade` or `token`
+    pub kind: String,
+    /// Trade id or mint
+    pub id: String,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Keeps the note unless replaced, and the tags sorted without duplicates
+const UPDATE_SET: &str = "note = COALESCE($2, note), \
+     tags = ARRAY(SELECT DISTINCT t FROM unnest(tags || $3::TEXT[]) t \
+     WHERE t <> ALL($4::TEXT[]) ORDER BY t)";
+
+/// Apply `edit` to a trade; false if there is no such trade
+pub async fn annotate_trade(pool: &PgPool, id: i32, edit: &Annotation) -> Result<bool> {
+    let updated = sqlx::query(&format!("UPDATE trades SET {} WHERE id = $1", UPDATE_SET))
+        .bind(id)
+        .bind(&edit.note)
+        .bind(normalize_tags(&edit.tags)?)
+        .bind(normalize_tags(&edit.untag)?)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(updated > 0)
+}
+
+/// Apply `edit` to a token; false if the token was never recorded
+pub async fn annotate_token(pool: &PgPool, mint: &str, edit: &Annotation) -> Result<bool> {
+    let updated = sqlx::query(&format!(
+        "UPDATE token_events SET {} WHERE id = $1",
+        UPDATE_SET
+    ))
+    .bind(mint)
+    .bind(&edit.note)
+    .bind(normalize_tags(&edit.tags)?)
+    .bind(normalize_tags(&edit.untag)?)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(updated > 0)
+}
+
+/// Annotated trades, then tokens, optionally only those with `tag`
+pub async fn list(pool: &PgPool, tag: Option<&str>) -> Result<Vec<Annotated>> {
+    let tag = tag.map(normalize_tag).transpose()?;
+    Ok(sqlx::query_as(&format!(
+        "SELECT 'trade' AS kind, id::TEXT AS id, note, tags FROM trades \
+         WHERE (note IS NOT NULL OR tags <> '{{}}') AND ($1::TEXT IS NULL OR $1 = ANY(tags)) \
+         UNION ALL \
+         SELECT 'token', id, note, tags FROM {} e \
+         WHERE (note IS NOT NULL OR tags <> '{{}}') AND ($1::TEXT IS NULL OR $1 = ANY(tags)) \
+         ORDER BY kind DESC, id",
+        LATEST_EVENTS
+    ))
+    .bind(tag)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn run(pool: &PgPool, cmd: AnnotateCommand) -> Result<()> {
+    match cmd {
+        AnnotateCommand::Trade { id, edit } => {
+            if !annotate_trade(pool, id, &edit).await? {
+                bail!("no trade {}", id);
+            }
+            println!("Annotated trade {}", id);
+        }
+        AnnotateCommand::Token { mint, edit } => {
+            if !annotate_token(pool, &mint, &edit).await? {
+                bail!("token {} was never recorded", mint);
+            }
+            println!("Annotated token {}", mint);
+        }
+        AnnotateCommand::List { tag } => {
+            let annotated = list(pool, tag.as_deref()).await?;
+            println!("{:<6} {:<44} {:<30} note", "kind", "id", "tags");
+            for a in &annotated {
+                println!(
+                    "{:<6} {:<44} {:<30} {}",
+                    a.kind,
+                    a.id,
+                    a.tags.join(","),
+                    a.note.as_deref().unwrap_or_default()
+                );
+            }
+            println!("{} annotated", annotated.len());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(normalize_tag(" Social  pump ").unwrap(), "social_pump");
+        assert_eq!(normalize_tag("late_entry").unwrap(), "late_entry");
+        assert!(normalize_tag("  ").is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use crate::alert_rules::AlertRule;
+use crate::annotations::{self, Annotated, Annotation};
+use crate::approvals::ApprovalRequest;
+use crate::auth::{ApiKeys, Denied, Scope};
+use crate::blacklist::validate_address;
+use crate::control::{SharedControl, StrategyPause};
+use crate::degrade::Degradation;
+use crate::health::{HealthReport, SharedHealth};
+use crate::latency::LatencyStats;
+use crate::portfolio_manager::GlobalHeadroom;
+use crate::queue::QueueCounters;
+use crate::regime::RegimeReading;
+use crate::repo::{OpenOrder, OrderRepo};
+use crate::signals::{ExternalSignal, SignalSender};
+use crate::watchlist::{self, AlertEntry, WatchlistEntry};
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{FromRef, Path, Query, Request, State},
+    http::{Method, StatusCode, header::AUTHORIZATION},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Latest mark-to-market view of a single open position
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionSnapshot {
+    pub strategy_id: String,
+    pub token_id: String,
+    pub entry_price: f64,
+    pub last_price: f64,
+    pub qty: f64,
+    pub usd_in: f64,
+    pub value_usd: f64,
+    pub unrealized_pnl: f64,
+    /// Wallet holding the position in live mode
+    pub wallet: Option<String>,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Portfolio state published by the simulator after each mark-to-market pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PortfolioSnapshot {
+    pub sol_balance: f64,
+    /// Profits set aside from trading and not yet swept
+    pub banked_sol: f64,
+    /// Profits sent to the sweep address
+    pub swept_sol: f64,
+    pub equity_usd: f64,
+    pub realized_pnl_usd: f64,
+    pub unrealized_pnl_usd: f64,
+    pub peak_equity_usd: f64,
+    pub drawdown_pct: f64,
+    pub positions: Vec<PositionSnapshot>,
+    /// Market regime when the snapshot was taken
+    pub regime: Option<RegimeReading>,
+    /// When the simulator last published the snapshot; unset before its first pass
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Exposure and room left under the limits, served on its own by `/risk`
+    pub risk: RiskSnapshot,
+}
+
+/// Exposure of the live strategies and how far they are from their entry limits
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RiskSnapshot {
+    pub sol_balance: f64,
+    pub sol_at_risk: f64,
+    pub usd_at_risk: f64,
+    /// Open positions, largest first
+    pub positions: Vec<PositionExposure>,
+    /// Share of equity in the largest position (0.0-1.0)
+    pub largest_position_pct: f64,
+    /// Equity change since the first snapshot of the UTC day
+    pub daily_pnl_usd: f64,
+    /// Room left under the global limits
+    pub headroom: GlobalHeadroom,
+    /// Room left before each strategy's losing-streak pause
+    pub breakers: Vec<BreakerDistance>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionExposure {
+    pub strategy_id: String,
+    pub token_id: String,
+    pub sol_in: f64,
+    pub value_usd: f64,
+    /// Share of equity (0.0-1.0)
+    pub equity_pct: f64,
+}
+
+/// Distance to a strategy's losing-streak pause; limits that are off are unset
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerDistance {
+    pub strategy_id: String,
+    pub stop_losses_left: Option<u32>,
+    /// Further fall below the equity high allowed (0.0-1.0)
+    pub drawdown_left_pct: Option<f64>,
+}
+
+pub type SharedSnapshot = Arc<RwLock<PortfolioSnapshot>>;
+
+#[derive(Clone)]
+struct ApiState {
+    snapshot: SharedSnapshot,
+    queue: Arc<QueueCounters>,
+    latency: Arc<LatencyStats>,
+    degradation: Arc<Degradation>,
+    health: SharedHealth,
+    signals: SignalSender,
+    control: SharedControl,
+}
+
+impl FromRef<ApiState> for SharedSnapshot {
+    fn from_ref(state: &ApiState) -> Self {
+        state.snapshot.clone()
+    }
+}
+
+impl FromRef<ApiState> for Arc<QueueCounters> {
+    fn from_ref(state: &ApiState) -> Self {
+        state.queue.clone()
+    }
+}
+
+impl FromRef<ApiState> for Arc<LatencyStats> {
+    fn from_ref(state: &ApiState) -> Self {
+        state.latency.clone()
+    }
+}
+
+impl FromRef<ApiState> for Arc<Degradation> {
+    fn from_ref(state: &ApiState) -> Self {
+        state.degradation.clone()
+    }
+}
+
+impl FromRef<ApiState> for SharedHealth {
+    fn from_ref(state: &ApiState) -> Self {
+        state.health.clone()
+    }
+}
+
+impl FromRef<ApiState> for SignalSender {
+    fn from_ref(state: &ApiState) -> Self {
+        state.signals.clone()
+    }
+}
+
+impl FromRef<ApiState> for SharedControl {
+    fn from_ref(state: &ApiState) -> Self {
+        state.control.clone()
+    }
+}
+
+/// Serve the HTTP API until the process exits
+#[allow(clippy::too_many_arguments)]
+pub async fn serve(
+    addr: &str,
+    snapshot: SharedSnapshot,
+    queue: Arc<QueueCounters>,
+    latency: Arc<LatencyStats>,
+    degradation: Arc<Degradation>,
+    health: SharedHealth,
+    signals: SignalSender,
+    control: SharedControl,
+    pool: Option<PgPool>,
+    keys: ApiKeys,
+) -> Result<()> {
+    let mut app = Router::new()
+        .route("/portfolio", get(portfolio))
+        .route("/risk", get(risk))
+        .route("/metrics", get(metrics))
+        .route("/health", get(health_report))
+        .route("/signals", post(submit_signal))
+        .route("/pauses", get(pauses))
+        .route("/strategies/:id/resume", post(resume_strategy))
+        .route("/approvals", get(list_approvals))
+        .route("/approvals/:id/approve", post(approve))
+        .route("/approvals/:id/reject", post(reject))
+        .route("/orders/:id/cancel", post(cancel_order))
+        .with_state(ApiState {
+            snapshot,
+            queue,
+            latency,
+            degradation,
+            health,
+            signals,
+            control,
+        });
+    // The watchlist, annotations, and orders live in the database
+    if let Some(pool) = pool {
+        let db_routes = Router::new()
+            .route("/orders", get(list_orders))
+            .route("/watchlist", get(list_watchlist).post(add_to_watchlist))
+            .route("/watchlist/:mint", delete(remove_from_watchlist))
+            .route("/watchlist/alerts", get(list_alerts))
+            .route("/watchlist/:mint/alerts", post(add_alert))
+            .route("/watchlist/alerts/:id", delete(remove_alert))
+            .route("/annotations", get(list_annotations))
+            .route("/trades/:id/annotations", post(annotate_trade))
+            .route("/tokens/:mint/annotations", post(annotate_token))
+            .with_state(pool);
+        app = app.merge(db_routes);
+    }
+    let app = app.layer(middleware::from_fn_with_state(
+        Arc::new(keys),
+        require_scope,
+    ));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("API listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Turn away requests without a key for their scope: `read` for GETs, `control` otherwise
+async fn require_scope(State(keys): State<Arc<ApiKeys>>, request: Request, next: Next) -> Response {
+    let needed = if request.method() == Method::GET || request.method() == Method::HEAD {
+        Scope::Read
+    } else {
+        Scope::Control
+    };
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    match keys.authorize(header, needed) {
+        Ok(()) => next.run(request).await,
+        Err(Denied::Unauthenticated) => {
+            (StatusCode::UNAUTHORIZED, "missing or unknown API key").into_response()
+        }
+        Err(Denied::Forbidden) => (
+            StatusCode::FORBIDDEN,
+            format!("this needs a {} key", needed.as_str()),
+        )
+            .into_response(),
+    }
+}
+
+async fn portfolio(State(snapshot): State<SharedSnapshot>) -> Json<PortfolioSnapshot> {
+    Json(snapshot.read().await.clone())
+}
+
+async fn risk(State(snapshot): State<SharedSnapshot>) -> Json<RiskSnapshot> {
+    Json(snapshot.read().await.risk.clone())
+}
+
+/// Dependency health; 503 while entries are paused
+async fn health_report(State(health): State<SharedHealth>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.report();
+    let status = if report.entries_paused {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report))
+}
+
+/// Strategies whose entries are paused after a losing streak
+async fn pauses(State(control): State<SharedControl>) -> Json<Vec<StrategyPause>> {
+    Json(control.paused_strategies(Utc::now()))
+}
+
+/// Resume a paused strategy's entries
+async fn resume_strategy(
+    State(control): State<SharedControl>,
+    Path(id): Path<String>,
+) -> (StatusCode, String) {
+    match control.resume_strategy(&id) {
+        Some(pause) => {
+            println!("[{}] Entries resumed through the API", id);
+            (
+                StatusCode::OK,
+                format!("resumed (paused: {})", pause.reason),
+            )
+        }
+        None => (StatusCode::NOT_FOUND, format!("{} is not paused", id)),
+    }
+}
+
+/// Live buys waiting for approval
+async fn list_approvals(State(control): State<SharedControl>) -> Json<Vec<ApprovalRequest>> {
+    Json(control.approvals().pending())
+}
+
+async fn approve(
+    State(control): State<SharedControl>,
+    Path(id): Path<u64>,
+) -> (StatusCode, String) {
+    answer_approval(&control, id, true)
+}
+
+async fn reject(State(control): State<SharedControl>, Path(id): Path<u64>) -> (StatusCode, String) {
+    answer_approval(&control, id, false)
+}
+
+fn answer_approval(control: &SharedControl, id: u64, approved: bool) -> (StatusCode, String) {
+    match control.approvals().resolve(id, approved) {
+        Some(request) => (
+            StatusCode::OK,
+            format!(
+                "{} {} SOL of {}",
+                if approved { "approved" } else { "rejected" },
+                request.sol_amount,
+                request.token_id
+            ),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("approval {} is not pending", id),
+        ),
+    }
+}
+
+/// Orders that haven't filled, failed, or been cancelled
+async fn list_orders(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<OpenOrder>>, (StatusCode, String)> {
+    OrderRepo::list_open(&pool)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Cancel the rest of a dip or sliced entry the next time its strategy works its orders
+async fn cancel_order(
+    State(control): State<SharedControl>,
+    Path(id): Path<String>,
+) -> (StatusCode, String) {
+    control.cancel_order(&id);
+    println!("[orders] Cancellation of {} requested through the API", id);
+    (StatusCode::ACCEPTED, format!("cancelling {}", id))
+}
+
+/// Watched mints with their last price
+async fn list_watchlist(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<WatchlistEntry>>, (StatusCode, String)> {
+    watchlist::list(&pool)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Watch a mint, or change an existing entry's alerts and note
+async fn add_to_watchlist(
+    State(pool): State<PgPool>,
+    Json(entry): Json<WatchlistEntry>,
+) -> (StatusCode, String) {
+    if let Err(e) = entry.validate() {
+        return (StatusCode::BAD_REQUEST, e.to_string());
+    }
+    match watchlist::add(&pool, &entry).await {
+        Ok(()) => (StatusCode::OK, format!("watching {}", entry.mint)),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn remove_from_watchlist(
+    State(pool): State<PgPool>,
+    Path(mint): Path<String>,
+) -> (StatusCode, String) {
+    match watchlist::remove(&pool, &mint).await {
+        Ok(true) => (StatusCode::OK, format!("removed {}", mint)),
+        Ok(false) => (StatusCode::NOT_FOUND, format!("{} is not watched", mint)),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn list_alerts(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<AlertEntry>>, (StatusCode, String)> {
+    watchlist::list_alerts(&pool)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+#[derive(Deserialize)]
+struct NewAlert {
+    rule: String,
+}
+
+/// Add an alert rule to a mint, watching it if needed
+async fn add_alert(
+    State(pool): State<PgPool>,
+    Path(mint): Path<String>,
+    Json(alert): Json<NewAlert>,
+) -> (StatusCode, String) {
+    let rule: AlertRule = match alert.rule.parse() {
+        Ok(rule) => rule,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    if let Err(e) = validate_address(&mint) {
+        return (StatusCode::BAD_REQUEST, e.to_string());
+    }
+    match watchlist::add_alert(&pool, &mint, &rule).await {
+        Ok(id) => (StatusCode::OK, id.to_string()),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn remove_alert(State(pool): State<PgPool>, Path(id): Path<i64>) -> (StatusCode, String) {
+    match watchlist::remove_alert(&pool, id).await {
+        Ok(true) => (StatusCode::OK, format!("removed alert rule {}", id)),
+        Ok(false) => (StatusCode::NOT_FOUND, format!("no alert rule {}", id)),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct TagQuery {
+    tag: Option<String>,
+}
+
+/// Annotated trades and tokens, optionally only those with `?tag=`
+async fn list_annotations(
+    State(pool): State<PgPool>,
+    Query(query): Query<TagQuery>,
+) -> Result<Json<Vec<Annotated>>, (StatusCode, String)> {
+    annotations::list(&pool, query.tag.as_deref())
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Replace a trade's note and add or remove its tags
+async fn annotate_trade(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Json(edit): Json<Annotation>,
+) -> (StatusCode, String) {
+    match annotations::annotate_trade(&pool, id, &edit).await {
+        Ok(true) => (StatusCode::OK, format!("annotated trade {}", id)),
+        Ok(false) => (StatusCode::NOT_FOUND, format!("no trade {}", id)),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn annotate_token(
+    State(pool): State<PgPool>,
+    Path(mint): Path<String>,
+    Json(edit): Json<Annotation>,
+) -> (StatusCode, String) {
+    match annotations::annotate_token(&pool, &mint, &edit).await {
+        Ok(true) => (StatusCode::OK, format!("annotated token {}", mint)),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            format!("token {} was never recorded", mint),
+        ),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Queue an external buy signal for the next scan cycle
+async fn submit_signal(
+    State(signals): State<SignalSender>,
+    Json(signal): Json<ExternalSignal>,
+) -> (StatusCode, String) {
+    if let Err(e) = signal.validate() {
+        return (StatusCode::BAD_REQUEST, e.to_string());
+    }
+    match signals.try_send(signal) {
+        Ok(()) => (StatusCode::ACCEPTED, "queued".to_string()),
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "signal queue is full".to_string(),
+        ),
+    }
+}
+
+/// Prometheus text exposition of the portfolio gauges, listing queue counters, pipeline
+/// latency histograms, and enrichment degradation
+async fn metrics(
+    State(snapshot): State<SharedSnapshot>,
+    State(queue): State<Arc<QueueCounters>>,
+    State(latency): State<Arc<LatencyStats>>,
+    State(degradation): State<Arc<Degradation>>,
+) -> String {
+    let s = snapshot.read().await;
+    let gauges = [
+        ("memebot_sol_balance", s.sol_balance),
+        ("memebot_banked_sol", s.banked_sol),
+        ("memebot_swept_sol", s.swept_sol),
+        ("memebot_equity_usd", s.equity_usd),
+        ("memebot_realized_pnl_usd", s.realized_pnl_usd),
+        ("memebot_unrealized_pnl_usd", s.unrealized_pnl_usd),
+        ("memebot_drawdown_pct", s.drawdown_pct),
+        ("memebot_open_positions", s.positions.len() as f64),
+    ];
+    let mut out = String::new();
+    for (name, value) in gauges {
+        out.push_str(&format!("# TYPE {} gauge\n{} {}\n", name, name, value));
+    }
+    let counters = [
+        ("memebot_listings_admitted_total", &queue.admitted),
+        ("memebot_listings_dropped_stale_total", &queue.dropped_stale),
+    ];
+    for (name, value) in counters {
+        let value = value.load(std::sync::atomic::Ordering::Relaxed);
+        out.push_str(&format!("# TYPE {} counter\n{} {}\n", name, name, value));
+    }
+    out.push_str(&latency.render_prometheus());
+    out.push_str(&degradation.render_prometheus());
+    out
+}
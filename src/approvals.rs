@@ -0,0 +1,342 @@
+//! Operator approval of large live buys
+//!
+//! With `APPROVAL_THRESHOLD_SOL` set, a live buy of more SOL waits for an operator. The
+//! request is posted to Telegram with Approve and Reject buttons and listed at
+//! `GET /approvals`, where `POST /approvals/:id/approve` or `/reject` answers it. A buy that
+//! isn't approved within `APPROVAL_TIMEOUT_SECS` is skipped. The strategy's other work waits
+//! with it, so the timeout should stay short.
+
+use crate::strategy::FilterRejection;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// A live buy waiting for an operator
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: u64,
+    pub strategy_id: String,
+    pub token_id: String,
+    pub sol_amount: f64,
+    pub score: f64,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Buys waiting for approval, answered through Telegram or the API
+#[derive(Default)]
+pub struct Approvals {
+    /// Unset when every buy goes out without asking
+    threshold_sol: Option<f64>,
+    timeout: Duration,
+    telegram: Option<Telegram>,
+    next_id: AtomicU64,
+    pending: Mutex<BTreeMap<u64, (ApprovalRequest, oneshot::Sender<bool>)>>,
+}
+
+impl Approvals {
+    pub fn new(threshold_sol: Option<f64>, timeout: Duration) -> Self {
+        Self {
+            threshold_sol,
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Post requests to a Telegram chat with buttons to answer them; `Telegram::run` must
+    /// poll for the answers
+    pub fn with_telegram(mut self, bot_token: String, chat_id: String) -> Self {
+        self.telegram = Some(Telegram::new(bot_token, chat_id));
+        self
+    }
+
+    pub fn telegram(&self) -> Option<&Telegram> {
+        self.telegram.as_ref()
+    }
+
+    /// Wait for an operator to approve a live buy over the threshold; the `approval`
+    /// rejection if it was rejected or timed out
+    pub async fn check(
+        &self,
+        strategy_id: &str,
+        token_id: &str,
+        sol_amount: f64,
+        score: f64,
+    ) -> Option<FilterRejection> {
+        if self.threshold_sol.is_none_or(|max| sol_amount <= max) {
+            return None;
+        }
+        let now = Utc::now();
+        let request = ApprovalRequest {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            strategy_id: strategy_id.to_string(),
+            token_id: token_id.to_string(),
+            sol_amount,
+            score,
+            requested_at: now,
+            expires_at: now + chrono::Duration::from_std(self.timeout).unwrap_or_default(),
+        };
+        let (reply, answer) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(request.id, (request.clone(), reply));
+        println!(
+            "[{}] Waiting up to {}s for approval #{} of {} SOL into {}",
+            strategy_id,
+            self.timeout.as_secs(),
+            request.id,
+            sol_amount,
+            token_id
+        );
+        if let Some(telegram) = &self.telegram
+            && let Err(e) = telegram.ask(&request).await
+        {
+            println!("[approvals] Telegram request failed: {}", e);
+        }
+
+        let detail = match tokio::time::timeout(self.timeout, answer).await {
+            Ok(Ok(true)) => return None,
+            Ok(_) => "rejected by the operator".to_string(),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request.id);
+                format!("not approved within {}s", self.timeout.as_secs())
+            }
+        };
+        Some(FilterRejection::new(
+            "approval",
+            format!("{} SOL {}", sol_amount, detail),
+        ))
+    }
+
+    /// Answer a pending request; `None` if it doesn't exist or already expired
+    pub fn resolve(&self, id: u64, approved: bool) -> Option<ApprovalRequest> {
+        let (request, reply) = self.pending.lock().unwrap().remove(&id)?;
+        // The buy stopped waiting if its receiver is gone
+        reply.send(approved).ok()?;
+        println!(
+            "[{}] Approval #{} {}",
+            request.strategy_id,
+            id,
+            if approved { "approved" } else { "rejected" }
+        );
+        Some(request)
+    }
+
+    /// Requests waiting for an answer, oldest first
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(request, _)| request.clone())
+            .collect()
+    }
+}
+
+/// `approve:<id>` or `reject:<id>` from an inline button
+fn parse_callback(data: &str) -> Option<(u64, bool)> {
+    let (action, id) = data.split_once(':')?;
+    let approved = match action {
+        "approve" => true,
+        "reject" => false,
+        _ => return None,
+    };
+    Some((id.parse().ok()?, approved))
+}
+
+#[derive(Debug, Deserialize)]
+struct Updates {
+    result: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackQuery {
+    id: String,
+    data: Option<String>,
+    message: Option<CallbackMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackMessage {
+    chat: Chat,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Approval requests as Telegram messages with inline buttons
+pub struct Telegram {
+    client: Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl Telegram {
+    fn new(bot_token: String, chat_id: String) -> Self {
+        // Long polls hold the request open for 30 seconds
+        let client = Client::builder()
+            .timeout(Duration::from_secs(40))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+        Self {
+            client,
+            bot_token,
+            chat_id,
+        }
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("https://api.telegram.org/bot{}/{}", self.bot_token, method)
+    }
+
+    async fn ask(&self, request: &ApprovalRequest) -> Result<()> {
+        let text = format!(
+            "Approve live buy #{}: {} SOL of {} for {} (score {:.1})? Skipped at {}",
+            request.id,
+            request.sol_amount,
+            request.token_id,
+            request.strategy_id,
+            request.score,
+            request.expires_at.format("%H:%M:%S UTC")
+        );
+        let body = serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "reply_markup": { "inline_keyboard": [[
+                { "text": "Approve", "callback_data": format!("approve:{}", request.id) },
+                { "text": "Reject", "callback_data": format!("reject:{}", request.id) },
+            ]] },
+        });
+        let status = self
+            .client
+            .post(self.url("sendMessage"))
+            .json(&body)
+            .send()
+            .await?
+            .status();
+        if !status.is_success() {
+            bail!("Telegram returned {}", status);
+        }
+        Ok(())
+    }
+
+    /// Answer requests from button presses in the configured chat, until the process exits
+    pub async fn run(&self, approvals: &Approvals) {
+        let mut offset = 0;
+        loop {
+            match self.poll(offset).await {
+                Ok(updates) => {
+                    for update in updates {
+                        offset = offset.max(update.update_id + 1);
+                        if let Some(query) = update.callback_query {
+                            self.answer(approvals, query).await;
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!("[approvals] Telegram poll failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }
+
+    async fn poll(&self, offset: i64) -> Result<Vec<Update>> {
+        let body = serde_json::json!({
+            "offset": offset,
+            "timeout": 30,
+            "allowed_updates": ["callback_query"],
+        });
+        let resp = self
+            .client
+            .post(self.url("getUpdates"))
+            .json(&body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            bail!("Telegram returned {}", resp.status());
+        }
+        Ok(resp.json::<Updates>().await?.result)
+    }
+
+    async fn answer(&self, approvals: &Approvals, query: CallbackQuery) {
+        // Only the operators' chat may answer
+        let from_chat = query
+            .message
+            .is_some_and(|m| m.chat.id.to_string() == self.chat_id);
+        let text = match query.data.as_deref().and_then(parse_callback) {
+            Some((id, approved)) if from_chat => match approvals.resolve(id, approved) {
+                Some(_) if approved => "Approved",
+                Some(_) => "Rejected",
+                None => "Already answered or expired",
+            },
+            _ => "Not an approval",
+        };
+        let body = serde_json::json!({ "callback_query_id": query.id, "text": text });
+        if let Err(e) = self
+            .client
+            .post(self.url("answerCallbackQuery"))
+            .json(&body)
+            .send()
+            .await
+        {
+            println!("[approvals] Telegram answer failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn waits_for_large_buys_only() {
+        let approvals = Arc::new(Approvals::new(Some(0.5), Duration::from_millis(200)));
+        assert!(
+            approvals
+                .check("default", "small", 0.5, 70.0)
+                .await
+                .is_none()
+        );
+
+        let waiting = {
+            let approvals = approvals.clone();
+            tokio::spawn(async move { approvals.check("default", "large", 1.0, 70.0).await })
+        };
+        while approvals.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let id = approvals.pending()[0].id;
+        assert_eq!(approvals.resolve(id, true).unwrap().token_id, "large");
+        assert!(waiting.await.unwrap().is_none());
+        assert!(approvals.resolve(id, true).is_none());
+
+        let expired = approvals
+            .check("default", "large", 1.0, 70.0)
+            .await
+            .unwrap();
+        assert_eq!(expired.filter, "approval");
+        assert!(approvals.pending().is_empty());
+
+        assert_eq!(parse_callback("approve:7"), Some((7, true)));
+        assert_eq!(parse_callback("reject:7"), Some((7, false)));
+        assert_eq!(parse_callback("approve:x"), None);
+    }
+}
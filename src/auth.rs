@@ -0,0 +1,135 @@
+//! Scoped API tokens for the HTTP API and the gRPC control plane
+//!
+//! `API_KEYS` lists `SCOPE:TOKEN` pairs, e.g. `read:dashboard-token,control:ops-token`.
+//! Requests carry a token as `Authorization: Bearer <token>`. A `read` token can only look:
+//! HTTP `GET`s and the gRPC getters and streams. A `control` token can also start and stop
+//! trading, change parameters, resume strategies, answer approvals, and submit signals.
+//! Without `API_KEYS` both interfaces stay open to anyone who can reach them.
+
+use anyhow::{Result, anyhow, bail};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Control,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Control => "control",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "read" => Ok(Scope::Read),
+            "control" => Ok(Scope::Control),
+            other => bail!("unknown scope '{}' (expected read or control)", other),
+        }
+    }
+}
+
+/// Why a request was turned away
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Denied {
+    /// No token, or one that isn't configured
+    Unauthenticated,
+    /// A valid token without the scope
+    Forbidden,
+}
+
+/// Tokens and their scopes; empty when the interfaces are open
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiKeys {
+    keys: Vec<(Scope, String)>,
+}
+
+impl ApiKeys {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Check an `Authorization` header value against the scope a request needs
+    pub fn authorize(&self, header: Option<&str>, needed: Scope) -> Result<(), Denied> {
+        if self.keys.is_empty() {
+            return Ok(());
+        }
+        let token = header
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or(Denied::Unauthenticated)?;
+        let scope = self
+            .keys
+            .iter()
+            .filter(|(_, key)| constant_time_eq(key.as_bytes(), token.as_bytes()))
+            .map(|(scope, _)| *scope)
+            .max()
+            .ok_or(Denied::Unauthenticated)?;
+        if scope < needed {
+            return Err(Denied::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+/// Compare tokens without leaking how much of a guess matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl FromStr for ApiKeys {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut keys = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (scope, token) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("key must look like SCOPE:TOKEN"))?;
+            let token = token.trim();
+            if token.len() < 16 {
+                bail!("{} token is shorter than 16 characters", scope.trim());
+            }
+            keys.push((scope.trim().parse()?, token.to_string()));
+        }
+        Ok(Self { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_tokens_also_read() {
+        let keys: ApiKeys = "read:dashboard-0123456789, control:operator-0123456789"
+            .parse()
+            .unwrap();
+        let read = Some("Bearer dashboard-0123456789");
+        let control = Some("Bearer operator-0123456789");
+        assert_eq!(keys.authorize(read, Scope::Read), Ok(()));
+        assert_eq!(keys.authorize(read, Scope::Control), Err(Denied::Forbidden));
+        assert_eq!(keys.authorize(control, Scope::Control), Ok(()));
+        assert_eq!(keys.authorize(control, Scope::Read), Ok(()));
+        assert_eq!(
+            keys.authorize(Some("Bearer guess"), Scope::Read),
+            Err(Denied::Unauthenticated)
+        );
+        assert_eq!(
+            keys.authorize(None, Scope::Read),
+            Err(Denied::Unauthenticated)
+        );
+        assert_eq!(ApiKeys::default().authorize(None, Scope::Control), Ok(()));
+
+        assert!("admin:operator-0123456789".parse::<ApiKeys>().is_err());
+        assert!("read:short".parse::<ApiKeys>().is_err());
+        assert!("operator-0123456789".parse::<ApiKeys>().is_err());
+    }
+}
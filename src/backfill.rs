@@ -0,0 +1,526 @@
+//! Historical backfill of pump.fun launches and their prices, so backtests can replay weeks of
+//! data instead of only what the bot happened to record live
+//!
+//! Launches come from Bitquery (`BITQUERY_API_KEY`), and minute prices after each launch from
+//! Birdeye (`BIRDEYE_API_KEY`). Launches are stored in `token_events` with `backfill_source`
+//! set and prices in `price_history`; rows already present are kept, so runs may overlap.
+//! `export` writes stored tokens with their prices, price times, and annotations in the backtest
+//! JSON format.
+//!
+//! Backfill only learns a launch's mint, symbol, time, and prices, so its market cap is derived
+//! from the first price and the remaining enrichment fields are reported as missing.
+
+use crate::db::{EVENT_COLUMNS, FIRST_EVENTS, stored_event};
+use crate::models::PUMPFUN_TOTAL_SUPPLY;
+use crate::storage::{Location, Storage};
+use crate::strategy::TokenEvent;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use solana_sniper_bot::backtest::BacktestToken;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use std::collections::HashSet;
+
+const BITQUERY_URL: &str = "https://streaming.bitquery.io/eap";
+const BIRDEYE_HISTORY_URL: &str = "https://public-api.birdeye.so/defi/history_price";
+const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const SOURCE: &str = "bitquery+birdeye";
+/// Launches requested per Bitquery page
+const PAGE_SIZE: usize = 100;
+/// Pause between Birdeye requests, within its public rate limit
+const BIRDEYE_DELAY: std::time::Duration = std::time::Duration::from_millis(1_100);
+/// Enrichment fields a backfilled launch has no data for
+const UNKNOWN_FIELDS: [&str; 6] = [
+    "liquidity",
+    "holders",
+    "dev_hold",
+    "authorities",
+    "dev_initial_buy",
+    "bonding_curve_progress",
+];
+
+const LAUNCHES_QUERY: &str = r#"query ($since: DateTime, $till: DateTime, $limit: Int) {
+  Solana {
+    TokenSupplyUpdates(
+      where: {Instruction: {Program: {Address: {is: "PROGRAM"}, Method: {is: "create"}}}, Block: {Time: {since: $since, till: $till}}}
+      orderBy: {ascending: Block_Time}
+      limit: {count: $limit}
+    ) {
+      Block { Time }
+      TokenSupplyUpdate { Currency { MintAddress Symbol } }
+    }
+  }
+}"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Launch {
+    pub mint: String,
+    pub symbol: String,
+    pub launched_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PricePoint {
+    pub at: DateTime<Utc>,
+    pub price_usd: f64,
+}
+
+impl Launch {
+    /// The launch as the scanner would have scored it at its first recorded price
+    pub fn to_event(&self, first: &PricePoint) -> TokenEvent {
+        TokenEvent {
+            id: self.mint.clone(),
+            source: "pumpfun".to_string(),
+            token_type: self.symbol.clone(),
+            market_cap_usd: first.price_usd * PUMPFUN_TOTAL_SUPPLY,
+            dev_hold_pct: 0.0,
+            liquidity_usd: 0.0,
+            holders: 0,
+            upgradeable: false,
+            freeze_authority: false,
+            momentum: false,
+            graduation: false,
+            base_price: first.price_usd,
+            priced_at_ms: Some(first.at.timestamp_millis()),
+            dev_wallet_address: None,
+            is_dev_known_rugger: false,
+            token_age_secs: Some((first.at - self.launched_at).num_seconds().max(0) as u64),
+            launched_at: Some(self.launched_at.timestamp()),
+            dev_initial_buy_pct: None,
+            bonding_curve_progress_pct: None,
+            signal_confidence: None,
+            blacklisted: false,
+            missing_fields: UNKNOWN_FIELDS.iter().map(|f| f.to_string()).collect(),
+            dev_stats: None,
+            effective_top_holder_pct: None,
+            transfer_fee_pct: None,
+            round_trip_tax_pct: None,
+            first_block_buys: None,
+            trades: None,
+        }
+    }
+}
+
+// Bitquery structures
+#[derive(Debug, Deserialize)]
+struct BitqueryResponse {
+    data: Option<BitqueryData>,
+    #[serde(default)]
+    errors: Vec<BitqueryError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitqueryError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BitqueryData {
+    solana: BitquerySolana,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BitquerySolana {
+    token_supply_updates: Vec<SupplyUpdate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SupplyUpdate {
+    block: BitqueryBlock,
+    token_supply_update: TokenSupplyUpdate,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BitqueryBlock {
+    time: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TokenSupplyUpdate {
+    currency: BitqueryCurrency,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BitqueryCurrency {
+    mint_address: String,
+    #[serde(default)]
+    symbol: String,
+}
+
+// Birdeye structures
+#[derive(Debug, Deserialize)]
+struct BirdeyeResponse {
+    #[serde(default)]
+    success: bool,
+    data: Option<BirdeyeHistory>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BirdeyeHistory {
+    items: Vec<BirdeyeItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BirdeyeItem {
+    unix_time: i64,
+    value: f64,
+}
+
+/// Launches in a Bitquery `TokenSupplyUpdates` response, oldest first
+pub fn parse_launches(body: &str) -> Result<Vec<Launch>> {
+    let resp: BitqueryResponse = serde_json::from_str(body)?;
+    if !resp.errors.is_empty() {
+        let messages: Vec<&str> = resp.errors.iter().map(|e| e.message.as_str()).collect();
+        bail!("Bitquery: {}", messages.join("; "));
+    }
+    let Some(data) = resp.data else {
+        bail!("Bitquery returned no data");
+    };
+    Ok(data
+        .solana
+        .token_supply_updates
+        .into_iter()
+        .map(|u| Launch {
+            mint: u.token_supply_update.currency.mint_address,
+            symbol: u.token_supply_update.currency.symbol,
+            launched_at: u.block.time,
+        })
+        .collect())
+}
+
+/// Positive prices in a Birdeye `history_price` response
+pub fn parse_prices(body: &str) -> Result<Vec<PricePoint>> {
+    let resp: BirdeyeResponse = serde_json::from_str(body)?;
+    let Some(data) = resp.data.filter(|_| resp.success) else {
+        bail!(
+            "Birdeye: {}",
+            resp.message.as_deref().unwrap_or("request failed")
+        );
+    };
+    Ok(data
+        .items
+        .into_iter()
+        .filter(|item| item.value > 0.0)
+        .filter_map(|item| {
+            Some(PricePoint {
+                at: DateTime::from_timestamp(item.unix_time, 0)?,
+                price_usd: item.value,
+            })
+        })
+        .collect())
+}
+
+pub struct Backfill {
+    client: Client,
+    bitquery_key: String,
+    birdeye_key: String,
+}
+
+impl Backfill {
+    pub fn new(bitquery_key: String, birdeye_key: String) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+        Self {
+            client,
+            bitquery_key,
+            birdeye_key,
+        }
+    }
+
+    /// Download up to `limit` launches since `since`, with `track` of prices after each, and
+    /// store those that have any prices
+    pub async fn run(
+        &self,
+        pool: &PgPool,
+        since: DateTime<Utc>,
+        limit: usize,
+        track: Duration,
+    ) -> Result<()> {
+        let launches = self.launches(since, Utc::now(), limit).await?;
+        let mints: Vec<&str> = launches.iter().map(|l| l.mint.as_str()).collect();
+        let stored: HashSet<String> =
+            sqlx::query("SELECT DISTINCT token_id FROM price_history WHERE token_id = ANY($1)")
+                .bind(&mints)
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|r| r.get("token_id"))
+                .collect();
+        println!(
+            "[backfill] {} launches since {}, {} already stored",
+            launches.len(),
+            since.format("%Y-%m-%d %H:%M UTC"),
+            stored.len()
+        );
+
+        let (mut saved, mut without_prices, mut failed) = (0, 0, 0);
+        for launch in launches.iter().filter(|l| !stored.contains(&l.mint)) {
+            tokio::time::sleep(BIRDEYE_DELAY).await;
+            let prices = match self
+                .prices(&launch.mint, launch.launched_at, launch.launched_at + track)
+                .await
+            {
+                Ok(prices) => prices,
+                Err(e) => {
+                    println!("[backfill] Prices for {} failed: {}", launch.mint, e);
+                    failed += 1;
+                    continue;
+                }
+            };
+            let Some(first) = prices.first() else {
+                without_prices += 1;
+                continue;
+            };
+            store(pool, launch, &launch.to_event(first), &prices).await?;
+            saved += 1;
+            if saved % 25 == 0 {
+                println!("[backfill] Stored {} launches", saved);
+            }
+        }
+        println!(
+            "[backfill] Stored {} launches ({} without prices, {} failed)",
+            saved, without_prices, failed
+        );
+        Ok(())
+    }
+
+    /// Launches from `since` to `till`, paging through Bitquery oldest first
+    async fn launches(
+        &self,
+        since: DateTime<Utc>,
+        till: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Launch>> {
+        let query = LAUNCHES_QUERY.replace("PROGRAM", PUMP_PROGRAM_ID);
+        let mut launches = Vec::new();
+        let mut seen = HashSet::new();
+        let mut from = since;
+        while launches.len() < limit {
+            let body = serde_json::json!({
+                "query": query,
+                "variables": {
+                    "since": from.to_rfc3339(),
+                    "till": till.to_rfc3339(),
+                    "limit": PAGE_SIZE,
+                },
+            });
+            let resp = self
+                .client
+                .post(BITQUERY_URL)
+                .bearer_auth(&self.bitquery_key)
+                .json(&body)
+                .send()
+                .await?;
+            let status = resp.status();
+            if !status.is_success() {
+                bail!("Bitquery returned {}", status);
+            }
+            let page = parse_launches(&resp.text().await?)?;
+            let full = page.len() == PAGE_SIZE;
+            // Pages start at the previous page's last second, so it is listed again
+            let new: Vec<Launch> = page
+                .into_iter()
+                .filter(|l| seen.insert(l.mint.clone()))
+                .collect();
+            let Some(last) = new.last() else { break };
+            from = last.launched_at;
+            launches.extend(new);
+            if !full {
+                break;
+            }
+        }
+        launches.truncate(limit);
+        Ok(launches)
+    }
+
+    /// One-minute prices of `mint` from `from` to `to`
+    async fn prices(
+        &self,
+        mint: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<PricePoint>> {
+        let resp = self
+            .client
+            .get(BIRDEYE_HISTORY_URL)
+            .query(&[
+                ("address", mint),
+                ("address_type", "token"),
+                ("type", "1m"),
+                ("time_from", &from.timestamp().to_string()),
+                ("time_to", &to.timestamp().to_string()),
+            ])
+            .header("X-API-KEY", &self.birdeye_key)
+            .header("x-chain", "solana")
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("Birdeye returned {}", status);
+        }
+        parse_prices(&resp.text().await?)
+    }
+}
+
+async fn store(
+    pool: &PgPool,
+    launch: &Launch,
+    event: &TokenEvent,
+    prices: &[PricePoint],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO token_events (id, generated_at, token_type, market_cap_usd, base_price, backfill_source) \
+         SELECT $1, to_timestamp($2), $3, $4, $5, $6 \
+         WHERE NOT EXISTS (SELECT 1 FROM token_events WHERE id = $1)",
+    )
+    .bind(&event.id)
+    .bind(launch.launched_at.timestamp() as f64)
+    .bind(&event.token_type)
+    .bind(event.market_cap_usd)
+    .bind(event.base_price)
+    .bind(SOURCE)
+    .execute(&mut *tx)
+    .await?;
+    let mut query = QueryBuilder::<Postgres>::new(
+        "INSERT INTO price_history (token_id, observed_at, price_usd, source) ",
+    );
+    query.push_values(prices, |mut b, point| {
+        b.push_bind(&event.id)
+            .push("to_timestamp(")
+            .push_bind_unseparated(point.at.timestamp() as f64)
+            .push_unseparated(")")
+            .push_bind(point.price_usd)
+            .push_bind(SOURCE);
+    });
+    query.push(" ON CONFLICT DO NOTHING");
+    query.build().execute(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Write every token generated since `since` that has price history as backtest JSON;
+/// returns how many were written
+pub async fn export(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    out: &Location,
+    storage: &Storage,
+) -> Result<usize> {
+    let rows = sqlx::query(&format!(
+        "SELECT {}, e.backfill_source, e.note, e.tags, \
+         EXTRACT(EPOCH FROM e.generated_at)::BIGINT AS generated_at_secs, \
+         EXTRACT(EPOCH FROM p.first_at - e.generated_at)::BIGINT AS age_secs, \
+         p.prices, p.observed_at \
+         FROM {} e CROSS JOIN LATERAL (SELECT MIN(observed_at) AS first_at, \
+         ARRAY_AGG(price_usd ORDER BY observed_at) AS prices, \
+         ARRAY_AGG(EXTRACT(EPOCH FROM observed_at)::BIGINT ORDER BY observed_at) AS observed_at \
+         FROM price_history WHERE token_id = e.id) p \
+         WHERE p.first_at IS NOT NULL AND e.generated_at >= to_timestamp($1) \
+         ORDER BY e.generated_at",
+        EVENT_COLUMNS, FIRST_EVENTS
+    ))
+    .bind(since.timestamp() as f64)
+    .fetch_all(pool)
+    .await?;
+
+    let tokens: Vec<BacktestToken> = rows
+        .into_iter()
+        .map(|r| {
+            let prices: Vec<f64> = r.get("prices");
+            let mut event = stored_event(&r);
+            if event.base_price <= 0.0 {
+                event.base_price = prices[0];
+            }
+            // Only a backfilled launch time is exact enough to age the token by
+            if r.get::<Option<String>, _>("backfill_source").is_some() {
+                event.token_age_secs = Some(r.get::<i64, _>("age_secs").max(0) as u64);
+                event.launched_at = Some(r.get("generated_at_secs"));
+                event.missing_fields.retain(|f| f != "token_age");
+            }
+            BacktestToken {
+                event,
+                prices,
+                observed_at: r.get("observed_at"),
+                note: r.get("note"),
+                tags: r.get("tags"),
+            }
+        })
+        .collect();
+
+    storage
+        .put(out, serde_json::to_vec_pretty(&tokens)?)
+        .await?;
+    Ok(tokens.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sniper_bot::backtest::run_backtest;
+    use solana_sniper_bot::strategy_config::StrategyConfig;
+
+    #[test]
+    fn parses_launches_and_prices_into_backtest_tokens() {
+        let launches = parse_launches(include_str!(
+            "../tests/data/backfill/bitquery_launches.json"
+        ))
+        .unwrap();
+        assert_eq!(launches.len(), 2);
+        assert_eq!(launches[0].symbol, "MCAT");
+        assert_eq!(
+            launches[0].launched_at,
+            "2026-10-01T12:00:05Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let prices =
+            parse_prices(include_str!("../tests/data/backfill/birdeye_history.json")).unwrap();
+        // The zero price is dropped
+        assert_eq!(prices.len(), 4);
+        let event = launches[0].to_event(&prices[0]);
+        assert_eq!(event.token_age_secs, Some(55));
+        assert!((event.market_cap_usd - 31_200.0).abs() < 1e-6);
+        assert!(event.has("market_cap") && !event.has("holders"));
+
+        assert!(
+            parse_launches(r#"{"data":null,"errors":[{"message":"Unauthorized"}]}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("Unauthorized")
+        );
+        assert!(parse_prices(r#"{"success":false,"message":"Too many requests"}"#).is_err());
+
+        // Strategies that don't require the unknown fields trade backfilled launches
+        let tokens = [BacktestToken {
+            event,
+            prices: prices.iter().map(|p| p.price_usd).collect(),
+            observed_at: prices.iter().map(|p| p.at.timestamp()).collect(),
+            note: None,
+            tags: Vec::new(),
+        }];
+        let config = StrategyConfig {
+            required_fields: vec!["market_cap".to_string()],
+            min_score_to_buy: 50.0,
+            require_momentum_or_graduation: false,
+            ..StrategyConfig::default()
+        };
+        assert_eq!(run_backtest(&tokens, &config).trades.len(), 1);
+        assert!(
+            run_backtest(&tokens, &StrategyConfig::default())
+                .trades
+                .is_empty()
+        );
+    }
+}
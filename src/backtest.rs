@@ -0,0 +1,723 @@
+//! Offline backtests: replay recorded tokens and the prices that followed them through the
+//! same entry and exit logic the bot trades with
+//!
+//! Positions are held one at a time and filled at the recorded price without slippage, so
+//! results isolate the strategy parameters from execution effects. To account for the
+//! bot's own speed, `min_fill_latency_ms` and `max_fill_latency_ms` delay every fill: it then
+//! happens at the price interpolated between the recorded prices around the delayed time, and
+//! prices recorded before a buy filled can't trigger its exit. Buys on the bonding curve also
+//! compete with the snipers that landed in the token's launch slot: with `sniper_ahead_pct`
+//! set, each of them may fill first and push the curve price up by its `sniper_buy_sol`, and
+//! a buy pushed past `sniper_max_slippage_pct` fails. Tokens without a recorded
+//! `first_block_buys` get the average of those that have one. A buy filling further from
+//! the price it was decided at than `max_entry_slippage_pct` is aborted. `Replay` instead steps
+//! through every token's prices in recorded time order, with positions overlapping as they did,
+//! so a caller can play it back at a compressed pace.
+
+use crate::strategy::{LiveTokenState, TokenEvent, decide, should_exit};
+use crate::strategy_config::{MIN_ENTRY_SOL, StrategyConfig};
+use chrono::DateTime;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Spacing of prices recorded without their times
+pub const DEFAULT_PRICE_INTERVAL_SECS: i64 = 60;
+
+/// SOL a fresh pump.fun bonding curve is priced as holding
+const PUMPFUN_INITIAL_VIRTUAL_SOL: f64 = 30.0;
+
+/// A token as it looked when scanned, followed by its observed prices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestToken {
+    pub event: TokenEvent,
+    pub prices: Vec<f64>,
+    /// Unix time of each price
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub observed_at: Vec<i64>,
+    /// Reviewer's note and tags on the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl BacktestToken {
+    /// Unix time of each price; without recorded times, prices are spaced
+    /// `DEFAULT_PRICE_INTERVAL_SECS` apart from the launch
+    pub fn price_times(&self) -> Vec<i64> {
+        if self.observed_at.len() == self.prices.len() {
+            return self.observed_at.clone();
+        }
+        let start = self.event.launched_at.unwrap_or(0);
+        (0..self.prices.len() as i64)
+            .map(|i| start + i * DEFAULT_PRICE_INTERVAL_SECS)
+            .collect()
+    }
+
+    /// Price at unix time `at`, interpolated between the recorded prices around it
+    fn price_at(&self, times: &[i64], at: f64) -> f64 {
+        let next = times.partition_point(|t| *t as f64 <= at);
+        if next == 0 {
+            return self.prices[0];
+        }
+        if next == self.prices.len() {
+            return self.prices[next - 1];
+        }
+        let (t0, t1) = (times[next - 1] as f64, times[next] as f64);
+        let (p0, p1) = (self.prices[next - 1], self.prices[next]);
+        p0 + (p1 - p0) * (at - t0) / (t1 - t0)
+    }
+
+    /// Fill price of a buy decided at the first price; it moves from `base_price` as the
+    /// recorded prices did during the delay
+    fn entry_fill(&self, times: &[i64], delay_secs: f64) -> f64 {
+        if delay_secs <= 0.0 || self.prices.is_empty() || self.prices[0] <= 0.0 {
+            return self.event.base_price;
+        }
+        self.event.base_price * self.price_at(times, times[0] as f64 + delay_secs) / self.prices[0]
+    }
+
+    /// Fill price of a sell decided at price `idx`
+    fn exit_fill(&self, times: &[i64], idx: usize, delay_secs: f64) -> f64 {
+        if delay_secs <= 0.0 {
+            return self.prices[idx];
+        }
+        self.price_at(times, times[idx] as f64 + delay_secs)
+    }
+}
+
+/// Delays between decisions and fills, drawn from a generator seeded by the token so reruns
+/// fill the same way
+#[derive(Debug)]
+struct FillLatency {
+    rng: StdRng,
+    min_secs: f64,
+    max_secs: f64,
+}
+
+/// Seed for a token's draws; FNV-1a, which unlike the std hasher is stable across builds
+fn token_seed(token_id: &str) -> u64 {
+    token_id.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl FillLatency {
+    fn new(token_id: &str, config: &StrategyConfig) -> Self {
+        let min_secs = config.min_fill_latency_ms as f64 / 1000.0;
+        Self {
+            rng: StdRng::seed_from_u64(token_seed(token_id)),
+            min_secs,
+            max_secs: (config.max_fill_latency_ms as f64 / 1000.0).max(min_secs),
+        }
+    }
+
+    fn next_secs(&mut self) -> f64 {
+        if self.max_secs > self.min_secs {
+            self.rng.gen_range(self.min_secs..self.max_secs)
+        } else {
+            self.min_secs
+        }
+    }
+}
+
+/// Snipers racing a buy on the bonding curve, drawn from a generator seeded by the token
+#[derive(Debug)]
+struct Contention {
+    rng: StdRng,
+    /// Buyers in the launch slot
+    snipers: u32,
+    ahead_chance: f64,
+    buy_sol: f64,
+    max_slippage: f64,
+}
+
+impl Contention {
+    /// `default_snipers` stands in for tokens without a recorded launch slot
+    fn new(ev: &TokenEvent, default_snipers: u32, config: &StrategyConfig) -> Self {
+        Self {
+            // Drawn apart from the fill delays, which stay as they were without contention
+            rng: StdRng::seed_from_u64(token_seed(&ev.id) ^ 0x5eed),
+            snipers: if ev.graduation {
+                0
+            } else {
+                ev.first_block_buys.unwrap_or(default_snipers)
+            },
+            ahead_chance: (config.sniper_ahead_pct / 100.0).clamp(0.0, 1.0),
+            buy_sol: config.sniper_buy_sol.max(0.0),
+            max_slippage: config.sniper_max_slippage_pct / 100.0,
+        }
+    }
+
+    /// Multiple of its price a buy fills at after the snipers that landed first; `None` when
+    /// they moved it past the slippage limit and the buy failed
+    fn fill_multiple(&mut self) -> Option<f64> {
+        if self.ahead_chance <= 0.0 {
+            return Some(1.0);
+        }
+        let ahead = (0..self.snipers)
+            .filter(|_| self.rng.gen_bool(self.ahead_chance))
+            .count();
+        let bought = ahead as f64 * self.buy_sol;
+        // A constant-product curve's price grows with the square of its SOL
+        let multiple =
+            ((PUMPFUN_INITIAL_VIRTUAL_SOL + bought) / PUMPFUN_INITIAL_VIRTUAL_SOL).powi(2);
+        (multiple - 1.0 <= self.max_slippage).then_some(multiple)
+    }
+}
+
+/// Launch-slot buyers assumed for tokens without a recorded count: the rounded average of
+/// the tokens that have one, or none
+fn default_snipers(tokens: &[BacktestToken]) -> u32 {
+    let recorded: Vec<u32> = tokens
+        .iter()
+        .filter_map(|t| t.event.first_block_buys)
+        .collect();
+    if recorded.is_empty() {
+        return 0;
+    }
+    (recorded.iter().map(|&n| n as f64).sum::<f64>() / recorded.len() as f64).round() as u32
+}
+
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestTrade {
+    pub token_id: String,
+    pub score: f64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub sol_in: f64,
+    pub pnl_usd: f64,
+    /// Exit reason from `should_exit`, or `end_of_data` when the prices ran out first
+    pub exit_reason: String,
+    /// Recorded seconds between entry and exit; only known to replays
+    pub held_secs: Option<i64>,
+}
+
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestResult {
+    pub trades: Vec<BacktestTrade>,
+    /// Tokens the strategy declined to buy
+    pub skipped: usize,
+    /// Buys that failed because snipers ahead moved the price past the slippage limit
+    pub sniped: usize,
+    /// Buys aborted because their fill moved past `max_entry_slippage_pct`
+    #[serde(default)]
+    pub slipped: usize,
+    pub final_sol_balance: f64,
+    pub realized_pnl_usd: f64,
+    /// Share of trades closed in profit (0.0-1.0)
+    pub win_rate: f64,
+    /// Baseline: PnL of buying every token for `max_sol_per_trade` at its first price and
+    /// selling at its last
+    pub every_token_pnl_usd: f64,
+}
+
+/// PnL of the naive baseline that buys every token with equal size and holds it to the end
+pub fn every_token_pnl_usd(tokens: &[BacktestToken], config: &StrategyConfig) -> f64 {
+    let usd_in = config.max_sol_per_trade * config.sol_usd_price;
+    tokens
+        .iter()
+        .filter(|t| t.event.base_price > 0.0)
+        .map(|t| {
+            let last = t.prices.last().copied().unwrap_or(t.event.base_price);
+            usd_in * (last / t.event.base_price - 1.0)
+        })
+        .sum()
+}
+
+pub fn run_backtest(tokens: &[BacktestToken], config: &StrategyConfig) -> BacktestResult {
+    let mut sol_balance = config.starting_sol_balance;
+    let mut trades = Vec::new();
+    let mut skipped = 0;
+    let mut sniped = 0;
+    let mut slipped = 0;
+    let default_snipers = default_snipers(tokens);
+
+    for token in tokens {
+        let ev = &token.event;
+        let decision = decide(ev, config);
+        let sol_in = f64::min(
+            config.max_sol_per_trade * decision.size_fraction * ev.signal_confidence.unwrap_or(1.0),
+            config.spendable_sol(sol_balance, 0),
+        )
+        .min(config.max_sol_for_liquidity(ev.liquidity_usd));
+        if !decision.should_buy || sol_in < MIN_ENTRY_SOL || ev.base_price <= 0.0 {
+            skipped += 1;
+            continue;
+        }
+
+        let times = token.price_times();
+        let mut latency = FillLatency::new(&ev.id, config);
+        let entry_delay = latency.next_secs();
+        let Some(contended) = Contention::new(ev, default_snipers, config).fill_multiple() else {
+            sniped += 1;
+            continue;
+        };
+        let entry_price = token.entry_fill(&times, entry_delay) * contended;
+        if config
+            .entry_slippage_breach(ev.base_price, entry_price)
+            .is_some()
+        {
+            slipped += 1;
+            continue;
+        }
+        let filled_at = times.first().map_or(0.0, |t| *t as f64 + entry_delay);
+        let usd_in = sol_in * config.sol_usd_price;
+        let qty = usd_in / entry_price;
+
+        let mut exit = None;
+        for (idx, &price) in token.prices.iter().enumerate() {
+            if (times[idx] as f64) < filled_at {
+                continue;
+            }
+            let live = LiveTokenState {
+                price_usd: price,
+                liquidity_usd: ev.liquidity_usd,
+                ..Default::default()
+            };
+            let d = should_exit(&live, entry_price, ev.liquidity_usd, config);
+            if d.should_exit {
+                exit = Some((token.exit_fill(&times, idx, latency.next_secs()), d.reason));
+                break;
+            }
+        }
+        let (exit_price, exit_reason) = exit.unwrap_or_else(|| {
+            let last = token.prices.last().copied().unwrap_or(entry_price);
+            (last, "end_of_data".to_string())
+        });
+
+        let pnl_usd = qty * exit_price - usd_in;
+        sol_balance += pnl_usd / config.sol_usd_price;
+        trades.push(BacktestTrade {
+            token_id: ev.id.clone(),
+            score: decision.score,
+            entry_price,
+            exit_price,
+            sol_in,
+            pnl_usd,
+            exit_reason,
+            held_secs: None,
+        });
+    }
+
+    summarize(
+        trades,
+        skipped,
+        sniped,
+        slipped,
+        sol_balance,
+        every_token_pnl_usd(tokens, config),
+    )
+}
+
+fn summarize(
+    trades: Vec<BacktestTrade>,
+    skipped: usize,
+    sniped: usize,
+    slipped: usize,
+    sol_balance: f64,
+    every_token_pnl_usd: f64,
+) -> BacktestResult {
+    let wins = trades.iter().filter(|t| t.pnl_usd > 0.0).count();
+    BacktestResult {
+        realized_pnl_usd: trades.iter().map(|t| t.pnl_usd).sum(),
+        win_rate: if trades.is_empty() {
+            0.0
+        } else {
+            wins as f64 / trades.len() as f64
+        },
+        trades,
+        skipped,
+        sniped,
+        slipped,
+        final_sol_balance: sol_balance,
+        every_token_pnl_usd,
+    }
+}
+
+/// What a replay step did
+#[derive(Debug, Clone)]
+pub enum ReplayAction {
+    Bought {
+        token_id: String,
+        price: f64,
+        sol_in: f64,
+    },
+    Sold(BacktestTrade),
+    /// A buy failed because snipers ahead moved the price past the slippage limit
+    Sniped {
+        token_id: String,
+    },
+    /// A buy was aborted because its fill moved `moved_pct` from the decision price
+    Slipped {
+        token_id: String,
+        moved_pct: f64,
+    },
+}
+
+#[derive(Debug)]
+struct ReplayPosition {
+    token: usize,
+    score: f64,
+    entry_price: f64,
+    sol_in: f64,
+    usd_in: f64,
+    qty: f64,
+    opened_at: i64,
+    /// When the buy filled, after its latency
+    filled_at: f64,
+    latency: FillLatency,
+}
+
+/// Tokens and their prices played in recorded time order, one price at a time
+///
+/// A token is decided on at its first price and may be bought while other positions are
+/// still open, sharing one balance. A position still open after its token's last price is
+/// closed there with `end_of_data`.
+pub struct Replay<'a> {
+    tokens: &'a [BacktestToken],
+    config: &'a StrategyConfig,
+    /// `(unix time, token, price index)`, oldest first
+    events: Vec<(i64, usize, usize)>,
+    next: usize,
+    open: Vec<ReplayPosition>,
+    sol_balance: f64,
+    trades: Vec<BacktestTrade>,
+    skipped: usize,
+    sniped: usize,
+    slipped: usize,
+    default_snipers: u32,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(tokens: &'a [BacktestToken], config: &'a StrategyConfig) -> Self {
+        let mut events: Vec<(i64, usize, usize)> = tokens
+            .iter()
+            .enumerate()
+            .flat_map(|(token, t)| {
+                t.price_times()
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(idx, at)| (at, token, idx))
+            })
+            .collect();
+        events.sort();
+        Self {
+            tokens,
+            config,
+            events,
+            next: 0,
+            open: Vec::new(),
+            sol_balance: config.starting_sol_balance,
+            trades: Vec::new(),
+            // Tokens without prices are never decided on
+            skipped: tokens.iter().filter(|t| t.prices.is_empty()).count(),
+            sniped: 0,
+            slipped: 0,
+            default_snipers: default_snipers(tokens),
+        }
+    }
+
+    /// Recorded unix time of the next step; `None` once every price was played
+    pub fn next_at(&self) -> Option<i64> {
+        self.events.get(self.next).map(|(at, _, _)| *at)
+    }
+
+    /// Play the next price
+    pub fn step(&mut self) -> Vec<ReplayAction> {
+        let Some(&(at, token, idx)) = self.events.get(self.next) else {
+            return Vec::new();
+        };
+        self.next += 1;
+        let mut actions = Vec::new();
+        if idx == 0
+            && let Some(entered) = self.enter(token, at)
+        {
+            actions.push(entered);
+        }
+
+        let Some(pos) = self.open.iter().position(|p| p.token == token) else {
+            return actions;
+        };
+        let t = &self.tokens[token];
+        let price = t.prices[idx];
+        let position = &mut self.open[pos];
+        if (at as f64) >= position.filled_at {
+            let live = LiveTokenState {
+                price_usd: price,
+                liquidity_usd: t.event.liquidity_usd,
+                updated_at: DateTime::from_timestamp(at, 0),
+                ..Default::default()
+            };
+            let exit = should_exit(
+                &live,
+                position.entry_price,
+                t.event.liquidity_usd,
+                self.config,
+            );
+            if exit.should_exit {
+                let delay = position.latency.next_secs();
+                let fill = t.exit_fill(&t.price_times(), idx, delay);
+                actions.push(self.close(pos, fill, exit.reason, at));
+                return actions;
+            }
+        }
+        if idx + 1 == t.prices.len() {
+            actions.push(self.close(pos, price, "end_of_data".to_string(), at));
+        }
+        actions
+    }
+
+    /// Play the remaining prices and summarize
+    pub fn finish(mut self) -> BacktestResult {
+        while self.next_at().is_some() {
+            self.step();
+        }
+        summarize(
+            self.trades,
+            self.skipped,
+            self.sniped,
+            self.slipped,
+            self.sol_balance,
+            every_token_pnl_usd(self.tokens, self.config),
+        )
+    }
+
+    fn enter(&mut self, token: usize, at: i64) -> Option<ReplayAction> {
+        let ev = &self.tokens[token].event;
+        let decision = decide(ev, self.config);
+        let sol_in = f64::min(
+            self.config.max_sol_per_trade
+                * decision.size_fraction
+                * ev.signal_confidence.unwrap_or(1.0),
+            self.config
+                .spendable_sol(self.sol_balance, self.open.len()),
+        )
+        .min(self.config.max_sol_for_liquidity(ev.liquidity_usd));
+        if !decision.should_buy || sol_in < MIN_ENTRY_SOL || ev.base_price <= 0.0 {
+            self.skipped += 1;
+            return None;
+        }
+        let t = &self.tokens[token];
+        let times = t.price_times();
+        let mut latency = FillLatency::new(&ev.id, self.config);
+        let delay = latency.next_secs();
+        let Some(contended) =
+            Contention::new(ev, self.default_snipers, self.config).fill_multiple()
+        else {
+            self.sniped += 1;
+            return Some(ReplayAction::Sniped {
+                token_id: ev.id.clone(),
+            });
+        };
+        let entry_price = t.entry_fill(&times, delay) * contended;
+        if let Some(moved_pct) = self
+            .config
+            .entry_slippage_breach(ev.base_price, entry_price)
+        {
+            self.slipped += 1;
+            return Some(ReplayAction::Slipped {
+                token_id: ev.id.clone(),
+                moved_pct,
+            });
+        }
+        let usd_in = sol_in * self.config.sol_usd_price;
+        self.sol_balance -= sol_in;
+        self.open.push(ReplayPosition {
+            token,
+            score: decision.score,
+            entry_price,
+            sol_in,
+            usd_in,
+            qty: usd_in / entry_price,
+            opened_at: at,
+            filled_at: at as f64 + delay,
+            latency,
+        });
+        Some(ReplayAction::Bought {
+            token_id: ev.id.clone(),
+            price: entry_price,
+            sol_in,
+        })
+    }
+
+    fn close(&mut self, pos: usize, price: f64, reason: String, at: i64) -> ReplayAction {
+        let pos = self.open.remove(pos);
+        let ev = &self.tokens[pos.token].event;
+        let pnl_usd = pos.qty * price - pos.usd_in;
+        self.sol_balance += pos.sol_in + pnl_usd / self.config.sol_usd_price;
+        let trade = BacktestTrade {
+            token_id: ev.id.clone(),
+            score: pos.score,
+            entry_price: pos.entry_price,
+            exit_price: price,
+            sol_in: pos.sol_in,
+            pnl_usd,
+            exit_reason: reason,
+            held_secs: Some(at - pos.opened_at),
+        };
+        self.trades.push(trade.clone());
+        ReplayAction::Sold(trade)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_entries_and_exits() {
+        let tokens: Vec<BacktestToken> =
+            serde_json::from_str(include_str!("../tests/data/backtest/tokens.json")).unwrap();
+        let config = StrategyConfig {
+            min_market_cap_usd: 0.0,
+            ..StrategyConfig::default()
+        };
+        let result = run_backtest(&tokens, &config);
+
+        assert_eq!(result.skipped, 1);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].exit_reason, "profit_target");
+        assert!(result.realized_pnl_usd > 0.0);
+        assert_eq!(result.win_rate, 1.0);
+        // Buying both tokens: +59.6% on one, -70.7% on the other, 15 USD each
+        assert!((result.every_token_pnl_usd - -1.67).abs() < 0.01);
+    }
+
+    #[test]
+    fn replays_overlapping_tokens_in_recorded_time() {
+        let mut tokens: Vec<BacktestToken> =
+            serde_json::from_str(include_str!("../tests/data/backtest/tokens.json")).unwrap();
+        let config = StrategyConfig {
+            min_market_cap_usd: 0.0,
+            min_score_to_buy: 0.0,
+            ..StrategyConfig::default()
+        };
+        // A second launch like the first arrives while the first is held, and is stopped out
+        // before the first takes profit
+        tokens[0].observed_at = vec![1_000, 1_300, 1_600];
+        let mut event = tokens[0].event.clone();
+        event.id = "second".to_string();
+        tokens[1] = BacktestToken {
+            event,
+            prices: vec![0.0000312, 0.0000100],
+            observed_at: vec![1_100, 1_200],
+            note: None,
+            tags: Vec::new(),
+        };
+        let times: Vec<i64> = {
+            let mut replay = Replay::new(&tokens, &config);
+            std::iter::from_fn(|| {
+                let at = replay.next_at()?;
+                replay.step();
+                Some(at)
+            })
+            .collect()
+        };
+        assert_eq!(times, [1_000, 1_100, 1_200, 1_300, 1_600]);
+
+        let mut replay = Replay::new(&tokens, &config);
+        assert!(matches!(replay.step()[..], [ReplayAction::Bought { .. }]));
+        let result = replay.finish();
+        let reasons: Vec<(&str, Option<i64>)> = result
+            .trades
+            .iter()
+            .map(|t| (t.exit_reason.as_str(), t.held_secs))
+            .collect();
+        assert_eq!(
+            reasons,
+            [("stop_loss", Some(100)), ("profit_target", Some(600))]
+        );
+        // Without recorded times, prices are a minute apart from the launch
+        tokens[1].observed_at.clear();
+        assert_eq!(tokens[1].price_times(), [1_760_599_400, 1_760_599_460]);
+    }
+
+    #[test]
+    fn fills_after_the_configured_latency() {
+        let mut tokens: Vec<BacktestToken> =
+            serde_json::from_str(include_str!("../tests/data/backtest/tokens.json")).unwrap();
+        tokens.truncate(1);
+        tokens[0].observed_at = vec![1_000, 1_060, 1_120];
+        let instant = StrategyConfig {
+            min_market_cap_usd: 0.0,
+            ..StrategyConfig::default()
+        };
+        // Half a minute late, the buy pays halfway to the second price, and the last price is
+        // no longer 50% above the fill
+        let late = StrategyConfig {
+            min_fill_latency_ms: 30_000,
+            max_fill_latency_ms: 30_000,
+            ..instant.clone()
+        };
+        let trade = &run_backtest(&tokens, &instant).trades[0];
+        assert_eq!(
+            (trade.entry_price, trade.exit_reason.as_str()),
+            (0.0000312, "profit_target")
+        );
+        let trade = &run_backtest(&tokens, &late).trades[0];
+        assert!((trade.entry_price - 0.00003565).abs() < 1e-12);
+        assert_eq!(trade.exit_reason, "end_of_data");
+        assert_eq!(
+            Replay::new(&tokens, &late).finish().trades[0].entry_price,
+            trade.entry_price
+        );
+
+        // That fill is 14% above the decision price, past a 10% slippage limit
+        let capped = StrategyConfig {
+            max_entry_slippage_pct: 10.0,
+            ..late.clone()
+        };
+        let result = run_backtest(&tokens, &capped);
+        assert_eq!((result.trades.len(), result.slipped), (0, 1));
+        assert_eq!(Replay::new(&tokens, &capped).finish().slipped, 1);
+
+        // A latency range draws the same delays on every run
+        let jittered = StrategyConfig {
+            min_fill_latency_ms: 400,
+            max_fill_latency_ms: 3_000,
+            ..instant
+        };
+        let first = run_backtest(&tokens, &jittered).trades[0].entry_price;
+        assert!(first > 0.0000312 && first < 0.0000320);
+        assert_eq!(run_backtest(&tokens, &jittered).trades[0].entry_price, first);
+    }
+
+    #[test]
+    fn loses_launch_fills_to_recorded_snipers() {
+        let mut tokens: Vec<BacktestToken> =
+            serde_json::from_str(include_str!("../tests/data/backtest/tokens.json")).unwrap();
+        tokens[0].event.first_block_buys = Some(2);
+        let config = StrategyConfig {
+            min_market_cap_usd: 0.0,
+            sniper_ahead_pct: 100.0,
+            sniper_buy_sol: 0.5,
+            ..StrategyConfig::default()
+        };
+        // Two half-SOL buys ahead lift a 30 SOL curve's price by (31/30)^2
+        let trade = &run_backtest(&tokens, &config).trades[0];
+        assert!((trade.entry_price - 0.0000312 * (31.0f64 / 30.0).powi(2)).abs() < 1e-12);
+
+        // Four are past the 10% slippage limit; the token without a count gets the average
+        tokens[0].event.first_block_buys = None;
+        tokens[1].event.first_block_buys = Some(4);
+        let result = run_backtest(&tokens, &config);
+        assert_eq!((result.trades.len(), result.sniped), (0, 1));
+        let mut replay = Replay::new(&tokens, &config);
+        assert!(matches!(replay.step()[..], [ReplayAction::Sniped { .. }]));
+        assert_eq!(replay.finish().sniped, 1);
+
+        // Without a chance of being beaten, fills are as before
+        let first = StrategyConfig {
+            sniper_ahead_pct: 0.0,
+            ..config
+        };
+        assert_eq!(
+            run_backtest(&tokens, &first).trades[0].entry_price,
+            0.0000312
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! `backtest`: run each strategy over a backtest file, reusing results already computed
+//!
+//! Results are stored in `backtest_cache`, keyed by a hash of the strategy's parameters, a
+//! hash of the tokens and prices replayed, and the version and commit of the binary. Running
+//! the same strategy over the same data with the same build again reads the stored result
+//! instead of replaying it, and says so. Any change to a parameter, to the data, or to the
+//! code misses the cache. Builds outside a git checkout share the commit `unknown`, so their
+//! results are only reused within one crate version.
+
+use crate::portfolio_manager::PortfolioManager;
+use crate::run_metadata::GIT_HASH;
+use crate::storage::{Location, Storage};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use solana_sniper_bot::backtest::{BacktestResult, BacktestToken, run_backtest};
+use solana_sniper_bot::strategy_config::StrategyConfig;
+use sqlx::PgPool;
+
+/// What a cached result was computed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheKey {
+    pub config_hash: String,
+    pub data_hash: String,
+    pub code_version: String,
+    /// Unix times of the first and last price replayed
+    pub data_from: Option<i64>,
+    pub data_to: Option<i64>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl CacheKey {
+    pub fn new(tokens: &[BacktestToken], config: &StrategyConfig) -> Result<Self> {
+        let times: Vec<i64> = tokens.iter().flat_map(|t| t.price_times()).collect();
+        Ok(Self {
+            config_hash: sha256_hex(&serde_json::to_vec(config)?),
+            data_hash: sha256_hex(&serde_json::to_vec(tokens)?),
+            code_version: format!("{}-{}", env!("CARGO_PKG_VERSION"), GIT_HASH),
+            data_from: times.iter().min().copied(),
+            data_to: times.iter().max().copied(),
+        })
+    }
+}
+
+/// The stored result for `key`, counting the hit
+async fn lookup(pool: &PgPool, key: &CacheKey) -> Result<Option<BacktestResult>> {
+    let result: Option<String> = sqlx::query_scalar(
+        "UPDATE backtest_cache SET hits = hits + 1, last_hit_at = NOW() \
+         WHERE config_hash = $1 AND data_hash = $2 AND code_version = $3 \
+         RETURNING result::TEXT",
+    )
+    .bind(&key.config_hash)
+    .bind(&key.data_hash)
+    .bind(&key.code_version)
+    .fetch_optional(pool)
+    .await?;
+    Ok(result.map(|r| serde_json::from_str(&r)).transpose()?)
+}
+
+async fn store(pool: &PgPool, key: &CacheKey, result: &BacktestResult) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO backtest_cache \
+         (config_hash, data_hash, code_version, data_from, data_to, result) \
+         VALUES ($1, $2, $3, to_timestamp($4), to_timestamp($5), $6::JSONB) \
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(&key.config_hash)
+    .bind(&key.data_hash)
+    .bind(&key.code_version)
+    .bind(key.data_from.map(|t| t as f64))
+    .bind(key.data_to.map(|t| t as f64))
+    .bind(serde_json::to_string(result)?)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Backtest `config` over `tokens`, or read the result of an identical earlier run; returns
+/// whether it came from the cache
+pub async fn cached_backtest(
+    pool: &PgPool,
+    tokens: &[BacktestToken],
+    config: &StrategyConfig,
+) -> Result<(BacktestResult, bool)> {
+    let key = CacheKey::new(tokens, config)?;
+    if let Some(result) = lookup(pool, &key).await? {
+        return Ok((result, true));
+    }
+    let result = run_backtest(tokens, config);
+    store(pool, &key, &result).await?;
+    Ok((result, false))
+}
+
+pub async fn run(
+    pool: &PgPool,
+    file: &Location,
+    manager: &PortfolioManager,
+    storage: &Storage,
+) -> Result<()> {
+    let tokens: Vec<BacktestToken> = serde_json::from_slice(&storage.get(file).await?)?;
+    println!("Backtesting {} tokens from {}", tokens.len(), file);
+    println!(
+        "{:<20} {:>7} {:>9} {:>12} {:>12} {:>8}",
+        "strategy", "trades", "win_rate", "pnl_usd", "final_sol", "source"
+    );
+    let mut hits = 0;
+    for slot in &manager.slots {
+        let (result, hit) = cached_backtest(pool, &tokens, &slot.config).await?;
+        hits += hit as usize;
+        println!(
+            "{:<20} {:>7} {:>8.1}% {:>12.2} {:>12.4} {:>8}",
+            slot.id,
+            result.trades.len(),
+            result.win_rate * 100.0,
+            result.realized_pnl_usd,
+            result.final_sol_balance,
+            if hit { "cached" } else { "computed" }
+        );
+    }
+    println!(
+        "{} of {} results read from the backtest cache",
+        hits,
+        manager.slots.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sniper_bot::models::PumpFunListing;
+
+    #[test]
+    fn keys_on_config_and_data() {
+        let token = BacktestToken {
+            event: PumpFunListing {
+                token_address: "mint".to_string(),
+                ..Default::default()
+            }
+            .into(),
+            prices: vec![1.0, 2.0],
+            observed_at: vec![100, 160],
+            note: None,
+            tags: Vec::new(),
+        };
+        let config = StrategyConfig::default();
+        let tokens = std::slice::from_ref(&token);
+        let key = CacheKey::new(tokens, &config).unwrap();
+        assert_eq!(key, CacheKey::new(tokens, &config).unwrap());
+        assert_eq!((key.data_from, key.data_to), (Some(100), Some(160)));
+        assert!(key.code_version.starts_with(env!("CARGO_PKG_VERSION")));
+
+        let tuned = StrategyConfig {
+            min_score_to_buy: config.min_score_to_buy + 1.0,
+            ..config.clone()
+        };
+        let retuned = CacheKey::new(tokens, &tuned).unwrap();
+        assert_ne!(retuned.config_hash, key.config_hash);
+        assert_eq!(retuned.data_hash, key.data_hash);
+
+        let repriced = BacktestToken {
+            prices: vec![1.0, 3.0],
+            ..token
+        };
+        let moved = CacheKey::new(&[repriced], &config).unwrap();
+        assert_eq!(moved.config_hash, key.config_hash);
+        assert_ne!(moved.data_hash, key.data_hash);
+    }
+}
@@ -0,0 +1,350 @@
+//! Wallet and mint blacklists stored in Postgres
+//!
+//! Blacklisted creator wallets are treated as known ruggers; blacklisted mints are
+//! rejected outright. Lists can be exported to and imported from a shareable JSON format
+//! (`{"version": 1, "entries": [{"kind", "address", "reason", "source", "added_at"}]}`) so
+//! operators can pool rug intel.
+
+use crate::storage::{Location, Storage};
+use crate::strategy::TokenEvent;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Wallet,
+    Mint,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Wallet => "wallet",
+            EntryKind::Mint => "mint",
+        }
+    }
+}
+
+impl FromStr for EntryKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "wallet" => Ok(EntryKind::Wallet),
+            "mint" => Ok(EntryKind::Mint),
+            _ => bail!("unknown blacklist kind '{}' (expected wallet or mint)", s),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum BlacklistCommand {
+    /// Blacklist a wallet or mint
+    Add {
+        /// wallet or mint
+        kind: EntryKind,
+        address: String,
+        /// why it was blacklisted (e.g. where the report came from)
+        #[structopt(long)]
+        reason: Option<String>,
+    },
+    /// Remove a wallet or mint from the blacklist
+    Remove { kind: EntryKind, address: String },
+    /// List blacklisted wallets and mints
+    List {
+        /// only list wallets or mints
+        kind: Option<EntryKind>,
+    },
+    /// Write the blacklist as shareable JSON
+    Export {
+        /// output file or `s3://bucket/key`; stdout when omitted
+        #[structopt(long)]
+        out: Option<Location>,
+    },
+    /// Merge a shared JSON list into the blacklist, skipping entries already present
+    Import {
+        /// file or `s3://bucket/key`
+        file: Location,
+        /// credit entries to this source (defaults to the source recorded in the file)
+        #[structopt(long)]
+        source: Option<String>,
+    },
+}
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Shareable blacklist file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedList {
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    pub entries: Vec<SharedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedEntry {
+    /// `wallet` or `mint`
+    pub kind: String,
+    pub address: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Who reported the entry (operator, community list)
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub added_at: Option<String>,
+}
+
+pub async fn run(pool: &PgPool, cmd: BlacklistCommand, storage: &Storage) -> Result<()> {
+    match cmd {
+        BlacklistCommand::Add {
+            kind,
+            address,
+            reason,
+        } => {
+            validate_address(&address)?;
+            sqlx::query(
+                "INSERT INTO blacklist (kind, address, reason) VALUES ($1,$2,$3) \
+                 ON CONFLICT (kind, address) DO UPDATE SET reason = COALESCE(EXCLUDED.reason, blacklist.reason)",
+            )
+            .bind(kind.as_str())
+            .bind(&address)
+            .bind(&reason)
+            .execute(pool)
+            .await?;
+            println!("Blacklisted {} {}", kind.as_str(), address);
+        }
+        BlacklistCommand::Remove { kind, address } => {
+            let removed = sqlx::query("DELETE FROM blacklist WHERE kind = $1 AND address = $2")
+                .bind(kind.as_str())
+                .bind(&address)
+                .execute(pool)
+                .await?
+                .rows_affected();
+            if removed == 0 {
+                bail!("{} {} is not blacklisted", kind.as_str(), address);
+            }
+            println!("Removed {} {}", kind.as_str(), address);
+        }
+        BlacklistCommand::List { kind } => {
+            let rows = sqlx::query(
+                "SELECT kind, address, reason, to_char(added_at, 'YYYY-MM-DD HH24:MI') AS added \
+                 FROM blacklist \
+                 WHERE $1::TEXT IS NULL OR kind = $1 ORDER BY kind, added_at",
+            )
+            .bind(kind.map(|k| k.as_str()))
+            .fetch_all(pool)
+            .await?;
+            println!("{:<7} {:<44} {:<20} reason", "kind", "address", "added");
+            for row in &rows {
+                println!(
+                    "{:<7} {:<44} {:<20} {}",
+                    row.get::<String, _>("kind"),
+                    row.get::<String, _>("address"),
+                    row.get::<Option<String>, _>("added").unwrap_or_default(),
+                    row.get::<Option<String>, _>("reason").unwrap_or_default()
+                );
+            }
+            println!("{} entries", rows.len());
+        }
+        BlacklistCommand::Export { out } => {
+            let list = export(pool).await?;
+            let json = serde_json::to_string_pretty(&list)?;
+            match out {
+                Some(out) => {
+                    storage.put(&out, json.into_bytes()).await?;
+                    println!("Exported {} entries to {}", list.entries.len(), out);
+                }
+                None => println!("{}", json),
+            }
+        }
+        BlacklistCommand::Import { file, source } => {
+            let list: SharedList = serde_json::from_slice(&storage.get(&file).await?)
+                .map_err(|e| anyhow!("{} is not a blacklist export: {}", file, e))?;
+            if list.version > FORMAT_VERSION {
+                bail!(
+                    "{} uses format version {}, this build reads up to {}",
+                    file,
+                    list.version,
+                    FORMAT_VERSION
+                );
+            }
+            let (entries, invalid) = dedupe(list.entries);
+            let mut added = 0;
+            for (kind, entry) in &entries {
+                // Existing entries keep their own reason and source unless they have none
+                let inserted: bool = sqlx::query(
+                    "INSERT INTO blacklist (kind, address, reason, source) VALUES ($1,$2,$3,$4) \
+                     ON CONFLICT (kind, address) DO UPDATE SET \
+                     reason = COALESCE(blacklist.reason, EXCLUDED.reason), \
+                     source = COALESCE(blacklist.source, EXCLUDED.source) \
+                     RETURNING (xmax = 0) AS inserted",
+                )
+                .bind(kind.as_str())
+                .bind(&entry.address)
+                .bind(&entry.reason)
+                .bind(source.as_ref().or(entry.source.as_ref()))
+                .fetch_one(pool)
+                .await?
+                .get("inserted");
+                if inserted {
+                    added += 1;
+                }
+            }
+            println!(
+                "Imported {}: {} new, {} already listed, {} invalid or duplicate entries skipped",
+                file,
+                added,
+                entries.len() - added,
+                invalid
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn export(pool: &PgPool) -> Result<SharedList> {
+    let rows = sqlx::query(
+        "SELECT kind, address, reason, source, \
+         to_char(added_at AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS added_at \
+         FROM blacklist ORDER BY kind, address",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(SharedList {
+        version: FORMAT_VERSION,
+        exported_at: Some(chrono::Utc::now().to_rfc3339()),
+        entries: rows
+            .iter()
+            .map(|row| SharedEntry {
+                kind: row.get("kind"),
+                address: row.get("address"),
+                reason: row.get("reason"),
+                source: row.get("source"),
+                added_at: row.get("added_at"),
+            })
+            .collect(),
+    })
+}
+
+/// Drop entries with an unknown kind or malformed address and collapse duplicates, keeping
+/// the first reason seen; returns the survivors and how many were dropped
+fn dedupe(entries: Vec<SharedEntry>) -> (Vec<(EntryKind, SharedEntry)>, usize) {
+    let total = entries.len();
+    let mut seen: HashMap<(&'static str, String), usize> = HashMap::new();
+    let mut kept: Vec<(EntryKind, SharedEntry)> = Vec::new();
+    for mut entry in entries {
+        let Ok(kind) = entry.kind.parse::<EntryKind>() else {
+            continue;
+        };
+        entry.address = entry.address.trim().to_string();
+        if validate_address(&entry.address).is_err() {
+            continue;
+        }
+        match seen.get(&(kind.as_str(), entry.address.clone())) {
+            Some(&i) => {
+                if kept[i].1.reason.is_none() {
+                    kept[i].1.reason = entry.reason;
+                }
+            }
+            None => {
+                seen.insert((kind.as_str(), entry.address.clone()), kept.len());
+                kept.push((kind, entry));
+            }
+        }
+    }
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
+
+/// Solana addresses are 32 bytes of base58
+pub(crate) fn validate_address(address: &str) -> Result<()> {
+    match bs58::decode(address).into_vec() {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => bail!("'{}' is not a Solana address", address),
+    }
+}
+
+/// In-memory snapshot of the blacklist, consulted for every enriched token
+#[derive(Debug, Default)]
+pub struct Blacklist {
+    wallets: HashSet<String>,
+    mints: HashSet<String>,
+}
+
+impl Blacklist {
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        let mut blacklist = Self::default();
+        for row in sqlx::query("SELECT kind, address FROM blacklist")
+            .fetch_all(pool)
+            .await?
+        {
+            let address: String = row.get("address");
+            match row.get::<String, _>("kind").as_str() {
+                "wallet" => blacklist.wallets.insert(address),
+                _ => blacklist.mints.insert(address),
+            };
+        }
+        Ok(blacklist)
+    }
+
+    /// Flag a token whose creator wallet or mint is blacklisted
+    pub fn apply(&self, ev: &mut TokenEvent) {
+        if let Some(dev) = &ev.dev_wallet_address
+            && self.wallets.contains(dev)
+        {
+            ev.is_dev_known_rugger = true;
+        }
+        if self.mints.contains(&ev.id) {
+            ev.blacklisted = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PumpFunListing;
+
+    #[test]
+    fn import_drops_invalid_and_duplicate_entries() {
+        let list: SharedList =
+            serde_json::from_str(include_str!("../tests/data/blacklist/community.json")).unwrap();
+        let (entries, dropped) = dedupe(list.entries);
+        assert_eq!(dropped, 3);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, EntryKind::Wallet);
+        // The duplicate's reason fills in the first entry's missing one
+        assert_eq!(entries[0].1.reason.as_deref(), Some("rugged RUGI"));
+        assert_eq!(entries[1].0, EntryKind::Mint);
+    }
+
+    #[test]
+    fn flags_blacklisted_creators_and_mints() {
+        let blacklist = Blacklist {
+            wallets: HashSet::from(["DevWa11et".to_string()]),
+            mints: HashSet::from(["BadMint".to_string()]),
+        };
+        let listing = |mint: &str, creator: &str| -> TokenEvent {
+            PumpFunListing {
+                token_address: mint.to_string(),
+                creator: Some(creator.to_string()),
+                ..Default::default()
+            }
+            .into()
+        };
+
+        let mut ev = listing("GoodMint", "DevWa11et");
+        blacklist.apply(&mut ev);
+        assert!(ev.is_dev_known_rugger && !ev.blacklisted);
+
+        let mut ev = listing("BadMint", "Someone");
+        blacklist.apply(&mut ev);
+        assert!(!ev.is_dev_known_rugger && ev.blacklisted);
+    }
+}
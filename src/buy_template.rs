@@ -0,0 +1,236 @@
+//! Pre-built pump.fun buy transactions for the launch-sniping path
+//!
+//! Everything that does not depend on the token is serialized once per wallet: the compute
+//! budget and priority fee instructions, the idempotent token account creation, and the fixed
+//! pump.fun accounts. When a token passes the filters, only its mint, the derived bonding curve
+//! and token accounts, the amounts, and the blockhash are patched in. The signature covers the
+//! whole message, so the transaction is signed after patching.
+
+use crate::executor::decode_address;
+use crate::message::{self, AccountMeta, Instruction, LookupTable};
+use anyhow::{Result, anyhow, bail};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use sha2::{Digest, Sha256};
+
+const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+const PUMP_GLOBAL: &str = "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf";
+const PUMP_FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+const PUMP_EVENT_AUTHORITY: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const ATA_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+const RENT_SYSVAR: &str = "SysvarRent111111111111111111111111111111111";
+pub const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+
+/// Anchor discriminator of pump.fun's `buy` instruction
+const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+/// pump.fun tokens use 6 decimals
+pub const TOKEN_DECIMALS: i32 = 6;
+/// Limit used until the executor replaces it with a simulated estimate
+pub const COMPUTE_UNIT_LIMIT: u32 = 120_000;
+/// Compute budget instruction tags
+pub const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Stand-ins for the token-specific accounts, replaced by `build`
+const PLACEHOLDER_MINT: [u8; 32] = [1; 32];
+const PLACEHOLDER_BONDING_CURVE: [u8; 32] = [2; 32];
+const PLACEHOLDER_ASSOCIATED_BONDING_CURVE: [u8; 32] = [3; 32];
+const PLACEHOLDER_USER_TOKEN_ACCOUNT: [u8; 32] = [4; 32];
+
+/// A serialized, unsigned buy transaction with the token-specific bytes left blank
+pub struct BuyTemplate {
+    tx: Vec<u8>,
+    payer: [u8; 32],
+    mint_offset: usize,
+    bonding_curve_offset: usize,
+    associated_bonding_curve_offset: usize,
+    user_token_account_offset: usize,
+    /// Offset of the buy instruction's `amount` and `max_sol_cost` arguments
+    amounts_offset: usize,
+}
+
+impl BuyTemplate {
+    /// Build the template for `payer`, paying `priority_fee_lamports` on top of the base fee.
+    /// Fixed accounts listed in `lookup_tables` are loaded from them.
+    pub fn new(
+        payer: [u8; 32],
+        priority_fee_lamports: u64,
+        lookup_tables: &[LookupTable],
+    ) -> Result<Self> {
+        let fixed = |address: &str| decode_address(address).expect("valid builtin address");
+        let system_program = [0u8; 32];
+        let compute_budget = fixed(COMPUTE_BUDGET_PROGRAM_ID);
+        let token_program = fixed(TOKEN_PROGRAM_ID);
+        let pump = fixed(PUMP_PROGRAM_ID);
+
+        let micro_lamports_per_cu = priority_fee_lamports * 1_000_000 / COMPUTE_UNIT_LIMIT as u64;
+        let mut set_limit = vec![SET_COMPUTE_UNIT_LIMIT];
+        set_limit.extend_from_slice(&COMPUTE_UNIT_LIMIT.to_le_bytes());
+        let mut set_price = vec![SET_COMPUTE_UNIT_PRICE];
+        set_price.extend_from_slice(&micro_lamports_per_cu.to_le_bytes());
+        let mut buy = BUY_DISCRIMINATOR.to_vec();
+        buy.extend_from_slice(&[0u8; 16]);
+
+        let instructions = [
+            Instruction {
+                program_id: compute_budget,
+                accounts: Vec::new(),
+                data: set_limit,
+            },
+            Instruction {
+                program_id: compute_budget,
+                accounts: Vec::new(),
+                data: set_price,
+            },
+            // Associated token account `CreateIdempotent`
+            Instruction {
+                program_id: fixed(ATA_PROGRAM_ID),
+                accounts: vec![
+                    AccountMeta::signer(payer),
+                    AccountMeta::writable(PLACEHOLDER_USER_TOKEN_ACCOUNT),
+                    AccountMeta::readonly(payer),
+                    AccountMeta::readonly(PLACEHOLDER_MINT),
+                    AccountMeta::readonly(system_program),
+                    AccountMeta::readonly(token_program),
+                ],
+                data: vec![1],
+            },
+            Instruction {
+                program_id: pump,
+                accounts: vec![
+                    AccountMeta::readonly(fixed(PUMP_GLOBAL)),
+                    AccountMeta::writable(fixed(PUMP_FEE_RECIPIENT)),
+                    AccountMeta::readonly(PLACEHOLDER_MINT),
+                    AccountMeta::writable(PLACEHOLDER_BONDING_CURVE),
+                    AccountMeta::writable(PLACEHOLDER_ASSOCIATED_BONDING_CURVE),
+                    AccountMeta::writable(PLACEHOLDER_USER_TOKEN_ACCOUNT),
+                    AccountMeta::signer(payer),
+                    AccountMeta::readonly(system_program),
+                    AccountMeta::readonly(token_program),
+                    AccountMeta::readonly(fixed(RENT_SYSVAR)),
+                    AccountMeta::readonly(fixed(PUMP_EVENT_AUTHORITY)),
+                    AccountMeta::readonly(pump),
+                ],
+                data: buy,
+            },
+        ];
+        let compiled = message::compile(payer, &instructions, lookup_tables)?;
+        let offset = |key: &[u8; 32]| {
+            compiled
+                .key_offset(key)
+                .ok_or_else(|| anyhow!("token account placeholder missing from the template"))
+        };
+        Ok(Self {
+            payer,
+            mint_offset: offset(&PLACEHOLDER_MINT)?,
+            bonding_curve_offset: offset(&PLACEHOLDER_BONDING_CURVE)?,
+            associated_bonding_curve_offset: offset(&PLACEHOLDER_ASSOCIATED_BONDING_CURVE)?,
+            user_token_account_offset: offset(&PLACEHOLDER_USER_TOKEN_ACCOUNT)?,
+            amounts_offset: compiled.data_offset(3) + BUY_DISCRIMINATOR.len(),
+            tx: compiled.bytes,
+        })
+    }
+
+    /// Unsigned buy of exactly `token_amount` raw units of `mint`, paying at most `max_sol_cost`
+    /// lamports
+    pub fn build(&self, mint: &str, token_amount: u64, max_sol_cost: u64) -> Result<Vec<u8>> {
+        let mint = decode_address(mint)?;
+        let bonding_curve = bonding_curve_account(&mint)?;
+
+        let mut tx = self.tx.clone();
+        let mut patch = |at: usize, bytes: &[u8]| tx[at..at + bytes.len()].copy_from_slice(bytes);
+        patch(self.mint_offset, &mint);
+        patch(self.bonding_curve_offset, &bonding_curve);
+        patch(
+            self.associated_bonding_curve_offset,
+            &associated_token_address(&bonding_curve, &mint)?,
+        );
+        patch(
+            self.user_token_account_offset,
+            &associated_token_address(&self.payer, &mint)?,
+        );
+        patch(self.amounts_offset, &token_amount.to_le_bytes());
+        patch(self.amounts_offset + 8, &max_sol_cost.to_le_bytes());
+        Ok(tx)
+    }
+}
+
+fn bonding_curve_account(mint: &[u8; 32]) -> Result<[u8; 32]> {
+    find_program_address(&[b"bonding-curve", mint], &decode_address(PUMP_PROGRAM_ID)?)
+}
+
+/// Address of a pump.fun mint's bonding curve, which holds the supply not sold yet
+pub fn bonding_curve_address(mint: &str) -> Result<String> {
+    Ok(bs58::encode(bonding_curve_account(&decode_address(mint)?)?).into_string())
+}
+
+/// Address of a mint's Metaplex metadata account, which holds its name, symbol, and URI
+pub fn metadata_address(mint: &str) -> Result<String> {
+    let program = decode_address(METADATA_PROGRAM_ID)?;
+    let address = find_program_address(&[b"metadata", &program, &decode_address(mint)?], &program)?;
+    Ok(bs58::encode(address).into_string())
+}
+
+/// `associated_token_address` of base58 addresses
+pub fn token_account_address(owner: &str, mint: &str) -> Result<String> {
+    let address = associated_token_address(&decode_address(owner)?, &decode_address(mint)?)?;
+    Ok(bs58::encode(address).into_string())
+}
+
+pub fn associated_token_address(owner: &[u8; 32], mint: &[u8; 32]) -> Result<[u8; 32]> {
+    let token_program = decode_address(TOKEN_PROGRAM_ID)?;
+    let ata_program = decode_address(ATA_PROGRAM_ID)?;
+    find_program_address(&[owner, &token_program, mint], &ata_program)
+}
+
+/// Solana's `find_program_address`: the first bump, counting down from 255, whose hash is not
+/// a valid ed25519 point
+fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Result<[u8; 32]> {
+    for bump in (0..=255u8).rev() {
+        let mut hasher = Sha256::new();
+        for seed in seeds {
+            hasher.update(seed);
+        }
+        hasher.update([bump]);
+        hasher.update(program_id);
+        hasher.update(b"ProgramDerivedAddress");
+        let hash: [u8; 32] = hasher.finalize().into();
+        if CompressedEdwardsY(hash).decompress().is_none() {
+            return Ok(hash);
+        }
+    }
+    bail!("no program address found for these seeds")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_mint_accounts_and_amounts() {
+        let usdc = decode_address("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        let template = BuyTemplate::new([7u8; 32], 10_000, &[]).unwrap();
+        let tx = template
+            .build(
+                "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                1_000_000,
+                5_000,
+            )
+            .unwrap();
+        let mint_at = template.mint_offset;
+        assert_eq!(tx[mint_at..mint_at + 32], usdc);
+        let user_ata_at = template.user_token_account_offset;
+        assert_eq!(
+            tx[user_ata_at..user_ata_at + 32],
+            associated_token_address(&[7u8; 32], &usdc).unwrap()
+        );
+        assert_eq!(
+            tx[template.amounts_offset..template.amounts_offset + 8],
+            1_000_000u64.to_le_bytes()
+        );
+        let ata = associated_token_address(&[7u8; 32], &usdc).unwrap();
+        assert!(CompressedEdwardsY(ata).decompress().is_none());
+        assert_eq!(tx.len(), template.tx.len());
+    }
+}
@@ -0,0 +1,109 @@
+//! In-process cache of per-mint token data
+//!
+//! Enrichment, exit monitoring, and re-entry checks can ask for the same mint several times
+//! within a poll, and every request counts against the RPC and DexScreener rate limits.
+//! Entries expire after a TTL and are dropped when a position opens or closes on their mint,
+//! so the decisions that matter start from fresh data.
+
+use crate::models::{
+    BondingCurveState, DexScreenerPair, HolderStats, TokenMetadata, TopHoldersResponse,
+};
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+/// Values by key that expire `ttl` after insertion; a zero TTL disables caching
+pub struct TtlCache<V> {
+    entries: DashMap<String, (V, Instant)>,
+    ttl: Duration,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        let fresh = self
+            .entries
+            .get(key)
+            .filter(|entry| entry.1.elapsed() < self.ttl)
+            .map(|entry| entry.0.clone());
+        if fresh.is_none() {
+            self.entries.remove(key);
+        }
+        fresh
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        if !self.ttl.is_zero() {
+            self.entries
+                .insert(key.to_string(), (value, Instant::now()));
+        }
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        self.entries.remove(key);
+    }
+}
+
+/// The scanner's cached lookups, keyed by mint except for bonding curves, which are keyed by
+/// their own address. Misses are cached too: a token without a DexScreener pair yet is not
+/// asked about again until the entry expires.
+pub struct TokenCache {
+    pub metadata: TtlCache<Option<TokenMetadata>>,
+    pub holders: TtlCache<Option<HolderStats>>,
+    pub top_holders: TtlCache<Option<TopHoldersResponse>>,
+    pub curves: TtlCache<Option<BondingCurveState>>,
+    pub pairs: TtlCache<Option<DexScreenerPair>>,
+    ttl: Duration,
+}
+
+impl TokenCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            metadata: TtlCache::new(ttl),
+            holders: TtlCache::new(ttl),
+            top_holders: TtlCache::new(ttl),
+            curves: TtlCache::new(ttl),
+            pairs: TtlCache::new(ttl),
+            ttl,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Drop everything cached for `mint`
+    pub fn invalidate(&self, mint: &str) {
+        self.metadata.invalidate(mint);
+        self.holders.invalidate(mint);
+        self.top_holders.invalidate(mint);
+        self.pairs.invalidate(mint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_and_invalidates_entries() {
+        let cache = TtlCache::new(Duration::from_millis(50));
+        cache.insert("mint", 1);
+        assert_eq!(cache.get("mint"), Some(1));
+        cache.invalidate("mint");
+        assert_eq!(cache.get("mint"), None);
+
+        cache.insert("mint", 2);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("mint"), None);
+
+        let disabled = TtlCache::new(Duration::ZERO);
+        disabled.insert("mint", 3);
+        assert_eq!(disabled.get("mint"), None);
+    }
+}
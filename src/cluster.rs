@@ -0,0 +1,70 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Solana cluster the live executor sends transactions to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+}
+
+impl Cluster {
+    /// Default public JSON-RPC endpoint for the cluster
+    pub fn rpc_url(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "mainnet",
+            Cluster::Devnet => "devnet",
+        }
+    }
+
+    /// Solscan link for a transaction signature on this cluster
+    pub fn explorer_tx_url(&self, signature: &str) -> String {
+        match self {
+            Cluster::Mainnet => format!("https://solscan.io/tx/{}", signature),
+            Cluster::Devnet => format!("https://solscan.io/tx/{}?cluster=devnet", signature),
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            "devnet" => Ok(Cluster::Devnet),
+            other => bail!("unknown cluster '{}' (expected mainnet or devnet)", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clusters_and_links_to_their_explorer() {
+        assert_eq!("mainnet-beta".parse::<Cluster>().unwrap(), Cluster::Mainnet);
+        assert_eq!("devnet".parse::<Cluster>().unwrap(), Cluster::Devnet);
+        assert!("testnet".parse::<Cluster>().is_err());
+
+        assert_eq!(Cluster::Devnet.rpc_url(), "https://api.devnet.solana.com");
+        assert_eq!(
+            Cluster::Devnet.explorer_tx_url("sig"),
+            "https://solscan.io/tx/sig?cluster=devnet"
+        );
+        assert_eq!(
+            Cluster::Mainnet.explorer_tx_url("sig"),
+            "https://solscan.io/tx/sig"
+        );
+    }
+}
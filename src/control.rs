@@ -0,0 +1,333 @@
+//! Runtime control and the in-process event stream, shared between the simulator loop,
+//! the gRPC control plane, and event publishers
+
+use crate::approvals::Approvals;
+use crate::portfolio_manager::PortfolioManager;
+use crate::sandwich::SandwichEstimate;
+use crate::strategy::TokenEvent;
+use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+/// Everything the bot observes and does, broadcast to in-process subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotEvent {
+    /// A scanned and enriched token
+    Token(TokenEvent),
+    Decision(DecisionEvent),
+    Trade(TradeEvent),
+    Health(HealthEvent),
+    MintChange(MintChangeEvent),
+}
+
+/// An entry decision or exit made by one strategy
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionEvent {
+    pub strategy_id: String,
+    pub token_id: String,
+    /// "buy", "skip", or "exit"
+    pub action: &'static str,
+    pub score: f64,
+    /// Filters that rejected the token, or the exit reason
+    pub reasons: Vec<String>,
+    /// Share of `max_sol_per_trade` the score sized the entry at, before the regime and
+    /// balance limits; unset on exits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_fraction: Option<f64>,
+    /// The price an entry was decided on and when it was observed (Unix ms); unset on exits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_usd: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priced_at_ms: Option<i64>,
+    /// How a live entry was sized and routed against sandwiches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandwich: Option<SandwichEstimate>,
+    pub at: DateTime<Utc>,
+}
+
+/// A filled buy or sell
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeEvent {
+    pub strategy_id: String,
+    pub token_id: String,
+    /// "buy" or "sell"
+    pub side: &'static str,
+    pub price_usd: f64,
+    pub qty: f64,
+    pub usd: f64,
+    /// Realized PnL, set on sells
+    pub pnl_usd: Option<f64>,
+    /// Exit reason, set on sells
+    pub reason: Option<String>,
+    pub is_reentry: bool,
+    /// Hypothetical trade of a shadow strategy
+    pub shadow: bool,
+    /// On-chain signature when trading live
+    pub signature: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// An external dependency turning degraded or recovering
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthEvent {
+    /// `rpc`, `pumpportal`, `dexscreener`, or `database`
+    pub dependency: &'static str,
+    pub healthy: bool,
+    /// Error of the failed check
+    pub detail: Option<String>,
+    /// Whether entries are paused after this change
+    pub entries_paused: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// A held token's mint or metadata changing in a way that can rug the position
+#[derive(Debug, Clone, Serialize)]
+pub struct MintChangeEvent {
+    pub strategy_id: String,
+    pub token_id: String,
+    /// See `mint_watch::MintChange`
+    pub reason: &'static str,
+    pub detail: String,
+    /// Whether the position is being sold for it
+    pub exiting: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// A strategy whose entries were paused after a losing streak
+#[derive(Debug, Clone, Serialize)]
+pub struct StrategyPause {
+    pub strategy_id: String,
+    pub reason: String,
+    pub since: DateTime<Utc>,
+    /// When entries resume on their own; unset until resumed through the API
+    pub until: Option<DateTime<Utc>>,
+}
+
+pub struct ControlState {
+    trading_enabled: AtomicBool,
+    /// Set by the health supervisor while a critical dependency is degraded
+    entries_paused: AtomicBool,
+    /// Strategies paused after a losing streak, keyed by strategy id
+    paused_strategies: RwLock<BTreeMap<String, StrategyPause>>,
+    /// Live strategy parameters, keyed by strategy id
+    strategies: RwLock<BTreeMap<String, StrategyConfig>>,
+    /// Bumped on every parameter change so the simulator knows to resync
+    version: AtomicU64,
+    events: broadcast::Sender<BotEvent>,
+    approvals: Approvals,
+    /// Entry orders an operator asked to cancel, until their strategy next works its orders
+    order_cancels: RwLock<HashSet<String>>,
+}
+
+pub type SharedControl = Arc<ControlState>;
+
+impl ControlState {
+    pub fn new(manager: &PortfolioManager) -> Self {
+        let strategies = manager
+            .slots
+            .iter()
+            .map(|slot| (slot.id.clone(), slot.config.clone()))
+            .collect();
+        let (events, _) = broadcast::channel(1024);
+        Self {
+            trading_enabled: AtomicBool::new(true),
+            entries_paused: AtomicBool::new(false),
+            paused_strategies: RwLock::new(BTreeMap::new()),
+            strategies: RwLock::new(strategies),
+            version: AtomicU64::new(0),
+            events,
+            approvals: Approvals::default(),
+            order_cancels: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Hold large live buys for `approvals`
+    pub fn with_approvals(mut self, approvals: Approvals) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    pub fn approvals(&self) -> &Approvals {
+        &self.approvals
+    }
+
+    /// Whether new positions may be opened; exits run regardless
+    pub fn trading_enabled(&self) -> bool {
+        self.trading_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_trading_enabled(&self, enabled: bool) {
+        self.trading_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn entries_paused(&self) -> bool {
+        self.entries_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_entries_paused(&self, paused: bool) {
+        self.entries_paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Whether trading is enabled and no critical dependency is degraded
+    pub fn entries_allowed(&self) -> bool {
+        self.trading_enabled() && !self.entries_paused()
+    }
+
+    pub fn pause_strategy(&self, pause: StrategyPause) {
+        self.paused_strategies
+            .write()
+            .unwrap()
+            .insert(pause.strategy_id.clone(), pause);
+    }
+
+    /// Lift a strategy's pause; `None` if it wasn't paused
+    pub fn resume_strategy(&self, strategy_id: &str) -> Option<StrategyPause> {
+        self.paused_strategies.write().unwrap().remove(strategy_id)
+    }
+
+    /// The strategy's pause, if it hasn't run out by `now`
+    pub fn strategy_pause(&self, strategy_id: &str, now: DateTime<Utc>) -> Option<StrategyPause> {
+        let mut paused = self.paused_strategies.write().unwrap();
+        let pause = paused.get(strategy_id)?;
+        if pause.until.is_some_and(|until| until <= now) {
+            paused.remove(strategy_id);
+            return None;
+        }
+        Some(pause.clone())
+    }
+
+    /// Pauses that haven't run out by `now`
+    pub fn paused_strategies(&self, now: DateTime<Utc>) -> Vec<StrategyPause> {
+        let mut paused = self.paused_strategies.write().unwrap();
+        paused.retain(|_, p| p.until.is_none_or(|until| until > now));
+        paused.values().cloned().collect()
+    }
+
+    pub fn strategy_ids(&self) -> Vec<String> {
+        self.strategies.read().unwrap().keys().cloned().collect()
+    }
+
+    pub fn config(&self, strategy_id: &str) -> Option<StrategyConfig> {
+        self.strategies.read().unwrap().get(strategy_id).cloned()
+    }
+
+    /// Apply `update` to a strategy's parameters; `None` for an unknown strategy
+    pub fn update_config(
+        &self,
+        strategy_id: &str,
+        update: impl FnOnce(&mut StrategyConfig),
+    ) -> Option<StrategyConfig> {
+        let mut strategies = self.strategies.write().unwrap();
+        let config = strategies.get_mut(strategy_id)?;
+        update(config);
+        self.version.fetch_add(1, Ordering::Relaxed);
+        Some(config.clone())
+    }
+
+    /// Ask for the rest of an entry order to be cancelled
+    pub fn cancel_order(&self, order_id: &str) {
+        self.order_cancels
+            .write()
+            .unwrap()
+            .insert(order_id.to_string());
+    }
+
+    /// Whether cancelling `order_id` was asked for, clearing the request
+    pub fn take_cancel(&self, order_id: &str) -> bool {
+        self.order_cancels.write().unwrap().remove(order_id)
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// Broadcast an event to subscribers; dropped when nobody is listening
+    pub fn publish(&self, event: BotEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BotEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::strategy_config::Profiles;
+
+    fn control() -> ControlState {
+        let manager =
+            PortfolioManager::from_specs("default", &Profiles::builtin(), &Config::from_env())
+                .unwrap();
+        ControlState::new(&manager)
+    }
+
+    #[test]
+    fn toggles_trading_and_versions_parameter_changes() {
+        let control = control();
+        assert!(control.entries_allowed());
+        control.set_trading_enabled(false);
+        assert!(!control.entries_allowed());
+        control.set_trading_enabled(true);
+        control.set_entries_paused(true);
+        assert!(control.trading_enabled() && !control.entries_allowed());
+
+        assert_eq!(control.strategy_ids(), ["default"]);
+        let updated = control
+            .update_config("default", |c| c.min_score_to_buy = 90.0)
+            .unwrap();
+        assert_eq!(updated.min_score_to_buy, 90.0);
+        assert_eq!(control.config("default").unwrap().min_score_to_buy, 90.0);
+        assert_eq!(control.version(), 1);
+
+        assert!(control.update_config("missing", |_| {}).is_none());
+        assert_eq!(control.version(), 1);
+    }
+
+    #[test]
+    fn broadcasts_decisions_to_subscribers() {
+        let control = control();
+        // Nobody listening yet: the event is dropped rather than queued
+        control.publish(BotEvent::Decision(DecisionEvent {
+            strategy_id: "default".to_string(),
+            token_id: "early".to_string(),
+            action: "skip",
+            score: 40.0,
+            reasons: vec!["holders".to_string()],
+            size_fraction: None,
+            price_usd: None,
+            priced_at_ms: None,
+            sandwich: None,
+            at: Utc::now(),
+        }));
+
+        let mut events = control.subscribe();
+        control.publish(BotEvent::Decision(DecisionEvent {
+            strategy_id: "default".to_string(),
+            token_id: "mint".to_string(),
+            action: "buy",
+            score: 80.0,
+            reasons: Vec::new(),
+            size_fraction: Some(1.0),
+            price_usd: Some(0.00003),
+            priced_at_ms: None,
+            sandwich: None,
+            at: Utc::now(),
+        }));
+        let BotEvent::Decision(decision) = events.try_recv().unwrap() else {
+            panic!("expected a decision");
+        };
+        assert_eq!(
+            (decision.token_id.as_str(), decision.action),
+            ("mint", "buy")
+        );
+        assert!(events.try_recv().is_err());
+    }
+}
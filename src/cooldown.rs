@@ -0,0 +1,160 @@
+use crate::strategy::FilterRejection;
+use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Why a token was put on cooldown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownReason {
+    Rejected,
+    Exited,
+    StoppedOut,
+}
+
+impl CooldownReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CooldownReason::Rejected => "rejected",
+            CooldownReason::Exited => "exited",
+            CooldownReason::StoppedOut => "stopped_out",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CooldownEntry {
+    until: DateTime<Utc>,
+    reason: CooldownReason,
+    score: f64,
+}
+
+/// Remembers recently exited or rejected tokens so the polling loop doesn't immediately re-enter them
+#[derive(Debug, Default)]
+pub struct CooldownTracker {
+    entries: HashMap<String, CooldownEntry>,
+}
+
+impl CooldownTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start (or extend) a cooldown for a token, remembering the score it had at the time
+    pub fn record(
+        &mut self,
+        token_id: &str,
+        reason: CooldownReason,
+        score: f64,
+        now: DateTime<Utc>,
+        config: &StrategyConfig,
+    ) {
+        let secs = match reason {
+            CooldownReason::Rejected => config.rejection_cooldown_secs,
+            CooldownReason::Exited => config.exit_cooldown_secs,
+            CooldownReason::StoppedOut => config.stop_loss_cooldown_secs,
+        };
+        if secs == 0 {
+            return;
+        }
+        let until = now + Duration::seconds(secs as i64);
+
+        // Never shorten an existing, longer cooldown (e.g. a rejection right after a stop-out)
+        if let Some(existing) = self.entries.get(token_id)
+            && existing.until > until
+        {
+            return;
+        }
+        self.entries.insert(
+            token_id.to_string(),
+            CooldownEntry {
+                until,
+                reason,
+                score,
+            },
+        );
+    }
+
+    /// Returns a rejection if the token is cooling down and its score hasn't improved enough
+    pub fn check(
+        &mut self,
+        token_id: &str,
+        score: f64,
+        now: DateTime<Utc>,
+        config: &StrategyConfig,
+    ) -> Option<FilterRejection> {
+        let entry = self.entries.get(token_id)?;
+        if entry.until <= now {
+            self.entries.remove(token_id);
+            return None;
+        }
+        if score >= entry.score + config.cooldown_override_score_improvement {
+            return None;
+        }
+        Some(FilterRejection::new(
+            "cooldown",
+            format!(
+                "{}, {}s remaining, score {:.1} < {:.1} needed to override",
+                entry.reason.as_str(),
+                (entry.until - now).num_seconds(),
+                score,
+                entry.score + config.cooldown_override_score_improvement
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> StrategyConfig {
+        StrategyConfig {
+            rejection_cooldown_secs: 60,
+            exit_cooldown_secs: 120,
+            stop_loss_cooldown_secs: 600,
+            cooldown_override_score_improvement: 10.0,
+            ..StrategyConfig::default()
+        }
+    }
+
+    #[test]
+    fn blocks_until_expiry_unless_the_score_improves() {
+        let config = config();
+        let now = Utc::now();
+        let mut tracker = CooldownTracker::new();
+        tracker.record("mint", CooldownReason::Exited, 70.0, now, &config);
+
+        let soon = now + Duration::seconds(60);
+        let rejection = tracker.check("mint", 75.0, soon, &config).unwrap();
+        assert_eq!(rejection.filter, "cooldown");
+        assert!(rejection.detail.starts_with("exited, 60s remaining"));
+        assert!(tracker.check("mint", 80.0, soon, &config).is_none());
+        assert!(tracker.check("other", 0.0, soon, &config).is_none());
+
+        let later = now + Duration::seconds(120);
+        assert!(tracker.check("mint", 0.0, later, &config).is_none());
+    }
+
+    #[test]
+    fn keeps_the_longer_cooldown() {
+        let config = config();
+        let now = Utc::now();
+        let mut tracker = CooldownTracker::new();
+        tracker.record("mint", CooldownReason::StoppedOut, 70.0, now, &config);
+        tracker.record("mint", CooldownReason::Rejected, 70.0, now, &config);
+
+        let after_rejection = now + Duration::seconds(300);
+        let rejection = tracker
+            .check("mint", 0.0, after_rejection, &config)
+            .unwrap();
+        assert!(rejection.detail.starts_with("stopped_out"));
+
+        // A zero-length cooldown records nothing
+        let disabled = StrategyConfig {
+            rejection_cooldown_secs: 0,
+            ..config
+        };
+        tracker.record("fresh", CooldownReason::Rejected, 70.0, now, &disabled);
+        assert!(tracker.check("fresh", 0.0, now, &disabled).is_none());
+    }
+}
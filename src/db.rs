@@ -1,5 +1,21 @@
-use sqlx::PgPool;
+use crate::strategy::TokenEvent;
 use anyhow::Result;
+use sqlx::postgres::PgRow;
+use sqlx::{Executor, PgPool, Row};
+
+/// `token_events` columns (aliased `e`) that `stored_event` reads
+pub const EVENT_COLUMNS: &str = "e.id, e.token_type, e.market_cap_usd, e.dev_hold_pct, \
+     e.liquidity_usd, e.holders, e.upgradeable, e.freeze_authority, e.momentum, e.graduation, \
+     e.base_price, e.dev_wallet, e.first_block_buys, e.trades::TEXT AS trades";
+
+/// The latest observation of each mint in `token_events`, to select from in place of the
+/// table where a mint should count once
+pub const LATEST_EVENTS: &str = "(SELECT DISTINCT ON (id) * FROM token_events \
+     ORDER BY id, generated_at DESC, event_id DESC)";
+
+/// The first observation of each mint, for what is measured from when it was detected
+pub const FIRST_EVENTS: &str = "(SELECT DISTINCT ON (id) * FROM token_events \
+     ORDER BY id, generated_at, event_id)";
 
 pub async fn connect(db_url: &str) -> Result<PgPool> {
     let pool = PgPool::connect(db_url).await?;
@@ -7,13 +23,82 @@ pub async fn connect(db_url: &str) -> Result<PgPool> {
 }
 
 pub async fn ensure_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
-    // Run the simple migration SQL from file path migrations/001_create_tables.sql
-    let sql = std::fs::read_to_string("migrations/001_create_tables.sql").expect("migrations file missing");
-    // Split the file into statements and execute them one-by-one
-    for stmt in sql.split(';') {
-        let s = stmt.trim();
-        if s.is_empty() { continue; }
-        sqlx::query(s).execute(pool).await?;
+    // Run every migrations/*.sql file in name order (001_..., 002_..., ...)
+    let mut files: Vec<_> = std::fs::read_dir("migrations")
+        .expect("migrations directory missing")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+
+    for file in files {
+        let sql = std::fs::read_to_string(&file).expect("migrations file unreadable");
+        // Send the whole file as one simple query; Postgres parses the statements itself,
+        // so semicolons inside comments and string literals are safe
+        pool.execute(sql.as_str()).await?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Rebuild a token event from its `token_events` row. Null columns become placeholders
+/// listed in `missing_fields`, as do the dev initial buy, bonding curve progress, and token
+/// age, which the table doesn't record
+pub fn stored_event(r: &PgRow) -> TokenEvent {
+    let market_cap: Option<f64> = r.get("market_cap_usd");
+    let dev_hold: Option<f64> = r.get("dev_hold_pct");
+    let liquidity: Option<f64> = r.get("liquidity_usd");
+    let holders: Option<i32> = r.get("holders");
+    let upgradeable: Option<bool> = r.get("upgradeable");
+    let freeze_authority: Option<bool> = r.get("freeze_authority");
+
+    let mut missing = vec!["dev_initial_buy", "bonding_curve_progress", "token_age"];
+    for (field, known) in [
+        ("market_cap", market_cap.is_some()),
+        ("liquidity", liquidity.is_some()),
+        ("holders", holders.is_some()),
+        ("dev_hold", dev_hold.is_some()),
+        (
+            "authorities",
+            upgradeable.is_some() && freeze_authority.is_some(),
+        ),
+    ] {
+        if !known {
+            missing.push(field);
+        }
+    }
+
+    TokenEvent {
+        id: r.get("id"),
+        source: "pumpfun".to_string(),
+        token_type: r.get::<Option<String>, _>("token_type").unwrap_or_default(),
+        market_cap_usd: market_cap.unwrap_or(0.0),
+        dev_hold_pct: dev_hold.unwrap_or(0.0),
+        liquidity_usd: liquidity.unwrap_or(0.0),
+        holders: holders.unwrap_or(0),
+        upgradeable: upgradeable.unwrap_or(false),
+        freeze_authority: freeze_authority.unwrap_or(false),
+        momentum: r.get::<Option<bool>, _>("momentum").unwrap_or(false),
+        graduation: r.get::<Option<bool>, _>("graduation").unwrap_or(false),
+        base_price: r.get::<Option<f64>, _>("base_price").unwrap_or(0.0),
+        priced_at_ms: None,
+        dev_wallet_address: r.get("dev_wallet"),
+        is_dev_known_rugger: false,
+        token_age_secs: None,
+        launched_at: None,
+        dev_initial_buy_pct: None,
+        bonding_curve_progress_pct: None,
+        signal_confidence: None,
+        blacklisted: false,
+        missing_fields: missing.iter().map(|f| f.to_string()).collect(),
+        dev_stats: None,
+        effective_top_holder_pct: None,
+        transfer_fee_pct: None,
+        round_trip_tax_pct: None,
+        first_block_buys: r
+            .get::<Option<i32>, _>("first_block_buys")
+            .map(|n| n.max(0) as u32),
+        trades: r
+            .get::<Option<String>, _>("trades")
+            .and_then(|t| serde_json::from_str(&t).ok()),
+    }
+}
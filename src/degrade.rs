@@ -0,0 +1,207 @@
+//! Cheaper enrichment while the RPC is throttling
+//!
+//! After each poll, the RPC's `429 Too Many Requests` responses since the previous poll are
+//! counted. `DEGRADE_THROTTLE_THRESHOLD` of them drop the pipeline one tier: `reduced` skips
+//! the holder scan (and funding tracing) and doubles the poll interval, `minimal` also skips
+//! the mint age and creator buy lookups and polls at a quarter of the rate. After
+//! `DEGRADE_RECOVER_POLLS` polls in a row without throttling it climbs back a tier. Skipped
+//! steps leave their fields in `missing_fields` rather than zeroed, and tier changes are
+//! logged and exported on `/metrics`.
+
+use crate::scanner::ApiErrorCounters;
+use crate::simulator::Enrichment;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Full,
+    Reduced,
+    Minimal,
+}
+
+impl Tier {
+    const ALL: [Tier; 3] = [Tier::Full, Tier::Reduced, Tier::Minimal];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tier::Full => "full",
+            Tier::Reduced => "reduced",
+            Tier::Minimal => "minimal",
+        }
+    }
+
+    fn cheaper(self) -> Tier {
+        Tier::ALL[(self as usize + 1).min(Tier::ALL.len() - 1)]
+    }
+
+    fn richer(self) -> Tier {
+        Tier::ALL[(self as usize).saturating_sub(1)]
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    /// Throttled responses counted as of the last poll
+    seen: u64,
+    clean_polls: u64,
+}
+
+/// The current tier, stepped by how much the RPC throttled each poll
+pub struct Degradation {
+    /// Throttled responses in one poll that drop a tier; 0 never degrades
+    threshold: u64,
+    recover_polls: u64,
+    counters: Option<Arc<ApiErrorCounters>>,
+    window: Mutex<Window>,
+    tier: AtomicU8,
+    changes: AtomicU64,
+}
+
+impl Degradation {
+    pub fn new(threshold: u64, recover_polls: u64) -> Self {
+        Self {
+            threshold,
+            recover_polls,
+            counters: None,
+            window: Mutex::new(Window::default()),
+            tier: AtomicU8::new(Tier::Full as u8),
+            changes: AtomicU64::new(0),
+        }
+    }
+
+    /// Watch the scanner's throttled RPC responses; without them the tier never changes
+    pub fn with_api_errors(mut self, counters: Arc<ApiErrorCounters>) -> Self {
+        self.counters = Some(counters);
+        self
+    }
+
+    pub fn tier(&self) -> Tier {
+        Tier::ALL[self.tier.load(Ordering::Relaxed) as usize]
+    }
+
+    /// Step the tier after a poll; logs and returns the new tier when it changed
+    pub fn refresh(&self) -> Option<Tier> {
+        let total = self
+            .counters
+            .as_ref()
+            .map_or(0, |c| c.rpc_throttled.load(Ordering::Relaxed));
+        self.observe(total)
+    }
+
+    /// Step the tier given the throttled responses counted since startup
+    fn observe(&self, throttled_total: u64) -> Option<Tier> {
+        if self.threshold == 0 {
+            return None;
+        }
+        let mut window = self.window.lock().unwrap();
+        let throttled = throttled_total.saturating_sub(window.seen);
+        window.seen = throttled_total;
+        let current = self.tier();
+        let next = if throttled >= self.threshold {
+            window.clean_polls = 0;
+            current.cheaper()
+        } else if throttled == 0 {
+            window.clean_polls += 1;
+            if window.clean_polls >= self.recover_polls {
+                window.clean_polls = 0;
+                current.richer()
+            } else {
+                current
+            }
+        } else {
+            window.clean_polls = 0;
+            current
+        };
+        if next == current {
+            return None;
+        }
+        self.tier.store(next as u8, Ordering::Relaxed);
+        self.changes.fetch_add(1, Ordering::Relaxed);
+        println!(
+            "[degrade] Enrichment {} from {} to {} ({} throttled RPC responses this poll)",
+            if next > current {
+                "degraded"
+            } else {
+                "recovered"
+            },
+            current.as_str(),
+            next.as_str(),
+            throttled
+        );
+        Some(next)
+    }
+
+    /// The configured steps, less those the current tier skips
+    pub fn enrichment(&self, configured: Enrichment) -> Enrichment {
+        let tier = self.tier();
+        Enrichment {
+            holders: configured.holders && tier < Tier::Reduced,
+            mint_age: configured.mint_age && tier < Tier::Minimal,
+            creator_buy: configured.creator_buy && tier < Tier::Minimal,
+            ..configured
+        }
+    }
+
+    /// The configured poll interval, lengthened by the current tier
+    pub fn poll_interval(&self, configured: Duration) -> Duration {
+        configured * (1 << (self.tier() as u32))
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        let throttled = self
+            .counters
+            .as_ref()
+            .map_or(0, |c| c.rpc_throttled.load(Ordering::Relaxed));
+        format!(
+            "# TYPE memebot_degradation_tier gauge\nmemebot_degradation_tier {}\n\
+             # TYPE memebot_degradation_changes_total counter\nmemebot_degradation_changes_total {}\n\
+             # TYPE memebot_rpc_throttled_total counter\nmemebot_rpc_throttled_total {}\n",
+            self.tier() as u8,
+            self.changes.load(Ordering::Relaxed),
+            throttled
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrades_under_throttling_and_recovers() {
+        let degradation = Degradation::new(3, 2);
+        let configured = Enrichment::default();
+        assert_eq!(degradation.observe(2), None);
+        assert_eq!(degradation.observe(5), Some(Tier::Reduced));
+        let steps = degradation.enrichment(configured);
+        assert!(!steps.holders && steps.mint_age && steps.dexscreener);
+        assert_eq!(
+            degradation.poll_interval(Duration::from_secs(5)),
+            Duration::from_secs(10)
+        );
+
+        assert_eq!(degradation.observe(9), Some(Tier::Minimal));
+        assert_eq!(degradation.observe(20), None);
+        assert!(!degradation.enrichment(configured).creator_buy);
+
+        // A poll with some throttling below the threshold restarts the recovery count
+        assert_eq!(degradation.observe(20), None);
+        assert_eq!(degradation.observe(21), None);
+        assert_eq!(degradation.observe(21), None);
+        assert_eq!(degradation.observe(21), Some(Tier::Reduced));
+        assert_eq!(degradation.observe(21), None);
+        assert_eq!(degradation.observe(21), Some(Tier::Full));
+        assert!(degradation.enrichment(configured).holders);
+        assert!(
+            degradation
+                .render_prometheus()
+                .contains("memebot_degradation_changes_total 4")
+        );
+
+        let disabled = Degradation::new(0, 2);
+        assert_eq!(disabled.observe(100), None);
+        assert_eq!(disabled.tier(), Tier::Full);
+    }
+}
@@ -0,0 +1,132 @@
+//! Per-creator history in the `dev_wallets` table: how many of a wallet's tokens were
+//! labeled, how many graduated or were rugged, and how high they peaked
+//!
+//! The outcome labeler adds each token to its creator's row as it labels it, so only tokens
+//! recorded with a creator count. Enrichment attaches the creator's row to new tokens as
+//! `dev_stats`.
+
+use crate::outcomes::Outcome;
+use crate::strategy::{DevStats, TokenEvent};
+use anyhow::Result;
+use sqlx::{PgExecutor, PgPool, Row};
+use std::collections::HashMap;
+
+/// Stats from a `dev_wallets` row's counters
+fn from_totals(tokens_launched: i32, graduated: i32, rugged: i32, peak_sum: f64) -> DevStats {
+    let launched = tokens_launched.max(0) as u32;
+    let per_token = |total: f64| {
+        if launched == 0 {
+            0.0
+        } else {
+            total / launched as f64
+        }
+    };
+    DevStats {
+        tokens_launched: launched,
+        graduation_rate: per_token(graduated as f64),
+        avg_peak_multiple: per_token(peak_sum),
+        rug_count: rugged.max(0) as u32,
+    }
+}
+
+/// Add a labeled token to its creator's row
+pub async fn record_outcome<'e>(
+    executor: impl PgExecutor<'e>,
+    address: &str,
+    outcome: &Outcome,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO dev_wallets (address, tokens_launched, graduated, rugged, peak_multiple_sum) \
+         VALUES ($1, 1, $2, $3, $4) ON CONFLICT (address) DO UPDATE SET \
+         tokens_launched = dev_wallets.tokens_launched + 1, \
+         graduated = dev_wallets.graduated + EXCLUDED.graduated, \
+         rugged = dev_wallets.rugged + EXCLUDED.rugged, \
+         peak_multiple_sum = dev_wallets.peak_multiple_sum + EXCLUDED.peak_multiple_sum, \
+         updated_at = NOW()",
+    )
+    .bind(address)
+    .bind(outcome.graduated as i32)
+    .bind((outcome.label == "rugged") as i32)
+    .bind(outcome.peak_multiple)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+fn stats(row: &sqlx::postgres::PgRow) -> DevStats {
+    from_totals(
+        row.get("tokens_launched"),
+        row.get("graduated"),
+        row.get("rugged"),
+        row.get("peak_multiple_sum"),
+    )
+}
+
+/// One creator's history, None if none of its tokens was labeled
+pub async fn lookup(pool: &PgPool, address: &str) -> Result<Option<DevStats>> {
+    let row = sqlx::query(
+        "SELECT tokens_launched, graduated, rugged, peak_multiple_sum FROM dev_wallets \
+         WHERE address = $1",
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(stats))
+}
+
+/// In-memory snapshot of `dev_wallets`, consulted for every enriched token
+#[derive(Debug, Default)]
+pub struct DevWallets {
+    wallets: HashMap<String, DevStats>,
+}
+
+impl DevWallets {
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        let mut wallets = HashMap::new();
+        for row in sqlx::query(
+            "SELECT address, tokens_launched, graduated, rugged, peak_multiple_sum \
+             FROM dev_wallets",
+        )
+        .fetch_all(pool)
+        .await?
+        {
+            wallets.insert(row.get("address"), stats(&row));
+        }
+        Ok(Self { wallets })
+    }
+
+    /// Attach the creator's history to a token
+    pub fn apply(&self, ev: &mut TokenEvent) {
+        if let Some(dev) = &ev.dev_wallet_address {
+            ev.dev_stats = self.wallets.get(dev).cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PumpFunListing;
+
+    #[test]
+    fn averages_over_launched_tokens() {
+        let stats = from_totals(4, 1, 2, 10.0);
+        assert_eq!(stats.tokens_launched, 4);
+        assert_eq!(stats.graduation_rate, 0.25);
+        assert_eq!(stats.avg_peak_multiple, 2.5);
+        assert_eq!(stats.rug_count, 2);
+        assert_eq!(from_totals(0, 0, 0, 0.0).avg_peak_multiple, 0.0);
+
+        let wallets = DevWallets {
+            wallets: HashMap::from([("creator".to_string(), stats.clone())]),
+        };
+        let mut ev: TokenEvent = PumpFunListing {
+            token_address: "mint".to_string(),
+            creator: Some("creator".to_string()),
+            ..Default::default()
+        }
+        .into();
+        wallets.apply(&mut ev);
+        assert_eq!(ev.dev_stats, Some(stats));
+    }
+}
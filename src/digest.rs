@@ -0,0 +1,314 @@
+//! Daily digest of the last 24 hours, posted to Telegram and/or Discord
+//!
+//! Once a day at `DIGEST_HOUR_UTC` the bot sends tokens scanned, buys, exits by reason,
+//! realized PnL, the best and worst closed trade, open positions, and how many requests to
+//! each upstream API failed since the previous digest. `digest` prints the same summary on
+//! demand (without API errors, which only a running bot counts).
+
+use crate::notifier::Notifier;
+use crate::scanner::ApiErrorCounters;
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use sqlx::{PgPool, Row};
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Open positions listed by name; the rest are only counted
+const MAX_LISTED_POSITIONS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct ClosedTrade {
+    pub token_id: String,
+    pub strategy_id: String,
+    pub pnl_usd: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenPosition {
+    pub token_id: String,
+    pub strategy_id: String,
+    pub usd_in: f64,
+    /// From the latest mark, if the position has been marked yet
+    pub unrealized_pnl_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub since: DateTime<Utc>,
+    pub tokens_scanned: i64,
+    pub buys: i64,
+    /// Closed trades per exit reason, most frequent first
+    pub exits: Vec<(String, i64)>,
+    pub realized_pnl_usd: f64,
+    pub best: Option<ClosedTrade>,
+    /// Unset unless at least two trades closed
+    pub worst: Option<ClosedTrade>,
+    pub open_positions: Vec<OpenPosition>,
+    /// Failed requests per API over the window; `None` when not counted
+    pub api_errors: Option<Vec<(&'static str, u64)>>,
+}
+
+impl Digest {
+    /// Summarize the `window` up to now from the database
+    pub async fn load(pool: &PgPool, window: Duration) -> Result<Self> {
+        let secs = window.num_seconds() as f64;
+        let since = "NOW() - make_interval(secs => $1)";
+
+        let tokens_scanned: i64 = sqlx::query(&format!(
+            "SELECT COUNT(DISTINCT id) AS n FROM token_events \
+             WHERE generated_at >= {} AND backfill_source IS NULL",
+            since
+        ))
+        .bind(secs)
+        .fetch_one(pool)
+        .await?
+        .get("n");
+        let buys: i64 = sqlx::query(&format!(
+            "SELECT COUNT(*) AS n FROM trades WHERE opened_at >= {} AND NOT shadow",
+            since
+        ))
+        .bind(secs)
+        .fetch_one(pool)
+        .await?
+        .get("n");
+
+        // Trades closed by reconciliation have no exit reason, only their action
+        let exits = sqlx::query(&format!(
+            "SELECT COALESCE(exit_reason, LOWER(action)) AS reason, COUNT(*) AS n FROM trades \
+             WHERE closed_at >= {} AND NOT shadow GROUP BY 1 ORDER BY 2 DESC, 1",
+            since
+        ))
+        .bind(secs)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|r| (r.get("reason"), r.get("n")))
+        .collect();
+
+        let closed = sqlx::query(&format!(
+            "SELECT token_id, strategy_id, pnl FROM trades \
+             WHERE closed_at >= {} AND pnl IS NOT NULL AND NOT shadow ORDER BY pnl DESC",
+            since
+        ))
+        .bind(secs)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|r| ClosedTrade {
+            token_id: r.get("token_id"),
+            strategy_id: r.get("strategy_id"),
+            pnl_usd: r.get("pnl"),
+        })
+        .collect::<Vec<_>>();
+
+        let open_positions = sqlx::query(
+            "SELECT t.token_id, t.strategy_id, t.usd_in, m.unrealized_pnl FROM trades t \
+             LEFT JOIN LATERAL (SELECT unrealized_pnl FROM position_marks \
+             WHERE token_id = t.token_id AND strategy_id = t.strategy_id \
+             ORDER BY marked_at DESC LIMIT 1) m ON TRUE \
+             WHERE t.closed_at IS NULL AND NOT t.shadow ORDER BY t.opened_at",
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|r| OpenPosition {
+            token_id: r.get("token_id"),
+            strategy_id: r.get("strategy_id"),
+            usd_in: r.get::<Option<f64>, _>("usd_in").unwrap_or(0.0),
+            unrealized_pnl_usd: r.get("unrealized_pnl"),
+        })
+        .collect();
+
+        Ok(Self {
+            since: Utc::now() - window,
+            tokens_scanned,
+            buys,
+            exits,
+            realized_pnl_usd: closed.iter().map(|t| t.pnl_usd).sum(),
+            best: closed.first().cloned(),
+            worst: closed.last().filter(|_| closed.len() > 1).cloned(),
+            open_positions,
+            api_errors: None,
+        })
+    }
+
+    /// Plain-text message, the same for every channel
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "memebot digest since {}\n\n",
+            self.since.format("%Y-%m-%d %H:%M UTC")
+        );
+        let _ = writeln!(out, "Tokens scanned: {}", self.tokens_scanned);
+        let _ = writeln!(out, "Buys: {}", self.buys);
+        let exits: i64 = self.exits.iter().map(|(_, n)| n).sum();
+        let by_reason: Vec<String> = self
+            .exits
+            .iter()
+            .map(|(reason, n)| format!("{} {}", reason, n))
+            .collect();
+        if by_reason.is_empty() {
+            let _ = writeln!(out, "Exits: 0");
+        } else {
+            let _ = writeln!(out, "Exits: {} ({})", exits, by_reason.join(", "));
+        }
+        let _ = writeln!(out, "Realized PnL: {}", usd(self.realized_pnl_usd));
+        if let Some(best) = &self.best {
+            let _ = writeln!(out, "Best trade: {}", trade(best));
+        }
+        if let Some(worst) = &self.worst {
+            let _ = writeln!(out, "Worst trade: {}", trade(worst));
+        }
+
+        let _ = writeln!(out, "\nOpen positions: {}", self.open_positions.len());
+        for pos in self.open_positions.iter().take(MAX_LISTED_POSITIONS) {
+            let pnl = pos.unrealized_pnl_usd.map_or("unmarked".to_string(), usd);
+            let _ = writeln!(
+                out,
+                "  {} [{}] ${:.2} in, {}",
+                pos.token_id, pos.strategy_id, pos.usd_in, pnl
+            );
+        }
+        if self.open_positions.len() > MAX_LISTED_POSITIONS {
+            let _ = writeln!(
+                out,
+                "  ... and {} more",
+                self.open_positions.len() - MAX_LISTED_POSITIONS
+            );
+        }
+
+        if let Some(errors) = &self.api_errors {
+            let counts: Vec<String> = errors
+                .iter()
+                .map(|(api, n)| format!("{} {}", api, n))
+                .collect();
+            let _ = writeln!(out, "\nAPI errors: {}", counts.join(", "));
+        }
+        out
+    }
+}
+
+fn usd(amount: f64) -> String {
+    if amount < 0.0 {
+        format!("-${:.2}", -amount)
+    } else {
+        format!("+${:.2}", amount)
+    }
+}
+
+fn trade(t: &ClosedTrade) -> String {
+    format!("{} [{}] {}", t.token_id, t.strategy_id, usd(t.pnl_usd))
+}
+
+/// Time from `now` until the next `hour`:00 UTC
+pub fn until_next(now: DateTime<Utc>, hour: u32) -> Duration {
+    let at = NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or(NaiveTime::MIN);
+    let mut next = now.date_naive().and_time(at).and_utc();
+    if next <= now {
+        next += Duration::days(1);
+    }
+    next - now
+}
+
+/// Sends the digest once a day through the notifier
+#[derive(Clone)]
+pub struct DigestNotifier {
+    notifier: Arc<Notifier>,
+    pool: PgPool,
+    hour_utc: u32,
+    api_errors: Option<Arc<ApiErrorCounters>>,
+}
+
+impl DigestNotifier {
+    pub fn new(notifier: Arc<Notifier>, pool: PgPool, hour_utc: u32) -> Self {
+        Self {
+            notifier,
+            pool,
+            hour_utc,
+            api_errors: None,
+        }
+    }
+
+    /// Report the scanner's API errors, as counted since the previous digest
+    pub fn with_api_errors(mut self, counters: Arc<ApiErrorCounters>) -> Self {
+        self.api_errors = Some(counters);
+        self
+    }
+
+    /// Send a digest every day at the configured hour until the process exits
+    pub async fn run(self) {
+        let mut reported = self.api_errors.as_ref().map(|c| c.snapshot());
+        loop {
+            let wait = until_next(Utc::now(), self.hour_utc);
+            tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+
+            let mut digest = match Digest::load(&self.pool, Duration::hours(24)).await {
+                Ok(digest) => digest,
+                Err(e) => {
+                    println!("[digest] Failed to load the daily summary: {}", e);
+                    continue;
+                }
+            };
+            if let (Some(counters), Some(previous)) = (&self.api_errors, &mut reported) {
+                let now = counters.snapshot();
+                digest.api_errors = Some(
+                    now.iter()
+                        .zip(previous.iter())
+                        .map(|((api, n), (_, before))| (*api, n - before))
+                        .collect(),
+                );
+                *previous = now;
+            }
+            self.notifier.send(&digest.render()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_summary_and_schedules_the_next_send() {
+        let since = "2026-10-15T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let closed = |token_id: &str, pnl_usd| ClosedTrade {
+            token_id: token_id.to_string(),
+            strategy_id: "default".to_string(),
+            pnl_usd,
+        };
+        let digest = Digest {
+            since,
+            tokens_scanned: 1_204,
+            buys: 3,
+            exits: vec![
+                ("profit_target".to_string(), 2),
+                ("stop_loss".to_string(), 1),
+            ],
+            realized_pnl_usd: 12.5,
+            best: Some(closed("MintA", 20.0)),
+            worst: Some(closed("MintB", -7.5)),
+            open_positions: vec![OpenPosition {
+                token_id: "MintC".to_string(),
+                strategy_id: "default".to_string(),
+                usd_in: 15.0,
+                unrealized_pnl_usd: None,
+            }],
+            api_errors: Some(vec![("rpc", 4), ("dexscreener", 0), ("jupiter", 1)]),
+        };
+        assert_eq!(
+            digest.render(),
+            "memebot digest since 2026-10-15 08:00 UTC\n\n\
+             Tokens scanned: 1204\n\
+             Buys: 3\n\
+             Exits: 3 (profit_target 2, stop_loss 1)\n\
+             Realized PnL: +$12.50\n\
+             Best trade: MintA [default] +$20.00\n\
+             Worst trade: MintB [default] -$7.50\n\
+             \nOpen positions: 1\n  MintC [default] $15.00 in, unmarked\n\
+             \nAPI errors: rpc 4, dexscreener 0, jupiter 1\n"
+        );
+
+        let now = "2026-10-16T09:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(until_next(now, 8), Duration::minutes(22 * 60 + 30));
+        assert_eq!(until_next(now, 10), Duration::minutes(30));
+    }
+}
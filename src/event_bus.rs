@@ -0,0 +1,161 @@
+//! Optional publisher that forwards the bot's event stream to NATS or Kafka as JSON
+//!
+//! Events go to `<prefix>.tokens`, `<prefix>.decisions`, `<prefix>.trades`, and
+//! `<prefix>.health` (NATS subjects or Kafka topics). Kafka messages are keyed by token id,
+//! or by dependency name for health events; NATS messages carry the key in a `Token-Id`
+//! header.
+
+use crate::control::BotEvent;
+use anyhow::{Result, bail};
+use tokio::sync::broadcast;
+
+enum Sink {
+    Nats(async_nats::Client),
+    #[cfg(feature = "kafka")]
+    Kafka(rdkafka::producer::FutureProducer),
+}
+
+pub struct EventBusPublisher {
+    sink: Sink,
+    prefix: String,
+}
+
+impl EventBusPublisher {
+    /// Connect to `nats://host:port` or `kafka://broker1:9092,broker2:9092`
+    pub async fn connect(url: &str, prefix: &str) -> Result<Self> {
+        let sink = if let Some(brokers) = url.strip_prefix("kafka://") {
+            Self::kafka_sink(brokers)?
+        } else if url.starts_with("nats://") || url.starts_with("tls://") {
+            Sink::Nats(async_nats::connect(url).await?)
+        } else {
+            bail!(
+                "unsupported event bus URL '{}' (expected nats:// or kafka://)",
+                url
+            );
+        };
+        Ok(Self {
+            sink,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    #[cfg(feature = "kafka")]
+    fn kafka_sink(brokers: &str) -> Result<Sink> {
+        let producer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "5000")
+            .create()?;
+        Ok(Sink::Kafka(producer))
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    fn kafka_sink(_brokers: &str) -> Result<Sink> {
+        bail!("Kafka publishing requires building with `--features kafka`")
+    }
+
+    /// Forward events until the sender side is dropped; publish failures are logged and skipped
+    pub async fn run(self, mut events: broadcast::Receiver<BotEvent>) {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    println!("[event_bus] Publisher fell behind, dropped {} events", n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let (kind, key) = route(&event);
+            let subject = format!("{}.{}", self.prefix, kind);
+            let payload = match serde_json::to_vec(&event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    println!("[event_bus] Failed to serialize {} event: {}", kind, e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.send(&subject, key, payload).await {
+                println!("[event_bus] Failed to publish to {}: {}", subject, e);
+            }
+        }
+    }
+
+    async fn send(&self, subject: &str, key: &str, payload: Vec<u8>) -> Result<()> {
+        match &self.sink {
+            Sink::Nats(client) => {
+                let mut headers = async_nats::HeaderMap::new();
+                headers.insert("Token-Id", key);
+                client
+                    .publish_with_headers(subject.to_string(), headers, payload.into())
+                    .await?;
+            }
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(producer) => {
+                use rdkafka::producer::FutureRecord;
+                let record = FutureRecord::to(subject).key(key).payload(&payload);
+                producer
+                    .send(record, std::time::Duration::from_secs(5))
+                    .await
+                    .map_err(|(e, _)| e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Subject suffix and message key an event is published under
+fn route(event: &BotEvent) -> (&'static str, &str) {
+    match event {
+        BotEvent::Token(e) => ("tokens", e.id.as_str()),
+        BotEvent::Decision(e) => ("decisions", e.token_id.as_str()),
+        BotEvent::Trade(e) => ("trades", e.token_id.as_str()),
+        BotEvent::Health(e) => ("health", e.dependency),
+        BotEvent::MintChange(e) => ("mint_changes", e.token_id.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::{HealthEvent, TradeEvent};
+    use chrono::Utc;
+
+    #[test]
+    fn routes_events_by_kind_and_key() {
+        let trade = BotEvent::Trade(TradeEvent {
+            strategy_id: "default".to_string(),
+            token_id: "mint".to_string(),
+            side: "sell",
+            price_usd: 0.00004,
+            qty: 1_000_000.0,
+            usd: 40.0,
+            pnl_usd: Some(10.0),
+            reason: Some("profit_target".to_string()),
+            is_reentry: false,
+            shadow: false,
+            signature: None,
+            at: Utc::now(),
+        });
+        assert_eq!(route(&trade), ("trades", "mint"));
+        let json = serde_json::to_value(&trade).unwrap();
+        assert_eq!(json["type"], "trade");
+        assert_eq!(json["side"], "sell");
+
+        let health = BotEvent::Health(HealthEvent {
+            dependency: "rpc",
+            healthy: false,
+            detail: Some("timed out".to_string()),
+            entries_paused: true,
+            at: Utc::now(),
+        });
+        assert_eq!(route(&health), ("health", "rpc"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_urls() {
+        let err = EventBusPublisher::connect("redis://localhost", "bot")
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("unsupported event bus URL"));
+    }
+}
@@ -0,0 +1,1476 @@
+use crate::buy_template::{
+    BuyTemplate, COMPUTE_BUDGET_PROGRAM_ID, SET_COMPUTE_UNIT_LIMIT, TOKEN_DECIMALS,
+    TOKEN_PROGRAM_ID, associated_token_address,
+};
+use crate::cluster::Cluster;
+use crate::message::{self, AccountMeta, Instruction, LookupTable};
+use crate::relay::{Relay, RelayClient};
+use crate::signer::Signer;
+use crate::token_accounts;
+use anyhow::{Context, Result, anyhow, bail};
+use ed25519_dalek::{Signer as _, SigningKey};
+use reqwest::Client;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const PUMPPORTAL_TRADE_LOCAL: &str = "https://pumpportal.fun/api/trade-local";
+const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Fresh blockhashes a transaction is re-signed with before it is reported dropped
+const MAX_SUBMIT_ATTEMPTS: usize = 3;
+/// How often an unconfirmed transaction is resent
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+/// Longest wait between confirmation polls while the RPC is failing
+const MAX_POLL_BACKOFF: Duration = Duration::from_secs(8);
+/// Headroom added to simulated compute units, since state can change before the transaction
+/// lands
+const COMPUTE_UNIT_MARGIN: f64 = 1.15;
+/// Most compute units a transaction may request
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+/// A cached blockhash older than this is refetched before sending; blockhashes stay valid
+/// for roughly a minute
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(20);
+const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// System program `Transfer` instruction index
+const SYSTEM_TRANSFER: u32 = 2;
+/// Token program `Burn` and `CloseAccount` instruction tags
+const TOKEN_BURN: u8 = 8;
+const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+/// Token accounts closed per transaction
+const CLOSE_BATCH_SIZE: usize = 20;
+
+/// Ed25519 keypair loaded from a Solana CLI keypair file (JSON array of 64 bytes)
+pub struct Keypair {
+    signing_key: SigningKey,
+}
+
+impl Keypair {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading keypair file {}", path))?;
+        let bytes: Vec<u8> =
+            serde_json::from_str(&raw).with_context(|| format!("parsing keypair file {}", path))?;
+        let bytes: [u8; 64] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("keypair file {} must contain 64 bytes", path))?;
+        let signing_key = SigningKey::from_keypair_bytes(&bytes)
+            .map_err(|e| anyhow!("invalid keypair in {}: {}", path, e))?;
+        Ok(Self { signing_key })
+    }
+
+    pub fn pubkey_bytes(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.signing_key.sign(message).to_bytes()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcContextValue<T> {
+    value: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestBlockhash {
+    blockhash: String,
+    last_valid_block_height: u64,
+}
+
+#[derive(Clone)]
+struct Blockhash {
+    hash: [u8; 32],
+    last_valid_block_height: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct LandedTransaction {
+    slot: u64,
+    meta: Option<LandedMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LandedMeta {
+    fee: u64,
+    #[serde(default)]
+    pre_balances: Vec<u64>,
+    #[serde(default)]
+    post_balances: Vec<u64>,
+    #[serde(default)]
+    pre_token_balances: Vec<TokenBalance>,
+    #[serde(default)]
+    post_token_balances: Vec<TokenBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenBalance {
+    account_index: usize,
+    mint: String,
+    owner: Option<String>,
+    ui_token_amount: UiTokenAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct UiTokenAmount {
+    amount: String,
+}
+
+impl LandedMeta {
+    /// Tokens of `mint` and SOL a trade moved for `owner`, the fee payer. The SOL excludes
+    /// the network fee and rent moving in or out of `owner`'s token accounts, leaving what
+    /// the trade itself cost or returned. `None` when no tokens moved.
+    fn fill(&self, mint: &str, owner: &str) -> Option<Fill> {
+        let owned = |balances: &[TokenBalance], index: usize| -> i128 {
+            balances
+                .iter()
+                .find(|b| b.account_index == index)
+                .and_then(|b| b.ui_token_amount.amount.parse::<i128>().ok())
+                .unwrap_or(0)
+        };
+        let lamports = |balances: &[u64], index: usize| -> i128 {
+            balances.get(index).copied().unwrap_or(0) as i128
+        };
+        let mut accounts: Vec<usize> = self
+            .pre_token_balances
+            .iter()
+            .chain(&self.post_token_balances)
+            .filter(|b| b.mint == mint && b.owner.as_deref() == Some(owner))
+            .map(|b| b.account_index)
+            .collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+
+        let mut tokens = 0;
+        let mut rent = 0;
+        for index in accounts {
+            tokens +=
+                owned(&self.post_token_balances, index) - owned(&self.pre_token_balances, index);
+            rent += lamports(&self.post_balances, index) - lamports(&self.pre_balances, index);
+        }
+        if tokens == 0 {
+            return None;
+        }
+        let wallet = lamports(&self.post_balances, 0) - lamports(&self.pre_balances, 0);
+        let sol = wallet + self.fee as i128 + rent;
+        Some(Fill {
+            token_raw: tokens.unsigned_abs() as u64,
+            sol: sol.unsigned_abs() as f64 / LAMPORTS_PER_SOL,
+        })
+    }
+}
+
+/// A confirmed trade
+#[derive(Debug, Clone)]
+pub struct Executed {
+    pub signature: String,
+    /// What the trade moved; `None` for devnet memos or when its transaction couldn't be read
+    pub fill: Option<Fill>,
+}
+
+/// Amounts a confirmed trade moved in the wallet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fill {
+    /// Raw token units bought or sold
+    pub token_raw: u64,
+    /// SOL spent by a buy or received by a sell, before network fees
+    pub sol: f64,
+}
+
+impl Fill {
+    /// Whole tokens bought or sold
+    pub fn tokens(&self) -> f64 {
+        self.token_raw as f64 / 10f64.powi(TOKEN_DECIMALS)
+    }
+}
+
+enum Landing {
+    Landed,
+    Failed(String),
+    /// The blockhash expired without the transaction landing
+    Expired,
+    /// The RPC kept failing, so whether the transaction landed is unknown
+    Unknown(String),
+}
+
+/// A transaction whose outcome couldn't be confirmed: it may still land, so its order should
+/// stay submitted until a retry of the order resolves it
+#[derive(Debug)]
+pub struct Unconfirmed {
+    pub signature: String,
+    pub error: String,
+}
+
+impl std::fmt::Display for Unconfirmed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "transaction {} unconfirmed: {}",
+            self.signature, self.error
+        )
+    }
+}
+
+impl std::error::Error for Unconfirmed {}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureStatus {
+    err: Option<serde_json::Value>,
+    confirmation_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationResult {
+    err: Option<serde_json::Value>,
+    units_consumed: Option<u64>,
+}
+
+/// `getMultipleAccounts` entry with `jsonParsed` data
+#[derive(Debug, Deserialize)]
+struct ParsedAccount {
+    lamports: u64,
+    data: serde_json::Value,
+}
+
+impl ParsedAccount {
+    /// Raw balance of a token account
+    fn token_amount(&self) -> Option<u64> {
+        self.data["parsed"]["info"]["tokenAmount"]["amount"]
+            .as_str()?
+            .parse()
+            .ok()
+    }
+
+    /// Balance of a token account in whole tokens
+    fn token_ui_amount(&self) -> Option<f64> {
+        self.data["parsed"]["info"]["tokenAmount"]["uiAmountString"]
+            .as_str()?
+            .parse()
+            .ok()
+    }
+
+    fn token_mint(&self) -> Option<&str> {
+        self.data["parsed"]["info"]["mint"].as_str()
+    }
+}
+
+/// `getTokenAccountsByOwner` entry with `jsonParsed` data
+#[derive(Debug, Deserialize)]
+struct OwnedTokenAccount {
+    pubkey: String,
+    account: ParsedAccount,
+}
+
+/// A token account of the trading wallet
+#[derive(Debug, Clone)]
+pub struct TokenHolding {
+    pub account: String,
+    pub mint: String,
+    /// Raw balance
+    pub amount: u64,
+    /// Balance in whole tokens
+    pub ui_amount: f64,
+    pub rent_lamports: u64,
+}
+
+/// `getAccountInfo` result with base64 data, encoded as `[data, "base64"]`
+#[derive(Debug, Deserialize)]
+struct AccountInfo {
+    data: (String, String),
+}
+
+/// Builds, signs, sends, and confirms real transactions
+///
+/// On mainnet, buy/sell transactions are built by PumpPortal's local-transaction API and
+/// signed here, except launch buys (`snipe`), which are patched into a pre-built template.
+/// Pump.fun launches only exist on mainnet, so on devnet the executor sends a memo
+/// transaction describing the trade instead: it exercises the same signing, sending, and
+/// confirmation path without touching real funds. Transactions built here are v0 messages
+/// that reference the configured address lookup tables.
+pub struct LiveExecutor {
+    client: Client,
+    cluster: Cluster,
+    rpc_url: String,
+    signer: Box<dyn Signer>,
+    slippage_pct: f64,
+    priority_fee_sol: f64,
+    confirm_timeout: Duration,
+    pool: Option<PgPool>,
+    buy_template: BuyTemplate,
+    /// Address lookup tables referenced by the transactions built here
+    lookup_tables: Vec<LookupTable>,
+    /// Private relays; without them every transaction goes through `rpc_url`
+    relays: Option<RelayClient>,
+    /// Kept fresh by `prewarm` so launch buys skip the blockhash round trip
+    blockhash_cache: Arc<Mutex<Option<(Blockhash, Instant)>>>,
+}
+
+impl LiveExecutor {
+    pub fn new(cluster: Cluster, rpc_url: String, signer: Box<dyn Signer>) -> Self {
+        let priority_fee_sol = 0.00001;
+        let buy_template = BuyTemplate::new(
+            signer.pubkey_bytes(),
+            (priority_fee_sol * LAMPORTS_PER_SOL) as u64,
+            &[],
+        )
+        .expect("buy template fits in a transaction");
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+
+        Self {
+            client,
+            cluster,
+            rpc_url,
+            signer,
+            slippage_pct: 10.0,
+            priority_fee_sol,
+            confirm_timeout: Duration::from_secs(60),
+            pool: None,
+            buy_template,
+            lookup_tables: Vec::new(),
+            relays: None,
+            blockhash_cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn pubkey(&self) -> String {
+        self.signer.pubkey()
+    }
+
+    /// Keep a recent blockhash cached in the background for as long as the executor lives
+    pub fn prewarm(&self) {
+        let client = self.client.clone();
+        let rpc_url = self.rpc_url.clone();
+        let cache = Arc::downgrade(&self.blockhash_cache);
+        tokio::spawn(async move {
+            while let Some(cache) = cache.upgrade() {
+                match fetch_latest_blockhash(&client, &rpc_url).await {
+                    Ok(blockhash) => {
+                        *cache.lock().unwrap() = Some((blockhash, Instant::now()));
+                    }
+                    Err(e) => println!("[executor] blockhash refresh failed: {}", e),
+                }
+                drop(cache);
+                tokio::time::sleep(BLOCKHASH_REFRESH_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Launch-path buy of `token_amount` whole tokens of a pump.fun `mint` for up to
+    /// `sol_amount` SOL plus slippage, built from the pre-warmed template rather than
+    /// fetched from PumpPortal. `detected_at` is when the listing was first seen; `jito`
+    /// sends it through Jito whatever the buy route.
+    pub async fn snipe(
+        &self,
+        mint: &str,
+        sol_amount: f64,
+        token_amount: f64,
+        detected_at: Instant,
+        order_id: &str,
+        jito: bool,
+    ) -> Result<Executed> {
+        let tx = match self.cluster {
+            Cluster::Mainnet => {
+                let raw_amount = (token_amount * 10f64.powi(TOKEN_DECIMALS)) as u64;
+                let max_sol_cost =
+                    sol_amount * (1.0 + self.slippage_pct / 100.0) * LAMPORTS_PER_SOL;
+                self.buy_template
+                    .build(mint, raw_amount, max_sol_cost as u64)?
+            }
+            Cluster::Devnet => {
+                self.memo_tx(&format!("sol-memebot buy {} {} SOL", mint, sol_amount))?
+            }
+        };
+        let signature = self
+            .submit(
+                "buy",
+                tx,
+                self.buy_relay(jito),
+                Some(detected_at),
+                Some(order_id),
+            )
+            .await?;
+        self.track_token_account(mint, true).await;
+        Ok(self.executed(signature, mint).await)
+    }
+
+    /// Buy `sol_amount` SOL worth of `mint`, through Jito with `jito`; returns the
+    /// confirmed trade
+    pub async fn buy(
+        &self,
+        mint: &str,
+        sol_amount: f64,
+        detected_at: Option<Instant>,
+        order_id: &str,
+        jito: bool,
+    ) -> Result<Executed> {
+        let tx = match self.cluster {
+            Cluster::Mainnet => {
+                self.pumpportal_trade_tx("buy", mint, &sol_amount.to_string(), true)
+                    .await?
+            }
+            Cluster::Devnet => {
+                self.memo_tx(&format!("sol-memebot buy {} {} SOL", mint, sol_amount))?
+            }
+        };
+        let signature = self
+            .submit("buy", tx, self.buy_relay(jito), detected_at, Some(order_id))
+            .await?;
+        self.track_token_account(mint, true).await;
+        Ok(self.executed(signature, mint).await)
+    }
+
+    /// Sell `token_raw` raw units of `mint`, the amount its buy received; returns the
+    /// confirmed trade. Other positions may hold the same mint in this wallet, so the whole
+    /// balance is only sold when the buy's fill is unknown.
+    pub async fn sell(
+        &self,
+        mint: &str,
+        token_raw: Option<u64>,
+        order_id: &str,
+    ) -> Result<Executed> {
+        let tx = match self.cluster {
+            Cluster::Mainnet => {
+                let amount = match token_raw {
+                    Some(raw) => (raw as f64 / 10f64.powi(TOKEN_DECIMALS)).to_string(),
+                    None => {
+                        println!(
+                            "[executor] bought amount of {} unknown, selling the whole balance",
+                            mint
+                        );
+                        "100%".to_string()
+                    }
+                };
+                self.pumpportal_trade_tx("sell", mint, &amount, false)
+                    .await?
+            }
+            Cluster::Devnet => self.memo_tx(&format!("sol-memebot sell {}", mint))?,
+        };
+        let signature = self
+            .submit("sell", tx, self.relay_for("sell"), None, Some(order_id))
+            .await?;
+        self.track_token_account(mint, false).await;
+        Ok(self.executed(signature, mint).await)
+    }
+
+    /// Read what the confirmed `signature` moved in `mint`. A transaction can take a moment
+    /// to become readable after confirmation, so the read is retried briefly.
+    async fn executed(&self, signature: String, mint: &str) -> Executed {
+        if self.cluster != Cluster::Mainnet {
+            return Executed {
+                signature,
+                fill: None,
+            };
+        }
+        let mut fill = None;
+        for attempt in 1..=3 {
+            match self.landed_transaction(&signature).await {
+                Ok(tx) => {
+                    fill = tx.meta.and_then(|m| m.fill(mint, &self.pubkey()));
+                    break;
+                }
+                Err(e) if attempt == 3 => {
+                    println!("[executor] fill of {} unavailable: {}", signature, e)
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(500)).await,
+            }
+        }
+        Executed { signature, fill }
+    }
+
+    /// Record the wallet's token account for `mint` as opened by a buy or emptied by a sell
+    async fn track_token_account(&self, mint: &str, opened: bool) {
+        // Devnet trades are memos and create no token accounts
+        let (Some(pool), Cluster::Mainnet) = (&self.pool, self.cluster) else {
+            return;
+        };
+        let result = async {
+            let address =
+                associated_token_address(&self.signer.pubkey_bytes(), &decode_address(mint)?)?;
+            let address = bs58::encode(address).into_string();
+            if opened {
+                token_accounts::record_opened(pool, &address, &self.pubkey(), mint).await
+            } else {
+                token_accounts::record_emptied(pool, &address).await
+            }
+        }
+        .await;
+        if let Err(e) = result {
+            println!(
+                "[executor] failed to track token account for {}: {}",
+                mint, e
+            );
+        }
+    }
+
+    /// Close this wallet's emptied token accounts in batches, reclaiming their rent; returns
+    /// the lamports reclaimed
+    pub async fn close_empty_token_accounts(&self) -> Result<u64> {
+        let Some(pool) = &self.pool else {
+            return Ok(0);
+        };
+        let addresses = token_accounts::emptied(pool, &self.pubkey()).await?;
+        if addresses.is_empty() {
+            return Ok(0);
+        }
+        let accounts: RpcContextValue<Vec<Option<ParsedAccount>>> = self
+            .rpc(
+                "getMultipleAccounts",
+                serde_json::json!([addresses, { "encoding": "jsonParsed" }]),
+            )
+            .await?;
+
+        let mut closable = Vec::new();
+        for (address, account) in addresses.iter().zip(accounts.value) {
+            match account {
+                None => token_accounts::record_closed(pool, address, None, None).await?,
+                Some(account) if account.token_amount() == Some(0) => {
+                    closable.push((address.clone(), account.lamports))
+                }
+                // Dust left by a partial sell; closing would fail
+                Some(_) => println!(
+                    "[executor] token account {} still holds tokens, leaving it open",
+                    address
+                ),
+            }
+        }
+
+        let mut reclaimed = 0;
+        for batch in closable.chunks(CLOSE_BATCH_SIZE) {
+            let addresses: Vec<&str> = batch.iter().map(|(a, _)| a.as_str()).collect();
+            let tx = self.close_accounts_tx(&addresses)?;
+            match self
+                .submit("close", tx, self.relay_for("close"), None, None)
+                .await
+            {
+                Ok(signature) => {
+                    for (address, lamports) in batch {
+                        token_accounts::record_closed(
+                            pool,
+                            address,
+                            Some(&signature),
+                            Some(*lamports),
+                        )
+                        .await?;
+                        reclaimed += lamports;
+                    }
+                }
+                Err(e) => println!(
+                    "[executor] closing {} token accounts failed: {}",
+                    batch.len(),
+                    e
+                ),
+            }
+        }
+        Ok(reclaimed)
+    }
+
+    /// Token accounts of this wallet that hold a balance
+    pub async fn token_holdings(&self) -> Result<Vec<TokenHolding>> {
+        let accounts: RpcContextValue<Vec<OwnedTokenAccount>> = self
+            .rpc(
+                "getTokenAccountsByOwner",
+                serde_json::json!([
+                    self.pubkey(),
+                    { "programId": TOKEN_PROGRAM_ID },
+                    { "encoding": "jsonParsed" },
+                ]),
+            )
+            .await?;
+        Ok(accounts
+            .value
+            .into_iter()
+            .filter_map(|owned| {
+                Some(TokenHolding {
+                    mint: owned.account.token_mint()?.to_string(),
+                    amount: owned.account.token_amount().filter(|amount| *amount > 0)?,
+                    ui_amount: owned.account.token_ui_amount()?,
+                    rent_lamports: owned.account.lamports,
+                    account: owned.pubkey,
+                })
+            })
+            .collect())
+    }
+
+    /// Burn a token account's whole balance and close it, reclaiming its rent
+    pub async fn burn_and_close(&self, holding: &TokenHolding) -> Result<String> {
+        let owner = self.signer.pubkey_bytes();
+        let token_program = decode_address(TOKEN_PROGRAM_ID)?;
+        let account = decode_address(&holding.account)?;
+        let mut burn = vec![TOKEN_BURN];
+        burn.extend_from_slice(&holding.amount.to_le_bytes());
+        let instructions = [
+            Instruction {
+                program_id: token_program,
+                accounts: vec![
+                    AccountMeta::writable(account),
+                    AccountMeta::writable(decode_address(&holding.mint)?),
+                    AccountMeta::signer(owner),
+                ],
+                data: burn,
+            },
+            Instruction {
+                program_id: token_program,
+                accounts: vec![
+                    AccountMeta::writable(account),
+                    AccountMeta::writable(owner),
+                    AccountMeta::signer(owner),
+                ],
+                data: vec![TOKEN_CLOSE_ACCOUNT],
+            },
+        ];
+        let tx = message::compile(owner, &instructions, &self.lookup_tables)?.bytes;
+        let signature = self
+            .submit("close", tx, self.relay_for("close"), None, None)
+            .await?;
+        if let Some(pool) = &self.pool {
+            token_accounts::record_closed(
+                pool,
+                &holding.account,
+                Some(&signature),
+                Some(holding.rent_lamports),
+            )
+            .await?;
+        }
+        Ok(signature)
+    }
+
+    /// Devnet faucet helper: request an airdrop to the trading wallet and wait for it to land
+    pub async fn request_airdrop(&self, sol: f64) -> Result<String> {
+        if self.cluster != Cluster::Devnet {
+            bail!("airdrops are only available on devnet");
+        }
+        let lamports = (sol * LAMPORTS_PER_SOL) as u64;
+        let signature: String = self
+            .rpc(
+                "requestAirdrop",
+                serde_json::json!([self.pubkey(), lamports]),
+            )
+            .await?;
+        self.confirm(&signature).await?;
+        Ok(signature)
+    }
+
+    /// Send `sol` SOL to `to`; returns the confirmed signature
+    pub async fn transfer(&self, to: &str, sol: f64) -> Result<String> {
+        let to = decode_address(to)?;
+        let lamports = (sol * LAMPORTS_PER_SOL) as u64;
+        let tx = self.transfer_tx(&to, lamports)?;
+        self.submit("transfer", tx, self.relay_for("transfer"), None, None)
+            .await
+    }
+
+    /// Wallet balance in SOL
+    pub async fn balance_sol(&self) -> Result<f64> {
+        let balance: RpcContextValue<u64> = self
+            .rpc("getBalance", serde_json::json!([self.pubkey()]))
+            .await?;
+        Ok(balance.value as f64 / LAMPORTS_PER_SOL)
+    }
+
+    /// Sign and send `tx` through `relay`, rebroadcasting until it lands. When its blockhash expires first,
+    /// the transaction is re-signed with a fresh blockhash, up to `MAX_SUBMIT_ATTEMPTS` times.
+    /// Every attempt and its outcome is recorded in the `transactions` table, along with the
+    /// time from `detected_at` to the first send. A transaction for an `order_id` that
+    /// already landed is not sent again; its signature is returned instead.
+    async fn submit(
+        &self,
+        kind: &str,
+        mut tx: Vec<u8>,
+        relay: Relay,
+        detected_at: Option<Instant>,
+        order_id: Option<&str>,
+    ) -> Result<String> {
+        if let Some(order_id) = order_id
+            && let Some(signature) = self.prior_execution(order_id).await?
+        {
+            println!(
+                "[executor] order {} already landed as {}; not resending",
+                order_id, signature
+            );
+            return Ok(signature);
+        }
+        self.fit_compute_limit(kind, &mut tx).await?;
+        for attempt in 1..=MAX_SUBMIT_ATTEMPTS {
+            let blockhash = match self.cached_blockhash() {
+                Some(blockhash) if attempt == 1 => blockhash,
+                _ => self.latest_blockhash().await?,
+            };
+            set_blockhash(&mut tx, &blockhash.hash)?;
+            sign_transaction(&mut tx, self.signer.as_ref()).await?;
+            let signature = self.send(relay, &tx).await?;
+            let latency_ms = detected_at
+                .filter(|_| attempt == 1)
+                .map(|at| at.elapsed().as_millis() as i64);
+            if let Some(ms) = latency_ms {
+                println!(
+                    "[executor] {} {} sent {} ms after detection",
+                    kind, signature, ms
+                );
+            }
+            self.record_sent(
+                &signature,
+                kind,
+                relay,
+                attempt,
+                latency_ms,
+                order_id,
+                blockhash.last_valid_block_height,
+            )
+            .await;
+
+            match self
+                .await_landing(relay, &signature, &tx, blockhash.last_valid_block_height)
+                .await?
+            {
+                Landing::Landed => {
+                    let (slot, fee) = self.landed_details(&signature).await.unwrap_or_default();
+                    self.record_outcome(&signature, "landed", slot, fee, None)
+                        .await;
+                    println!(
+                        "[executor] confirmed {} (slot {:?}, fee {:?} lamports)",
+                        self.cluster.explorer_tx_url(&signature),
+                        slot,
+                        fee
+                    );
+                    return Ok(signature);
+                }
+                Landing::Failed(err) => {
+                    self.record_outcome(&signature, "failed", None, None, Some(&err))
+                        .await;
+                    bail!("transaction {} failed: {}", signature, err);
+                }
+                Landing::Expired => {
+                    self.record_outcome(&signature, "expired", None, None, None)
+                        .await;
+                    println!(
+                        "[executor] {} {} expired before landing (attempt {}/{})",
+                        kind, signature, attempt, MAX_SUBMIT_ATTEMPTS
+                    );
+                }
+                Landing::Unknown(error) => {
+                    // Left as sent, so a retry of the order resolves it before resending
+                    return Err(Unconfirmed { signature, error }.into());
+                }
+            }
+        }
+        bail!(
+            "{} transaction dropped: blockhash expired {} times",
+            kind,
+            MAX_SUBMIT_ATTEMPTS
+        )
+    }
+
+    /// Resolve earlier transactions for `order_id`, e.g. from a retry after a network error
+    /// left the outcome unknown. Returns the signature of one that landed. Bails while one
+    /// could still land, so the order is never executed twice.
+    async fn prior_execution(&self, order_id: &str) -> Result<Option<String>> {
+        let Some(pool) = &self.pool else {
+            return Ok(None);
+        };
+        let earlier: Vec<(String, String, Option<i64>)> = sqlx::query_as(
+            "SELECT signature, status, last_valid_block_height FROM transactions \
+             WHERE order_id=$1 AND status IN ('sent', 'landed') ORDER BY sent_at",
+        )
+        .bind(order_id)
+        .fetch_all(pool)
+        .await?;
+        for (signature, status, last_valid) in earlier {
+            if status == "landed" {
+                return Ok(Some(signature));
+            }
+            match self.signature_status(&signature).await? {
+                Some(status) if status.err.is_some() => {
+                    let err = status.err.unwrap_or_default().to_string();
+                    self.record_outcome(&signature, "failed", None, None, Some(&err))
+                        .await;
+                }
+                Some(status)
+                    if matches!(
+                        status.confirmation_status.as_deref(),
+                        Some("confirmed") | Some("finalized")
+                    ) =>
+                {
+                    let (slot, fee) = self.landed_details(&signature).await.unwrap_or_default();
+                    self.record_outcome(&signature, "landed", slot, fee, None)
+                        .await;
+                    return Ok(Some(signature));
+                }
+                _ => {
+                    let height: u64 = self.rpc("getBlockHeight", serde_json::json!([])).await?;
+                    if last_valid.is_none_or(|last| height as i64 <= last) {
+                        bail!(
+                            "order {} is still in flight as {}; retry once it resolves",
+                            order_id,
+                            signature
+                        );
+                    }
+                    self.record_outcome(&signature, "expired", None, None, None)
+                        .await;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Lower the transaction's compute unit limit, if it sets one, to its simulated usage plus
+    /// a margin. Priority fees are paid per requested unit, so an oversized limit costs extra
+    /// on every trade. A failed simulation keeps the preset limit.
+    async fn fit_compute_limit(&self, kind: &str, tx: &mut [u8]) -> Result<()> {
+        let Some(offset) = compute_limit_offset(tx)? else {
+            return Ok(());
+        };
+        let preset = u32::from_le_bytes(tx[offset..offset + 4].try_into()?);
+        // Simulate with the maximum so a low preset doesn't cut the run short
+        let mut probe = tx.to_vec();
+        probe[offset..offset + 4].copy_from_slice(&MAX_COMPUTE_UNITS.to_le_bytes());
+        let units = match self.simulate_units(&probe).await {
+            Ok(units) => units,
+            Err(e) => {
+                println!(
+                    "[executor] {} simulation failed, keeping {} CU: {}",
+                    kind, preset, e
+                );
+                return Ok(());
+            }
+        };
+        let limit = ((units as f64 * COMPUTE_UNIT_MARGIN) as u32).min(MAX_COMPUTE_UNITS);
+        tx[offset..offset + 4].copy_from_slice(&limit.to_le_bytes());
+        println!(
+            "[executor] {} uses {} CU, limit {} (was {})",
+            kind, units, limit, preset
+        );
+        Ok(())
+    }
+
+    async fn simulate_units(&self, tx: &[u8]) -> Result<u64> {
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(tx);
+        let result: RpcContextValue<SimulationResult> = self
+            .rpc(
+                "simulateTransaction",
+                serde_json::json!([encoded, {
+                    "encoding": "base64",
+                    "sigVerify": false,
+                    "replaceRecentBlockhash": true,
+                    "commitment": "processed",
+                }]),
+            )
+            .await?;
+        if let Some(err) = result.value.err {
+            bail!("simulation error: {}", err);
+        }
+        result
+            .value
+            .units_consumed
+            .ok_or_else(|| anyhow!("simulation reported no compute units"))
+    }
+
+    /// Poll until `signature` lands, fails, or its blockhash expires, resending meanwhile.
+    /// RPC errors are retried with backoff; expiry is only decided from a block height read
+    /// before a status that still shows the transaction unlanded. If the RPC keeps failing
+    /// for `confirm_timeout`, the outcome is reported unknown.
+    async fn await_landing(
+        &self,
+        relay: Relay,
+        signature: &str,
+        tx: &[u8],
+        last_valid_block_height: u64,
+    ) -> Result<Landing> {
+        let mut last_send = Instant::now();
+        let mut last_ok = Instant::now();
+        let mut delay = Duration::from_millis(500);
+        loop {
+            tokio::time::sleep(delay).await;
+            match self.poll_landing(signature, last_valid_block_height).await {
+                Ok(Some(landing)) => return Ok(landing),
+                Ok(None) => {
+                    last_ok = Instant::now();
+                    delay = Duration::from_millis(500);
+                }
+                Err(e) => {
+                    if last_ok.elapsed() >= self.confirm_timeout {
+                        return Ok(Landing::Unknown(e.to_string()));
+                    }
+                    println!(
+                        "[executor] status of {} unavailable, retrying: {}",
+                        signature, e
+                    );
+                    delay = (delay * 2).min(MAX_POLL_BACKOFF);
+                }
+            }
+            // Validators drop transactions under load; resending the same bytes is idempotent
+            if last_send.elapsed() >= REBROADCAST_INTERVAL {
+                if let Err(e) = self.send(relay, tx).await {
+                    println!("[executor] rebroadcast of {} failed: {}", signature, e);
+                }
+                last_send = Instant::now();
+            }
+        }
+    }
+
+    /// One confirmation poll: the landing if it is decided, `None` while still pending
+    async fn poll_landing(
+        &self,
+        signature: &str,
+        last_valid_block_height: u64,
+    ) -> Result<Option<Landing>> {
+        // Read the height first: a status read after it that still shows nothing means the
+        // transaction can no longer land
+        let height: u64 = self.rpc("getBlockHeight", serde_json::json!([])).await?;
+        match self.signature_status(signature).await? {
+            Some(status) if status.err.is_some() => Ok(Some(Landing::Failed(
+                status.err.unwrap_or_default().to_string(),
+            ))),
+            Some(status)
+                if matches!(
+                    status.confirmation_status.as_deref(),
+                    Some("confirmed") | Some("finalized")
+                ) =>
+            {
+                Ok(Some(Landing::Landed))
+            }
+            _ if height > last_valid_block_height => Ok(Some(Landing::Expired)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn signature_status(&self, signature: &str) -> Result<Option<SignatureStatus>> {
+        let statuses: RpcContextValue<Vec<Option<SignatureStatus>>> = self
+            .rpc(
+                "getSignatureStatuses",
+                serde_json::json!([[signature], { "searchTransactionHistory": true }]),
+            )
+            .await?;
+        Ok(statuses.value.into_iter().next().flatten())
+    }
+
+    /// Slot and fee of a landed transaction
+    async fn landed_details(&self, signature: &str) -> Result<(Option<i64>, Option<i64>)> {
+        let tx = self.landed_transaction(signature).await?;
+        Ok((Some(tx.slot as i64), tx.meta.map(|m| m.fee as i64)))
+    }
+
+    async fn landed_transaction(&self, signature: &str) -> Result<LandedTransaction> {
+        self.rpc(
+            "getTransaction",
+            serde_json::json!([signature, {
+                "encoding": "json",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )
+        .await
+    }
+
+    /// Use `pool` to record submitted transactions
+    pub fn with_pool(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    // Recording is best-effort: a database hiccup must not abandon a transaction in flight
+
+    #[allow(clippy::too_many_arguments)]
+    async fn record_sent(
+        &self,
+        signature: &str,
+        kind: &str,
+        relay: Relay,
+        attempt: usize,
+        detection_latency_ms: Option<i64>,
+        order_id: Option<&str>,
+        last_valid_block_height: u64,
+    ) {
+        let Some(pool) = &self.pool else {
+            return;
+        };
+        let result = sqlx::query(
+            "INSERT INTO transactions (signature, kind, wallet, cluster, attempt, status, detection_latency_ms, relay, \
+             order_id, last_valid_block_height) \
+             VALUES ($1,$2,$3,$4,$5,'sent',$6,$7,$8,$9) ON CONFLICT (signature) DO NOTHING",
+        )
+        .bind(signature)
+        .bind(kind)
+        .bind(self.pubkey())
+        .bind(self.cluster.as_str())
+        .bind(attempt as i32)
+        .bind(detection_latency_ms)
+        .bind(relay.as_str())
+        .bind(order_id)
+        .bind(last_valid_block_height as i64)
+        .execute(pool)
+        .await;
+        if let Err(e) = result {
+            println!("[executor] failed to record {}: {}", signature, e);
+        }
+    }
+
+    async fn record_outcome(
+        &self,
+        signature: &str,
+        status: &str,
+        slot: Option<i64>,
+        fee_lamports: Option<i64>,
+        error: Option<&str>,
+    ) {
+        let Some(pool) = &self.pool else {
+            return;
+        };
+        let result = sqlx::query(
+            "UPDATE transactions SET status=$2, slot=$3, fee_lamports=$4, error=$5, \
+             finalized_at=NOW() WHERE signature=$1",
+        )
+        .bind(signature)
+        .bind(status)
+        .bind(slot)
+        .bind(fee_lamports)
+        .bind(error)
+        .execute(pool)
+        .await;
+        if let Err(e) = result {
+            println!("[executor] failed to record {}: {}", signature, e);
+        }
+    }
+
+    /// Fetch an unsigned pump.fun trade transaction from PumpPortal
+    async fn pumpportal_trade_tx(
+        &self,
+        action: &str,
+        mint: &str,
+        amount: &str,
+        denominated_in_sol: bool,
+    ) -> Result<Vec<u8>> {
+        let body = serde_json::json!({
+            "publicKey": self.pubkey(),
+            "action": action,
+            "mint": mint,
+            "amount": amount,
+            "denominatedInSol": denominated_in_sol.to_string(),
+            "slippage": self.slippage_pct,
+            "priorityFee": self.priority_fee_sol,
+            "pool": "auto",
+        });
+        let resp = self
+            .client
+            .post(PUMPPORTAL_TRADE_LOCAL)
+            .json(&body)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            bail!(
+                "PumpPortal trade-local {} failed: {} {}",
+                action,
+                status,
+                resp.text().await.unwrap_or_default()
+            );
+        }
+        Ok(resp.bytes().await?.to_vec())
+    }
+
+    /// Build an unsigned transaction carrying a single memo instruction; the blockhash is
+    /// filled in by `submit`
+    fn memo_tx(&self, memo: &str) -> Result<Vec<u8>> {
+        let payer = self.signer.pubkey_bytes();
+        let ix = Instruction {
+            program_id: decode_address(MEMO_PROGRAM_ID)?,
+            accounts: vec![AccountMeta::signer(payer)],
+            data: memo.as_bytes().to_vec(),
+        };
+        Ok(message::compile(payer, &[ix], &self.lookup_tables)?.bytes)
+    }
+
+    /// Build an unsigned transaction with a single system program transfer; the blockhash is
+    /// filled in by `submit`
+    fn transfer_tx(&self, to: &[u8; 32], lamports: u64) -> Result<Vec<u8>> {
+        let payer = self.signer.pubkey_bytes();
+        let mut data = SYSTEM_TRANSFER.to_le_bytes().to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+        let ix = Instruction {
+            // The system program id is all zeros
+            program_id: [0u8; 32],
+            accounts: vec![AccountMeta::signer(payer), AccountMeta::writable(*to)],
+            data,
+        };
+        Ok(message::compile(payer, &[ix], &self.lookup_tables)?.bytes)
+    }
+
+    /// Build an unsigned transaction closing empty token accounts owned by this wallet, with
+    /// their rent returned to it
+    fn close_accounts_tx(&self, addresses: &[&str]) -> Result<Vec<u8>> {
+        let owner = self.signer.pubkey_bytes();
+        let token_program = decode_address(TOKEN_PROGRAM_ID)?;
+        let instructions = addresses
+            .iter()
+            .map(|address| {
+                Ok(Instruction {
+                    program_id: token_program,
+                    accounts: vec![
+                        AccountMeta::writable(decode_address(address)?),
+                        AccountMeta::writable(owner),
+                        AccountMeta::signer(owner),
+                    ],
+                    data: vec![TOKEN_CLOSE_ACCOUNT],
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(message::compile(owner, &instructions, &self.lookup_tables)?.bytes)
+    }
+
+    /// Load address lookup tables that transactions built here may reference
+    pub async fn load_lookup_tables(&mut self, addresses: &[String]) -> Result<()> {
+        use base64::Engine;
+        let mut tables = Vec::new();
+        for address in addresses {
+            let account: RpcContextValue<Option<AccountInfo>> = self
+                .rpc(
+                    "getAccountInfo",
+                    serde_json::json!([address, { "encoding": "base64" }]),
+                )
+                .await?;
+            let Some(account) = account.value else {
+                bail!("lookup table {} does not exist", address);
+            };
+            let data = base64::engine::general_purpose::STANDARD.decode(&account.data.0)?;
+            let table = LookupTable::from_account_data(decode_address(address)?, &data)
+                .with_context(|| format!("reading lookup table {}", address))?;
+            println!(
+                "[executor] loaded lookup table {} ({} addresses)",
+                address,
+                table.addresses.len()
+            );
+            tables.push(table);
+        }
+        self.buy_template = BuyTemplate::new(
+            self.signer.pubkey_bytes(),
+            (self.priority_fee_sol * LAMPORTS_PER_SOL) as u64,
+            &tables,
+        )?;
+        self.lookup_tables = tables;
+        Ok(())
+    }
+
+    async fn latest_blockhash(&self) -> Result<Blockhash> {
+        let blockhash = fetch_latest_blockhash(&self.client, &self.rpc_url).await?;
+        *self.blockhash_cache.lock().unwrap() = Some((blockhash.clone(), Instant::now()));
+        Ok(blockhash)
+    }
+
+    fn cached_blockhash(&self) -> Option<Blockhash> {
+        self.blockhash_cache
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(_, fetched)| fetched.elapsed() < BLOCKHASH_MAX_AGE)
+            .map(|(blockhash, _)| blockhash.clone())
+    }
+
+    /// Send a signed transaction through `relay`; returns its signature
+    async fn send(&self, relay: Relay, tx: &[u8]) -> Result<String> {
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(tx);
+        if relay == Relay::Rpc {
+            return self
+                .rpc(
+                    "sendTransaction",
+                    serde_json::json!([encoded, { "encoding": "base64", "preflightCommitment": "confirmed" }]),
+                )
+                .await;
+        }
+        self.relays
+            .as_ref()
+            .ok_or_else(|| anyhow!("no relay client to send through {}", relay.as_str()))?
+            .send(relay, &encoded)
+            .await?;
+        // Relays don't all echo the signature; it is the first one in the transaction
+        let (_, offset) = decode_compact_u16(tx)?;
+        Ok(bs58::encode(&tx[offset..offset + 64]).into_string())
+    }
+
+    fn relay_for(&self, kind: &str) -> Relay {
+        self.relays
+            .as_ref()
+            .map_or(Relay::Rpc, |relays| relays.relay_for(kind))
+    }
+
+    /// Relay for a buy: its route, or Jito with `jito` unless the route is already private.
+    /// Jito only lands mainnet transactions, so devnet buys keep their route.
+    fn buy_relay(&self, jito: bool) -> Relay {
+        match self.relay_for("buy") {
+            Relay::Rpc if jito && self.cluster == Cluster::Mainnet => Relay::Jito,
+            routed => routed,
+        }
+    }
+
+    /// Send transactions through `relays` according to their routes
+    pub fn with_relays(mut self, relays: RelayClient) -> Self {
+        self.relays = Some(relays);
+        self
+    }
+
+    /// Poll signature status until the transaction is confirmed, fails, or times out
+    async fn confirm(&self, signature: &str) -> Result<()> {
+        let start = Instant::now();
+        while start.elapsed() < self.confirm_timeout {
+            let statuses: RpcContextValue<Vec<Option<SignatureStatus>>> = self
+                .rpc(
+                    "getSignatureStatuses",
+                    serde_json::json!([[signature], { "searchTransactionHistory": true }]),
+                )
+                .await?;
+            if let Some(Some(status)) = statuses.value.into_iter().next() {
+                if let Some(err) = status.err {
+                    bail!("transaction {} failed: {}", signature, err);
+                }
+                if matches!(
+                    status.confirmation_status.as_deref(),
+                    Some("confirmed") | Some("finalized")
+                ) {
+                    return Ok(());
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        bail!(
+            "transaction {} not confirmed within {:?}",
+            signature,
+            self.confirm_timeout
+        )
+    }
+
+    async fn rpc<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        rpc_call(&self.client, &self.rpc_url, method, params).await
+    }
+}
+
+async fn rpc_call<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<T> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let resp: RpcResponse<T> = client
+        .post(rpc_url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+    if let Some(err) = resp.error {
+        bail!("{} failed: {}", method, err);
+    }
+    resp.result
+        .ok_or_else(|| anyhow!("{} returned no result", method))
+}
+
+async fn fetch_latest_blockhash(client: &Client, rpc_url: &str) -> Result<Blockhash> {
+    let resp: RpcContextValue<LatestBlockhash> = rpc_call(
+        client,
+        rpc_url,
+        "getLatestBlockhash",
+        serde_json::json!([{ "commitment": "confirmed" }]),
+    )
+    .await?;
+    Ok(Blockhash {
+        hash: bs58::decode(&resp.value.blockhash)
+            .into_vec()?
+            .try_into()
+            .map_err(|_| anyhow!("blockhash is not 32 bytes"))?,
+        last_valid_block_height: resp.value.last_valid_block_height,
+    })
+}
+
+/// Sign a serialized (legacy or v0) transaction in place as its first signer
+async fn sign_transaction(tx: &mut [u8], signer: &dyn Signer) -> Result<()> {
+    let (num_signatures, offset) = decode_compact_u16(tx)?;
+    if num_signatures == 0 {
+        bail!("transaction has no signature slots");
+    }
+    let message_start = offset + num_signatures * 64;
+    if tx.len() <= message_start {
+        bail!("transaction is truncated");
+    }
+    let signature = signer.sign(&tx[message_start..]).await?;
+    tx[offset..offset + 64].copy_from_slice(&signature);
+    Ok(())
+}
+
+pub(crate) fn decode_address(address: &str) -> Result<[u8; 32]> {
+    bs58::decode(address)
+        .into_vec()?
+        .try_into()
+        .map_err(|_| anyhow!("{} is not a 32-byte address", address))
+}
+
+/// Offsets of the static account keys and the recent blockhash in a serialized (legacy or
+/// v0) transaction, along with the number of static keys
+fn message_layout(tx: &[u8]) -> Result<(usize, usize, usize)> {
+    let (num_signatures, offset) = decode_compact_u16(tx)?;
+    let mut pos = offset + num_signatures * 64;
+    // Versioned messages start with a byte that has the high bit set
+    if tx.get(pos).is_some_and(|b| b & 0x80 != 0) {
+        pos += 1;
+    }
+    pos += 3; // header
+    let (num_keys, len) = decode_compact_u16(tx.get(pos..).unwrap_or_default())?;
+    let keys_offset = pos + len;
+    let blockhash_offset = keys_offset + num_keys * 32;
+    if tx.len() < blockhash_offset + 32 {
+        bail!("transaction is truncated");
+    }
+    Ok((keys_offset, num_keys, blockhash_offset))
+}
+
+/// Replace the recent blockhash of a serialized transaction
+fn set_blockhash(tx: &mut [u8], blockhash: &[u8; 32]) -> Result<()> {
+    let (_, _, offset) = message_layout(tx)?;
+    tx[offset..offset + 32].copy_from_slice(blockhash);
+    Ok(())
+}
+
+/// Offset of the `u32` argument of the transaction's `SetComputeUnitLimit` instruction, if
+/// it has one
+fn compute_limit_offset(tx: &[u8]) -> Result<Option<usize>> {
+    let (keys_offset, num_keys, blockhash_offset) = message_layout(tx)?;
+    let budget_program = decode_address(COMPUTE_BUDGET_PROGRAM_ID)?;
+    let Some(program) = (0..num_keys)
+        .position(|i| tx[keys_offset + i * 32..keys_offset + (i + 1) * 32] == budget_program)
+    else {
+        return Ok(None);
+    };
+
+    let mut pos = blockhash_offset + 32;
+    let read_len = |pos: &mut usize| -> Result<usize> {
+        let (value, len) = decode_compact_u16(tx.get(*pos..).unwrap_or_default())?;
+        *pos += len;
+        Ok(value)
+    };
+    let num_instructions = read_len(&mut pos)?;
+    for _ in 0..num_instructions {
+        let Some(&program_index) = tx.get(pos) else {
+            bail!("transaction is truncated");
+        };
+        pos += 1;
+        pos += read_len(&mut pos)?; // account indexes
+        let data_len = read_len(&mut pos)?;
+        if program_index as usize == program
+            && data_len == 5
+            && tx.get(pos) == Some(&SET_COMPUTE_UNIT_LIMIT)
+        {
+            return Ok(Some(pos + 1));
+        }
+        pos += data_len;
+    }
+    Ok(None)
+}
+
+/// Solana's "shortvec" length prefix
+pub(crate) fn encode_compact_u16(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        byte |= 0x80;
+        out.push(byte);
+    }
+}
+
+fn decode_compact_u16(data: &[u8]) -> Result<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, byte) in data.iter().take(3).enumerate() {
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    bail!("invalid compact-u16 length prefix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_blockhash_patches_the_message() {
+        let keypair = Keypair {
+            signing_key: SigningKey::from_bytes(&[7u8; 32]),
+        };
+        let executor = LiveExecutor::new(Cluster::Devnet, String::new(), Box::new(keypair));
+        let mut tx = executor.transfer_tx(&[9u8; 32], 1_000).unwrap();
+        set_blockhash(&mut tx, &[0xab; 32]).unwrap();
+        // 1 signature, version, header, 3 keys, then the blockhash
+        let start = 1 + 64 + 1 + 3 + 1 + 3 * 32;
+        assert_eq!(tx[start..start + 32], [0xab; 32]);
+        assert!(set_blockhash(&mut tx[..start], &[0; 32]).is_err());
+        assert_eq!(compute_limit_offset(&tx).unwrap(), None);
+    }
+
+    #[test]
+    fn reads_a_fill_from_balance_changes() {
+        // A buy that opened the wallet's token account (index 1) for 0.00203928 SOL of rent
+        let meta: LandedMeta = serde_json::from_value(serde_json::json!({
+            "fee": 5000,
+            "preBalances": [1_000_000_000u64, 0, 50_000_000],
+            "postBalances": [947_955_720u64, 2_039_280, 100_000_000],
+            "preTokenBalances": [
+                { "accountIndex": 2, "mint": "Mint", "owner": "Curve",
+                  "uiTokenAmount": { "amount": "900000000" } },
+            ],
+            "postTokenBalances": [
+                { "accountIndex": 1, "mint": "Mint", "owner": "Wallet",
+                  "uiTokenAmount": { "amount": "1500000" } },
+                { "accountIndex": 2, "mint": "Mint", "owner": "Curve",
+                  "uiTokenAmount": { "amount": "898500000" } },
+            ],
+        }))
+        .unwrap();
+        let fill = meta.fill("Mint", "Wallet").unwrap();
+        assert_eq!(fill.token_raw, 1_500_000);
+        assert!((fill.sol - 0.05).abs() < 1e-9);
+        assert!((fill.tokens() - 1.5).abs() < 1e-9);
+        assert_eq!(meta.fill("Other", "Wallet"), None);
+    }
+
+    #[test]
+    fn finds_the_compute_unit_limit() {
+        let template = BuyTemplate::new([7u8; 32], 10_000, &[]).unwrap();
+        let tx = template
+            .build("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 1, 1)
+            .unwrap();
+        let offset = compute_limit_offset(&tx).unwrap().unwrap();
+        assert_eq!(
+            tx[offset..offset + 4],
+            crate::buy_template::COMPUTE_UNIT_LIMIT.to_le_bytes()
+        );
+    }
+}
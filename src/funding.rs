@@ -0,0 +1,262 @@
+//! Clusters of a token's top holders that were funded from the same place
+//!
+//! Each top holder's funding is traced back up to `FUNDING_HOPS` wallets: the wallet that sent
+//! it SOL in its first transaction, that wallet's funder, and so on. Holders whose chains share
+//! a wallet are merged into one cluster, since ten wallets holding 3% each, funded by one
+//! wallet, are one whale holding 30%. `effective_top_holder_pct` is the largest cluster's
+//! share. Wallets in `FUNDING_IGNORE`, such as exchange hot wallets that fund unrelated users,
+//! end a chain without linking anything. The pump.fun bonding curve is not a holder.
+
+use crate::buy_template::bonding_curve_address;
+use crate::scanner::TokenDataProvider;
+use crate::strategy::TokenEvent;
+use anyhow::Result;
+use futures::{StreamExt, stream};
+use std::collections::{HashMap, HashSet};
+
+/// Funder lookups in flight at once
+const CONCURRENCY: usize = 8;
+
+/// Top holders that share a funding source, or a lone holder
+#[derive(Debug, Clone, PartialEq)]
+pub struct HolderCluster {
+    /// Largest holder first
+    pub wallets: Vec<String>,
+    pub supply_pct: f64,
+    /// The wallet on most of the members' funding chains, None for a lone holder
+    pub funder: Option<String>,
+}
+
+/// Group holders whose funding chains (each starting with the holder itself) share a
+/// wallet; largest share first
+pub fn cluster(
+    holders: &[(String, f64)],
+    chains: &HashMap<String, Vec<String>>,
+) -> Vec<HolderCluster> {
+    // Union-find over the holders, joined through every wallet on their chains
+    let mut parent: Vec<usize> = (0..holders.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let chain = |owner: &str| {
+        chains
+            .get(owner)
+            .cloned()
+            .unwrap_or_else(|| vec![owner.to_string()])
+    };
+    let mut first_holder: HashMap<String, usize> = HashMap::new();
+    for (idx, (owner, _)) in holders.iter().enumerate() {
+        for wallet in chain(owner) {
+            match first_holder.get(&wallet) {
+                Some(&other) => {
+                    let (a, b) = (root(&mut parent, idx), root(&mut parent, other));
+                    parent[a] = b;
+                }
+                None => {
+                    first_holder.insert(wallet, idx);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idx in 0..holders.len() {
+        let r = root(&mut parent, idx);
+        groups.entry(r).or_default().push(idx);
+    }
+    let mut clusters: Vec<HolderCluster> = groups
+        .into_values()
+        .map(|mut members| {
+            members.sort_by(|a, b| holders[*b].1.total_cmp(&holders[*a].1));
+            let funder = (members.len() > 1)
+                .then(|| {
+                    let mut counts: Vec<(String, usize)> = Vec::new();
+                    for &m in &members {
+                        for wallet in chain(&holders[m].0) {
+                            match counts.iter_mut().find(|(w, _)| *w == wallet) {
+                                Some((_, n)) => *n += 1,
+                                None => counts.push((wallet, 1)),
+                            }
+                        }
+                    }
+                    counts
+                        .into_iter()
+                        .rev()
+                        .max_by_key(|(_, n)| *n)
+                        .map(|(w, _)| w)
+                })
+                .flatten();
+            HolderCluster {
+                wallets: members.iter().map(|&m| holders[m].0.clone()).collect(),
+                supply_pct: members.iter().map(|&m| holders[m].1).sum(),
+                funder,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.supply_pct.total_cmp(&a.supply_pct));
+    clusters
+}
+
+/// Traces top holders' funding, as configured with `FUNDING_HOPS` and `FUNDING_IGNORE`
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+    hops: usize,
+    ignore: HashSet<String>,
+}
+
+impl Tracer {
+    pub fn new(hops: usize, ignore: &[String]) -> Self {
+        Self {
+            hops,
+            ignore: ignore.iter().cloned().collect(),
+        }
+    }
+
+    /// Each holder's funding chain: the holder, then up to `hops` funders, traced a hop at a
+    /// time
+    async fn chains<P: TokenDataProvider>(
+        &self,
+        scanner: &P,
+        holders: &[(String, f64)],
+    ) -> HashMap<String, Vec<String>> {
+        let mut chains: HashMap<String, Vec<String>> = holders
+            .iter()
+            .map(|(owner, _)| (owner.clone(), vec![owner.clone()]))
+            .collect();
+        // Funders are looked up once, however many chains they are on
+        let mut funders: HashMap<String, Option<String>> = HashMap::new();
+        for _ in 0..self.hops {
+            let pending: HashSet<String> = chains
+                .values()
+                .filter_map(|chain| chain.last())
+                .filter(|w| !funders.contains_key(*w) && !self.ignore.contains(*w))
+                .cloned()
+                .collect();
+            let found: Vec<(String, Option<String>)> = stream::iter(pending)
+                .map(|wallet| async move {
+                    // A failed lookup ends the chain, like an unknown funder
+                    let funder = scanner.query_funder(&wallet).await.ok().flatten();
+                    (wallet, funder)
+                })
+                .buffer_unordered(CONCURRENCY)
+                .collect()
+                .await;
+            funders.extend(found);
+
+            let mut extended = false;
+            for chain in chains.values_mut() {
+                let Some(last) = chain.last() else { continue };
+                if self.ignore.contains(last) {
+                    continue;
+                }
+                if let Some(Some(funder)) = funders.get(last)
+                    && !self.ignore.contains(funder)
+                    && !chain.contains(funder)
+                {
+                    chain.push(funder.clone());
+                    extended = true;
+                }
+            }
+            if !extended {
+                break;
+            }
+        }
+        chains
+    }
+
+    /// Top holders of `mint`, without its bonding curve, merged into funding clusters
+    pub async fn clusters<P: TokenDataProvider>(
+        &self,
+        scanner: &P,
+        mint: &str,
+    ) -> Result<Vec<HolderCluster>> {
+        let curve = bonding_curve_address(mint).ok();
+        let holders: Vec<(String, f64)> = scanner
+            .query_token_top_holders(mint)
+            .await?
+            .and_then(|top| top.result)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|h| {
+                let owner = h.owner_address?;
+                (Some(&owner) != curve.as_ref())
+                    .then(|| (owner, h.percentage_relative_to_total_supply.unwrap_or(0.0)))
+            })
+            .collect();
+        let chains = self.chains(scanner, &holders).await;
+        Ok(cluster(&holders, &chains))
+    }
+
+    /// Set the token's `effective_top_holder_pct`; does nothing with `FUNDING_HOPS=0`
+    pub async fn apply<P: TokenDataProvider>(&self, scanner: &P, ev: &mut TokenEvent) {
+        if self.hops == 0 {
+            return;
+        }
+        if let Ok(clusters) = self.clusters(scanner, &ev.id).await
+            && let Some(largest) = clusters.first()
+        {
+            ev.effective_top_holder_pct = Some(largest.supply_pct);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_scanner::MockScanner;
+
+    #[tokio::test]
+    async fn merges_holders_with_a_common_funder() {
+        let holders: Vec<(String, f64)> = [("whale", 12.0), ("a", 3.0), ("b", 3.0), ("c", 3.0)]
+            .iter()
+            .map(|(w, pct)| (w.to_string(), *pct))
+            .collect();
+        let chains: HashMap<String, Vec<String>> = [
+            ("whale", vec!["whale", "exchange"]),
+            ("a", vec!["a", "mixer", "boss"]),
+            ("b", vec!["b", "boss"]),
+            ("c", vec!["c", "a"]),
+        ]
+        .into_iter()
+        .map(|(w, chain)| (w.to_string(), chain.iter().map(|s| s.to_string()).collect()))
+        .collect();
+        let clusters = cluster(&holders, &chains);
+        assert_eq!(clusters[0].wallets, ["whale"]);
+        assert_eq!(clusters[0].funder, None);
+        assert_eq!(clusters[1].wallets, ["a", "b", "c"]);
+        assert_eq!(clusters[1].supply_pct, 9.0);
+        // "a" is on two chains, as is "boss"; the tie goes to the first one found
+        assert_eq!(clusters[1].funder.as_deref(), Some("a"));
+
+        // Both snipers were funded by one wallet, in the recorded first transactions
+        let mock = MockScanner::new();
+        let tracer = Tracer::new(2, &[]);
+        let snipers = [
+            (
+                "Sniper1111111111111111111111111111111111111".to_string(),
+                4.0,
+            ),
+            (
+                "Sniper2222222222222222222222222222222222222".to_string(),
+                5.0,
+            ),
+        ];
+        let chains = tracer.chains(&mock, &snipers).await;
+        let clusters = cluster(&snipers, &chains);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].funder.as_deref(),
+            Some("Funder11111111111111111111111111111111111111")
+        );
+        let ignoring = Tracer::new(
+            2,
+            &["Funder11111111111111111111111111111111111111".to_string()],
+        );
+        let chains = ignoring.chains(&mock, &snipers).await;
+        assert_eq!(cluster(&snipers, &chains).len(), 2);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::api::SharedSnapshot;
+use crate::auth::{ApiKeys, Denied, Scope};
+use crate::control::{BotEvent, SharedControl};
+use crate::strategy_config::StrategyConfig;
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use tokio_stream::wrappers::BroadcastStream;
+use tonic::{Request, Response, Status};
+
+pub mod pb {
+    tonic::include_proto!("memebot.control.v1");
+}
+
+use pb::control_server::{Control, ControlServer};
+
+pub struct ControlService {
+    control: SharedControl,
+    snapshot: SharedSnapshot,
+    keys: ApiKeys,
+}
+
+/// Serve the gRPC control plane until the process exits
+pub async fn serve(
+    addr: &str,
+    control: SharedControl,
+    snapshot: SharedSnapshot,
+    keys: ApiKeys,
+) -> Result<()> {
+    let addr = addr.parse()?;
+    println!("gRPC control plane listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(ControlServer::new(ControlService {
+            control,
+            snapshot,
+            keys,
+        }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+impl ControlService {
+    /// Check the request's `authorization` metadata against the scope the call needs
+    #[allow(clippy::result_large_err)]
+    fn authorize<T>(&self, request: &Request<T>, needed: Scope) -> Result<(), Status> {
+        let header = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok());
+        self.keys
+            .authorize(header, needed)
+            .map_err(|denied| match denied {
+                Denied::Unauthenticated => Status::unauthenticated("missing or unknown API key"),
+                Denied::Forbidden => {
+                    Status::permission_denied(format!("this needs a {} key", needed.as_str()))
+                }
+            })
+    }
+
+    fn trading_state(&self) -> pb::TradingState {
+        pb::TradingState {
+            trading_enabled: self.control.trading_enabled(),
+            strategy_ids: self.control.strategy_ids(),
+        }
+    }
+}
+
+fn strategy_params(strategy_id: &str, c: &StrategyConfig) -> pb::StrategyParams {
+    pb::StrategyParams {
+        strategy_id: strategy_id.to_string(),
+        min_score_to_buy: c.min_score_to_buy,
+        min_liquidity_usd: c.min_liquidity_usd,
+        max_dev_hold_pct: c.max_dev_hold_pct,
+        max_sol_per_trade: c.max_sol_per_trade,
+        max_positions: c.max_positions as u32,
+        stop_loss_pct: c.stop_loss_pct,
+        min_profit_target_pct: c.min_profit_target_pct,
+        max_profit_target_pct: c.max_profit_target_pct,
+    }
+}
+
+fn unknown_strategy(strategy_id: &str) -> Status {
+    Status::not_found(format!("unknown strategy '{}'", strategy_id))
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    async fn start_trading(
+        &self,
+        request: Request<pb::StartTradingRequest>,
+    ) -> Result<Response<pb::TradingState>, Status> {
+        self.authorize(&request, Scope::Control)?;
+        self.control.set_trading_enabled(true);
+        println!("[grpc] Trading started");
+        Ok(Response::new(self.trading_state()))
+    }
+
+    async fn stop_trading(
+        &self,
+        request: Request<pb::StopTradingRequest>,
+    ) -> Result<Response<pb::TradingState>, Status> {
+        self.authorize(&request, Scope::Control)?;
+        self.control.set_trading_enabled(false);
+        println!("[grpc] Trading stopped");
+        Ok(Response::new(self.trading_state()))
+    }
+
+    async fn get_trading_state(
+        &self,
+        request: Request<pb::GetTradingStateRequest>,
+    ) -> Result<Response<pb::TradingState>, Status> {
+        self.authorize(&request, Scope::Read)?;
+        Ok(Response::new(self.trading_state()))
+    }
+
+    async fn get_strategy_params(
+        &self,
+        request: Request<pb::GetStrategyParamsRequest>,
+    ) -> Result<Response<pb::StrategyParams>, Status> {
+        self.authorize(&request, Scope::Read)?;
+        let id = request.into_inner().strategy_id;
+        let config = self
+            .control
+            .config(&id)
+            .ok_or_else(|| unknown_strategy(&id))?;
+        Ok(Response::new(strategy_params(&id, &config)))
+    }
+
+    async fn update_strategy_params(
+        &self,
+        request: Request<pb::UpdateStrategyParamsRequest>,
+    ) -> Result<Response<pb::StrategyParams>, Status> {
+        self.authorize(&request, Scope::Control)?;
+        let req = request.into_inner();
+        if let (Some(min), Some(max)) = (req.min_profit_target_pct, req.max_profit_target_pct)
+            && min > max
+        {
+            return Err(Status::invalid_argument(
+                "min_profit_target_pct must not exceed max_profit_target_pct",
+            ));
+        }
+        let config = self
+            .control
+            .update_config(&req.strategy_id, |c| {
+                if let Some(v) = req.min_score_to_buy {
+                    c.min_score_to_buy = v;
+                }
+                if let Some(v) = req.min_liquidity_usd {
+                    c.min_liquidity_usd = v;
+                }
+                if let Some(v) = req.max_dev_hold_pct {
+                    c.max_dev_hold_pct = v;
+                }
+                if let Some(v) = req.max_sol_per_trade {
+                    c.max_sol_per_trade = v;
+                }
+                if let Some(v) = req.max_positions {
+                    c.max_positions = v as usize;
+                }
+                if let Some(v) = req.stop_loss_pct {
+                    c.stop_loss_pct = v;
+                }
+                if let Some(v) = req.min_profit_target_pct {
+                    c.min_profit_target_pct = v;
+                }
+                if let Some(v) = req.max_profit_target_pct {
+                    c.max_profit_target_pct = v;
+                }
+            })
+            .ok_or_else(|| unknown_strategy(&req.strategy_id))?;
+        println!("[grpc] Updated parameters for {}", req.strategy_id);
+        Ok(Response::new(strategy_params(&req.strategy_id, &config)))
+    }
+
+    async fn list_positions(
+        &self,
+        request: Request<pb::ListPositionsRequest>,
+    ) -> Result<Response<pb::ListPositionsResponse>, Status> {
+        self.authorize(&request, Scope::Read)?;
+        let filter = request.into_inner().strategy_id;
+        let positions = self
+            .snapshot
+            .read()
+            .await
+            .positions
+            .iter()
+            .filter(|p| filter.is_empty() || p.strategy_id == filter)
+            .map(|p| pb::Position {
+                strategy_id: p.strategy_id.clone(),
+                token_id: p.token_id.clone(),
+                entry_price: p.entry_price,
+                last_price: p.last_price,
+                qty: p.qty,
+                usd_in: p.usd_in,
+                value_usd: p.value_usd,
+                unrealized_pnl: p.unrealized_pnl,
+                wallet: p.wallet.clone().unwrap_or_default(),
+            })
+            .collect();
+        Ok(Response::new(pb::ListPositionsResponse { positions }))
+    }
+
+    type SubscribeDecisionsStream =
+        Pin<Box<dyn Stream<Item = Result<pb::DecisionEvent, Status>> + Send>>;
+
+    #[allow(clippy::result_large_err)]
+    async fn subscribe_decisions(
+        &self,
+        request: Request<pb::SubscribeDecisionsRequest>,
+    ) -> Result<Response<Self::SubscribeDecisionsStream>, Status> {
+        self.authorize(&request, Scope::Read)?;
+        let filter = request.into_inner().strategy_id;
+        // Slow subscribers skip events they lagged behind on rather than ending the stream
+        let stream = BroadcastStream::new(self.control.subscribe()).filter_map(move |event| {
+            let event = match event {
+                Ok(BotEvent::Decision(e)) => Some(e),
+                _ => None,
+            };
+            let event = event
+                .filter(|e| filter.is_empty() || e.strategy_id == filter)
+                .map(|e| {
+                    Ok(pb::DecisionEvent {
+                        strategy_id: e.strategy_id,
+                        token_id: e.token_id,
+                        action: e.action.to_string(),
+                        score: e.score,
+                        reasons: e.reasons,
+                        size_fraction: e.size_fraction.unwrap_or(0.0),
+                        price_usd: e.price_usd.unwrap_or(0.0),
+                        priced_at_ms: e.priced_at_ms.unwrap_or(0),
+                        timestamp: e.at.timestamp(),
+                    })
+                });
+            futures::future::ready(event)
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,302 @@
+//! Health supervisor for the bot's external dependencies
+//!
+//! Every `HEALTH_CHECK_INTERVAL_SECS` the supervisor probes the Solana RPC, the PumpPortal
+//! WebSocket, DexScreener, and the database. A dependency is degraded after
+//! `HEALTH_FAILURE_THRESHOLD` consecutive failed checks and healthy again after one passing
+//! check. While any dependency listed in `HEALTH_CRITICAL` is degraded, new entries are
+//! paused; exits keep running on whatever data is available. Entries resume on their own once
+//! every critical dependency recovers. Each change is published as a `health` event.
+
+use crate::control::{BotEvent, HealthEvent, SharedControl};
+use crate::scanner::Scanner;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Longest a single check may take before it counts as failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dependency {
+    Rpc,
+    PumpPortal,
+    DexScreener,
+    Database,
+}
+
+impl Dependency {
+    pub const ALL: [Dependency; 4] = [
+        Dependency::Rpc,
+        Dependency::PumpPortal,
+        Dependency::DexScreener,
+        Dependency::Database,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dependency::Rpc => "rpc",
+            Dependency::PumpPortal => "pumpportal",
+            Dependency::DexScreener => "dexscreener",
+            Dependency::Database => "database",
+        }
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Dependency::ALL.into_iter().find(|d| d.as_str() == s) {
+            Some(dependency) => Ok(dependency),
+            None => bail!(
+                "unknown dependency '{}' (expected rpc, pumpportal, dexscreener, or database)",
+                s
+            ),
+        }
+    }
+}
+
+/// Latest check result of one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyHealth {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub critical: bool,
+    pub consecutive_failures: u32,
+    /// Error of the last failed check
+    pub detail: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+/// What `GET /health` returns
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// `ok`, or `degraded` while any dependency is
+    pub status: &'static str,
+    pub entries_paused: bool,
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+pub struct HealthState {
+    dependencies: RwLock<Vec<DependencyHealth>>,
+    failure_threshold: u32,
+}
+
+pub type SharedHealth = Arc<HealthState>;
+
+impl HealthState {
+    /// Every dependency starts out healthy until checks say otherwise
+    pub fn new(critical: &[Dependency], failure_threshold: u32) -> Self {
+        let dependencies = Dependency::ALL
+            .iter()
+            .map(|d| DependencyHealth {
+                name: d.as_str(),
+                healthy: true,
+                critical: critical.contains(d),
+                consecutive_failures: 0,
+                detail: None,
+                latency_ms: None,
+                checked_at: None,
+            })
+            .collect();
+        Self {
+            dependencies: RwLock::new(dependencies),
+            failure_threshold: failure_threshold.max(1),
+        }
+    }
+
+    /// Apply a check result; returns the dependency's new state when it changed between
+    /// healthy and degraded
+    pub fn record(
+        &self,
+        dependency: Dependency,
+        result: Result<Duration>,
+        at: DateTime<Utc>,
+    ) -> Option<DependencyHealth> {
+        let mut dependencies = self.dependencies.write().unwrap();
+        let health = dependencies
+            .iter_mut()
+            .find(|h| h.name == dependency.as_str())?;
+        let was_healthy = health.healthy;
+        health.checked_at = Some(at);
+        match result {
+            Ok(latency) => {
+                health.healthy = true;
+                health.consecutive_failures = 0;
+                health.detail = None;
+                health.latency_ms = Some(latency.as_millis() as u64);
+            }
+            Err(e) => {
+                health.consecutive_failures += 1;
+                health.detail = Some(e.to_string());
+                health.latency_ms = None;
+                if health.consecutive_failures >= self.failure_threshold {
+                    health.healthy = false;
+                }
+            }
+        }
+        (health.healthy != was_healthy).then(|| health.clone())
+    }
+
+    /// Names of the critical dependencies that are degraded
+    pub fn degraded_critical(&self) -> Vec<&'static str> {
+        self.dependencies
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|h| h.critical && !h.healthy)
+            .map(|h| h.name)
+            .collect()
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let dependencies = self.dependencies.read().unwrap().clone();
+        let entries_paused = dependencies.iter().any(|h| h.critical && !h.healthy);
+        let status = if dependencies.iter().all(|h| h.healthy) {
+            "ok"
+        } else {
+            "degraded"
+        };
+        HealthReport {
+            status,
+            entries_paused,
+            dependencies,
+        }
+    }
+}
+
+pub struct HealthSupervisor {
+    scanner: Scanner,
+    /// Without a database (`--no-db`) there is none to check
+    pool: Option<PgPool>,
+    state: SharedHealth,
+    control: SharedControl,
+    interval: Duration,
+}
+
+impl HealthSupervisor {
+    pub fn new(
+        scanner: Scanner,
+        pool: Option<PgPool>,
+        state: SharedHealth,
+        control: SharedControl,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            scanner,
+            pool,
+            state,
+            control,
+            interval,
+        }
+    }
+
+    /// Check every dependency each interval until the process exits
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            for dependency in Dependency::ALL {
+                if dependency == Dependency::Database && self.pool.is_none() {
+                    continue;
+                }
+                let result = self.check(dependency).await;
+                let Some(changed) = self.state.record(dependency, result, Utc::now()) else {
+                    continue;
+                };
+                self.apply(changed);
+            }
+        }
+    }
+
+    async fn check(&self, dependency: Dependency) -> Result<Duration> {
+        let started = Instant::now();
+        let check = async {
+            match dependency {
+                Dependency::Rpc => self.scanner.check_rpc().await,
+                Dependency::PumpPortal => self.scanner.check_pumpportal().await,
+                Dependency::DexScreener => self.scanner.check_dexscreener().await,
+                Dependency::Database => {
+                    if let Some(pool) = &self.pool {
+                        sqlx::query("SELECT 1").execute(pool).await?;
+                    }
+                    Ok(())
+                }
+            }
+        };
+        match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+            Ok(result) => result.map(|()| started.elapsed()),
+            Err(_) => bail!("no answer within {}s", CHECK_TIMEOUT.as_secs()),
+        }
+    }
+
+    /// Log a dependency's change, pause or resume entries, and publish it
+    fn apply(&self, changed: DependencyHealth) {
+        let degraded = self.state.degraded_critical();
+        let paused = !degraded.is_empty();
+        if changed.healthy {
+            println!("[health] {} recovered", changed.name);
+        } else {
+            println!(
+                "[health] {} degraded: {}",
+                changed.name,
+                changed.detail.as_deref().unwrap_or("unknown error")
+            );
+        }
+        if paused != self.control.entries_paused() {
+            self.control.set_entries_paused(paused);
+            if paused {
+                println!(
+                    "[health] Pausing entries while {} is degraded",
+                    degraded.join(", ")
+                );
+            } else {
+                println!("[health] Critical dependencies recovered, resuming entries");
+            }
+        }
+        self.control.publish(BotEvent::Health(HealthEvent {
+            dependency: changed.name,
+            healthy: changed.healthy,
+            detail: changed.detail,
+            entries_paused: paused,
+            at: changed.checked_at.unwrap_or_else(Utc::now),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn degrades_after_repeated_failures_and_recovers_at_once() {
+        let state = HealthState::new(&[Dependency::Rpc], 2);
+        let now = Utc::now();
+        let fail = || Err(anyhow!("connection refused"));
+
+        assert!(state.record(Dependency::Rpc, fail(), now).is_none());
+        let degraded = state.record(Dependency::Rpc, fail(), now).unwrap();
+        assert!(!degraded.healthy && degraded.consecutive_failures == 2);
+        assert_eq!(state.degraded_critical(), vec!["rpc"]);
+
+        // A non-critical dependency is reported but doesn't pause entries
+        state.record(Dependency::DexScreener, fail(), now);
+        state.record(Dependency::DexScreener, fail(), now);
+        assert_eq!(state.degraded_critical(), vec!["rpc"]);
+        let report = state.report();
+        assert!(report.status == "degraded" && report.entries_paused);
+
+        let recovered = state
+            .record(Dependency::Rpc, Ok(Duration::from_millis(80)), now)
+            .unwrap();
+        assert!(recovered.healthy && recovered.latency_ms == Some(80));
+        assert!(state.degraded_critical().is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+//! Top-holder history for post-mortems
+//!
+//! While the bot runs, every `HOLDER_SNAPSHOT_INTERVAL_SECS` the top 20 holders of each token it
+//! holds, sold within the last hour, or watches are stored in `holder_snapshots`. After a rug,
+//! `holders <MINT>` shows how the top of the holder list changed and which wallets
+//! accumulated and dumped.
+
+use crate::blacklist::validate_address;
+use crate::scanner::{Scanner, TokenDataProvider};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A wallet's share of supply at one snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding {
+    pub owner: String,
+    pub supply_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub at: DateTime<Utc>,
+    /// Largest holder first
+    pub holders: Vec<Holding>,
+}
+
+/// A wallet's share at every snapshot, None where it wasn't among the top holders
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletHistory {
+    pub owner: String,
+    pub shares: Vec<Option<f64>>,
+}
+
+impl WalletHistory {
+    pub fn first(&self) -> f64 {
+        self.shares.iter().flatten().next().copied().unwrap_or(0.0)
+    }
+
+    pub fn peak(&self) -> f64 {
+        self.shares.iter().flatten().copied().fold(0.0, f64::max)
+    }
+
+    /// What the wallet did between its first snapshot and the last one
+    pub fn trend(&self) -> &'static str {
+        let Some(Some(last)) = self.shares.last() else {
+            return "exited";
+        };
+        if *last <= self.peak() * 0.5 {
+            "dumped"
+        } else if *last >= self.first() * 1.5 {
+            "accumulated"
+        } else {
+            "held"
+        }
+    }
+}
+
+/// Every wallet seen among the top holders, largest peak share first
+pub fn wallet_histories(snapshots: &[Snapshot]) -> Vec<WalletHistory> {
+    let mut by_owner: HashMap<&str, Vec<Option<f64>>> = HashMap::new();
+    for (idx, snapshot) in snapshots.iter().enumerate() {
+        for holding in &snapshot.holders {
+            by_owner
+                .entry(&holding.owner)
+                .or_insert_with(|| vec![None; snapshots.len()])[idx] = Some(holding.supply_pct);
+        }
+    }
+    let mut histories: Vec<WalletHistory> = by_owner
+        .into_iter()
+        .map(|(owner, shares)| WalletHistory {
+            owner: owner.to_string(),
+            shares,
+        })
+        .collect();
+    histories.sort_by(|a, b| b.peak().total_cmp(&a.peak()).then(a.owner.cmp(&b.owner)));
+    histories
+}
+
+/// Snapshots of a mint's top holders, oldest first
+pub async fn load(pool: &PgPool, mint: &str) -> Result<Vec<Snapshot>> {
+    let rows = sqlx::query(
+        "SELECT EXTRACT(EPOCH FROM snapshot_at)::FLOAT8 AS at, owner, supply_pct \
+         FROM holder_snapshots WHERE token_id = $1 ORDER BY snapshot_at, rank",
+    )
+    .bind(mint)
+    .fetch_all(pool)
+    .await?;
+    let mut snapshots: Vec<Snapshot> = Vec::new();
+    for row in rows {
+        let at = DateTime::from_timestamp_micros((row.get::<f64, _>("at") * 1e6) as i64)
+            .unwrap_or_default();
+        if snapshots.last().is_none_or(|s| s.at != at) {
+            snapshots.push(Snapshot {
+                at,
+                holders: Vec::new(),
+            });
+        }
+        if let Some(snapshot) = snapshots.last_mut() {
+            snapshot.holders.push(Holding {
+                owner: row.get("owner"),
+                supply_pct: row.get("supply_pct"),
+            });
+        }
+    }
+    Ok(snapshots)
+}
+
+/// `holders <MINT>`: print how a mint's top holders changed across its snapshots
+pub async fn report(pool: &PgPool, mint: &str) -> Result<()> {
+    validate_address(mint)?;
+    let snapshots = load(pool, mint).await?;
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        println!("No holder snapshots of {}", mint);
+        return Ok(());
+    };
+    println!(
+        "Top holders of {}: {} snapshots, {} to {}",
+        mint,
+        snapshots.len(),
+        first.at.format("%Y-%m-%d %H:%M"),
+        last.at.format("%Y-%m-%d %H:%M")
+    );
+    println!(
+        "  {:<17} {:>7} {:>7} {:>7} {:>5} {:>5}",
+        "Time", "Top 1", "Top 10", "Top 20", "New", "Gone"
+    );
+    let top = |s: &Snapshot, n: usize| s.holders.iter().take(n).map(|h| h.supply_pct).sum::<f64>();
+    for (idx, snapshot) in snapshots.iter().enumerate() {
+        let (new, gone) = match idx.checked_sub(1).map(|prev| &snapshots[prev]) {
+            Some(prev) => (
+                snapshot
+                    .holders
+                    .iter()
+                    .filter(|h| prev.holders.iter().all(|p| p.owner != h.owner))
+                    .count(),
+                prev.holders
+                    .iter()
+                    .filter(|p| snapshot.holders.iter().all(|h| h.owner != p.owner))
+                    .count(),
+            ),
+            None => (snapshot.holders.len(), 0),
+        };
+        println!(
+            "  {:<17} {:>6.1}% {:>6.1}% {:>6.1}% {:>5} {:>5}",
+            snapshot.at.format("%Y-%m-%d %H:%M"),
+            top(snapshot, 1),
+            top(snapshot, 10),
+            top(snapshot, 20),
+            new,
+            gone
+        );
+    }
+
+    println!();
+    println!(
+        "  {:<44} {:>7} {:>7} {:>7}  Trend",
+        "Wallet", "First", "Peak", "Last"
+    );
+    for wallet in wallet_histories(&snapshots) {
+        let last = match wallet.shares.last() {
+            Some(Some(pct)) => format!("{:.1}%", pct),
+            _ => "-".to_string(),
+        };
+        println!(
+            "  {:<44} {:>6.1}% {:>6.1}% {:>7}  {}",
+            wallet.owner,
+            wallet.first(),
+            wallet.peak(),
+            last,
+            wallet.trend()
+        );
+    }
+    Ok(())
+}
+
+/// Stores the top holders of held, recently sold, and watched tokens, until the process exits
+#[derive(Clone)]
+pub struct Snapshotter {
+    pool: PgPool,
+    scanner: Scanner,
+    interval: Duration,
+}
+
+impl Snapshotter {
+    pub fn new(pool: PgPool, scanner: Scanner, interval: Duration) -> Self {
+        Self {
+            pool,
+            scanner,
+            interval,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.snapshot_all().await {
+                println!("[holders] Snapshot failed: {}", e);
+            }
+        }
+    }
+
+    async fn snapshot_all(&self) -> Result<()> {
+        let mints: Vec<String> = sqlx::query_scalar(
+            "SELECT token_id FROM trades \
+             WHERE closed_at IS NULL OR closed_at > NOW() - INTERVAL '1 hour' \
+             UNION SELECT mint FROM watchlist",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for mint in mints {
+            // A failed lookup leaves a gap rather than an empty holder list
+            let Ok(Some(top)) = self.scanner.query_token_top_holders(&mint).await else {
+                continue;
+            };
+            let holders = top.result.unwrap_or_default();
+            if holders.is_empty() {
+                continue;
+            }
+            let mut ranks = Vec::with_capacity(holders.len());
+            let mut owners = Vec::with_capacity(holders.len());
+            let mut amounts = Vec::with_capacity(holders.len());
+            let mut shares = Vec::with_capacity(holders.len());
+            for (rank, holder) in holders.into_iter().enumerate() {
+                let (Some(owner), Some(amount)) = (holder.owner_address, holder.amount) else {
+                    continue;
+                };
+                ranks.push(rank as i16 + 1);
+                owners.push(owner);
+                amounts.push(amount);
+                shares.push(holder.percentage_relative_to_total_supply.unwrap_or(0.0));
+            }
+            sqlx::query(
+                "INSERT INTO holder_snapshots \
+                 (token_id, snapshot_at, rank, owner, amount, supply_pct) \
+                 SELECT $1, NOW(), rank, owner, amount::NUMERIC, supply_pct \
+                 FROM UNNEST($2::SMALLINT[], $3::TEXT[], $4::TEXT[], $5::FLOAT8[]) \
+                 AS h(rank, owner, amount, supply_pct)",
+            )
+            .bind(&mint)
+            .bind(&ranks)
+            .bind(&owners)
+            .bind(&amounts)
+            .bind(&shares)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_wallets_across_snapshots() {
+        let snapshot = |minute: i64, holders: &[(&str, f64)]| Snapshot {
+            at: DateTime::from_timestamp(1_760_600_000 + minute * 60, 0).unwrap(),
+            holders: holders
+                .iter()
+                .map(|(owner, pct)| Holding {
+                    owner: owner.to_string(),
+                    supply_pct: *pct,
+                })
+                .collect(),
+        };
+        let snapshots = [
+            snapshot(0, &[("dev", 20.0), ("sniper", 5.0), ("fan", 2.0)]),
+            snapshot(5, &[("sniper", 15.0), ("dev", 10.0), ("fan", 2.2)]),
+            snapshot(10, &[("dev", 9.0), ("fan", 4.0), ("late", 1.0)]),
+        ];
+        let histories = wallet_histories(&snapshots);
+        let trend = |owner: &str| {
+            histories
+                .iter()
+                .find(|h| h.owner == owner)
+                .map(|h| h.trend())
+                .unwrap()
+        };
+        assert_eq!(histories[0].owner, "dev");
+        assert_eq!(histories[0].shares, [Some(20.0), Some(10.0), Some(9.0)]);
+        assert_eq!(trend("dev"), "dumped");
+        assert_eq!(trend("sniper"), "exited");
+        assert_eq!(trend("fan"), "accumulated");
+        assert_eq!(trend("late"), "held");
+    }
+}
@@ -0,0 +1,187 @@
+//! Per-stage pipeline latency histograms
+//!
+//! Each listing is timed from the moment its WebSocket message was received:
+//! - `queue`: waiting for enrichment to start
+//! - `enrich`: chain, holder, and DEX lookups
+//! - `decide`: the entry decision, including risk checks and the sell-route quote
+//! - `submit`: from deciding to buy until the entry is recorded, after the live order lands
+//!   or the simulated fill
+//! - `to_decision` / `to_submission`: end to end from receipt
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets in milliseconds; slower samples land in `+Inf`
+const BUCKETS_MS: [f64; 14] = [
+    1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0,
+    30_000.0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Queue,
+    Enrich,
+    Decide,
+    Submit,
+    ToDecision,
+    ToSubmission,
+}
+
+impl Stage {
+    const ALL: [Stage; 6] = [
+        Stage::Queue,
+        Stage::Enrich,
+        Stage::Decide,
+        Stage::Submit,
+        Stage::ToDecision,
+        Stage::ToSubmission,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Queue => "queue",
+            Stage::Enrich => "enrich",
+            Stage::Decide => "decide",
+            Stage::Submit => "submit",
+            Stage::ToDecision => "to_decision",
+            Stage::ToSubmission => "to_submission",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Histogram {
+    /// One count per bucket in `BUCKETS_MS`, then `+Inf`
+    counts: [AtomicU64; BUCKETS_MS.len() + 1],
+    sum_us: AtomicU64,
+}
+
+impl Histogram {
+    pub fn record(&self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1_000.0;
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKETS_MS.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_us
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Upper bound of the bucket holding quantile `q`, in milliseconds; infinite when it
+    /// falls past the last bucket
+    pub fn quantile_ms(&self, q: f64) -> Option<f64> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+        let rank = (q * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count.load(Ordering::Relaxed);
+            if seen >= rank {
+                return Some(BUCKETS_MS.get(i).copied().unwrap_or(f64::INFINITY));
+            }
+        }
+        None
+    }
+}
+
+/// Histograms for every stage, shared between the pipeline and the metrics endpoint
+#[derive(Debug, Default)]
+pub struct LatencyStats {
+    stages: [Histogram; Stage::ALL.len()],
+}
+
+impl LatencyStats {
+    pub fn record(&self, stage: Stage, elapsed: Duration) {
+        self.histogram(stage).record(elapsed);
+    }
+
+    pub fn histogram(&self, stage: Stage) -> &Histogram {
+        &self.stages[stage as usize]
+    }
+
+    /// Prometheus text exposition, one `memebot_pipeline_latency_ms` series per stage
+    pub fn render_prometheus(&self) -> String {
+        let name = "memebot_pipeline_latency_ms";
+        let mut out = format!("# TYPE {} histogram\n", name);
+        for stage in Stage::ALL {
+            let histogram = self.histogram(stage);
+            let mut cumulative = 0;
+            for (i, count) in histogram.counts.iter().enumerate() {
+                cumulative += count.load(Ordering::Relaxed);
+                let le = BUCKETS_MS
+                    .get(i)
+                    .map_or("+Inf".to_string(), |b| b.to_string());
+                out.push_str(&format!(
+                    "{}_bucket{{stage=\"{}\",le=\"{}\"}} {}\n",
+                    name,
+                    stage.as_str(),
+                    le,
+                    cumulative
+                ));
+            }
+            let sum_ms = histogram.sum_us.load(Ordering::Relaxed) as f64 / 1_000.0;
+            out.push_str(&format!(
+                "{}_sum{{stage=\"{}\"}} {}\n{}_count{{stage=\"{}\"}} {}\n",
+                name,
+                stage.as_str(),
+                sum_ms,
+                name,
+                stage.as_str(),
+                cumulative
+            ));
+        }
+        out
+    }
+}
+
+/// One line per stage with samples: count, p50, and p99
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stage in Stage::ALL {
+            let histogram = self.histogram(stage);
+            let (Some(p50), Some(p99)) = (histogram.quantile_ms(0.5), histogram.quantile_ms(0.99))
+            else {
+                continue;
+            };
+            writeln!(
+                f,
+                "{:<14} n={:<6} p50<={}ms p99<={}ms",
+                stage.as_str(),
+                histogram.count(),
+                p50,
+                p99
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_samples_by_stage() {
+        let stats = LatencyStats::default();
+        for ms in [3, 4, 8, 40, 90_000] {
+            stats.record(Stage::Enrich, Duration::from_millis(ms));
+        }
+        let enrich = stats.histogram(Stage::Enrich);
+        assert_eq!(enrich.count(), 5);
+        assert_eq!(enrich.quantile_ms(0.5), Some(10.0));
+        assert_eq!(enrich.quantile_ms(0.99), Some(f64::INFINITY));
+        assert_eq!(stats.histogram(Stage::Decide).quantile_ms(0.5), None);
+
+        let text = stats.render_prometheus();
+        assert!(text.contains("memebot_pipeline_latency_ms_bucket{stage=\"enrich\",le=\"5\"} 2"));
+        assert!(text.contains("memebot_pipeline_latency_ms_count{stage=\"enrich\"} 5"));
+    }
+}
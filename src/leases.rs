@@ -0,0 +1,198 @@
+//! Leases in the `leases` table, so several instances can share one database and wallet
+//!
+//! With `LEASE_TTL_SECS` set, an instance claims `position:<strategy>:<mint>` before buying a
+//! token and holds it until the position closes, so another instance running the same
+//! strategy neither buys the token again nor sells it out from under the first. One instance
+//! also holds the `leader` lease and runs the jobs that must not run twice: the daily digest,
+//! outcome labeling, and holder snapshots. Leases are renewed every third of the TTL; those
+//! of an instance that stops expire, and another instance takes over its jobs. An instance
+//! that fails to renew or loses the `leader` lease stops its jobs until it leads again.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+const LEADER: &str = "leader";
+
+/// Lease held while a strategy has a position in `mint`
+pub fn position_lease(strategy_id: &str, mint: &str) -> String {
+    format!("position:{}:{}", strategy_id, mint)
+}
+
+pub struct Leases {
+    pool: PgPool,
+    /// This instance, as recorded in `leases.holder`
+    holder: String,
+    ttl: Duration,
+    /// Whether this instance held the `leader` lease at its last renewal
+    leader: watch::Sender<bool>,
+}
+
+impl Leases {
+    pub fn new(pool: PgPool, holder: String, ttl: Duration) -> Self {
+        Self {
+            pool,
+            holder,
+            ttl,
+            leader: watch::Sender::new(false),
+        }
+    }
+
+    /// Take `name` unless another instance holds it unexpired; renews it if this one does
+    pub async fn claim(&self, name: &str) -> Result<bool> {
+        let claimed: Option<String> = sqlx::query_scalar(
+            "INSERT INTO leases (name, holder, expires_at) \
+             VALUES ($1, $2, NOW() + make_interval(secs => $3)) \
+             ON CONFLICT (name) DO UPDATE SET holder = EXCLUDED.holder, \
+             expires_at = EXCLUDED.expires_at \
+             WHERE leases.holder = EXCLUDED.holder OR leases.expires_at < NOW() \
+             RETURNING holder",
+        )
+        .bind(name)
+        .bind(&self.holder)
+        .bind(self.ttl.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(claimed.is_some())
+    }
+
+    /// Give up `name` if this instance holds it
+    pub async fn release(&self, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM leases WHERE name = $1 AND holder = $2")
+            .bind(name)
+            .bind(&self.holder)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Release `name`, logging rather than failing; an unreleased lease expires on its own
+    pub async fn release_logged(&self, name: &str) {
+        if let Err(e) = self.release(name).await {
+            println!("[leases] Failed to release {}: {}", name, e);
+        }
+    }
+
+    async fn renew(&self) -> Result<()> {
+        sqlx::query(
+            "UPDATE leases SET expires_at = NOW() + make_interval(secs => $2) \
+             WHERE holder = $1",
+        )
+        .bind(&self.holder)
+        .bind(self.ttl.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+        let leader = self.claim(LEADER).await?;
+        self.set_leader(leader);
+        Ok(())
+    }
+
+    fn set_leader(&self, leader: bool) {
+        if leader != self.leader.send_replace(leader) {
+            println!(
+                "[leases] {} {} the leader",
+                self.holder,
+                if leader { "is now" } else { "is no longer" }
+            );
+        }
+    }
+
+    /// Renew this instance's leases and contend for leadership until the process exits
+    pub async fn heartbeat(&self) {
+        let mut ticker = tokio::time::interval(self.ttl / 3);
+        loop {
+            ticker.tick().await;
+            // Without a renewal the `leader` lease may lapse to another instance
+            if let Err(e) = self.renew().await {
+                println!("[leases] Renewal failed: {}", e);
+                self.set_leader(false);
+            }
+        }
+    }
+
+    /// Run the job `start` makes while this instance is the leader. The job is dropped as
+    /// soon as leadership is lost, and started afresh once it is regained.
+    pub async fn as_leader<F: Future<Output = ()>>(&self, name: &str, start: impl Fn() -> F) {
+        let mut leader = self.leader.subscribe();
+        loop {
+            if leader.wait_for(|leader| *leader).await.is_err() {
+                return;
+            }
+            println!("[leases] Running {} as the leader", name);
+            tokio::select! {
+                _ = start() => return,
+                _ = leader.wait_for(|leader| !*leader) => {
+                    println!("[leases] Stopped {}: no longer the leader", name);
+                }
+            }
+        }
+    }
+}
+
+/// Spawn a job that must not run on two instances at once: on the leader when leases are on.
+/// `start` makes the job, again each time this instance becomes the leader.
+pub fn spawn_singleton<F>(
+    leases: Option<&Arc<Leases>>,
+    name: &'static str,
+    start: impl Fn() -> F + Send + 'static,
+) where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match leases.cloned() {
+        Some(leases) => {
+            tokio::spawn(async move { leases.as_leader(name, start).await });
+        }
+        None => {
+            tokio::spawn(start());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn stops_the_job_when_leadership_is_lost() {
+        let pool = PgPool::connect_lazy("postgres://localhost/leases").unwrap();
+        let leases = Leases::new(pool, "a".to_string(), Duration::from_secs(30));
+        let starts = AtomicUsize::new(0);
+        let running = AtomicUsize::new(0);
+        let job = || async {
+            starts.fetch_add(1, Ordering::Relaxed);
+            running.fetch_add(1, Ordering::Relaxed);
+            // Counts as stopped once dropped
+            struct Stop<'a>(&'a AtomicUsize);
+            impl Drop for Stop<'_> {
+                fn drop(&mut self) {
+                    self.0.fetch_sub(1, Ordering::Relaxed);
+                }
+            }
+            let _stop = Stop(&running);
+            std::future::pending::<()>().await
+        };
+        let settle = || tokio::time::sleep(Duration::from_millis(20));
+
+        tokio::select! {
+            _ = leases.as_leader("test", job) => panic!("the job never ends"),
+            _ = async {
+                settle().await;
+                assert_eq!(starts.load(Ordering::Relaxed), 0);
+                leases.set_leader(true);
+                settle().await;
+                assert_eq!(running.load(Ordering::Relaxed), 1);
+                leases.set_leader(false);
+                settle().await;
+                assert_eq!(running.load(Ordering::Relaxed), 0);
+                leases.set_leader(true);
+                settle().await;
+                assert_eq!(starts.load(Ordering::Relaxed), 2);
+                assert_eq!(running.load(Ordering::Relaxed), 1);
+            } => {}
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Ledger of each strategy's SOL balance
+//!
+//! Every entry, exit, and banking of profits appends the strategy's free balance and the SOL
+//! in its open positions after the change. A trade row and its ledger entry are written in one
+//! database transaction, and the in-memory portfolio only changes once that commits, so a crash
+//! between statements cannot leave them disagreeing. Each process appends a `start` entry per
+//! strategy with its starting balance, which begins a new chain; chains are checked on startup.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use sqlx::postgres::PgExecutor;
+use std::collections::HashMap;
+
+/// Rounding tolerance when comparing SOL amounts
+const EPSILON_SOL: f64 = 1e-6;
+
+pub struct Entry<'a> {
+    pub strategy_id: &'a str,
+    /// `start`, `buy`, `sell`, or `bank`
+    pub kind: &'a str,
+    pub order_id: Option<&'a str>,
+    pub delta_sol: f64,
+    pub balance_sol: f64,
+    pub open_sol: f64,
+}
+
+/// Append `entry`; an order that already has an entry is left as recorded
+pub async fn record<'e>(executor: impl PgExecutor<'e>, entry: &Entry<'_>) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO balance_ledger (strategy_id, kind, client_order_id, delta_sol, balance_sol, open_sol) \
+         VALUES ($1,$2,$3,$4,$5,$6) ON CONFLICT (client_order_id) DO NOTHING",
+    )
+    .bind(entry.strategy_id)
+    .bind(entry.kind)
+    .bind(entry.order_id)
+    .bind(entry.delta_sol)
+    .bind(entry.balance_sol)
+    .bind(entry.open_sol)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Row {
+    id: i32,
+    strategy_id: String,
+    kind: String,
+    client_order_id: Option<String>,
+    delta_sol: f64,
+    balance_sol: f64,
+    open_sol: f64,
+}
+
+/// Check the ledger against itself and against `trades`; returns every violation found
+pub async fn check(pool: &PgPool) -> Result<Vec<String>> {
+    let rows: Vec<Row> = sqlx::query_as(
+        "SELECT id, strategy_id, kind, client_order_id, delta_sol, balance_sol, open_sol \
+         FROM balance_ledger ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let open_trades: Vec<(String, f64)> = sqlx::query_as(
+        "SELECT client_order_id, sol_in FROM trades \
+         WHERE closed_at IS NULL AND client_order_id IS NOT NULL AND sol_in IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut found = violations(&rows, &open_trades);
+
+    // Trades recorded since order IDs exist must each have their ledger entries
+    let unrecorded: Vec<(String,)> = sqlx::query_as(
+        "SELECT order_id FROM ( \
+           SELECT client_order_id AS order_id FROM trades WHERE sol_in IS NOT NULL \
+           UNION ALL SELECT exit_order_id FROM trades WHERE sol_in IS NOT NULL AND exit_order_id IS NOT NULL \
+         ) orders \
+         WHERE NOT EXISTS (SELECT 1 FROM balance_ledger l WHERE l.client_order_id = orders.order_id)",
+    )
+    .fetch_all(pool)
+    .await?;
+    found.extend(
+        unrecorded
+            .into_iter()
+            .map(|(order_id,)| format!("order {} has a trade but no ledger entry", order_id)),
+    );
+    Ok(found)
+}
+
+/// Each entry's balance must follow from the previous one in its chain, and the SOL in open
+/// positions at the end of each strategy's latest chain must match its still-open trades
+fn violations(rows: &[Row], open_trades: &[(String, f64)]) -> Vec<String> {
+    let mut found = Vec::new();
+    // Per strategy: the previous entry and the orders bought in the current chain
+    let mut chains: HashMap<&str, (&Row, Vec<&str>)> = HashMap::new();
+    for row in rows {
+        let chain = chains.get_mut(row.strategy_id.as_str());
+        match chain {
+            Some((prev, bought)) if row.kind != "start" => {
+                let expected = prev.balance_sol + row.delta_sol;
+                if (row.balance_sol - expected).abs() > EPSILON_SOL {
+                    found.push(format!(
+                        "{}: ledger entry {} has balance {:.6} SOL, expected {:.6} SOL",
+                        row.strategy_id, row.id, row.balance_sol, expected
+                    ));
+                }
+                if row.kind == "buy"
+                    && let Some(order_id) = &row.client_order_id
+                {
+                    bought.push(order_id);
+                }
+                *prev = row;
+            }
+            _ => {
+                chains.insert(&row.strategy_id, (row, Vec::new()));
+            }
+        }
+    }
+
+    for (strategy_id, (last, bought)) in chains {
+        let open: f64 = open_trades
+            .iter()
+            .filter(|(order_id, _)| bought.contains(&order_id.as_str()))
+            .map(|(_, sol_in)| sol_in)
+            .sum();
+        if (last.open_sol - open).abs() > EPSILON_SOL {
+            found.push(format!(
+                "{}: ledger has {:.6} SOL in open positions, open trades hold {:.6} SOL",
+                strategy_id, last.open_sol, open
+            ));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i32, kind: &str, order: Option<&str>, delta: f64, balance: f64, open: f64) -> Row {
+        Row {
+            id,
+            strategy_id: "default".into(),
+            kind: kind.into(),
+            client_order_id: order.map(Into::into),
+            delta_sol: delta,
+            balance_sol: balance,
+            open_sol: open,
+        }
+    }
+
+    #[test]
+    fn checks_balance_chains_against_open_trades() {
+        let rows = vec![
+            row(1, "start", None, 3.0, 3.0, 0.0),
+            row(2, "buy", Some("a"), -0.5, 2.5, 0.5),
+            row(3, "buy", Some("b"), -0.5, 2.0, 1.0),
+            row(4, "sell", Some("a-exit"), 0.8, 2.8, 0.5),
+        ];
+        let open = vec![("b".to_string(), 0.5)];
+        assert!(violations(&rows, &open).is_empty());
+
+        // A trade left open without its exit entry, and a balance that skips an entry
+        let open = vec![("a".to_string(), 0.5), ("b".to_string(), 0.5)];
+        assert_eq!(violations(&rows, &open).len(), 1);
+        let mut skipped = rows;
+        skipped[3].balance_sol = 3.3;
+        assert_eq!(violations(&skipped, &[("b".to_string(), 0.5)]).len(), 1);
+    }
+}
@@ -0,0 +1,8 @@
+//! Strategy, scoring, and backtest code shared by the bot and its Python bindings
+
+pub mod backtest;
+pub mod models;
+#[cfg(feature = "python")]
+mod python;
+pub mod strategy;
+pub mod strategy_config;
@@ -0,0 +1,147 @@
+//! Pauses a strategy's entries when the market turns against it: after
+//! `pause_after_stop_losses` stop-losses in a row, or once its equity falls
+//! `pause_drawdown_pct` below its high within the last `pause_window_secs`.
+//!
+//! A pause lasts `pause_cooldown_secs`, or until resumed through the API when that is 0.
+//! Open positions keep being managed while entries are paused.
+
+use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+/// Tracks one strategy's recent losses
+#[derive(Debug, Default)]
+pub struct LosingStreak {
+    stop_losses: u32,
+    /// Equity samples within the rolling window, oldest first
+    equity: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl LosingStreak {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an exit; returns why entries should pause when it completes a losing streak
+    pub fn record_exit(&mut self, reason: &str, config: &StrategyConfig) -> Option<String> {
+        if reason != "stop_loss" {
+            self.stop_losses = 0;
+            return None;
+        }
+        self.stop_losses += 1;
+        if config.pause_after_stop_losses == 0 || self.stop_losses < config.pause_after_stop_losses
+        {
+            return None;
+        }
+        let reason = format!("{} stop-losses in a row", self.stop_losses);
+        self.reset();
+        Some(reason)
+    }
+
+    /// Record the strategy's equity; returns why entries should pause when it has fallen too
+    /// far below its high within the window
+    pub fn record_equity(
+        &mut self,
+        equity_usd: f64,
+        now: DateTime<Utc>,
+        config: &StrategyConfig,
+    ) -> Option<String> {
+        if config.pause_drawdown_pct <= 0.0 {
+            return None;
+        }
+        let cutoff = now - Duration::seconds(config.pause_window_secs as i64);
+        while self.equity.front().is_some_and(|(at, _)| *at < cutoff) {
+            self.equity.pop_front();
+        }
+        self.equity.push_back((now, equity_usd));
+
+        let peak = self.equity.iter().map(|(_, e)| *e).fold(0.0, f64::max);
+        if peak <= 0.0 || (peak - equity_usd) / peak < config.pause_drawdown_pct {
+            return None;
+        }
+        let reason = format!(
+            "equity down {:.1}% from ${:.2} within {} min",
+            (peak - equity_usd) / peak * 100.0,
+            peak,
+            config.pause_window_secs / 60
+        );
+        self.reset();
+        Some(reason)
+    }
+
+    /// Stop-losses in a row still allowed before the pause; `None` when the rule is off
+    pub fn stop_losses_left(&self, config: &StrategyConfig) -> Option<u32> {
+        (config.pause_after_stop_losses > 0).then(|| {
+            config
+                .pause_after_stop_losses
+                .saturating_sub(self.stop_losses)
+        })
+    }
+
+    /// How much further equity may fall below its high in the window before the pause, as a
+    /// fraction of the high; `None` when the rule is off
+    pub fn drawdown_left_pct(&self, equity_usd: f64, config: &StrategyConfig) -> Option<f64> {
+        if config.pause_drawdown_pct <= 0.0 {
+            return None;
+        }
+        let peak = self
+            .equity
+            .iter()
+            .map(|(_, e)| *e)
+            .fold(equity_usd, f64::max);
+        if peak <= 0.0 {
+            return Some(config.pause_drawdown_pct);
+        }
+        Some((config.pause_drawdown_pct - (peak - equity_usd) / peak).max(0.0))
+    }
+
+    /// Start counting afresh, so a resumed strategy isn't paused again by the same losses
+    fn reset(&mut self) {
+        self.stop_losses = 0;
+        self.equity.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_on_stop_loss_streaks_and_drawdowns() {
+        let config = StrategyConfig {
+            pause_after_stop_losses: 3,
+            pause_drawdown_pct: 0.2,
+            pause_window_secs: 600,
+            ..StrategyConfig::default()
+        };
+        let mut streak = LosingStreak::new();
+
+        // A take-profit in between breaks the streak
+        assert_eq!(streak.record_exit("stop_loss", &config), None);
+        assert_eq!(streak.record_exit("stop_loss", &config), None);
+        assert_eq!(streak.record_exit("take_profit", &config), None);
+        assert_eq!(streak.record_exit("stop_loss", &config), None);
+        assert_eq!(streak.record_exit("stop_loss", &config), None);
+        assert_eq!(streak.stop_losses_left(&config), Some(1));
+        assert_eq!(
+            streak.record_exit("stop_loss", &config).as_deref(),
+            Some("3 stop-losses in a row")
+        );
+        assert_eq!(streak.record_exit("stop_loss", &config), None);
+
+        // A slow decline spread beyond the window doesn't count
+        let start = Utc::now();
+        let at = |mins: i64| start + Duration::minutes(mins);
+        assert_eq!(streak.record_equity(100.0, at(0), &config), None);
+        assert_eq!(streak.record_equity(90.0, at(8), &config), None);
+        assert_eq!(streak.record_equity(82.0, at(16), &config), None);
+        // 82 is 8.9% below the 90 high, 11.1% short of the pause
+        let left = streak.drawdown_left_pct(82.0, &config).unwrap();
+        assert!((left - 0.111).abs() < 0.001);
+        assert_eq!(
+            streak.record_equity(70.0, at(18), &config).as_deref(),
+            Some("equity down 22.2% from $90.00 within 10 min")
+        );
+        assert_eq!(streak.record_equity(65.0, at(19), &config), None);
+    }
+}
@@ -1,40 +1,843 @@
+mod alert_rules;
+mod alerts;
+mod analyze;
+mod annotations;
+mod api;
+mod approvals;
+mod auth;
+mod backfill;
+mod backtest_cache;
+mod blacklist;
+mod buy_template;
+mod cache;
+mod cluster;
 mod config;
+mod control;
+mod cooldown;
 mod db;
-mod models;
+mod degrade;
+mod dev_wallets;
+mod digest;
+mod event_bus;
+mod executor;
+mod funding;
+mod grpc;
+mod health;
+mod holders;
+mod latency;
+mod leases;
+mod ledger;
+mod losing_streak;
+mod memory_store;
+mod message;
+mod mint_watch;
+#[cfg(test)]
+mod mock_scanner;
+mod notifier;
+mod orders;
+mod outcomes;
+mod plugin;
+mod portfolio_manager;
+mod pumpportal;
+mod queue;
+mod reconcile;
+mod reentry;
+mod regime;
+mod relay;
+mod replay;
+mod repo;
+mod report;
+mod retention;
+mod risk;
+mod run_metadata;
+mod sandwich;
 mod scanner;
+mod schedule;
+mod score_model;
+mod score_test;
+mod signals;
+mod signer;
 mod simulator;
-mod strategy;
-mod strategy_config;
+mod storage;
+mod tax;
+mod timescale;
+mod token_accounts;
+mod volume_spike;
+mod wallets;
+mod watch;
+mod watchlist;
+mod webhook;
+mod write_behind;
 
 use crate::config::Config;
 use crate::db::{connect, ensure_migrations};
 use anyhow::Result;
+use solana_sniper_bot::{models, strategy, strategy_config};
 use structopt::StructOpt;
 
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Print PnL, win rate, and hold time per strategy, preset, and source
+    Report {
+        /// instead, print how often each entry filter rejected tokens that went on to 2x,
+        /// and passed tokens that didn't
+        #[structopt(long)]
+        filters: bool,
+        /// only count trades tagged with this, themselves or through their token
+        #[structopt(long)]
+        tag: Option<String>,
+        /// only count trades opened, and tokens observed, by this run (a `run_metadata` id)
+        #[structopt(long)]
+        run: Option<i32>,
+    },
+    /// Enrich and score one token, print the score breakdown and entry decision, and exit
+    Analyze {
+        /// Token mint address
+        mint: String,
+        /// funders to trace back from each top holder when clustering them
+        #[structopt(long, default_value = "2")]
+        funding_hops: usize,
+    },
+    /// Attach notes and tags to trades and tokens for reviewing the journal
+    Annotate(annotations::AnnotateCommand),
+    /// Manage the wallet and mint blacklists
+    Blacklist(blacklist::BlacklistCommand),
+    /// Manage the mints tracked with price snapshots and alerts, held or not
+    Watchlist(watchlist::WatchlistCommand),
+    /// Print how a token's top 20 holders changed across their recorded snapshots
+    Holders {
+        /// Token mint address
+        mint: String,
+    },
+    /// Stream scored detections without trading or writing to the database
+    Watch {
+        /// print one JSON object per token instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Print the last 24 hours' digest: scans, buys, exits, PnL, and open positions
+    Digest {
+        /// also send it to the configured Telegram and Discord channels
+        #[structopt(long)]
+        send: bool,
+    },
+    /// Download historical pump.fun launches and their prices for backtests
+    Backfill {
+        /// days of launches to cover, counting back from now
+        #[structopt(long, default_value = "7")]
+        days: u32,
+        /// maximum launches to download
+        #[structopt(long, default_value = "1000")]
+        limit: usize,
+        /// minutes of prices to download after each launch
+        #[structopt(long, default_value = "60")]
+        track_mins: u32,
+        /// write stored tokens with price history from the last --days to this file as
+        /// backtest JSON instead of downloading
+        #[structopt(long)]
+        export: Option<storage::Location>,
+    },
+    /// Backtest each strategy over a backtest file, reusing cached results of identical runs
+    Backtest {
+        /// backtest JSON, as written by `backfill --export`
+        file: storage::Location,
+    },
+    /// Play a backtest file back through each strategy, keeping its recorded timing
+    Replay {
+        /// backtest JSON, as written by `backfill --export`
+        file: storage::Location,
+        /// how many times faster than recorded to play; 0 plays without waiting
+        #[structopt(long, default_value = "60")]
+        speed: f64,
+    },
+    /// Train the score model on labeled outcomes and store it as a new version
+    Train {
+        /// labeled tokens required to train
+        #[structopt(long, default_value = "200")]
+        min_samples: usize,
+        /// make the new version the one the bot scores with
+        #[structopt(long)]
+        activate: bool,
+    },
+    /// Rescore the golden scoring fixtures in tests/data/scoring and rewrite any that changed
+    ScoreTest {
+        /// only report changes, and fail if there are any
+        #[structopt(long)]
+        check: bool,
+    },
+    /// Request a devnet airdrop to the trading wallet
+    Airdrop {
+        /// SOL to request
+        #[structopt(long, default_value = "1")]
+        sol: f64,
+    },
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "sol-memebot")]
 struct Opt {
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+
     /// simulated minutes to run
     #[structopt(long, default_value = "60")]
     minutes: u64,
+
+    /// strategies to run side by side as [id=]profile[:budget_sol], comma-separated
+    /// (e.g. early_snipe:1.5,safe=conservative:1.5)
+    #[structopt(long)]
+    strategies: Option<String>,
+
+    /// run a single named profile (shorthand for --strategies NAME)
+    #[structopt(long, conflicts_with = "strategies")]
+    profile: Option<String>,
+
+    /// TOML file with [profiles.NAME] tables (overrides PROFILES_FILE)
+    #[structopt(long)]
+    profiles: Option<String>,
+
+    /// override a strategy parameter for this run as [strategy_id.]key=value (repeatable)
+    #[structopt(long = "set", number_of_values = 1)]
+    set: Vec<String>,
+
+    /// strategy ids from --strategies to run in shadow mode, trading hypothetically
+    /// alongside the live ones (comma-separated)
+    #[structopt(long)]
+    shadow: Option<String>,
+
+    /// seconds between PumpPortal polls (overrides POLL_INTERVAL_SECS)
+    #[structopt(long)]
+    poll_interval_secs: Option<u64>,
+
+    /// seconds between passes of the exit engine over open positions (overrides
+    /// EXIT_INTERVAL_SECS)
+    #[structopt(long)]
+    exit_interval_secs: Option<u64>,
+
+    /// seconds to listen on the WebSocket per poll (overrides LISTEN_WINDOW_SECS)
+    #[structopt(long)]
+    listen_window_secs: Option<u64>,
+
+    /// listings enriched concurrently (overrides SCAN_CONCURRENCY)
+    #[structopt(long)]
+    scan_concurrency: Option<usize>,
+
+    /// cluster live transactions are sent to: mainnet or devnet (overrides CLUSTER)
+    #[structopt(long)]
+    cluster: Option<cluster::Cluster>,
+
+    /// run as a daemon, trading only inside this UTC schedule, e.g. "mon-fri 13:00-21:00"
+    /// (overrides SCHEDULE; --minutes is ignored)
+    #[structopt(long)]
+    schedule: Option<schedule::Schedule>,
+
+    /// sign and send real transactions instead of simulating fills
+    #[structopt(long)]
+    live: bool,
+
+    /// read external buy signals from stdin (`MINT [CONFIDENCE]` or JSON per line)
+    #[structopt(long)]
+    signals_stdin: bool,
+
+    /// watch graduated tokens for volume spikes and trade them as `volume_spike` signals
+    /// (requires BIRDEYE_API_KEY)
+    #[structopt(long)]
+    volume_spikes: bool,
+
+    /// run without Postgres, keeping events and trades in memory; subcommands, `--live`, the
+    /// score model, and the background jobs that read the database are unavailable
+    #[structopt(long, conflicts_with = "live")]
+    no_db: bool,
+
+    /// with --no-db, write everything recorded to this JSON file (or `s3://bucket/key`) when
+    /// the run ends
+    #[structopt(long, requires = "no-db")]
+    dump: Option<storage::Location>,
+
+    /// WASM scoring plugin (overrides SCORING_PLUGIN)
+    #[structopt(long)]
+    scoring_plugin: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Variables already set in the environment take precedence over .env
+    dotenvy::dotenv().ok();
     env_logger::init();
     let opt = Opt::from_args();
-    let cfg = Config::from_env();
+    let mut cfg = Config::from_env();
+    if let Some(v) = opt.poll_interval_secs {
+        cfg.poll_interval_secs = v;
+    }
+    if let Some(v) = opt.exit_interval_secs {
+        cfg.exit_interval_secs = v;
+    }
+    if let Some(v) = opt.listen_window_secs {
+        cfg.listen_window_secs = v;
+    }
+    if let Some(v) = opt.scan_concurrency {
+        cfg.scan_concurrency = v;
+    }
+    if let Some(v) = opt.cluster {
+        cfg.cluster = v;
+    }
+    if let Some(v) = opt.scoring_plugin.clone() {
+        cfg.scoring_plugin = Some(v);
+    }
+    if let Some(v) = opt.profiles.clone() {
+        cfg.profiles_file = Some(v);
+    }
+    if let Some(v) = opt.schedule.clone() {
+        cfg.schedule = Some(v);
+    }
+    cfg.param_overrides = opt.set.clone();
+
+    let needs = match &opt.cmd {
+        Some(Command::Analyze { .. } | Command::Watch { .. }) => config::Requirements {
+            market_rpc: true,
+            ..Default::default()
+        },
+        Some(Command::Replay { .. } | Command::ScoreTest { .. }) => config::Requirements::default(),
+        Some(
+            Command::Report { .. }
+            | Command::Annotate(_)
+            | Command::Blacklist(_)
+            | Command::Watchlist(_)
+            | Command::Holders { .. }
+            | Command::Digest { .. }
+            | Command::Backfill { .. }
+            | Command::Backtest { .. }
+            | Command::Train { .. },
+        ) => config::Requirements {
+            database: true,
+            ..Default::default()
+        },
+        Some(Command::Airdrop { .. }) => config::Requirements {
+            database: true,
+            signing: true,
+            ..Default::default()
+        },
+        None => config::Requirements {
+            database: !opt.no_db,
+            market_rpc: true,
+            signing: opt.live,
+        },
+    };
+    cfg.validate(needs).await?;
+
+    let mut scanner = scanner::Scanner::new(cfg.dexscreener_key.clone())
+        .with_listen_window(std::time::Duration::from_secs(cfg.listen_window_secs))
+        .with_cache_ttl(std::time::Duration::from_secs(cfg.token_cache_ttl_secs));
+    if let Some(Command::Watch { json: true }) = opt.cmd {
+        scanner = scanner.with_logs_to_stderr();
+    }
+    let profiles = cfg.load_profiles()?;
+    let specs = opt
+        .profile
+        .as_deref()
+        .or(opt.strategies.as_deref())
+        .unwrap_or("default");
+    let mut manager = portfolio_manager::PortfolioManager::from_specs(specs, &profiles, &cfg)?;
+    if let Some(ids) = &opt.shadow {
+        manager.set_shadow(ids)?;
+        println!("Shadow strategies (no real trades): {}", ids);
+    }
+    if let Some(path) = &cfg.scoring_plugin {
+        manager.load_plugin(&plugin::PluginModule::load(path)?)?;
+        println!("Scoring with plugin {}", path);
+    }
+
+    let storage = storage::Storage::new(cfg.s3_credentials.clone());
+
+    // Analysis, watching, and replays need no database
+    match &opt.cmd {
+        Some(Command::Analyze { mint, funding_hops }) => {
+            // Only for the creator's history, so analysis works without a reachable database
+            let pool = tokio::time::timeout(
+                std::time::Duration::from_secs(5),
+                connect(&cfg.database_url),
+            )
+            .await
+            .ok()
+            .and_then(Result::ok);
+            let funding = funding::Tracer::new(*funding_hops, &cfg.funding_ignore);
+            return analyze::run(&scanner, mint, &mut manager, pool.as_ref(), &funding).await;
+        }
+        Some(Command::Watch { json }) => {
+            return watch::run(&scanner, &cfg, &mut manager, *json).await;
+        }
+        Some(Command::Replay { file, speed }) => {
+            return replay::run(file, *speed, &manager, &storage).await;
+        }
+        Some(Command::ScoreTest { check }) => {
+            return score_test::run(std::path::Path::new(score_test::FIXTURES_DIR), *check);
+        }
+        _ => {}
+    }
+
+    // `--no-db` runs keep what they record in memory
+    let pool = if opt.no_db {
+        if opt.cmd.is_some() {
+            anyhow::bail!("only trading runs work with --no-db; subcommands need the database");
+        }
+        None
+    } else {
+        let pool = connect(&cfg.database_url).await?;
+        ensure_migrations(&pool).await.expect("migrations failed");
+        Some(pool)
+    };
+    let hypertables = match &pool {
+        Some(pool) => {
+            timescale::setup(pool, cfg.timescale, cfg.timescale_compress_after_hours).await?
+        }
+        None => false,
+    };
+
+    if let Some(pool) = &pool {
+        match opt.cmd {
+            Some(Command::Report {
+                filters: false,
+                tag,
+                run,
+            }) => return report::print_attribution(pool, tag.as_deref(), run).await,
+            Some(Command::Report {
+                filters: true,
+                tag,
+                run,
+            }) => {
+                return report::print_filter_effectiveness(pool, &manager, tag.as_deref(), run)
+                    .await;
+            }
+            Some(Command::Annotate(cmd)) => return annotations::run(pool, cmd).await,
+            Some(Command::Blacklist(cmd)) => return blacklist::run(pool, cmd, &storage).await,
+            Some(Command::Watchlist(cmd)) => return watchlist::run(pool, cmd).await,
+            Some(Command::Holders { mint }) => return holders::report(pool, &mint).await,
+            Some(Command::Digest { send }) => {
+                let digest = digest::Digest::load(pool, chrono::Duration::hours(24)).await?;
+                let text = digest.render();
+                print!("{}", text);
+                if send {
+                    let Some(notifier) = notifier::Notifier::from_config(&cfg) else {
+                        anyhow::bail!(
+                            "set TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID, or DISCORD_WEBHOOK_URL"
+                        );
+                    };
+                    notifier.send(&text).await;
+                }
+                return Ok(());
+            }
+            Some(Command::Backfill {
+                days,
+                limit,
+                track_mins,
+                export,
+            }) => {
+                let since = chrono::Utc::now() - chrono::Duration::days(days as i64);
+                if let Some(out) = export {
+                    let written = backfill::export(pool, since, &out, &storage).await?;
+                    println!("Wrote {} tokens to {}", written, out);
+                    return Ok(());
+                }
+                let (Some(bitquery), Some(birdeye)) =
+                    (cfg.bitquery_api_key.clone(), cfg.birdeye_api_key.clone())
+                else {
+                    anyhow::bail!("set BITQUERY_API_KEY and BIRDEYE_API_KEY to backfill");
+                };
+                let track = chrono::Duration::minutes(track_mins as i64);
+                return backfill::Backfill::new(bitquery, birdeye)
+                    .run(pool, since, limit, track)
+                    .await;
+            }
+            Some(Command::Train {
+                min_samples,
+                activate,
+            }) => return score_model::train(pool, min_samples, activate).await,
+            Some(Command::Backtest { file }) => {
+                return backtest_cache::run(pool, &file, &manager, &storage).await;
+            }
+            Some(Command::Airdrop { sol }) => {
+                for executor in live_wallets(&cfg, &manager, pool).await?.iter() {
+                    let sig = executor.request_airdrop(sol).await?;
+                    println!(
+                        "Airdropped {} SOL to {} ({}), balance now {} SOL",
+                        sol,
+                        executor.pubkey(),
+                        cfg.cluster.explorer_tx_url(&sig),
+                        executor.balance_sol().await?
+                    );
+                }
+                return Ok(());
+            }
+            Some(
+                Command::Analyze { .. }
+                | Command::Watch { .. }
+                | Command::Replay { .. }
+                | Command::ScoreTest { .. },
+            )
+            | None => {}
+        }
+    }
+
+    if cfg.score_model_weight > 0.0 {
+        let Some(pool) = &pool else {
+            anyhow::bail!("SCORE_MODEL_WEIGHT needs the database models are stored in");
+        };
+        let Some(model) = score_model::ScoreModel::load_active(pool).await? else {
+            anyhow::bail!(
+                "SCORE_MODEL_WEIGHT is set but no model is active; run `train --activate`"
+            );
+        };
+        println!(
+            "Scoring with model v{} at weight {}",
+            model.version, cfg.score_model_weight
+        );
+        manager.load_model(std::sync::Arc::new(model), cfg.score_model_weight);
+    }
+
+    // Report inconsistencies left by earlier runs, then start a new ledger chain per strategy
+    let store = match &pool {
+        Some(pool) => {
+            for violation in ledger::check(pool).await? {
+                println!("[ledger] invariant violated: {}", violation);
+            }
+            repo::Store::Postgres(pool.clone())
+        }
+        None => {
+            println!("Keeping events and trades in memory (--no-db)");
+            repo::Store::Memory(std::sync::Arc::new(memory_store::MemoryStore::new()))
+        }
+    };
+    for slot in &manager.slots {
+        store
+            .record_ledger(&ledger::Entry {
+                strategy_id: &slot.id,
+                kind: "start",
+                order_id: None,
+                delta_sol: slot.portfolio.sol_balance,
+                balance_sol: slot.portfolio.sol_balance,
+                open_sol: 0.0,
+            })
+            .await?;
+    }
+
+    // `--live` conflicts with `--no-db`
+    let wallets = if let Some(pool) = pool.as_ref().filter(|_| opt.live) {
+        let wallets = live_wallets(&cfg, &manager, pool).await?;
+        println!(
+            "Live trading on {} with {} wallet(s), {}",
+            cfg.cluster.as_str(),
+            cfg.keypair_paths.len(),
+            wallets.assignment().as_str()
+        );
+        for executor in wallets.iter() {
+            println!(
+                "  {} ({} SOL)",
+                executor.pubkey(),
+                executor.balance_sol().await?
+            );
+            executor.prewarm();
+            // Devnet trades are memos and never hold tokens
+            if cfg.cluster == cluster::Cluster::Mainnet {
+                reconcile::reconcile_wallet(pool, executor).await?;
+            }
+        }
+        Some(wallets)
+    } else {
+        None
+    };
 
-    let pool = connect(&cfg.database_url).await?;
-    ensure_migrations(&pool).await.expect("migrations failed");
+    // Instances sharing the database coordinate through leases
+    let leases = match &pool {
+        Some(pool) if cfg.lease_ttl_secs > 0 => {
+            let leases = std::sync::Arc::new(leases::Leases::new(
+                pool.clone(),
+                cfg.instance_id.clone(),
+                std::time::Duration::from_secs(cfg.lease_ttl_secs),
+            ));
+            println!("Holding leases as {}", cfg.instance_id);
+            let heartbeat = leases.clone();
+            tokio::spawn(async move { heartbeat.heartbeat().await });
+            Some(leases)
+        }
+        _ => None,
+    };
 
-    let scanner = scanner::Scanner::new(cfg.dexscreener_key.clone());
+    let snapshot = api::SharedSnapshot::default();
+    let queue_counters = std::sync::Arc::new(queue::QueueCounters::default());
+    let latency = std::sync::Arc::new(latency::LatencyStats::default());
+    let degradation = std::sync::Arc::new(
+        degrade::Degradation::new(cfg.degrade_throttle_threshold, cfg.degrade_recover_polls)
+            .with_api_errors(scanner.api_errors()),
+    );
+    let health = std::sync::Arc::new(health::HealthState::new(
+        &cfg.health_critical,
+        cfg.health_failure_threshold,
+    ));
+    let (signal_tx, mut signal_rx) = signals::channel();
+    let mut approvals = approvals::Approvals::new(
+        cfg.approval_threshold_sol,
+        std::time::Duration::from_secs(cfg.approval_timeout_secs),
+    );
+    if cfg.approval_threshold_sol.is_some()
+        && let (Some(token), Some(chat_id)) = (&cfg.telegram_bot_token, &cfg.telegram_chat_id)
+    {
+        approvals = approvals.with_telegram(token.clone(), chat_id.clone());
+    }
+    let control =
+        std::sync::Arc::new(control::ControlState::new(&manager).with_approvals(approvals));
+    if control.approvals().telegram().is_some() {
+        let control = control.clone();
+        tokio::spawn(async move {
+            if let Some(telegram) = control.approvals().telegram() {
+                telegram.run(control.approvals()).await;
+            }
+        });
+    }
+    if let Some(addr) = cfg.api_addr.clone() {
+        let snapshot = snapshot.clone();
+        let queue_counters = queue_counters.clone();
+        let latency = latency.clone();
+        let degradation = degradation.clone();
+        let health = health.clone();
+        let signal_tx = signal_tx.clone();
+        let control = control.clone();
+        let pool = pool.clone();
+        let keys = cfg.api_keys.clone();
+        tokio::spawn(async move {
+            if let Err(e) = api::serve(
+                &addr,
+                snapshot,
+                queue_counters,
+                latency,
+                degradation,
+                health,
+                signal_tx,
+                control,
+                pool,
+                keys,
+            )
+            .await
+            {
+                println!("API server stopped: {}", e);
+            }
+        });
+    }
+
+    if opt.signals_stdin {
+        tokio::spawn(signals::read_stdin(signal_tx.clone()));
+    }
+    if opt.volume_spikes {
+        let Some(key) = cfg.birdeye_api_key.clone() else {
+            anyhow::bail!("set BIRDEYE_API_KEY to watch for volume spikes");
+        };
+        let Some(pool) = &pool else {
+            anyhow::bail!("--volume-spikes picks the tokens it watches from the database");
+        };
+        let detector = volume_spike::SpikeDetector::new(pool.clone(), &cfg, key, signal_tx.clone());
+        tokio::spawn(detector.run());
+    }
+
+    if cfg.health_check_interval_secs > 0 {
+        let supervisor = health::HealthSupervisor::new(
+            scanner.clone(),
+            pool.clone(),
+            health,
+            control.clone(),
+            std::time::Duration::from_secs(cfg.health_check_interval_secs),
+        );
+        tokio::spawn(supervisor.run());
+    }
+    if let Some(addr) = cfg.grpc_addr.clone() {
+        let control = control.clone();
+        let snapshot = snapshot.clone();
+        let keys = cfg.api_keys.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(&addr, control, snapshot, keys).await {
+                println!("gRPC server stopped: {}", e);
+            }
+        });
+    }
+    if let Some(url) = &cfg.event_bus_url {
+        let publisher = event_bus::EventBusPublisher::connect(url, &cfg.event_bus_prefix).await?;
+        println!("Publishing events to {}", url);
+        tokio::spawn(publisher.run(control.subscribe()));
+    }
+    if !cfg.webhook_urls.is_empty() {
+        let notifier = webhook::WebhookNotifier::new(
+            cfg.webhook_urls.clone(),
+            cfg.webhook_secret.clone(),
+            cfg.webhook_events.clone(),
+            cfg.webhook_rug_loss_pct,
+        );
+        println!("Sending webhooks to {} URL(s)", cfg.webhook_urls.len());
+        tokio::spawn(notifier.run(control.subscribe()));
+    }
+
+    let notifier = notifier::Notifier::from_config(&cfg).map(std::sync::Arc::new);
+    if let Some(notifier) = &notifier
+        && let Some(pool) = &pool
+    {
+        let digest =
+            digest::DigestNotifier::new(notifier.clone(), pool.clone(), cfg.digest_hour_utc)
+                .with_api_errors(scanner.api_errors());
+        println!(
+            "Sending a daily digest at {:02}:00 UTC",
+            cfg.digest_hour_utc
+        );
+        leases::spawn_singleton(leases.as_ref(), "the daily digest", move || {
+            digest.clone().run()
+        });
+    }
+    let write_counters = std::sync::Arc::new(write_behind::WriteCounters::default());
+    if cfg.alert_interval_secs > 0 {
+        let watchdog = alerts::Watchdog::new(
+            alerts::AlertRules::from_config(&cfg),
+            scanner.ws_counters(),
+            write_counters.clone(),
+            snapshot.clone(),
+            control.clone(),
+            notifier.clone(),
+            std::time::Duration::from_secs(cfg.alert_interval_secs),
+        );
+        tokio::spawn(watchdog.run());
+    }
+
+    if cfg.outcome_delay_hours > 0
+        && let Some(pool) = &pool
+    {
+        let labeler = outcomes::Labeler::new(
+            pool.clone(),
+            scanner.clone(),
+            chrono::Duration::hours(cfg.outcome_delay_hours as i64),
+            hypertables,
+        );
+        leases::spawn_singleton(leases.as_ref(), "outcome labeling", move || {
+            labeler.clone().run()
+        });
+    }
+    if cfg.watchlist_interval_secs > 0
+        && let Some(pool) = &pool
+    {
+        let tracker = watchlist::Tracker::new(
+            pool.clone(),
+            scanner.clone(),
+            notifier.clone(),
+            std::time::Duration::from_secs(cfg.watchlist_interval_secs),
+        );
+        tokio::spawn(tracker.run());
+    }
+    if cfg.holder_snapshot_interval_secs > 0
+        && let Some(pool) = &pool
+    {
+        let snapshotter = holders::Snapshotter::new(
+            pool.clone(),
+            scanner.clone(),
+            std::time::Duration::from_secs(cfg.holder_snapshot_interval_secs),
+        );
+        leases::spawn_singleton(leases.as_ref(), "holder snapshots", move || {
+            snapshotter.clone().run()
+        });
+    }
+    if cfg.retention_days > 0
+        && let Some(pool) = &pool
+    {
+        println!(
+            "Archiving events, prices, and marks older than {} days to {}",
+            cfg.retention_days, cfg.archive_target
+        );
+        let retention = retention::Retention::new(
+            pool.clone(),
+            storage.clone(),
+            cfg.archive_target.clone(),
+            cfg.retention_days,
+        );
+        tokio::spawn(retention.run());
+    }
+
+    let queue = queue::ListingQueue::new(cfg.max_listing_age_secs, queue_counters);
+    let runtime = simulator::Runtime {
+        snapshot: &snapshot,
+        queue: &queue,
+        latency: &latency,
+        wallets: wallets.as_ref(),
+        control: &control,
+        write_counters: &write_counters,
+        leases: leases.as_deref(),
+        degradation: &degradation,
+    };
+    if let Some(schedule) = &cfg.schedule {
+        println!("Running as a daemon on schedule '{}'", schedule.as_str());
+        return simulator::run_scheduled(
+            &store,
+            &cfg,
+            schedule,
+            &scanner,
+            &mut manager,
+            &runtime,
+            Some(signal_rx),
+        )
+        .await;
+    }
 
     println!(
         "Running simulation for {} minutes (using real APIs)...",
         opt.minutes
     );
-    simulator::run_simulation(&pool, opt.minutes, &scanner).await?;
+    simulator::run_simulation(
+        &store,
+        &cfg,
+        std::time::Duration::from_secs(opt.minutes * 60),
+        &scanner,
+        &mut manager,
+        &runtime,
+        Some(&mut signal_rx),
+    )
+    .await?;
+
+    if let repo::Store::Memory(memory) = &store {
+        let (events, trades) = memory.counts();
+        match &opt.dump {
+            Some(out) => {
+                storage.put(out, memory.dump()?).await?;
+                println!(
+                    "Wrote {} token events and {} trades to {}",
+                    events, trades, out
+                );
+            }
+            None => println!(
+                "Discarding {} token events and {} trades kept in memory (--dump keeps them)",
+                events, trades
+            ),
+        }
+    }
 
     Ok(())
 }
+
+async fn live_wallets(
+    cfg: &Config,
+    manager: &portfolio_manager::PortfolioManager,
+    pool: &sqlx::PgPool,
+) -> Result<wallets::WalletPool> {
+    let mut executors = Vec::new();
+    for uri in &cfg.keypair_paths {
+        let mut executor = executor::LiveExecutor::new(
+            cfg.cluster,
+            cfg.cluster_rpc_url(),
+            signer::open(uri).await?,
+        )
+        .with_pool(pool.clone());
+        executor.load_lookup_tables(&cfg.lookup_tables).await?;
+        // Relays are attached even with plain RPC routes, for buys sent through Jito
+        executor = executor.with_relays(relay::RelayClient::new(
+            cfg.tx_routes.clone(),
+            cfg.jito_url.clone(),
+            cfg.bloxroute_url.clone(),
+            cfg.bloxroute_auth.clone(),
+            cfg.private_relay_url.clone(),
+        ));
+        executors.push(executor);
+    }
+    let strategy_ids = manager.slots.iter().map(|s| s.id.clone()).collect();
+    wallets::WalletPool::new(executors, cfg.wallet_assignment, strategy_ids)
+}
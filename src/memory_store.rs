@@ -0,0 +1,513 @@
+//! In-memory storage for `--no-db` runs: what the decision loop would write to Postgres is
+//! kept in the process instead, and can be dumped as JSON when the run ends
+//!
+//! Rows follow the tables they stand in for (`run_metadata`, `token_events`, `trades`,
+//! `orders`, `position_marks`, `price_history`, `balance_ledger`, `profit_sweeps`), with
+//! the same rules for repeated writes: every observation of a token is kept, an order
+//! recorded once is not recorded again, only a trade's first exit is kept, and orders move
+//! only as their status allows.
+//! Everything is lost when the process exits.
+
+use crate::ledger;
+use crate::orders::OrderStatus;
+use crate::repo::{NewOrder, NewTrade, OrderUpdate, TradeExit, TradeSlice};
+use crate::strategy::TokenEvent;
+use crate::write_behind::{EventRow, MarkRow};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Debug, Serialize)]
+struct Run {
+    id: i32,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    config: Value,
+    events_seen: Option<usize>,
+    trades_made: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    event_id: i64,
+    generated_at: DateTime<Utc>,
+    score: f64,
+    run_id: Option<i32>,
+    #[serde(flatten)]
+    event: TokenEvent,
+}
+
+#[derive(Debug, Serialize)]
+struct Trade {
+    token_id: String,
+    strategy_id: String,
+    run_id: Option<i32>,
+    preset: String,
+    source: String,
+    entry_price: f64,
+    qty: f64,
+    usd_in: f64,
+    sol_in: f64,
+    score: f64,
+    is_reentry: bool,
+    shadow: bool,
+    launch_hour: i16,
+    entry_type: String,
+    entry_slices: i32,
+    wallet: Option<String>,
+    entry_signature: Option<String>,
+    client_order_id: String,
+    opened_at: DateTime<Utc>,
+    exit_price: Option<f64>,
+    pnl: Option<f64>,
+    exit_reason: Option<String>,
+    exit_signature: Option<String>,
+    exit_order_id: Option<String>,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Order {
+    client_order_id: String,
+    strategy_id: String,
+    run_id: Option<i32>,
+    token_id: String,
+    side: String,
+    order_type: String,
+    status: OrderStatus,
+    sol_amount: Option<f64>,
+    filled_sol: f64,
+    signature: Option<String>,
+    error: Option<String>,
+    shadow: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct Mark {
+    strategy_id: String,
+    run_id: Option<i32>,
+    token_id: String,
+    marked_at: DateTime<Utc>,
+    price_usd: f64,
+    value_usd: f64,
+    unrealized_pnl: f64,
+    equity_usd: f64,
+    drawdown_pct: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Price {
+    token_id: String,
+    observed_at: DateTime<Utc>,
+    price_usd: f64,
+    source: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LedgerEntry {
+    strategy_id: String,
+    kind: String,
+    client_order_id: Option<String>,
+    delta_sol: f64,
+    balance_sol: f64,
+    open_sol: f64,
+    recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct Sweep {
+    strategy_id: String,
+    sol: f64,
+    destination: String,
+    signature: Option<String>,
+    swept_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Tables {
+    runs: Vec<Run>,
+    token_events: Vec<Event>,
+    trades: Vec<Trade>,
+    orders: Vec<Order>,
+    position_marks: Vec<Mark>,
+    price_history: Vec<Price>,
+    balance_ledger: Vec<LedgerEntry>,
+    profit_sweeps: Vec<Sweep>,
+    #[serde(skip)]
+    ledger_orders: HashSet<String>,
+}
+
+/// Wall-clock time at which something that happened at `at` happened
+fn wall_time(at: Instant) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::from_std(at.elapsed()).unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_run(&self, config: Value) -> i32 {
+        let mut tables = self.tables.lock().unwrap();
+        let id = tables.runs.len() as i32 + 1;
+        tables.runs.push(Run {
+            id,
+            started_at: Utc::now(),
+            finished_at: None,
+            config,
+            events_seen: None,
+            trades_made: None,
+        });
+        id
+    }
+
+    pub fn finish_run(&self, id: i32, events_seen: usize, trades_made: usize) {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(run) = tables.runs.iter_mut().find(|r| r.id == id) {
+            run.finished_at = Some(Utc::now());
+            run.events_seen = Some(events_seen);
+            run.trades_made = Some(trades_made);
+        }
+    }
+
+    pub fn insert_events(&self, rows: &[EventRow]) {
+        let mut tables = self.tables.lock().unwrap();
+        let first_id = tables.token_events.len() as i64 + 1;
+        tables
+            .token_events
+            .extend(rows.iter().zip(first_id..).map(|(row, event_id)| Event {
+                event_id,
+                generated_at: wall_time(row.queued_at),
+                score: row.score,
+                run_id: row.run_id,
+                event: row.event.clone(),
+            }));
+    }
+
+    pub fn insert_marks(&self, rows: &[MarkRow]) {
+        let mut tables = self.tables.lock().unwrap();
+        tables.position_marks.extend(rows.iter().map(|row| Mark {
+            strategy_id: row.strategy_id.clone(),
+            run_id: row.run_id,
+            token_id: row.token_id.clone(),
+            marked_at: wall_time(row.queued_at),
+            price_usd: row.price_usd,
+            value_usd: row.value_usd,
+            unrealized_pnl: row.unrealized_pnl,
+            equity_usd: row.equity_usd,
+            drawdown_pct: row.drawdown_pct,
+        }));
+    }
+
+    pub fn record_price(&self, token_id: &str, price_usd: f64, source: &str) {
+        self.tables.lock().unwrap().price_history.push(Price {
+            token_id: token_id.to_string(),
+            observed_at: Utc::now(),
+            price_usd,
+            source: source.to_string(),
+        });
+    }
+
+    pub fn open_trade(&self, trade: &NewTrade<'_>, entry: &ledger::Entry<'_>) {
+        let mut tables = self.tables.lock().unwrap();
+        if tables
+            .trades
+            .iter()
+            .all(|t| t.client_order_id != trade.client_order_id)
+        {
+            tables.trades.push(Trade {
+                token_id: trade.token_id.to_string(),
+                strategy_id: trade.strategy_id.to_string(),
+                run_id: trade.run_id,
+                preset: trade.preset.to_string(),
+                source: trade.source.to_string(),
+                entry_price: trade.entry_price,
+                qty: trade.qty,
+                usd_in: trade.usd_in,
+                sol_in: trade.sol_in,
+                score: trade.score,
+                is_reentry: trade.is_reentry,
+                shadow: trade.shadow,
+                launch_hour: trade.launch_hour,
+                entry_type: trade.entry_type.to_string(),
+                entry_slices: 1,
+                wallet: trade.wallet.map(str::to_string),
+                entry_signature: trade.entry_signature.map(str::to_string),
+                client_order_id: trade.client_order_id.to_string(),
+                opened_at: Utc::now(),
+                exit_price: None,
+                pnl: None,
+                exit_reason: None,
+                exit_signature: None,
+                exit_order_id: None,
+                closed_at: None,
+            });
+        }
+        tables.record_ledger(entry);
+    }
+
+    pub fn add_slice(&self, slice: &TradeSlice<'_>, entry: &ledger::Entry<'_>) {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(trade) = tables.trades.iter_mut().find(|t| {
+            t.client_order_id == slice.client_order_id
+                && t.exit_order_id.is_none()
+                && t.entry_slices < slice.slice
+        }) {
+            trade.qty += slice.qty;
+            trade.usd_in += slice.usd_in;
+            trade.sol_in += slice.sol_in;
+            if trade.qty > 0.0 {
+                trade.entry_price = trade.usd_in / trade.qty;
+            }
+            trade.entry_slices = slice.slice;
+        }
+        tables.record_ledger(entry);
+    }
+
+    pub fn close_trade(&self, exit: &TradeExit<'_>, entry: &ledger::Entry<'_>) {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(trade) = tables
+            .trades
+            .iter_mut()
+            .find(|t| t.client_order_id == exit.client_order_id && t.exit_order_id.is_none())
+        {
+            trade.exit_price = Some(exit.exit_price);
+            trade.pnl = Some(exit.pnl);
+            trade.exit_reason = Some(exit.exit_reason.to_string());
+            trade.exit_signature = exit.exit_signature.map(str::to_string);
+            trade.exit_order_id = Some(exit.exit_order_id.to_string());
+            trade.closed_at = Some(Utc::now());
+        }
+        tables.record_ledger(entry);
+    }
+
+    pub fn open_order(&self, order: &NewOrder<'_>) {
+        let mut tables = self.tables.lock().unwrap();
+        let now = Utc::now();
+        if let Some(existing) = tables
+            .orders
+            .iter_mut()
+            .find(|o| o.client_order_id == order.client_order_id)
+        {
+            if existing.status == OrderStatus::Failed {
+                existing.status = OrderStatus::Pending;
+                existing.error = None;
+                existing.expires_at = order.expires_at;
+                existing.updated_at = now;
+            }
+            return;
+        }
+        tables.orders.push(Order {
+            client_order_id: order.client_order_id.to_string(),
+            strategy_id: order.strategy_id.to_string(),
+            run_id: order.run_id,
+            token_id: order.token_id.to_string(),
+            side: order.side.to_string(),
+            order_type: order.order_type.to_string(),
+            status: OrderStatus::Pending,
+            sol_amount: order.sol_amount,
+            filled_sol: 0.0,
+            signature: None,
+            error: None,
+            shadow: order.shadow,
+            created_at: now,
+            updated_at: now,
+            expires_at: order.expires_at,
+        });
+    }
+
+    /// Move an order on if its current status allows it; returns whether it moved
+    pub fn update_order(&self, update: &OrderUpdate<'_>) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let Some(order) = tables.orders.iter_mut().find(|o| {
+            o.client_order_id == update.client_order_id && update.status.can_follow(o.status)
+        }) else {
+            return false;
+        };
+        order.status = update.status;
+        order.filled_sol += update.filled_sol;
+        if let Some(signature) = update.signature {
+            order.signature = Some(signature.to_string());
+        }
+        order.error = update.error.map(str::to_string);
+        order.updated_at = Utc::now();
+        true
+    }
+
+    /// Close orders still open past their expiry, as `OrderRepo::expire` does
+    pub fn expire_orders(&self) -> u64 {
+        let mut tables = self.tables.lock().unwrap();
+        let now = Utc::now();
+        let mut expired = 0;
+        for order in tables
+            .orders
+            .iter_mut()
+            .filter(|o| o.status.is_open() && o.expires_at < now)
+        {
+            order.status = if order.status == OrderStatus::Submitted {
+                OrderStatus::Failed
+            } else {
+                OrderStatus::Cancelled
+            };
+            order.error = Some("timed out".to_string());
+            order.updated_at = now;
+            expired += 1;
+        }
+        expired
+    }
+
+    pub fn record_ledger(&self, entry: &ledger::Entry<'_>) {
+        self.tables.lock().unwrap().record_ledger(entry);
+    }
+
+    pub fn record_sweep(
+        &self,
+        strategy_id: &str,
+        sol: f64,
+        destination: &str,
+        signature: Option<&str>,
+    ) {
+        self.tables.lock().unwrap().profit_sweeps.push(Sweep {
+            strategy_id: strategy_id.to_string(),
+            sol,
+            destination: destination.to_string(),
+            signature: signature.map(str::to_string),
+            swept_at: Utc::now(),
+        });
+    }
+
+    /// Token events (every observation) and trades recorded so far
+    pub fn counts(&self) -> (usize, usize) {
+        let tables = self.tables.lock().unwrap();
+        (tables.token_events.len(), tables.trades.len())
+    }
+
+    /// Everything recorded, as JSON with one array per table
+    pub fn dump(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec_pretty(&*self.tables.lock().unwrap())
+    }
+}
+
+impl Tables {
+    fn record_ledger(&mut self, entry: &ledger::Entry<'_>) {
+        if let Some(order_id) = entry.order_id
+            && !self.ledger_orders.insert(order_id.to_string())
+        {
+            return;
+        }
+        self.balance_ledger.push(LedgerEntry {
+            strategy_id: entry.strategy_id.to_string(),
+            kind: entry.kind.to_string(),
+            client_order_id: entry.order_id.map(str::to_string),
+            delta_sol: entry.delta_sol,
+            balance_sol: entry.balance_sol,
+            open_sol: entry.open_sol,
+            recorded_at: Utc::now(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PumpFunListing;
+
+    #[test]
+    fn keeps_first_writes_like_the_tables() {
+        let store = MemoryStore::new();
+        let run = store.start_run(serde_json::json!({"minutes": 5}));
+
+        let event: TokenEvent = PumpFunListing {
+            token_address: "mint".to_string(),
+            ..Default::default()
+        }
+        .into();
+        let row = || EventRow {
+            event: event.clone(),
+            score: 70.0,
+            run_id: Some(run),
+            queued_at: Instant::now(),
+        };
+        // A token observed twice keeps both observations
+        store.insert_events(&[row(), row()]);
+
+        let trade = NewTrade {
+            token_id: "mint",
+            entry_price: 0.001,
+            qty: 1000.0,
+            usd_in: 1.0,
+            sol_in: 0.01,
+            score: 70.0,
+            is_reentry: false,
+            strategy_id: "default",
+            run_id: Some(run),
+            preset: "default",
+            source: "pumpfun",
+            entry_signature: None,
+            wallet: None,
+            client_order_id: "order-1",
+            shadow: false,
+            launch_hour: 12,
+            entry_type: "market",
+        };
+        let entry = |kind, order_id| ledger::Entry {
+            strategy_id: "default",
+            kind,
+            order_id: Some(order_id),
+            delta_sol: 0.0,
+            balance_sol: 1.0,
+            open_sol: 0.0,
+        };
+        store.open_trade(&trade, &entry("buy", "order-1"));
+        store.open_trade(&trade, &entry("buy", "order-1"));
+        let exit = |reason| TradeExit {
+            client_order_id: "order-1",
+            exit_price: 0.002,
+            pnl: 1.0,
+            exit_signature: None,
+            exit_order_id: "order-1-exit",
+            exit_reason: reason,
+        };
+        store.close_trade(&exit("take_profit"), &entry("sell", "order-1-exit"));
+        store.close_trade(&exit("stop_loss"), &entry("sell", "order-1-exit"));
+        let order = NewOrder {
+            client_order_id: "order-1-exit",
+            strategy_id: "default",
+            run_id: Some(run),
+            token_id: "mint",
+            side: "sell",
+            order_type: "exit",
+            sol_amount: None,
+            shadow: false,
+            expires_at: Utc::now(),
+        };
+        store.open_order(&order);
+        assert!(store.update_order(&OrderUpdate::new("order-1-exit", OrderStatus::Filled)));
+        assert!(!store.update_order(&OrderUpdate::new("order-1-exit", OrderStatus::Cancelled)));
+        store.open_order(&order);
+        assert_eq!(store.expire_orders(), 0);
+        store.finish_run(run, 1, 1);
+
+        assert_eq!(store.counts(), (2, 1));
+        let dump: Value = serde_json::from_slice(&store.dump().unwrap()).unwrap();
+        assert_eq!(dump["token_events"][0]["id"], "mint");
+        assert_eq!(dump["token_events"][1]["id"], "mint");
+        assert_eq!(dump["token_events"][1]["event_id"], 2);
+        assert_eq!(dump["trades"][0]["exit_reason"], "take_profit");
+        assert_eq!(dump["trades"][0]["run_id"], run);
+        assert_eq!(dump["balance_ledger"].as_array().unwrap().len(), 2);
+        assert_eq!(dump["orders"][0]["status"], "filled");
+        assert_eq!(dump["runs"][0]["trades_made"], 1);
+    }
+}
@@ -0,0 +1,301 @@
+//! Compiles instructions into v0 versioned transactions
+//!
+//! Accounts found in one of the supplied address lookup tables are referenced by a one-byte
+//! table index instead of a 32-byte key, which keeps multi-hop routes under the packet size
+//! limit once compute budget and tip instructions are added. Signers and invoked programs
+//! always stay in the static keys, as the runtime requires.
+
+use crate::executor::encode_compact_u16;
+use anyhow::{Result, bail};
+
+/// Largest serialized transaction a validator accepts (IPv6 MTU minus headers)
+pub const MAX_TX_SIZE: usize = 1232;
+/// Size of an address lookup table account's header before its addresses
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+/// High bit marking a versioned message; the low bits are the version
+const VERSION_0_PREFIX: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AccountMeta {
+    pub key: [u8; 32],
+    pub signer: bool,
+    pub writable: bool,
+}
+
+impl AccountMeta {
+    pub fn writable(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            signer: false,
+            writable: true,
+        }
+    }
+
+    pub fn readonly(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            signer: false,
+            writable: false,
+        }
+    }
+
+    pub fn signer(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            signer: true,
+            writable: true,
+        }
+    }
+}
+
+pub struct Instruction {
+    pub program_id: [u8; 32],
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+/// Addresses stored in an on-chain address lookup table
+#[derive(Debug, Clone)]
+pub struct LookupTable {
+    pub address: [u8; 32],
+    pub addresses: Vec<[u8; 32]>,
+}
+
+impl LookupTable {
+    /// Parse the raw data of a lookup table account
+    pub fn from_account_data(address: [u8; 32], data: &[u8]) -> Result<Self> {
+        let Some(body) = data.get(LOOKUP_TABLE_META_SIZE..) else {
+            bail!("lookup table account is shorter than its header");
+        };
+        if body.len() % 32 != 0 {
+            bail!("lookup table holds a partial address");
+        }
+        Ok(Self {
+            address,
+            addresses: body
+                .chunks_exact(32)
+                .map(|chunk| chunk.try_into().expect("32-byte chunk"))
+                .collect(),
+        })
+    }
+}
+
+/// An unsigned transaction with zeroed signatures and blockhash
+pub struct CompiledTransaction {
+    pub bytes: Vec<u8>,
+    static_keys: Vec<[u8; 32]>,
+    keys_offset: usize,
+    data_offsets: Vec<usize>,
+}
+
+impl CompiledTransaction {
+    /// Byte offset of a static account key, for patching it after compilation
+    pub fn key_offset(&self, key: &[u8; 32]) -> Option<usize> {
+        self.static_keys
+            .iter()
+            .position(|k| k == key)
+            .map(|i| self.keys_offset + i * 32)
+    }
+
+    /// Byte offset of the data of the `index`th instruction
+    pub fn data_offset(&self, index: usize) -> usize {
+        self.data_offsets[index]
+    }
+}
+
+/// Compile `instructions` paid for by `payer` into a v0 transaction, moving every account
+/// that may be looked up into `lookup_tables`
+pub fn compile(
+    payer: [u8; 32],
+    instructions: &[Instruction],
+    lookup_tables: &[LookupTable],
+) -> Result<CompiledTransaction> {
+    // Merge each account's flags across instructions, payer first
+    let mut accounts: Vec<(AccountMeta, bool)> = vec![(AccountMeta::signer(payer), false)];
+    let mut add = |meta: AccountMeta, invoked: bool| match accounts
+        .iter_mut()
+        .find(|(a, _)| a.key == meta.key)
+    {
+        Some((a, inv)) => {
+            a.signer |= meta.signer;
+            a.writable |= meta.writable;
+            *inv |= invoked;
+        }
+        None => accounts.push((meta, invoked)),
+    };
+    for ix in instructions {
+        add(AccountMeta::readonly(ix.program_id), true);
+        for meta in &ix.accounts {
+            add(*meta, false);
+        }
+    }
+
+    // Non-signer, non-program accounts found in a table are loaded from it
+    let mut lookups: Vec<(usize, Vec<u8>, Vec<u8>)> = Vec::new();
+    let mut looked_up_writable = Vec::new();
+    let mut looked_up_readonly = Vec::new();
+    let mut static_accounts = Vec::new();
+    for (meta, invoked) in accounts {
+        let found = (!meta.signer && !invoked)
+            .then(|| {
+                lookup_tables.iter().enumerate().find_map(|(t, table)| {
+                    let index = table.addresses.iter().position(|a| *a == meta.key)?;
+                    Some((t, u8::try_from(index).ok()?))
+                })
+            })
+            .flatten();
+        let Some((table, index)) = found else {
+            static_accounts.push(meta);
+            continue;
+        };
+        let pos = match lookups.iter().position(|(t, _, _)| *t == table) {
+            Some(pos) => pos,
+            None => {
+                lookups.push((table, Vec::new(), Vec::new()));
+                lookups.len() - 1
+            }
+        };
+        if meta.writable {
+            lookups[pos].1.push(index);
+            looked_up_writable.push((pos, meta.key));
+        } else {
+            lookups[pos].2.push(index);
+            looked_up_readonly.push((pos, meta.key));
+        }
+    }
+    // Loaded addresses are indexed after the static keys: all writable ones in table order,
+    // then all read-only ones
+    looked_up_writable.sort_by_key(|(pos, _)| *pos);
+    looked_up_readonly.sort_by_key(|(pos, _)| *pos);
+
+    // Writable signers (payer first), read-only signers, writable, then read-only accounts
+    static_accounts.sort_by_key(|a| match (a.signer, a.writable) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    });
+    let num_signers = static_accounts.iter().filter(|a| a.signer).count();
+    let readonly_signed = static_accounts
+        .iter()
+        .filter(|a| a.signer && !a.writable)
+        .count();
+    let readonly_unsigned = static_accounts
+        .iter()
+        .filter(|a| !a.signer && !a.writable)
+        .count();
+    let static_keys: Vec<[u8; 32]> = static_accounts.iter().map(|a| a.key).collect();
+    let all_keys: Vec<[u8; 32]> = static_keys
+        .iter()
+        .copied()
+        .chain(looked_up_writable.iter().map(|(_, k)| *k))
+        .chain(looked_up_readonly.iter().map(|(_, k)| *k))
+        .collect();
+    let index_of = |key: &[u8; 32]| -> Result<u8> {
+        let index = all_keys
+            .iter()
+            .position(|k| k == key)
+            .expect("every account was collected");
+        u8::try_from(index).map_err(|_| anyhow::anyhow!("transaction uses over 256 accounts"))
+    };
+
+    // Signatures first, then the message
+    let mut tx = Vec::new();
+    encode_compact_u16(&mut tx, num_signers);
+    tx.resize(tx.len() + num_signers * 64, 0);
+    tx.push(VERSION_0_PREFIX);
+    tx.extend_from_slice(&[
+        num_signers as u8,
+        readonly_signed as u8,
+        readonly_unsigned as u8,
+    ]);
+    encode_compact_u16(&mut tx, static_keys.len());
+    let keys_offset = tx.len();
+    for key in &static_keys {
+        tx.extend_from_slice(key);
+    }
+    tx.extend_from_slice(&[0u8; 32]); // blockhash, set at submit time
+
+    let mut data_offsets = Vec::with_capacity(instructions.len());
+    encode_compact_u16(&mut tx, instructions.len());
+    for ix in instructions {
+        tx.push(index_of(&ix.program_id)?);
+        encode_compact_u16(&mut tx, ix.accounts.len());
+        for meta in &ix.accounts {
+            tx.push(index_of(&meta.key)?);
+        }
+        encode_compact_u16(&mut tx, ix.data.len());
+        data_offsets.push(tx.len());
+        tx.extend_from_slice(&ix.data);
+    }
+
+    encode_compact_u16(&mut tx, lookups.len());
+    for (table, writable, readonly) in &lookups {
+        tx.extend_from_slice(&lookup_tables[*table].address);
+        encode_compact_u16(&mut tx, writable.len());
+        tx.extend_from_slice(writable);
+        encode_compact_u16(&mut tx, readonly.len());
+        tx.extend_from_slice(readonly);
+    }
+
+    if tx.len() > MAX_TX_SIZE {
+        bail!(
+            "transaction is {} bytes, over the {}-byte limit; add its accounts to a lookup table",
+            tx.len(),
+            MAX_TX_SIZE
+        );
+    }
+    Ok(CompiledTransaction {
+        bytes: tx,
+        static_keys,
+        keys_offset,
+        data_offsets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> [u8; 32] {
+        [n; 32]
+    }
+
+    #[test]
+    fn moves_table_accounts_out_of_static_keys() {
+        let ix = Instruction {
+            program_id: key(9),
+            accounts: vec![
+                AccountMeta::signer(key(1)),
+                AccountMeta::writable(key(2)),
+                AccountMeta::readonly(key(3)),
+                AccountMeta::readonly(key(4)),
+            ],
+            data: vec![42],
+        };
+        let table = LookupTable {
+            address: key(100),
+            addresses: vec![key(7), key(4), key(2), key(9)],
+        };
+        let plain = compile(key(1), std::slice::from_ref(&ix), &[]).unwrap();
+        let compact = compile(key(1), &[ix], &[table]).unwrap();
+
+        // The program stays static even though the table lists it
+        assert!(compact.key_offset(&key(9)).is_some());
+        assert!(compact.key_offset(&key(2)).is_none());
+        assert_eq!(plain.bytes.len() - compact.bytes.len(), 2 * 32 - (32 + 4));
+        // Header: one signer, no read-only signer, the read-only key 3 and the program
+        assert_eq!(compact.bytes[65..69], [VERSION_0_PREFIX, 1, 0, 2]);
+        // Static keys: payer, 9, 3; then looked up: 2 (writable), 4 (read-only)
+        let data = compact.data_offset(0);
+        assert_eq!(compact.bytes[data - 7..=data], [1, 4, 0, 3, 2, 4, 1, 42]);
+        assert_eq!(
+            compact.bytes[data + 1..],
+            [&[1u8][..], &key(100), &[1, 2, 1, 1]].concat()[..]
+        );
+
+        let data = [vec![0u8; LOOKUP_TABLE_META_SIZE], key(5).to_vec()].concat();
+        let parsed = LookupTable::from_account_data(key(100), &data).unwrap();
+        assert_eq!(parsed.addresses, vec![key(5)]);
+    }
+}
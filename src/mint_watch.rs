@@ -0,0 +1,225 @@
+//! On-chain rug vectors of held tokens, which the price shows only once it is too late
+//!
+//! While a position is open, every exit pass reads its mint account, its Metaplex metadata,
+//! and in live mode the wallet's token account. The first reading is the baseline. A later
+//! one that shows the token account frozen, new supply minted, a new mint or freeze
+//! authority, or a changed name, symbol, or URI publishes a `mint_change` event, and sells
+//! the position with that reason unless the strategy sets `exit_on_mint_change = false`.
+
+use crate::models::TokenMetadata;
+use crate::scanner::TokenDataProvider;
+
+/// Fields of a Metaplex metadata account a rug would rewrite
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaplexFields {
+    pub update_authority: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl MetaplexFields {
+    /// Decode the start of a metadata account: key, update authority, mint, then the
+    /// length-prefixed name, symbol, and URI, padded with NULs
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        let update_authority = bs58::encode(data.get(1..33)?).into_string();
+        let mut offset = 65;
+        let mut string = || {
+            let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+            let bytes = data.get(offset + 4..offset + 4 + len)?;
+            offset += 4 + len;
+            Some(
+                String::from_utf8_lossy(bytes)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            )
+        };
+        Some(Self {
+            update_authority,
+            name: string()?,
+            symbol: string()?,
+            uri: string()?,
+        })
+    }
+}
+
+/// A mint and its metadata as read on one exit pass; `None` fields couldn't be read
+#[derive(Debug, Clone, Default)]
+pub struct MintState {
+    pub account: Option<TokenMetadata>,
+    pub metadata: Option<MetaplexFields>,
+    /// Whether the wallet's token account is frozen; `None` when simulated
+    pub frozen: Option<bool>,
+}
+
+/// What changed on a held token's mint
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintChange {
+    /// `freeze_issued`, `supply_minted`, `mint_authority`, `freeze_authority`, or
+    /// `metadata_changed`
+    pub reason: &'static str,
+    pub detail: String,
+}
+
+/// Read `mint`, and `token_account` when the position is held in a live wallet
+pub async fn read<P: TokenDataProvider>(
+    scanner: &P,
+    mint: &str,
+    token_account: Option<&str>,
+) -> MintState {
+    let frozen = match token_account {
+        Some(address) => scanner
+            .query_token_account_frozen(address)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    MintState {
+        account: scanner.query_token_metadata(mint).await.ok().flatten(),
+        metadata: scanner.query_metaplex_metadata(mint).await.ok().flatten(),
+        frozen,
+    }
+}
+
+impl MintState {
+    /// The first change since `baseline` that puts the position at risk; anything that
+    /// couldn't be read this time is assumed unchanged
+    pub fn change_since(&self, baseline: &MintState) -> Option<MintChange> {
+        let change = |reason, detail: String| Some(MintChange { reason, detail });
+        if self.frozen == Some(true) && baseline.frozen != Some(true) {
+            return change(
+                "freeze_issued",
+                "the wallet's token account was frozen".into(),
+            );
+        }
+        if let (Some(now), Some(then)) = (&self.account, &baseline.account) {
+            let supply = |m: &TokenMetadata| m.supply.as_deref()?.parse::<u64>().ok();
+            if let (Some(now_supply), Some(then_supply)) = (supply(now), supply(then))
+                && now_supply > then_supply
+                && then_supply > 0
+            {
+                let pct = (now_supply - then_supply) as f64 / then_supply as f64 * 100.0;
+                return change("supply_minted", format!("supply up {:.2}%", pct));
+            }
+            if now.mint_authority.is_some() && now.mint_authority != then.mint_authority {
+                return change(
+                    "mint_authority",
+                    format!("mint authority set to {}", now.mint_authority.as_ref()?),
+                );
+            }
+            if now.freeze_authority.is_some() && now.freeze_authority != then.freeze_authority {
+                return change(
+                    "freeze_authority",
+                    format!("freeze authority set to {}", now.freeze_authority.as_ref()?),
+                );
+            }
+        }
+        if let (Some(now), Some(then)) = (&self.metadata, &baseline.metadata)
+            && (now.name != then.name || now.symbol != then.symbol || now.uri != then.uri)
+        {
+            return change(
+                "metadata_changed",
+                format!(
+                    "{} ({}) {} is now {} ({}) {}",
+                    then.name, then.symbol, then.uri, now.name, now.symbol, now.uri
+                ),
+            );
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mint(supply: &str, freeze_authority: Option<&str>) -> Option<TokenMetadata> {
+        Some(TokenMetadata {
+            mint: None,
+            standard: None,
+            name: None,
+            symbol: None,
+            metaplex: None,
+            decimals: Some(6),
+            mint_authority: None,
+            freeze_authority: freeze_authority.map(String::from),
+            supply: Some(supply.to_string()),
+            transfer_fee_bps: None,
+        })
+    }
+
+    fn metadata_account(name: &str, symbol: &str, uri: &str) -> Vec<u8> {
+        let mut data = vec![4];
+        data.extend([9; 32]);
+        data.extend([1; 32]);
+        for (field, padded) in [(name, 32), (symbol, 10), (uri, 200)] {
+            data.extend((padded as u32).to_le_bytes());
+            let mut bytes = field.as_bytes().to_vec();
+            bytes.resize(padded, 0);
+            data.extend(bytes);
+        }
+        data
+    }
+
+    #[test]
+    fn flags_freezes_mints_and_rewritten_metadata() {
+        let metadata =
+            MetaplexFields::from_account_data(&metadata_account("Cat", "MCAT", "https://a"))
+                .unwrap();
+        assert_eq!(metadata.name, "Cat");
+        assert_eq!(metadata.uri, "https://a");
+        assert_eq!(
+            metadata.update_authority,
+            bs58::encode([9; 32]).into_string()
+        );
+        assert!(MetaplexFields::from_account_data(&[4; 40]).is_none());
+
+        let baseline = MintState {
+            account: mint("1000000", None),
+            metadata: Some(metadata.clone()),
+            frozen: Some(false),
+        };
+        let unread = MintState::default();
+        assert_eq!(unread.change_since(&baseline), None);
+
+        let minted = MintState {
+            account: mint("1500000", None),
+            ..baseline.clone()
+        };
+        let change = minted.change_since(&baseline).unwrap();
+        assert_eq!(change.reason, "supply_minted");
+        assert_eq!(change.detail, "supply up 50.00%");
+
+        let frozen = MintState {
+            frozen: Some(true),
+            ..baseline.clone()
+        };
+        assert_eq!(
+            frozen.change_since(&baseline).unwrap().reason,
+            "freeze_issued"
+        );
+
+        let freezable = MintState {
+            account: mint("1000000", Some("Authority1111")),
+            ..baseline.clone()
+        };
+        assert_eq!(
+            freezable.change_since(&baseline).unwrap().reason,
+            "freeze_authority"
+        );
+
+        let renamed = MintState {
+            metadata: Some(MetaplexFields {
+                uri: "https://b".to_string(),
+                ..metadata
+            }),
+            ..baseline.clone()
+        };
+        assert_eq!(
+            renamed.change_since(&baseline).unwrap().reason,
+            "metadata_changed"
+        );
+        assert_eq!(baseline.change_since(&baseline), None);
+    }
+}
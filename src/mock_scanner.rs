@@ -0,0 +1,354 @@
+//! Offline scanner that replays recorded PumpPortal messages and HTTP response bodies
+//!
+//! Fixtures live under `tests/data/`:
+//! - `pumpportal/new_tokens.jsonl`: raw WebSocket messages, one per line, including trades
+//! - `rpc/<method>/<key>.json`: JSON-RPC response bodies, keyed by mint, signature, or account
+//! - `rpc/batch/<name>.json`: batched JSON-RPC responses
+//! - `dexscreener/<mint>/<n>.json`: DexScreener responses, replayed in order per mint
+//! - `jupiter/<mint>.json`: Jupiter sell quotes, for any amount
+//! - `jupiter/buy/<mint>.json`: Jupiter buy quotes, for any amount
+//!
+//! Responses go through the same parsers as live ones. A missing fixture behaves like a
+//! non-2xx response.
+
+use crate::buy_template::metadata_address;
+use crate::mint_watch::MetaplexFields;
+use crate::models::*;
+use crate::pumpportal::{PumpPortalMessage, TradeFeed};
+use crate::sandwich;
+use crate::scanner::{self, ListingSource, TokenDataProvider};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub struct MockScanner {
+    root: PathBuf,
+    /// Next DexScreener response to replay per mint
+    dexscreener_calls: Mutex<HashMap<String, usize>>,
+}
+
+impl MockScanner {
+    /// Replay the fixtures checked into `tests/data/`
+    pub fn new() -> Self {
+        Self::with_root(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data"))
+    }
+
+    pub fn with_root(root: PathBuf) -> Self {
+        Self {
+            root,
+            dexscreener_calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn read(&self, relative: &str) -> Option<String> {
+        std::fs::read_to_string(self.root.join(relative)).ok()
+    }
+
+    fn rpc_fixture(&self, method: &str, key: &str) -> Option<String> {
+        self.read(&format!("rpc/{}/{}.json", method, key))
+    }
+}
+
+impl ListingSource for MockScanner {
+    async fn fetch_pumpfun_listings(&self) -> Result<Vec<PumpFunListing>> {
+        let Some(raw) = self.read("pumpportal/new_tokens.jsonl") else {
+            return Ok(Vec::new());
+        };
+        // Recorded frames are replayed as if they had all just arrived
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut listings: Vec<PumpFunListing> = Vec::new();
+        let mut trades = TradeFeed::default();
+        for line in raw.lines() {
+            match PumpPortalMessage::parse(line) {
+                Ok(PumpPortalMessage::NewToken(msg)) => listings.push((*msg).into()),
+                Ok(msg) => trades.record(&msg, now_ms),
+                Err(_) => {}
+            }
+        }
+        trades.attach(&mut listings);
+        Ok(listings)
+    }
+}
+
+impl TokenDataProvider for MockScanner {
+    async fn query_mint_launch(&self, mint: &str) -> Result<Option<MintLaunch>> {
+        match self.rpc_fixture("getSignaturesForAddress", mint) {
+            Some(body) => scanner::parse_mint_launch(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_creator_initial_buy_pct(
+        &self,
+        signature: &str,
+        creator: &str,
+        mint: &str,
+    ) -> Result<Option<f64>> {
+        match self.rpc_fixture("getTransaction", signature) {
+            Some(body) => scanner::parse_creator_initial_buy_pct(&body, creator, mint),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_bonding_curve(&self, bonding_curve: &str) -> Result<Option<BondingCurveState>> {
+        match self.rpc_fixture("getAccountInfo", bonding_curve) {
+            Some(body) => scanner::parse_bonding_curve(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_token_holder_stats(&self, mint: &str) -> Result<Option<HolderStats>> {
+        match self.rpc_fixture("getProgramAccounts", mint) {
+            Some(body) => scanner::parse_holder_stats(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_token_metadata(&self, mint: &str) -> Result<Option<TokenMetadata>> {
+        match self.rpc_fixture("getAccountInfo", mint) {
+            Some(body) => scanner::parse_mint_account(&body, mint),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_token_top_holders(&self, mint: &str) -> Result<Option<TopHoldersResponse>> {
+        match self.rpc_fixture("getProgramAccounts", mint) {
+            Some(body) => scanner::parse_top_holders(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_metaplex_metadata(&self, mint: &str) -> Result<Option<MetaplexFields>> {
+        match self.rpc_fixture("getAccountInfo", &metadata_address(mint)?) {
+            Some(body) => scanner::parse_metaplex_account(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_token_account_frozen(&self, address: &str) -> Result<Option<bool>> {
+        match self.rpc_fixture("getAccountInfo", address) {
+            Some(body) => scanner::parse_token_account_frozen(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_funder(&self, wallet: &str) -> Result<Option<String>> {
+        let Some(signature) = self
+            .rpc_fixture("getSignaturesForAddress", wallet)
+            .map(|body| scanner::parse_oldest_signature(&body))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+        match self.rpc_fixture("getTransaction", &signature) {
+            Some(body) => scanner::parse_funder(&body, wallet),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_recent_sandwiches(&self, mint: &str) -> Result<Option<u32>> {
+        let Some(body) = self.rpc_fixture("getSignaturesForAddress", mint) else {
+            return Ok(None);
+        };
+        let mut payers = Vec::new();
+        for (slot, signature) in scanner::parse_crowded_signatures(&body)? {
+            let Some(payer) = self
+                .rpc_fixture("getTransaction", &signature)
+                .map(|body| scanner::parse_fee_payer(&body))
+                .transpose()?
+                .flatten()
+            else {
+                return Ok(None);
+            };
+            payers.push((slot, payer));
+        }
+        Ok(Some(sandwich::count_sandwiches(&payers)))
+    }
+
+    /// Each call returns the mint's next recorded response, repeating the last one
+    async fn query_dexscreener_pair(&self, mint: &str) -> Result<Option<DexScreenerPair>> {
+        let call = {
+            let mut calls = self.dexscreener_calls.lock().unwrap();
+            let n = calls.entry(mint.to_string()).or_insert(0);
+            *n += 1;
+            *n - 1
+        };
+        let body = (0..=call)
+            .rev()
+            .find_map(|n| self.read(&format!("dexscreener/{}/{}.json", mint, n)));
+        match body {
+            Some(body) => Ok(Some(scanner::parse_dexscreener_pair(&body)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_sell_route(&self, mint: &str, _amount: u64) -> Result<Option<SellRoute>> {
+        match self.read(&format!("jupiter/{}.json", mint)) {
+            Some(body) => scanner::parse_jupiter_quote(&body),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_buy_route(&self, mint: &str, _lamports: u64) -> Result<Option<SellRoute>> {
+        match self.read(&format!("jupiter/buy/{}.json", mint)) {
+            Some(body) => scanner::parse_jupiter_quote(&body),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{Enrichment, enrich_listing, enrich_mint};
+    use crate::strategy_config::StrategyConfig;
+
+    const GOOD_MINT: &str = "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump";
+
+    #[tokio::test]
+    async fn replays_listings_and_skips_non_token_messages() {
+        let listings = MockScanner::new().fetch_pumpfun_listings().await.unwrap();
+        assert_eq!(listings.len(), 2);
+        assert_eq!(listings[0].token_address, GOOD_MINT);
+        assert_eq!(listings[0].symbol.as_deref(), Some("MCAT"));
+        assert!(listings[0].creator.is_some() && listings[0].bonding_curve.is_some());
+    }
+
+    #[tokio::test]
+    async fn decodes_recorded_rpc_responses() {
+        let mock = MockScanner::new();
+        let listing = mock.fetch_pumpfun_listings().await.unwrap().remove(0);
+
+        let launch = mock.query_mint_launch(GOOD_MINT).await.unwrap().unwrap();
+        assert_eq!(launch.created_at, Some(1_760_599_400));
+        // One sniper landed in the launch slot and another failed there
+        assert_eq!(launch.first_block_buys, 1);
+
+        let pct = mock
+            .query_creator_initial_buy_pct(
+                listing.signature.as_deref().unwrap(),
+                listing.creator.as_deref().unwrap(),
+                GOOD_MINT,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((pct - 1.0).abs() < 1e-6);
+
+        let curve = mock
+            .query_bonding_curve(listing.bonding_curve.as_deref().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((curve.progress_pct() - 15.13).abs() < 0.01);
+
+        let holders = mock.query_token_holder_stats(GOOD_MINT).await.unwrap();
+        assert_eq!(holders.unwrap().total, Some(40));
+
+        let metadata = mock.query_token_metadata(GOOD_MINT).await.unwrap().unwrap();
+        assert_eq!(metadata.decimals, Some(6));
+        assert!(metadata.mint_authority.is_none() && metadata.freeze_authority.is_none());
+    }
+
+    #[tokio::test]
+    async fn records_which_enrichment_fields_are_missing() {
+        let mock = MockScanner::new();
+        let mut listings = mock.fetch_pumpfun_listings().await.unwrap();
+        let good = enrich_listing(&mock, listings.remove(0), &Enrichment::default()).await;
+        assert!(good.missing_fields.is_empty(), "{:?}", good.missing_fields);
+
+        // No fixtures at all for this mint
+        let bare = enrich_mint(&mock, "Unknown1111", &Enrichment::default()).await;
+        assert_eq!(bare.missing_fields, crate::strategy::ENRICHMENT_FIELDS);
+        let mut config = StrategyConfig::default();
+        let rejections = bare.filter_rejections(&config);
+        assert_eq!(rejections[0].filter, "missing_data");
+        assert_eq!(
+            rejections[0].detail,
+            "no market_cap, holders, dev_hold, authorities data"
+        );
+
+        // With every field optional, the missing ones are neither filtered nor scored
+        config.required_fields.clear();
+        assert!(bare.filter_rejections(&config).is_empty());
+        let rules: Vec<_> = bare
+            .score_breakdown(&config)
+            .into_iter()
+            .map(|(r, _)| r)
+            .collect();
+        assert!(!rules.contains(&"holders") && !rules.contains(&"dev_hold"));
+    }
+
+    #[tokio::test]
+    async fn skips_disabled_enrichment_steps() {
+        let mock = MockScanner::new();
+        let listing = mock.fetch_pumpfun_listings().await.unwrap().remove(0);
+        let steps = Enrichment {
+            holders: false,
+            dexscreener: false,
+            ..Enrichment::default()
+        };
+        let ev = enrich_listing(&mock, listing, &steps).await;
+        assert_eq!(ev.holders, 0);
+        assert_eq!(ev.dev_hold_pct, 0.0);
+        assert_eq!(ev.missing_fields, ["liquidity", "holders", "dev_hold"]);
+        // Still read from the chain
+        assert!(ev.token_age_secs.is_some() && ev.bonding_curve_progress_pct.is_some());
+    }
+
+    #[tokio::test]
+    async fn replays_dexscreener_responses_in_order() {
+        let mock = MockScanner::new();
+        let mut prices = Vec::new();
+        for _ in 0..4 {
+            let pair = mock
+                .query_dexscreener_pair(GOOD_MINT)
+                .await
+                .unwrap()
+                .unwrap();
+            prices.push(pair.pairs.unwrap()[0].price_usd.unwrap());
+        }
+        assert_eq!(prices, vec![0.0000312, 0.0000401, 0.0000498, 0.0000498]);
+        assert!(
+            mock.query_dexscreener_pair("unknown")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn decodes_jupiter_sell_quotes() {
+        let mock = MockScanner::new();
+        let route = mock
+            .query_sell_route(GOOD_MINT, 35_000_000_000)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(route.out_lamports, 487_213_004);
+        assert!((route.price_impact_pct - 2.13).abs() < 1e-9);
+        assert_eq!(route.venues, vec!["Raydium CPMM"]);
+        assert!(mock.query_sell_route("unknown", 1).await.unwrap().is_none());
+        let no_route =
+            r#"{"error":"Could not find any route","errorCode":"COULD_NOT_FIND_ANY_ROUTE"}"#;
+        assert!(scanner::parse_jupiter_quote(no_route).unwrap().is_none());
+    }
+
+    #[test]
+    fn splits_batched_rpc_responses() {
+        let body = MockScanner::new().read("rpc/batch/prefetch.json").unwrap();
+        let bodies = scanner::split_batch(&body, 4).unwrap();
+        assert!(bodies[2].is_none() && bodies[3].is_none());
+
+        let mints = [GOOD_MINT, "missing"];
+        let metadata = scanner::parse_mint_accounts(bodies[0].as_ref().unwrap(), &mints).unwrap();
+        assert_eq!(metadata[0].as_ref().unwrap().decimals, Some(6));
+        assert!(metadata[1].is_none());
+
+        let curves = scanner::parse_bonding_curves(bodies[1].as_ref().unwrap()).unwrap();
+        assert!((curves[0].as_ref().unwrap().progress_pct() - 15.13).abs() < 0.01);
+        assert!(curves[1].is_none());
+    }
+}
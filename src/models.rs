@@ -1,13 +1,12 @@
+use crate::strategy::{TokenEvent, TokenTrade};
 use serde::Deserialize;
 
-// Pump.fun API structures
-#[derive(Debug, Deserialize)]
-pub struct PumpFunResponse {
-    pub result: Option<Vec<PumpFunListing>>,
-}
+/// Every pump.fun token is minted with a fixed 1B supply
+pub const PUMPFUN_TOTAL_SUPPLY: f64 = 1_000_000_000.0;
 
+// Pump.fun API structures
 #[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PumpFunListing {
     pub token_address: String,
@@ -20,6 +19,77 @@ pub struct PumpFunListing {
     pub liquidity: Option<String>,
     pub fully_diluted_valuation: Option<String>,
     pub created_at: Option<String>,
+    /// Creator wallet (fee payer of the create transaction)
+    pub creator: Option<String>,
+    /// Signature of the create transaction
+    pub signature: Option<String>,
+    /// Tokens bought by the creator in the create transaction
+    pub initial_buy: Option<String>,
+    /// Pump.fun bonding curve account for this mint
+    pub bonding_curve: Option<String>,
+    /// When the scanner received the listing, for latency tracking
+    #[serde(skip)]
+    pub received_at: Option<std::time::Instant>,
+    /// Trades received on the stream while listening, oldest first (None unless watched)
+    #[serde(skip)]
+    pub trades: Option<Vec<TokenTrade>>,
+}
+
+impl From<PumpFunListing> for TokenEvent {
+    fn from(p: PumpFunListing) -> Self {
+        // helper to parse optional numeric strings
+        fn parse_opt_f64(s: Option<String>) -> f64 {
+            s.as_deref()
+                .and_then(|v| v.replace(',', "").parse::<f64>().ok())
+                .unwrap_or(0.0)
+        }
+
+        let market_cap = parse_opt_f64(p.fully_diluted_valuation);
+        let base_price = parse_opt_f64(p.price_usd);
+        // A listing's price is as old as the listing
+        let priced_at_ms = (base_price > 0.0).then(|| {
+            let now = chrono::Utc::now().timestamp_millis();
+            now - p.received_at.map_or(0, |r| r.elapsed().as_millis() as i64)
+        });
+        let liquidity_usd = parse_opt_f64(p.liquidity);
+        // Fallback until the create transaction is decoded during enrichment
+        let dev_initial_buy_pct = p
+            .initial_buy
+            .as_deref()
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|tokens| tokens / PUMPFUN_TOTAL_SUPPLY * 100.0);
+
+        TokenEvent {
+            id: p.token_address.clone(),
+            source: "pumpfun".to_string(),
+            token_type: p.symbol.unwrap_or_else(|| "unknown".to_string()),
+            market_cap_usd: market_cap,
+            dev_hold_pct: 0.0,
+            liquidity_usd,
+            holders: 0,
+            upgradeable: false,
+            freeze_authority: false,
+            momentum: false,
+            graduation: false,
+            base_price,
+            priced_at_ms,
+            dev_wallet_address: p.creator,
+            is_dev_known_rugger: false,
+            token_age_secs: None,
+            launched_at: None,
+            dev_initial_buy_pct,
+            bonding_curve_progress_pct: None,
+            signal_confidence: None,
+            blacklisted: false,
+            missing_fields: Vec::new(),
+            dev_stats: None,
+            effective_top_holder_pct: None,
+            transfer_fee_pct: None,
+            round_trip_tax_pct: None,
+            first_block_buys: None,
+            trades: p.trades,
+        }
+    }
 }
 
 // Token metadata structures (formerly from Moralis, now generic)
@@ -36,6 +106,8 @@ pub struct TokenMetadata {
     pub mint_authority: Option<String>,
     pub freeze_authority: Option<String>,
     pub supply: Option<String>,
+    /// Token-2022 transfer fee in basis points, the higher of the current and scheduled one
+    pub transfer_fee_bps: Option<u16>,
 }
 
 #[allow(dead_code)]
@@ -92,7 +164,155 @@ pub struct DexScreenerPair {
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawDexPairInfo")]
 pub struct DexPairInfo {
+    pub dex_id: Option<String>,
     pub liquidity_usd: Option<f64>,
     pub price_usd: Option<f64>,
+    pub volume_m5_usd: Option<f64>,
+    pub market_cap_usd: Option<f64>,
+}
+
+// DEX Screener wire format: prices are strings, liquidity and volume are nested
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDexPairInfo {
+    dex_id: Option<String>,
+    price_usd: Option<String>,
+    liquidity: Option<RawDexLiquidity>,
+    volume: Option<RawDexVolume>,
+    market_cap: Option<f64>,
+    fdv: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawDexLiquidity {
+    usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawDexVolume {
+    m5: Option<f64>,
+}
+
+impl From<RawDexPairInfo> for DexPairInfo {
+    fn from(raw: RawDexPairInfo) -> Self {
+        Self {
+            dex_id: raw.dex_id,
+            liquidity_usd: raw.liquidity.and_then(|l| l.usd),
+            price_usd: raw.price_usd.and_then(|p| p.parse::<f64>().ok()),
+            volume_m5_usd: raw.volume.and_then(|v| v.m5),
+            market_cap_usd: raw.market_cap.or(raw.fdv),
+        }
+    }
+}
+
+// Jupiter quote for selling a token back to SOL
+#[derive(Debug, Clone)]
+pub struct SellRoute {
+    /// Lamports the sale would return
+    pub out_lamports: u64,
+    /// Price impact of the sale in percent
+    pub price_impact_pct: f64,
+    /// AMMs the route goes through, e.g. "Raydium"
+    pub venues: Vec<String>,
+}
+
+// A mint's first on-chain transaction and the buys that landed in the same slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintLaunch {
+    /// Unix time of the first transaction (None if the block time is unknown)
+    pub created_at: Option<i64>,
+    /// Other successful transactions in the launch slot, i.e. snipers
+    pub first_block_buys: u32,
+}
+
+// Jupiter quotes for buying a token with SOL and selling what it returns straight back
+#[derive(Debug, Clone)]
+pub struct RoundTrip {
+    pub in_lamports: u64,
+    pub out_lamports: u64,
+    /// Price impact of both legs in percent
+    pub price_impact_pct: f64,
+}
+
+// Pump.fun bonding curve account state
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct BondingCurveState {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl BondingCurveState {
+    /// Real token reserves a fresh pump.fun curve starts with (793.1M tokens, 6 decimals)
+    pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+
+    /// Decode the account data: 8-byte discriminator followed by five u64 fields and a bool
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() < 49 {
+            return None;
+        }
+        let read_u64 = |offset: usize| -> u64 {
+            let bytes: [u8; 8] = data[offset..offset + 8].try_into().unwrap_or([0; 8]);
+            u64::from_le_bytes(bytes)
+        };
+        Some(Self {
+            virtual_token_reserves: read_u64(8),
+            virtual_sol_reserves: read_u64(16),
+            real_token_reserves: read_u64(24),
+            real_sol_reserves: read_u64(32),
+            token_total_supply: read_u64(40),
+            complete: data[48] != 0,
+        })
+    }
+
+    /// Percentage of the curve's sellable tokens already bought (100 = graduated)
+    pub fn progress_pct(&self) -> f64 {
+        if self.complete {
+            return 100.0;
+        }
+        let remaining = self.real_token_reserves as f64 / Self::INITIAL_REAL_TOKEN_RESERVES as f64;
+        ((1.0 - remaining) * 100.0).clamp(0.0, 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve_account(real_token_reserves: u64, complete: bool) -> Vec<u8> {
+        let mut data = vec![0u8; 8]; // discriminator
+        for field in [1, 2, real_token_reserves, 3, 4] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.push(complete as u8);
+        data
+    }
+
+    #[test]
+    fn decodes_bonding_curve_progress() {
+        let half = BondingCurveState::INITIAL_REAL_TOKEN_RESERVES / 2;
+        let curve = BondingCurveState::from_account_data(&curve_account(half, false)).unwrap();
+        assert_eq!(curve.virtual_token_reserves, 1);
+        assert_eq!(curve.token_total_supply, 4);
+        assert!((curve.progress_pct() - 50.0).abs() < 1e-9);
+
+        let fresh = BondingCurveState::from_account_data(&curve_account(
+            BondingCurveState::INITIAL_REAL_TOKEN_RESERVES,
+            false,
+        ))
+        .unwrap();
+        assert_eq!(fresh.progress_pct(), 0.0);
+
+        // Graduated curves are done whatever their reserves say
+        let graduated = BondingCurveState::from_account_data(&curve_account(half, true)).unwrap();
+        assert_eq!(graduated.progress_pct(), 100.0);
+
+        assert!(BondingCurveState::from_account_data(&curve_account(half, false)[..48]).is_none());
+    }
 }
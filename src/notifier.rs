@@ -0,0 +1,73 @@
+//! Plain-text notifications to Telegram and Discord, used by the daily digest and alerts
+
+use crate::config::Config;
+use anyhow::{Result, bail};
+use reqwest::Client;
+use std::time::Duration;
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_CHARS: usize = 2_000;
+
+pub struct Notifier {
+    client: Client,
+    /// Bot token and chat id
+    telegram: Option<(String, String)>,
+    discord_url: Option<String>,
+}
+
+impl Notifier {
+    /// `None` when no channel is configured
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        let telegram = cfg
+            .telegram_bot_token
+            .clone()
+            .zip(cfg.telegram_chat_id.clone());
+        if telegram.is_none() && cfg.discord_webhook_url.is_none() {
+            return None;
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+        Some(Self {
+            client,
+            telegram,
+            discord_url: cfg.discord_webhook_url.clone(),
+        })
+    }
+
+    /// Post `text` to every channel, logging failures
+    pub async fn send(&self, text: &str) {
+        if let Some((token, chat_id)) = &self.telegram
+            && let Err(e) = self.send_telegram(token, chat_id, text).await
+        {
+            println!("[notifier] Telegram delivery failed: {}", e);
+        }
+        if let Some(url) = &self.discord_url
+            && let Err(e) = self.send_discord(url, text).await
+        {
+            println!("[notifier] Discord delivery failed: {}", e);
+        }
+    }
+
+    async fn send_telegram(&self, token: &str, chat_id: &str, text: &str) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+        let body = serde_json::json!({ "chat_id": chat_id, "text": text });
+        let status = self.client.post(&url).json(&body).send().await?.status();
+        if !status.is_success() {
+            bail!("Telegram returned {}", status);
+        }
+        Ok(())
+    }
+
+    async fn send_discord(&self, url: &str, text: &str) -> Result<()> {
+        let content: String = text.chars().take(DISCORD_MAX_CHARS).collect();
+        let body = serde_json::json!({ "content": content });
+        let status = self.client.post(url).json(&body).send().await?.status();
+        if !status.is_success() {
+            bail!("Discord returned {}", status);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+//! Orders and their lifecycle, from the decision to the fill
+//!
+//! Every buy and sell is an order in the `orders` table, keyed by its client order ID. An
+//! order starts `pending`, becomes `submitted` when it goes to a wallet, and ends `filled`,
+//! `failed`, or `cancelled`; a sliced entry is `partially_filled` until its last slice. A
+//! failed order goes back to `pending` when it is retried under the same ID, and nothing
+//! else leaves a final status. Paper orders fill without being submitted. Orders still open
+//! at `expires_at`, e.g. those of a process that stopped mid-order, are closed as timed out
+//! when the next run starts.
+//!
+//! With `entry_dip_pct` set, a strategy that decides to buy places a limit order that far
+//! below the price the token was detected at. The order fills if DexScreener shows the
+//! price there within `entry_dip_window_secs`, and is cancelled otherwise. With
+//! `entry_slices` above 1, a position is bought in that many equal slices,
+//! `entry_slice_interval_secs` apart, starting when it would otherwise have been bought
+//! whole. Each slice adds to the position and its `trades` row, and the rest are cancelled
+//! once the position closes, a risk limit stops one, or an operator cancels the order.
+//! `trades.entry_type` records how the position was entered and `trades.entry_slices` how
+//! many slices were bought.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+/// How long a market order or exit may stay open; longer than a wallet keeps resending
+pub const ORDER_TIMEOUT_SECS: i64 = 300;
+
+/// Where an order is in its lifecycle, as recorded in `orders.status`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Pending,
+    Submitted,
+    PartiallyFilled,
+    Filled,
+    Failed,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Submitted => "submitted",
+            OrderStatus::PartiallyFilled => "partially_filled",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Failed => "failed",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Statuses an order may move to this one from
+    pub fn allowed_from(&self) -> &'static [OrderStatus] {
+        use OrderStatus::*;
+        match self {
+            // Only a retry reopens an order
+            Pending => &[Failed],
+            Submitted => &[Pending],
+            PartiallyFilled => &[Pending, Submitted, PartiallyFilled],
+            Filled => &[Pending, Submitted, PartiallyFilled],
+            Failed => &[Pending, Submitted],
+            Cancelled => &[Pending, PartiallyFilled],
+        }
+    }
+
+    pub fn can_follow(&self, previous: OrderStatus) -> bool {
+        self.allowed_from().contains(&previous)
+    }
+
+    /// Whether an order in this status may still fill
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Pending | OrderStatus::Submitted | OrderStatus::PartiallyFilled
+        )
+    }
+}
+
+/// How a position is entered, as recorded in `trades.entry_type`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryType {
+    /// Bought whole on the decision
+    Market,
+    /// Bought once the price retraced, in slices if the strategy slices entries
+    Dip,
+    /// Bought in slices, the first on the decision
+    Twap,
+}
+
+impl EntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryType::Market => "market",
+            EntryType::Dip => "dip",
+            EntryType::Twap => "twap",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderState {
+    /// Waiting for the price to fall to `limit_price` before `expires_at`
+    Dip {
+        limit_price: f64,
+        expires_at: DateTime<Utc>,
+    },
+    /// `filled` slices bought, the next due at `next_at`
+    Slicing { filled: u32, next_at: DateTime<Utc> },
+}
+
+/// An entry still being worked
+#[derive(Debug, Clone)]
+pub struct EntryOrder {
+    /// Client order ID of the position the order opens or adds to
+    pub order_id: String,
+    pub token_id: String,
+    pub source: String,
+    pub score: f64,
+    pub dev_wallet_address: Option<String>,
+    pub launched_at: Option<i64>,
+    pub entry_type: EntryType,
+    /// SOL each slice spends
+    pub slice_sol: f64,
+    pub slices: u32,
+    pub slice_interval: Duration,
+    pub state: OrderState,
+}
+
+impl EntryOrder {
+    /// Client order ID of the `n`th slice (from 1); the first is the position's own
+    pub fn slice_order_id(&self, n: u32) -> String {
+        if n <= 1 {
+            self.order_id.clone()
+        } else {
+            format!("{}-{}", self.order_id, n)
+        }
+    }
+}
+
+/// One strategy's working entry orders
+#[derive(Debug, Default)]
+pub struct OrderManager {
+    orders: Vec<EntryOrder>,
+}
+
+impl OrderManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn place(&mut self, order: EntryOrder) {
+        self.orders.push(order);
+    }
+
+    /// Whether an order is already working on `token_id`
+    pub fn is_working(&self, token_id: &str) -> bool {
+        self.orders.iter().any(|o| o.token_id == token_id)
+    }
+
+    /// Drop the dip orders still unfilled at `now` and return them
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<EntryOrder> {
+        let (expired, working) = self.orders.drain(..).partition(
+            |o| matches!(o.state, OrderState::Dip { expires_at, .. } if expires_at <= now),
+        );
+        self.orders = working;
+        expired
+    }
+
+    /// Dip orders waiting for their price, and orders with a slice due at `now`
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<EntryOrder> {
+        self.orders
+            .iter()
+            .filter(|o| match o.state {
+                OrderState::Dip { .. } => true,
+                OrderState::Slicing { next_at, .. } => next_at <= now,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Record a slice bought at `now`; the order is done once every slice is bought
+    pub fn filled(&mut self, order_id: &str, now: DateTime<Utc>) {
+        let Some(idx) = self.orders.iter().position(|o| o.order_id == order_id) else {
+            return;
+        };
+        let order = &mut self.orders[idx];
+        let filled = match order.state {
+            OrderState::Dip { .. } => 1,
+            OrderState::Slicing { filled, .. } => filled + 1,
+        };
+        if filled >= order.slices {
+            self.orders.remove(idx);
+        } else {
+            order.state = OrderState::Slicing {
+                filled,
+                next_at: now + order.slice_interval,
+            };
+        }
+    }
+
+    /// Client order IDs of the working orders
+    pub fn order_ids(&self) -> Vec<String> {
+        self.orders.iter().map(|o| o.order_id.clone()).collect()
+    }
+
+    /// Stop working `order_id`, returning it if it was working
+    pub fn cancel(&mut self, order_id: &str) -> Option<EntryOrder> {
+        let idx = self.orders.iter().position(|o| o.order_id == order_id)?;
+        Some(self.orders.remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(entry_type: EntryType, slices: u32, state: OrderState) -> EntryOrder {
+        EntryOrder {
+            order_id: "order".to_string(),
+            token_id: "mint".to_string(),
+            source: "pumpfun".to_string(),
+            score: 80.0,
+            dev_wallet_address: None,
+            launched_at: None,
+            entry_type,
+            slice_sol: 0.1,
+            slices,
+            slice_interval: Duration::seconds(30),
+            state,
+        }
+    }
+
+    #[test]
+    fn final_statuses_only_reopen_on_retry() {
+        use OrderStatus::*;
+        assert!(Submitted.can_follow(Pending));
+        assert!(Filled.can_follow(Submitted));
+        assert!(Filled.can_follow(Pending));
+        assert!(!Submitted.can_follow(Filled));
+        assert!(!Cancelled.can_follow(Submitted));
+        assert!(!Filled.can_follow(Cancelled));
+        assert!(Pending.can_follow(Failed));
+        assert!(!Pending.can_follow(Filled));
+    }
+
+    #[test]
+    fn works_dips_and_slices_until_done() {
+        let now = Utc::now();
+        let mut orders = OrderManager::new();
+        orders.place(order(
+            EntryType::Dip,
+            2,
+            OrderState::Dip {
+                limit_price: 0.9,
+                expires_at: now + Duration::seconds(60),
+            },
+        ));
+        assert!(orders.is_working("mint"));
+        assert_eq!(orders.due(now).len(), 1);
+        assert!(orders.expire(now).is_empty());
+
+        // A filled dip goes on to slice the rest of the position
+        orders.filled("order", now);
+        assert!(orders.due(now).is_empty());
+        let next = orders.due(now + Duration::seconds(30));
+        assert_eq!(next[0].slice_order_id(2), "order-2");
+        orders.filled("order", now + Duration::seconds(30));
+        assert!(!orders.is_working("mint"));
+
+        orders.place(order(
+            EntryType::Dip,
+            1,
+            OrderState::Dip {
+                limit_price: 0.9,
+                expires_at: now,
+            },
+        ));
+        assert_eq!(orders.expire(now).len(), 1);
+        assert!(!orders.is_working("mint"));
+
+        orders.place(order(
+            EntryType::Twap,
+            3,
+            OrderState::Slicing {
+                filled: 1,
+                next_at: now,
+            },
+        ));
+        assert_eq!(orders.due(now)[0].slice_order_id(1), "order");
+        assert_eq!(orders.order_ids(), vec!["order".to_string()]);
+        assert!(orders.cancel("order").is_some());
+        assert!(orders.due(now).is_empty());
+        assert!(orders.cancel("order").is_none());
+    }
+}
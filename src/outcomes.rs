@@ -0,0 +1,221 @@
+//! Labels every recorded token with what became of it, `OUTCOME_DELAY_HOURS` after it was
+//! seen, in the `outcomes` table, as a dataset for evaluating filters and training models
+//!
+//! A token gets the first label that applies:
+//! - `rugged`: liquidity pulled from its pool, or the price down 90% or more
+//! - `died`: no DEX pair left, or the price down 50% or more
+//! - `10x` / `2x`: peaked at 10x / 2x the price it was seen at
+//! - `graduated`: left the pump.fun bonding curve for a DEX pool
+//! - `flat`: none of the above
+//!
+//! The peak is the highest of the current price and any price the bot recorded for the token
+//! (position marks and backfilled history), so spikes on tokens never held are missed. With
+//! TimescaleDB it is read from the one-minute candles rather than every recorded price.
+//!
+//! Each label is also added to the token creator's `dev_wallets` row.
+
+use crate::db::FIRST_EVENTS;
+use crate::dev_wallets;
+use crate::models::DexPairInfo;
+use crate::scanner::{Scanner, TokenDataProvider};
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use std::time::Duration;
+
+/// Pool liquidity below which a token counts as rugged
+const RUG_LIQUIDITY_USD: f64 = 100.0;
+/// Tokens labeled per pass
+const BATCH_SIZE: i64 = 50;
+const PASS_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub label: &'static str,
+    pub final_price: Option<f64>,
+    /// Peak and final price as multiples of the price the token was seen at
+    pub peak_multiple: f64,
+    pub final_multiple: f64,
+    pub liquidity_usd: Option<f64>,
+    pub graduated: bool,
+}
+
+/// Label a token first seen at `base_price` from its current DEX pair and the highest
+/// price recorded for it
+pub fn label(base_price: f64, recorded_peak: Option<f64>, pair: Option<&DexPairInfo>) -> Outcome {
+    let final_price = pair.and_then(|p| p.price_usd);
+    let liquidity_usd = pair.and_then(|p| p.liquidity_usd);
+    let graduated = pair
+        .and_then(|p| p.dex_id.as_deref())
+        .is_some_and(|dex_id| dex_id != "pumpfun");
+    let final_multiple = final_price.unwrap_or(0.0) / base_price;
+    let peak_multiple = recorded_peak
+        .map_or(final_multiple, |peak| {
+            (peak / base_price).max(final_multiple)
+        })
+        .max(1.0);
+
+    let rugged = pair.is_some()
+        && (liquidity_usd.is_some_and(|l| l < RUG_LIQUIDITY_USD) || final_multiple <= 0.1);
+    let label = if rugged {
+        "rugged"
+    } else if pair.is_none() || final_multiple <= 0.5 {
+        "died"
+    } else if peak_multiple >= 10.0 {
+        "10x"
+    } else if peak_multiple >= 2.0 {
+        "2x"
+    } else if graduated {
+        "graduated"
+    } else {
+        "flat"
+    };
+    Outcome {
+        label,
+        final_price,
+        peak_multiple,
+        final_multiple,
+        liquidity_usd,
+        graduated,
+    }
+}
+
+/// Labels tokens once they are old enough, until the process exits
+#[derive(Clone)]
+pub struct Labeler {
+    pool: PgPool,
+    scanner: Scanner,
+    delay: chrono::Duration,
+    /// Whether the TimescaleDB candle aggregates exist
+    candles: bool,
+}
+
+impl Labeler {
+    pub fn new(pool: PgPool, scanner: Scanner, delay: chrono::Duration, candles: bool) -> Self {
+        Self {
+            pool,
+            scanner,
+            delay,
+            candles,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(PASS_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match self.label_due().await {
+                Ok(0) => {}
+                Ok(n) => println!("[outcomes] Labeled {} tokens", n),
+                Err(e) => println!("[outcomes] Labeling failed: {}", e),
+            }
+        }
+    }
+
+    /// Label up to a batch of unlabeled tokens seen at least the delay ago; returns how many
+    /// were labeled
+    async fn label_due(&self) -> Result<usize> {
+        let (prices, marks, column) = if self.candles {
+            ("price_candles_1m", "mark_candles_1m", "high")
+        } else {
+            ("price_history", "position_marks", "price_usd")
+        };
+        let due = sqlx::query(&format!(
+            "SELECT e.id, e.base_price, e.dev_wallet, GREATEST( \
+             (SELECT MAX({column}) FROM {prices} WHERE token_id = e.id), \
+             (SELECT MAX({column}) FROM {marks} WHERE token_id = e.id)) AS peak \
+             FROM {first_events} e \
+             WHERE e.base_price > 0 AND e.generated_at <= NOW() - make_interval(secs => $1) \
+             AND NOT EXISTS (SELECT 1 FROM outcomes o WHERE o.token_id = e.id) \
+             ORDER BY e.generated_at LIMIT $2",
+            first_events = FIRST_EVENTS
+        ))
+        .bind(self.delay.num_seconds() as f64)
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut labeled = 0;
+        for row in due {
+            let token_id: String = row.get("id");
+            // A failed lookup is retried next pass rather than labeled as dead
+            let Ok(pairs) = self.scanner.query_dexscreener_pair(&token_id).await else {
+                continue;
+            };
+            let pair = pairs
+                .and_then(|d| d.pairs)
+                .and_then(|p| p.into_iter().next());
+            let outcome = label(row.get("base_price"), row.get("peak"), pair.as_ref());
+            // The creator's row counts each token once, with the outcome that was kept
+            let mut tx = self.pool.begin().await?;
+            let inserted = sqlx::query(
+                "INSERT INTO outcomes (token_id, outcome, final_price, peak_multiple, \
+                 final_multiple, liquidity_usd, graduated) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                 ON CONFLICT (token_id) DO NOTHING",
+            )
+            .bind(&token_id)
+            .bind(outcome.label)
+            .bind(outcome.final_price)
+            .bind(outcome.peak_multiple)
+            .bind(outcome.final_multiple)
+            .bind(outcome.liquidity_usd)
+            .bind(outcome.graduated)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0;
+            if inserted && let Some(dev) = row.get::<Option<String>, _>("dev_wallet") {
+                dev_wallets::record_outcome(&mut *tx, &dev, &outcome).await?;
+            }
+            tx.commit().await?;
+            labeled += 1;
+        }
+        Ok(labeled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_by_liquidity_and_price_multiple() {
+        let pair = |dex_id: &str, price: f64, liquidity: f64| DexPairInfo {
+            dex_id: Some(dex_id.to_string()),
+            liquidity_usd: Some(liquidity),
+            price_usd: Some(price),
+            volume_m5_usd: None,
+            market_cap_usd: None,
+        };
+        let labeled = |peak, pair: Option<&DexPairInfo>| label(1.0, peak, pair).label;
+
+        assert_eq!(labeled(None, Some(&pair("raydium", 0.9, 20.0))), "rugged");
+        // A 10x that was later rugged is still a rug
+        assert_eq!(
+            labeled(Some(12.0), Some(&pair("pumpfun", 0.05, 900.0))),
+            "rugged"
+        );
+        assert_eq!(labeled(None, None), "died");
+        assert_eq!(
+            labeled(Some(1.5), Some(&pair("pumpfun", 0.3, 900.0))),
+            "died"
+        );
+        assert_eq!(
+            labeled(Some(11.0), Some(&pair("raydium", 4.0, 90_000.0))),
+            "10x"
+        );
+        assert_eq!(labeled(None, Some(&pair("pumpfun", 2.5, 9_000.0))), "2x");
+        assert_eq!(
+            labeled(None, Some(&pair("raydium", 1.2, 60_000.0))),
+            "graduated"
+        );
+        assert_eq!(
+            labeled(Some(1.4), Some(&pair("pumpfun", 0.8, 4_000.0))),
+            "flat"
+        );
+
+        let outcome = label(2.0, Some(5.0), Some(&pair("raydium", 3.0, 60_000.0)));
+        assert_eq!(outcome.peak_multiple, 2.5);
+        assert_eq!(outcome.final_multiple, 1.5);
+        assert!(outcome.graduated);
+    }
+}
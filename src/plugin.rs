@@ -0,0 +1,234 @@
+//! WASM scoring plugins
+//!
+//! A plugin replaces the built-in entry score and can veto entries without recompiling the
+//! bot. ABI version 1 — the module must export:
+//! - `memory`
+//! - `memebot_abi_version() -> i32`, returning `1`
+//! - `alloc(len: i32) -> i32`, returning a buffer the host writes the input JSON into
+//! - `score(ptr: i32, len: i32) -> f64`, returning the token's score
+//!
+//! and may export `should_enter(ptr: i32, len: i32) -> i32`, where `0` vetoes the entry.
+//! The input is `{"token": <TokenEvent>, "builtin_score": <f64>}`. Each call is limited by
+//! fuel and the module's memory is capped, so a runaway plugin fails instead of stalling
+//! the bot.
+
+use crate::strategy::TokenEvent;
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use wasmi::{
+    Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+
+pub const ABI_VERSION: i32 = 1;
+/// Fuel (roughly instructions) available to each call into the plugin
+const FUEL_PER_CALL: u64 = 10_000_000;
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    token: &'a TokenEvent,
+    builtin_score: f64,
+}
+
+/// The plugin's verdict on one token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluginVerdict {
+    pub score: f64,
+    pub allow: bool,
+}
+
+/// A compiled plugin module, instantiated once per strategy
+pub struct PluginModule {
+    engine: Engine,
+    module: Module,
+}
+
+impl PluginModule {
+    pub fn load(path: &str) -> Result<Self> {
+        let wasm = std::fs::read(path)
+            .map_err(|e| anyhow!("failed to read scoring plugin {}: {}", path, e))?;
+        Self::from_bytes(&wasm)
+    }
+
+    pub fn from_bytes(wasm: &[u8]) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)?;
+        Ok(Self { engine, module })
+    }
+
+    pub fn instantiate(&self) -> Result<ScoringPlugin> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .expect("fuel metering is enabled");
+        // Plugins get no host imports
+        let linker = Linker::<StoreLimits>::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "memebot_abi_version")
+            .map_err(|_| anyhow!("plugin does not export memebot_abi_version"))?
+            .call(&mut store, ())?;
+        if version != ABI_VERSION {
+            bail!(
+                "plugin ABI version {} is not supported (expected {})",
+                version,
+                ABI_VERSION
+            );
+        }
+
+        Ok(ScoringPlugin {
+            memory: instance
+                .get_memory(&store, "memory")
+                .ok_or_else(|| anyhow!("plugin does not export memory"))?,
+            alloc: typed_func(&instance, &store, "alloc")?,
+            score: typed_func(&instance, &store, "score")?,
+            should_enter: instance.get_typed_func(&store, "should_enter").ok(),
+            store,
+        })
+    }
+}
+
+fn typed_func<P, R>(
+    instance: &Instance,
+    store: &Store<StoreLimits>,
+    name: &str,
+) -> Result<TypedFunc<P, R>>
+where
+    P: wasmi::WasmParams,
+    R: wasmi::WasmResults,
+{
+    instance
+        .get_typed_func(store, name)
+        .map_err(|e| anyhow!("plugin export '{}': {}", name, e))
+}
+
+/// A live plugin instance with its own store, fuel, and memory
+pub struct ScoringPlugin {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    score: TypedFunc<(i32, i32), f64>,
+    should_enter: Option<TypedFunc<(i32, i32), i32>>,
+}
+
+impl ScoringPlugin {
+    pub fn evaluate(&mut self, token: &TokenEvent, builtin_score: f64) -> Result<PluginVerdict> {
+        let input = serde_json::to_vec(&PluginInput {
+            token,
+            builtin_score,
+        })?;
+        let len = i32::try_from(input.len())?;
+
+        self.store
+            .set_fuel(FUEL_PER_CALL)
+            .expect("fuel metering is enabled");
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|e| anyhow!("plugin buffer at {} is unusable: {}", ptr, e))?;
+        let score = self.score.call(&mut self.store, (ptr, len))?;
+        if !score.is_finite() {
+            bail!("plugin returned a non-finite score");
+        }
+        let allow = match &self.should_enter {
+            Some(f) => f.call(&mut self.store, (ptr, len))? != 0,
+            None => true,
+        };
+        Ok(PluginVerdict { score, allow })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(wat: &str) -> Result<ScoringPlugin> {
+        PluginModule::from_bytes(&wat::parse_str(wat).unwrap())?.instantiate()
+    }
+
+    fn token() -> TokenEvent {
+        serde_json::from_value(serde_json::json!({
+            "id": "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump",
+            "source": "pumpfun",
+            "token_type": "meme",
+            "market_cap_usd": 31200.0,
+            "dev_hold_pct": 4.0,
+            "liquidity_usd": 22000.0,
+            "holders": 40,
+            "upgradeable": false,
+            "freeze_authority": false,
+            "momentum": true,
+            "graduation": false,
+            "base_price": 0.0000312,
+            "dev_wallet_address": null,
+            "is_dev_known_rugger": false,
+            "token_age_secs": 120,
+            "launched_at": 1760599400,
+            "dev_initial_buy_pct": 3.5,
+            "bonding_curve_progress_pct": 15.13
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn scores_and_vetoes_through_the_abi() {
+        let mut plugin = plugin(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "memebot_abi_version") (result i32) i32.const 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "score") (param i32 i32) (result f64) f64.const 87.5)
+                ;; veto when the input JSON is longer than 4 KiB
+                (func (export "should_enter") (param i32 i32) (result i32)
+                    local.get 1 i32.const 4096 i32.lt_s))"#,
+        )
+        .unwrap();
+        let verdict = plugin.evaluate(&token(), 60.0).unwrap();
+        assert_eq!(
+            verdict,
+            PluginVerdict {
+                score: 87.5,
+                allow: true
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_other_abi_versions() {
+        let err = plugin(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "memebot_abi_version") (result i32) i32.const 2))"#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("ABI version 2"));
+    }
+
+    #[test]
+    fn runaway_plugins_run_out_of_fuel() {
+        let mut plugin = plugin(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "memebot_abi_version") (result i32) i32.const 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "score") (param i32 i32) (result f64)
+                    (loop $spin br $spin)
+                    f64.const 0))"#,
+        )
+        .unwrap();
+        let err = plugin.evaluate(&token(), 60.0).unwrap_err();
+        assert!(err.to_string().to_lowercase().contains("fuel"), "{}", err);
+    }
+}
@@ -0,0 +1,513 @@
+use crate::api::{BreakerDistance, PortfolioSnapshot, PositionExposure, RiskSnapshot};
+use crate::config::Config;
+use crate::cooldown::CooldownTracker;
+use crate::losing_streak::LosingStreak;
+use crate::orders::OrderManager;
+use crate::plugin::{PluginModule, ScoringPlugin};
+use crate::reentry::ReentryWatch;
+use crate::regime::{RegimeRules, RegimeTracker};
+use crate::score_model::ScoreModel;
+use crate::simulator::Portfolio;
+use crate::strategy::{FilterRejection, TokenEvent, TradeDecision, decide, decide_with_score};
+use crate::strategy_config::{Profiles, StrategyConfig};
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// One strategy running under the portfolio manager, with its own budget and state
+pub struct StrategySlot {
+    pub id: String,
+    /// Profile the strategy's config was built from
+    pub preset: String,
+    pub config: StrategyConfig,
+    pub portfolio: Portfolio,
+    pub cooldowns: CooldownTracker,
+    pub reentries: ReentryWatch,
+    /// Dip orders and split entries still being bought
+    pub orders: OrderManager,
+    pub streak: LosingStreak,
+    /// WASM plugin that replaces the built-in entry score
+    pub plugin: Option<ScoringPlugin>,
+    /// Outcome-trained model blended into the built-in score, with its weight (0.0-1.0)
+    pub model: Option<(Arc<ScoreModel>, f64)>,
+    /// Trades hypothetically, never through a wallet, for comparing against live strategies
+    pub shadow: bool,
+}
+
+impl StrategySlot {
+    pub fn new(id: &str, preset: &str, config: StrategyConfig) -> Self {
+        let mut portfolio = Portfolio::new(config.starting_sol_balance);
+        portfolio.update_drawdown(config.sol_usd_price);
+        Self {
+            id: id.to_string(),
+            preset: preset.to_string(),
+            config,
+            portfolio,
+            cooldowns: CooldownTracker::new(),
+            reentries: ReentryWatch::new(),
+            orders: OrderManager::new(),
+            streak: LosingStreak::new(),
+            plugin: None,
+            model: None,
+            shadow: false,
+        }
+    }
+
+    /// Entry decision for a token, blended with the score model and then scored by the plugin
+    /// when either is loaded
+    pub fn decide(&mut self, ev: &TokenEvent) -> TradeDecision {
+        let mut decision = decide(ev, &self.config);
+        if let Some((model, weight)) = &self.model {
+            let score = model.blend(ev, decision.score, *weight);
+            decision = decide_with_score(ev, &self.config, score);
+        }
+        if let Some(plugin) = self.plugin.as_mut() {
+            // A failing plugin blocks the entry rather than falling back to the built-in score
+            match plugin.evaluate(ev, decision.score) {
+                Ok(verdict) => {
+                    decision = decide_with_score(ev, &self.config, verdict.score);
+                    if !verdict.allow {
+                        decision.should_buy = false;
+                        decision
+                            .rejections
+                            .push(FilterRejection::new("plugin", "vetoed by scoring plugin"));
+                    }
+                }
+                Err(e) => {
+                    println!("[{}] Scoring plugin failed for {}: {}", self.id, ev.id, e);
+                    decision.should_buy = false;
+                    decision
+                        .rejections
+                        .push(FilterRejection::new("plugin_error", e.to_string()));
+                }
+            }
+        }
+        decision
+    }
+}
+
+/// Remaining room under the global limits, consumed as positions are opened
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct GlobalHeadroom {
+    pub positions_left: usize,
+    pub sol_left: f64,
+    /// Entries and SOL left in this run's budget; `None` when unlimited
+    pub run_entries_left: Option<usize>,
+    pub run_sol_left: Option<f64>,
+}
+
+impl GlobalHeadroom {
+    pub fn rejections(&self, sol_amount: f64) -> Vec<FilterRejection> {
+        let mut rejections = Vec::new();
+        if self.positions_left == 0 {
+            rejections.push(FilterRejection::new(
+                "global_positions",
+                "global position cap reached",
+            ));
+        }
+        if sol_amount > self.sol_left {
+            rejections.push(FilterRejection::new(
+                "global_exposure",
+                format!(
+                    "{:.3} SOL > {:.3} SOL left under the global limit",
+                    sol_amount, self.sol_left
+                ),
+            ));
+        }
+        if self.run_entries_left == Some(0) {
+            rejections.push(FilterRejection::new(
+                "run_trade_budget",
+                "max_trades_per_run reached",
+            ));
+        }
+        if let Some(left) = self.run_sol_left
+            && sol_amount > left
+        {
+            rejections.push(FilterRejection::new(
+                "run_spend_budget",
+                format!(
+                    "{:.3} SOL > {:.3} SOL left under max_sol_spent_per_run",
+                    sol_amount, left
+                ),
+            ));
+        }
+        rejections
+    }
+
+    pub fn consume(&mut self, sol_amount: f64) {
+        self.positions_left = self.positions_left.saturating_sub(1);
+        self.sol_left -= sol_amount;
+        self.run_entries_left = self.run_entries_left.map(|n| n.saturating_sub(1));
+        self.run_sol_left = self.run_sol_left.map(|sol| sol - sol_amount);
+    }
+
+    /// No further entries are possible this run (dust below 0.01 SOL doesn't count)
+    pub fn run_budget_exhausted(&self) -> bool {
+        self.run_entries_left == Some(0) || self.run_sol_left.is_some_and(|sol| sol < 0.01)
+    }
+}
+
+/// Runs several strategies side by side and enforces limits across all of them
+pub struct PortfolioManager {
+    pub slots: Vec<StrategySlot>,
+    pub max_total_positions: usize,
+    pub max_total_sol_at_risk: f64,
+    /// Entry budget per run, after which only exits are processed
+    pub max_trades_per_run: Option<usize>,
+    pub max_sol_spent_per_run: Option<f64>,
+    peak_equity_usd: f64,
+    /// Equity at the first snapshot of the current UTC day
+    day_start: Option<(NaiveDate, f64)>,
+    /// Market regime, shared by all strategies
+    pub regime: RegimeTracker,
+}
+
+impl PortfolioManager {
+    /// Build from a comma-separated list of `[id=]profile[:budget_sol]` specs,
+    /// e.g. `early_snipe:1.5,safe=conservative:1.5`
+    /// `cfg.param_overrides` are applied on top of each profile before budgets are set up
+    pub fn from_specs(specs: &str, profiles: &Profiles, cfg: &Config) -> Result<Self> {
+        let mut strategies: Vec<(&str, &str, StrategyConfig)> = Vec::new();
+        for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (id, rest) = match spec.split_once('=') {
+                Some((id, rest)) => (id, rest),
+                None => (spec.split(':').next().unwrap_or(spec), spec),
+            };
+            let (profile, budget) = match rest.split_once(':') {
+                Some((profile, budget)) => (profile, Some(budget.parse::<f64>()?)),
+                None => (rest, None),
+            };
+            let Some(mut config) = profiles.get(profile) else {
+                bail!(
+                    "unknown strategy profile '{}' (available: {})",
+                    profile,
+                    profiles.names().collect::<Vec<_>>().join(", ")
+                );
+            };
+            if let Some(budget) = budget {
+                config.starting_sol_balance = budget;
+            }
+            if strategies.iter().any(|(other, _, _)| *other == id) {
+                bail!("strategy '{}' listed more than once", id);
+            }
+            strategies.push((id, profile, config));
+        }
+        if strategies.is_empty() {
+            bail!("no strategies configured");
+        }
+        apply_overrides(&mut strategies, &cfg.param_overrides)?;
+        // Sweeping without a cap sets aside every profit above the starting balance
+        if cfg.sweep_address.is_some() {
+            for (_, _, config) in strategies.iter_mut() {
+                config
+                    .compound_cap_sol
+                    .get_or_insert(config.starting_sol_balance);
+            }
+        }
+        let slots = strategies
+            .into_iter()
+            .map(|(id, profile, config)| StrategySlot::new(id, profile, config))
+            .collect();
+
+        Ok(Self {
+            slots,
+            max_total_positions: cfg.max_total_positions,
+            max_total_sol_at_risk: cfg.max_total_sol_at_risk,
+            max_trades_per_run: cfg.max_trades_per_run,
+            max_sol_spent_per_run: cfg.max_sol_spent_per_run,
+            peak_equity_usd: 0.0,
+            day_start: None,
+            regime: RegimeTracker::new(RegimeRules::from_config(cfg)),
+        })
+    }
+
+    /// Give every strategy its own instance of a scoring plugin
+    pub fn load_plugin(&mut self, module: &PluginModule) -> Result<()> {
+        for slot in &mut self.slots {
+            slot.plugin = Some(module.instantiate()?);
+        }
+        Ok(())
+    }
+
+    /// Blend a score model into every strategy's score
+    pub fn load_model(&mut self, model: Arc<ScoreModel>, weight: f64) {
+        for slot in &mut self.slots {
+            slot.model = Some((model.clone(), weight));
+        }
+    }
+
+    /// Config used for strategy-independent bookkeeping (e.g. the score stored on token_events)
+    pub fn primary_config(&self) -> &StrategyConfig {
+        &self.slots[0].config
+    }
+
+    /// Reset the per-run entry budget for run `run_id`; open positions are kept
+    pub fn start_run(&mut self, run_id: i32) {
+        for slot in &mut self.slots {
+            slot.portfolio.run_entries = 0;
+            slot.portfolio.run_sol_spent = 0.0;
+            slot.portfolio.run_id = Some(run_id);
+        }
+    }
+
+    pub fn headroom(&self) -> GlobalHeadroom {
+        self.headroom_of(false)
+    }
+
+    /// Room left under the global limits among the live strategies, or among the shadow
+    /// ones, which are held to the same limits as if they traded on their own
+    pub fn headroom_of(&self, shadow: bool) -> GlobalHeadroom {
+        let slots = || self.slots.iter().filter(move |s| s.shadow == shadow);
+        let positions: usize = slots().map(|s| s.portfolio.positions.len()).sum();
+        let at_risk: f64 = slots()
+            .flat_map(|s| s.portfolio.positions.iter())
+            .map(|p| p.sol_in)
+            .sum();
+        GlobalHeadroom {
+            positions_left: self.max_total_positions.saturating_sub(positions),
+            sol_left: self.max_total_sol_at_risk - at_risk,
+            run_entries_left: self.max_trades_per_run.map(|max| {
+                let entries: usize = slots().map(|s| s.portfolio.run_entries).sum();
+                max.saturating_sub(entries)
+            }),
+            run_sol_left: self.max_sol_spent_per_run.map(|max| {
+                let spent: f64 = slots().map(|s| s.portfolio.run_sol_spent).sum();
+                max - spent
+            }),
+        }
+    }
+
+    /// Run the comma-separated strategy ids in shadow mode
+    pub fn set_shadow(&mut self, ids: &str) -> Result<()> {
+        for id in ids.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let Some(slot) = self.slots.iter_mut().find(|s| s.id == id) else {
+                bail!("--shadow names unknown strategy '{}'", id);
+            };
+            slot.shadow = true;
+        }
+        Ok(())
+    }
+
+    /// Aggregate snapshot across the live strategies
+    pub fn snapshot(&mut self) -> PortfolioSnapshot {
+        let mut total = PortfolioSnapshot::default();
+        for slot in self.slots.iter().filter(|s| !s.shadow) {
+            let s = slot.portfolio.snapshot(&slot.id, slot.config.sol_usd_price);
+            total.sol_balance += s.sol_balance;
+            total.equity_usd += s.equity_usd;
+            total.realized_pnl_usd += s.realized_pnl_usd;
+            total.unrealized_pnl_usd += s.unrealized_pnl_usd;
+            total.positions.extend(s.positions);
+            total.updated_at = s.updated_at;
+        }
+        self.peak_equity_usd = self.peak_equity_usd.max(total.equity_usd);
+        total.peak_equity_usd = self.peak_equity_usd;
+        total.regime = Some(self.regime.reading(Utc::now()));
+        if self.peak_equity_usd > 0.0 {
+            total.drawdown_pct = (self.peak_equity_usd - total.equity_usd) / self.peak_equity_usd;
+        }
+        total.risk = self.risk(total.sol_balance, total.equity_usd, Utc::now());
+        total
+    }
+
+    /// Exposure of the live strategies and their distance to the entry limits
+    fn risk(&mut self, sol_balance: f64, equity_usd: f64, now: DateTime<Utc>) -> RiskSnapshot {
+        let today = now.date_naive();
+        let day_start = match self.day_start {
+            Some((day, equity)) if day == today => equity,
+            _ => {
+                self.day_start = Some((today, equity_usd));
+                equity_usd
+            }
+        };
+        let live = || self.slots.iter().filter(|s| !s.shadow);
+        let mut positions: Vec<PositionExposure> = live()
+            .flat_map(|s| s.portfolio.positions.iter().map(move |p| (s, p)))
+            .map(|(s, p)| PositionExposure {
+                strategy_id: s.id.clone(),
+                token_id: p.token_id.clone(),
+                sol_in: p.sol_in,
+                value_usd: p.value_usd(),
+                equity_pct: if equity_usd > 0.0 {
+                    p.value_usd() / equity_usd
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        positions.sort_by(|a, b| b.value_usd.total_cmp(&a.value_usd));
+        RiskSnapshot {
+            sol_balance,
+            sol_at_risk: positions.iter().map(|p| p.sol_in).sum(),
+            usd_at_risk: positions.iter().map(|p| p.value_usd).sum(),
+            largest_position_pct: positions.first().map_or(0.0, |p| p.equity_pct),
+            positions,
+            daily_pnl_usd: equity_usd - day_start,
+            headroom: self.headroom(),
+            breakers: live()
+                .map(|s| BreakerDistance {
+                    strategy_id: s.id.clone(),
+                    stop_losses_left: s.streak.stop_losses_left(&s.config),
+                    drawdown_left_pct: s.streak.drawdown_left_pct(
+                        s.portfolio.equity_usd(s.config.sol_usd_price),
+                        &s.config,
+                    ),
+                })
+                .collect(),
+            updated_at: Some(now),
+        }
+    }
+}
+
+/// Apply `key=value` (every strategy) or `strategy_id.key=value` (one strategy) overrides
+fn apply_overrides(
+    strategies: &mut [(&str, &str, StrategyConfig)],
+    overrides: &[String],
+) -> Result<()> {
+    for o in overrides {
+        let Some((target, value)) = o.split_once('=') else {
+            bail!("override '{}' is not key=value", o);
+        };
+        let (strategy_id, key) = match target.split_once('.') {
+            Some((id, key)) => (Some(id), key),
+            None => (None, target),
+        };
+        if let Some(id) = strategy_id
+            && !strategies.iter().any(|(other, _, _)| *other == id)
+        {
+            bail!("override '{}' names unknown strategy '{}'", o, id);
+        }
+        for (id, _, config) in strategies.iter_mut() {
+            if strategy_id.is_none_or(|target| target == *id) {
+                config.set(key.trim(), value.trim())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{Position, test_position};
+
+    #[test]
+    fn run_budget_blocks_entries_once_spent() {
+        let mut headroom = GlobalHeadroom {
+            positions_left: 10,
+            sol_left: 5.0,
+            run_entries_left: Some(2),
+            run_sol_left: Some(0.6),
+        };
+        assert!(headroom.rejections(0.5).is_empty());
+        headroom.consume(0.5);
+        let rejections = headroom.rejections(0.5);
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].filter, "run_spend_budget");
+        headroom.consume(0.1);
+        assert!(headroom.run_budget_exhausted());
+        assert_eq!(headroom.rejections(0.0)[0].filter, "run_trade_budget");
+    }
+
+    fn manager(ids: &[&str]) -> PortfolioManager {
+        PortfolioManager {
+            slots: ids
+                .iter()
+                .map(|id| StrategySlot::new(id, "default", StrategyConfig::default()))
+                .collect(),
+            max_total_positions: 10,
+            max_total_sol_at_risk: 5.0,
+            max_trades_per_run: Some(3),
+            max_sol_spent_per_run: None,
+            peak_equity_usd: 0.0,
+            day_start: None,
+            regime: RegimeTracker::new(RegimeRules {
+                window: chrono::Duration::minutes(60),
+                sol_dump_pct: 5.0,
+                dead_launches_per_hour: 30.0,
+                hot_launches_per_hour: 300.0,
+                hot_graduation_pct: 1.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn builds_strategies_with_their_own_budgets() {
+        let profiles = Profiles::builtin();
+        let cfg = Config::from_env();
+        let manager =
+            PortfolioManager::from_specs("early_snipe:1.5, conservative", &profiles, &cfg).unwrap();
+        let ids: Vec<_> = manager.slots.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["early_snipe", "conservative"]);
+        assert_eq!(manager.slots[0].portfolio.sol_balance, 1.5);
+        assert_eq!(manager.slots[1].portfolio.sol_balance, 3.0);
+
+        for bad in ["", "missing", "default,default", "default:lots"] {
+            assert!(PortfolioManager::from_specs(bad, &profiles, &cfg).is_err());
+        }
+    }
+
+    #[test]
+    fn global_headroom_is_shared_by_every_strategy() {
+        let manager = manager(&["a", "b"]);
+        let mut headroom = manager.headroom();
+        assert_eq!(headroom.positions_left, 10);
+        assert!(headroom.rejections(1.0).is_empty());
+
+        headroom.consume(4.5);
+        let names: Vec<_> = headroom
+            .rejections(1.0)
+            .into_iter()
+            .map(|r| r.filter)
+            .collect();
+        assert_eq!(names, ["global_exposure"]);
+        assert_eq!(headroom.positions_left, 9);
+    }
+
+    #[test]
+    fn shadow_strategies_have_their_own_headroom() {
+        let mut manager = manager(&["live", "candidate"]);
+        assert!(manager.set_shadow("live,missing").is_err());
+        manager.slots[0].shadow = false;
+        manager.set_shadow("candidate").unwrap();
+        assert!(manager.slots[1].shadow);
+
+        manager.slots[0].portfolio.run_entries = 1;
+        manager.slots[1].portfolio.run_entries = 3;
+        assert_eq!(manager.headroom().run_entries_left, Some(2));
+        assert_eq!(manager.headroom_of(true).run_entries_left, Some(0));
+    }
+
+    #[test]
+    fn risk_tracks_concentration_and_daily_pnl() {
+        let mut manager = manager(&["live"]);
+        let slot = &mut manager.slots[0];
+        slot.config.pause_after_stop_losses = 3;
+        for (token_id, price) in [("small", 1.0), ("large", 3.0)] {
+            let mut live = Position::new_live_state(1.0, 1_000.0);
+            live.price_usd = price;
+            slot.portfolio.sol_balance -= 0.5;
+            let mut position = test_position(token_id, 1.0, 15.0, 0.5);
+            position.live = live;
+            slot.portfolio.positions.push(position);
+        }
+        let now = Utc::now();
+        let equity = manager.slots[0].portfolio.equity_usd(30.0);
+        let risk = manager.risk(0.0, equity, now);
+        assert_eq!(risk.positions[0].token_id, "large");
+        assert_eq!(risk.usd_at_risk, 60.0);
+        assert_eq!(risk.largest_position_pct, 45.0 / equity);
+        assert_eq!(risk.headroom.sol_left, 4.0);
+        assert_eq!(risk.breakers[0].stop_losses_left, Some(3));
+        assert_eq!(risk.breakers[0].drawdown_left_pct, None);
+
+        // The day's PnL counts from the first snapshot of the day
+        assert_eq!(manager.risk(0.0, equity - 10.0, now).daily_pnl_usd, -10.0);
+        let tomorrow = now + chrono::Duration::days(1);
+        assert_eq!(
+            manager.risk(0.0, equity - 10.0, tomorrow).daily_pnl_usd,
+            0.0
+        );
+    }
+}
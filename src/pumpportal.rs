@@ -0,0 +1,398 @@
+//! PumpPortal data WebSocket messages
+//!
+//! The feed mixes token creation events with subscription acks, errors, and events for
+//! other subscriptions. Field types also drift between numbers and numeric strings. Every
+//! text frame is classified here rather than probed ad hoc, and counted so schema changes
+//! show up in the logs instead of as silently missing listings.
+//!
+//! Each new token is also subscribed on the trade stream for the rest of the listen window.
+//! Its buys and sells, other than the creator's buy in the create transaction, are attached
+//! to the listing, and strategies measure momentum from them.
+
+use crate::models::PumpFunListing;
+use crate::strategy::TokenTrade;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// One text frame from the PumpPortal WebSocket
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum PumpPortalMessage {
+    /// A token creation event
+    NewToken(Box<NewTokenMessage>),
+    /// A trade or other event for an existing token
+    TokenEvent {
+        mint: String,
+        tx_type: String,
+        trader: Option<String>,
+        sol_amount: Option<f64>,
+    },
+    /// Subscription confirmation or other informational text
+    Ack(String),
+    /// Error reported by the server
+    Error(String),
+    /// Well-formed JSON with no recognised shape
+    Unknown(serde_json::Value),
+}
+
+/// Token creation event; only `mint` is required
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTokenMessage {
+    #[serde(deserialize_with = "scalar_string")]
+    pub mint: String,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub symbol: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub uri: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub image: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub trader_public_key: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub signature: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub bonding_curve_key: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub sol_amount: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub initial_buy: Option<f64>,
+    #[serde(default, alias = "marketCapSol", deserialize_with = "lenient_f64")]
+    pub market_cap: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub virtual_sol_reserves: Option<f64>,
+}
+
+impl PumpPortalMessage {
+    /// Classify a text frame; errors only for frames that aren't valid JSON or whose
+    /// creation event is missing a usable mint
+    pub fn parse(text: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let Some(obj) = value.as_object() else {
+            return Ok(PumpPortalMessage::Unknown(value));
+        };
+
+        if let Some(err) = obj.get("errors").or_else(|| obj.get("error")) {
+            let detail = err
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| err.to_string());
+            return Ok(PumpPortalMessage::Error(detail));
+        }
+
+        if obj.contains_key("mint") {
+            let tx_type = obj
+                .get("txType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("create");
+            if tx_type != "create" {
+                let tx_type = tx_type.to_string();
+                let event: TradeMessage = serde_json::from_value(value)?;
+                return Ok(PumpPortalMessage::TokenEvent {
+                    mint: event.mint,
+                    tx_type,
+                    trader: event.trader_public_key,
+                    sol_amount: event.sol_amount,
+                });
+            }
+            let msg: NewTokenMessage = serde_json::from_value(value)?;
+            if msg.mint.is_empty() {
+                bail!("creation event with empty mint");
+            }
+            return Ok(PumpPortalMessage::NewToken(Box::new(msg)));
+        }
+
+        if let Some(message) = obj.get("message").and_then(|v| v.as_str()) {
+            return Ok(PumpPortalMessage::Ack(message.to_string()));
+        }
+
+        Ok(PumpPortalMessage::Unknown(value))
+    }
+
+    /// The buy or sell a trade event records, received at `at_ms`
+    pub fn trade(&self, at_ms: i64) -> Option<(&str, TokenTrade)> {
+        let PumpPortalMessage::TokenEvent {
+            mint,
+            tx_type,
+            trader: Some(trader),
+            sol_amount: Some(sol_amount),
+        } = self
+        else {
+            return None;
+        };
+        let buy = match tx_type.as_str() {
+            "buy" => true,
+            "sell" => false,
+            _ => return None,
+        };
+        Some((
+            mint,
+            TokenTrade {
+                trader: trader.clone(),
+                buy,
+                sol_amount: *sol_amount,
+                at_ms,
+            },
+        ))
+    }
+}
+
+/// Fields of a trade event the trade stream is measured by
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TradeMessage {
+    #[serde(default, deserialize_with = "scalar_string")]
+    mint: String,
+    #[serde(default, deserialize_with = "lenient_string")]
+    trader_public_key: Option<String>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    sol_amount: Option<f64>,
+}
+
+/// Trades received per mint during one listen window
+#[derive(Debug, Default)]
+pub struct TradeFeed {
+    trades: HashMap<String, Vec<TokenTrade>>,
+}
+
+impl TradeFeed {
+    /// Keep `msg` if it is a buy or sell, received at `at_ms`
+    pub fn record(&mut self, msg: &PumpPortalMessage, at_ms: i64) {
+        if let Some((mint, trade)) = msg.trade(at_ms) {
+            self.trades.entry(mint.to_string()).or_default().push(trade);
+        }
+    }
+
+    /// Give each listing the trades received for its mint; a watched mint without any gets
+    /// an empty list
+    pub fn attach(mut self, listings: &mut [PumpFunListing]) {
+        for l in listings {
+            l.trades = Some(self.trades.remove(&l.token_address).unwrap_or_default());
+        }
+    }
+}
+
+impl From<NewTokenMessage> for PumpFunListing {
+    fn from(m: NewTokenMessage) -> Self {
+        PumpFunListing {
+            token_address: m.mint,
+            name: m.name,
+            symbol: m.symbol,
+            logo: m.uri.or(m.image),
+            decimals: Some("6".to_string()),
+            price_native: match (m.sol_amount, m.initial_buy) {
+                (Some(sol), Some(tokens)) if tokens > 0.0 => Some((sol / tokens).to_string()),
+                _ => None,
+            },
+            price_usd: None,
+            liquidity: m
+                .virtual_sol_reserves
+                .map(|l| (l / 1_000_000_000.0).to_string()),
+            fully_diluted_valuation: m.market_cap.map(|c| c.to_string()),
+            created_at: Some(chrono::Utc::now().timestamp().to_string()),
+            creator: m.trader_public_key,
+            signature: m.signature,
+            initial_buy: m.initial_buy.map(|b| b.to_string()),
+            bonding_curve: m.bonding_curve_key,
+            received_at: Some(std::time::Instant::now()),
+            trades: None,
+        }
+    }
+}
+
+/// Running counts of WebSocket frames by kind, shared across polls
+#[derive(Debug, Default)]
+pub struct MessageCounters {
+    /// Polls that connected and subscribed
+    pub connects: AtomicU64,
+    /// Polls that couldn't connect or subscribe
+    pub connect_failures: AtomicU64,
+    /// Connections that ended before the listen window did
+    pub disconnects: AtomicU64,
+    pub new_tokens: AtomicU64,
+    pub token_events: AtomicU64,
+    pub acks: AtomicU64,
+    pub errors: AtomicU64,
+    pub unknown: AtomicU64,
+    pub malformed: AtomicU64,
+    pub pings: AtomicU64,
+    pub binary: AtomicU64,
+}
+
+impl MessageCounters {
+    /// Count a parsed frame (or a parse failure)
+    pub fn record(&self, parsed: &Result<PumpPortalMessage>) {
+        let counter = match parsed {
+            Ok(PumpPortalMessage::NewToken(_)) => &self.new_tokens,
+            Ok(PumpPortalMessage::TokenEvent { .. }) => &self.token_events,
+            Ok(PumpPortalMessage::Ack(_)) => &self.acks,
+            Ok(PumpPortalMessage::Error(_)) => &self.errors,
+            Ok(PumpPortalMessage::Unknown(_)) => &self.unknown,
+            Err(_) => &self.malformed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for MessageCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        write!(
+            f,
+            "new_tokens={} token_events={} acks={} errors={} unknown={} malformed={} pings={} binary={}",
+            get(&self.new_tokens),
+            get(&self.token_events),
+            get(&self.acks),
+            get(&self.errors),
+            get(&self.unknown),
+            get(&self.malformed),
+            get(&self.pings),
+            get(&self.binary)
+        )
+    }
+}
+
+/// Accept any JSON scalar as a string; null and containers become `None`
+fn lenient_string<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Ok(match serde_json::Value::deserialize(d)? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    })
+}
+
+fn scalar_string<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    lenient_string(d).map(|s| s.unwrap_or_default())
+}
+
+/// Accept numbers and numeric strings; anything else becomes `None`
+fn lenient_f64<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    Ok(match serde_json::Value::deserialize(d)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .filter(|v| v.is_finite()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> Vec<String> {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/pumpportal/corpus.jsonl"
+        );
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_every_captured_payload_without_panicking() {
+        let counters = MessageCounters::default();
+        for line in corpus() {
+            counters.record(&PumpPortalMessage::parse(&line));
+        }
+        assert_eq!(
+            counters.to_string(),
+            "new_tokens=4 token_events=2 acks=2 errors=1 unknown=2 malformed=3 pings=0 binary=0"
+        );
+    }
+
+    #[test]
+    fn tolerates_string_numbers_and_nulls() {
+        let text = r#"{"signature":null,"mint":"Fq3Tc4ehXoxJ8cmy1w2MgWjp3DokzKGyfaAM6bxMpump","traderPublicKey":"4Fp8G6kVZcEr2wbYd8hV1o4W5i4kjTCVWpVC2bzvi1kK","txType":"create","initialBuy":"35000000.5","solAmount":"1","marketCapSol":28.9,"name":"","symbol":7}"#;
+        let PumpPortalMessage::NewToken(msg) = PumpPortalMessage::parse(text).unwrap() else {
+            panic!("expected a creation event");
+        };
+        assert_eq!(msg.initial_buy, Some(35_000_000.5));
+        assert_eq!(msg.sol_amount, Some(1.0));
+        assert_eq!(msg.market_cap, Some(28.9));
+        assert_eq!(msg.signature, None);
+        assert_eq!(msg.name, None);
+        assert_eq!(msg.symbol.as_deref(), Some("7"));
+
+        let listing: PumpFunListing = (*msg).into();
+        assert!(listing.price_native.is_some());
+    }
+
+    /// Replay the first `frames` frames of a recorded trade stream, two seconds apart
+    fn replay_trades(frames: usize) -> PumpFunListing {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/pumpportal/trades.jsonl"
+        );
+        let mut listings = Vec::new();
+        let mut feed = TradeFeed::default();
+        for (i, line) in std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .take(frames)
+            .enumerate()
+        {
+            match PumpPortalMessage::parse(line).unwrap() {
+                PumpPortalMessage::NewToken(msg) => listings.push((*msg).into()),
+                msg => feed.record(&msg, i as i64 * 2000),
+            }
+        }
+        feed.attach(&mut listings);
+        listings.remove(0)
+    }
+
+    #[test]
+    fn measures_momentum_from_recorded_trades() {
+        use crate::strategy::{TokenEvent, TradeFlow};
+        use crate::strategy_config::StrategyConfig;
+
+        let config = StrategyConfig::default();
+        // Six buys from five wallets and one sale; the creator's own buy doesn't count
+        let early: TokenEvent = replay_trades(10).into();
+        let trades = early.trades.as_deref().unwrap();
+        assert_eq!(trades.len(), 7);
+        let flow = TradeFlow::over(trades, config.momentum_window_secs);
+        assert!((flow.net_inflow_sol - 2.85).abs() < 1e-9);
+        assert_eq!(flow.buyers, 5);
+        assert!(early.has_momentum(&config));
+        let choosier = StrategyConfig {
+            momentum_min_buyers: 6,
+            ..StrategyConfig::default()
+        };
+        assert!(!early.has_momentum(&choosier));
+
+        // By the end of the feed the early buyers have sold most of it back
+        let faded: TokenEvent = replay_trades(usize::MAX).into();
+        let trades = faded.trades.as_deref().unwrap();
+        assert!((TradeFlow::over(trades, 30).net_inflow_sol - 0.3).abs() < 1e-9);
+        let last_10s = TradeFlow::over(trades, 10);
+        assert!((last_10s.net_inflow_sol + 2.3).abs() < 1e-9);
+        assert_eq!(last_10s.buyers, 1);
+        assert!(!faded.has_momentum(&config));
+
+        // An unwatched token keeps its recorded flag
+        let unwatched = TokenEvent {
+            trades: None,
+            momentum: true,
+            ..faded
+        };
+        assert!(unwatched.has_momentum(&config));
+    }
+
+    #[test]
+    fn trade_events_are_not_listings() {
+        let text = r#"{"mint":"Fq3Tc4ehXoxJ8cmy1w2MgWjp3DokzKGyfaAM6bxMpump","txType":"buy","solAmount":0.5}"#;
+        assert!(matches!(
+            PumpPortalMessage::parse(text).unwrap(),
+            PumpPortalMessage::TokenEvent { ref tx_type, .. } if tx_type == "buy"
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+//! Python bindings (`import memebot`), built with `maturin develop --release`
+//!
+//! Exposes the same `TokenEvent`, `StrategyConfig`, scoring, and backtest code the bot
+//! trades with, so parameters tuned in a notebook behave identically in production.
+
+use crate::backtest::{self, BacktestResult, BacktestToken};
+use crate::strategy::{self, DevStats, TokenEvent, TokenTrade};
+use crate::strategy_config::{Profiles, ScoreFactor, SizeTier, StrategyConfig};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+#[pymethods]
+impl TokenEvent {
+    /// Parse a token event as published to `<prefix>.tokens` on the event bus
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("TokenEvent(id={:?}, source={:?})", self.id, self.source)
+    }
+}
+
+#[pymethods]
+impl StrategyConfig {
+    /// The default strategy; override fields by assigning attributes
+    #[new]
+    fn py_new() -> Self {
+        Self::default()
+    }
+
+    /// A built-in profile (`default`, `early_snipe`, `conservative`, `aggressive`)
+    #[staticmethod]
+    #[pyo3(name = "preset")]
+    fn py_preset(name: &str) -> PyResult<Self> {
+        Self::preset(name)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown strategy profile '{}'", name)))
+    }
+
+    /// A profile from a TOML file with `[profiles.<name>]` tables
+    #[staticmethod]
+    fn from_profile(path: &str, name: &str) -> PyResult<Self> {
+        Profiles::load(path)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .get(name)
+            .ok_or_else(|| PyValueError::new_err(format!("no profile '{}' in {}", name, path)))
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[pyfunction]
+fn compute_score(event: &TokenEvent, config: &StrategyConfig) -> f64 {
+    event.compute_score(config)
+}
+
+/// `(should_buy, score, [(filter, detail), ...])`
+#[pyfunction]
+fn decide(event: &TokenEvent, config: &StrategyConfig) -> (bool, f64, Vec<(String, String)>) {
+    let decision = strategy::decide(event, config);
+    (
+        decision.should_buy,
+        decision.score,
+        decision
+            .rejections
+            .into_iter()
+            .map(|r| (r.filter, r.detail))
+            .collect(),
+    )
+}
+
+/// Replay `[(event, [price, ...]), ...]` through the entry and exit logic
+#[pyfunction]
+fn run_backtest(tokens: Vec<(TokenEvent, Vec<f64>)>, config: &StrategyConfig) -> BacktestResult {
+    let tokens: Vec<BacktestToken> = tokens
+        .into_iter()
+        .map(|(event, prices)| BacktestToken {
+            event,
+            prices,
+            observed_at: Vec::new(),
+            note: None,
+            tags: Vec::new(),
+        })
+        .collect();
+    backtest::run_backtest(&tokens, config)
+}
+
+#[pymodule]
+fn memebot(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<TokenEvent>()?;
+    m.add_class::<DevStats>()?;
+    m.add_class::<TokenTrade>()?;
+    m.add_class::<StrategyConfig>()?;
+    m.add_class::<ScoreFactor>()?;
+    m.add_class::<SizeTier>()?;
+    m.add_class::<backtest::BacktestTrade>()?;
+    m.add_class::<BacktestResult>()?;
+    m.add_function(wrap_pyfunction!(compute_score, m)?)?;
+    m.add_function(wrap_pyfunction!(decide, m)?)?;
+    m.add_function(wrap_pyfunction!(run_backtest, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Freshness-first ordering of listings waiting for enrichment
+//!
+//! Each poll can return more listings than the pipeline enriches before the next one. Rather
+//! than working through them in arrival order, the newest launches go first, and a listing
+//! that has waited longer than `MAX_LISTING_AGE_SECS` by the time its turn comes is dropped:
+//! by then snipers that saw it first have already moved the price. The age is checked again
+//! once the listing is enriched, right before it is decided on.
+
+use crate::models::PumpFunListing;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Running counts of listings let through or dropped, shared with the metrics endpoint
+#[derive(Debug, Default)]
+pub struct QueueCounters {
+    pub admitted: AtomicU64,
+    pub dropped_stale: AtomicU64,
+}
+
+impl fmt::Display for QueueCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "admitted={} dropped_stale={}",
+            self.admitted.load(Ordering::Relaxed),
+            self.dropped_stale.load(Ordering::Relaxed)
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ListingQueue {
+    /// 0 admits listings of any age
+    max_age_secs: u64,
+    counters: Arc<QueueCounters>,
+}
+
+impl ListingQueue {
+    pub fn new(max_age_secs: u64, counters: Arc<QueueCounters>) -> Self {
+        Self {
+            max_age_secs,
+            counters,
+        }
+    }
+
+    /// Newest listings first; listings without a receive time keep their place at the end
+    pub fn prioritize(&self, listings: &mut [PumpFunListing]) {
+        listings.sort_by_key(|l| std::cmp::Reverse(received_at(l).unwrap_or(i64::MIN)));
+    }
+
+    /// Whether `listing` is still fresh enough to enrich at `now` (unix seconds); stale
+    /// listings are counted as dropped
+    pub fn admit(&self, listing: &PumpFunListing, now: i64) -> bool {
+        let fresh = self.is_fresh(received_at(listing), now);
+        let counter = if fresh {
+            &self.counters.admitted
+        } else {
+            &self.counters.dropped_stale
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        fresh
+    }
+
+    /// Whether an admitted listing, received at `received`, is still fresh enough to decide
+    /// on at `now`, after its enrichment and the decisions ahead of it; stale listings are
+    /// counted as dropped
+    pub fn recheck(&self, received: Option<i64>, now: i64) -> bool {
+        let fresh = self.is_fresh(received, now);
+        if !fresh {
+            self.counters.dropped_stale.fetch_add(1, Ordering::Relaxed);
+        }
+        fresh
+    }
+
+    fn is_fresh(&self, received: Option<i64>, now: i64) -> bool {
+        self.max_age_secs == 0
+            || received
+                .is_none_or(|received| now.saturating_sub(received) <= self.max_age_secs as i64)
+    }
+}
+
+/// When the listing was received, which the scanner records in `created_at`
+pub fn received_at(listing: &PumpFunListing) -> Option<i64> {
+    listing.created_at.as_deref()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(mint: &str, received: Option<i64>) -> PumpFunListing {
+        PumpFunListing {
+            token_address: mint.to_string(),
+            created_at: received.map(|t| t.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn enriches_newest_first_and_drops_stale_listings() {
+        let counters = Arc::new(QueueCounters::default());
+        let queue = ListingQueue::new(30, counters.clone());
+        let mut listings = vec![
+            listing("old", Some(1_000)),
+            listing("unknown", None),
+            listing("new", Some(1_040)),
+        ];
+        queue.prioritize(&mut listings);
+        let order: Vec<&str> = listings.iter().map(|l| l.token_address.as_str()).collect();
+        assert_eq!(order, vec!["new", "old", "unknown"]);
+
+        let admitted: Vec<bool> = listings.iter().map(|l| queue.admit(l, 1_045)).collect();
+        assert_eq!(admitted, vec![true, false, true]);
+        assert_eq!(counters.to_string(), "admitted=2 dropped_stale=1");
+        assert!(ListingQueue::new(0, counters).admit(&listings[1], 1_045));
+    }
+
+    #[test]
+    fn drops_listings_that_went_stale_before_their_decision() {
+        let counters = Arc::new(QueueCounters::default());
+        let queue = ListingQueue::new(30, counters.clone());
+        let new = listing("new", Some(1_040));
+        assert!(queue.admit(&new, 1_045));
+        assert!(queue.recheck(Some(1_040), 1_070));
+        assert!(!queue.recheck(Some(1_040), 1_071));
+        assert!(queue.recheck(None, 1_071));
+        assert_eq!(counters.to_string(), "admitted=1 dropped_stale=1");
+    }
+}
@@ -0,0 +1,203 @@
+//! Startup reconciliation of recorded positions against on-chain balances
+//!
+//! After a crash the database can disagree with a wallet: a sell may have landed without being
+//! recorded, a partial sell may have left dust behind, or tokens may have arrived from outside
+//! the bot. Before live trading starts, each wallet's token accounts are compared with its
+//! trades. Positions that are gone on-chain are closed in the database and dust is burned so
+//! its account can be closed; anything else is only reported.
+
+use crate::executor::{LiveExecutor, TokenHolding};
+use anyhow::Result;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// A leftover balance up to this fraction of the last position is treated as dust
+const DUST_FRACTION: f64 = 0.01;
+
+/// A trade still open in the database
+pub struct OpenTrade {
+    pub id: i32,
+    pub token_id: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Discrepancy {
+    /// Open in the database, but the wallet holds none of the token
+    ClosedOnChain { trade_id: i32, mint: String },
+    /// A small balance left after the token's positions were closed
+    Dust { account: String, mint: String },
+    /// A balance left after the token's positions were closed that is too large to be dust
+    Unsold { mint: String, ui_amount: f64 },
+    /// A token the wallet never traded
+    Unknown { mint: String, ui_amount: f64 },
+}
+
+/// Compare `open` trades and the quantity of each mint's last `closed` position with the
+/// wallet's `holdings`
+pub fn discrepancies(
+    open: &[OpenTrade],
+    closed: &HashMap<String, f64>,
+    holdings: &[TokenHolding],
+) -> Vec<Discrepancy> {
+    let mut found: Vec<Discrepancy> = open
+        .iter()
+        .filter(|trade| !holdings.iter().any(|h| h.mint == trade.token_id))
+        .map(|trade| Discrepancy::ClosedOnChain {
+            trade_id: trade.id,
+            mint: trade.token_id.clone(),
+        })
+        .collect();
+    for holding in holdings {
+        if open.iter().any(|trade| trade.token_id == holding.mint) {
+            continue;
+        }
+        found.push(match closed.get(&holding.mint) {
+            Some(qty) if holding.ui_amount <= qty * DUST_FRACTION => Discrepancy::Dust {
+                account: holding.account.clone(),
+                mint: holding.mint.clone(),
+            },
+            Some(_) => Discrepancy::Unsold {
+                mint: holding.mint.clone(),
+                ui_amount: holding.ui_amount,
+            },
+            None => Discrepancy::Unknown {
+                mint: holding.mint.clone(),
+                ui_amount: holding.ui_amount,
+            },
+        });
+    }
+    found
+}
+
+/// Reconcile the wallet of `executor` with its recorded trades
+pub async fn reconcile_wallet(pool: &PgPool, executor: &LiveExecutor) -> Result<()> {
+    let wallet = executor.pubkey();
+    let holdings = executor.token_holdings().await?;
+    let open: Vec<OpenTrade> = sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, token_id FROM trades WHERE wallet=$1 AND closed_at IS NULL",
+    )
+    .bind(&wallet)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(id, token_id)| OpenTrade { id, token_id })
+    .collect();
+    let closed: HashMap<String, f64> = sqlx::query_as(
+        "SELECT DISTINCT ON (token_id) token_id, qty FROM trades \
+         WHERE wallet=$1 AND closed_at IS NOT NULL AND qty IS NOT NULL \
+         ORDER BY token_id, closed_at DESC",
+    )
+    .bind(&wallet)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let found = discrepancies(&open, &closed, &holdings);
+    if found.is_empty() {
+        println!(
+            "[reconcile] {}: {} open position(s) match on-chain balances",
+            wallet,
+            open.len()
+        );
+        return Ok(());
+    }
+    for discrepancy in found {
+        match discrepancy {
+            Discrepancy::ClosedOnChain { trade_id, mint } => {
+                println!(
+                    "[reconcile] {}: {} is open in the database but not held; closing trade {}",
+                    wallet, mint, trade_id
+                );
+                sqlx::query(
+                    "UPDATE trades SET action='RECONCILED', closed_at=NOW() \
+                     WHERE id=$1 AND closed_at IS NULL",
+                )
+                .bind(trade_id)
+                .execute(pool)
+                .await?;
+            }
+            Discrepancy::Dust { account, mint } => {
+                let Some(holding) = holdings.iter().find(|h| h.account == account) else {
+                    continue;
+                };
+                match executor.burn_and_close(holding).await {
+                    Ok(signature) => println!(
+                        "[reconcile] {}: burned {} {} of dust and closed {} ({})",
+                        wallet, holding.ui_amount, mint, account, signature
+                    ),
+                    Err(e) => println!(
+                        "[reconcile] {}: failed to burn dust of {}: {}",
+                        wallet, mint, e
+                    ),
+                }
+            }
+            Discrepancy::Unsold { mint, ui_amount } => println!(
+                "[reconcile] {}: still holds {} {} after its position closed; sell it manually",
+                wallet, ui_amount, mint
+            ),
+            Discrepancy::Unknown { mint, ui_amount } => println!(
+                "[reconcile] {}: holds {} of {}, which the bot never traded",
+                wallet, ui_amount, mint
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(mint: &str, ui_amount: f64) -> TokenHolding {
+        TokenHolding {
+            account: format!("{}-ata", mint),
+            mint: mint.to_string(),
+            amount: (ui_amount * 1e6) as u64,
+            ui_amount,
+            rent_lamports: 2_039_280,
+        }
+    }
+
+    #[test]
+    fn classifies_balances_against_trades() {
+        let open = vec![
+            OpenTrade {
+                id: 1,
+                token_id: "held".into(),
+            },
+            OpenTrade {
+                id: 2,
+                token_id: "sold".into(),
+            },
+        ];
+        let closed = HashMap::from([("dust".to_string(), 1_000.0), ("big".to_string(), 1_000.0)]);
+        let holdings = vec![
+            holding("held", 500.0),
+            holding("dust", 3.0),
+            holding("big", 400.0),
+            holding("airdrop", 42.0),
+        ];
+        assert_eq!(
+            discrepancies(&open, &closed, &holdings),
+            vec![
+                Discrepancy::ClosedOnChain {
+                    trade_id: 2,
+                    mint: "sold".into()
+                },
+                Discrepancy::Dust {
+                    account: "dust-ata".into(),
+                    mint: "dust".into()
+                },
+                Discrepancy::Unsold {
+                    mint: "big".into(),
+                    ui_amount: 400.0
+                },
+                Discrepancy::Unknown {
+                    mint: "airdrop".into(),
+                    ui_amount: 42.0
+                },
+            ]
+        );
+    }
+}
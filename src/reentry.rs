@@ -0,0 +1,142 @@
+use crate::models::DexPairInfo;
+use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// A previously exited winner being watched for a confirmed recovery
+#[derive(Debug, Clone)]
+pub struct ReentryCandidate {
+    pub token_id: String,
+    pub source: String,
+    /// Highest price we saw before exiting (the level it must reclaim)
+    pub prior_high_price: f64,
+    /// 5-minute volume at exit, the baseline for "renewed" volume
+    pub exit_volume_m5_usd: f64,
+    pub exited_at: DateTime<Utc>,
+    pub score: f64,
+    pub dev_wallet_address: Option<String>,
+    pub launched_at: Option<i64>,
+}
+
+/// Tracks exited winners and decides when one has recovered enough to buy back
+#[derive(Debug, Default)]
+pub struct ReentryWatch {
+    candidates: HashMap<String, ReentryCandidate>,
+    reentry_counts: HashMap<String, u32>,
+}
+
+impl ReentryWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start watching an exited winner, unless it already used up its re-entries
+    pub fn watch(&mut self, candidate: ReentryCandidate, config: &StrategyConfig) {
+        let count = self
+            .reentry_counts
+            .get(&candidate.token_id)
+            .copied()
+            .unwrap_or(0);
+        if count >= config.max_reentries_per_token {
+            return;
+        }
+        self.candidates
+            .insert(candidate.token_id.clone(), candidate);
+    }
+
+    /// Drop candidates whose watch window has expired and return the rest
+    pub fn candidates(
+        &mut self,
+        now: DateTime<Utc>,
+        config: &StrategyConfig,
+    ) -> Vec<ReentryCandidate> {
+        let window = Duration::seconds(config.reentry_window_secs as i64);
+        self.candidates.retain(|_, c| now - c.exited_at <= window);
+        self.candidates.values().cloned().collect()
+    }
+
+    /// A recovery is confirmed when price reclaims the prior high and 5-minute volume
+    /// has picked back up by the configured multiple of the volume at exit
+    pub fn is_confirmed(
+        &self,
+        candidate: &ReentryCandidate,
+        pair: &DexPairInfo,
+        config: &StrategyConfig,
+    ) -> bool {
+        let price = pair.price_usd.unwrap_or(0.0);
+        let volume = pair.volume_m5_usd.unwrap_or(0.0);
+        price > 0.0
+            && price >= candidate.prior_high_price
+            && volume > 0.0
+            && volume >= candidate.exit_volume_m5_usd * config.reentry_volume_multiplier
+    }
+
+    /// Record that a candidate was bought back and stop watching it
+    pub fn mark_reentered(&mut self, token_id: &str) {
+        self.candidates.remove(token_id);
+        *self.reentry_counts.entry(token_id.to_string()).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(token_id: &str, exited_at: DateTime<Utc>) -> ReentryCandidate {
+        ReentryCandidate {
+            token_id: token_id.to_string(),
+            source: "pumpportal".to_string(),
+            prior_high_price: 2.0,
+            exit_volume_m5_usd: 1_000.0,
+            exited_at,
+            score: 80.0,
+            dev_wallet_address: None,
+            launched_at: None,
+        }
+    }
+
+    fn pair(price_usd: f64, volume_m5_usd: f64) -> DexPairInfo {
+        DexPairInfo {
+            dex_id: None,
+            liquidity_usd: None,
+            price_usd: Some(price_usd),
+            volume_m5_usd: Some(volume_m5_usd),
+            market_cap_usd: None,
+        }
+    }
+
+    #[test]
+    fn confirms_a_recovery_on_price_and_volume() {
+        let config = StrategyConfig::default();
+        let watch = ReentryWatch::new();
+        let c = candidate("mint", Utc::now());
+        assert!(watch.is_confirmed(&c, &pair(2.1, 1_500.0), &config));
+        // Below the prior high, or without the volume pickup
+        assert!(!watch.is_confirmed(&c, &pair(1.9, 5_000.0), &config));
+        assert!(!watch.is_confirmed(&c, &pair(2.1, 1_200.0), &config));
+    }
+
+    #[test]
+    fn expires_candidates_and_caps_reentries() {
+        let config = StrategyConfig {
+            max_reentries_per_token: 1,
+            reentry_window_secs: 600,
+            ..StrategyConfig::default()
+        };
+        let now = Utc::now();
+        let mut watch = ReentryWatch::new();
+        watch.watch(candidate("fresh", now), &config);
+        watch.watch(candidate("stale", now - Duration::seconds(601)), &config);
+        let ids: Vec<_> = watch
+            .candidates(now, &config)
+            .into_iter()
+            .map(|c| c.token_id)
+            .collect();
+        assert_eq!(ids, ["fresh"]);
+
+        watch.mark_reentered("fresh");
+        assert!(watch.candidates(now, &config).is_empty());
+        watch.watch(candidate("fresh", now), &config);
+        assert!(watch.candidates(now, &config).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! Market regime from the SOL price trend, new launches per hour, and the share of launches
+//! already graduated, all over the last `REGIME_WINDOW_MINS`:
+//! - `dead`: SOL fell at least `REGIME_SOL_DUMP_PCT`, or fewer than
+//!   `REGIME_DEAD_LAUNCHES_PER_HOUR` launches were seen
+//! - `hot`: SOL isn't falling, at least `REGIME_HOT_LAUNCHES_PER_HOUR` launches were seen, and
+//!   at least `REGIME_HOT_GRADUATION_PCT` of them graduated
+//! - `neutral`: anything else, including the first minutes before launch rates mean anything
+//!
+//! Strategies scale their position size by `hot_regime_size_multiplier` and
+//! `dead_regime_size_multiplier`; a multiplier of 0 stands the strategy down.
+
+use crate::config::Config;
+use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Observation time needed before launch rates are trusted
+const WARMUP_MINS: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Regime {
+    Hot,
+    Neutral,
+    Dead,
+}
+
+impl Regime {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Regime::Hot => "hot",
+            Regime::Neutral => "neutral",
+            Regime::Dead => "dead",
+        }
+    }
+
+    /// Factor applied to a strategy's position size in this regime
+    pub fn size_multiplier(&self, config: &StrategyConfig) -> f64 {
+        match self {
+            Regime::Hot => config.hot_regime_size_multiplier,
+            Regime::Neutral => 1.0,
+            Regime::Dead => config.dead_regime_size_multiplier,
+        }
+    }
+}
+
+/// Thresholds of the regime rules
+#[derive(Debug, Clone)]
+pub struct RegimeRules {
+    pub window: Duration,
+    pub sol_dump_pct: f64,
+    pub dead_launches_per_hour: f64,
+    pub hot_launches_per_hour: f64,
+    pub hot_graduation_pct: f64,
+}
+
+impl RegimeRules {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            window: Duration::minutes(cfg.regime_window_mins as i64),
+            sol_dump_pct: cfg.regime_sol_dump_pct,
+            dead_launches_per_hour: cfg.regime_dead_launches_per_hour,
+            hot_launches_per_hour: cfg.regime_hot_launches_per_hour,
+            hot_graduation_pct: cfg.regime_hot_graduation_pct,
+        }
+    }
+}
+
+/// The regime with the measurements it was derived from
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeReading {
+    pub regime: Regime,
+    /// SOL price change over the window; unset with fewer than two prices
+    pub sol_change_pct: Option<f64>,
+    /// Unset while warming up
+    pub launches_per_hour: Option<f64>,
+    pub graduation_pct: Option<f64>,
+}
+
+impl fmt::Display for RegimeReading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_unknown = |v: Option<f64>, precision: usize| {
+            v.map_or("?".to_string(), |v| format!("{:.*}", precision, v))
+        };
+        write!(
+            f,
+            "{} (SOL {}%, {} launches/h, {}% graduated)",
+            self.regime.as_str(),
+            or_unknown(self.sol_change_pct, 1),
+            or_unknown(self.launches_per_hour, 0),
+            or_unknown(self.graduation_pct, 1)
+        )
+    }
+}
+
+/// Rolling market activity seen by the scanner
+#[derive(Debug)]
+pub struct RegimeTracker {
+    rules: RegimeRules,
+    /// First observation, for launch rates over less than a full window
+    started_at: Option<DateTime<Utc>>,
+    sol_prices: VecDeque<(DateTime<Utc>, f64)>,
+    /// Launches seen, with whether each had already graduated
+    launches: VecDeque<(DateTime<Utc>, bool)>,
+    current: Regime,
+}
+
+impl RegimeTracker {
+    pub fn new(rules: RegimeRules) -> Self {
+        Self {
+            rules,
+            started_at: None,
+            sol_prices: VecDeque::new(),
+            launches: VecDeque::new(),
+            current: Regime::Neutral,
+        }
+    }
+
+    pub fn regime(&self) -> Regime {
+        self.current
+    }
+
+    pub fn observe_sol_price(&mut self, price_usd: f64, now: DateTime<Utc>) {
+        self.started_at.get_or_insert(now);
+        self.sol_prices.push_back((now, price_usd));
+    }
+
+    pub fn observe_launch(&mut self, graduated: bool, now: DateTime<Utc>) {
+        self.started_at.get_or_insert(now);
+        self.launches.push_back((now, graduated));
+    }
+
+    /// Drop observations that left the window and reclassify; returns the new reading when
+    /// the regime changed
+    pub fn refresh(&mut self, now: DateTime<Utc>) -> Option<RegimeReading> {
+        let cutoff = now - self.rules.window;
+        while self.sol_prices.front().is_some_and(|(at, _)| *at < cutoff) {
+            self.sol_prices.pop_front();
+        }
+        while self.launches.front().is_some_and(|(at, _)| *at < cutoff) {
+            self.launches.pop_front();
+        }
+        let reading = self.reading(now);
+        if reading.regime == self.current {
+            return None;
+        }
+        self.current = reading.regime;
+        Some(reading)
+    }
+
+    pub fn reading(&self, now: DateTime<Utc>) -> RegimeReading {
+        let rules = &self.rules;
+        let sol_change_pct = match (self.sol_prices.front(), self.sol_prices.back()) {
+            (Some((first_at, first)), Some((last_at, last))) if first_at < last_at => {
+                Some((last - first) / first * 100.0)
+            }
+            _ => None,
+        };
+        let observed = self
+            .started_at
+            .map_or(Duration::zero(), |start| (now - start).min(rules.window));
+        let (launches_per_hour, graduation_pct) = if observed >= Duration::minutes(WARMUP_MINS) {
+            let launches = self.launches.len() as f64;
+            let graduated = self.launches.iter().filter(|(_, g)| *g).count() as f64;
+            (
+                Some(launches * 3600.0 / observed.num_seconds() as f64),
+                (launches > 0.0).then(|| graduated / launches * 100.0),
+            )
+        } else {
+            (None, None)
+        };
+
+        let dumping = sol_change_pct.is_some_and(|pct| pct <= -rules.sol_dump_pct);
+        let regime = if dumping
+            || launches_per_hour.is_some_and(|rate| rate < rules.dead_launches_per_hour)
+        {
+            Regime::Dead
+        } else if sol_change_pct.is_none_or(|pct| pct >= 0.0)
+            && launches_per_hour.is_some_and(|rate| rate >= rules.hot_launches_per_hour)
+            && graduation_pct.is_some_and(|pct| pct >= rules.hot_graduation_pct)
+        {
+            Regime::Hot
+        } else {
+            Regime::Neutral
+        };
+        RegimeReading {
+            regime,
+            sol_change_pct,
+            launches_per_hour,
+            graduation_pct,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_sol_trend_and_launch_activity() {
+        let mut tracker = RegimeTracker::new(RegimeRules {
+            window: Duration::minutes(60),
+            sol_dump_pct: 5.0,
+            dead_launches_per_hour: 30.0,
+            hot_launches_per_hour: 300.0,
+            hot_graduation_pct: 1.0,
+        });
+        let start = Utc::now();
+        let at = |mins: i64| start + Duration::minutes(mins);
+
+        // 10 launches a minute, one in fifty graduated
+        for minute in 0..20 {
+            for i in 0..10 {
+                tracker.observe_launch(minute % 5 == 0 && i == 0, at(minute));
+            }
+        }
+        tracker.observe_sol_price(150.0, at(0));
+        tracker.observe_sol_price(151.0, at(19));
+        assert!(tracker.refresh(at(5)).is_none());
+        let reading = tracker.refresh(at(20)).unwrap();
+        assert_eq!(reading.regime, Regime::Hot);
+        assert_eq!(reading.launches_per_hour, Some(600.0));
+        assert_eq!(reading.graduation_pct, Some(2.0));
+
+        // SOL dumps 6%
+        tracker.observe_sol_price(141.0, at(21));
+        let reading = tracker.refresh(at(21)).unwrap();
+        assert_eq!(reading.regime, Regime::Dead);
+        assert_eq!(
+            reading.to_string(),
+            "dead (SOL -6.0%, 571 launches/h, 2.0% graduated)"
+        );
+
+        // An hour later the dump has left the window, but so have the launches
+        tracker.observe_sol_price(142.0, at(82));
+        assert!(tracker.refresh(at(82)).is_none());
+        assert_eq!(tracker.regime(), Regime::Dead);
+        let reading = tracker.reading(at(82));
+        assert_eq!(reading.sol_change_pct, None);
+        assert_eq!(reading.launches_per_hour, Some(0.0));
+    }
+}
@@ -0,0 +1,229 @@
+//! Where signed transactions are sent
+//!
+//! Besides the cluster RPC, transactions can go to Jito's block engine, bloXroute's submit
+//! API, or any private relay that speaks JSON-RPC `sendTransaction` (Temporal/Nozomi style).
+//! `TX_ROUTES` picks a relay per transaction kind, e.g. `buy=jito,sell=rpc`. Confirmation
+//! is always tracked through the cluster RPC.
+
+use anyhow::{Result, anyhow, bail};
+use reqwest::Client;
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const DEFAULT_JITO_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/transactions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relay {
+    Rpc,
+    Jito,
+    Bloxroute,
+    /// JSON-RPC endpoint at `PRIVATE_RELAY_URL`
+    Private,
+}
+
+impl Relay {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relay::Rpc => "rpc",
+            Relay::Jito => "jito",
+            Relay::Bloxroute => "bloxroute",
+            Relay::Private => "private",
+        }
+    }
+}
+
+impl FromStr for Relay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rpc" => Ok(Relay::Rpc),
+            "jito" => Ok(Relay::Jito),
+            "bloxroute" => Ok(Relay::Bloxroute),
+            "private" => Ok(Relay::Private),
+            other => bail!(
+                "unknown relay '{}' (expected rpc, jito, bloxroute, or private)",
+                other
+            ),
+        }
+    }
+}
+
+/// Relay per transaction kind (`buy`, `sell`, `transfer`, `close`); `default=` sets the
+/// fallback, which is otherwise plain RPC
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Routes {
+    default: Relay,
+    by_kind: Vec<(String, Relay)>,
+}
+
+impl Default for Routes {
+    fn default() -> Self {
+        Self {
+            default: Relay::Rpc,
+            by_kind: Vec::new(),
+        }
+    }
+}
+
+impl Routes {
+    pub fn relay_for(&self, kind: &str) -> Relay {
+        self.by_kind
+            .iter()
+            .find(|(k, _)| k == kind)
+            .map(|(_, relay)| *relay)
+            .unwrap_or(self.default)
+    }
+
+    /// Every relay some transaction may be routed to, without repeats
+    pub fn relays(&self) -> Vec<Relay> {
+        let mut relays = vec![self.default];
+        for (_, relay) in &self.by_kind {
+            if !relays.contains(relay) {
+                relays.push(*relay);
+            }
+        }
+        relays
+    }
+}
+
+impl FromStr for Routes {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut routes = Routes::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (kind, relay) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("route '{}' must look like KIND=RELAY", entry))?;
+            let relay = relay.trim().parse()?;
+            match kind.trim() {
+                "default" => routes.default = relay,
+                kind @ ("buy" | "sell" | "transfer" | "close") => {
+                    routes.by_kind.push((kind.to_string(), relay))
+                }
+                other => bail!(
+                    "unknown transaction kind '{}' (expected buy, sell, transfer, close, or default)",
+                    other
+                ),
+            }
+        }
+        Ok(routes)
+    }
+}
+
+/// Endpoints of the non-RPC relays
+#[derive(Clone)]
+pub struct RelayClient {
+    client: Client,
+    routes: Routes,
+    jito_url: String,
+    bloxroute_url: Option<String>,
+    bloxroute_auth: Option<String>,
+    private_url: Option<String>,
+}
+
+impl RelayClient {
+    pub fn new(
+        routes: Routes,
+        jito_url: String,
+        bloxroute_url: Option<String>,
+        bloxroute_auth: Option<String>,
+        private_url: Option<String>,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+        Self {
+            client,
+            routes,
+            jito_url,
+            bloxroute_url,
+            bloxroute_auth,
+            private_url,
+        }
+    }
+
+    pub fn relay_for(&self, kind: &str) -> Relay {
+        self.routes.relay_for(kind)
+    }
+
+    /// Send a base64-encoded signed transaction through `relay`, which must not be `Rpc`
+    pub async fn send(&self, relay: Relay, tx_base64: &str) -> Result<()> {
+        match relay {
+            Relay::Rpc => bail!("plain RPC sends go through the executor"),
+            Relay::Jito => self.send_json_rpc(&self.jito_url, tx_base64).await,
+            Relay::Private => {
+                let url = self
+                    .private_url
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("PRIVATE_RELAY_URL is not set"))?;
+                self.send_json_rpc(url, tx_base64).await
+            }
+            Relay::Bloxroute => {
+                let (Some(url), Some(auth)) = (&self.bloxroute_url, &self.bloxroute_auth) else {
+                    bail!("BLOXROUTE_URL and BLOXROUTE_AUTH_HEADER must be set");
+                };
+                let body = serde_json::json!({
+                    "transaction": { "content": tx_base64 },
+                    "frontRunningProtection": true,
+                });
+                let resp = self
+                    .client
+                    .post(format!("{}/api/v2/submit", url.trim_end_matches('/')))
+                    .header("Authorization", auth)
+                    .json(&body)
+                    .send()
+                    .await?;
+                if !resp.status().is_success() {
+                    bail!(
+                        "bloXroute returned {}: {}",
+                        resp.status(),
+                        resp.text().await.unwrap_or_default()
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn send_json_rpc(&self, url: &str, tx_base64: &str) -> Result<()> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendTransaction",
+            "params": [tx_base64, { "encoding": "base64" }],
+        });
+        let resp: serde_json::Value = self
+            .client
+            .post(url)
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(err) = resp.get("error") {
+            bail!("relay {} rejected the transaction: {}", url, err);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_by_transaction_kind() {
+        let routes: Routes = "buy=jito, default=private".parse().unwrap();
+        assert_eq!(routes.relay_for("buy"), Relay::Jito);
+        assert_eq!(routes.relay_for("sell"), Relay::Private);
+        assert_eq!(Routes::default().relay_for("buy"), Relay::Rpc);
+        assert!("swap=jito".parse::<Routes>().is_err());
+        assert!("buy=flashbots".parse::<Routes>().is_err());
+    }
+}
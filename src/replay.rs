@@ -0,0 +1,106 @@
+//! `replay`: play a backtest file back through every strategy in recorded time
+//!
+//! The gaps between recorded prices are kept, divided by `--speed`, so an hour of launches
+//! plays in a minute at 60x and exits fire in the order and at the holding times they would
+//! have. Speed 0 plays back without waiting.
+
+use crate::portfolio_manager::PortfolioManager;
+use crate::storage::{Location, Storage};
+use anyhow::{Result, bail};
+use solana_sniper_bot::backtest::{BacktestToken, Replay, ReplayAction};
+use std::time::Duration;
+
+pub async fn run(
+    file: &Location,
+    speed: f64,
+    manager: &PortfolioManager,
+    storage: &Storage,
+) -> Result<()> {
+    if !(speed >= 0.0 && speed.is_finite()) {
+        bail!("--speed must be 0 or positive, got {}", speed);
+    }
+    let tokens: Vec<BacktestToken> = serde_json::from_slice(&storage.get(file).await?)?;
+    let mut replays: Vec<(&str, Replay)> = manager
+        .slots
+        .iter()
+        .map(|slot| (slot.id.as_str(), Replay::new(&tokens, &slot.config)))
+        .collect();
+    println!(
+        "Replaying {} tokens from {} at {}x",
+        tokens.len(),
+        file,
+        speed
+    );
+
+    // Every strategy plays the same timeline, so the first one sets the pace
+    let start = replays[0].1.next_at();
+    let mut clock = start;
+    while let Some(at) = replays[0].1.next_at() {
+        if let Some(prev) = clock
+            && speed > 0.0
+            && at > prev
+        {
+            tokio::time::sleep(Duration::from_secs_f64((at - prev) as f64 / speed)).await;
+        }
+        clock = Some(at);
+        let elapsed = at - start.unwrap_or(at);
+        let offset = format!(
+            "+{:02}:{:02}:{:02}",
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60
+        );
+        for (id, replay) in &mut replays {
+            for action in replay.step() {
+                match action {
+                    ReplayAction::Bought {
+                        token_id,
+                        price,
+                        sol_in,
+                    } => println!(
+                        "{} [{}] BUY {} at ${:.8} for {:.3} SOL",
+                        offset, id, token_id, price, sol_in
+                    ),
+                    ReplayAction::Sniped { token_id } => println!(
+                        "{} [{}] MISSED {}: snipers ahead moved the price past the slippage limit",
+                        offset, id, token_id
+                    ),
+                    ReplayAction::Slipped {
+                        token_id,
+                        moved_pct,
+                    } => println!(
+                        "{} [{}] ABORTED {}: the price moved {:+.1}% before the buy filled",
+                        offset, id, token_id, moved_pct
+                    ),
+                    ReplayAction::Sold(trade) => println!(
+                        "{} [{}] SELL {} at ${:.8} ({}) after {}s: PnL ${:.2}",
+                        offset,
+                        id,
+                        trade.token_id,
+                        trade.exit_price,
+                        trade.exit_reason,
+                        trade.held_secs.unwrap_or_default(),
+                        trade.pnl_usd
+                    ),
+                }
+            }
+        }
+    }
+
+    for (id, replay) in replays {
+        let result = replay.finish();
+        println!(
+            "[{}] {} trades, {} skipped, {} lost to snipers, {} aborted on slippage, win rate {:.0}%, PnL ${:.2} (buying every token: ${:.2}), final balance {:.3} SOL",
+            id,
+            result.trades.len(),
+            result.skipped,
+            result.sniped,
+            result.slipped,
+            result.win_rate * 100.0,
+            result.realized_pnl_usd,
+            result.every_token_pnl_usd,
+            result.final_sol_balance
+        );
+    }
+    Ok(())
+}
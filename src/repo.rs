@@ -0,0 +1,643 @@
+//! Typed access to the tables the decision loop writes, so the simulator and write-behind
+//! queue hold no SQL of their own
+//!
+//! `Store` sends them to Postgres, or keeps them in memory for `--no-db` runs.
+//!
+//! Queries are checked against the schema at compile time. The checked metadata is cached in
+//! `.sqlx/`, so building needs no database; after changing a query or its tables, refresh
+//! the cache with `cargo sqlx prepare` against a migrated database.
+
+use crate::blacklist::Blacklist;
+use crate::config::Config;
+use crate::dev_wallets::DevWallets;
+use crate::ledger;
+use crate::memory_store::MemoryStore;
+use crate::orders::OrderStatus;
+use crate::portfolio_manager::PortfolioManager;
+use crate::run_metadata::{RunRecord, config_snapshot};
+use crate::write_behind::{EventRow, MarkRow};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use std::sync::Arc;
+
+/// A buy to record as an open trade
+pub struct NewTrade<'a> {
+    pub token_id: &'a str,
+    pub entry_price: f64,
+    pub qty: f64,
+    pub usd_in: f64,
+    pub sol_in: f64,
+    pub score: f64,
+    pub is_reentry: bool,
+    pub strategy_id: &'a str,
+    /// `run_metadata` row of the run that opened the trade
+    pub run_id: Option<i32>,
+    pub preset: &'a str,
+    pub source: &'a str,
+    pub entry_signature: Option<&'a str>,
+    pub wallet: Option<&'a str>,
+    pub client_order_id: &'a str,
+    pub shadow: bool,
+    pub launch_hour: i16,
+    /// `market`, `dip`, or `twap`; see `orders::EntryType`
+    pub entry_type: &'a str,
+}
+
+/// A later slice bought into an open trade
+pub struct TradeSlice<'a> {
+    /// Client order ID of the trade's buy
+    pub client_order_id: &'a str,
+    /// Slices bought including this one
+    pub slice: i32,
+    pub qty: f64,
+    pub usd_in: f64,
+    pub sol_in: f64,
+}
+
+/// The sell closing a trade
+pub struct TradeExit<'a> {
+    /// Client order ID of the trade's buy
+    pub client_order_id: &'a str,
+    pub exit_price: f64,
+    pub pnl: f64,
+    pub exit_signature: Option<&'a str>,
+    pub exit_order_id: &'a str,
+    pub exit_reason: &'a str,
+}
+
+/// An order to record as it is placed
+pub struct NewOrder<'a> {
+    pub client_order_id: &'a str,
+    pub strategy_id: &'a str,
+    pub run_id: Option<i32>,
+    pub token_id: &'a str,
+    /// `buy` or `sell`
+    pub side: &'a str,
+    /// `market`, `dip`, `twap`, or `exit`
+    pub order_type: &'a str,
+    /// SOL a buy is sized at; `None` for sells
+    pub sol_amount: Option<f64>,
+    pub shadow: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An order moving to a new status
+pub struct OrderUpdate<'a> {
+    pub client_order_id: &'a str,
+    pub status: OrderStatus,
+    /// SOL spent or received by this fill, added to what the order already filled
+    pub filled_sol: f64,
+    pub signature: Option<&'a str>,
+    pub error: Option<&'a str>,
+}
+
+impl<'a> OrderUpdate<'a> {
+    pub fn new(client_order_id: &'a str, status: OrderStatus) -> Self {
+        Self {
+            client_order_id,
+            status,
+            filled_sol: 0.0,
+            signature: None,
+            error: None,
+        }
+    }
+}
+
+/// An order that hasn't reached a final status
+#[derive(Debug, Serialize)]
+pub struct OpenOrder {
+    pub client_order_id: String,
+    pub strategy_id: String,
+    pub token_id: String,
+    pub side: String,
+    pub order_type: String,
+    pub status: String,
+    pub sol_amount: Option<f64>,
+    pub filled_sol: f64,
+    pub created_at_secs: i64,
+    pub expires_at_secs: i64,
+}
+
+/// `trades` and `profit_sweeps`
+pub struct TradeRepo;
+
+impl TradeRepo {
+    /// Record a buy; a retried order that is already recorded is left as it is
+    pub async fn open<'e>(executor: impl PgExecutor<'e>, trade: &NewTrade<'_>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO trades (token_id, action, entry_price, qty, usd_in, opened_at, score, \
+             is_reentry, strategy_id, preset, source, entry_signature, wallet, client_order_id, \
+             sol_in, shadow, launch_hour, entry_type, run_id) \
+             VALUES ($1, 'BUY', $2, $3, $4, NOW(), $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, \
+             $16, $17) \
+             ON CONFLICT (client_order_id) DO NOTHING",
+            trade.token_id,
+            trade.entry_price,
+            trade.qty,
+            trade.usd_in,
+            trade.score,
+            trade.is_reentry,
+            trade.strategy_id,
+            trade.preset,
+            trade.source,
+            trade.entry_signature,
+            trade.wallet,
+            trade.client_order_id,
+            trade.sol_in,
+            trade.shadow,
+            trade.launch_hour,
+            trade.entry_type,
+            trade.run_id,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Add a slice to an open trade at their average entry price; a retried slice that is
+    /// already recorded is left out
+    pub async fn add_slice<'e>(
+        executor: impl PgExecutor<'e>,
+        slice: &TradeSlice<'_>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE trades SET qty = qty + $3, usd_in = usd_in + $4, sol_in = sol_in + $5, \
+             entry_price = (usd_in + $4) / NULLIF(qty + $3, 0), entry_slices = $2 \
+             WHERE client_order_id = $1 AND exit_order_id IS NULL AND entry_slices < $2",
+            slice.client_order_id,
+            slice.slice,
+            slice.qty,
+            slice.usd_in,
+            slice.sol_in,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the sell closing a trade; only its first exit is kept
+    pub async fn close<'e>(executor: impl PgExecutor<'e>, exit: &TradeExit<'_>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE trades SET action = 'SELL', exit_price = $2, pnl = $3, closed_at = NOW(), \
+             exit_signature = $4, exit_order_id = $5, exit_reason = $6 \
+             WHERE client_order_id = $1 AND exit_order_id IS NULL",
+            exit.client_order_id,
+            exit.exit_price,
+            exit.pnl,
+            exit.exit_signature,
+            exit.exit_order_id,
+            exit.exit_reason,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_sweep<'e>(
+        executor: impl PgExecutor<'e>,
+        strategy_id: &str,
+        sol: f64,
+        destination: &str,
+        signature: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO profit_sweeps (strategy_id, sol, destination, signature) \
+             VALUES ($1, $2, $3, $4)",
+            strategy_id,
+            sol,
+            destination,
+            signature,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+}
+
+/// `orders`
+pub struct OrderRepo;
+
+impl OrderRepo {
+    /// Record a new order as pending; a failed order placed again under its ID is reopened,
+    /// and any other already recorded one is left as it is
+    pub async fn open<'e>(executor: impl PgExecutor<'e>, order: &NewOrder<'_>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO orders (client_order_id, strategy_id, token_id, side, order_type, \
+             status, sol_amount, shadow, expires_at, run_id) \
+             VALUES ($1, $2, $3, $4, $5, 'pending', $6, $7, to_timestamp($8), $9) \
+             ON CONFLICT (client_order_id) DO UPDATE SET status = 'pending', error = NULL, \
+             expires_at = EXCLUDED.expires_at, updated_at = NOW() \
+             WHERE orders.status = 'failed'",
+            order.client_order_id,
+            order.strategy_id,
+            order.token_id,
+            order.side,
+            order.order_type,
+            order.sol_amount,
+            order.shadow,
+            order.expires_at.timestamp_millis() as f64 / 1000.0,
+            order.run_id,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Move an order on if its current status allows it; returns whether it moved
+    pub async fn update<'e>(
+        executor: impl PgExecutor<'e>,
+        update: &OrderUpdate<'_>,
+    ) -> Result<bool> {
+        let allowed: Vec<String> = update
+            .status
+            .allowed_from()
+            .iter()
+            .map(|s| s.as_str().to_string())
+            .collect();
+        let result = sqlx::query!(
+            "UPDATE orders SET status = $2, filled_sol = filled_sol + $3, \
+             signature = COALESCE($4, signature), error = $5, updated_at = NOW() \
+             WHERE client_order_id = $1 AND status = ANY($6)",
+            update.client_order_id,
+            update.status.as_str(),
+            update.filled_sol,
+            update.signature,
+            update.error,
+            &allowed,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Close orders still open past their expiry: submitted ones as failed, since their
+    /// outcome is unknown, and the rest as cancelled. Returns how many were closed.
+    pub async fn expire<'e>(executor: impl PgExecutor<'e>) -> Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE orders SET status = CASE WHEN status = 'submitted' THEN 'failed' \
+             ELSE 'cancelled' END, error = 'timed out', updated_at = NOW() \
+             WHERE status IN ('pending', 'submitted', 'partially_filled') AND expires_at < NOW()"
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Orders that haven't reached a final status, oldest first
+    pub async fn list_open(pool: &PgPool) -> Result<Vec<OpenOrder>> {
+        Ok(sqlx::query_as!(
+            OpenOrder,
+            "SELECT client_order_id, strategy_id, token_id, side, order_type, status, \
+             sol_amount, filled_sol, EXTRACT(EPOCH FROM created_at)::BIGINT AS \"created_at_secs!\", \
+             EXTRACT(EPOCH FROM expires_at)::BIGINT AS \"expires_at_secs!\" FROM orders \
+             WHERE status IN ('pending', 'submitted', 'partially_filled') ORDER BY created_at"
+        )
+        .fetch_all(pool)
+        .await?)
+    }
+}
+
+/// `token_events` and `price_history`
+pub struct EventRepo;
+
+impl EventRepo {
+    /// Insert token events in one statement, timestamped when they were queued; a mint
+    /// already recorded gets another row for the new observation
+    pub async fn insert<'e>(executor: impl PgExecutor<'e>, rows: &[EventRow]) -> sqlx::Result<()> {
+        let mut ids = Vec::with_capacity(rows.len());
+        let mut ages = Vec::with_capacity(rows.len());
+        let mut token_types = Vec::with_capacity(rows.len());
+        let mut market_caps = Vec::with_capacity(rows.len());
+        let mut dev_holds = Vec::with_capacity(rows.len());
+        let mut liquidities = Vec::with_capacity(rows.len());
+        let mut holders = Vec::with_capacity(rows.len());
+        let mut upgradeable = Vec::with_capacity(rows.len());
+        let mut freeze_authority = Vec::with_capacity(rows.len());
+        let mut momentum = Vec::with_capacity(rows.len());
+        let mut graduation = Vec::with_capacity(rows.len());
+        let mut base_prices = Vec::with_capacity(rows.len());
+        let mut scores = Vec::with_capacity(rows.len());
+        let mut dev_wallets = Vec::with_capacity(rows.len());
+        let mut first_block_buys = Vec::with_capacity(rows.len());
+        let mut trades = Vec::with_capacity(rows.len());
+        let mut run_ids = Vec::with_capacity(rows.len());
+        for row in rows {
+            let ev = &row.event;
+            ids.push(ev.id.clone());
+            ages.push(row.queued_at.elapsed().as_secs_f64());
+            token_types.push(ev.token_type.clone());
+            market_caps.push(ev.market_cap_usd);
+            dev_holds.push(ev.dev_hold_pct);
+            liquidities.push(ev.liquidity_usd);
+            holders.push(ev.holders);
+            upgradeable.push(ev.upgradeable);
+            freeze_authority.push(ev.freeze_authority);
+            momentum.push(ev.momentum);
+            graduation.push(ev.graduation);
+            base_prices.push(ev.base_price);
+            scores.push(row.score);
+            dev_wallets.push(ev.dev_wallet_address.clone());
+            first_block_buys.push(ev.first_block_buys.map(|n| n as i32));
+            trades.push(
+                ev.trades
+                    .as_ref()
+                    .and_then(|t| serde_json::to_string(t).ok()),
+            );
+            run_ids.push(row.run_id);
+        }
+        sqlx::query!(
+            "INSERT INTO token_events (id, generated_at, token_type, market_cap_usd, \
+             dev_hold_pct, liquidity_usd, holders, upgradeable, freeze_authority, momentum, \
+             graduation, base_price, score, dev_wallet, first_block_buys, trades, run_id) \
+             SELECT id, NOW() - make_interval(secs => age), token_type, market_cap_usd, \
+             dev_hold_pct, liquidity_usd, holders, upgradeable, freeze_authority, momentum, \
+             graduation, base_price, score, dev_wallet, first_block_buys, trades::JSONB, run_id \
+             FROM UNNEST($1::TEXT[], $2::FLOAT8[], $3::TEXT[], $4::FLOAT8[], $5::FLOAT8[], \
+             $6::FLOAT8[], $7::INT4[], $8::BOOL[], $9::BOOL[], $10::BOOL[], $11::BOOL[], \
+             $12::FLOAT8[], $13::FLOAT8[], $14::TEXT[], $15::INT4[], $16::TEXT[], $17::INT4[]) \
+             AS r(id, age, token_type, market_cap_usd, dev_hold_pct, liquidity_usd, holders, \
+             upgradeable, freeze_authority, momentum, graduation, base_price, score, dev_wallet, \
+             first_block_buys, trades, run_id)",
+            &ids,
+            &ages,
+            &token_types,
+            &market_caps,
+            &dev_holds,
+            &liquidities,
+            &holders,
+            &upgradeable,
+            &freeze_authority,
+            &momentum,
+            &graduation,
+            &base_prices,
+            &scores,
+            &dev_wallets as &[Option<String>],
+            &first_block_buys as &[Option<i32>],
+            &trades as &[Option<String>],
+            &run_ids as &[Option<i32>],
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a token's price as observed now by `source`
+    pub async fn record_price<'e>(
+        executor: impl PgExecutor<'e>,
+        token_id: &str,
+        price_usd: f64,
+        source: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO price_history (token_id, observed_at, price_usd, source) \
+             VALUES ($1, NOW(), $2, $3) ON CONFLICT DO NOTHING",
+            token_id,
+            price_usd,
+            source,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+}
+
+/// `position_marks`
+pub struct PositionRepo;
+
+impl PositionRepo {
+    /// Insert position marks in one statement, timestamped when they were queued
+    pub async fn insert_marks<'e>(
+        executor: impl PgExecutor<'e>,
+        rows: &[MarkRow],
+    ) -> sqlx::Result<()> {
+        let mut token_ids = Vec::with_capacity(rows.len());
+        let mut ages = Vec::with_capacity(rows.len());
+        let mut prices = Vec::with_capacity(rows.len());
+        let mut values = Vec::with_capacity(rows.len());
+        let mut unrealized = Vec::with_capacity(rows.len());
+        let mut equity = Vec::with_capacity(rows.len());
+        let mut drawdowns = Vec::with_capacity(rows.len());
+        let mut strategy_ids = Vec::with_capacity(rows.len());
+        let mut run_ids = Vec::with_capacity(rows.len());
+        for row in rows {
+            token_ids.push(row.token_id.clone());
+            ages.push(row.queued_at.elapsed().as_secs_f64());
+            prices.push(row.price_usd);
+            values.push(row.value_usd);
+            unrealized.push(row.unrealized_pnl);
+            equity.push(row.equity_usd);
+            drawdowns.push(row.drawdown_pct);
+            strategy_ids.push(row.strategy_id.clone());
+            run_ids.push(row.run_id);
+        }
+        sqlx::query!(
+            "INSERT INTO position_marks (token_id, marked_at, price_usd, value_usd, \
+             unrealized_pnl, equity_usd, drawdown_pct, strategy_id, run_id) \
+             SELECT token_id, NOW() - make_interval(secs => age), price_usd, value_usd, \
+             unrealized_pnl, equity_usd, drawdown_pct, strategy_id, run_id \
+             FROM UNNEST($1::TEXT[], $2::FLOAT8[], $3::FLOAT8[], $4::FLOAT8[], $5::FLOAT8[], \
+             $6::FLOAT8[], $7::FLOAT8[], $8::TEXT[], $9::INT4[]) AS r(token_id, age, price_usd, \
+             value_usd, unrealized_pnl, equity_usd, drawdown_pct, strategy_id, run_id)",
+            &token_ids,
+            &ages,
+            &prices,
+            &values,
+            &unrealized,
+            &equity,
+            &drawdowns,
+            &strategy_ids,
+            &run_ids as &[Option<i32>],
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Where a run's rows are stored
+#[derive(Clone)]
+pub enum Store {
+    Postgres(PgPool),
+    /// Nothing outlives the process; `--no-db`
+    Memory(Arc<MemoryStore>),
+}
+
+impl Store {
+    pub async fn start_run(&self, cfg: &Config, manager: &PortfolioManager) -> Result<RunRecord> {
+        match self {
+            Store::Postgres(pool) => RunRecord::start(pool, cfg, manager).await,
+            Store::Memory(memory) => Ok(RunRecord {
+                id: memory.start_run(config_snapshot(cfg, manager)),
+            }),
+        }
+    }
+
+    pub async fn finish_run(
+        &self,
+        run: &RunRecord,
+        events_seen: usize,
+        trades_made: usize,
+    ) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => run.finish(pool, events_seen, trades_made).await,
+            Store::Memory(memory) => {
+                memory.finish_run(run.id, events_seen, trades_made);
+                Ok(())
+            }
+        }
+    }
+
+    /// Creator histories; empty without a database
+    pub async fn load_dev_wallets(&self) -> Result<DevWallets> {
+        match self {
+            Store::Postgres(pool) => DevWallets::load(pool).await,
+            Store::Memory(_) => Ok(DevWallets::default()),
+        }
+    }
+
+    /// The blacklist; empty without a database
+    pub async fn load_blacklist(&self) -> Result<Blacklist> {
+        match self {
+            Store::Postgres(pool) => Blacklist::load(pool).await,
+            Store::Memory(_) => Ok(Blacklist::default()),
+        }
+    }
+
+    pub async fn insert_events(&self, rows: &[EventRow]) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => Ok(EventRepo::insert(pool, rows).await?),
+            Store::Memory(memory) => {
+                memory.insert_events(rows);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn insert_marks(&self, rows: &[MarkRow]) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => Ok(PositionRepo::insert_marks(pool, rows).await?),
+            Store::Memory(memory) => {
+                memory.insert_marks(rows);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn record_price(&self, token_id: &str, price_usd: f64, source: &str) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => {
+                EventRepo::record_price(pool, token_id, price_usd, source).await
+            }
+            Store::Memory(memory) => {
+                memory.record_price(token_id, price_usd, source);
+                Ok(())
+            }
+        }
+    }
+
+    /// Record a buy and its ledger entry together
+    pub async fn open_trade(&self, trade: &NewTrade<'_>, entry: &ledger::Entry<'_>) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => {
+                let mut tx = pool.begin().await?;
+                TradeRepo::open(&mut *tx, trade).await?;
+                ledger::record(&mut *tx, entry).await?;
+                tx.commit().await?;
+            }
+            Store::Memory(memory) => memory.open_trade(trade, entry),
+        }
+        Ok(())
+    }
+
+    /// Record a slice added to an open trade and its ledger entry together
+    pub async fn add_slice(&self, slice: &TradeSlice<'_>, entry: &ledger::Entry<'_>) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => {
+                let mut tx = pool.begin().await?;
+                TradeRepo::add_slice(&mut *tx, slice).await?;
+                ledger::record(&mut *tx, entry).await?;
+                tx.commit().await?;
+            }
+            Store::Memory(memory) => memory.add_slice(slice, entry),
+        }
+        Ok(())
+    }
+
+    /// Record a sell and its ledger entry together
+    pub async fn close_trade(&self, exit: &TradeExit<'_>, entry: &ledger::Entry<'_>) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => {
+                let mut tx = pool.begin().await?;
+                TradeRepo::close(&mut *tx, exit).await?;
+                ledger::record(&mut *tx, entry).await?;
+                tx.commit().await?;
+            }
+            Store::Memory(memory) => memory.close_trade(exit, entry),
+        }
+        Ok(())
+    }
+
+    pub async fn open_order(&self, order: &NewOrder<'_>) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => OrderRepo::open(pool, order).await,
+            Store::Memory(memory) => {
+                memory.open_order(order);
+                Ok(())
+            }
+        }
+    }
+
+    /// Move an order on; an update its status doesn't allow is logged and skipped
+    pub async fn update_order(&self, update: &OrderUpdate<'_>) -> Result<()> {
+        let moved = match self {
+            Store::Postgres(pool) => OrderRepo::update(pool, update).await?,
+            Store::Memory(memory) => memory.update_order(update),
+        };
+        if !moved {
+            println!(
+                "[orders] {} can't become {} from its current status",
+                update.client_order_id,
+                update.status.as_str()
+            );
+        }
+        Ok(())
+    }
+
+    /// Close orders left open past their expiry
+    pub async fn expire_orders(&self) -> Result<u64> {
+        match self {
+            Store::Postgres(pool) => OrderRepo::expire(pool).await,
+            Store::Memory(memory) => Ok(memory.expire_orders()),
+        }
+    }
+
+    pub async fn record_ledger(&self, entry: &ledger::Entry<'_>) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => ledger::record(pool, entry).await,
+            Store::Memory(memory) => {
+                memory.record_ledger(entry);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn record_sweep(
+        &self,
+        strategy_id: &str,
+        sol: f64,
+        destination: &str,
+        signature: Option<&str>,
+    ) -> Result<()> {
+        match self {
+            Store::Postgres(pool) => {
+                TradeRepo::record_sweep(pool, strategy_id, sol, destination, signature).await
+            }
+            Store::Memory(memory) => {
+                memory.record_sweep(strategy_id, sol, destination, signature);
+                Ok(())
+            }
+        }
+    }
+}
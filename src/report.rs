@@ -0,0 +1,456 @@
+use crate::annotations::normalize_tag;
+use crate::db::{EVENT_COLUMNS, LATEST_EVENTS, stored_event};
+use crate::portfolio_manager::PortfolioManager;
+use crate::scanner::WRAPPED_SOL_MINT;
+use crate::strategy::{TokenEvent, decide};
+use crate::strategy_config::StrategyConfig;
+use anyhow::{Result, bail};
+use sqlx::{PgPool, Row};
+
+/// Trade dimensions the attribution report can group by
+pub const GROUP_BY_COLUMNS: &[&str] = &["strategy_id", "preset", "source", "launch_hour"];
+
+/// Entry checks the filter report covers: the filters that can apply to stored token events,
+/// then the score minimum and momentum requirement, then `entry` for all of them together
+pub const REPORTED_FILTERS: &[&str] = &[
+    "missing_data",
+    "market_cap",
+    "holders",
+    "dev_hold",
+    "upgradeable",
+    "freeze_authority",
+    "score",
+    "momentum",
+    "entry",
+];
+
+#[derive(Debug, Clone)]
+pub struct AttributionRow {
+    pub key: String,
+    pub trades: i64,
+    pub wins: i64,
+    pub pnl_usd: f64,
+    pub avg_hold_secs: f64,
+}
+
+impl AttributionRow {
+    pub fn win_rate(&self) -> f64 {
+        if self.trades > 0 {
+            self.wins as f64 / self.trades as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Trades tagged with $1, themselves or through their token, or all trades when $1 is null
+const TAGGED: &str = "($1::TEXT IS NULL OR $1 = ANY(trades.tags) OR EXISTS \
+     (SELECT 1 FROM token_events e WHERE e.id = trades.token_id AND $1 = ANY(e.tags)))";
+
+/// Trades opened by run $2, or all trades when $2 is null
+const IN_RUN: &str = "($2::INT4 IS NULL OR trades.run_id = $2)";
+
+/// Realized PnL, win rate, and average hold time of closed trades grouped by `column`,
+/// optionally only those tagged with `tag` or opened by `run`
+pub async fn attribution(
+    pool: &PgPool,
+    column: &str,
+    tag: Option<&str>,
+    run: Option<i32>,
+) -> Result<Vec<AttributionRow>> {
+    // The column is interpolated into SQL, so only whitelisted names are accepted
+    if !GROUP_BY_COLUMNS.contains(&column) {
+        bail!(
+            "cannot group by '{}' (expected one of {})",
+            column,
+            GROUP_BY_COLUMNS.join(", ")
+        );
+    }
+
+    let sql = format!(
+        "SELECT COALESCE({col}::TEXT, 'unknown') AS key, COUNT(*) AS trades, COUNT(*) FILTER (WHERE pnl > 0) AS wins, \
+         COALESCE(SUM(pnl), 0) AS pnl, \
+         COALESCE(AVG(EXTRACT(EPOCH FROM closed_at - opened_at)), 0)::DOUBLE PRECISION AS avg_hold \
+         FROM trades WHERE closed_at IS NOT NULL AND {tagged} AND {in_run} \
+         GROUP BY {col} ORDER BY pnl DESC",
+        col = column,
+        tagged = TAGGED,
+        in_run = IN_RUN
+    );
+    let rows = sqlx::query(&sql)
+        .bind(tag.map(normalize_tag).transpose()?)
+        .bind(run)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| AttributionRow {
+            key: r.get("key"),
+            trades: r.get("trades"),
+            wins: r.get("wins"),
+            pnl_usd: r.get("pnl"),
+            avg_hold_secs: r.get("avg_hold"),
+        })
+        .collect())
+}
+
+/// Closed trades' returns next to two baselines over the window they span: holding SOL, and
+/// buying every token detected in it with equal size and holding it until it was labeled
+#[derive(Debug, Clone, Default)]
+pub struct Benchmark {
+    pub trades: i64,
+    pub usd_in: f64,
+    pub pnl_usd: f64,
+    /// SOL price change over the window; unset without recorded SOL prices
+    pub sol_change_pct: Option<f64>,
+    /// Live-detected tokens in the window with an outcome label
+    pub labeled_tokens: i64,
+    /// Mean return of those tokens from the price they were seen at to their labeled price
+    pub every_token_return_pct: Option<f64>,
+}
+
+impl Benchmark {
+    pub fn return_pct(&self) -> f64 {
+        if self.usd_in > 0.0 {
+            self.pnl_usd / self.usd_in * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Baseline PnL of buying every labeled token with the average trade size
+    pub fn every_token_pnl_usd(&self) -> Option<f64> {
+        let avg_size = self.usd_in / self.trades.max(1) as f64;
+        self.every_token_return_pct
+            .map(|pct| self.labeled_tokens as f64 * avg_size * pct / 100.0)
+    }
+}
+
+pub async fn benchmark(pool: &PgPool, tag: Option<&str>, run: Option<i32>) -> Result<Benchmark> {
+    let row = sqlx::query(&format!(
+        "SELECT COUNT(*) AS trades, COALESCE(SUM(usd_in), 0) AS usd_in, \
+         COALESCE(SUM(pnl), 0) AS pnl, \
+         EXTRACT(EPOCH FROM MIN(opened_at))::DOUBLE PRECISION AS from_secs, \
+         EXTRACT(EPOCH FROM MAX(closed_at))::DOUBLE PRECISION AS to_secs \
+         FROM trades WHERE closed_at IS NOT NULL AND NOT shadow AND {} AND {}",
+        TAGGED, IN_RUN
+    ))
+    .bind(tag.map(normalize_tag).transpose()?)
+    .bind(run)
+    .fetch_one(pool)
+    .await?;
+    let mut bench = Benchmark {
+        trades: row.get("trades"),
+        usd_in: row.get("usd_in"),
+        pnl_usd: row.get("pnl"),
+        ..Default::default()
+    };
+    let (Some(from), Some(to)) = (
+        row.get::<Option<f64>, _>("from_secs"),
+        row.get::<Option<f64>, _>("to_secs"),
+    ) else {
+        return Ok(bench);
+    };
+
+    // The first SOL price recorded in the window and the last
+    let sol: (Option<f64>, Option<f64>) = sqlx::query_as(
+        "SELECT \
+         (SELECT price_usd FROM price_history WHERE token_id = $1 \
+          AND observed_at BETWEEN to_timestamp($2) AND to_timestamp($3) \
+          ORDER BY observed_at LIMIT 1), \
+         (SELECT price_usd FROM price_history WHERE token_id = $1 \
+          AND observed_at BETWEEN to_timestamp($2) AND to_timestamp($3) \
+          ORDER BY observed_at DESC LIMIT 1)",
+    )
+    .bind(WRAPPED_SOL_MINT)
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+    if let (Some(first), Some(last)) = sol
+        && first > 0.0
+    {
+        bench.sol_change_pct = Some((last - first) / first * 100.0);
+    }
+
+    let (labeled, mean_multiple): (i64, Option<f64>) = sqlx::query_as(&format!(
+        "SELECT COUNT(*), AVG(COALESCE(o.final_multiple, 0)) \
+         FROM {} e JOIN outcomes o ON o.token_id = e.id \
+         WHERE e.backfill_source IS NULL \
+         AND e.generated_at BETWEEN to_timestamp($1) AND to_timestamp($2) \
+         AND ($3::INT4 IS NULL OR EXISTS \
+         (SELECT 1 FROM token_events r WHERE r.id = e.id AND r.run_id = $3))",
+        LATEST_EVENTS
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(run)
+    .fetch_one(pool)
+    .await?;
+    bench.labeled_tokens = labeled;
+    bench.every_token_return_pct = mean_multiple.map(|m| (m - 1.0) * 100.0);
+    Ok(bench)
+}
+
+fn print_benchmark(bench: &Benchmark) {
+    println!("\n=== Strategy vs baselines ===");
+    println!(
+        "{:<18} {:>8.1}%  (PnL ${:.2} over {} trades)",
+        "strategy",
+        bench.return_pct(),
+        bench.pnl_usd,
+        bench.trades
+    );
+    match bench.sol_change_pct {
+        Some(pct) => println!("{:<18} {:>8.1}%", "hold SOL", pct),
+        None => println!("{:<18} {:>9}  (no SOL prices recorded)", "hold SOL", "-"),
+    }
+    match (bench.every_token_return_pct, bench.every_token_pnl_usd()) {
+        (Some(pct), Some(pnl)) => println!(
+            "{:<18} {:>8.1}%  (PnL ${:.2} over {} labeled tokens)",
+            "buy every token", pct, pnl, bench.labeled_tokens
+        ),
+        _ => println!("{:<18} {:>9}  (no labeled tokens)", "buy every token", "-"),
+    }
+}
+
+/// Print the baselines, then attribution tables for every supported dimension
+pub async fn print_attribution(pool: &PgPool, tag: Option<&str>, run: Option<i32>) -> Result<()> {
+    if let Some(tag) = tag {
+        println!("Trades tagged {}", normalize_tag(tag)?);
+    }
+    if let Some(run) = run {
+        println!("Trades opened by run {}", run);
+    }
+    print_benchmark(&benchmark(pool, tag, run).await?);
+    for column in GROUP_BY_COLUMNS {
+        let rows = attribution(pool, column, tag, run).await?;
+        println!("\n=== PnL by {} ===", column);
+        println!(
+            "{:<20} {:>7} {:>9} {:>12} {:>10}",
+            column, "trades", "win_rate", "pnl_usd", "avg_hold"
+        );
+        for row in rows {
+            println!(
+                "{:<20} {:>7} {:>8.1}% {:>12.2} {:>9.0}s",
+                row.key,
+                row.trades,
+                row.win_rate() * 100.0,
+                row.pnl_usd,
+                row.avg_hold_secs
+            );
+        }
+    }
+    Ok(())
+}
+
+/// How one entry check split labeled tokens; a token "won" if it reached 2x
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterStats {
+    pub filter: &'static str,
+    pub rejected: usize,
+    /// Rejected tokens that reached 2x anyway
+    pub rejected_won: usize,
+    pub passed: usize,
+    /// Passed tokens that did not reach 2x
+    pub passed_failed: usize,
+}
+
+impl FilterStats {
+    /// Share of rejected tokens that did fail
+    pub fn precision(&self) -> f64 {
+        ratio(self.rejected - self.rejected_won, self.rejected)
+    }
+
+    /// Share of failed tokens the check rejected
+    pub fn recall(&self) -> f64 {
+        let caught = self.rejected - self.rejected_won;
+        ratio(caught, caught + self.passed_failed)
+    }
+}
+
+fn ratio(n: usize, total: usize) -> f64 {
+    if total > 0 {
+        n as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// Replay `config`'s entry checks over `tokens` (each with whether it reached 2x)
+pub fn filter_effectiveness(
+    tokens: &[(TokenEvent, bool)],
+    config: &StrategyConfig,
+) -> Vec<FilterStats> {
+    let mut stats: Vec<FilterStats> = REPORTED_FILTERS
+        .iter()
+        .map(|filter| FilterStats {
+            filter,
+            ..Default::default()
+        })
+        .collect();
+    for (ev, won) in tokens {
+        let decision = decide(ev, config);
+        for s in &mut stats {
+            let rejected = match s.filter {
+                "score" => decision.score < config.min_score_to_buy,
+                "momentum" => {
+                    config.require_momentum_or_graduation
+                        && !ev.has_momentum(config)
+                        && !ev.graduation
+                }
+                "entry" => !decision.should_buy,
+                filter => decision.rejections.iter().any(|r| r.filter == filter),
+            };
+            if rejected {
+                s.rejected += 1;
+                s.rejected_won += usize::from(*won);
+            } else {
+                s.passed += 1;
+                s.passed_failed += usize::from(!*won);
+            }
+        }
+    }
+    stats
+}
+
+/// Print, per strategy, how its current entry checks would have split the labeled tokens,
+/// optionally only those tagged with `tag` or observed by `run`
+pub async fn print_filter_effectiveness(
+    pool: &PgPool,
+    manager: &PortfolioManager,
+    tag: Option<&str>,
+    run: Option<i32>,
+) -> Result<()> {
+    // Backfilled launches lack most of what the filters check
+    let rows = sqlx::query(&format!(
+        "SELECT {}, o.outcome FROM outcomes o JOIN {} e ON e.id = o.token_id \
+         WHERE e.backfill_source IS NULL AND ($1::TEXT IS NULL OR $1 = ANY(e.tags)) \
+         AND ($2::INT4 IS NULL OR EXISTS \
+         (SELECT 1 FROM token_events r WHERE r.id = e.id AND r.run_id = $2))",
+        EVENT_COLUMNS, LATEST_EVENTS
+    ))
+    .bind(tag.map(normalize_tag).transpose()?)
+    .bind(run)
+    .fetch_all(pool)
+    .await?;
+    let tokens: Vec<(TokenEvent, bool)> = rows
+        .iter()
+        .map(|r| {
+            let outcome: String = r.get("outcome");
+            (stored_event(r), outcome == "2x" || outcome == "10x")
+        })
+        .collect();
+    let winners = tokens.iter().filter(|(_, won)| *won).count();
+
+    for slot in &manager.slots {
+        println!(
+            "\n=== Filters of {} over {} labeled tokens ({} reached 2x) ===",
+            slot.id,
+            tokens.len(),
+            winners
+        );
+        println!(
+            "{:<18} {:>9} {:>12} {:>8} {:>13} {:>10} {:>8}",
+            "filter", "rejected", "rejected_won", "passed", "passed_failed", "precision", "recall"
+        );
+        for s in filter_effectiveness(&tokens, &slot.config) {
+            println!(
+                "{:<18} {:>9} {:>12} {:>8} {:>13} {:>9.1}% {:>7.1}%",
+                s.filter,
+                s.rejected,
+                s.rejected_won,
+                s.passed,
+                s.passed_failed,
+                s.precision() * 100.0,
+                s.recall() * 100.0
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn attribution_only_groups_by_known_columns() {
+        // The lazy pool never connects: the column is checked before any query
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let err = attribution(&pool, "pnl; DROP TABLE trades", None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("cannot group by"));
+
+        let row = AttributionRow {
+            key: "early_snipe".to_string(),
+            trades: 4,
+            wins: 3,
+            pnl_usd: 12.0,
+            avg_hold_secs: 90.0,
+        };
+        assert_eq!(row.win_rate(), 0.75);
+        assert_eq!(AttributionRow { trades: 0, ..row }.win_rate(), 0.0);
+    }
+
+    #[test]
+    fn counts_winners_each_filter_rejected() {
+        let base: TokenEvent = serde_json::from_value(serde_json::json!({
+            "id": "Mint", "source": "pumpfun", "token_type": "T", "market_cap_usd": 30000.0,
+            "dev_hold_pct": 5.0, "liquidity_usd": 20000.0, "holders": 40, "upgradeable": false,
+            "freeze_authority": false, "momentum": true, "graduation": false,
+            "base_price": 0.00003, "dev_wallet_address": null, "is_dev_known_rugger": false,
+            "token_age_secs": null, "launched_at": null, "dev_initial_buy_pct": null,
+            "bonding_curve_progress_pct": null
+        }))
+        .unwrap();
+        let with = |f: fn(&mut TokenEvent)| {
+            let mut ev = base.clone();
+            f(&mut ev);
+            ev
+        };
+        let config = StrategyConfig {
+            min_score_to_buy: 0.0,
+            ..StrategyConfig::default()
+        };
+        let tokens = [
+            (base.clone(), true),
+            (base.clone(), false),
+            // The holders filter rejects one winner and one loser
+            (with(|ev| ev.holders = 3), true),
+            (with(|ev| ev.holders = 2), false),
+            (with(|ev| ev.dev_hold_pct = 40.0), false),
+        ];
+        let stats = filter_effectiveness(&tokens, &config);
+        let get = |name| stats.iter().find(|s| s.filter == name).unwrap();
+
+        let holders = get("holders");
+        assert_eq!((holders.rejected, holders.rejected_won), (2, 1));
+        assert_eq!((holders.passed, holders.passed_failed), (3, 2));
+        assert_eq!(holders.precision(), 0.5);
+        assert!((holders.recall() - 1.0 / 3.0).abs() < 1e-9);
+
+        let dev_hold = get("dev_hold");
+        assert_eq!((dev_hold.rejected, dev_hold.precision()), (1, 1.0));
+        let entry = get("entry");
+        assert_eq!((entry.rejected, entry.rejected_won), (3, 1));
+        assert_eq!((entry.passed, entry.passed_failed), (2, 1));
+    }
+
+    #[test]
+    fn scales_the_every_token_baseline_to_the_average_trade() {
+        let bench = Benchmark {
+            trades: 4,
+            usd_in: 60.0,
+            pnl_usd: 6.0,
+            sol_change_pct: Some(-3.0),
+            labeled_tokens: 100,
+            every_token_return_pct: Some(-40.0),
+        };
+        assert_eq!(bench.return_pct(), 10.0);
+        assert_eq!(bench.every_token_pnl_usd(), Some(-600.0));
+        assert_eq!(Benchmark::default().every_token_pnl_usd(), None);
+    }
+}
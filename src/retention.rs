@@ -0,0 +1,312 @@
+//! Retention: rows older than `RETENTION_DAYS` are moved out of the hot tables into
+//! zstd-compressed Parquet files at `ARCHIVE_URL`, so continuous recording doesn't bloat
+//! Postgres
+//!
+//! Archived are `token_events` (except tokens that were traded, annotated, or backfilled),
+//! `price_history` (except backfilled prices), and `position_marks`. Each pass writes one file
+//! per table and UTC day, `<table>/day=<YYYY-MM-DD>/<unix time>.parquet`, with every column the
+//! table has. Its rows are deleted in the same transaction, which is rolled back if the file
+//! can't be written.
+
+use crate::storage::{Location, Storage};
+use anyhow::Result;
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{Field, Schema};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+
+const PASS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// A table's time column and the condition its archived rows meet
+struct Table {
+    name: &'static str,
+    time_column: &'static str,
+    prunable: String,
+}
+
+fn tables() -> [Table; 3] {
+    [
+        Table {
+            name: "token_events",
+            time_column: "generated_at",
+            prunable: "backfill_source IS NULL AND note IS NULL AND tags = '{}' \
+                 AND NOT EXISTS (SELECT 1 FROM trades t WHERE t.token_id = token_events.id)"
+                .to_string(),
+        },
+        Table {
+            name: "price_history",
+            time_column: "observed_at",
+            prunable: format!("source IS DISTINCT FROM '{}'", crate::backfill::SOURCE),
+        },
+        Table {
+            name: "position_marks",
+            time_column: "marked_at",
+            prunable: "TRUE".to_string(),
+        },
+    ]
+}
+
+/// An archived column's values, read as one of a few Parquet types
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Text(Vec<Option<String>>),
+    Float(Vec<Option<f64>>),
+    Int(Vec<Option<i64>>),
+    Bool(Vec<Option<bool>>),
+    /// Microseconds since the epoch
+    Time(Vec<Option<i64>>),
+    TextList(Vec<Option<Vec<String>>>),
+}
+
+impl Column {
+    /// Empty column for a Postgres type (as in `information_schema.columns.data_type`), and
+    /// the expression it is read with; types without a match are read as text
+    fn for_type(name: &str, data_type: &str) -> (Self, String) {
+        match data_type {
+            "double precision" | "real" | "numeric" => (
+                Column::Float(Vec::new()),
+                format!("{}::DOUBLE PRECISION", name),
+            ),
+            "smallint" | "integer" | "bigint" => {
+                (Column::Int(Vec::new()), format!("{}::BIGINT", name))
+            }
+            "boolean" => (Column::Bool(Vec::new()), name.to_string()),
+            "timestamp with time zone" | "timestamp without time zone" => (
+                Column::Time(Vec::new()),
+                format!("(EXTRACT(EPOCH FROM {}) * 1000000)::BIGINT", name),
+            ),
+            "ARRAY" => (Column::TextList(Vec::new()), format!("{}::TEXT[]", name)),
+            _ => (Column::Text(Vec::new()), format!("{}::TEXT", name)),
+        }
+    }
+
+    fn push(&mut self, row: &PgRow, idx: usize) -> Result<()> {
+        match self {
+            Column::Text(v) => v.push(row.try_get(idx)?),
+            Column::Float(v) => v.push(row.try_get(idx)?),
+            Column::Int(v) | Column::Time(v) => v.push(row.try_get(idx)?),
+            Column::Bool(v) => v.push(row.try_get(idx)?),
+            Column::TextList(v) => v.push(row.try_get(idx)?),
+        }
+        Ok(())
+    }
+
+    fn into_array(self) -> ArrayRef {
+        match self {
+            Column::Text(v) => Arc::new(StringArray::from(v)),
+            Column::Float(v) => Arc::new(Float64Array::from(v)),
+            Column::Int(v) => Arc::new(Int64Array::from(v)),
+            Column::Bool(v) => Arc::new(BooleanArray::from(v)),
+            Column::Time(v) => Arc::new(TimestampMicrosecondArray::from(v).with_timezone("UTC")),
+            Column::TextList(v) => {
+                let mut builder = ListBuilder::new(StringBuilder::new());
+                for list in v {
+                    match list {
+                        Some(list) => {
+                            for s in list {
+                                builder.values().append_value(s);
+                            }
+                            builder.append(true);
+                        }
+                        None => builder.append(false),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+        }
+    }
+}
+
+/// Encode named columns as a zstd-compressed Parquet file
+fn to_parquet(columns: Vec<(String, Column)>) -> Result<Vec<u8>> {
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns
+        .into_iter()
+        .map(|(name, column)| {
+            let array = column.into_array();
+            (Field::new(name, array.data_type().clone(), true), array)
+        })
+        .unzip();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut buf = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buf, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(buf)
+}
+
+/// Archives and prunes old rows every hour, until the process exits
+pub struct Retention {
+    pool: PgPool,
+    storage: Storage,
+    target: Location,
+    days: u64,
+}
+
+impl Retention {
+    pub fn new(pool: PgPool, storage: Storage, target: Location, days: u64) -> Self {
+        Self {
+            pool,
+            storage,
+            target,
+            days,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(PASS_INTERVAL);
+        loop {
+            ticker.tick().await;
+            // Only whole days are archived
+            let Ok(cutoff) =
+                (Utc::now() - Duration::days(self.days as i64)).duration_trunc(Duration::days(1))
+            else {
+                continue;
+            };
+            for table in tables() {
+                match self.archive_table(&table, cutoff).await {
+                    Ok(0) => {}
+                    Ok(n) => println!("[retention] Archived {} rows of {}", n, table.name),
+                    Err(e) => println!("[retention] Archiving {} failed: {}", table.name, e),
+                }
+            }
+        }
+    }
+
+    /// Archive the table's prunable rows recorded before `cutoff`, a day at a time; returns
+    /// how many were archived
+    async fn archive_table(&self, table: &Table, cutoff: DateTime<Utc>) -> Result<usize> {
+        let days: Vec<i64> = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT EXTRACT(EPOCH FROM date_trunc('day', {time} AT TIME ZONE 'UTC'))::BIGINT \
+             FROM {name} WHERE {time} < to_timestamp($1) AND {prunable} ORDER BY 1",
+            time = table.time_column,
+            name = table.name,
+            prunable = table.prunable,
+        ))
+        .bind(cutoff.timestamp() as f64)
+        .fetch_all(&self.pool)
+        .await?;
+        if days.is_empty() {
+            return Ok(0);
+        }
+
+        let types: Vec<(String, String)> = sqlx::query_as(
+            "SELECT column_name::TEXT, data_type::TEXT FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1 ORDER BY ordinal_position",
+        )
+        .bind(table.name)
+        .fetch_all(&self.pool)
+        .await?;
+        let select: Vec<String> = types
+            .iter()
+            .map(|(name, data_type)| Column::for_type(name, data_type).1)
+            .collect();
+
+        let pass = Utc::now().timestamp();
+        let mut archived = 0;
+        for day in days {
+            let mut tx = self.pool.begin().await?;
+            let rows = sqlx::query(&format!(
+                "DELETE FROM {name} WHERE {time} >= to_timestamp($1) \
+                 AND {time} < to_timestamp($2) AND {prunable} RETURNING {select}",
+                name = table.name,
+                time = table.time_column,
+                prunable = table.prunable,
+                select = select.join(", "),
+            ))
+            .bind(day as f64)
+            .bind((day + 86_400) as f64)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let mut columns: Vec<(String, Column)> = types
+                .iter()
+                .map(|(name, data_type)| (name.clone(), Column::for_type(name, data_type).0))
+                .collect();
+            for row in &rows {
+                for (idx, (_, column)) in columns.iter_mut().enumerate() {
+                    column.push(row, idx)?;
+                }
+            }
+            let date = DateTime::from_timestamp(day, 0).unwrap_or_default();
+            let key = format!(
+                "{}/day={}/{}.parquet",
+                table.name,
+                date.format("%Y-%m-%d"),
+                pass
+            );
+            self.storage
+                .put(&self.target.join(&key), to_parquet(columns)?)
+                .await?;
+            tx.commit().await?;
+            archived += rows.len();
+        }
+        Ok(archived)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, ListArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn writes_columns_as_parquet() {
+        let (empty, select) = Column::for_type("generated_at", "timestamp with time zone");
+        assert_eq!(empty, Column::Time(Vec::new()));
+        assert!(select.starts_with("(EXTRACT(EPOCH FROM generated_at)"));
+        assert_eq!(Column::for_type("config", "jsonb").1, "config::TEXT");
+
+        let bytes = to_parquet(vec![
+            (
+                "id".to_string(),
+                Column::Text(vec![Some("mint".to_string()), None]),
+            ),
+            (
+                "generated_at".to_string(),
+                Column::Time(vec![Some(1_760_600_000_000_000), None]),
+            ),
+            ("score".to_string(), Column::Float(vec![Some(72.5), None])),
+            (
+                "tags".to_string(),
+                Column::TextList(vec![Some(vec!["late_entry".to_string()]), Some(Vec::new())]),
+            ),
+        ])
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("retention-{}.parquet", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let scores = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!((scores.value(0), scores.is_null(1)), (72.5, true));
+        let tags = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(tags.value_length(0), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::models::SellRoute;
+use crate::simulator::Portfolio;
+use crate::strategy::FilterRejection;
+use crate::strategy_config::{MIN_ENTRY_SOL, StrategyConfig};
+
+/// Check portfolio-level exposure limits for a prospective entry of `sol_amount` SOL
+/// Returns the limits the entry would breach (empty if the entry is allowed)
+pub fn exposure_rejections(
+    portfolio: &Portfolio,
+    dev_wallet_address: Option<&str>,
+    launched_at: Option<i64>,
+    sol_amount: f64,
+    config: &StrategyConfig,
+) -> Vec<FilterRejection> {
+    let mut rejections = Vec::new();
+
+    // Sizing never dips into the SOL needed to pay for exits
+    if sol_amount < MIN_ENTRY_SOL {
+        rejections.push(FilterRejection::new(
+            "fee_reserve",
+            format!(
+                "{:.3} SOL spendable after reserving {:.3} SOL for fees",
+                sol_amount,
+                config.fee_reserve(portfolio.positions.len())
+            ),
+        ));
+    }
+
+    // Total SOL at risk across all open positions
+    let total_at_risk: f64 = portfolio.positions.iter().map(|p| p.sol_in).sum();
+    if total_at_risk + sol_amount > config.max_total_sol_at_risk {
+        rejections.push(FilterRejection::new(
+            "total_exposure",
+            format!(
+                "{:.3} + {:.3} SOL > {:.3} SOL",
+                total_at_risk, sol_amount, config.max_total_sol_at_risk
+            ),
+        ));
+    }
+
+    // Exposure to tokens launched by the same creator wallet
+    if let Some(creator) = dev_wallet_address {
+        let creator_exposure: f64 = portfolio
+            .positions
+            .iter()
+            .filter(|p| p.dev_wallet_address.as_deref() == Some(creator))
+            .map(|p| p.sol_in)
+            .sum();
+        if creator_exposure + sol_amount > config.max_sol_per_creator {
+            rejections.push(FilterRejection::new(
+                "creator_exposure",
+                format!(
+                    "{:.3} + {:.3} SOL > {:.3} SOL for creator {}",
+                    creator_exposure, sol_amount, config.max_sol_per_creator, creator
+                ),
+            ));
+        }
+    }
+
+    // Exposure to tokens from the same launch wave
+    if let Some(launched_at) = launched_at {
+        let window = config.launch_window_secs as i64;
+        let wave_exposure: f64 = portfolio
+            .positions
+            .iter()
+            .filter(|p| {
+                p.launched_at
+                    .is_some_and(|other| (other - launched_at).abs() < window)
+            })
+            .map(|p| p.sol_in)
+            .sum();
+        if wave_exposure + sol_amount > config.max_sol_per_launch_window {
+            rejections.push(FilterRejection::new(
+                "launch_window_exposure",
+                format!(
+                    "{:.3} + {:.3} SOL > {:.3} SOL within {}s of launch",
+                    wave_exposure, sol_amount, config.max_sol_per_launch_window, window
+                ),
+            ));
+        }
+    }
+
+    rejections
+}
+
+/// Check that a graduated token can be sold back out: Jupiter must quote a route for the whole
+/// position without moving the price more than `max_exit_price_impact_pct`
+pub fn sell_route_rejection(
+    route: Option<&SellRoute>,
+    config: &StrategyConfig,
+) -> Option<FilterRejection> {
+    let Some(route) = route else {
+        return Some(FilterRejection::new(
+            "sell_route",
+            "no Jupiter route back to SOL",
+        ));
+    };
+    if route.out_lamports == 0 || route.price_impact_pct > config.max_exit_price_impact_pct {
+        return Some(FilterRejection::new(
+            "sell_route",
+            format!(
+                "exit via {} returns {} lamports at {:.2}% impact (max {:.2}%)",
+                route.venues.join(" > "),
+                route.out_lamports,
+                route.price_impact_pct,
+                config.max_exit_price_impact_pct
+            ),
+        ));
+    }
+    None
+}
+
+/// Check that the pool is deep enough to exit even a minimum entry; deeper pools instead cap
+/// the entry at `max_sol_for_liquidity`
+pub fn exit_liquidity_rejection(
+    liquidity_usd: f64,
+    config: &StrategyConfig,
+) -> Option<FilterRejection> {
+    let cap = config.max_sol_for_liquidity(liquidity_usd);
+    (cap < MIN_ENTRY_SOL).then(|| {
+        FilterRejection::new(
+            "exit_liquidity",
+            format!(
+                "${:.0} of liquidity fits {:.4} SOL at {}x (min {} SOL)",
+                liquidity_usd, cap, config.min_liquidity_multiple, MIN_ENTRY_SOL
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{Position, test_position};
+
+    fn position(sol_in: f64, creator: &str, launched_at: i64) -> Position {
+        let token_id = format!("{}-{}", creator, launched_at);
+        let mut position = test_position(&token_id, 1.0, sol_in * 30.0, sol_in);
+        position.dev_wallet_address = Some(creator.into());
+        position.launched_at = Some(launched_at);
+        position
+    }
+
+    #[test]
+    fn limits_exposure_per_creator_and_launch_wave() {
+        let config = StrategyConfig {
+            max_total_sol_at_risk: 2.0,
+            max_sol_per_creator: 0.5,
+            max_sol_per_launch_window: 1.0,
+            launch_window_secs: 60,
+            ..StrategyConfig::default()
+        };
+        let mut portfolio = Portfolio::new(10.0);
+        portfolio.positions.push(position(0.4, "dev", 1_000));
+        portfolio.positions.push(position(0.4, "other", 1_030));
+        let names = |creator, launched_at, sol| {
+            exposure_rejections(&portfolio, creator, launched_at, sol, &config)
+                .into_iter()
+                .map(|r| r.filter)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(names(Some("fresh"), Some(5_000), 0.2).is_empty());
+        assert_eq!(names(Some("dev"), Some(5_000), 0.2), ["creator_exposure"]);
+        assert_eq!(
+            names(Some("fresh"), Some(1_050), 0.3),
+            ["launch_window_exposure"]
+        );
+        // Unknown creators and launch times aren't grouped with anything
+        assert!(names(None, None, 0.3).is_empty());
+        assert_eq!(names(None, None, 1.5), ["total_exposure"]);
+        assert_eq!(names(None, None, 0.0), ["fee_reserve"]);
+    }
+
+    #[test]
+    fn rejects_missing_or_thin_exit_routes() {
+        let config = StrategyConfig::default();
+        let route = |price_impact_pct| SellRoute {
+            out_lamports: 480_000_000,
+            price_impact_pct,
+            venues: vec!["Raydium".to_string()],
+        };
+        assert!(sell_route_rejection(Some(&route(2.0)), &config).is_none());
+        assert!(sell_route_rejection(Some(&route(35.0)), &config).is_some());
+        assert!(sell_route_rejection(None, &config).is_some());
+    }
+
+    #[test]
+    fn sizes_entries_to_pool_liquidity() {
+        let config = StrategyConfig::default();
+        // $600 behind 10x holds $60, 2 SOL at $30
+        assert!((config.max_sol_for_liquidity(600.0) - 2.0).abs() < 1e-9);
+        assert!(exit_liquidity_rejection(600.0, &config).is_none());
+        assert!(exit_liquidity_rejection(2.0, &config).is_some());
+        // Unknown liquidity doesn't cap the entry
+        assert!(exit_liquidity_rejection(0.0, &config).is_none());
+
+        let disabled = StrategyConfig {
+            min_liquidity_multiple: 0.0,
+            ..StrategyConfig::default()
+        };
+        assert_eq!(disabled.max_sol_for_liquidity(2.0), f64::INFINITY);
+    }
+}
@@ -0,0 +1,186 @@
+//! Sandwich exposure of a live buy, estimated before it is sent
+//!
+//! A sandwich bot buys just ahead of our order and sells right after it, pocketing the price
+//! impact our buy leaves inside its slippage tolerance. The estimate weighs the order against
+//! the pool's depth and counts sandwiches among the token's recent transactions. An order is
+//! shrunk to `max_entry_price_impact_pct`, sent through Jito past `jito_entry_price_impact_pct`
+//! or when the pool is already being worked, and skipped past `max_recent_sandwiches`.
+
+use crate::strategy::FilterRejection;
+use crate::strategy_config::{MIN_ENTRY_SOL, StrategyConfig};
+use serde::Serialize;
+
+/// Transactions of a token looked at for sandwiches
+pub const RECENT_TRANSACTIONS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandwichAction {
+    Send,
+    Shrink,
+    Skip,
+}
+
+/// What a live buy risked and how it was sent, attached to its decision
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SandwichEstimate {
+    /// Size the strategy decided on
+    pub requested_sol: f64,
+    /// Quote side of the pool, half its USD liquidity; None when the liquidity is unknown
+    pub pool_depth_usd: Option<f64>,
+    /// Price impact of the requested size
+    pub price_impact_pct: Option<f64>,
+    /// Sandwiches among the token's last `RECENT_TRANSACTIONS`; None when they couldn't
+    /// be fetched
+    pub recent_sandwiches: Option<u32>,
+    pub action: SandwichAction,
+    /// Size the buy goes out with, 0 when skipped
+    pub sol_amount: f64,
+    /// Sent through Jito whatever the buy route
+    pub jito: bool,
+}
+
+impl SandwichEstimate {
+    /// Size and route a buy of `sol_amount` SOL into a pool with `liquidity_usd` of liquidity
+    pub fn new(
+        sol_amount: f64,
+        liquidity_usd: f64,
+        recent_sandwiches: Option<u32>,
+        config: &StrategyConfig,
+    ) -> Self {
+        let depth = (liquidity_usd > 0.0).then_some(liquidity_usd / 2.0);
+        // Constant-product impact of paying `usd` into `depth` dollars of quote reserve
+        let impact = |sol: f64| {
+            let usd = sol * config.sol_usd_price;
+            depth.map(|d| usd / (d + usd) * 100.0)
+        };
+        let price_impact_pct = impact(sol_amount);
+
+        let mut action = SandwichAction::Send;
+        let mut size = sol_amount;
+        if recent_sandwiches.unwrap_or(0) > config.max_recent_sandwiches {
+            action = SandwichAction::Skip;
+        } else if let (Some(d), Some(pct)) = (depth, price_impact_pct)
+            && pct > config.max_entry_price_impact_pct
+        {
+            let p = config.max_entry_price_impact_pct / 100.0;
+            size = p * d / (1.0 - p) / config.sol_usd_price;
+            action = if size < MIN_ENTRY_SOL {
+                SandwichAction::Skip
+            } else {
+                SandwichAction::Shrink
+            };
+        }
+        if action == SandwichAction::Skip {
+            size = 0.0;
+        }
+        let jito = action != SandwichAction::Skip
+            && (recent_sandwiches.unwrap_or(0) > 0
+                || impact(size).is_none_or(|pct| pct > config.jito_entry_price_impact_pct));
+
+        Self {
+            requested_sol: sol_amount,
+            pool_depth_usd: depth,
+            price_impact_pct,
+            recent_sandwiches,
+            action,
+            sol_amount: size,
+            jito,
+        }
+    }
+
+    /// The `sandwich_risk` rejection of a skipped buy
+    pub fn rejection(&self) -> Option<FilterRejection> {
+        (self.action == SandwichAction::Skip).then(|| {
+            FilterRejection::new(
+                "sandwich_risk",
+                format!(
+                    "{} SOL at {} impact with {} recent sandwiches",
+                    self.requested_sol,
+                    self.price_impact_pct
+                        .map_or("unknown".to_string(), |p| format!("{:.2}%", p)),
+                    self.recent_sandwiches
+                        .map_or("unknown".to_string(), |n| n.to_string())
+                ),
+            )
+        })
+    }
+}
+
+/// Slots in which one fee payer sent transactions on both sides of someone else's, from
+/// `(slot, fee payer)` pairs in the order they executed
+pub fn count_sandwiches(transactions: &[(u64, String)]) -> u32 {
+    let mut count = 0;
+    for (idx, (slot, _)) in transactions.iter().enumerate() {
+        if idx > 0 && transactions[idx - 1].0 == *slot {
+            continue;
+        }
+        let payers: Vec<&str> = transactions[idx..]
+            .iter()
+            .take_while(|(s, _)| s == slot)
+            .map(|(_, payer)| payer.as_str())
+            .collect();
+        let sandwiched = payers.iter().enumerate().any(|(first, payer)| {
+            payers[first + 1..]
+                .iter()
+                .position(|p| p == payer)
+                .is_some_and(|gap| {
+                    payers[first + 1..first + 1 + gap]
+                        .iter()
+                        .any(|p| p != payer)
+                })
+        });
+        count += sandwiched as u32;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinks_protects_and_skips_exposed_buys() {
+        let config = StrategyConfig::default();
+        let usd = |sol: f64| sol * config.sol_usd_price;
+
+        // A small buy into a deep pool goes out as decided
+        let deep = SandwichEstimate::new(0.1, usd(1000.0), Some(0), &config);
+        assert_eq!(deep.action, SandwichAction::Send);
+        assert_eq!(deep.sol_amount, 0.1);
+        assert!(!deep.jito);
+
+        // 1 SOL into 20 SOL of depth moves the price ~4.8%, so it is cut down to 3%
+        let shallow = SandwichEstimate::new(1.0, usd(40.0), Some(0), &config);
+        assert_eq!(shallow.action, SandwichAction::Shrink);
+        assert!((shallow.sol_amount - 0.03 * 20.0 / 0.97).abs() < 1e-9);
+        assert!(shallow.jito);
+
+        // Unknown depth can't be sized, only protected
+        let unknown = SandwichEstimate::new(0.1, 0.0, Some(0), &config);
+        assert_eq!(unknown.action, SandwichAction::Send);
+        assert!(unknown.jito);
+
+        let worked = SandwichEstimate::new(0.1, usd(1000.0), Some(3), &config);
+        assert_eq!(worked.action, SandwichAction::Skip);
+        assert_eq!(worked.rejection().unwrap().filter, "sandwich_risk");
+        assert!(
+            SandwichEstimate::new(0.1, usd(0.2), Some(0), &config)
+                .rejection()
+                .is_some()
+        );
+
+        let tx = |slot: u64, payer: &str| (slot, payer.to_string());
+        let transactions = [
+            tx(7, "bot"),
+            tx(7, "victim"),
+            tx(7, "bot"),
+            tx(8, "a"),
+            tx(8, "a"),
+            tx(8, "b"),
+            tx(9, "bot"),
+            tx(10, "bot"),
+        ];
+        assert_eq!(count_sandwiches(&transactions), 1);
+    }
+}
@@ -1,16 +1,63 @@
+use crate::buy_template::metadata_address;
+use crate::cache::TokenCache;
+use crate::mint_watch::MetaplexFields;
 use crate::models::*;
-use crate::strategy::TokenEvent;
-use anyhow::Result;
-use reqwest::Client;
+use crate::pumpportal::{MessageCounters, PumpPortalMessage, TradeFeed};
+use crate::sandwich::{RECENT_TRANSACTIONS, count_sandwiches};
+use anyhow::{Result, bail};
+use reqwest::{Client, Response};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
+/// Market data always comes from mainnet, whichever cluster trades are sent to
+pub const MAINNET_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+const JUPITER_QUOTE_URL: &str = "https://lite-api.jup.ag/swap/v1/quote";
+pub const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct Scanner {
     client: Client,
     rpc_url: String,
     dexscreener_key: Option<String>,
+    listen_window: Duration,
+    ws_counters: Arc<MessageCounters>,
+    /// Send progress logs to stderr so stdout carries only command output
+    log_to_stderr: bool,
+    /// Shared by clones
+    cache: Arc<TokenCache>,
+    api_errors: Arc<ApiErrorCounters>,
+}
+
+/// Failed requests per upstream API since startup: network errors and error statuses
+#[derive(Debug, Default)]
+pub struct ApiErrorCounters {
+    pub rpc: AtomicU64,
+    pub dexscreener: AtomicU64,
+    pub jupiter: AtomicU64,
+    /// RPC responses that were `429 Too Many Requests`, also counted in `rpc`
+    pub rpc_throttled: AtomicU64,
+}
+
+impl ApiErrorCounters {
+    pub fn snapshot(&self) -> [(&'static str, u64); 3] {
+        [
+            ("rpc", self.rpc.load(Ordering::Relaxed)),
+            ("dexscreener", self.dexscreener.load(Ordering::Relaxed)),
+            ("jupiter", self.jupiter.load(Ordering::Relaxed)),
+        ]
+    }
+}
+
+/// Count a request that got no response at all against `counter`
+fn counted(counter: &AtomicU64, result: reqwest::Result<Response>) -> Result<Response> {
+    result.map_err(|e| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        e.into()
+    })
 }
 
 // Solana RPC structures
@@ -30,14 +77,215 @@ struct RpcResponse<T> {
 
 #[derive(Debug, Deserialize)]
 struct ProgramAccount {
-    pubkey: String,
     account: AccountData,
 }
 
 #[derive(Debug, Deserialize)]
 struct AccountData {
     data: Vec<String>, // [base64_data, encoding]
-    lamports: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionResult {
+    meta: Option<TransactionMeta>,
+    transaction: Option<ParsedTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionMeta {
+    err: Option<serde_json::Value>,
+    post_token_balances: Option<Vec<TokenBalance>>,
+    inner_instructions: Option<Vec<InnerInstructions>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTransaction {
+    message: Option<ParsedMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedMessage {
+    /// The fee payer first
+    #[serde(default)]
+    account_keys: Vec<ParsedAccountKey>,
+    instructions: Vec<ParsedInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedAccountKey {
+    pubkey: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InnerInstructions {
+    instructions: Vec<ParsedInstruction>,
+}
+
+/// A jsonParsed instruction; `parsed` is only an object for programs the node can decode
+#[derive(Debug, Deserialize)]
+struct ParsedInstruction {
+    program: Option<String>,
+    parsed: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenBalance {
+    mint: String,
+    owner: Option<String>,
+    ui_token_amount: UiTokenAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedAccountInfo {
+    value: Option<ParsedAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedAccount {
+    owner: String,
+    data: ParsedAccountData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedAccountData {
+    parsed: ParsedMint,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedMint {
+    info: MintInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccountInfo {
+    value: Option<ParsedTokenAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccount {
+    data: ParsedTokenAccountData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccountData {
+    parsed: ParsedTokenAccountState,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccountState {
+    info: TokenAccountState,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAccountState {
+    /// `initialized` or `frozen`
+    state: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MintInfo {
+    decimals: u8,
+    mint_authority: Option<String>,
+    freeze_authority: Option<String>,
+    supply: String,
+    /// Token-2022 extensions; absent on SPL Token mints
+    #[serde(default)]
+    extensions: Vec<MintExtension>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MintExtension {
+    /// e.g. `transferFeeConfig`
+    extension: String,
+    #[serde(default)]
+    state: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferFeeConfig {
+    newer_transfer_fee: TransferFee,
+    older_transfer_fee: TransferFee,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferFee {
+    transfer_fee_basis_points: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct UiTokenAmount {
+    amount: String,
+    decimals: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountInfoValue {
+    value: Option<AccountData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureInfo {
+    signature: String,
+    slot: u64,
+    err: Option<serde_json::Value>,
+    block_time: Option<i64>,
+}
+
+const PUMPPORTAL_WS: &str = "wss://pumpportal.fun/api/data";
+
+/// Listings prefetched per batched request; each adds one `getProgramAccounts` call
+const PREFETCH_BATCH_SIZE: usize = 10;
+
+/// Source of newly launched tokens
+pub trait ListingSource {
+    async fn fetch_pumpfun_listings(&self) -> Result<Vec<PumpFunListing>>;
+}
+
+/// Per-token on-chain and DEX data used for enrichment and exit monitoring
+pub trait TokenDataProvider {
+    async fn query_mint_launch(&self, mint: &str) -> Result<Option<MintLaunch>>;
+    async fn query_creator_initial_buy_pct(
+        &self,
+        signature: &str,
+        creator: &str,
+        mint: &str,
+    ) -> Result<Option<f64>>;
+    async fn query_bonding_curve(&self, bonding_curve: &str) -> Result<Option<BondingCurveState>>;
+    async fn query_token_holder_stats(&self, mint: &str) -> Result<Option<HolderStats>>;
+    /// Mint account authorities, decimals, and supply
+    async fn query_token_metadata(&self, mint: &str) -> Result<Option<TokenMetadata>>;
+    async fn query_token_top_holders(&self, mint: &str) -> Result<Option<TopHoldersResponse>>;
+    /// Name, symbol, URI, and update authority from the mint's Metaplex metadata account
+    async fn query_metaplex_metadata(&self, mint: &str) -> Result<Option<MetaplexFields>>;
+    /// Whether a token account is frozen; `None` when it doesn't exist
+    async fn query_token_account_frozen(&self, address: &str) -> Result<Option<bool>>;
+    /// The wallet that sent `wallet` SOL in its first transaction; `None` when that
+    /// transaction funded it some other way
+    async fn query_funder(&self, wallet: &str) -> Result<Option<String>>;
+    /// Sandwiches among the last `RECENT_TRANSACTIONS` transactions touching `mint`; see
+    /// `sandwich::count_sandwiches`
+    async fn query_recent_sandwiches(&self, mint: &str) -> Result<Option<u32>>;
+    async fn query_dexscreener_pair(&self, mint: &str) -> Result<Option<DexScreenerPair>>;
+    /// Jupiter's best route for selling `amount` raw units of `mint` for SOL; `None` when
+    /// there is no route
+    async fn query_sell_route(&self, mint: &str, amount: u64) -> Result<Option<SellRoute>>;
+    /// Jupiter's best route for buying `mint` with `lamports`; `out_lamports` is then the raw
+    /// token units it would return
+    async fn query_buy_route(&self, mint: &str, lamports: u64) -> Result<Option<SellRoute>>;
+    /// Drop anything cached for `mint`, e.g. when a position opens or closes on it
+    fn invalidate(&self, _mint: &str) {}
+    /// Fetch what enrichment will ask about `listings` in as few requests as possible
+    async fn prefetch(&self, _listings: &[PumpFunListing]) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl Scanner {
@@ -50,34 +298,224 @@ impl Scanner {
 
         Scanner {
             client,
-            rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
+            rpc_url: MAINNET_RPC_URL.to_string(),
             dexscreener_key,
+            listen_window: Duration::from_secs(3),
+            ws_counters: Arc::new(MessageCounters::default()),
+            log_to_stderr: false,
+            cache: Arc::new(TokenCache::new(Duration::ZERO)),
+            api_errors: Arc::new(ApiErrorCounters::default()),
+        }
+    }
+
+    /// WebSocket counts shared by every clone of this scanner
+    pub fn ws_counters(&self) -> Arc<MessageCounters> {
+        self.ws_counters.clone()
+    }
+
+    /// Error counts shared by every clone of this scanner
+    pub fn api_errors(&self) -> Arc<ApiErrorCounters> {
+        self.api_errors.clone()
+    }
+
+    /// Cache metadata, holder stats, and DexScreener pairs for `ttl`
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache = Arc::new(TokenCache::new(ttl));
+        self
+    }
+
+    /// Log progress to stderr, e.g. when stdout is a JSON stream
+    pub fn with_logs_to_stderr(mut self) -> Self {
+        self.log_to_stderr = true;
+        self
+    }
+
+    fn log(&self, msg: std::fmt::Arguments) {
+        if self.log_to_stderr {
+            eprintln!("[fetch_pumpfun_listings] {}", msg);
+        } else {
+            println!("[fetch_pumpfun_listings] {}", msg);
+        }
+    }
+
+    /// Set how long each PumpPortal poll listens for new tokens
+    pub fn with_listen_window(mut self, listen_window: Duration) -> Self {
+        self.listen_window = listen_window;
+        self
+    }
+
+    fn count_rpc_error(&self, status: reqwest::StatusCode) {
+        self.api_errors.rpc.fetch_add(1, Ordering::Relaxed);
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            self.api_errors
+                .rpc_throttled
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Jupiter's best route swapping `amount` raw units of `input` for `output`
+    async fn jupiter_quote(
+        &self,
+        input: &str,
+        output: &str,
+        amount: u64,
+    ) -> Result<Option<SellRoute>> {
+        let url = format!(
+            "{}?inputMint={}&outputMint={}&amount={}&slippageBps=500",
+            JUPITER_QUOTE_URL, input, output, amount
+        );
+        let resp = counted(&self.api_errors.jupiter, self.client.get(&url).send().await)?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        // Jupiter answers 400 with an error body when no route exists
+        if status.is_success() || status == reqwest::StatusCode::BAD_REQUEST {
+            parse_jupiter_quote(&body)
+        } else {
+            self.api_errors.jupiter.fetch_add(1, Ordering::Relaxed);
+            anyhow::bail!("Jupiter quote returned {}", status)
+        }
+    }
+
+    /// POST a JSON-RPC request and return the raw response body; `None` on a non-2xx status
+    async fn rpc_body(&self, method: &str, params: serde_json::Value) -> Result<Option<String>> {
+        let request = RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            method: method.to_string(),
+            params,
+        };
+
+        let response = counted(
+            &self.api_errors.rpc,
+            self.client.post(&self.rpc_url).json(&request).send().await,
+        )?;
+
+        if !response.status().is_success() {
+            self.count_rpc_error(response.status());
+            return Ok(None);
+        }
+        Ok(Some(response.text().await?))
+    }
+
+    /// The page of an address's signatures holding its oldest, newest first, looking back at
+    /// most 10,000
+    async fn oldest_page(&self, address: &str) -> Result<Option<Vec<SignatureInfo>>> {
+        walk_to_oldest_page(|before| async move {
+            let mut opts = serde_json::json!({ "limit": SIGNATURE_PAGE_LIMIT });
+            if let Some(sig) = before {
+                opts["before"] = serde_json::Value::String(sig);
+            }
+
+            let Some(body) = self
+                .rpc_body(
+                    "getSignaturesForAddress",
+                    serde_json::json!([address, opts]),
+                )
+                .await?
+            else {
+                return Ok(None);
+            };
+            parse_rpc_result::<Vec<SignatureInfo>>(&body)
+        })
+        .await
+    }
+
+    /// The oldest of an address's signatures, looking back at most 10,000
+    async fn oldest_signature(&self, address: &str) -> Result<Option<SignatureInfo>> {
+        Ok(self
+            .oldest_page(address)
+            .await?
+            .and_then(|page| page.into_iter().last()))
+    }
+
+    /// Fails unless the RPC node reports itself healthy
+    pub async fn check_rpc(&self) -> Result<()> {
+        let Some(body) = self.rpc_body("getHealth", serde_json::json!([])).await? else {
+            bail!("RPC returned an HTTP error");
+        };
+        let response: RpcResponse<String> = serde_json::from_str(&body)?;
+        match (response.result.as_deref(), response.error) {
+            (Some("ok"), _) => Ok(()),
+            (_, Some(error)) => bail!("RPC unhealthy: {}", error),
+            (result, None) => bail!("unexpected getHealth result {:?}", result),
+        }
+    }
+
+    /// Fails unless the PumpPortal WebSocket accepts a connection
+    pub async fn check_pumpportal(&self) -> Result<()> {
+        let (mut ws, _) = tokio_tungstenite::connect_async(PUMPPORTAL_WS).await?;
+        let _ = ws.close(None).await;
+        Ok(())
+    }
+
+    /// Fails unless DexScreener answers a lookup of wrapped SOL, which always has pairs
+    pub async fn check_dexscreener(&self) -> Result<()> {
+        let url = format!(
+            "https://api.dexscreener.com/latest/dex/tokens/solana/{}",
+            WRAPPED_SOL_MINT
+        );
+        let mut req = self.client.get(&url);
+        if let Some(k) = &self.dexscreener_key {
+            req = req.header("x-api-key", k);
+        }
+        let status = req.send().await?.status();
+        if !status.is_success() {
+            bail!("DexScreener returned {}", status);
         }
+        Ok(())
     }
 
+    /// POST several JSON-RPC requests as one batch; returns each raw response body in request
+    /// order, `None` for any that failed or went unanswered
+    async fn rpc_batch(
+        &self,
+        calls: Vec<(&str, serde_json::Value)>,
+    ) -> Result<Vec<Option<String>>> {
+        let count = calls.len();
+        let requests: Vec<RpcRequest> = calls
+            .into_iter()
+            .enumerate()
+            .map(|(id, (method, params))| RpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: id as u64,
+                method: method.to_string(),
+                params,
+            })
+            .collect();
+        let response = counted(
+            &self.api_errors.rpc,
+            self.client.post(&self.rpc_url).json(&requests).send().await,
+        )?;
+        if !response.status().is_success() {
+            self.count_rpc_error(response.status());
+            return Ok(vec![None; count]);
+        }
+        split_batch(&response.text().await?, count)
+    }
+}
+
+impl ListingSource for Scanner {
     /// Fetch recent new mints / token listings from Pump.fun using PumpPortal WebSocket
     /// Connects to PumpPortal's free WebSocket API and listens for new token creation events
-    pub async fn fetch_pumpfun_listings(&self) -> Result<Vec<PumpFunListing>> {
+    async fn fetch_pumpfun_listings(&self) -> Result<Vec<PumpFunListing>> {
         use futures::{SinkExt, StreamExt};
         use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-        const PUMPPORTAL_WS: &str = "wss://pumpportal.fun/api/data";
-
-        println!("[fetch_pumpfun_listings] Connecting to PumpPortal WebSocket...");
+        self.log(format_args!("Connecting to PumpPortal WebSocket..."));
 
         // Connect to WebSocket
         let (ws_stream, _) = match connect_async(PUMPPORTAL_WS).await {
             Ok(conn) => conn,
             Err(e) => {
-                println!(
-                    "[fetch_pumpfun_listings] WebSocket connection failed: {}",
-                    e
-                );
+                self.log(format_args!("WebSocket connection failed: {}", e));
+                self.ws_counters
+                    .connect_failures
+                    .fetch_add(1, Ordering::Relaxed);
                 return Ok(Vec::new());
             }
         };
 
-        println!("[fetch_pumpfun_listings] Connected! Subscribing to new tokens...");
+        self.log(format_args!("Connected! Subscribing to new tokens..."));
 
         let (mut write, mut read) = ws_stream.split();
 
@@ -87,79 +525,80 @@ impl Scanner {
         });
 
         if let Err(e) = write.send(Message::Text(subscribe_msg.to_string())).await {
-            println!("[fetch_pumpfun_listings] Failed to subscribe: {}", e);
+            self.log(format_args!("Failed to subscribe: {}", e));
+            self.ws_counters
+                .connect_failures
+                .fetch_add(1, Ordering::Relaxed);
             return Ok(Vec::new());
         }
+        self.ws_counters.connects.fetch_add(1, Ordering::Relaxed);
 
-        println!("[fetch_pumpfun_listings] Subscribed! Listening for new tokens (3 seconds)...");
+        self.log(format_args!(
+            "Subscribed! Listening for new tokens ({} seconds)...",
+            self.listen_window.as_secs()
+        ));
 
-        let mut listings = Vec::new();
+        let mut listings: Vec<PumpFunListing> = Vec::new();
+        let mut trades = TradeFeed::default();
         let start_time = std::time::Instant::now();
-        let listen_duration = std::time::Duration::from_secs(3);
 
-        // Listen for messages for 3 seconds
-        while start_time.elapsed() < listen_duration {
+        // Listen for messages until the listen window closes
+        while start_time.elapsed() < self.listen_window {
             let timeout =
                 tokio::time::timeout(std::time::Duration::from_millis(500), read.next()).await;
 
             match timeout {
                 Ok(Some(Ok(Message::Text(text)))) => {
-                    // Parse the message
-                    if let Ok(data) = serde_json::from_str::<serde_json::Value>(&text) {
-                        // Extract token information from the event
-                        if let Some(mint) = data.get("mint").and_then(|v| v.as_str()) {
-                            println!("[fetch_pumpfun_listings] ✅ New token: {}", mint);
-
-                            let listing = PumpFunListing {
-                                token_address: mint.to_string(),
-                                name: data
-                                    .get("name")
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.to_string()),
-                                symbol: data
-                                    .get("symbol")
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.to_string()),
-                                logo: data
-                                    .get("uri")
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.to_string())
-                                    .or_else(|| {
-                                        data.get("image")
-                                            .and_then(|v| v.as_str())
-                                            .map(|s| s.to_string())
-                                    }),
-                                decimals: Some("6".to_string()),
-                                price_native: data
-                                    .get("initialBuy")
-                                    .and_then(|v| v.as_f64())
-                                    .map(|p| p.to_string()),
-                                price_usd: None,
-                                liquidity: data
-                                    .get("virtualSolReserves")
-                                    .and_then(|v| v.as_f64())
-                                    .map(|l| (l / 1_000_000_000.0).to_string()),
-                                fully_diluted_valuation: data
-                                    .get("marketCap")
-                                    .and_then(|v| v.as_f64())
-                                    .map(|m| m.to_string()),
-                                created_at: Some(chrono::Utc::now().timestamp().to_string()),
-                            };
-
-                            listings.push(listing);
+                    let parsed = PumpPortalMessage::parse(&text);
+                    self.ws_counters.record(&parsed);
+                    match parsed {
+                        Ok(PumpPortalMessage::NewToken(msg)) => {
+                            self.log(format_args!("✅ New token: {}", msg.mint));
+                            // Watch its trades for the rest of the window
+                            let subscribe_trades = serde_json::json!({
+                                "method": "subscribeTokenTrade",
+                                "keys": [msg.mint]
+                            });
+                            if let Err(e) = write
+                                .send(Message::Text(subscribe_trades.to_string()))
+                                .await
+                            {
+                                self.log(format_args!("Failed to subscribe to trades: {}", e));
+                            }
+                            listings.push((*msg).into());
+                        }
+                        Ok(msg @ PumpPortalMessage::TokenEvent { .. }) => {
+                            trades.record(&msg, chrono::Utc::now().timestamp_millis());
+                        }
+                        Ok(PumpPortalMessage::Error(e)) => {
+                            self.log(format_args!("Server error: {}", e));
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            self.log(format_args!("Malformed message: {}", e));
                         }
                     }
                 }
+                Ok(Some(Ok(Message::Ping(_)))) => {
+                    // tungstenite queues the pong reply itself
+                    self.ws_counters.pings.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(Some(Ok(Message::Binary(_)))) => {
+                    self.ws_counters.binary.fetch_add(1, Ordering::Relaxed);
+                }
                 Ok(Some(Ok(Message::Close(_)))) => {
-                    println!("[fetch_pumpfun_listings] WebSocket closed by server");
+                    self.log(format_args!("WebSocket closed by server"));
+                    self.ws_counters.disconnects.fetch_add(1, Ordering::Relaxed);
                     break;
                 }
                 Ok(Some(Err(e))) => {
-                    println!("[fetch_pumpfun_listings] WebSocket error: {}", e);
+                    self.log(format_args!("WebSocket error: {}", e));
+                    self.ws_counters.disconnects.fetch_add(1, Ordering::Relaxed);
                     break;
                 }
                 Ok(None) => {
-                    println!("[fetch_pumpfun_listings] WebSocket stream ended");
+                    self.log(format_args!("WebSocket stream ended"));
+                    self.ws_counters.disconnects.fetch_add(1, Ordering::Relaxed);
                     break;
                 }
                 Err(_) => {
@@ -170,154 +609,183 @@ impl Scanner {
             }
         }
 
-        println!(
-            "[fetch_pumpfun_listings] Collected {} new tokens",
-            listings.len()
-        );
+        trades.attach(&mut listings);
+        self.log(format_args!(
+            "Collected {} new tokens (messages so far: {})",
+            listings.len(),
+            self.ws_counters
+        ));
         Ok(listings)
     }
+}
 
-    /// Query Solana RPC to get token holder stats using HTTP JSON-RPC
-    pub async fn query_token_holder_stats(&self, mint: &str) -> Result<Option<HolderStats>> {
-        // Build RPC request for getProgramAccounts
-        let request = RpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "getProgramAccounts".to_string(),
-            params: serde_json::json!([
-                "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", // SPL Token Program
-                {
-                    "encoding": "base64",
-                    "filters": [
-                        { "dataSize": 165 },
-                        { "memcmp": { "offset": 0, "bytes": mint } }
-                    ]
-                }
-            ]),
-        };
-
-        // Send HTTP request
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
+impl TokenDataProvider for Scanner {
+    /// Query Solana RPC for the mint's creation time (block time of its first signature) and
+    /// the buys that landed alongside it
+    /// Walks getSignaturesForAddress backwards until the oldest signature is reached
+    async fn query_mint_launch(&self, mint: &str) -> Result<Option<MintLaunch>> {
+        Ok(self
+            .oldest_page(mint)
+            .await?
+            .and_then(|page| mint_launch(&page)))
+    }
 
-        if !response.status().is_success() {
-            return Ok(None);
+    /// Decode the pump.fun create transaction to find the share of supply the creator bought at launch
+    /// Reads the creator's post-transaction token balance for the mint
+    async fn query_creator_initial_buy_pct(
+        &self,
+        signature: &str,
+        creator: &str,
+        mint: &str,
+    ) -> Result<Option<f64>> {
+        let params = serde_json::json!([
+            signature,
+            {
+                "encoding": "jsonParsed",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0
+            }
+        ]);
+        match self.rpc_body("getTransaction", params).await? {
+            Some(body) => parse_creator_initial_buy_pct(&body, creator, mint),
+            None => Ok(None),
         }
+    }
 
-        let rpc_response: RpcResponse<Vec<ProgramAccount>> = response.json().await?;
+    /// Fetch and decode a pump.fun bonding curve account
+    async fn query_bonding_curve(&self, bonding_curve: &str) -> Result<Option<BondingCurveState>> {
+        if let Some(hit) = self.cache.curves.get(bonding_curve) {
+            return Ok(hit);
+        }
+        let params = serde_json::json!([bonding_curve, { "encoding": "base64" }]);
+        let curve = match self.rpc_body("getAccountInfo", params).await? {
+            Some(body) => parse_bonding_curve(&body)?,
+            None => None,
+        };
+        self.cache.curves.insert(bonding_curve, curve.clone());
+        Ok(curve)
+    }
 
-        if let Some(accounts) = rpc_response.result {
-            let total_holders = accounts.len() as u64;
-            Ok(Some(HolderStats {
-                total: Some(total_holders),
-                supply_distribution: None,
-            }))
-        } else {
-            Ok(None)
+    /// Query Solana RPC to get token holder stats using HTTP JSON-RPC
+    async fn query_token_holder_stats(&self, mint: &str) -> Result<Option<HolderStats>> {
+        if let Some(hit) = self.cache.holders.get(mint) {
+            return Ok(hit);
         }
+        let stats = match self
+            .rpc_body("getProgramAccounts", token_accounts_params(mint))
+            .await?
+        {
+            Some(body) => parse_holder_stats(&body)?,
+            None => None,
+        };
+        self.cache.holders.insert(mint, stats.clone());
+        Ok(stats)
     }
 
     /// Query Solana RPC to get top token holders using HTTP JSON-RPC
-    pub async fn query_token_top_holders(&self, mint: &str) -> Result<Option<TopHoldersResponse>> {
-        // Build RPC request for getProgramAccounts
-        let request = RpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "getProgramAccounts".to_string(),
-            params: serde_json::json!([
-                "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", // SPL Token Program
-                {
-                    "encoding": "base64",
-                    "filters": [
-                        { "dataSize": 165 },
-                        { "memcmp": { "offset": 0, "bytes": mint } }
-                    ]
-                }
-            ]),
+    async fn query_token_metadata(&self, mint: &str) -> Result<Option<TokenMetadata>> {
+        if let Some(hit) = self.cache.metadata.get(mint) {
+            return Ok(hit);
+        }
+        let params = serde_json::json!([mint, { "encoding": "jsonParsed" }]);
+        let metadata = match self.rpc_body("getAccountInfo", params).await? {
+            Some(body) => parse_mint_account(&body, mint)?,
+            None => None,
         };
+        self.cache.metadata.insert(mint, metadata.clone());
+        Ok(metadata)
+    }
 
-        // Send HTTP request
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Ok(None);
+    async fn query_token_top_holders(&self, mint: &str) -> Result<Option<TopHoldersResponse>> {
+        if let Some(hit) = self.cache.top_holders.get(mint) {
+            return Ok(hit);
         }
+        let top = match self
+            .rpc_body("getProgramAccounts", token_accounts_params(mint))
+            .await?
+        {
+            Some(body) => parse_top_holders(&body)?,
+            None => None,
+        };
+        self.cache.top_holders.insert(mint, top.clone());
+        Ok(top)
+    }
 
-        let rpc_response: RpcResponse<Vec<ProgramAccount>> = response.json().await?;
-
-        if let Some(accounts) = rpc_response.result {
-            // Parse token account data to get balances
-            let mut holders: Vec<(String, u64)> = Vec::new();
-            let mut total_supply: u64 = 0;
-
-            for account_info in accounts {
-                // Decode base64 account data
-                if let Some(base64_data) = account_info.account.data.get(0) {
-                    use base64::Engine;
-                    if let Ok(data) = base64::engine::general_purpose::STANDARD.decode(base64_data)
-                    {
-                        // Token account layout:
-                        // 0-32: mint (32 bytes)
-                        // 32-64: owner (32 bytes)
-                        // 64-72: amount (8 bytes, little-endian u64)
-                        if data.len() >= 72 {
-                            let owner = bs58::encode(&data[32..64]).into_string();
-                            let amount_bytes: [u8; 8] = data[64..72].try_into().unwrap_or([0; 8]);
-                            let amount = u64::from_le_bytes(amount_bytes);
-
-                            if amount > 0 {
-                                holders.push((owner, amount));
-                                total_supply += amount;
-                            }
-                        }
-                    }
-                }
-            }
+    async fn query_metaplex_metadata(&self, mint: &str) -> Result<Option<MetaplexFields>> {
+        let params = serde_json::json!([metadata_address(mint)?, { "encoding": "base64" }]);
+        match self.rpc_body("getAccountInfo", params).await? {
+            Some(body) => parse_metaplex_account(&body),
+            None => Ok(None),
+        }
+    }
 
-            // Sort by amount descending
-            holders.sort_by(|a, b| b.1.cmp(&a.1));
+    async fn query_token_account_frozen(&self, address: &str) -> Result<Option<bool>> {
+        let params = serde_json::json!([address, { "encoding": "jsonParsed" }]);
+        match self.rpc_body("getAccountInfo", params).await? {
+            Some(body) => parse_token_account_frozen(&body),
+            None => Ok(None),
+        }
+    }
 
-            // Take top 20 holders
-            let top_holders: Vec<TopHolder> = holders
-                .iter()
-                .take(20)
-                .map(|(owner, amount)| {
-                    let percentage = if total_supply > 0 {
-                        (*amount as f64 / total_supply as f64) * 100.0
-                    } else {
-                        0.0
-                    };
-
-                    TopHolder {
-                        owner_address: Some(owner.clone()),
-                        amount: Some(amount.to_string()),
-                        amount_formatted: Some(amount.to_string()),
-                        percentage_relative_to_total_supply: Some(percentage),
-                        usd_value: None,
-                    }
-                })
-                .collect();
+    async fn query_funder(&self, wallet: &str) -> Result<Option<String>> {
+        let Some(first) = self.oldest_signature(wallet).await? else {
+            return Ok(None);
+        };
+        let params = serde_json::json!([
+            first.signature,
+            {
+                "encoding": "jsonParsed",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0
+            }
+        ]);
+        match self.rpc_body("getTransaction", params).await? {
+            Some(body) => parse_funder(&body, wallet),
+            None => Ok(None),
+        }
+    }
 
-            Ok(Some(TopHoldersResponse {
-                result: Some(top_holders),
-            }))
-        } else {
-            Ok(None)
+    async fn query_recent_sandwiches(&self, mint: &str) -> Result<Option<u32>> {
+        let params = serde_json::json!([mint, { "limit": RECENT_TRANSACTIONS }]);
+        let Some(body) = self.rpc_body("getSignaturesForAddress", params).await? else {
+            return Ok(None);
+        };
+        let crowded = parse_crowded_signatures(&body)?;
+        if crowded.is_empty() {
+            return Ok(Some(0));
         }
+        let calls = crowded
+            .iter()
+            .map(|(_, signature)| {
+                (
+                    "getTransaction",
+                    serde_json::json!([
+                        signature,
+                        {
+                            "encoding": "jsonParsed",
+                            "commitment": "confirmed",
+                            "maxSupportedTransactionVersion": 0
+                        }
+                    ]),
+                )
+            })
+            .collect();
+        let mut payers = Vec::with_capacity(crowded.len());
+        for ((slot, _), body) in crowded.into_iter().zip(self.rpc_batch(calls).await?) {
+            // A transaction that can't be fetched can't be counted, so the estimate is unknown
+            let Some(payer) = body.map(|b| parse_fee_payer(&b)).transpose()?.flatten() else {
+                return Ok(None);
+            };
+            payers.push((slot, payer));
+        }
+        Ok(Some(count_sandwiches(&payers)))
     }
 
     /// Query DEX-Screener for liquidity information
-    pub async fn query_dexscreener_pair(&self, mint: &str) -> Result<Option<DexScreenerPair>> {
-        // DexScreener API (placeholder)
+    async fn query_dexscreener_pair(&self, mint: &str) -> Result<Option<DexScreenerPair>> {
+        if let Some(hit) = self.cache.pairs.get(mint) {
+            return Ok(hit);
+        }
         // Real endpoint: https://api.dexscreener.com/latest/dex/tokens/{chain}/{token_address}
         let url = format!(
             "https://api.dexscreener.com/latest/dex/tokens/solana/{}",
@@ -327,48 +795,590 @@ impl Scanner {
         if let Some(k) = &self.dexscreener_key {
             req = req.header("x-api-key", k);
         }
-        let resp = req.send().await?;
+        let resp = counted(&self.api_errors.dexscreener, req.send().await)?;
         let status = resp.status();
         let body = resp.text().await?;
-        //println!("[query_dexscreener_pair] URL={} STATUS={} RESPONSE_BODY={}", url, status, body);
-        if status.is_success() {
-            let p: DexScreenerPair = serde_json::from_str(&body)?;
-            Ok(Some(p))
+        let pair = if status.is_success() {
+            Some(parse_dexscreener_pair(&body)?)
         } else {
-            Ok(None)
+            self.api_errors.dexscreener.fetch_add(1, Ordering::Relaxed);
+            None
+        };
+        self.cache.pairs.insert(mint, pair.clone());
+        Ok(pair)
+    }
+
+    async fn query_sell_route(&self, mint: &str, amount: u64) -> Result<Option<SellRoute>> {
+        self.jupiter_quote(mint, WRAPPED_SOL_MINT, amount).await
+    }
+
+    async fn query_buy_route(&self, mint: &str, lamports: u64) -> Result<Option<SellRoute>> {
+        self.jupiter_quote(WRAPPED_SOL_MINT, mint, lamports).await
+    }
+
+    fn invalidate(&self, mint: &str) {
+        self.cache.invalidate(mint);
+    }
+
+    /// Mint accounts and bonding curves come from `getMultipleAccounts`, and token accounts
+    /// from one `getProgramAccounts` per mint, all sent as one batched request per chunk of
+    /// listings. Results land in the token cache, which enrichment reads first.
+    async fn prefetch(&self, listings: &[PumpFunListing]) -> Result<()> {
+        if !self.cache.is_enabled() {
+            return Ok(());
+        }
+        for chunk in listings.chunks(PREFETCH_BATCH_SIZE) {
+            let mints: Vec<&str> = chunk.iter().map(|l| l.token_address.as_str()).collect();
+            let curves: Vec<&str> = chunk
+                .iter()
+                .filter_map(|l| l.bonding_curve.as_deref())
+                .collect();
+            let mut calls = vec![
+                (
+                    "getMultipleAccounts",
+                    serde_json::json!([mints, { "encoding": "jsonParsed" }]),
+                ),
+                (
+                    "getMultipleAccounts",
+                    serde_json::json!([curves, { "encoding": "base64" }]),
+                ),
+            ];
+            calls.extend(
+                mints
+                    .iter()
+                    .map(|mint| ("getProgramAccounts", token_accounts_params(mint))),
+            );
+            let mut bodies = self.rpc_batch(calls).await?.into_iter();
+
+            if let Some(Some(body)) = bodies.next() {
+                for (mint, metadata) in mints.iter().zip(parse_mint_accounts(&body, &mints)?) {
+                    self.cache.metadata.insert(mint, metadata);
+                }
+            }
+            if let Some(Some(body)) = bodies.next() {
+                for (curve, state) in curves.iter().zip(parse_bonding_curves(&body)?) {
+                    self.cache.curves.insert(curve, state);
+                }
+            }
+            for (mint, body) in mints.iter().zip(bodies) {
+                let Some(body) = body else {
+                    continue;
+                };
+                self.cache.holders.insert(mint, parse_holder_stats(&body)?);
+                self.cache
+                    .top_holders
+                    .insert(mint, parse_top_holders(&body)?);
+            }
         }
+        Ok(())
     }
 }
 
-impl From<PumpFunListing> for TokenEvent {
-    fn from(p: PumpFunListing) -> Self {
-        // helper to parse optional numeric strings
-        fn parse_opt_f64(s: Option<String>) -> f64 {
-            s.as_deref()
-                .and_then(|v| v.replace(',', "").parse::<f64>().ok())
-                .unwrap_or(0.0)
+// Response parsing is kept separate from transport so recorded fixtures go through the
+// same code as live responses
+
+/// getProgramAccounts params selecting every SPL token account for `mint`
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PhnBqCXEpPxuEb";
+
+fn token_accounts_params(mint: &str) -> serde_json::Value {
+    serde_json::json!([
+        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", // SPL Token Program
+        {
+            "encoding": "base64",
+            "filters": [
+                { "dataSize": 165 },
+                { "memcmp": { "offset": 0, "bytes": mint } }
+            ]
+        }
+    ])
+}
+
+fn parse_rpc_result<T: DeserializeOwned>(body: &str) -> Result<Option<T>> {
+    let rpc_response: RpcResponse<T> = serde_json::from_str(body)?;
+    Ok(rpc_response.result)
+}
+
+/// Split a batched JSON-RPC response into one body per request, in request order. Responses
+/// may arrive in any order and are matched by id; errors are left out, so nothing gets cached
+/// for them and enrichment asks again on its own.
+pub(crate) fn split_batch(body: &str, count: usize) -> Result<Vec<Option<String>>> {
+    let responses: Vec<serde_json::Value> = serde_json::from_str(body)?;
+    let mut bodies = vec![None; count];
+    for response in responses {
+        if response.get("error").is_some() {
+            continue;
+        }
+        if let Some(slot) = response["id"]
+            .as_u64()
+            .and_then(|id| bodies.get_mut(id as usize))
+        {
+            *slot = Some(response.to_string());
+        }
+    }
+    Ok(bodies)
+}
+
+/// Accounts of a getMultipleAccounts response in request order; accounts that don't exist
+/// or don't decode as `T` are `None`
+fn parse_multiple_accounts<T: DeserializeOwned>(body: &str) -> Result<Vec<Option<T>>> {
+    #[derive(Deserialize)]
+    struct Accounts {
+        value: Vec<serde_json::Value>,
+    }
+    let Some(accounts) = parse_rpc_result::<Accounts>(body)? else {
+        return Ok(Vec::new());
+    };
+    Ok(accounts
+        .value
+        .into_iter()
+        .map(|account| serde_json::from_value(account).ok())
+        .collect())
+}
+
+/// Block time of the oldest signature in a single getSignaturesForAddress page
+#[cfg(test)]
+pub(crate) fn parse_mint_launch(body: &str) -> Result<Option<MintLaunch>> {
+    let page = parse_rpc_result::<Vec<SignatureInfo>>(body)?;
+    Ok(page.and_then(|p| mint_launch(&p)))
+}
+
+const SIGNATURE_PAGE_LIMIT: usize = 1000;
+const MAX_SIGNATURE_PAGES: usize = 10;
+
+/// Page backwards through an address's signatures, `fetch_page` taking the signature to list
+/// before, until a short page shows the oldest was reached. None when a fetch fails or the
+/// address has more history than `MAX_SIGNATURE_PAGES` pages, since the page reached then
+/// doesn't hold the oldest signature
+async fn walk_to_oldest_page<F, Fut>(mut fetch_page: F) -> Result<Option<Vec<SignatureInfo>>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<Option<Vec<SignatureInfo>>>>,
+{
+    let mut before: Option<String> = None;
+    let mut oldest: Option<Vec<SignatureInfo>> = None;
+
+    for _ in 0..MAX_SIGNATURE_PAGES {
+        let Some(page) = fetch_page(before.take()).await? else {
+            return Ok(None);
+        };
+
+        // A short page means we've reached the address's first signature
+        if page.len() < SIGNATURE_PAGE_LIMIT {
+            if page.is_empty() {
+                return Ok(oldest);
+            }
+            return Ok(Some(page));
         }
+        before = page.last().map(|last| last.signature.clone());
+        oldest = Some(page);
+    }
+
+    Ok(None)
+}
+
+/// The launch from the page holding a mint's oldest signatures: its first transaction, and
+/// the other successful ones in the same slot
+fn mint_launch(page: &[SignatureInfo]) -> Option<MintLaunch> {
+    let first = page.last()?;
+    let first_block_buys = page
+        .iter()
+        .filter(|s| s.slot == first.slot && s.err.is_none() && s.signature != first.signature)
+        .count();
+    Some(MintLaunch {
+        created_at: first.block_time,
+        first_block_buys: first_block_buys as u32,
+    })
+}
+
+/// Signature of the oldest transaction in a single getSignaturesForAddress page
+#[cfg(test)]
+pub(crate) fn parse_oldest_signature(body: &str) -> Result<Option<String>> {
+    let page = parse_rpc_result::<Vec<SignatureInfo>>(body)?;
+    Ok(page.and_then(|p| p.into_iter().last()).map(|s| s.signature))
+}
+
+/// Successful transactions in slots with at least three of them, as `(slot, signature)` in
+/// execution order, from a getSignaturesForAddress page; a sandwich needs three
+pub(crate) fn parse_crowded_signatures(body: &str) -> Result<Vec<(u64, String)>> {
+    let page = parse_rpc_result::<Vec<SignatureInfo>>(body)?.unwrap_or_default();
+    // Pages list the newest first
+    let landed: Vec<SignatureInfo> = page.into_iter().rev().filter(|s| s.err.is_none()).collect();
+    Ok(landed
+        .iter()
+        .filter(|s| landed.iter().filter(|other| other.slot == s.slot).count() >= 3)
+        .map(|s| (s.slot, s.signature.clone()))
+        .collect())
+}
+
+/// Fee payer of a jsonParsed getTransaction response
+pub(crate) fn parse_fee_payer(body: &str) -> Result<Option<String>> {
+    let tx = parse_rpc_result::<TransactionResult>(body)?;
+    Ok(tx
+        .and_then(|t| t.transaction)
+        .and_then(|t| t.message)
+        .and_then(|m| m.account_keys.into_iter().next())
+        .map(|k| k.pubkey))
+}
+
+/// Sender of the first System Program transfer or account creation paying `wallet` in a
+/// jsonParsed getTransaction response, inner instructions included
+pub(crate) fn parse_funder(body: &str, wallet: &str) -> Result<Option<String>> {
+    let Some(tx) = parse_rpc_result::<TransactionResult>(body)? else {
+        return Ok(None);
+    };
+    let inner = tx
+        .meta
+        .and_then(|m| m.inner_instructions)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|i| i.instructions);
+    let funder = tx
+        .transaction
+        .and_then(|t| t.message)
+        .map(|m| m.instructions)
+        .unwrap_or_default()
+        .into_iter()
+        .chain(inner)
+        .filter(|i| i.program.as_deref() == Some("system"))
+        .filter_map(|i| i.parsed)
+        .find_map(|parsed| {
+            let info = &parsed["info"];
+            let recipient = match parsed["type"].as_str()? {
+                "transfer" | "transferWithSeed" => &info["destination"],
+                "createAccount" | "createAccountWithSeed" => &info["newAccount"],
+                _ => return None,
+            };
+            let source = info["source"].as_str()?;
+            (recipient.as_str() == Some(wallet) && source != wallet).then(|| source.to_string())
+        });
+    Ok(funder)
+}
+
+/// Creator's share of supply from a jsonParsed getTransaction response
+pub(crate) fn parse_creator_initial_buy_pct(
+    body: &str,
+    creator: &str,
+    mint: &str,
+) -> Result<Option<f64>> {
+    let meta = match parse_rpc_result::<TransactionResult>(body)?.and_then(|tx| tx.meta) {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return Ok(None),
+    };
+
+    let creator_balance = meta
+        .post_token_balances
+        .unwrap_or_default()
+        .into_iter()
+        .find(|b| b.mint == mint && b.owner.as_deref() == Some(creator));
+
+    // No balance entry for the creator means they didn't buy at launch
+    let Some(balance) = creator_balance else {
+        return Ok(Some(0.0));
+    };
+
+    let raw_amount = balance.ui_token_amount.amount.parse::<f64>().unwrap_or(0.0);
+    let tokens = raw_amount / 10f64.powi(balance.ui_token_amount.decimals as i32);
+    Ok(Some(tokens / PUMPFUN_TOTAL_SUPPLY * 100.0))
+}
+
+/// Bonding curve state from a base64 getAccountInfo response
+pub(crate) fn parse_bonding_curve(body: &str) -> Result<Option<BondingCurveState>> {
+    let Some(account) = parse_rpc_result::<AccountInfoValue>(body)?.and_then(|r| r.value) else {
+        return Ok(None);
+    };
+    Ok(bonding_curve_state(account))
+}
+
+/// Mint metadata from a jsonParsed getMultipleAccounts response for `mints`
+pub(crate) fn parse_mint_accounts(
+    body: &str,
+    mints: &[&str],
+) -> Result<Vec<Option<TokenMetadata>>> {
+    Ok(parse_multiple_accounts::<ParsedAccount>(body)?
+        .into_iter()
+        .zip(mints)
+        .map(|(account, mint)| account.map(|account| mint_metadata(account, mint)))
+        .collect())
+}
+
+/// Bonding curve states from a base64 getMultipleAccounts response
+pub(crate) fn parse_bonding_curves(body: &str) -> Result<Vec<Option<BondingCurveState>>> {
+    Ok(parse_multiple_accounts::<AccountData>(body)?
+        .into_iter()
+        .map(|account| account.and_then(bonding_curve_state))
+        .collect())
+}
+
+/// Metaplex metadata from a base64 getAccountInfo response
+pub(crate) fn parse_metaplex_account(body: &str) -> Result<Option<MetaplexFields>> {
+    use base64::Engine;
+    let Some(account) = parse_rpc_result::<AccountInfoValue>(body)?.and_then(|r| r.value) else {
+        return Ok(None);
+    };
+    Ok(account
+        .data
+        .first()
+        .and_then(|b64| base64::engine::general_purpose::STANDARD.decode(b64).ok())
+        .and_then(|data| MetaplexFields::from_account_data(&data)))
+}
+
+/// Whether a token account is frozen, from a jsonParsed getAccountInfo response
+pub(crate) fn parse_token_account_frozen(body: &str) -> Result<Option<bool>> {
+    Ok(parse_rpc_result::<ParsedTokenAccountInfo>(body)?
+        .and_then(|r| r.value)
+        .map(|account| account.data.parsed.info.state == "frozen"))
+}
+
+fn bonding_curve_state(account: AccountData) -> Option<BondingCurveState> {
+    use base64::Engine;
+    account
+        .data
+        .first()
+        .and_then(|b64| base64::engine::general_purpose::STANDARD.decode(b64).ok())
+        .and_then(|data| BondingCurveState::from_account_data(&data))
+}
+
+/// Mint authorities, decimals, and supply from a jsonParsed getAccountInfo response
+pub(crate) fn parse_mint_account(body: &str, mint: &str) -> Result<Option<TokenMetadata>> {
+    let Some(account) = parse_rpc_result::<ParsedAccountInfo>(body)?.and_then(|r| r.value) else {
+        return Ok(None);
+    };
+    Ok(Some(mint_metadata(account, mint)))
+}
+
+fn mint_metadata(account: ParsedAccount, mint: &str) -> TokenMetadata {
+    let info = account.data.parsed.info;
+    TokenMetadata {
+        mint: Some(mint.to_string()),
+        standard: Some(
+            if account.owner == TOKEN_2022_PROGRAM {
+                "spl-token-2022"
+            } else {
+                "spl-token"
+            }
+            .to_string(),
+        ),
+        name: None,
+        symbol: None,
+        metaplex: None,
+        decimals: Some(info.decimals),
+        mint_authority: info.mint_authority,
+        freeze_authority: info.freeze_authority,
+        supply: Some(info.supply),
+        transfer_fee_bps: transfer_fee_bps(&info.extensions),
+    }
+}
+
+/// The higher of a mint's current and scheduled transfer fee; a scheduled raise takes effect
+/// at a later epoch, likely while the position is still held
+fn transfer_fee_bps(extensions: &[MintExtension]) -> Option<u16> {
+    let config = extensions
+        .iter()
+        .find(|e| e.extension == "transferFeeConfig")?;
+    let config: TransferFeeConfig = serde_json::from_value(config.state.clone()).ok()?;
+    Some(
+        config
+            .newer_transfer_fee
+            .transfer_fee_basis_points
+            .max(config.older_transfer_fee.transfer_fee_basis_points),
+    )
+}
 
-        let market_cap = parse_opt_f64(p.fully_diluted_valuation);
-        let base_price = parse_opt_f64(p.price_usd);
-        let liquidity_usd = parse_opt_f64(p.liquidity);
-
-        TokenEvent {
-            id: p.token_address.clone(),
-            token_type: p.symbol.unwrap_or_else(|| "unknown".to_string()),
-            market_cap_usd: market_cap,
-            dev_hold_pct: 0.0,
-            liquidity_usd,
-            holders: 0,
-            upgradeable: false,
-            freeze_authority: false,
-            momentum: false,
-            graduation: false,
-            base_price,
-            dev_wallet_address: None,
-            is_dev_known_rugger: false,
-            entry_market_cap: market_cap,
-            raydium_lp_detected: false,
+/// Holder count from a getProgramAccounts token-account response
+pub(crate) fn parse_holder_stats(body: &str) -> Result<Option<HolderStats>> {
+    Ok(
+        parse_rpc_result::<Vec<ProgramAccount>>(body)?.map(|accounts| HolderStats {
+            total: Some(accounts.len() as u64),
+            supply_distribution: None,
+        }),
+    )
+}
+
+/// Top 20 holders from a getProgramAccounts token-account response
+pub(crate) fn parse_top_holders(body: &str) -> Result<Option<TopHoldersResponse>> {
+    let Some(accounts) = parse_rpc_result::<Vec<ProgramAccount>>(body)? else {
+        return Ok(None);
+    };
+
+    // Parse token account data to get balances
+    let mut holders: Vec<(String, u64)> = Vec::new();
+    let mut total_supply: u64 = 0;
+
+    for account_info in accounts {
+        // Decode base64 account data
+        if let Some(base64_data) = account_info.account.data.first() {
+            use base64::Engine;
+            if let Ok(data) = base64::engine::general_purpose::STANDARD.decode(base64_data) {
+                // Token account layout:
+                // 0-32: mint (32 bytes)
+                // 32-64: owner (32 bytes)
+                // 64-72: amount (8 bytes, little-endian u64)
+                if data.len() >= 72 {
+                    let owner = bs58::encode(&data[32..64]).into_string();
+                    let amount_bytes: [u8; 8] = data[64..72].try_into().unwrap_or([0; 8]);
+                    let amount = u64::from_le_bytes(amount_bytes);
+
+                    if amount > 0 {
+                        holders.push((owner, amount));
+                        total_supply += amount;
+                    }
+                }
+            }
         }
     }
+
+    // Sort by amount descending
+    holders.sort_by_key(|h| std::cmp::Reverse(h.1));
+
+    // Take top 20 holders
+    let top_holders: Vec<TopHolder> = holders
+        .iter()
+        .take(20)
+        .map(|(owner, amount)| {
+            let percentage = if total_supply > 0 {
+                (*amount as f64 / total_supply as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            TopHolder {
+                owner_address: Some(owner.clone()),
+                amount: Some(amount.to_string()),
+                amount_formatted: Some(amount.to_string()),
+                percentage_relative_to_total_supply: Some(percentage),
+                usd_value: None,
+            }
+        })
+        .collect();
+
+    Ok(Some(TopHoldersResponse {
+        result: Some(top_holders),
+    }))
+}
+
+/// DexScreener token-pairs response body
+pub(crate) fn parse_dexscreener_pair(body: &str) -> Result<DexScreenerPair> {
+    Ok(serde_json::from_str(body)?)
+}
+
+// Jupiter wire format: amounts and price impact (a fraction) are strings
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterQuote {
+    out_amount: String,
+    price_impact_pct: String,
+    #[serde(default)]
+    route_plan: Vec<JupiterRouteStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterRouteStep {
+    swap_info: JupiterSwapInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct JupiterSwapInfo {
+    label: Option<String>,
+}
+
+/// Jupiter quote response body; an error body (e.g. `COULD_NOT_FIND_ANY_ROUTE`) means no route
+pub(crate) fn parse_jupiter_quote(body: &str) -> Result<Option<SellRoute>> {
+    let value: serde_json::Value = serde_json::from_str(body)?;
+    if value.get("error").is_some() {
+        return Ok(None);
+    }
+    let quote: JupiterQuote = serde_json::from_value(value)?;
+    if quote.route_plan.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(SellRoute {
+        out_lamports: quote.out_amount.parse()?,
+        price_impact_pct: quote.price_impact_pct.parse::<f64>()? * 100.0,
+        venues: quote
+            .route_plan
+            .into_iter()
+            .filter_map(|step| step.swap_info.label)
+            .collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(from: usize, len: usize) -> Vec<SignatureInfo> {
+        (from..from + len)
+            .map(|i| SignatureInfo {
+                signature: format!("sig{}", i),
+                slot: i as u64,
+                err: None,
+                block_time: Some(i as i64),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn walks_back_to_the_short_page() {
+        let mut requested = Vec::new();
+        let oldest = walk_to_oldest_page(|before| {
+            requested.push(before.clone());
+            let from = if before.is_some() {
+                SIGNATURE_PAGE_LIMIT
+            } else {
+                0
+            };
+            let len = if before.is_some() {
+                3
+            } else {
+                SIGNATURE_PAGE_LIMIT
+            };
+            async move { Ok(Some(page(from, len))) }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(requested, vec![None, Some("sig999".to_string())]);
+        assert_eq!(oldest.len(), 3);
+        assert_eq!(oldest.last().unwrap().signature, "sig1002");
+    }
+
+    #[tokio::test]
+    async fn keeps_the_last_full_page_when_the_next_is_empty() {
+        let oldest = walk_to_oldest_page(|before| {
+            let len = if before.is_some() {
+                0
+            } else {
+                SIGNATURE_PAGE_LIMIT
+            };
+            async move { Ok(Some(page(0, len))) }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(oldest.len(), SIGNATURE_PAGE_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_history_outruns_the_page_budget() {
+        let mut fetched = 0;
+        let oldest = walk_to_oldest_page(|_| {
+            fetched += 1;
+            let from = fetched * SIGNATURE_PAGE_LIMIT;
+            async move { Ok(Some(page(from, SIGNATURE_PAGE_LIMIT))) }
+        })
+        .await
+        .unwrap();
+
+        // The page reached isn't the oldest, so it mustn't pass for the launch
+        assert!(oldest.is_none());
+        assert_eq!(fetched, MAX_SIGNATURE_PAGES);
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_yields_nothing() {
+        let oldest = walk_to_oldest_page(|_| async { Ok(None) }).await.unwrap();
+        assert!(oldest.is_none());
+    }
 }
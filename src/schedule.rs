@@ -0,0 +1,177 @@
+//! Trading schedule for daemon mode: which UTC days and hours the bot is active
+//!
+//! A schedule is written as `[DAYS] [HH:MM-HH:MM]`, e.g. `mon-fri 13:00-21:00`, `sat,sun`,
+//! or `22-04`. Days default to every day and hours to the whole day. An hour window that
+//! wraps midnight belongs to the day it starts on.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc, Weekday};
+use serde::{Serialize, Serializer};
+use std::str::FromStr;
+
+/// How far ahead `next_start` and `window_end` look before giving up
+const HORIZON_MINUTES: i64 = 8 * 24 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// Active weekdays, indexed from Monday
+    days: [bool; 7],
+    /// Active minutes of the day as `[start, end)`; `None` means all day
+    hours: Option<(u32, u32)>,
+    spec: String,
+}
+
+impl Schedule {
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        let day = at.weekday().num_days_from_monday() as usize;
+        let minute = at.hour() * 60 + at.minute();
+        match self.hours {
+            None => self.days[day],
+            Some((start, end)) if start < end => self.days[day] && start <= minute && minute < end,
+            // Wrapping window: the early-morning part belongs to the previous day
+            Some((start, end)) => {
+                (self.days[day] && minute >= start) || (self.days[(day + 6) % 7] && minute < end)
+            }
+        }
+    }
+
+    /// First minute at or after `from` when the schedule is active
+    pub fn next_start(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.is_active(from) {
+            return Some(from);
+        }
+        let minute = truncate(from);
+        (1..=HORIZON_MINUTES)
+            .map(|i| minute + Duration::minutes(i))
+            .find(|t| self.is_active(*t))
+    }
+
+    /// When the window active at `from` closes; `None` if it never does (always active)
+    pub fn window_end(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let minute = truncate(from);
+        (1..=HORIZON_MINUTES)
+            .map(|i| minute + Duration::minutes(i))
+            .find(|t| !self.is_active(*t))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+}
+
+impl Serialize for Schedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.spec)
+    }
+}
+
+fn truncate(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(Duration::minutes(1)).unwrap_or(at)
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut days = None;
+        let mut hours = None;
+        for part in s.split_whitespace() {
+            if part.starts_with(|c: char| c.is_ascii_digit()) {
+                if hours.is_some() {
+                    bail!("schedule '{}' has more than one hour window", s);
+                }
+                hours = Some(parse_hours(part)?);
+            } else {
+                if days.is_some() {
+                    bail!("schedule '{}' has more than one day list", s);
+                }
+                days = Some(parse_days(part)?);
+            }
+        }
+        if days.is_none() && hours.is_none() {
+            bail!("empty schedule");
+        }
+        Ok(Schedule {
+            days: days.unwrap_or([true; 7]),
+            hours,
+            spec: s.trim().to_string(),
+        })
+    }
+}
+
+/// `13-21` or `13:30-21:00`; `24` may be used as the end of the day
+fn parse_hours(s: &str) -> Result<(u32, u32)> {
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| anyhow!("hour window '{}' must look like HH:MM-HH:MM", s))?;
+    let (start, end) = (parse_time(start)?, parse_time(end)?);
+    if start == end {
+        bail!("hour window '{}' is empty", s);
+    }
+    Ok((start, end))
+}
+
+fn parse_time(s: &str) -> Result<u32> {
+    let (h, m) = s.split_once(':').unwrap_or((s, "0"));
+    let h: u32 = h.parse().map_err(|_| anyhow!("invalid hour '{}'", s))?;
+    let m: u32 = m.parse().map_err(|_| anyhow!("invalid minute '{}'", s))?;
+    if m >= 60 || h * 60 + m > 24 * 60 {
+        bail!("time '{}' is out of range", s);
+    }
+    Ok(h * 60 + m)
+}
+
+/// Comma-separated days or day ranges: `mon-fri`, `sat,sun`, `mon,wed-fri`
+fn parse_days(s: &str) -> Result<[bool; 7]> {
+    let mut days = [false; 7];
+    for item in s.split(',') {
+        let (first, last) = item.split_once('-').unwrap_or((item, item));
+        let first = parse_day(first)?.num_days_from_monday() as usize;
+        let last = parse_day(last)?.num_days_from_monday() as usize;
+        let mut day = first;
+        loop {
+            days[day] = true;
+            if day == last {
+                break;
+            }
+            day = (day + 1) % 7;
+        }
+    }
+    Ok(days)
+}
+
+fn parse_day(s: &str) -> Result<Weekday> {
+    s.parse()
+        .map_err(|_| anyhow!("unknown day '{}' (expected mon, tue, ... sun)", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // 2024-01-01 was a Monday
+        Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn weekday_hours_window() {
+        let schedule: Schedule = "mon-fri 13:00-21:00".parse().unwrap();
+        assert!(schedule.is_active(at(1, 13, 0)));
+        assert!(!schedule.is_active(at(1, 21, 0)));
+        assert!(!schedule.is_active(at(6, 15, 0)));
+        // Friday evening waits for Monday
+        assert_eq!(schedule.next_start(at(5, 22, 0)), Some(at(8, 13, 0)));
+        assert_eq!(schedule.window_end(at(2, 14, 30)), Some(at(2, 21, 0)));
+    }
+
+    #[test]
+    fn wrapping_window_belongs_to_its_start_day() {
+        let schedule: Schedule = "fri 22-04".parse().unwrap();
+        assert!(schedule.is_active(at(5, 23, 0)));
+        assert!(schedule.is_active(at(6, 3, 59)));
+        assert!(!schedule.is_active(at(5, 3, 0)));
+        assert!("mon 25-26".parse::<Schedule>().is_err());
+    }
+}
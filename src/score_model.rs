@@ -0,0 +1,371 @@
+//! Logistic regression that estimates a token's probability of reaching 2x, trained on the
+//! `outcomes` dataset and versioned in `score_models`
+//!
+//! `train` fits a model on labeled tokens, holding out every fifth one to report AUC and log
+//! loss, and stores it as a new version; `train --activate` also makes it the version the bot
+//! loads. With `SCORE_MODEL_WEIGHT` above 0 the probability (as 0-100) is blended into each
+//! strategy's score: 1 replaces the built-in score, 0.5 averages the two.
+
+use crate::db::LATEST_EVENTS;
+use crate::strategy::TokenEvent;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+
+/// Inputs the model sees, all recorded on `token_events`
+pub const FEATURES: [&str; 8] = [
+    "ln_market_cap",
+    "ln_liquidity",
+    "ln_holders",
+    "dev_hold_pct",
+    "upgradeable",
+    "freeze_authority",
+    "momentum",
+    "graduation",
+];
+/// Peak multiple a token must reach to count as a positive example
+const TARGET_MULTIPLE: f64 = 2.0;
+const EPOCHS: usize = 2_000;
+const LEARNING_RATE: f64 = 0.3;
+const L2_PENALTY: f64 = 1e-3;
+
+#[allow(clippy::too_many_arguments)]
+fn encode(
+    market_cap_usd: f64,
+    liquidity_usd: f64,
+    holders: f64,
+    dev_hold_pct: f64,
+    upgradeable: bool,
+    freeze_authority: bool,
+    momentum: bool,
+    graduation: bool,
+) -> [f64; FEATURES.len()] {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    [
+        market_cap_usd.max(0.0).ln_1p(),
+        liquidity_usd.max(0.0).ln_1p(),
+        holders.max(0.0).ln_1p(),
+        dev_hold_pct,
+        flag(upgradeable),
+        flag(freeze_authority),
+        flag(momentum),
+        flag(graduation),
+    ]
+}
+
+pub fn features(ev: &TokenEvent) -> [f64; FEATURES.len()] {
+    encode(
+        ev.market_cap_usd,
+        ev.liquidity_usd,
+        ev.holders as f64,
+        ev.dev_hold_pct,
+        ev.upgradeable,
+        ev.freeze_authority,
+        ev.momentum,
+        ev.graduation,
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub x: [f64; FEATURES.len()],
+    pub reached_target: bool,
+}
+
+/// Fitted parameters, stored as JSON in `score_models.params`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreModel {
+    #[serde(skip)]
+    pub version: i32,
+    pub features: Vec<String>,
+    /// Per-feature mean and standard deviation the inputs are standardized with
+    pub means: Vec<f64>,
+    pub scales: Vec<f64>,
+    pub weights: Vec<f64>,
+    pub bias: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Validation {
+    pub samples: usize,
+    pub auc: f64,
+    pub log_loss: f64,
+}
+
+impl ScoreModel {
+    /// Fit by gradient descent on standardized features
+    pub fn fit(samples: &[Sample]) -> Self {
+        let n = samples.len().max(1) as f64;
+        let dims = FEATURES.len();
+        let means: Vec<f64> = (0..dims)
+            .map(|j| samples.iter().map(|s| s.x[j]).sum::<f64>() / n)
+            .collect();
+        let scales = (0..dims)
+            .map(|j| {
+                let var = samples
+                    .iter()
+                    .map(|s| (s.x[j] - means[j]).powi(2))
+                    .sum::<f64>()
+                    / n;
+                // A constant feature carries no signal (beyond rounding); leave it unscaled
+                if var.sqrt() > 1e-9 { var.sqrt() } else { 1.0 }
+            })
+            .collect();
+        let mut model = Self {
+            version: 0,
+            features: FEATURES.iter().map(|f| f.to_string()).collect(),
+            means,
+            scales,
+            weights: vec![0.0; dims],
+            bias: 0.0,
+        };
+
+        let rows: Vec<(Vec<f64>, f64)> = samples
+            .iter()
+            .map(|s| {
+                (
+                    model.standardize(&s.x),
+                    f64::from(u8::from(s.reached_target)),
+                )
+            })
+            .collect();
+        for _ in 0..EPOCHS {
+            let mut grad = vec![0.0; dims];
+            let mut grad_bias = 0.0;
+            for (z, y) in &rows {
+                let err = model.logistic(z) - y;
+                for (g, v) in grad.iter_mut().zip(z) {
+                    *g += err * v;
+                }
+                grad_bias += err;
+            }
+            for (w, g) in model.weights.iter_mut().zip(&grad) {
+                *w -= LEARNING_RATE * (g / n + L2_PENALTY * *w);
+            }
+            model.bias -= LEARNING_RATE * grad_bias / n;
+        }
+        model
+    }
+
+    fn standardize(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(self.means.iter().zip(&self.scales))
+            .map(|(v, (mean, scale))| (v - mean) / scale)
+            .collect()
+    }
+
+    fn logistic(&self, z: &[f64]) -> f64 {
+        let t: f64 = self.bias + z.iter().zip(&self.weights).map(|(a, w)| a * w).sum::<f64>();
+        1.0 / (1.0 + (-t).exp())
+    }
+
+    /// Probability (0.0-1.0) that the token reaches 2x
+    pub fn probability(&self, x: &[f64]) -> f64 {
+        self.logistic(&self.standardize(x))
+    }
+
+    /// Built-in `score` blended with the model's probability as 0-100; a `weight` of 1 uses
+    /// the model alone
+    pub fn blend(&self, ev: &TokenEvent, score: f64, weight: f64) -> f64 {
+        let model_score = self.probability(&features(ev)) * 100.0;
+        (1.0 - weight) * score + weight * model_score
+    }
+
+    pub fn validate(&self, samples: &[Sample]) -> Validation {
+        let mut scored: Vec<(f64, bool)> = samples
+            .iter()
+            .map(|s| (self.probability(&s.x), s.reached_target))
+            .collect();
+        let log_loss = scored
+            .iter()
+            .map(|(p, y)| {
+                let p = p.clamp(1e-9, 1.0 - 1e-9);
+                if *y { -p.ln() } else { -(1.0 - p).ln() }
+            })
+            .sum::<f64>()
+            / scored.len().max(1) as f64;
+
+        // AUC as the share of positive/negative pairs ranked correctly, ties counting half
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let positives = scored.iter().filter(|(_, y)| *y).count() as f64;
+        let negatives = scored.len() as f64 - positives;
+        let (mut negatives_below, mut correct, mut i) = (0.0, 0.0, 0);
+        while i < scored.len() {
+            let tie_end = scored[i..]
+                .iter()
+                .position(|(p, _)| *p != scored[i].0)
+                .map_or(scored.len(), |k| i + k);
+            let tie = &scored[i..tie_end];
+            let tie_pos = tie.iter().filter(|(_, y)| *y).count() as f64;
+            let tie_neg = tie.len() as f64 - tie_pos;
+            correct += tie_pos * (negatives_below + tie_neg / 2.0);
+            negatives_below += tie_neg;
+            i = tie_end;
+        }
+        let auc = if positives > 0.0 && negatives > 0.0 {
+            correct / (positives * negatives)
+        } else {
+            0.5
+        };
+        Validation {
+            samples: samples.len(),
+            auc,
+            log_loss,
+        }
+    }
+
+    /// The active version, if one has been trained and activated
+    pub async fn load_active(pool: &PgPool) -> Result<Option<Self>> {
+        let Some(row) =
+            sqlx::query("SELECT version, params FROM score_models WHERE active LIMIT 1")
+                .fetch_optional(pool)
+                .await?
+        else {
+            return Ok(None);
+        };
+        let mut model: Self = serde_json::from_str(row.get("params"))?;
+        model.version = row.get("version");
+        if model.features != FEATURES {
+            bail!(
+                "score model v{} was trained on different features; retrain it",
+                model.version
+            );
+        }
+        Ok(Some(model))
+    }
+}
+
+/// Every labeled token that was recorded live, with its features and whether it reached 2x
+pub async fn load_samples(pool: &PgPool) -> Result<Vec<Sample>> {
+    let rows = sqlx::query(&format!(
+        "SELECT e.market_cap_usd, e.liquidity_usd, e.holders, e.dev_hold_pct, e.upgradeable, \
+         e.freeze_authority, e.momentum, e.graduation, o.peak_multiple \
+         FROM outcomes o JOIN {} e ON e.id = o.token_id \
+         WHERE e.backfill_source IS NULL ORDER BY e.generated_at",
+        LATEST_EVENTS
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| Sample {
+            x: encode(
+                r.get::<Option<f64>, _>("market_cap_usd").unwrap_or(0.0),
+                r.get::<Option<f64>, _>("liquidity_usd").unwrap_or(0.0),
+                r.get::<Option<i32>, _>("holders").unwrap_or(0) as f64,
+                r.get::<Option<f64>, _>("dev_hold_pct").unwrap_or(0.0),
+                r.get::<Option<bool>, _>("upgradeable").unwrap_or(false),
+                r.get::<Option<bool>, _>("freeze_authority")
+                    .unwrap_or(false),
+                r.get::<Option<bool>, _>("momentum").unwrap_or(false),
+                r.get::<Option<bool>, _>("graduation").unwrap_or(false),
+            ),
+            reached_target: r.get::<Option<f64>, _>("peak_multiple").unwrap_or(0.0)
+                >= TARGET_MULTIPLE,
+        })
+        .collect())
+}
+
+/// Train on the labeled dataset and store the model as a new version
+pub async fn train(pool: &PgPool, min_samples: usize, activate: bool) -> Result<()> {
+    let samples = load_samples(pool).await?;
+    if samples.len() < min_samples {
+        bail!(
+            "only {} labeled tokens, need {} (see OUTCOME_DELAY_HOURS)",
+            samples.len(),
+            min_samples
+        );
+    }
+    let (holdout, training): (Vec<_>, Vec<_>) =
+        samples.iter().enumerate().partition(|(i, _)| i % 5 == 4);
+    let training: Vec<Sample> = training.into_iter().map(|(_, s)| *s).collect();
+    let holdout: Vec<Sample> = holdout.into_iter().map(|(_, s)| *s).collect();
+    let positives = samples.iter().filter(|s| s.reached_target).count();
+
+    let model = ScoreModel::fit(&training);
+    let check = model.validate(&holdout);
+    let mut tx = pool.begin().await?;
+    if activate {
+        sqlx::query("UPDATE score_models SET active = FALSE WHERE active")
+            .execute(&mut *tx)
+            .await?;
+    }
+    let version: i32 = sqlx::query(
+        "INSERT INTO score_models (kind, params, samples, positives, validation_auc, \
+         validation_log_loss, active) VALUES ('logistic', $1, $2, $3, $4, $5, $6) \
+         RETURNING version",
+    )
+    .bind(serde_json::to_string(&model)?)
+    .bind(samples.len() as i32)
+    .bind(positives as i32)
+    .bind(check.auc)
+    .bind(check.log_loss)
+    .bind(activate)
+    .fetch_one(&mut *tx)
+    .await?
+    .get("version");
+    tx.commit().await?;
+
+    println!(
+        "Trained score model v{} on {} tokens ({} reached 2x)",
+        version,
+        training.len(),
+        positives
+    );
+    println!(
+        "Holdout of {}: AUC {:.3}, log loss {:.4}",
+        check.samples, check.auc, check.log_loss
+    );
+    for (name, weight) in FEATURES.iter().zip(&model.weights) {
+        println!("  {:<16} {:+.3}", name, weight);
+    }
+    if activate {
+        println!("Activated v{}", version);
+    } else {
+        println!("Run `train --activate` to train and activate a version the bot loads");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learns_which_features_predict_a_2x() {
+        // Tokens with many holders and no freeze authority tend to reach 2x
+        let samples: Vec<Sample> = (0..400)
+            .map(|i| {
+                let holders = (i % 100) as f64;
+                let freeze = i % 7 == 0;
+                Sample {
+                    x: encode(
+                        30_000.0,
+                        20_000.0,
+                        holders,
+                        5.0,
+                        false,
+                        freeze,
+                        i % 2 == 0,
+                        false,
+                    ),
+                    reached_target: holders > 60.0 && !freeze,
+                }
+            })
+            .collect();
+        let model = ScoreModel::fit(&samples);
+        assert!(
+            model.weights[2] > 0.0,
+            "holders weight {}",
+            model.weights[2]
+        );
+        assert!(model.weights[5] < 0.0, "freeze weight {}", model.weights[5]);
+        assert!(model.validate(&samples).auc > 0.9);
+
+        let json = serde_json::to_string(&model).unwrap();
+        let loaded: ScoreModel = serde_json::from_str(&json).unwrap();
+        let x = encode(30_000.0, 20_000.0, 90.0, 5.0, false, false, true, false);
+        assert!((loaded.probability(&x) - model.probability(&x)).abs() < 1e-9);
+        assert!(model.probability(&x) > 0.5);
+    }
+}
@@ -0,0 +1,163 @@
+//! `score-test`: golden scoring fixtures, so changes to scoring show up as diffs in review
+//!
+//! Each file in `tests/data/scoring/` pins a token event together with the score breakdown,
+//! score, and failed entry filters it gets under the default strategy. A test checks every
+//! fixture against the current scoring code. After a deliberate change to a factor or a
+//! default weight, `score-test` prints what moved and rewrites the fixtures to match, and the
+//! rewritten files go into the same commit. `--check` only reports, and fails if anything
+//! moved.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use solana_sniper_bot::strategy::TokenEvent;
+use solana_sniper_bot::strategy_config::StrategyConfig;
+use std::path::{Path, PathBuf};
+
+pub const FIXTURES_DIR: &str = "tests/data/scoring";
+
+/// A token event and how the default strategy scores it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreFixture {
+    pub event: TokenEvent,
+    /// Points per factor, in evaluation order
+    #[serde(default)]
+    pub breakdown: Vec<(String, f64)>,
+    #[serde(default)]
+    pub score: f64,
+    /// Entry filters the event fails
+    #[serde(default)]
+    pub rejections: Vec<String>,
+}
+
+impl ScoreFixture {
+    /// The fixture as the current scoring code would pin it
+    pub fn rescored(&self) -> Self {
+        let config = StrategyConfig::default();
+        Self {
+            event: self.event.clone(),
+            breakdown: self
+                .event
+                .score_breakdown(&config)
+                .into_iter()
+                .map(|(name, points)| (name.to_string(), points))
+                .collect(),
+            score: self.event.compute_score(&config),
+            rejections: self
+                .event
+                .filter_rejections(&config)
+                .into_iter()
+                .map(|r| r.filter)
+                .collect(),
+        }
+    }
+
+    /// How `other` differs from this fixture, one line per change
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let points = |breakdown: &[(String, f64)], name: &str| {
+            breakdown.iter().find(|(n, _)| n == name).map(|(_, p)| *p)
+        };
+        let mut names: Vec<&str> = self.breakdown.iter().map(|(n, _)| n.as_str()).collect();
+        for (name, _) in &other.breakdown {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        for name in names {
+            let (was, now) = (
+                points(&self.breakdown, name),
+                points(&other.breakdown, name),
+            );
+            if was != now {
+                let show = |p: Option<f64>| p.map_or("-".to_string(), |p| format!("{:+.1}", p));
+                lines.push(format!("{}: {} -> {}", name, show(was), show(now)));
+            }
+        }
+        let order = |b: &[(String, f64)]| b.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+        if lines.is_empty() && order(&self.breakdown) != order(&other.breakdown) {
+            lines.push(format!(
+                "order: {} -> {}",
+                order(&self.breakdown).join(", "),
+                order(&other.breakdown).join(", ")
+            ));
+        }
+        if self.score != other.score {
+            lines.push(format!("score: {:.1} -> {:.1}", self.score, other.score));
+        }
+        if self.rejections != other.rejections {
+            lines.push(format!(
+                "rejections: [{}] -> [{}]",
+                self.rejections.join(", "),
+                other.rejections.join(", ")
+            ));
+        }
+        lines
+    }
+}
+
+/// Every fixture in `dir`, by file name
+pub fn load(dir: &Path) -> Result<Vec<(PathBuf, ScoreFixture)>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let fixture = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| anyhow::anyhow!("invalid fixture {}: {}", path.display(), e))?;
+            Ok((path, fixture))
+        })
+        .collect()
+}
+
+/// Rescore every fixture in `dir`, print what changed, and rewrite the changed ones unless
+/// `check` is set
+pub fn run(dir: &Path, check: bool) -> Result<()> {
+    let fixtures = load(dir)?;
+    let mut changed = 0;
+    for (path, pinned) in &fixtures {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let rescored = pinned.rescored();
+        let diff = pinned.diff(&rescored);
+        if diff.is_empty() {
+            println!("{:<16} {:>5.1}  unchanged", name, rescored.score);
+            continue;
+        }
+        changed += 1;
+        println!("{:<16} {:>5.1}  changed", name, rescored.score);
+        for line in diff {
+            println!("  {}", line);
+        }
+        if !check {
+            std::fs::write(path, serde_json::to_string_pretty(&rescored)? + "\n")?;
+        }
+    }
+    println!("{} fixtures, {} changed", fixtures.len(), changed);
+    if check && changed > 0 {
+        bail!("scoring fixtures are out of date; run `score-test` to regenerate them");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_fixtures_match_current_scoring() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURES_DIR);
+        let fixtures = load(&dir).unwrap();
+        assert!(fixtures.len() >= 4);
+        for (path, pinned) in fixtures {
+            let diff = pinned.diff(&pinned.rescored());
+            assert!(
+                diff.is_empty(),
+                "{} no longer matches (run `cargo run -- score-test` to regenerate):\n{}",
+                path.display(),
+                diff.join("\n")
+            );
+        }
+    }
+}
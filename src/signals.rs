@@ -0,0 +1,109 @@
+//! Externally generated buy signals
+//!
+//! Signals arrive over HTTP (`POST /signals`) or stdin and are enriched, filtered, scored,
+//! sized, and executed exactly like tokens found by the scanner. Their trades are attributed
+//! to the `signal` source, or to the detector that raised them (e.g. `volume_spike`).
+
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+
+pub type SignalSender = mpsc::Sender<ExternalSignal>;
+pub type SignalReceiver = mpsc::Receiver<ExternalSignal>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalSignal {
+    pub mint: String,
+    /// 0.0-1.0; scales the position size, full size when omitted
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// Source of signals raised inside the bot; external ones are `signal`
+    #[serde(skip)]
+    pub source: Option<&'static str>,
+}
+
+impl ExternalSignal {
+    pub fn validate(&self) -> Result<()> {
+        let bytes = bs58::decode(&self.mint)
+            .into_vec()
+            .map_err(|_| anyhow!("mint '{}' is not base58", self.mint))?;
+        if bytes.len() != 32 {
+            bail!("mint '{}' is not a 32-byte address", self.mint);
+        }
+        if let Some(c) = self.confidence
+            && !(0.0..=1.0).contains(&c)
+        {
+            bail!("confidence {} outside 0.0-1.0", c);
+        }
+        Ok(())
+    }
+
+    /// Parse a stdin line: either JSON (`{"mint": ..., "confidence": ...}`) or `MINT [CONFIDENCE]`
+    pub fn parse_line(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let signal = if line.starts_with('{') {
+            serde_json::from_str(line)?
+        } else {
+            let mut parts = line.split_whitespace();
+            let mint = parts.next().ok_or_else(|| anyhow!("empty signal"))?;
+            let confidence = parts
+                .next()
+                .map(|c| c.parse::<f64>())
+                .transpose()
+                .map_err(|_| anyhow!("confidence is not a number"))?;
+            ExternalSignal {
+                mint: mint.to_string(),
+                confidence,
+                source: None,
+            }
+        };
+        signal.validate()?;
+        Ok(signal)
+    }
+}
+
+pub fn channel() -> (SignalSender, SignalReceiver) {
+    mpsc::channel(256)
+}
+
+/// Read signals from stdin, one per line, until EOF
+pub async fn read_stdin(tx: SignalSender) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match ExternalSignal::parse_line(&line) {
+            Ok(signal) => {
+                if tx.send(signal).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => println!("[signals] Ignoring stdin line: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump";
+
+    #[test]
+    fn parses_plain_and_json_lines() {
+        let plain = ExternalSignal::parse_line(&format!("{} 0.8", MINT)).unwrap();
+        assert_eq!(plain.mint, MINT);
+        assert_eq!(plain.confidence, Some(0.8));
+
+        let json = ExternalSignal::parse_line(&format!(r#"{{"mint":"{}"}}"#, MINT)).unwrap();
+        assert_eq!(json.confidence, None);
+    }
+
+    #[test]
+    fn rejects_bad_mints_and_confidence() {
+        assert!(ExternalSignal::parse_line("not-a-mint").is_err());
+        assert!(ExternalSignal::parse_line(&format!("{} 1.5", MINT)).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+//! Transaction signing backends
+//!
+//! `KEYPAIR_PATHS` entries select the backend:
+//! - a file path: a Solana CLI keypair file (the key is held in memory)
+//! - `usb://ledger` or `usb://ledger?key=N`: a Ledger running the Solana app, using the
+//!   derivation path `44'/501'/N'` (default `44'/501'`)
+//! - `http://` or `https://` URL: a remote signing service (see `RemoteSigner`)
+//!
+//! With the latter two the machine running the bot never holds a private key.
+
+use crate::executor::Keypair;
+use anyhow::{Context, Result, anyhow, bail};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::time::Duration;
+
+pub trait Signer: Send + Sync {
+    fn pubkey_bytes(&self) -> [u8; 32];
+
+    /// Ed25519 signature over a serialized transaction message
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<[u8; 64]>>;
+
+    fn pubkey(&self) -> String {
+        bs58::encode(self.pubkey_bytes()).into_string()
+    }
+}
+
+impl Signer for Keypair {
+    fn pubkey_bytes(&self) -> [u8; 32] {
+        Keypair::pubkey_bytes(self)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<[u8; 64]>> {
+        Box::pin(async move { Ok(Keypair::sign(self, message)) })
+    }
+}
+
+/// Open the signer described by a `KEYPAIR_PATHS` entry
+pub async fn open(uri: &str) -> Result<Box<dyn Signer>> {
+    if let Some(rest) = uri.strip_prefix("usb://ledger") {
+        let account = match rest.strip_prefix("?key=") {
+            Some(n) => Some(
+                n.parse()
+                    .map_err(|_| anyhow!("invalid Ledger key in {}", uri))?,
+            ),
+            None if rest.is_empty() => None,
+            None => bail!(
+                "unsupported Ledger URI {} (expected usb://ledger[?key=N])",
+                uri
+            ),
+        };
+        return Ok(Box::new(ledger::LedgerSigner::open(account).await?));
+    }
+    if uri.starts_with("http://") || uri.starts_with("https://") {
+        return Ok(Box::new(RemoteSigner::connect(uri).await?));
+    }
+    Ok(Box::new(Keypair::from_file(uri)?))
+}
+
+/// Whether a `KEYPAIR_PATHS` entry refers to a file that must exist locally
+pub fn is_file_signer(uri: &str) -> bool {
+    !(uri.starts_with("usb://") || uri.starts_with("http://") || uri.starts_with("https://"))
+}
+
+/// Delegates signing to an HTTP service that holds the key
+///
+/// The service answers `GET {url}/pubkey` with `{"pubkey": "<base58>"}` and
+/// `POST {url}/sign` with body `{"message": "<base64>"}` with `{"signature": "<base58>"}`.
+/// When `REMOTE_SIGNER_TOKEN` is set it is sent as a bearer token.
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    pubkey: [u8; 32],
+}
+
+#[derive(Deserialize)]
+struct PubkeyResponse {
+    pubkey: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl RemoteSigner {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("sol-memebot/0.1")
+            .build()?;
+        let url = url.trim_end_matches('/').to_string();
+        let token = std::env::var("REMOTE_SIGNER_TOKEN").ok();
+        let mut req = client.get(format!("{}/pubkey", url));
+        if let Some(token) = &token {
+            req = req.bearer_auth(token);
+        }
+        let resp: PubkeyResponse = req
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("remote signer {} returned no pubkey", url))?;
+        let pubkey = decode_fixed(&resp.pubkey)
+            .with_context(|| format!("remote signer {} returned a bad pubkey", url))?;
+        Ok(Self {
+            client,
+            url,
+            token,
+            pubkey,
+        })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn pubkey_bytes(&self) -> [u8; 32] {
+        self.pubkey
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<[u8; 64]>> {
+        Box::pin(async move {
+            use base64::Engine;
+            let body = serde_json::json!({
+                "message": base64::engine::general_purpose::STANDARD.encode(message),
+            });
+            let mut req = self.client.post(format!("{}/sign", self.url)).json(&body);
+            if let Some(token) = &self.token {
+                req = req.bearer_auth(token);
+            }
+            let resp: SignResponse = req.send().await?.error_for_status()?.json().await?;
+            decode_fixed(&resp.signature).context("remote signer returned a bad signature")
+        })
+    }
+}
+
+fn decode_fixed<const N: usize>(base58: &str) -> Result<[u8; N]> {
+    bs58::decode(base58)
+        .into_vec()?
+        .try_into()
+        .map_err(|_| anyhow!("expected {} bytes", N))
+}
+
+/// Ledger Solana app over Linux hidraw, without a native HID library
+mod ledger {
+    use super::Signer;
+    use anyhow::{Result, anyhow, bail};
+    use futures::future::BoxFuture;
+    use std::fs::File;
+    use std::sync::{Arc, Mutex};
+
+    const LEDGER_VENDOR_ID: &str = "00002C97";
+    const CLA: u8 = 0xe0;
+    const INS_GET_PUBKEY: u8 = 0x05;
+    const INS_SIGN_MESSAGE: u8 = 0x06;
+    const P1_CONFIRM: u8 = 0x01;
+    const P2_EXTEND: u8 = 0x01;
+    const P2_MORE: u8 = 0x02;
+    const MAX_CHUNK: usize = 255;
+    const HID_PACKET: usize = 64;
+    const CHANNEL: u16 = 0x0101;
+    const TAG_APDU: u8 = 0x05;
+    const SW_OK: u16 = 0x9000;
+    const HARDENED: u32 = 0x8000_0000;
+
+    pub struct LedgerSigner {
+        device: Arc<Mutex<File>>,
+        path: Vec<u32>,
+        pubkey: [u8; 32],
+    }
+
+    impl LedgerSigner {
+        pub async fn open(account: Option<u32>) -> Result<Self> {
+            let mut path = vec![44 | HARDENED, 501 | HARDENED];
+            if let Some(account) = account {
+                path.push(account | HARDENED);
+            }
+            let device = Arc::new(Mutex::new(find_device()?));
+            let data = path_bytes(&path);
+            let reply = exchange(device.clone(), INS_GET_PUBKEY, 0, data).await?;
+            let pubkey = reply
+                .get(..32)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| anyhow!("Ledger returned a short pubkey"))?;
+            Ok(Self {
+                device,
+                path,
+                pubkey,
+            })
+        }
+    }
+
+    impl Signer for LedgerSigner {
+        fn pubkey_bytes(&self) -> [u8; 32] {
+            self.pubkey
+        }
+
+        fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<[u8; 64]>> {
+            Box::pin(async move {
+                // One signer, its derivation path, then the message
+                let mut data = vec![1];
+                data.extend(path_bytes(&self.path));
+                data.extend_from_slice(message);
+                let reply =
+                    exchange(self.device.clone(), INS_SIGN_MESSAGE, P1_CONFIRM, data).await?;
+                reply
+                    .get(..64)
+                    .and_then(|b| b.try_into().ok())
+                    .ok_or_else(|| anyhow!("Ledger returned a short signature"))
+            })
+        }
+    }
+
+    fn path_bytes(path: &[u32]) -> Vec<u8> {
+        let mut out = vec![path.len() as u8];
+        for part in path {
+            out.extend_from_slice(&part.to_be_bytes());
+        }
+        out
+    }
+
+    /// First hidraw node belonging to a Ledger
+    #[cfg(target_os = "linux")]
+    fn find_device() -> Result<File> {
+        let mut nodes: Vec<_> = std::fs::read_dir("/sys/class/hidraw")
+            .map_err(|e| anyhow!("cannot list hidraw devices: {}", e))?
+            .filter_map(|e| e.ok())
+            .collect();
+        nodes.sort_by_key(|e| e.file_name());
+        for node in nodes {
+            let uevent =
+                std::fs::read_to_string(node.path().join("device/uevent")).unwrap_or_default();
+            if uevent.contains(LEDGER_VENDOR_ID) {
+                let dev = std::path::Path::new("/dev").join(node.file_name());
+                return std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&dev)
+                    .map_err(|e| {
+                        anyhow!(
+                            "cannot open Ledger at {}: {} (check udev rules)",
+                            dev.display(),
+                            e
+                        )
+                    });
+            }
+        }
+        bail!("no Ledger found; connect it, unlock it, and open the Solana app")
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn find_device() -> Result<File> {
+        bail!("Ledger signing is only supported on Linux")
+    }
+
+    /// Send an APDU in chunks of at most 255 bytes and return the final reply
+    async fn exchange(device: Arc<Mutex<File>>, ins: u8, p1: u8, data: Vec<u8>) -> Result<Vec<u8>> {
+        tokio::task::spawn_blocking(move || {
+            let mut device = device.lock().map_err(|_| anyhow!("Ledger lock poisoned"))?;
+            let chunks: Vec<&[u8]> = data.chunks(MAX_CHUNK).collect();
+            let mut reply = Vec::new();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let mut p2 = 0;
+                if i > 0 {
+                    p2 |= P2_EXTEND;
+                }
+                if i + 1 < chunks.len() {
+                    p2 |= P2_MORE;
+                }
+                let mut apdu = vec![CLA, ins, p1, p2, chunk.len() as u8];
+                apdu.extend_from_slice(chunk);
+                reply = transfer(&mut *device, &apdu)?;
+            }
+            Ok(reply)
+        })
+        .await?
+    }
+
+    /// One APDU round trip using Ledger's HID framing
+    fn transfer(
+        device: &mut (impl std::io::Read + std::io::Write),
+        apdu: &[u8],
+    ) -> Result<Vec<u8>> {
+        // Frame: channel, tag, sequence index, then (first packet only) the APDU length
+        let mut payload = (apdu.len() as u16).to_be_bytes().to_vec();
+        payload.extend_from_slice(apdu);
+        for (seq, chunk) in payload.chunks(HID_PACKET - 5).enumerate() {
+            let mut packet = vec![0u8]; // HID report id
+            packet.extend_from_slice(&CHANNEL.to_be_bytes());
+            packet.push(TAG_APDU);
+            packet.extend_from_slice(&(seq as u16).to_be_bytes());
+            packet.extend_from_slice(chunk);
+            packet.resize(HID_PACKET + 1, 0);
+            device.write_all(&packet)?;
+        }
+
+        let mut response = Vec::new();
+        let mut expected = None;
+        let mut seq = 0u16;
+        while expected.is_none_or(|len| response.len() < len) {
+            let mut packet = [0u8; HID_PACKET];
+            let n = device.read(&mut packet)?;
+            if n < 5 || packet[2] != TAG_APDU || u16::from_be_bytes([packet[3], packet[4]]) != seq {
+                bail!("unexpected packet from Ledger");
+            }
+            let mut body = &packet[5..n];
+            if seq == 0 {
+                if body.len() < 2 {
+                    bail!("truncated first packet from Ledger");
+                }
+                expected = Some(u16::from_be_bytes([body[0], body[1]]) as usize);
+                body = &body[2..];
+            }
+            response.extend_from_slice(body);
+            seq += 1;
+        }
+        let len = expected.unwrap_or(0);
+        if len < 2 {
+            bail!("empty reply from Ledger");
+        }
+        response.truncate(len);
+        let status = u16::from_be_bytes([response[len - 2], response[len - 1]]);
+        if status != SW_OK {
+            bail!(
+                "Ledger rejected the request (status {:#06x}); is the Solana app open and blind signing enabled?",
+                status
+            );
+        }
+        response.truncate(len - 2);
+        Ok(response)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::io::{Read, Write};
+
+        /// Accepts every write and answers reads from `replies`, one packet per read
+        struct FakeDevice {
+            replies: Vec<Vec<u8>>,
+        }
+
+        impl Read for FakeDevice {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let packet = self.replies.remove(0);
+                buf[..packet.len()].copy_from_slice(&packet);
+                Ok(packet.len())
+            }
+        }
+
+        impl Write for FakeDevice {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        fn packet(seq: u16, body: &[u8]) -> Vec<u8> {
+            let mut packet = CHANNEL.to_be_bytes().to_vec();
+            packet.push(TAG_APDU);
+            packet.extend_from_slice(&seq.to_be_bytes());
+            packet.extend_from_slice(body);
+            packet
+        }
+
+        #[test]
+        fn reads_the_reply_and_strips_the_status() {
+            let mut device = FakeDevice {
+                replies: vec![packet(0, &[0x00, 0x03, 0xaa, 0x90, 0x00])],
+            };
+            assert_eq!(transfer(&mut device, &[CLA]).unwrap(), [0xaa]);
+        }
+
+        #[test]
+        fn rejects_a_first_packet_too_short_for_the_length() {
+            for body in [&[][..], &[0x00][..]] {
+                let mut device = FakeDevice {
+                    replies: vec![packet(0, body)],
+                };
+                let err = transfer(&mut device, &[CLA]).unwrap_err();
+                assert!(err.to_string().contains("truncated"), "{}", err);
+            }
+        }
+    }
+}
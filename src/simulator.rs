@@ -1,24 +1,183 @@
-use crate::scanner::Scanner;
-use crate::strategy::{TokenEvent, decide};
+use crate::api::{PortfolioSnapshot, PositionSnapshot, RiskSnapshot, SharedSnapshot};
+use crate::buy_template::{TOKEN_DECIMALS, token_account_address};
+use crate::config::Config;
+use crate::control::{
+    BotEvent, ControlState, DecisionEvent, MintChangeEvent, StrategyPause, TradeEvent,
+};
+use crate::cooldown::CooldownReason;
+use crate::degrade::Degradation;
+use crate::executor::{Fill, Unconfirmed};
+use crate::funding::Tracer;
+use crate::latency::{LatencyStats, Stage};
+use crate::leases::{Leases, position_lease};
+use crate::ledger;
+use crate::mint_watch::{self, MintChange, MintState};
+use crate::models::{DexPairInfo, PumpFunListing};
+use crate::orders::{EntryOrder, EntryType, ORDER_TIMEOUT_SECS, OrderState, OrderStatus};
+use crate::portfolio_manager::{GlobalHeadroom, PortfolioManager, StrategySlot};
+use crate::queue::{ListingQueue, received_at};
+use crate::reentry::ReentryCandidate;
+use crate::regime::Regime;
+use crate::repo::{NewOrder, NewTrade, OrderUpdate, Store, TradeExit, TradeSlice};
+use crate::risk::{exit_liquidity_rejection, exposure_rejections, sell_route_rejection};
+use crate::sandwich::SandwichEstimate;
+use crate::scanner::{ListingSource, TokenDataProvider, WRAPPED_SOL_MINT};
+use crate::schedule::Schedule;
+use crate::signals::{ExternalSignal, SignalReceiver};
+use crate::strategy::{
+    ENRICHMENT_FIELDS, ExitDecision, FilterRejection, LiveTokenState, TokenEvent, launch_hour,
+    should_exit,
+};
 use crate::strategy_config::StrategyConfig;
-use anyhow::Result;
-use chrono::Utc;
+use crate::tax;
+use crate::wallets::WalletPool;
+use crate::write_behind::{MarkRow, WriteBehind, WriteCounters};
+use anyhow::{Result, bail};
+use chrono::{Timelike, Utc};
+use futures::{FutureExt, StreamExt, future, stream};
 use rand::Rng;
-use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Handles shared between the simulation loop and the rest of the process
+#[derive(Clone, Copy)]
+pub struct Runtime<'a> {
+    /// Portfolio view published for the HTTP and gRPC APIs
+    pub snapshot: &'a SharedSnapshot,
+    /// Orders and filters listings before enrichment
+    pub queue: &'a ListingQueue,
+    pub latency: &'a LatencyStats,
+    /// Set when trading live rather than simulating fills
+    pub wallets: Option<&'a WalletPool>,
+    pub control: &'a ControlState,
+    /// Batched write counts, kept across runs
+    pub write_counters: &'a Arc<WriteCounters>,
+    /// Set when instances sharing the database coordinate through leases
+    pub leases: Option<&'a Leases>,
+    /// Cheapens enrichment while the RPC throttles
+    pub degradation: &'a Degradation,
+}
 
 pub struct Portfolio {
     pub sol_balance: f64,
     pub positions: Vec<Position>,
+    pub realized_pnl_usd: f64,
+    pub peak_equity_usd: f64,
+    /// Realized profits set aside above the compound cap, waiting to be swept
+    pub banked_sol: f64,
+    /// Profits already sent to the sweep address
+    pub swept_sol: f64,
+    /// Entries opened and SOL spent on them during the current run, for the run budget
+    pub run_entries: usize,
+    pub run_sol_spent: f64,
+    /// `run_metadata` row of the current run, recorded on what the strategy writes
+    pub run_id: Option<i32>,
 }
 
 #[allow(dead_code)]
 pub struct Position {
+    /// Client order ID of the entry, unique per position; see `new_order_id`
+    pub order_id: String,
     pub token_id: String,
+    pub source: String,
     pub entry_price: f64,
     pub qty: f64,
     pub usd_in: f64,
+    pub sol_in: f64,
     pub opened_at: chrono::DateTime<Utc>,
     pub score: f64,
+    pub dev_wallet_address: Option<String>,
+    pub launched_at: Option<i64>,
+    pub is_reentry: bool,
+    /// Public key of the wallet that bought the position; `None` when simulated
+    pub wallet: Option<String>,
+    /// Raw token units the live buy received, which its sell sells; `None` when simulated or
+    /// the fill couldn't be read
+    pub token_raw: Option<u64>,
+    /// Set while a sell or a slice buy for the position is sent with the manager unlocked;
+    /// neither starts while the other is in flight
+    pub trading: bool,
+    /// Liquidity observed when the position was opened
+    pub entry_liquidity_usd: f64,
+    /// Highest observed price while the position has been open
+    pub peak_price: f64,
+    /// Live state of this position's own token, used for exits and mark-to-market
+    pub live: LiveTokenState,
+    pub last_marked_at: Option<chrono::DateTime<Utc>>,
+    /// Mint and metadata as first read after entry, or as of the last reported change
+    pub mint_baseline: Option<MintState>,
+    /// Mint change the position is being sold for, kept until a sell goes through
+    pub mint_exit: Option<&'static str>,
+}
+
+/// Random client order ID, generated when a strategy decides to enter. It is carried through
+/// execution to the `trades` row, whose unique constraints make retried recording a no-op.
+pub fn new_order_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// UTC hour a trade is attributed to: the token's launch hour, or the current hour when the
+/// creation time is unknown (new listings are seen within seconds of launch)
+fn trade_hour(launched_at: Option<i64>) -> i16 {
+    launch_hour(launched_at).unwrap_or_else(|| Utc::now().hour()) as i16
+}
+
+impl Position {
+    /// Client order ID of the sell closing this position. Derived from the entry's, so a
+    /// retried exit reuses it.
+    pub fn exit_order_id(&self) -> String {
+        format!("{}-exit", self.order_id)
+    }
+
+    pub fn new_live_state(entry_price: f64, entry_liquidity_usd: f64) -> LiveTokenState {
+        LiveTokenState {
+            price_usd: entry_price,
+            liquidity_usd: entry_liquidity_usd,
+            ..Default::default()
+        }
+    }
+
+    /// Refresh the live state from a DEX Screener pair and track the peak price
+    pub fn refresh(&mut self, pair: &DexPairInfo) {
+        self.live.update_from_pair(pair);
+        self.peak_price = self.peak_price.max(self.live.price_usd);
+    }
+
+    pub fn value_usd(&self) -> f64 {
+        self.qty * self.live.price_usd
+    }
+
+    pub fn unrealized_pnl_usd(&self) -> f64 {
+        self.value_usd() - self.usd_in
+    }
+}
+
+/// A simulated position on `token_id` that cost `usd_in` ($) and `sol_in` at `entry_price`,
+/// in a $1,000 pool; tests override the other fields they care about
+#[cfg(test)]
+pub fn test_position(token_id: &str, entry_price: f64, usd_in: f64, sol_in: f64) -> Position {
+    Position {
+        order_id: new_order_id(),
+        token_id: token_id.into(),
+        source: "pumpportal".into(),
+        entry_price,
+        qty: usd_in / entry_price,
+        usd_in,
+        sol_in,
+        opened_at: Utc::now(),
+        score: 80.0,
+        dev_wallet_address: None,
+        launched_at: None,
+        is_reentry: false,
+        wallet: None,
+        token_raw: None,
+        trading: false,
+        entry_liquidity_usd: 1_000.0,
+        peak_price: entry_price,
+        live: Position::new_live_state(entry_price, 1_000.0),
+        last_marked_at: None,
+        mint_baseline: None,
+        mint_exit: None,
+    }
 }
 
 impl Portfolio {
@@ -26,209 +185,3003 @@ impl Portfolio {
         Self {
             sol_balance,
             positions: vec![],
+            realized_pnl_usd: 0.0,
+            peak_equity_usd: 0.0,
+            banked_sol: 0.0,
+            swept_sol: 0.0,
+            run_entries: 0,
+            run_sol_spent: 0.0,
+            run_id: None,
         }
     }
+
+    pub fn unrealized_pnl_usd(&self) -> f64 {
+        self.positions.iter().map(|p| p.unrealized_pnl_usd()).sum()
+    }
+
+    /// Cash, including banked and swept profits, plus open positions at their latest marks
+    pub fn equity_usd(&self, sol_usd_price: f64) -> f64 {
+        (self.sol_balance + self.banked_sol + self.swept_sol) * sol_usd_price
+            + self.positions.iter().map(|p| p.value_usd()).sum::<f64>()
+    }
+
+    /// SOL committed to open positions
+    pub fn open_sol(&self) -> f64 {
+        self.positions.iter().map(|p| p.sol_in).sum()
+    }
+
+    /// Move free SOL above `cap` of trading capital (free balance plus open positions) out of
+    /// the tradable balance; returns the SOL banked
+    pub fn bank_above(&mut self, cap: f64) -> f64 {
+        let excess = (self.sol_balance + self.open_sol() - cap).min(self.sol_balance);
+        if excess <= 0.0 {
+            return 0.0;
+        }
+        self.sol_balance -= excess;
+        self.banked_sol += excess;
+        excess
+    }
+
+    /// Update the equity high-water mark and return the current drawdown from it (0.2 = -20%)
+    pub fn update_drawdown(&mut self, sol_usd_price: f64) -> f64 {
+        let equity = self.equity_usd(sol_usd_price);
+        self.peak_equity_usd = self.peak_equity_usd.max(equity);
+        if self.peak_equity_usd > 0.0 {
+            (self.peak_equity_usd - equity) / self.peak_equity_usd
+        } else {
+            0.0
+        }
+    }
+
+    pub fn snapshot(&self, strategy_id: &str, sol_usd_price: f64) -> PortfolioSnapshot {
+        let equity_usd = self.equity_usd(sol_usd_price);
+        PortfolioSnapshot {
+            sol_balance: self.sol_balance,
+            banked_sol: self.banked_sol,
+            swept_sol: self.swept_sol,
+            equity_usd,
+            realized_pnl_usd: self.realized_pnl_usd,
+            unrealized_pnl_usd: self.unrealized_pnl_usd(),
+            peak_equity_usd: self.peak_equity_usd,
+            drawdown_pct: if self.peak_equity_usd > 0.0 {
+                (self.peak_equity_usd - equity_usd) / self.peak_equity_usd
+            } else {
+                0.0
+            },
+            positions: self
+                .positions
+                .iter()
+                .map(|p| PositionSnapshot {
+                    strategy_id: strategy_id.to_string(),
+                    token_id: p.token_id.clone(),
+                    entry_price: p.entry_price,
+                    last_price: p.live.price_usd,
+                    qty: p.qty,
+                    usd_in: p.usd_in,
+                    value_usd: p.value_usd(),
+                    unrealized_pnl: p.unrealized_pnl_usd(),
+                    wallet: p.wallet.clone(),
+                    opened_at: p.opened_at,
+                })
+                .collect(),
+            regime: None,
+            updated_at: Some(Utc::now()),
+            risk: RiskSnapshot::default(),
+        }
+    }
+}
+
+/// Longest single run in daemon mode, so an always-open schedule still gets daily
+/// `run_metadata` rows
+const MAX_SEGMENT_HOURS: i64 = 24;
+
+/// Daemon mode: trade only while `schedule` is active and record each active window as its
+/// own run. Positions carry over from one window to the next.
+pub async fn run_scheduled<S: ListingSource + TokenDataProvider>(
+    store: &Store,
+    cfg: &Config,
+    schedule: &Schedule,
+    scanner: &S,
+    manager: &mut PortfolioManager,
+    runtime: &Runtime<'_>,
+    mut signals: Option<SignalReceiver>,
+) -> Result<()> {
+    loop {
+        let now = Utc::now();
+        let Some(start) = schedule.next_start(now) else {
+            bail!("schedule '{}' is never active", schedule.as_str());
+        };
+        if start > now {
+            println!(
+                "[schedule] outside '{}', paused until {}",
+                schedule.as_str(),
+                start.format("%Y-%m-%d %H:%M UTC")
+            );
+            tokio::time::sleep((start - now).to_std()?).await;
+            continue;
+        }
+        let max_end = now + chrono::Duration::hours(MAX_SEGMENT_HOURS);
+        let end = schedule
+            .window_end(now)
+            .map_or(max_end, |end| end.min(max_end));
+        println!(
+            "[schedule] window '{}' open until {}",
+            schedule.as_str(),
+            end.format("%Y-%m-%d %H:%M UTC")
+        );
+        run_simulation(
+            store,
+            cfg,
+            (end - now).to_std()?,
+            scanner,
+            manager,
+            runtime,
+            signals.as_mut(),
+        )
+        .await?;
+    }
 }
 
-pub async fn run_simulation(pool: &PgPool, minutes: u64, scanner: &Scanner) -> Result<()> {
-    let mut collected = Vec::new();
+pub async fn run_simulation<S: ListingSource + TokenDataProvider>(
+    store: &Store,
+    cfg: &Config,
+    duration: std::time::Duration,
+    scanner: &S,
+    manager: &mut PortfolioManager,
+    runtime: &Runtime<'_>,
+    mut signals: Option<&mut SignalReceiver>,
+) -> Result<()> {
+    let Runtime {
+        queue,
+        latency,
+        wallets,
+        control,
+        write_counters,
+        degradation,
+        ..
+    } = *runtime;
+    let writes = WriteBehind::spawn(
+        store.clone(),
+        cfg.write_batch_size,
+        std::time::Duration::from_millis(cfg.write_flush_interval_ms),
+        write_counters.clone(),
+    );
 
-    // Set deadline based on minutes parameter
     let start_time = std::time::Instant::now();
-    let duration = std::time::Duration::from_secs(minutes * 60);
     let deadline = start_time + duration;
+    let run = store.start_run(cfg, manager).await?;
+    manager.start_run(run.id);
+    let expired = store.expire_orders().await?;
+    if expired > 0 {
+        println!(
+            "[orders] Closed {} orders left open by an earlier run",
+            expired
+        );
+    }
 
-    println!("Simulation will run for {} minutes", minutes);
+    // Open positions are watched by the exit engine on its own cadence until the deadline,
+    // while the scan decides on each new token as soon as it is enriched
+    let shared = tokio::sync::Mutex::new(manager);
+    let (stop_exits, exits_stopped) = tokio::sync::watch::channel(false);
+    let exits = run_exit_engine(
+        store,
+        scanner,
+        &shared,
+        &writes,
+        cfg,
+        runtime,
+        exits_stopped,
+    );
+    let scan = async {
+        let mut budget_exhausted = false;
+        let mut last_sweep = start_time;
+        let mut last_reclaim = start_time;
+        let mut last_sol_price: Option<std::time::Instant> = None;
+        let funding = Tracer::new(cfg.funding_hops, &cfg.funding_ignore);
+        let blacklist = store.load_blacklist().await?;
+        let dev_wallets = store.load_dev_wallets().await?;
+        let mut control_version = control.version();
+        let mut events_seen = 0;
 
-    while std::time::Instant::now() < deadline {
-        let listings = scanner.fetch_pumpfun_listings().await.unwrap_or_default();
-        println!("Fetched {} listings from Pump.fun", listings.len());
-        for l in listings.into_iter() {
-            // Check if we've exceeded the time limit
+        println!(
+            "Simulation will run for {} minutes",
+            duration.as_secs().div_ceil(60)
+        );
+
+        while std::time::Instant::now() < deadline {
+            let steps = degradation.enrichment(cfg.enrichment());
+            // Wrapped SOL's first pair is priced in USD
+            let now = Utc::now();
+            let mut sol_price = None;
+            if cfg.regime_sol_interval_secs > 0
+                && steps.dexscreener
+                && last_sol_price
+                    .is_none_or(|at| at.elapsed().as_secs() >= cfg.regime_sol_interval_secs)
+            {
+                last_sol_price = Some(std::time::Instant::now());
+                if let Ok(Some(d)) = scanner.query_dexscreener_pair(WRAPPED_SOL_MINT).await
+                    && let Some(price) = d
+                        .pairs
+                        .as_ref()
+                        .and_then(|p| p.first())
+                        .and_then(|p| p.price_usd)
+                {
+                    sol_price = Some(price);
+                    // Kept as the hold-SOL baseline of `report`
+                    if let Err(e) = store.record_price(WRAPPED_SOL_MINT, price, "sol").await {
+                        println!("[regime] Failed to store the SOL price: {}", e);
+                    }
+                }
+            }
+            let mut manager = shared.lock().await;
+            if let Some(price) = sol_price {
+                manager.regime.observe_sol_price(price, now);
+            }
+            if let Some(reading) = manager.regime.refresh(now) {
+                println!("[regime] Market is {}", reading);
+            }
+            drop(manager);
+
+            let mut listings = if cfg.enable_pumpfun {
+                scanner.fetch_pumpfun_listings().await.unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            let detected_at = std::time::Instant::now();
+            queue.prioritize(&mut listings);
+            println!("Fetched {} listings from Pump.fun", listings.len());
+            if let Err(e) = scanner.prefetch(&listings).await {
+                println!("[scanner] batched prefetch failed: {}", e);
+            }
+
+            // Enrich up to `scan_concurrency` listings at once, newest first, skipping any that
+            // went stale while waiting and stopping at the deadline. Each one is decided on as
+            // soon as it is enriched
+            let mut enriched = std::pin::pin!(
+                stream::iter(listings)
+                    .take_while(|_| future::ready(std::time::Instant::now() < deadline))
+                    .filter(|l| future::ready(queue.admit(l, Utc::now().timestamp())))
+                    .map(|l| {
+                        let received = received_at(&l);
+                        enrich_timed(scanner, &steps, &funding, l, detected_at, latency)
+                            .map(move |(ev, detected_at)| (ev, detected_at, received))
+                    })
+                    .buffered(cfg.scan_concurrency.max(1))
+            );
+            while let Some((mut ev, detected_at, received)) = enriched.next().await {
+                shared
+                    .lock()
+                    .await
+                    .regime
+                    .observe_launch(ev.graduation, Utc::now());
+                // Deciding on the listings ahead of it, or waiting for an approval, may have
+                // left this one stale as well
+                if !queue.recheck(received, Utc::now().timestamp()) {
+                    println!("[queue] {} went stale before its decision, dropping", ev.id);
+                    continue;
+                }
+                blacklist.apply(&mut ev);
+                dev_wallets.apply(&mut ev);
+                process_event(
+                    store,
+                    scanner,
+                    &shared,
+                    &writes,
+                    cfg,
+                    runtime,
+                    run.id,
+                    &mut control_version,
+                    &ev,
+                    detected_at,
+                )
+                .await?;
+                events_seen += 1;
+            }
             if std::time::Instant::now() >= deadline {
                 println!("Time limit reached, stopping collection...");
-                break;
             }
 
-            let mut ev: TokenEvent = l.clone().into();
+            // External signals go through the same enrichment and decisions as scanned listings
+            let mut pending = Vec::new();
+            if let Some(rx) = signals.as_mut() {
+                while let Ok(signal) = rx.try_recv() {
+                    pending.push(signal);
+                }
+            }
+            if !pending.is_empty() {
+                let detected_at = std::time::Instant::now();
+                println!("Received {} external signals", pending.len());
+                let mut enriched = std::pin::pin!(
+                    stream::iter(pending)
+                        .map(|s| enrich_signal(scanner, &steps, s))
+                        .buffered(cfg.scan_concurrency.max(1))
+                );
+                while let Some(mut ev) = enriched.next().await {
+                    blacklist.apply(&mut ev);
+                    dev_wallets.apply(&mut ev);
+                    process_event(
+                        store,
+                        scanner,
+                        &shared,
+                        &writes,
+                        cfg,
+                        runtime,
+                        run.id,
+                        &mut control_version,
+                        &ev,
+                        detected_at,
+                    )
+                    .await?;
+                    events_seen += 1;
+                }
+            }
 
-            if let Ok(Some(holder_stats)) = scanner.query_token_holder_stats(&l.token_address).await
+            if let Some(address) = &cfg.sweep_address
+                && last_sweep.elapsed().as_secs() >= cfg.sweep_interval_secs
+            {
+                sweep_profits(store, cfg, address, &shared, wallets).await?;
+                last_sweep = std::time::Instant::now();
+            }
+            if let Some(wallets) = wallets
+                && last_reclaim.elapsed().as_secs() >= cfg.rent_reclaim_interval_secs
             {
-                ev.holders = holder_stats.total.unwrap_or(0) as i32;
+                reclaim_rent(wallets).await;
+                last_reclaim = std::time::Instant::now();
             }
 
-            if let Ok(Some(top_holders)) = scanner.query_token_top_holders(&l.token_address).await {
-                if let Some(holders_list) = top_holders.result {
-                    if let Some(first_holder) = holders_list.first() {
-                        // Assume first holder is the dev/creator
-                        ev.dev_hold_pct = first_holder
-                            .percentage_relative_to_total_supply
-                            .unwrap_or(0.0);
-                        ev.dev_wallet_address = first_holder.owner_address.clone();
-                    }
-                }
+            if !budget_exhausted && shared.lock().await.headroom().run_budget_exhausted() {
+                budget_exhausted = true;
+                println!(
+                    "[budget] Run budget spent, managing open positions only until the run ends"
+                );
             }
-            if let Ok(Some(d)) = scanner.query_dexscreener_pair(&l.token_address).await {
-                if let Some(pairs) = d.pairs {
-                    if let Some(first) = pairs.get(0) {
-                        ev.liquidity_usd = first.liquidity_usd.unwrap_or(0.0);
-                        if ev.base_price <= 0.0 {
-                            ev.base_price = first.price_usd.unwrap_or(0.0);
-                        }
-                    }
-                }
+
+            degradation.refresh();
+            // small delay to avoid hammering (and to wait for new listings on next poll)
+            tokio::time::sleep(
+                degradation.poll_interval(std::time::Duration::from_secs(cfg.poll_interval_secs)),
+            )
+            .await;
+        }
+        let _ = stop_exits.send(true);
+        Ok::<_, anyhow::Error>(events_seen)
+    };
+    let (events_seen, ()) = tokio::try_join!(scan, exits)?;
+    let manager = shared.into_inner();
+
+    writes.flush().await;
+    println!("[db] batched writes: {}", writes.counters);
+    print!("[latency] pipeline latency by stage:\n{}", latency);
+    let trades_made = manager
+        .slots
+        .iter()
+        .filter(|s| !s.shadow)
+        .map(|s| s.portfolio.run_entries)
+        .sum();
+    store.finish_run(&run, events_seen, trades_made).await?;
+
+    for slot in &manager.slots {
+        let portfolio = &slot.portfolio;
+        println!(
+            "[{}] {}Remaining SOL balance: {} SOL ({:.4} banked, {:.4} swept), equity ${:.2} (realized ${:.2}, unrealized ${:.2})",
+            slot.id,
+            if slot.shadow { "(shadow) " } else { "" },
+            portfolio.sol_balance,
+            portfolio.banked_sol,
+            portfolio.swept_sol,
+            portfolio.equity_usd(slot.config.sol_usd_price),
+            portfolio.realized_pnl_usd,
+            portfolio.unrealized_pnl_usd()
+        );
+    }
+    println!("Simulation finished.");
+    Ok(())
+}
+
+/// The portfolio manager, shared between the scan and the exit engine
+type SharedManager<'a> = tokio::sync::Mutex<&'a mut PortfolioManager>;
+
+/// Record a newly seen token and run every strategy on it: the entry decision, then the
+/// strategy's working orders and re-entries. The manager is locked one step at a time and
+/// never across network or chain I/O, so the exit engine runs while they wait.
+#[allow(clippy::too_many_arguments)]
+async fn process_event<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    writes: &WriteBehind,
+    cfg: &Config,
+    runtime: &Runtime<'_>,
+    run_id: i32,
+    control_version: &mut u64,
+    ev: &TokenEvent,
+    detected_at: std::time::Instant,
+) -> Result<()> {
+    let Runtime {
+        latency,
+        wallets,
+        control,
+        leases,
+        ..
+    } = *runtime;
+    let mut manager = shared.lock().await;
+
+    // Pick up parameter changes made through the control plane
+    if control.version() != *control_version {
+        *control_version = control.version();
+        for slot in manager.slots.iter_mut() {
+            if let Some(config) = control.config(&slot.id) {
+                slot.config = config;
             }
-            // heuristics for momentum/graduation: Pump.fun may include flags; here we set based on market cap or liquidity
-            ev.momentum = ev.liquidity_usd > 1000.0;
-            ev.graduation = ev.market_cap_usd >= 50000.0
-                && ev.market_cap_usd <= 300000.0
-                && ev.liquidity_usd > 1000.0;
-
-            collected.push(ev);
-        }
-        // small delay to avoid hammering (and to wait for new listings on next poll)
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-    }
-
-    // Load strategy config
-    let config = StrategyConfig::default();
-
-    // portfolio setup from config
-    let mut portfolio = Portfolio::new(config.starting_sol_balance);
-    let sol_usd_price = config.sol_usd_price;
-    let max_per_trade_sol = config.max_sol_per_trade;
-
-    for ev in collected.into_iter() {
-        // persist token event
-        let score = ev.compute_score(&config);
-        sqlx::query("INSERT INTO token_events (id, token_type, market_cap_usd, dev_hold_pct, liquidity_usd, holders, upgradeable, freeze_authority, momentum, graduation, base_price, score) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12) ON CONFLICT (id) DO NOTHING")
-            .bind(&ev.id)
-            .bind(&ev.token_type)
-            .bind(ev.market_cap_usd)
-            .bind(ev.dev_hold_pct)
-            .bind(ev.liquidity_usd)
-            .bind(ev.holders)
-            .bind(ev.upgradeable)
-            .bind(ev.freeze_authority)
-            .bind(ev.momentum)
-            .bind(ev.graduation)
-            .bind(ev.base_price)
-            .bind(score)
-            .execute(pool)
+        }
+    }
+
+    // persist token event
+    let score = ev.compute_score(manager.primary_config());
+    let regime = manager.regime.regime();
+    let slots = manager.slots.len();
+    drop(manager);
+    writes.token_event(ev.clone(), score, Some(run_id)).await;
+    control.publish(BotEvent::Token(ev.clone()));
+
+    // Every strategy sees every event; global limits are shared between them, and
+    // separately between shadow strategies, which never touch the wallets
+    for idx in 0..slots {
+        let (mut headroom, wallets, strategy_id) = {
+            let manager = shared.lock().await;
+            let slot = &manager.slots[idx];
+            (
+                manager.headroom_of(slot.shadow),
+                wallets.filter(|_| !slot.shadow),
+                slot.id.clone(),
+            )
+        };
+        try_enter(
+            store,
+            scanner,
+            shared,
+            idx,
+            ev,
+            detected_at,
+            &mut headroom,
+            regime,
+            wallets,
+            control,
+            latency,
+            leases,
+        )
+        .await?;
+        process_orders(
+            store,
+            scanner,
+            shared,
+            idx,
+            &mut headroom,
+            wallets,
+            control,
+            leases,
+        )
+        .await?;
+        if control.entries_allowed() && control.strategy_pause(&strategy_id, Utc::now()).is_none() {
+            process_reentries(
+                store,
+                scanner,
+                shared,
+                idx,
+                &mut headroom,
+                regime,
+                wallets,
+                control,
+                leases,
+            )
             .await?;
+        }
+        track_equity(writes, cfg, control, &mut shared.lock().await.slots[idx]);
+    }
+    let snapshot = shared.lock().await.snapshot();
+    *runtime.snapshot.write().await = snapshot;
+    Ok(())
+}
 
-        let decision = decide(&ev, &config);
-        // Enforce max positions from config
-        if decision.should_buy
-            && portfolio.sol_balance > 0.01
-            && portfolio.positions.len() < config.max_positions
-        {
-            let to_spend_sol = f64::min(max_per_trade_sol, portfolio.sol_balance);
-            let mut rng = rand::thread_rng();
-            let impact = 1.0 + rng.gen_range(0.0..0.05);
-            let entry_price = ev.base_price * impact;
-            let usd_in = to_spend_sol * sol_usd_price;
-            let qty = if entry_price > 0.0 {
-                usd_in / entry_price
+/// Run one strategy's entry decision for a newly observed token, first seen at `detected_at`.
+/// The manager is only locked to decide and to apply the result: lookups, the lease, an
+/// approval, order records, and the buy itself go out with it unlocked, so the exit engine
+/// keeps running. The limits are checked again under the lock before the buy is sent.
+#[allow(clippy::too_many_arguments)]
+async fn try_enter<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    idx: usize,
+    ev: &TokenEvent,
+    detected_at: std::time::Instant,
+    headroom: &mut GlobalHeadroom,
+    regime: Regime,
+    wallets: Option<&WalletPool>,
+    control: &ControlState,
+    latency: &LatencyStats,
+    leases: Option<&Leases>,
+) -> Result<()> {
+    let started = std::time::Instant::now();
+    let now = Utc::now();
+
+    // Decide and check the limits under the lock
+    let mut guard = shared.lock().await;
+    let slot = &mut guard.slots[idx];
+    let decision = slot.decide(ev);
+    let target = EntryTarget {
+        token_id: &ev.id,
+        dev_wallet_address: ev.dev_wallet_address.as_deref(),
+        launched_at: ev.launched_at,
+        graduation: ev.graduation,
+        liquidity_usd: ev.liquidity_usd,
+        price: ev.base_price,
+        priced_at_ms: decision.priced_at_ms,
+    };
+    let score = decision.score;
+    let config = slot.config.clone();
+    let strategy_id = slot.id.clone();
+    let preset = slot.preset.clone();
+    let shadow = slot.shadow;
+    let run_id = slot.portfolio.run_id;
+
+    // Skip tokens we recently exited or rejected unless they've improved enough
+    let cooldown = if decision.should_buy {
+        slot.cooldowns.check(&ev.id, score, now, &config)
+    } else {
+        slot.cooldowns
+            .record(&ev.id, CooldownReason::Rejected, score, now, &config);
+        None
+    };
+    if let Some(r) = &cooldown {
+        println!("[{}] Cooldown: {} ({})", strategy_id, ev.id, r.detail);
+    }
+    let paused = control
+        .strategy_pause(&strategy_id, now)
+        .map(|p| FilterRejection::new("paused", p.reason));
+
+    // The market regime scales the position, down to standing aside entirely
+    let size_multiplier = regime.size_multiplier(&config);
+    let stand_down = (size_multiplier <= 0.0).then(|| {
+        FilterRejection::new(
+            "regime",
+            format!("standing down in a {} market", regime.as_str()),
+        )
+    });
+    let to_spend_sol = entry_size(
+        &config,
+        &slot.portfolio,
+        config.max_sol_per_trade
+            * decision.size_fraction
+            * ev.signal_confidence.unwrap_or(1.0)
+            * size_multiplier,
+        ev.liquidity_usd,
+    );
+
+    // Strategy and global exposure limits are checked before anything is executed
+    let checked = decision.should_buy && cooldown.is_none() && stand_down.is_none();
+    let mut exposure = if checked {
+        entry_limits(slot, headroom, &target, to_spend_sol)
+    } else {
+        Vec::new()
+    };
+    let has_room = slot.portfolio.positions.len() < config.max_positions;
+    drop(guard);
+
+    if checked && exposure.is_empty() {
+        exposure.extend(sell_route_check(scanner, &target, to_spend_sol, &config).await);
+    }
+    for r in &exposure {
+        println!(
+            "[{}] Risk limit: {} {} ({})",
+            strategy_id, ev.id, r.filter, r.detail
+        );
+    }
+
+    let sandwich = if wallets.is_some()
+        && decision.should_buy
+        && cooldown.is_none()
+        && paused.is_none()
+        && stand_down.is_none()
+        && exposure.is_empty()
+    {
+        Some(sandwich_estimate(scanner, &target, to_spend_sol, &config).await)
+    } else {
+        None
+    };
+    let sandwich_risk = sandwich.as_ref().and_then(|s| s.rejection());
+    if let Some(r) = &sandwich_risk {
+        println!("[{}] Sandwich risk: {} ({})", strategy_id, ev.id, r.detail);
+    }
+    let to_spend_sol = sandwich.as_ref().map_or(to_spend_sol, |s| s.sol_amount);
+    let jito = sandwich.as_ref().is_some_and(|s| s.jito);
+
+    // Enforce max positions from config
+    let mut enter = decision.should_buy
+        && cooldown.is_none()
+        && paused.is_none()
+        && stand_down.is_none()
+        && exposure.is_empty()
+        && sandwich_risk.is_none()
+        && has_room
+        && control.entries_allowed();
+
+    // Instances sharing the database claim the token before buying it
+    let lease = position_lease(&strategy_id, &ev.id);
+    let leased = match leases {
+        Some(leases) if enter => claim_rejection(leases, &lease).await,
+        _ => None,
+    };
+    if let Some(r) = &leased {
+        println!("[{}] Leased: {} ({})", strategy_id, ev.id, r.detail);
+        enter = false;
+    }
+
+    // Large live buys wait for an operator while exits go on
+    let approval = if enter && wallets.is_some() {
+        control
+            .approvals()
+            .check(&strategy_id, &ev.id, to_spend_sol, score)
+            .await
+    } else {
+        None
+    };
+    if let Some(r) = &approval {
+        println!("[{}] Not approved: {} ({})", strategy_id, ev.id, r.detail);
+        enter = false;
+        if let Some(leases) = leases {
+            leases.release_logged(&lease).await;
+        }
+    }
+    control.publish(BotEvent::Decision(DecisionEvent {
+        strategy_id: strategy_id.clone(),
+        token_id: ev.id.clone(),
+        action: if enter { "buy" } else { "skip" },
+        score,
+        reasons: decision
+            .rejections
+            .iter()
+            .chain(cooldown.iter())
+            .chain(paused.iter())
+            .chain(stand_down.iter())
+            .chain(exposure.iter())
+            .chain(sandwich_risk.iter())
+            .chain(leased.iter())
+            .chain(approval.iter())
+            .map(|r| r.filter.clone())
+            .collect(),
+        size_fraction: Some(decision.size_fraction),
+        price_usd: Some(decision.price),
+        priced_at_ms: decision.priced_at_ms,
+        sandwich,
+        at: now,
+    }));
+    latency.record(Stage::Decide, started.elapsed());
+    latency.record(Stage::ToDecision, detected_at.elapsed());
+    if !enter {
+        return Ok(());
+    }
+    let decided = std::time::Instant::now();
+    let order_id = new_order_id();
+    let slices = config.entry_slices.max(1);
+    let slice_interval = chrono::Duration::seconds(config.entry_slice_interval_secs as i64);
+    // An order may stay open until its last slice has had time to fill
+    let order_timeout =
+        slice_interval * (slices as i32 - 1) + chrono::Duration::seconds(ORDER_TIMEOUT_SECS);
+
+    // A dip order waits for the price to come to it, holding the token's lease meanwhile
+    if config.entry_dip_pct > 0.0 && ev.base_price > 0.0 {
+        let limit_price = ev.base_price * (1.0 - config.entry_dip_pct / 100.0);
+        println!(
+            "[{}] Dip order: {} at ${:.8} ({:.1}% below ${:.8}) for {}s",
+            strategy_id,
+            ev.id,
+            limit_price,
+            config.entry_dip_pct,
+            ev.base_price,
+            config.entry_dip_window_secs
+        );
+        let expires_at = now + chrono::Duration::seconds(config.entry_dip_window_secs as i64);
+        store
+            .open_order(&NewOrder {
+                client_order_id: &order_id,
+                strategy_id: &strategy_id,
+                run_id,
+                token_id: &ev.id,
+                side: "buy",
+                order_type: EntryType::Dip.as_str(),
+                sol_amount: Some(to_spend_sol),
+                shadow,
+                expires_at: expires_at + order_timeout,
+            })
+            .await?;
+        shared.lock().await.slots[idx].orders.place(EntryOrder {
+            order_id,
+            token_id: ev.id.clone(),
+            source: ev.source.clone(),
+            score,
+            dev_wallet_address: ev.dev_wallet_address.clone(),
+            launched_at: ev.launched_at,
+            entry_type: EntryType::Dip,
+            slice_sol: to_spend_sol / slices as f64,
+            slices,
+            slice_interval,
+            state: OrderState::Dip {
+                limit_price,
+                expires_at,
+            },
+        });
+        return Ok(());
+    }
+    // A split entry buys its first slice now and the rest in `process_orders`
+    let entry_type = if slices > 1 {
+        EntryType::Twap
+    } else {
+        EntryType::Market
+    };
+    let slice_sol = to_spend_sol / slices as f64;
+    let order = NewOrder {
+        client_order_id: &order_id,
+        strategy_id: &strategy_id,
+        run_id,
+        token_id: &ev.id,
+        side: "buy",
+        order_type: entry_type.as_str(),
+        sol_amount: Some(to_spend_sol),
+        shadow,
+        expires_at: now + order_timeout,
+    };
+
+    let (quote, modeled_price) =
+        match send_quote(scanner, &strategy_id, &target, &config, wallets.is_some()).await {
+            Ok(quote) => quote,
+            Err(reason) => {
+                abort_entry(store, &order, &reason, leases, &lease).await?;
+                return Ok(());
+            }
+        };
+    let to_spend_sol = slice_sol;
+
+    // The slot was unlocked since the decision, so its limits are checked again before the
+    // buy is sent
+    let recheck = {
+        let guard = shared.lock().await;
+        still_allowed(&guard.slots[idx], headroom, &target, to_spend_sol, true)
+    };
+    if let Some(r) = recheck.first() {
+        println!(
+            "[{}] Risk limit before sending: {} {} ({})",
+            strategy_id, ev.id, r.filter, r.detail
+        );
+        abort_entry(store, &order, &r.detail, leases, &lease).await?;
+        return Ok(());
+    }
+    submit_order(store, &order, wallets.is_some()).await?;
+
+    // In live mode the position only opens once the buy is confirmed on-chain
+    let executor = wallets.map(|w| w.for_entry(&strategy_id));
+    let wallet = executor.map(|e| e.pubkey());
+    let executed = match executor {
+        Some(executor) => {
+            // Tokens still on the bonding curve are bought through the pre-built launch template
+            let result = if !ev.graduation && quote > 0.0 {
+                let tokens = to_spend_sol * config.sol_usd_price / quote;
+                executor
+                    .snipe(&ev.id, to_spend_sol, tokens, detected_at, &order_id, jito)
+                    .await
             } else {
-                0.0
+                executor
+                    .buy(&ev.id, to_spend_sol, Some(detected_at), &order_id, jito)
+                    .await
             };
+            match result {
+                Ok(executed) => Some(executed),
+                Err(e) => {
+                    println!("[{}] Live buy failed for {}: {}", strategy_id, ev.id, e);
+                    fail_order(store, &order_id, &e).await?;
+                    // The lease stays held while the buy may still land
+                    if let Some(leases) = leases
+                        && !e.is::<Unconfirmed>()
+                    {
+                        leases.release_logged(&lease).await;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
 
-            portfolio.sol_balance -= to_spend_sol;
-            let pos = Position {
-                token_id: ev.id.clone(),
+    // A live buy is recorded at what it executed; paper and shadow fills are modeled
+    let entry_signature = executed.as_ref().map(|e| e.signature.clone());
+    let entry_fill = executed.and_then(|e| e.fill);
+    let (entry_price, qty, to_spend_sol) = fill_entry(
+        entry_fill,
+        modeled_price,
+        to_spend_sol,
+        config.sol_usd_price,
+    );
+    let usd_in = to_spend_sol * config.sol_usd_price;
+
+    // The trade and its ledger entry commit together, under the lock so the ledger's running
+    // balance follows the portfolio's
+    let mut guard = shared.lock().await;
+    let slot = &mut guard.slots[idx];
+    let portfolio = &mut slot.portfolio;
+    store
+        .open_trade(
+            &NewTrade {
+                token_id: &ev.id,
                 entry_price,
                 qty,
                 usd_in,
-                opened_at: Utc::now(),
+                sol_in: to_spend_sol,
                 score,
-            };
-            portfolio.positions.push(pos);
-
-            sqlx::query("INSERT INTO trades (token_id, action, entry_price, qty, usd_in, opened_at, score) VALUES ($1,$2,$3,$4,$5,NOW(),$6)")
-                .bind(&ev.id)
-                .bind("BUY")
-                .bind(entry_price)
-                .bind(qty)
-                .bind(usd_in)
-                .bind(score)
-                .execute(pool)
-                .await?;
+                is_reentry: false,
+                strategy_id: &strategy_id,
+                run_id,
+                preset: &preset,
+                source: &ev.source,
+                entry_signature: entry_signature.as_deref(),
+                wallet: wallet.as_deref(),
+                client_order_id: &order_id,
+                shadow,
+                launch_hour: trade_hour(ev.launched_at),
+                entry_type: entry_type.as_str(),
+            },
+            &ledger::Entry {
+                strategy_id: &strategy_id,
+                kind: "buy",
+                order_id: Some(&order_id),
+                delta_sol: -to_spend_sol,
+                balance_sol: portfolio.sol_balance - to_spend_sol,
+                open_sol: portfolio.open_sol() + to_spend_sol,
+            },
+        )
+        .await?;
+    portfolio.sol_balance -= to_spend_sol;
+    portfolio.run_entries += 1;
+    portfolio.run_sol_spent += to_spend_sol;
+    headroom.consume(to_spend_sol);
+    // Exit monitoring starts from fresh data rather than what enrichment cached
+    scanner.invalidate(&ev.id);
+    portfolio.positions.push(Position {
+        order_id: order_id.clone(),
+        token_id: ev.id.clone(),
+        source: ev.source.clone(),
+        entry_price,
+        qty,
+        usd_in,
+        sol_in: to_spend_sol,
+        opened_at: Utc::now(),
+        score,
+        dev_wallet_address: ev.dev_wallet_address.clone(),
+        launched_at: ev.launched_at,
+        is_reentry: false,
+        wallet: wallet.clone(),
+        token_raw: entry_fill.map(|f| f.token_raw),
+        trading: false,
+        entry_liquidity_usd: ev.liquidity_usd,
+        peak_price: entry_price,
+        live: Position::new_live_state(entry_price, ev.liquidity_usd),
+        last_marked_at: None,
+        mint_baseline: None,
+        mint_exit: None,
+    });
+    if slices > 1 {
+        slot.orders.place(EntryOrder {
+            order_id: order_id.clone(),
+            token_id: ev.id.clone(),
+            source: ev.source.clone(),
+            score,
+            dev_wallet_address: ev.dev_wallet_address.clone(),
+            launched_at: ev.launched_at,
+            entry_type,
+            slice_sol,
+            slices,
+            slice_interval,
+            state: OrderState::Slicing {
+                filled: 1,
+                next_at: Utc::now() + slice_interval,
+            },
+        });
+    }
+    drop(guard);
+
+    let status = if slices > 1 {
+        OrderStatus::PartiallyFilled
+    } else {
+        OrderStatus::Filled
+    };
+    fill_order(
+        store,
+        &order_id,
+        status,
+        to_spend_sol,
+        entry_signature.as_deref(),
+    )
+    .await?;
+    latency.record(Stage::Submit, decided.elapsed());
+    latency.record(Stage::ToSubmission, detected_at.elapsed());
+    control.publish(BotEvent::Trade(TradeEvent {
+        strategy_id,
+        token_id: ev.id.clone(),
+        side: "buy",
+        price_usd: entry_price,
+        qty,
+        usd: usd_in,
+        pnl_usd: None,
+        reason: None,
+        is_reentry: false,
+        shadow,
+        signature: entry_signature,
+        at: Utc::now(),
+    }));
+    Ok(())
+}
+
+/// The exit engine: every `exit_interval_secs` until `stopped`, refresh each strategy's open
+/// positions and sell those whose exit triggered, whether or not new tokens are coming in
+async fn run_exit_engine<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    manager: &SharedManager<'_>,
+    writes: &WriteBehind,
+    cfg: &Config,
+    runtime: &Runtime<'_>,
+    mut stopped: tokio::sync::watch::Receiver<bool>,
+) -> Result<()> {
+    let interval = std::time::Duration::from_secs(cfg.exit_interval_secs);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = stopped.changed() => return Ok(()),
         }
+        monitor_positions(store, scanner, manager, writes, cfg, runtime).await?;
+        let snapshot = manager.lock().await.snapshot();
+        *runtime.snapshot.write().await = snapshot;
+    }
+}
+
+/// One pass of the exit engine over every strategy's open positions
+async fn monitor_positions<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    writes: &WriteBehind,
+    cfg: &Config,
+    runtime: &Runtime<'_>,
+) -> Result<()> {
+    let slots = shared.lock().await.slots.len();
+    for idx in 0..slots {
+        let shadow = shared.lock().await.slots[idx].shadow;
+        let wallets = runtime.wallets.filter(|_| !shadow);
+        process_exits(
+            store,
+            scanner,
+            shared,
+            idx,
+            wallets,
+            runtime.control,
+            runtime.leases,
+        )
+        .await?;
+        track_equity(
+            writes,
+            cfg,
+            runtime.control,
+            &mut shared.lock().await.slots[idx],
+        );
+    }
+    Ok(())
+}
 
-        // Simulate exits using strategy-based exit logic
-        let mut closed_idxs = vec![];
-        for (idx, pos) in portfolio.positions.iter().enumerate() {
-            // Re-query current state for this token
-            let mut current_ev = ev.clone();
-            current_ev.id = pos.token_id.clone();
+/// A position whose exit triggered, taken out of the lock to be sold
+struct PendingExit {
+    order_id: String,
+    exit_order_id: String,
+    token_id: String,
+    wallet: Option<String>,
+    token_raw: Option<u64>,
+    reason: String,
+}
 
-            // Get current liquidity for LP spike detection
-            let entry_liquidity = ev.liquidity_usd;
+/// Simulate exits for one strategy's open positions using strategy-based exit logic. Token
+/// and mint state are read and sells sent with the manager unlocked; positions are marked
+/// `trading` while their sell is out, so no slice is bought into them meanwhile.
+async fn process_exits<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    idx: usize,
+    wallets: Option<&WalletPool>,
+    control: &ControlState,
+    leases: Option<&Leases>,
+) -> Result<()> {
+    let (strategy_id, run_id, shadow, watch, held) = {
+        let guard = shared.lock().await;
+        let slot = &guard.slots[idx];
+        let held: Vec<(String, String, Option<String>)> = slot
+            .portfolio
+            .positions
+            .iter()
+            .filter(|p| !p.trading)
+            .map(|p| (p.order_id.clone(), p.token_id.clone(), p.wallet.clone()))
+            .collect();
+        (
+            slot.id.clone(),
+            slot.portfolio.run_id,
+            slot.shadow,
+            slot.config.watch_mint,
+            held,
+        )
+    };
 
-            if let Ok(Some(d)) = scanner.query_dexscreener_pair(&pos.token_id).await {
-                if let Some(p) = d.pairs.and_then(|v| v.get(0).cloned()) {
-                    let current_liquidity = p.liquidity_usd.unwrap_or(0.0);
-                    current_ev.liquidity_usd = current_liquidity;
+    // Refresh each position's own token state; on failure keep the last known state
+    let mut observed = Vec::with_capacity(held.len());
+    for (order_id, token_id, wallet) in held {
+        let pair = match scanner.query_dexscreener_pair(&token_id).await {
+            Ok(Some(d)) => d.pairs.and_then(|v| v.into_iter().next()),
+            _ => None,
+        };
+        let mint = if watch {
+            let token_account = wallet
+                .as_deref()
+                .and_then(|wallet| token_account_address(wallet, &token_id).ok());
+            Some(mint_watch::read(scanner, &token_id, token_account.as_deref()).await)
+        } else {
+            None
+        };
+        observed.push((order_id, pair, mint));
+    }
+
+    let mut exits = vec![];
+    {
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let config = &slot.config;
+        for (order_id, pair, mint) in observed {
+            // A slice may have started on the position since it was read
+            let Some(pos) = slot
+                .portfolio
+                .positions
+                .iter_mut()
+                .find(|p| p.order_id == order_id && !p.trading)
+            else {
+                continue;
+            };
+            if let Some(pair) = &pair {
+                pos.refresh(pair);
+            }
+
+            // A rug on the mint itself overrides the price-based exits
+            if let Some(change) = mint.and_then(|state| observe_mint(pos, state)) {
+                let exiting = config.exit_on_mint_change;
+                if exiting {
+                    pos.mint_exit = Some(change.reason);
+                }
+                println!(
+                    "[{}] Mint change on {}: {} ({}){}",
+                    slot.id,
+                    pos.token_id,
+                    change.reason,
+                    change.detail,
+                    if exiting { ", selling" } else { "" }
+                );
+                control.publish(BotEvent::MintChange(MintChangeEvent {
+                    strategy_id: slot.id.clone(),
+                    token_id: pos.token_id.clone(),
+                    reason: change.reason,
+                    detail: change.detail,
+                    exiting,
+                    at: Utc::now(),
+                }));
+            }
+            let exit_decision = match pos.mint_exit {
+                Some(reason) => ExitDecision {
+                    should_exit: true,
+                    reason: reason.to_string(),
+                },
+                None => should_exit(&pos.live, pos.entry_price, pos.entry_liquidity_usd, config),
+            };
+            if exit_decision.should_exit {
+                pos.trading = true;
+                exits.push(PendingExit {
+                    order_id: pos.order_id.clone(),
+                    exit_order_id: pos.exit_order_id(),
+                    token_id: pos.token_id.clone(),
+                    wallet: pos.wallet.clone(),
+                    token_raw: pos.token_raw,
+                    reason: exit_decision.reason,
+                });
+            }
+        }
+    }
 
-                    // Detect Raydium LP spike (>2x liquidity increase)
-                    if current_liquidity > entry_liquidity * 2.0 {
-                        current_ev.raydium_lp_detected = true;
+    let mut closed = false;
+    for exit in exits {
+        // In live mode a failed sell keeps the position open for the next pass; the
+        // position is sold from the wallet that holds it
+        let exit_order_id = &exit.exit_order_id;
+        let executor = wallets.map(|w| w.for_exit(exit.wallet.as_deref()));
+        submit_order(
+            store,
+            &NewOrder {
+                client_order_id: exit_order_id,
+                strategy_id: &strategy_id,
+                run_id,
+                token_id: &exit.token_id,
+                side: "sell",
+                order_type: "exit",
+                sol_amount: None,
+                shadow,
+                expires_at: Utc::now() + chrono::Duration::seconds(ORDER_TIMEOUT_SECS),
+            },
+            executor.is_some(),
+        )
+        .await?;
+        let executed = match executor {
+            Some(executor) => match executor
+                .sell(&exit.token_id, exit.token_raw, exit_order_id)
+                .await
+            {
+                Ok(executed) => Some(executed),
+                Err(e) => {
+                    println!(
+                        "[{}] Live sell failed for {}: {}",
+                        strategy_id, exit.token_id, e
+                    );
+                    fail_order(store, exit_order_id, &e).await?;
+                    let mut guard = shared.lock().await;
+                    if let Some(pos) = guard.slots[idx]
+                        .portfolio
+                        .positions
+                        .iter_mut()
+                        .find(|p| p.order_id == exit.order_id)
+                    {
+                        pos.trading = false;
                     }
+                    continue;
                 }
+            },
+            None => None,
+        };
+
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let config = &slot.config;
+        let portfolio = &mut slot.portfolio;
+        let Some(at) = portfolio
+            .positions
+            .iter()
+            .position(|p| p.order_id == exit.order_id)
+        else {
+            continue;
+        };
+        let pos = portfolio.positions.remove(at);
+        let sol_usd_price = config.sol_usd_price;
+
+        // A live sell is recorded at what it executed; paper and shadow sells fill at the
+        // last observed price minus a little slippage
+        let exit_signature = executed.as_ref().map(|e| e.signature.clone());
+        let mut rng = rand::thread_rng();
+        let slippage = 1.0 - rng.gen_range(0.0..0.02);
+        let (exit_price, proceeds_sol) = fill_exit(
+            executed.and_then(|e| e.fill),
+            pos.live.price_usd * slippage,
+            pos.qty,
+            sol_usd_price,
+        );
+        let proceeds_usd = proceeds_sol * sol_usd_price;
+
+        // Only the first exit of the position's own row is recorded, together with its
+        // ledger entry; it's written under the lock, so the running balance follows the
+        // portfolio's
+        store
+            .close_trade(
+                &TradeExit {
+                    client_order_id: &pos.order_id,
+                    exit_price,
+                    pnl: proceeds_usd - pos.usd_in,
+                    exit_signature: exit_signature.as_deref(),
+                    exit_order_id,
+                    exit_reason: &exit.reason,
+                },
+                &ledger::Entry {
+                    strategy_id: &strategy_id,
+                    kind: "sell",
+                    order_id: Some(exit_order_id),
+                    delta_sol: proceeds_sol,
+                    balance_sol: portfolio.sol_balance + proceeds_sol,
+                    open_sol: portfolio.open_sol(),
+                },
+            )
+            .await?;
+        portfolio.sol_balance += proceeds_sol;
+        portfolio.realized_pnl_usd += proceeds_usd - pos.usd_in;
+        closed = true;
+
+        let reason = if exit.reason == "stop_loss" {
+            CooldownReason::StoppedOut
+        } else {
+            CooldownReason::Exited
+        };
+        slot.cooldowns
+            .record(&pos.token_id, reason, pos.score, Utc::now(), config);
+        if let Some(reason) = slot.streak.record_exit(&exit.reason, config) {
+            pause_entries(control, &strategy_id, config, reason);
+        }
+
+        // Winners become re-entry candidates
+        if config.enable_reentry && proceeds_usd > pos.usd_in {
+            slot.reentries.watch(
+                ReentryCandidate {
+                    token_id: pos.token_id.clone(),
+                    source: pos.source.clone(),
+                    prior_high_price: pos.peak_price,
+                    exit_volume_m5_usd: pos.live.volume_m5_usd,
+                    exited_at: Utc::now(),
+                    score: pos.score,
+                    dev_wallet_address: pos.dev_wallet_address.clone(),
+                    launched_at: pos.launched_at,
+                },
+                config,
+            );
+        }
+        drop(guard);
+
+        fill_order(
+            store,
+            exit_order_id,
+            OrderStatus::Filled,
+            proceeds_sol,
+            exit_signature.as_deref(),
+        )
+        .await?;
+        scanner.invalidate(&pos.token_id);
+        if let Some(leases) = leases {
+            leases
+                .release_logged(&position_lease(&strategy_id, &pos.token_id))
+                .await;
+        }
+        control.publish(BotEvent::Trade(TradeEvent {
+            strategy_id: strategy_id.clone(),
+            token_id: pos.token_id.clone(),
+            side: "sell",
+            price_usd: exit_price,
+            qty: pos.qty,
+            usd: proceeds_usd,
+            pnl_usd: Some(proceeds_usd - pos.usd_in),
+            reason: Some(exit.reason.clone()),
+            is_reentry: pos.is_reentry,
+            shadow,
+            signature: exit_signature,
+            at: Utc::now(),
+        }));
+        control.publish(BotEvent::Decision(DecisionEvent {
+            strategy_id: strategy_id.clone(),
+            token_id: pos.token_id.clone(),
+            action: "exit",
+            score: pos.score,
+            reasons: vec![exit.reason.clone()],
+            size_fraction: None,
+            price_usd: None,
+            priced_at_ms: None,
+            sandwich: None,
+            at: Utc::now(),
+        }));
+        println!(
+            "[{}] Exit: {} reason={} entry=${:.8} exit=${:.8} peak=${:.8} pnl=${:.2}",
+            strategy_id,
+            pos.token_id,
+            exit.reason,
+            pos.entry_price,
+            exit_price,
+            pos.peak_price,
+            proceeds_usd - pos.usd_in
+        );
+    }
+
+    if closed {
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let portfolio = &mut slot.portfolio;
+        if let Some(cap) = slot.config.compound_cap_sol {
+            let banked = portfolio.bank_above(cap);
+            if banked > 0.0 {
+                store
+                    .record_ledger(&ledger::Entry {
+                        strategy_id: &strategy_id,
+                        kind: "bank",
+                        order_id: None,
+                        delta_sol: -banked,
+                        balance_sol: portfolio.sol_balance,
+                        open_sol: portfolio.open_sol(),
+                    })
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Send every strategy's banked profits to the sweep address once they reach the threshold
+async fn sweep_profits(
+    store: &Store,
+    cfg: &Config,
+    address: &str,
+    shared: &SharedManager<'_>,
+    wallets: Option<&WalletPool>,
+) -> Result<()> {
+    // Shadow strategies' profits are hypothetical. The transfer goes out with the manager
+    // unlocked, so only the amounts planned here are taken off the strategies afterwards
+    let planned: Vec<(usize, String, f64)> = shared
+        .lock()
+        .await
+        .slots
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.shadow && s.portfolio.banked_sol > 0.0)
+        .map(|(idx, s)| (idx, s.id.clone(), s.portfolio.banked_sol))
+        .collect();
+    let total: f64 = planned.iter().map(|(_, _, banked)| banked).sum();
+    if total < cfg.sweep_threshold_sol {
+        return Ok(());
+    }
+    let signature = match wallets {
+        Some(wallets) => match wallets.primary().transfer(address, total).await {
+            Ok(sig) => Some(sig),
+            Err(e) => {
+                println!(
+                    "[sweep] Transfer of {:.4} SOL to {} failed: {}",
+                    total, address, e
+                );
+                return Ok(());
             }
+        },
+        None => None,
+    };
+    {
+        let mut guard = shared.lock().await;
+        for (idx, _, banked) in &planned {
+            let portfolio = &mut guard.slots[*idx].portfolio;
+            portfolio.swept_sol += banked;
+            portfolio.banked_sol -= banked;
+        }
+    }
+    for (_, strategy_id, banked) in &planned {
+        store
+            .record_sweep(strategy_id, *banked, address, signature.as_deref())
+            .await?;
+    }
+    println!("[sweep] Swept {:.4} SOL of profits to {}", total, address);
+    Ok(())
+}
+
+/// Close the token accounts of sold positions to get their rent back
+async fn reclaim_rent(wallets: &WalletPool) {
+    for executor in wallets.iter() {
+        match executor.close_empty_token_accounts().await {
+            Ok(0) => {}
+            Ok(lamports) => println!(
+                "[rent] Reclaimed {:.6} SOL of token account rent for {}",
+                lamports as f64 / 1e9,
+                executor.pubkey()
+            ),
+            Err(e) => println!(
+                "[rent] Token account cleanup failed for {}: {}",
+                executor.pubkey(),
+                e
+            ),
+        }
+    }
+}
 
-            // Use strategy exit logic
-            use crate::strategy::should_exit;
-            let exit_decision = should_exit(&current_ev, entry_liquidity, &config);
+/// Record an order as pending, reopening it if it failed before, and as submitted when it
+/// goes to a wallet
+async fn submit_order(store: &Store, order: &NewOrder<'_>, live: bool) -> Result<()> {
+    store.open_order(order).await?;
+    if live {
+        store
+            .update_order(&OrderUpdate::new(
+                order.client_order_id,
+                OrderStatus::Submitted,
+            ))
+            .await?;
+    }
+    Ok(())
+}
 
-            if exit_decision.should_exit {
-                let mut rng = rand::thread_rng();
-                // Different multipliers based on exit reason
-                let mult = match exit_decision.reason.as_str() {
-                    "profit_target" => rng.gen_range(1.5..2.5),
-                    "lp_spike" => rng.gen_range(1.3..3.0),
-                    "stop_loss" => rng.gen_range(0.6..0.8),
-                    "graduation" => rng.gen_range(1.5..3.0),
-                    _ => rng.gen_range(1.2..2.0),
+/// Record a wallet's failure to fill an order. A transaction that may still land leaves the
+/// order submitted, so it isn't mistaken for one that never executed.
+async fn fail_order(store: &Store, order_id: &str, error: &anyhow::Error) -> Result<()> {
+    let status = if error.is::<Unconfirmed>() {
+        OrderStatus::Submitted
+    } else {
+        OrderStatus::Failed
+    };
+    let error = error.to_string();
+    store
+        .update_order(&OrderUpdate {
+            error: Some(&error),
+            ..OrderUpdate::new(order_id, status)
+        })
+        .await
+}
+
+/// Price, quantity and SOL spent of a buy of `sol`: a live buy's executed fill when it could
+/// be read, otherwise a fill at `modeled_price`
+fn fill_entry(
+    fill: Option<Fill>,
+    modeled_price: f64,
+    sol: f64,
+    sol_usd_price: f64,
+) -> (f64, f64, f64) {
+    match fill {
+        Some(fill) => {
+            let qty = fill.tokens();
+            (fill.sol * sol_usd_price / qty, qty, fill.sol)
+        }
+        None if modeled_price > 0.0 => (modeled_price, sol * sol_usd_price / modeled_price, sol),
+        None => (modeled_price, 0.0, sol),
+    }
+}
+
+/// Price and SOL received for selling `qty`: a live sell's executed fill when it could be
+/// read, otherwise a fill at `modeled_price`
+fn fill_exit(fill: Option<Fill>, modeled_price: f64, qty: f64, sol_usd_price: f64) -> (f64, f64) {
+    match fill {
+        Some(fill) => (fill.sol * sol_usd_price / fill.tokens(), fill.sol),
+        None => (modeled_price, qty * modeled_price / sol_usd_price),
+    }
+}
+
+/// Record the SOL a fill spent or received
+async fn fill_order(
+    store: &Store,
+    order_id: &str,
+    status: OrderStatus,
+    sol: f64,
+    signature: Option<&str>,
+) -> Result<()> {
+    store
+        .update_order(&OrderUpdate {
+            filled_sol: sol,
+            signature,
+            ..OrderUpdate::new(order_id, status)
+        })
+        .await
+}
+
+/// Record an entry order's cancellation and why
+async fn cancel_order(store: &Store, order_id: &str, reason: &str) -> Result<()> {
+    store
+        .update_order(&OrderUpdate {
+            error: Some(reason),
+            ..OrderUpdate::new(order_id, OrderStatus::Cancelled)
+        })
+        .await
+}
+
+/// Record an entry stopped before it was sent as a cancelled order, releasing its lease
+async fn abort_entry(
+    store: &Store,
+    order: &NewOrder<'_>,
+    reason: &str,
+    leases: Option<&Leases>,
+    lease: &str,
+) -> Result<()> {
+    store.open_order(order).await?;
+    cancel_order(store, order.client_order_id, reason).await?;
+    if let Some(leases) = leases {
+        leases.release_logged(lease).await;
+    }
+    Ok(())
+}
+
+/// A token's current DexScreener price, looked up past the cache
+async fn fresh_price<P: TokenDataProvider>(scanner: &P, mint: &str) -> Option<f64> {
+    scanner.invalidate(mint);
+    let d = scanner.query_dexscreener_pair(mint).await.ok()??;
+    d.pairs?.first()?.price_usd.filter(|p| *p > 0.0)
+}
+
+/// The `leased` rejection when another instance holds `lease` or it can't be claimed
+async fn claim_rejection(leases: &Leases, lease: &str) -> Option<FilterRejection> {
+    match leases.claim(lease).await {
+        Ok(true) => None,
+        Ok(false) => Some(FilterRejection::new(
+            "leased",
+            "another instance holds this position",
+        )),
+        Err(e) => Some(FilterRejection::new(
+            "leased",
+            format!("lease claim failed: {}", e),
+        )),
+    }
+}
+
+/// A buy as the pre-trade checks shared by entries and re-entries see it
+struct EntryTarget<'a> {
+    token_id: &'a str,
+    dev_wallet_address: Option<&'a str>,
+    launched_at: Option<i64>,
+    graduation: bool,
+    liquidity_usd: f64,
+    /// Price the buy was decided at, and when it was quoted
+    price: f64,
+    priced_at_ms: Option<i64>,
+}
+
+/// SOL a buy of up to `max_sol` spends, capped by the free balance and, since the position
+/// must stay small next to the pool it will be sold back into, by the pool's depth
+fn entry_size(
+    config: &StrategyConfig,
+    portfolio: &Portfolio,
+    max_sol: f64,
+    liquidity_usd: f64,
+) -> f64 {
+    let sol = max_sol.min(config.spendable_sol(portfolio.sol_balance, portfolio.positions.len()));
+    if exit_liquidity_rejection(liquidity_usd, config).is_none() {
+        sol.min(config.max_sol_for_liquidity(liquidity_usd))
+    } else {
+        sol
+    }
+}
+
+/// Strategy and global exposure limits, the pool's depth, and any entry order already
+/// working on the token
+fn entry_limits(
+    slot: &StrategySlot,
+    headroom: &GlobalHeadroom,
+    target: &EntryTarget<'_>,
+    to_spend_sol: f64,
+) -> Vec<FilterRejection> {
+    let mut rejections = exposure_rejections(
+        &slot.portfolio,
+        target.dev_wallet_address,
+        target.launched_at,
+        to_spend_sol,
+        &slot.config,
+    );
+    rejections.extend(headroom.rejections(to_spend_sol));
+    rejections.extend(exit_liquidity_rejection(target.liquidity_usd, &slot.config));
+    if slot.orders.is_working(target.token_id) {
+        rejections.push(FilterRejection::new(
+            "entry_order",
+            "an entry order is already working on the token",
+        ));
+    }
+    rejections
+}
+
+/// Limits checked again under the lock before a buy planned with the manager unlocked is
+/// sent. Only exits ran meanwhile, which free up room, but nothing is sent on a stale check.
+/// `opens` is whether the buy opens a position rather than adding to one.
+fn still_allowed(
+    slot: &StrategySlot,
+    headroom: &GlobalHeadroom,
+    target: &EntryTarget<'_>,
+    to_spend_sol: f64,
+    opens: bool,
+) -> Vec<FilterRejection> {
+    let config = &slot.config;
+    let portfolio = &slot.portfolio;
+    let mut rejections = exposure_rejections(
+        portfolio,
+        target.dev_wallet_address,
+        target.launched_at,
+        to_spend_sol,
+        config,
+    );
+    rejections.extend(headroom.rejections(to_spend_sol));
+    if to_spend_sol > config.spendable_sol(portfolio.sol_balance, portfolio.positions.len()) + 1e-9
+    {
+        rejections.push(FilterRejection::new("balance", "not enough free SOL"));
+    }
+    if opens && portfolio.positions.len() >= config.max_positions {
+        rejections.push(FilterRejection::new("max_positions", "no free position"));
+    }
+    rejections
+}
+
+/// A graduated token is only worth buying if the position can be sold back out
+async fn sell_route_check<P: TokenDataProvider>(
+    scanner: &P,
+    target: &EntryTarget<'_>,
+    to_spend_sol: f64,
+    config: &StrategyConfig,
+) -> Option<FilterRejection> {
+    if !target.graduation || target.price <= 0.0 {
+        return None;
+    }
+    let tokens = to_spend_sol * config.sol_usd_price / target.price;
+    let raw_amount = (tokens * 10f64.powi(TOKEN_DECIMALS)) as u64;
+    match scanner.query_sell_route(target.token_id, raw_amount).await {
+        Ok(route) => sell_route_rejection(route.as_ref(), config),
+        Err(e) => Some(FilterRejection::new(
+            "sell_route",
+            format!("Jupiter quote failed: {}", e),
+        )),
+    }
+}
+
+/// Live buys are sized and routed against the pool's sandwich exposure
+async fn sandwich_estimate<P: TokenDataProvider>(
+    scanner: &P,
+    target: &EntryTarget<'_>,
+    to_spend_sol: f64,
+    config: &StrategyConfig,
+) -> SandwichEstimate {
+    // Unknown recent activity still sizes the buy against the pool's depth
+    let recent = scanner
+        .query_recent_sandwiches(target.token_id)
+        .await
+        .ok()
+        .flatten();
+    SandwichEstimate::new(to_spend_sol, target.liquidity_usd, recent, config)
+}
+
+/// The quote to send a buy on and the price a paper buy fills at, or why the buy is aborted.
+/// A buy isn't sent on a price older than `max_quote_age_ms`: the price is looked up again,
+/// and the buy cancelled when there is no fresh one. A buy whose price ran too far since the
+/// decision is aborted too; live buys check the price looked up again, paper buys the price
+/// they would fill at.
+async fn send_quote<P: TokenDataProvider>(
+    scanner: &P,
+    strategy_id: &str,
+    target: &EntryTarget<'_>,
+    config: &StrategyConfig,
+    live: bool,
+) -> std::result::Result<(f64, f64), String> {
+    let now_ms = Utc::now().timestamp_millis();
+    let stale = config.quote_is_stale(target.priced_at_ms, now_ms);
+    let fresh = if stale || (config.max_entry_slippage_pct > 0.0 && live) {
+        fresh_price(scanner, target.token_id).await
+    } else {
+        None
+    };
+    if stale {
+        let age = target
+            .priced_at_ms
+            .map_or("of unknown age".to_string(), |at| {
+                format!("{}ms old", now_ms - at)
+            });
+        let Some(price) = fresh else {
+            println!(
+                "[{}] Stale quote: {} price {} and no fresh one, cancelling",
+                strategy_id, target.token_id, age
+            );
+            return Err(format!("stale quote ({})", age));
+        };
+        println!(
+            "[{}] Stale quote: {} price {}, refreshed at ${:.8}",
+            strategy_id, target.token_id, age, price
+        );
+    }
+    let quote = fresh.unwrap_or(target.price);
+
+    let impact = 1.0 + rand::thread_rng().gen_range(0.0..0.05);
+    let send_price = if live { fresh } else { Some(quote * impact) };
+    if let Some(moved_pct) = send_price.and_then(|p| config.entry_slippage_breach(target.price, p))
+    {
+        println!(
+            "[{}] Slippage abort: {} moved {:+.1}% from ${:.8} since the decision",
+            strategy_id, target.token_id, moved_pct, target.price
+        );
+        return Err(format!("price moved {:+.1}% since the decision", moved_pct));
+    }
+    Ok((quote, quote * impact))
+}
+
+/// Work one strategy's entry orders: drop dip orders that expired, fill those whose price
+/// came, and buy the slices that are due. The rest of an order is cancelled when its
+/// position has closed, entries are paused, a risk limit stops a slice, or an operator
+/// cancels it. Like entries, prices are looked up and slices bought with the manager
+/// unlocked, and the limits are checked under the lock just before each buy.
+#[allow(clippy::too_many_arguments)]
+async fn process_orders<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    idx: usize,
+    headroom: &mut GlobalHeadroom,
+    wallets: Option<&WalletPool>,
+    control: &ControlState,
+    leases: Option<&Leases>,
+) -> Result<()> {
+    let now = Utc::now();
+    let (strategy_id, cancelled, expired, due) = {
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let cancelled: Vec<EntryOrder> = slot
+            .orders
+            .order_ids()
+            .into_iter()
+            .filter(|order_id| control.take_cancel(order_id))
+            .filter_map(|order_id| slot.orders.cancel(&order_id))
+            .collect();
+        let expired = slot.orders.expire(now);
+        (slot.id.clone(), cancelled, expired, slot.orders.due(now))
+    };
+    for order in cancelled {
+        println!(
+            "[{}] Entry order cancelled by an operator: {}",
+            strategy_id, order.token_id
+        );
+        cancel_order(store, &order.order_id, "cancelled by an operator").await?;
+        // A dip that never filled holds the lease without a position
+        if matches!(order.state, OrderState::Dip { .. })
+            && let Some(leases) = leases
+        {
+            leases
+                .release_logged(&position_lease(&strategy_id, &order.token_id))
+                .await;
+        }
+    }
+    for order in expired {
+        println!(
+            "[{}] Dip order expired: {} never reached its price",
+            strategy_id, order.token_id
+        );
+        cancel_order(store, &order.order_id, "never reached its price").await?;
+        if let Some(leases) = leases {
+            leases
+                .release_logged(&position_lease(&strategy_id, &order.token_id))
+                .await;
+        }
+    }
+
+    for order in due {
+        // A dip order fills once the price has come to it
+        let dip = match order.state {
+            OrderState::Dip { limit_price, .. } => {
+                let Ok(Some(d)) = scanner.query_dexscreener_pair(&order.token_id).await else {
+                    continue;
                 };
+                let Some(pair) = d.pairs.and_then(|v| v.first().cloned()) else {
+                    continue;
+                };
+                let price = pair.price_usd.unwrap_or(0.0);
+                if price <= 0.0 || price > limit_price {
+                    continue;
+                }
+                Some((price, pair.liquidity_usd.unwrap_or(0.0)))
+            }
+            OrderState::Slicing { .. } => None,
+        };
 
-                let exit_price = pos.entry_price * mult;
-                let proceeds_usd = pos.qty * exit_price;
-                let proceeds_sol = proceeds_usd / sol_usd_price;
-                portfolio.sol_balance += proceeds_sol;
-
-                sqlx::query("UPDATE trades SET action=$1, exit_price=$2, pnl=$3, closed_at=NOW() WHERE token_id=$4 AND action='BUY' AND exit_price IS NULL")
-                    .bind("SELL")
-                    .bind(exit_price)
-                    .bind(proceeds_usd - pos.usd_in)
-                    .bind(&pos.token_id)
-                    .execute(pool)
-                    .await?;
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let (slice, price, liquidity, held_by) = match (&order.state, dip) {
+            (OrderState::Dip { .. }, Some((price, liquidity))) => (1, price, liquidity, None),
+            (OrderState::Slicing { filled, .. }, _) => match slot
+                .portfolio
+                .positions
+                .iter()
+                .find(|p| p.order_id == order.order_id)
+            {
+                // The position is being sold; the slice waits for the sell to resolve
+                Some(pos) if pos.trading => continue,
+                Some(pos) => (
+                    *filled + 1,
+                    pos.live.price_usd,
+                    pos.live.liquidity_usd,
+                    Some(pos.wallet.clone()),
+                ),
+                None => {
+                    slot.orders.cancel(&order.order_id);
+                    drop(guard);
+                    cancel_order(store, &order.order_id, "position closed").await?;
+                    continue;
+                }
+            },
+            (OrderState::Dip { .. }, None) => continue,
+        };
 
-                closed_idxs.push(idx);
+        // A dip order opens a position and needs a free slot; later slices add to one
+        let config = &slot.config;
+        let portfolio = &slot.portfolio;
+        let sol = f64::min(
+            order.slice_sol,
+            config.spendable_sol(portfolio.sol_balance, portfolio.positions.len()),
+        );
+        let mut stopped = exposure_rejections(
+            portfolio,
+            order.dev_wallet_address.as_deref(),
+            order.launched_at,
+            sol,
+            config,
+        );
+        stopped.extend(headroom.rejections(sol));
+        if slice == 1 && portfolio.positions.len() >= config.max_positions {
+            stopped.push(FilterRejection::new("max_positions", "no free position"));
+        }
+        if !control.entries_allowed() || control.strategy_pause(&slot.id, now).is_some() {
+            stopped.push(FilterRejection::new("paused", "entries are paused"));
+        }
+        if let Some(r) = stopped.first() {
+            println!(
+                "[{}] Entry order cancelled: {} at slice {}/{} ({})",
+                slot.id, order.token_id, slice, order.slices, r.detail
+            );
+            let reason = r.detail.clone();
+            slot.orders.cancel(&order.order_id);
+            drop(guard);
+            cancel_order(store, &order.order_id, &reason).await?;
+            if slice == 1
+                && let Some(leases) = leases
+            {
+                leases
+                    .release_logged(&position_lease(&strategy_id, &order.token_id))
+                    .await;
+            }
+            continue;
+        }
+        let sol_usd_price = config.sol_usd_price;
+        let run_id = portfolio.run_id;
+        let preset = slot.preset.clone();
+        let shadow = slot.shadow;
+        // No sell starts on the position while its slice is bought
+        if let Some(pos) = slot
+            .portfolio
+            .positions
+            .iter_mut()
+            .find(|p| p.order_id == order.order_id)
+        {
+            pos.trading = true;
+        }
+        drop(guard);
 
+        // Slices are bought through the wallet holding the position
+        let slice_order_id = order.slice_order_id(slice);
+        let executor = wallets.map(|w| match &held_by {
+            Some(wallet) => w.for_exit(wallet.as_deref()),
+            None => w.for_entry(&strategy_id),
+        });
+        let wallet = executor.map(|e| e.pubkey());
+        // A filling dip goes to the wallet like a market order; later slices add to an
+        // order that is already partially filled
+        if slice == 1 {
+            submit_order(
+                store,
+                &NewOrder {
+                    client_order_id: &order.order_id,
+                    strategy_id: &strategy_id,
+                    run_id,
+                    token_id: &order.token_id,
+                    side: "buy",
+                    order_type: order.entry_type.as_str(),
+                    sol_amount: Some(order.slice_sol * order.slices as f64),
+                    shadow,
+                    expires_at: now
+                        + order.slice_interval * (order.slices as i32 - 1)
+                        + chrono::Duration::seconds(ORDER_TIMEOUT_SECS),
+                },
+                executor.is_some(),
+            )
+            .await?;
+        }
+        let result = match executor {
+            Some(executor) => executor
+                .buy(&order.token_id, sol, None, &slice_order_id, false)
+                .await
+                .map(Some),
+            None => Ok(None),
+        };
+
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let portfolio = &mut slot.portfolio;
+        if let Some(pos) = portfolio
+            .positions
+            .iter_mut()
+            .find(|p| p.order_id == order.order_id)
+        {
+            pos.trading = false;
+        }
+        let executed = match result {
+            Ok(executed) => executed,
+            Err(e) => {
+                drop(guard);
+                // Tried again on the next pass
                 println!(
-                    "Exit: {} reason={} mult={:.2}x pnl=${:.2}",
-                    pos.token_id,
-                    exit_decision.reason,
-                    mult,
-                    proceeds_usd - pos.usd_in
+                    "[{}] Live order buy failed for {}: {}",
+                    strategy_id, order.token_id, e
                 );
+                if slice == 1 {
+                    fail_order(store, &order.order_id, &e).await?;
+                } else {
+                    let error = e.to_string();
+                    store
+                        .update_order(&OrderUpdate {
+                            error: Some(&error),
+                            ..OrderUpdate::new(&order.order_id, OrderStatus::PartiallyFilled)
+                        })
+                        .await?;
+                }
+                continue;
+            }
+        };
+
+        let signature = executed.as_ref().map(|e| e.signature.clone());
+        let fill = executed.and_then(|e| e.fill);
+        let mut rng = rand::thread_rng();
+        let impact = 1.0 + rng.gen_range(0.0..0.05);
+        let (entry_price, qty, sol) = fill_entry(fill, price * impact, sol, sol_usd_price);
+        let usd_in = sol * sol_usd_price;
+        // The ledger entry is written under the lock, so its running balance follows the
+        // portfolio's
+        let entry = ledger::Entry {
+            strategy_id: &strategy_id,
+            kind: "buy",
+            order_id: Some(&slice_order_id),
+            delta_sol: -sol,
+            balance_sol: portfolio.sol_balance - sol,
+            open_sol: portfolio.open_sol() + sol,
+        };
+        if slice == 1 {
+            store
+                .open_trade(
+                    &NewTrade {
+                        token_id: &order.token_id,
+                        entry_price,
+                        qty,
+                        usd_in,
+                        sol_in: sol,
+                        score: order.score,
+                        is_reentry: false,
+                        strategy_id: &strategy_id,
+                        run_id,
+                        preset: &preset,
+                        source: &order.source,
+                        entry_signature: signature.as_deref(),
+                        wallet: wallet.as_deref(),
+                        client_order_id: &order.order_id,
+                        shadow,
+                        launch_hour: trade_hour(order.launched_at),
+                        entry_type: order.entry_type.as_str(),
+                    },
+                    &entry,
+                )
+                .await?;
+            portfolio.run_entries += 1;
+            scanner.invalidate(&order.token_id);
+            portfolio.positions.push(Position {
+                order_id: order.order_id.clone(),
+                token_id: order.token_id.clone(),
+                source: order.source.clone(),
+                entry_price,
+                qty,
+                usd_in,
+                sol_in: sol,
+                opened_at: Utc::now(),
+                score: order.score,
+                dev_wallet_address: order.dev_wallet_address.clone(),
+                launched_at: order.launched_at,
+                is_reentry: false,
+                wallet,
+                token_raw: fill.map(|f| f.token_raw),
+                trading: false,
+                entry_liquidity_usd: liquidity,
+                peak_price: entry_price,
+                live: Position::new_live_state(entry_price, liquidity),
+                last_marked_at: None,
+                mint_baseline: None,
+                mint_exit: None,
+            });
+        } else {
+            store
+                .add_slice(
+                    &TradeSlice {
+                        client_order_id: &order.order_id,
+                        slice: slice as i32,
+                        qty,
+                        usd_in,
+                        sol_in: sol,
+                    },
+                    &entry,
+                )
+                .await?;
+            if let Some(pos) = portfolio
+                .positions
+                .iter_mut()
+                .find(|p| p.order_id == order.order_id)
+            {
+                pos.qty += qty;
+                pos.token_raw = match (pos.token_raw, fill) {
+                    (Some(raw), Some(fill)) => Some(raw + fill.token_raw),
+                    _ => None,
+                };
+                pos.usd_in += usd_in;
+                pos.sol_in += sol;
+                pos.entry_price = pos.usd_in / pos.qty;
             }
         }
-        for j in closed_idxs.iter().rev() {
-            portfolio.positions.remove(*j);
+        portfolio.sol_balance -= sol;
+        portfolio.run_sol_spent += sol;
+        headroom.consume(sol);
+        slot.orders.filled(&order.order_id, Utc::now());
+        drop(guard);
+
+        let status = if slice >= order.slices {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        fill_order(store, &order.order_id, status, sol, signature.as_deref()).await?;
+        println!(
+            "[{}] {} entry: {} slice {}/{} at ${:.8}",
+            strategy_id,
+            order.entry_type.as_str(),
+            order.token_id,
+            slice,
+            order.slices,
+            entry_price
+        );
+        control.publish(BotEvent::Trade(TradeEvent {
+            strategy_id: strategy_id.clone(),
+            token_id: order.token_id.clone(),
+            side: "buy",
+            price_usd: entry_price,
+            qty,
+            usd: usd_in,
+            pnl_usd: None,
+            reason: None,
+            is_reentry: false,
+            shadow,
+            signature,
+            at: Utc::now(),
+        }));
+    }
+    Ok(())
+}
+
+/// Buy back exited winners that confirmed a recovery. Re-entries go through the same
+/// pre-trade checks as new entries: the market regime, exposure limits, the sell route,
+/// sandwich risk, and the quote's age and slippage. As with entries, the manager is
+/// unlocked for lookups and the buy, and the limits are checked again before it's sent.
+#[allow(clippy::too_many_arguments)]
+async fn process_reentries<P: TokenDataProvider>(
+    store: &Store,
+    scanner: &P,
+    shared: &SharedManager<'_>,
+    idx: usize,
+    headroom: &mut GlobalHeadroom,
+    regime: Regime,
+    wallets: Option<&WalletPool>,
+    control: &ControlState,
+    leases: Option<&Leases>,
+) -> Result<()> {
+    let (strategy_id, config, run_id, preset, shadow, candidates) = {
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        if !slot.config.enable_reentry {
+            return Ok(());
         }
+        (
+            slot.id.clone(),
+            slot.config.clone(),
+            slot.portfolio.run_id,
+            slot.preset.clone(),
+            slot.shadow,
+            slot.reentries.candidates(Utc::now(), &slot.config),
+        )
+    };
+    let size_multiplier = regime.size_multiplier(&config);
+    if size_multiplier <= 0.0 {
+        return Ok(());
     }
 
-    sqlx::query("INSERT INTO run_metadata (finished_at) VALUES (NOW())")
-        .execute(pool)
+    // Held or no room for it: no need to look the token up
+    let taken = |slot: &StrategySlot, token_id: &str| {
+        let portfolio = &slot.portfolio;
+        portfolio.positions.len() >= slot.config.max_positions
+            || portfolio.positions.iter().any(|p| p.token_id == token_id)
+    };
+    for candidate in candidates {
+        if taken(&shared.lock().await.slots[idx], &candidate.token_id) {
+            continue;
+        }
+
+        let Ok(Some(d)) = scanner.query_dexscreener_pair(&candidate.token_id).await else {
+            continue;
+        };
+        let Some(pair) = d.pairs.and_then(|v| v.first().cloned()) else {
+            continue;
+        };
+
+        let liquidity_usd = pair.liquidity_usd.unwrap_or(0.0);
+        // The pair may come from the scanner's cache, so its price is of unknown age
+        let target = EntryTarget {
+            token_id: &candidate.token_id,
+            dev_wallet_address: candidate.dev_wallet_address.as_deref(),
+            launched_at: candidate.launched_at,
+            graduation: pair.dex_id.as_deref().is_some_and(|dex| dex != "pumpfun"),
+            liquidity_usd,
+            price: pair.price_usd.unwrap_or(0.0),
+            priced_at_ms: None,
+        };
+        let (to_spend_sol, mut rejections) = {
+            let guard = shared.lock().await;
+            let slot = &guard.slots[idx];
+            if !slot.reentries.is_confirmed(&candidate, &pair, &config) {
+                continue;
+            }
+            let to_spend_sol = entry_size(
+                &config,
+                &slot.portfolio,
+                config.reentry_max_sol * size_multiplier,
+                liquidity_usd,
+            );
+            let rejections = entry_limits(slot, headroom, &target, to_spend_sol);
+            (to_spend_sol, rejections)
+        };
+        if rejections.is_empty() {
+            rejections.extend(sell_route_check(scanner, &target, to_spend_sol, &config).await);
+        }
+        let sandwich = if wallets.is_some() && rejections.is_empty() {
+            let sandwich = sandwich_estimate(scanner, &target, to_spend_sol, &config).await;
+            rejections.extend(sandwich.rejection());
+            Some(sandwich)
+        } else {
+            None
+        };
+        if let Some(r) = rejections.first() {
+            println!(
+                "[{}] Re-entry skipped: {} {} ({})",
+                strategy_id, candidate.token_id, r.filter, r.detail
+            );
+            continue;
+        }
+        let to_spend_sol = sandwich.as_ref().map_or(to_spend_sol, |s| s.sol_amount);
+        let jito = sandwich.as_ref().is_some_and(|s| s.jito);
+
+        // Large live buys wait for an operator
+        if wallets.is_some()
+            && let Some(r) = control
+                .approvals()
+                .check(
+                    &strategy_id,
+                    &candidate.token_id,
+                    to_spend_sol,
+                    candidate.score,
+                )
+                .await
+        {
+            println!(
+                "[{}] Re-entry not approved: {} ({})",
+                strategy_id, candidate.token_id, r.detail
+            );
+            continue;
+        }
+        let lease = position_lease(&strategy_id, &candidate.token_id);
+        if let Some(leases) = leases
+            && let Some(r) = claim_rejection(leases, &lease).await
+        {
+            println!(
+                "[{}] Re-entry leased: {} ({})",
+                strategy_id, candidate.token_id, r.detail
+            );
+            continue;
+        }
+
+        let order_id = new_order_id();
+        let executor = wallets.map(|w| w.for_entry(&strategy_id));
+        let wallet = executor.map(|e| e.pubkey());
+        let order = NewOrder {
+            client_order_id: &order_id,
+            strategy_id: &strategy_id,
+            run_id,
+            token_id: &candidate.token_id,
+            side: "buy",
+            order_type: EntryType::Market.as_str(),
+            sol_amount: Some(to_spend_sol),
+            shadow,
+            expires_at: Utc::now() + chrono::Duration::seconds(ORDER_TIMEOUT_SECS),
+        };
+        let modeled_price =
+            match send_quote(scanner, &strategy_id, &target, &config, executor.is_some()).await {
+                Ok((_, modeled_price)) => modeled_price,
+                Err(reason) => {
+                    abort_entry(store, &order, &reason, leases, &lease).await?;
+                    continue;
+                }
+            };
+
+        // The slot was unlocked since the decision, so its limits are checked again before
+        // the buy is sent
+        let recheck = {
+            let guard = shared.lock().await;
+            let slot = &guard.slots[idx];
+            let mut recheck = still_allowed(slot, headroom, &target, to_spend_sol, true);
+            if taken(slot, &candidate.token_id) {
+                recheck.push(FilterRejection::new("held", "the token is already held"));
+            }
+            recheck
+        };
+        if let Some(r) = recheck.first() {
+            println!(
+                "[{}] Risk limit before sending: {} {} ({})",
+                strategy_id, candidate.token_id, r.filter, r.detail
+            );
+            abort_entry(store, &order, &r.detail, leases, &lease).await?;
+            continue;
+        }
+        submit_order(store, &order, executor.is_some()).await?;
+        let executed = match executor {
+            Some(executor) => match executor
+                .buy(&candidate.token_id, to_spend_sol, None, &order_id, jito)
+                .await
+            {
+                Ok(executed) => Some(executed),
+                Err(e) => {
+                    println!(
+                        "[{}] Live re-entry failed for {}: {}",
+                        strategy_id, candidate.token_id, e
+                    );
+                    fail_order(store, &order_id, &e).await?;
+                    // The lease stays held while the buy may still land
+                    if let Some(leases) = leases
+                        && !e.is::<Unconfirmed>()
+                    {
+                        leases.release_logged(&lease).await;
+                    }
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let entry_signature = executed.as_ref().map(|e| e.signature.clone());
+        let entry_fill = executed.and_then(|e| e.fill);
+        let (entry_price, qty, to_spend_sol) = fill_entry(
+            entry_fill,
+            modeled_price,
+            to_spend_sol,
+            config.sol_usd_price,
+        );
+        let usd_in = to_spend_sol * config.sol_usd_price;
+
+        // Recorded under the lock, so the ledger's running balance follows the portfolio's
+        let mut guard = shared.lock().await;
+        let slot = &mut guard.slots[idx];
+        let portfolio = &mut slot.portfolio;
+        store
+            .open_trade(
+                &NewTrade {
+                    token_id: &candidate.token_id,
+                    entry_price,
+                    qty,
+                    usd_in,
+                    sol_in: to_spend_sol,
+                    score: candidate.score,
+                    is_reentry: true,
+                    strategy_id: &strategy_id,
+                    run_id,
+                    preset: &preset,
+                    source: &candidate.source,
+                    entry_signature: entry_signature.as_deref(),
+                    wallet: wallet.as_deref(),
+                    client_order_id: &order_id,
+                    shadow,
+                    launch_hour: trade_hour(candidate.launched_at),
+                    entry_type: EntryType::Market.as_str(),
+                },
+                &ledger::Entry {
+                    strategy_id: &strategy_id,
+                    kind: "buy",
+                    order_id: Some(&order_id),
+                    delta_sol: -to_spend_sol,
+                    balance_sol: portfolio.sol_balance - to_spend_sol,
+                    open_sol: portfolio.open_sol() + to_spend_sol,
+                },
+            )
+            .await?;
+        portfolio.sol_balance -= to_spend_sol;
+        portfolio.run_entries += 1;
+        portfolio.run_sol_spent += to_spend_sol;
+        headroom.consume(to_spend_sol);
+        scanner.invalidate(&candidate.token_id);
+        portfolio.positions.push(Position {
+            order_id: order_id.clone(),
+            token_id: candidate.token_id.clone(),
+            source: candidate.source.clone(),
+            entry_price,
+            qty,
+            usd_in,
+            sol_in: to_spend_sol,
+            opened_at: Utc::now(),
+            score: candidate.score,
+            dev_wallet_address: candidate.dev_wallet_address.clone(),
+            launched_at: candidate.launched_at,
+            is_reentry: true,
+            wallet: wallet.clone(),
+            token_raw: entry_fill.map(|f| f.token_raw),
+            trading: false,
+            entry_liquidity_usd: liquidity_usd,
+            peak_price: entry_price,
+            live: Position::new_live_state(entry_price, liquidity_usd),
+            last_marked_at: None,
+            mint_baseline: None,
+            mint_exit: None,
+        });
+        slot.reentries.mark_reentered(&candidate.token_id);
+        drop(guard);
+
+        fill_order(
+            store,
+            &order_id,
+            OrderStatus::Filled,
+            to_spend_sol,
+            entry_signature.as_deref(),
+        )
         .await?;
+        control.publish(BotEvent::Trade(TradeEvent {
+            strategy_id: strategy_id.clone(),
+            token_id: candidate.token_id.clone(),
+            side: "buy",
+            price_usd: entry_price,
+            qty,
+            usd: usd_in,
+            pnl_usd: None,
+            reason: None,
+            is_reentry: true,
+            shadow,
+            signature: entry_signature,
+            at: Utc::now(),
+        }));
 
-    println!(
-        "Simulation finished. Remaining SOL balance: {} SOL",
-        portfolio.sol_balance
-    );
+        println!(
+            "[{}] Re-entry: {} price=${:.8} (prior high ${:.8})",
+            strategy_id, candidate.token_id, entry_price, candidate.prior_high_price
+        );
+    }
     Ok(())
 }
+
+/// Enrichment steps to run, so expensive lookups can be turned off on rate-limited RPC
+/// plans; the fields a skipped step fills stay missing
+#[derive(Debug, Clone, Copy)]
+pub struct Enrichment {
+    pub mint_age: bool,
+    pub creator_buy: bool,
+    pub bonding_curve: bool,
+    /// Holder count and top holders, which funding tracing also needs
+    pub holders: bool,
+    pub metadata: bool,
+    pub dexscreener: bool,
+    /// Jupiter round trip of graduated tokens; see `tax`
+    pub tax_probe: bool,
+}
+
+impl Default for Enrichment {
+    fn default() -> Self {
+        Self {
+            mint_age: true,
+            creator_buy: true,
+            bonding_curve: true,
+            holders: true,
+            metadata: true,
+            dexscreener: true,
+            tax_probe: true,
+        }
+    }
+}
+
+/// Compare a fresh read of a held token's mint with the position's baseline; the baseline
+/// moves to each reported change, so a change is reported once
+fn observe_mint(pos: &mut Position, state: MintState) -> Option<MintChange> {
+    let change = match &pos.mint_baseline {
+        Some(baseline) => state.change_since(baseline),
+        None => None,
+    };
+    if pos.mint_baseline.is_none() || change.is_some() {
+        pos.mint_baseline = Some(state);
+    }
+    change
+}
+
+/// Run the enabled enrichment steps (chain, holders, DEX data) for a single listing
+pub async fn enrich_listing<P: TokenDataProvider>(
+    scanner: &P,
+    l: PumpFunListing,
+    steps: &Enrichment,
+) -> TokenEvent {
+    let mut ev: TokenEvent = l.clone().into();
+
+    if steps.mint_age
+        && let Ok(Some(launch)) = scanner.query_mint_launch(&l.token_address).await
+    {
+        if let Some(created_at) = launch.created_at {
+            let age = Utc::now().timestamp() - created_at;
+            ev.token_age_secs = Some(age.max(0) as u64);
+            ev.launched_at = Some(created_at);
+        }
+        ev.first_block_buys = Some(launch.first_block_buys);
+    }
+
+    if steps.creator_buy
+        && let (Some(signature), Some(creator)) = (&l.signature, &l.creator)
+        && let Ok(Some(pct)) = scanner
+            .query_creator_initial_buy_pct(signature, creator, &l.token_address)
+            .await
+    {
+        ev.dev_initial_buy_pct = Some(pct);
+    }
+
+    if steps.bonding_curve
+        && let Some(bonding_curve) = &l.bonding_curve
+        && let Ok(Some(curve)) = scanner.query_bonding_curve(bonding_curve).await
+    {
+        ev.bonding_curve_progress_pct = Some(curve.progress_pct());
+    }
+
+    let mut found = vec![];
+    if steps.holders
+        && let Ok(Some(holder_stats)) = scanner.query_token_holder_stats(&l.token_address).await
+        && let Some(total) = holder_stats.total
+    {
+        ev.holders = total as i32;
+        found.push("holders");
+    }
+
+    // A live freeze authority can stop holders from selling (honeypot); a live mint
+    // authority can still inflate the supply
+    let mut transfer_fee_bps = None;
+    if steps.metadata
+        && let Ok(Some(metadata)) = scanner.query_token_metadata(&l.token_address).await
+    {
+        ev.freeze_authority = metadata.freeze_authority.is_some();
+        ev.upgradeable = metadata.mint_authority.is_some();
+        transfer_fee_bps = Some(metadata.transfer_fee_bps.unwrap_or(0));
+        found.push("authorities");
+    }
+
+    if steps.holders
+        && let Ok(Some(top_holders)) = scanner.query_token_top_holders(&l.token_address).await
+        && let Some(holders_list) = top_holders.result
+        && let Some(first_holder) = holders_list.first()
+    {
+        // Assume first holder is the dev/creator
+        if let Some(pct) = first_holder.percentage_relative_to_total_supply {
+            ev.dev_hold_pct = pct;
+            found.push("dev_hold");
+        }
+        if ev.dev_wallet_address.is_none() {
+            ev.dev_wallet_address = first_holder.owner_address.clone();
+        }
+    }
+    if l.liquidity.is_some() {
+        found.push("liquidity");
+    }
+    if steps.dexscreener
+        && let Ok(Some(d)) = scanner.query_dexscreener_pair(&l.token_address).await
+        && let Some(pairs) = d.pairs
+        && let Some(first) = pairs.first()
+    {
+        if let Some(liquidity) = first.liquidity_usd {
+            ev.liquidity_usd = liquidity;
+            found.push("liquidity");
+        }
+        if ev.market_cap_usd <= 0.0 {
+            ev.market_cap_usd = first.market_cap_usd.unwrap_or(0.0);
+        }
+        if ev.base_price <= 0.0 {
+            ev.base_price = first.price_usd.unwrap_or(0.0);
+            ev.priced_at_ms = Some(Utc::now().timestamp_millis());
+        }
+    }
+    if ev.market_cap_usd > 0.0 {
+        found.push("market_cap");
+    }
+    if ev.token_age_secs.is_some() {
+        found.push("token_age");
+    }
+    if ev.dev_initial_buy_pct.is_some() {
+        found.push("dev_initial_buy");
+    }
+    if ev.bonding_curve_progress_pct.is_some() {
+        found.push("bonding_curve_progress");
+    }
+    ev.missing_fields = ENRICHMENT_FIELDS
+        .into_iter()
+        .filter(|f| !found.contains(f))
+        .map(String::from)
+        .collect();
+    // Momentum is recorded under the default parameters, from the trade stream when it was
+    // watched; tokens from signals and detectors fall back to their liquidity
+    ev.momentum = match ev.trades {
+        Some(_) => ev.has_momentum(&StrategyConfig::default()),
+        None => ev.liquidity_usd > 1000.0,
+    };
+    // Graduation heuristic from market cap and liquidity
+    ev.graduation =
+        ev.market_cap_usd >= 50000.0 && ev.market_cap_usd <= 300000.0 && ev.liquidity_usd > 1000.0;
+
+    // Tokens still on the bonding curve have no pool that could tax them
+    let round_trip = if steps.tax_probe && ev.graduation {
+        tax::probe(scanner, &l.token_address).await
+    } else {
+        None
+    };
+    ev.transfer_fee_pct = transfer_fee_bps.map(|bps| bps as f64 / 100.0);
+    ev.round_trip_tax_pct = tax::round_trip_tax_pct(transfer_fee_bps, round_trip.as_ref());
+
+    ev
+}
+
+/// Enrich `l`, recording how long it waited and how long enrichment took; returns the event
+/// with when it was received, falling back to when it was polled
+async fn enrich_timed<P: TokenDataProvider>(
+    scanner: &P,
+    steps: &Enrichment,
+    funding: &Tracer,
+    l: PumpFunListing,
+    polled_at: std::time::Instant,
+    latency: &LatencyStats,
+) -> (TokenEvent, std::time::Instant) {
+    let received_at = l.received_at.unwrap_or(polled_at);
+    let started = std::time::Instant::now();
+    latency.record(Stage::Queue, started.saturating_duration_since(received_at));
+    let mut ev = enrich_listing(scanner, l, steps).await;
+    if steps.holders {
+        funding.apply(scanner, &mut ev).await;
+    }
+    latency.record(Stage::Enrich, started.elapsed());
+    (ev, received_at)
+}
+
+/// Enrich a bare mint that did not come from a PumpPortal listing
+pub async fn enrich_mint<P: TokenDataProvider>(
+    scanner: &P,
+    mint: &str,
+    steps: &Enrichment,
+) -> TokenEvent {
+    let listing = PumpFunListing {
+        token_address: mint.to_string(),
+        ..Default::default()
+    };
+    enrich_listing(scanner, listing, steps).await
+}
+
+/// Enrich a mint received from an external signal or a detector
+async fn enrich_signal<P: TokenDataProvider>(
+    scanner: &P,
+    steps: &Enrichment,
+    signal: ExternalSignal,
+) -> TokenEvent {
+    let mut ev = enrich_mint(scanner, &signal.mint, steps).await;
+    ev.source = signal.source.unwrap_or("signal").to_string();
+    ev.signal_confidence = signal.confidence;
+    ev
+}
+
+/// Pause a strategy's entries after a losing streak, until its cooldown runs out or it is
+/// resumed through the API
+fn pause_entries(
+    control: &ControlState,
+    strategy_id: &str,
+    config: &StrategyConfig,
+    reason: String,
+) {
+    let now = Utc::now();
+    let until = (config.pause_cooldown_secs > 0)
+        .then(|| now + chrono::Duration::seconds(config.pause_cooldown_secs as i64));
+    match until {
+        Some(until) => println!(
+            "[{}] Entries paused until {}: {}",
+            strategy_id,
+            until.format("%H:%M:%S UTC"),
+            reason
+        ),
+        None => println!("[{}] Entries paused until resumed: {}", strategy_id, reason),
+    }
+    control.pause_strategy(StrategyPause {
+        strategy_id: strategy_id.to_string(),
+        reason,
+        since: now,
+        until,
+    });
+}
+
+/// Mark a strategy's open positions, and pause its entries when its equity shows a losing
+/// streak
+fn track_equity(
+    writes: &WriteBehind,
+    cfg: &Config,
+    control: &ControlState,
+    slot: &mut StrategySlot,
+) {
+    persist_marks(writes, cfg, slot);
+    let equity = slot.portfolio.equity_usd(slot.config.sol_usd_price);
+    if let Some(reason) = slot.streak.record_equity(equity, Utc::now(), &slot.config) {
+        pause_entries(control, &slot.id, &slot.config, reason);
+    }
+}
+
+/// Queue a mark for every open position whose last mark is older than the mark interval
+fn persist_marks(writes: &WriteBehind, cfg: &Config, slot: &mut StrategySlot) {
+    let sol_usd_price = slot.config.sol_usd_price;
+    let portfolio = &mut slot.portfolio;
+    let drawdown = portfolio.update_drawdown(sol_usd_price);
+    let equity = portfolio.equity_usd(sol_usd_price);
+    let now = Utc::now();
+    let interval = chrono::Duration::seconds(cfg.mark_interval_secs as i64);
+
+    for pos in portfolio.positions.iter_mut() {
+        if pos.last_marked_at.is_some_and(|t| now - t < interval) {
+            continue;
+        }
+        writes.mark(MarkRow {
+            strategy_id: slot.id.clone(),
+            run_id: portfolio.run_id,
+            token_id: pos.token_id.clone(),
+            price_usd: pos.live.price_usd,
+            value_usd: pos.value_usd(),
+            unrealized_pnl: pos.unrealized_pnl_usd(),
+            equity_usd: equity,
+            drawdown_pct: drawdown,
+            queued_at: std::time::Instant::now(),
+        });
+        pos.last_marked_at = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_scanner::MockScanner;
+    use crate::strategy::decide;
+
+    /// PumpPortal reports market cap in SOL, so relax the filter that depends on it
+    fn fixture_config() -> StrategyConfig {
+        StrategyConfig {
+            min_market_cap_usd: 0.0,
+            ..StrategyConfig::default()
+        }
+    }
+
+    async fn enriched_fixture_events(scanner: &MockScanner) -> Vec<TokenEvent> {
+        let mut events = Vec::new();
+        for l in scanner.fetch_pumpfun_listings().await.unwrap() {
+            events.push(enrich_listing(scanner, l, &Enrichment::default()).await);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn enriches_and_decides_on_recorded_listings() {
+        let scanner = MockScanner::new();
+        let config = fixture_config();
+        let events = enriched_fixture_events(&scanner).await;
+
+        let good = &events[0];
+        assert_eq!(good.holders, 40);
+        assert!((good.dev_hold_pct - 4.0).abs() < 0.01);
+        assert_eq!(good.liquidity_usd, 22000.0);
+        assert_eq!(good.base_price, 0.0000312);
+        assert_eq!(good.launched_at, Some(1_760_599_400));
+        assert!(good.momentum);
+        assert_eq!(good.trades.as_ref().map(Vec::len), Some(4));
+        assert!(!events[1].has_momentum(&config));
+        assert!(!good.freeze_authority && !good.upgradeable);
+        assert!(decide(good, &config).should_buy);
+
+        let rug = decide(&events[1], &config);
+        assert!(events[1].freeze_authority);
+        assert!(!rug.should_buy);
+        assert!(rug.rejections.iter().any(|r| r.filter == "dev_hold"));
+        assert!(
+            rug.rejections
+                .iter()
+                .any(|r| r.filter == "freeze_authority")
+        );
+    }
+
+    #[tokio::test]
+    async fn external_signals_use_the_scanner_pipeline() {
+        let scanner = MockScanner::new();
+        let signal = ExternalSignal {
+            mint: "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump".to_string(),
+            confidence: Some(0.5),
+            source: None,
+        };
+        let ev = enrich_signal(&scanner, &Enrichment::default(), signal).await;
+
+        assert_eq!(ev.source, "signal");
+        assert_eq!(ev.signal_confidence, Some(0.5));
+        assert_eq!(ev.holders, 40);
+        // The price comes from DexScreener, so its age is known
+        assert!(ev.base_price > 0.0 && ev.priced_at_ms.is_some());
+        // No listing data, so market cap comes from DexScreener
+        assert_eq!(ev.market_cap_usd, 31_200.0);
+        assert!(decide(&ev, &fixture_config()).should_buy);
+    }
+
+    #[tokio::test]
+    async fn exits_on_profit_target_from_replayed_prices() {
+        let scanner = MockScanner::new();
+        let config = fixture_config();
+        let ev = enriched_fixture_events(&scanner).await.remove(0);
+
+        let mut live = Position::new_live_state(ev.base_price, ev.liquidity_usd);
+        let mut exits = Vec::new();
+        for _ in 0..2 {
+            let d = scanner
+                .query_dexscreener_pair(&ev.id)
+                .await
+                .unwrap()
+                .unwrap();
+            live.update_from_pair(&d.pairs.unwrap()[0]);
+            exits.push(should_exit(&live, ev.base_price, ev.liquidity_usd, &config));
+        }
+
+        // +28% holds, +60% clears the minimum profit target
+        assert!(!exits[0].should_exit);
+        assert!(exits[1].should_exit);
+        assert_eq!(exits[1].reason, "profit_target");
+    }
+
+    #[tokio::test]
+    async fn exit_engine_sells_without_new_events() {
+        let scanner = MockScanner::new();
+        let ev = enriched_fixture_events(&scanner).await.remove(0);
+        let store = Store::Memory(Arc::new(crate::memory_store::MemoryStore::new()));
+        let mut cfg = Config::from_env();
+        cfg.exit_interval_secs = 1;
+        let profiles = crate::strategy_config::Profiles::builtin();
+        let mut manager = PortfolioManager::from_specs("default", &profiles, &cfg).unwrap();
+        manager.slots[0].config = fixture_config();
+        let mut position = test_position(&ev.id, ev.base_price, 0.5 * 150.0, 0.5);
+        position.entry_liquidity_usd = ev.liquidity_usd;
+        position.live = Position::new_live_state(ev.base_price, ev.liquidity_usd);
+        manager.slots[0].portfolio.positions.push(position);
+        let control = ControlState::new(&manager);
+        let write_counters = Arc::new(WriteCounters::default());
+        let writes = WriteBehind::spawn(
+            store.clone(),
+            10,
+            std::time::Duration::from_secs(1),
+            write_counters.clone(),
+        );
+        let runtime = Runtime {
+            snapshot: &SharedSnapshot::default(),
+            queue: &ListingQueue::new(0, Default::default()),
+            latency: &LatencyStats::default(),
+            wallets: None,
+            control: &control,
+            write_counters: &write_counters,
+            leases: None,
+            degradation: &Degradation::new(0, 0),
+        };
+
+        // The engine runs alongside a scan that sees no events: its first pass holds at +28%,
+        // its second takes profit at +60%, and it returns once it is told to stop
+        let shared = tokio::sync::Mutex::new(&mut manager);
+        let (stop, stopped) = tokio::sync::watch::channel(false);
+        let engine = run_exit_engine(&store, &scanner, &shared, &writes, &cfg, &runtime, stopped);
+        let scan = async {
+            while !shared.lock().await.slots[0].portfolio.positions.is_empty() {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            stop.send(true).unwrap();
+        };
+        let (result, ()) = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            tokio::join!(engine, scan)
+        })
+        .await
+        .expect("the exit engine neither sold nor stopped");
+        result.unwrap();
+        assert!(manager.slots[0].portfolio.realized_pnl_usd > 0.0);
+    }
+
+    #[tokio::test]
+    async fn leaves_a_position_alone_while_it_trades() {
+        let scanner = MockScanner::new();
+        let store = Store::Memory(Arc::new(crate::memory_store::MemoryStore::new()));
+        let cfg = Config::from_env();
+        let profiles = crate::strategy_config::Profiles::builtin();
+        let mut manager = PortfolioManager::from_specs("default", &profiles, &cfg).unwrap();
+        manager.slots[0].config = fixture_config();
+        let mut position = test_position(
+            "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump",
+            0.0000312,
+            75.0,
+            0.5,
+        );
+        position.mint_exit = Some("freeze_issued");
+        position.trading = true;
+        manager.slots[0].portfolio.positions.push(position);
+        let control = ControlState::new(&manager);
+        let shared = tokio::sync::Mutex::new(&mut manager);
+        let exits = || process_exits(&store, &scanner, &shared, 0, None, &control, None);
+
+        // A slice is being bought into the position, so its exit waits for the next pass
+        exits().await.unwrap();
+        assert_eq!(shared.lock().await.slots[0].portfolio.positions.len(), 1);
+        shared.lock().await.slots[0].portfolio.positions[0].trading = false;
+        exits().await.unwrap();
+        assert!(shared.lock().await.slots[0].portfolio.positions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refreshes_only_stale_quotes_before_buying() {
+        let scanner = MockScanner::new();
+        let mut ev = enriched_fixture_events(&scanner).await.remove(0);
+        let store = Store::Memory(Arc::new(crate::memory_store::MemoryStore::new()));
+        let cfg = Config::from_env();
+        let profiles = crate::strategy_config::Profiles::builtin();
+        let mut manager = PortfolioManager::from_specs("default", &profiles, &cfg).unwrap();
+        manager.slots[0].config = StrategyConfig {
+            max_quote_age_ms: 5_000,
+            ..fixture_config()
+        };
+        let control = ControlState::new(&manager);
+        let latency = LatencyStats::default();
+        let shared = tokio::sync::Mutex::new(&mut manager);
+
+        // A fresh quote is bought on as is, leaving the next recorded price (+28%) unread. A
+        // stale one is looked up again, and the buy fills at that price instead
+        for (age_ms, min_ratio, max_ratio) in [(0, 1.0, 1.05), (60_000, 1.28, 1.35)] {
+            ev.priced_at_ms = Some(Utc::now().timestamp_millis() - age_ms);
+            let mut headroom = shared.lock().await.headroom();
+            try_enter(
+                &store,
+                &scanner,
+                &shared,
+                0,
+                &ev,
+                std::time::Instant::now(),
+                &mut headroom,
+                Regime::Neutral,
+                None,
+                &control,
+                &latency,
+                None,
+            )
+            .await
+            .unwrap();
+            let position = shared.lock().await.slots[0].portfolio.positions.pop();
+            let ratio = position.expect("no position opened").entry_price / ev.base_price;
+            assert!(
+                (min_ratio..max_ratio).contains(&ratio),
+                "quote {}ms old filled at {:.3}x",
+                age_ms,
+                ratio
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_reentries_like_new_entries() {
+        let scanner = MockScanner::new();
+        let store = Store::Memory(Arc::new(crate::memory_store::MemoryStore::new()));
+        let cfg = Config::from_env();
+        let profiles = crate::strategy_config::Profiles::builtin();
+        let mut manager = PortfolioManager::from_specs("default", &profiles, &cfg).unwrap();
+        let token_id = "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump";
+        let slot = &mut manager.slots[0];
+        slot.config = StrategyConfig {
+            enable_reentry: true,
+            dead_regime_size_multiplier: 0.0,
+            max_quote_age_ms: 5_000,
+            max_entry_slippage_pct: 10.0,
+            ..fixture_config()
+        };
+        slot.reentries.watch(
+            ReentryCandidate {
+                token_id: token_id.into(),
+                source: "pumpportal".into(),
+                prior_high_price: 0.00003,
+                exit_volume_m5_usd: 1_000.0,
+                exited_at: Utc::now(),
+                score: 80.0,
+                dev_wallet_address: None,
+                launched_at: None,
+            },
+            &slot.config,
+        );
+        let control = ControlState::new(&manager);
+        let shared = tokio::sync::Mutex::new(&mut manager);
+        let (store, scanner, shared, control) = (&store, &scanner, &shared, &control);
+        let reenter = |regime| async move {
+            let mut headroom = shared.lock().await.headroom();
+            process_reentries(
+                store,
+                scanner,
+                shared,
+                0,
+                &mut headroom,
+                regime,
+                None,
+                control,
+                None,
+            )
+            .await
+            .unwrap();
+            shared.lock().await.slots[0].portfolio.positions.len()
+        };
+
+        // A dead market stands re-entries down like any other buy
+        assert_eq!(reenter(Regime::Dead).await, 0);
+        // The recovery at $0.0000312 is priced from the cache, so it is looked up again and
+        // has run 28% to $0.0000401: too far to buy
+        assert_eq!(reenter(Regime::Neutral).await, 0);
+        shared.lock().await.slots[0].config.max_entry_slippage_pct = 0.0;
+        assert_eq!(reenter(Regime::Neutral).await, 1);
+    }
+
+    #[test]
+    fn records_live_trades_at_their_executed_fill() {
+        // 0.1 SOL at $150 bought 2,000 tokens: $0.0075 each, whatever the quote said
+        let fill = Fill {
+            token_raw: 2_000_000_000,
+            sol: 0.1,
+        };
+        let (price, qty, sol) = fill_entry(Some(fill), 0.005, 0.12, 150.0);
+        assert!((price - 0.0075).abs() < 1e-12);
+        assert_eq!((qty, sol), (2_000.0, 0.1));
+        // Without a fill the modeled price stands
+        assert_eq!(fill_entry(None, 0.005, 0.1, 150.0), (0.005, 3_000.0, 0.1));
+
+        let sold = Fill {
+            token_raw: 2_000_000_000,
+            sol: 0.05,
+        };
+        let (price, proceeds) = fill_exit(Some(sold), 0.02, 2_000.0, 150.0);
+        assert!((price - 0.00375).abs() < 1e-12);
+        assert_eq!(proceeds, 0.05);
+        assert_eq!(fill_exit(None, 0.015, 2_000.0, 150.0), (0.015, 0.2));
+    }
+
+    #[test]
+    fn marks_open_positions_to_market() {
+        let mut portfolio = Portfolio::new(1.0);
+        portfolio
+            .positions
+            .push(test_position("open", 1.0, 30.0, 0.2));
+        // 1 SOL at $150 plus the position at its entry price
+        assert_eq!(portfolio.update_drawdown(150.0), 0.0);
+        assert_eq!(portfolio.peak_equity_usd, 180.0);
+
+        portfolio.positions[0].live.price_usd = 0.4;
+        assert_eq!(portfolio.unrealized_pnl_usd(), -18.0);
+        assert!((portfolio.update_drawdown(150.0) - 0.1).abs() < 1e-9);
+        // The high-water mark stays put on the way down
+        assert_eq!(portfolio.peak_equity_usd, 180.0);
+
+        // Refreshing from the pair tracks the peak for re-entries
+        let pair = crate::models::DexPairInfo {
+            dex_id: Some("pumpfun".to_string()),
+            liquidity_usd: None,
+            price_usd: Some(1.5),
+            volume_m5_usd: None,
+            market_cap_usd: None,
+        };
+        portfolio.positions[0].refresh(&pair);
+        assert_eq!(portfolio.positions[0].peak_price, 1.5);
+        portfolio.positions[0].live.price_usd = 0.4;
+
+        let snapshot = portfolio.snapshot("default", 150.0);
+        assert_eq!(snapshot.equity_usd, 162.0);
+        assert!((snapshot.drawdown_pct - 0.1).abs() < 1e-9);
+        assert_eq!(snapshot.positions[0].last_price, 0.4);
+        assert_eq!(snapshot.positions[0].value_usd, 12.0);
+    }
+
+    #[test]
+    fn banks_profits_above_the_compound_cap() {
+        let mut portfolio = Portfolio::new(3.2);
+        portfolio
+            .positions
+            .push(test_position("open", 1.0, 15.0, 0.5));
+
+        // 3.2 free + 0.5 open against a 3.0 cap
+        portfolio.bank_above(3.0);
+        assert!((portfolio.sol_balance - 2.5).abs() < 1e-9);
+        assert!((portfolio.banked_sol - 0.7).abs() < 1e-9);
+        portfolio.bank_above(3.0);
+        assert!((portfolio.banked_sol - 0.7).abs() < 1e-9);
+    }
+}
@@ -1,9 +1,27 @@
+use crate::models::DexPairInfo;
 use crate::strategy_config::StrategyConfig;
+use chrono::{DateTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Enrichment lookups a token event can be missing, as named in `missing_fields` and
+/// `StrategyConfig::required_fields`
+pub const ENRICHMENT_FIELDS: [&str; 8] = [
+    "market_cap",
+    "liquidity",
+    "holders",
+    "dev_hold",
+    "authorities",
+    "token_age",
+    "dev_initial_buy",
+    "bonding_curve_progress",
+];
+
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenEvent {
     pub id: String,
+    /// Detection source (e.g. "pumpfun")
+    pub source: String,
     pub token_type: String,
     pub market_cap_usd: f64,
     pub dev_hold_pct: f64,
@@ -11,100 +29,409 @@ pub struct TokenEvent {
     pub holders: i32,
     pub upgradeable: bool,
     pub freeze_authority: bool,
+    /// Momentum under the default parameters when the token was recorded; strategies judge
+    /// their own with `has_momentum`
     pub momentum: bool,
     pub graduation: bool,
     pub base_price: f64,
+    /// Unix time (ms) `base_price` was observed at (None if unknown)
+    #[serde(default)]
+    pub priced_at_ms: Option<i64>,
     // New fields for enhanced strategy
     pub dev_wallet_address: Option<String>,
     pub is_dev_known_rugger: bool,
-    pub entry_market_cap: f64,
-    pub raydium_lp_detected: bool,
+    /// Seconds since the mint's first on-chain signature (None if unknown)
+    pub token_age_secs: Option<u64>,
+    /// Unix timestamp of the mint's first on-chain signature (None if unknown)
+    pub launched_at: Option<i64>,
+    /// Percentage of supply the creator bought in the launch transaction
+    pub dev_initial_buy_pct: Option<f64>,
+    /// Percentage of the pump.fun bonding curve sold toward graduation
+    pub bonding_curve_progress_pct: Option<f64>,
+    /// Confidence (0.0-1.0) attached to an external signal; scales position size
+    #[serde(default)]
+    pub signal_confidence: Option<f64>,
+    /// The mint is on the operator's blacklist
+    #[serde(default)]
+    pub blacklisted: bool,
+    /// Enrichment fields whose lookup came back empty; their values are placeholders
+    #[serde(default)]
+    pub missing_fields: Vec<String>,
+    /// Outcomes of the creator's earlier tokens (None if the creator or its history is unknown)
+    #[serde(default)]
+    pub dev_stats: Option<DevStats>,
+    /// Share of supply (%) held by the largest cluster of top holders with a common funder
+    /// (None unless funding was traced)
+    #[serde(default)]
+    pub effective_top_holder_pct: Option<f64>,
+    /// Token-2022 transfer fee (%) taken on every transfer (None unless the mint was read)
+    #[serde(default)]
+    pub transfer_fee_pct: Option<f64>,
+    /// Share of a buy (%) lost to transfer fees and pool taxes on buying and selling straight
+    /// back (None if neither was measured)
+    #[serde(default)]
+    pub round_trip_tax_pct: Option<f64>,
+    /// Buys that landed in the mint's launch slot besides the create transaction (None unless
+    /// the launch was looked up); backtests model competing snipers from it
+    #[serde(default)]
+    pub first_block_buys: Option<u32>,
+    /// Buys and sells received on the trade stream after the listing, oldest first (None
+    /// unless the stream was watched)
+    #[serde(default)]
+    pub trades: Option<Vec<TokenTrade>>,
 }
 
-impl TokenEvent {
-    pub fn compute_score(&self, config: &StrategyConfig) -> f64 {
-        let mut score = 50.0;
+/// A buy or sell of a token on PumpPortal's trade stream
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenTrade {
+    pub trader: String,
+    pub buy: bool,
+    pub sol_amount: f64,
+    /// Unix time (ms) the trade was received
+    pub at_ms: i64,
+}
 
-        // Known rugger = instant fail
-        if self.is_dev_known_rugger {
-            return 0.0;
-        }
+/// What a stretch of the trade stream bought and sold
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TradeFlow {
+    /// SOL bought less SOL sold
+    pub net_inflow_sol: f64,
+    /// Distinct wallets that bought
+    pub buyers: u32,
+}
 
-        // Holder count: bonus for holders above minimum
-        if self.holders >= config.min_holders {
-            score += ((self.holders as f64 - config.min_holders as f64) / 50.0).min(30.0);
-        } else {
-            // Penalty for low holders
-            score -= (config.min_holders as f64 - self.holders as f64) / 10.0;
+impl TradeFlow {
+    /// Flow over the `window_secs` up to the last of `trades`
+    pub fn over(trades: &[TokenTrade], window_secs: u64) -> Self {
+        let Some(last) = trades.iter().map(|t| t.at_ms).max() else {
+            return Self::default();
+        };
+        let since = last - window_secs as i64 * 1000;
+        let mut flow = Self::default();
+        let mut buyers = Vec::new();
+        for t in trades.iter().filter(|t| t.at_ms >= since) {
+            if t.buy {
+                flow.net_inflow_sol += t.sol_amount;
+                if !buyers.contains(&&t.trader) {
+                    buyers.push(&t.trader);
+                }
+            } else {
+                flow.net_inflow_sol -= t.sol_amount;
+            }
         }
+        flow.buyers = buyers.len() as u32;
+        flow
+    }
+}
 
-        // Dev hold percentage: stricter penalties
-        if self.dev_hold_pct > config.max_dev_hold_pct {
-            score -= 100.0; // Auto-fail
-        } else if self.dev_hold_pct > 10.0 {
-            score -= (self.dev_hold_pct - 10.0) * config.high_dev_hold_penalty_multiplier;
-        } else if self.dev_hold_pct < 5.0 {
-            score += config.low_dev_hold_bonus;
-        }
+/// What became of a creator's labeled tokens, from the `dev_wallets` table
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DevStats {
+    pub tokens_launched: u32,
+    /// Share (0.0-1.0) of the tokens that left the bonding curve
+    pub graduation_rate: f64,
+    pub avg_peak_multiple: f64,
+    pub rug_count: u32,
+}
 
-        // Liquidity: strong buy pressure indicator
-        score += (self.liquidity_usd / config.liquidity_bonus_divisor).min(25.0);
+impl TokenEvent {
+    /// Whether enrichment found data for `field` (see `ENRICHMENT_FIELDS`)
+    pub fn has(&self, field: &str) -> bool {
+        !self.missing_fields.iter().any(|f| f == field)
+    }
 
-        // Market cap sweet spot
-        if self.market_cap_usd >= 50_000.0 && self.market_cap_usd <= 250_000.0 {
-            score += config.market_cap_sweet_spot_bonus;
-        } else if self.market_cap_usd > 250_000.0
-            && self.market_cap_usd <= config.max_market_cap_usd
-        {
-            score += 5.0; // Small bonus for near sweet spot
-        }
+    /// UTC hour (0-23) the mint was created in, when known
+    pub fn launch_hour(&self) -> Option<u32> {
+        launch_hour(self.launched_at)
+    }
 
-        // Safety flags
-        if self.upgradeable {
-            score -= config.upgradeable_penalty;
-        }
-        if self.freeze_authority {
-            score -= config.freeze_authority_penalty;
-        }
+    /// Whether enough net SOL flowed in from enough buyers over `momentum_window_secs`; tokens
+    /// that weren't watched on the trade stream keep their recorded `momentum`
+    pub fn has_momentum(&self, config: &StrategyConfig) -> bool {
+        let Some(trades) = &self.trades else {
+            return self.momentum;
+        };
+        let flow = TradeFlow::over(trades, config.momentum_window_secs);
+        flow.net_inflow_sol >= config.momentum_min_net_inflow_sol
+            && flow.buyers >= config.momentum_min_buyers
+    }
 
-        // Momentum and graduation signals
-        if self.momentum {
-            score += config.momentum_bonus;
-        }
-        if self.graduation {
-            score += config.graduation_bonus;
+    pub fn compute_score(&self, config: &StrategyConfig) -> f64 {
+        // Known rugger = instant fail
+        if self.is_dev_known_rugger {
+            return 0.0;
         }
+        let score: f64 = self
+            .score_breakdown(config)
+            .iter()
+            .map(|(_, pts)| pts)
+            .sum();
+        score.clamp(0.0, 100.0)
+    }
 
-        score.max(0.0).min(100.0)
+    /// Points each enabled factor of `score_factors` contributed, weighted and in order,
+    /// starting from a base of 50; the score is their sum clamped to 0-100 (a known rugger
+    /// scores 0 regardless)
+    pub fn score_breakdown(&self, config: &StrategyConfig) -> Vec<(&'static str, f64)> {
+        let mut parts = vec![("base", 50.0)];
+        for factor in config.score_factors.iter().filter(|f| f.enabled) {
+            let Some((name, points)) = SCORE_FACTORS.iter().find(|(n, _)| *n == factor.name) else {
+                continue;
+            };
+            if let Some(points) = points(self, config) {
+                parts.push((name, points * factor.weight));
+            }
+        }
+        parts
     }
 
-    pub fn passes_basic_filters(&self, config: &StrategyConfig) -> bool {
+    /// Evaluate every entry filter and return the ones that failed, with details
+    pub fn filter_rejections(&self, config: &StrategyConfig) -> Vec<FilterRejection> {
+        let mut rejections = Vec::new();
+
         // Known rugger = instant reject
         if self.is_dev_known_rugger {
-            return false;
+            rejections.push(FilterRejection::new(
+                "known_rugger",
+                format!("dev wallet {:?} is a known rugger", self.dev_wallet_address),
+            ));
+        }
+        if self.blacklisted {
+            rejections.push(FilterRejection::new("blacklisted", "mint is blacklisted"));
+        }
+        // Required enrichment fields; the filters below skip optional ones that are missing
+        let missing: Vec<&str> = config
+            .required_fields
+            .iter()
+            .map(String::as_str)
+            .filter(|f| !self.has(f))
+            .collect();
+        if !missing.is_empty() {
+            rejections.push(FilterRejection::new(
+                "missing_data",
+                format!("no {} data", missing.join(", ")),
+            ));
         }
         // Market cap range
-        if self.market_cap_usd < config.min_market_cap_usd
-            || self.market_cap_usd > config.max_market_cap_usd
+        if self.has("market_cap")
+            && (self.market_cap_usd < config.min_market_cap_usd
+                || self.market_cap_usd > config.max_market_cap_usd)
         {
-            return false;
+            rejections.push(FilterRejection::new(
+                "market_cap",
+                format!(
+                    "${:.0} outside ${:.0}-${:.0}",
+                    self.market_cap_usd, config.min_market_cap_usd, config.max_market_cap_usd
+                ),
+            ));
         }
         // Holders minimum
-        if self.holders < config.min_holders {
-            return false;
+        if self.has("holders") && self.holders < config.min_holders {
+            rejections.push(FilterRejection::new(
+                "holders",
+                format!("{} < {}", self.holders, config.min_holders),
+            ));
         }
         // Dev hold maximum
-        if self.dev_hold_pct >= config.max_dev_hold_pct {
-            return false;
+        if self.has("dev_hold") && self.dev_hold_pct >= config.max_dev_hold_pct {
+            rejections.push(FilterRejection::new(
+                "dev_hold",
+                format!(
+                    "{:.1}% >= {:.1}%",
+                    self.dev_hold_pct, config.max_dev_hold_pct
+                ),
+            ));
         }
         // Safety: reject based on config
         if config.reject_upgradeable && self.upgradeable {
-            return false;
+            rejections.push(FilterRejection::new("upgradeable", "token is upgradeable"));
         }
         if config.reject_freeze_authority && self.freeze_authority {
-            return false;
+            rejections.push(FilterRejection::new(
+                "freeze_authority",
+                "token has a freeze authority",
+            ));
+        }
+        // Token age window (only enforced when the on-chain creation time is known)
+        if let Some(age) = self.token_age_secs
+            && (age < config.min_token_age_secs
+                || (config.max_token_age_secs > 0 && age > config.max_token_age_secs))
+        {
+            let detail = if age < config.min_token_age_secs {
+                format!("{}s below the {}s minimum", age, config.min_token_age_secs)
+            } else {
+                format!("{}s above the {}s maximum", age, config.max_token_age_secs)
+            };
+            rejections.push(FilterRejection::new("token_age", detail));
+        }
+        // Creator initial buy window
+        if let Some(pct) = self.dev_initial_buy_pct
+            && (pct < config.min_dev_initial_buy_pct || pct > config.max_dev_initial_buy_pct)
+        {
+            rejections.push(FilterRejection::new(
+                "dev_initial_buy",
+                format!(
+                    "{:.1}% outside {:.1}-{:.1}%",
+                    pct, config.min_dev_initial_buy_pct, config.max_dev_initial_buy_pct
+                ),
+            ));
+        }
+        // Detection source
+        if !config.allowed_sources.is_empty() && !config.allowed_sources.contains(&self.source) {
+            rejections.push(FilterRejection::new(
+                "source",
+                format!("source {} not traded", self.source),
+            ));
+        }
+        // Trading hours (only enforced when the on-chain creation time is known)
+        if let Some(hour) = self.launch_hour()
+            && !config.allowed_launch_hours_utc.is_empty()
+            && !config.allowed_launch_hours_utc.contains(&hour)
+        {
+            rejections.push(FilterRejection::new(
+                "launch_hour",
+                format!("launched at {:02}:00 UTC, outside the allowed hours", hour),
+            ));
+        }
+        // Round-trip tax, which comes straight off the profit target
+        if let Some(tax) = self.round_trip_tax_pct
+            && tax > config.max_total_tax_pct
+        {
+            rejections.push(FilterRejection::new(
+                "tax",
+                format!("{:.1}% round trip > {:.1}%", tax, config.max_total_tax_pct),
+            ));
+        }
+        // Bonding curve progress window (only enforced when the curve was decoded)
+        if let Some(progress) = self.bonding_curve_progress_pct
+            && (progress < config.min_bonding_curve_progress_pct
+                || progress > config.max_bonding_curve_progress_pct)
+        {
+            rejections.push(FilterRejection::new(
+                "bonding_curve_progress",
+                format!(
+                    "{:.1}% outside {:.1}-{:.1}%",
+                    progress,
+                    config.min_bonding_curve_progress_pct,
+                    config.max_bonding_curve_progress_pct
+                ),
+            ));
+        }
+        rejections
+    }
+}
+
+/// Points a scoring factor gives a token, or `None` when it doesn't apply
+pub type ScoreFn = fn(&TokenEvent, &StrategyConfig) -> Option<f64>;
+
+/// Scoring factors by the name `score_factors` refers to them by, in their default order.
+/// Rules for optional fields that enrichment couldn't fill in are skipped rather than scored
+/// on placeholder zeros
+pub const SCORE_FACTORS: [(&str, ScoreFn); 9] = [
+    ("holders", score_holders),
+    ("dev_hold", score_dev_hold),
+    ("liquidity", score_liquidity),
+    ("market_cap", score_market_cap),
+    ("upgradeable", score_upgradeable),
+    ("freeze_authority", score_freeze_authority),
+    ("momentum", score_momentum),
+    ("graduation", score_graduation),
+    ("bonding_curve_progress", score_bonding_curve_progress),
+];
+
+/// Bonus for holders above the minimum, penalty below it
+fn score_holders(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    if !ev.has("holders") {
+        return None;
+    }
+    let (holders, min) = (ev.holders as f64, config.min_holders as f64);
+    Some(if holders >= min {
+        ((holders - min) / 50.0).min(30.0)
+    } else {
+        -(min - holders) / 10.0
+    })
+}
+
+/// Dev hold percentage: stricter penalties
+fn score_dev_hold(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    if !ev.has("dev_hold") {
+        return None;
+    }
+    if ev.dev_hold_pct > config.max_dev_hold_pct {
+        Some(-100.0) // Auto-fail
+    } else if ev.dev_hold_pct > 10.0 {
+        Some(-(ev.dev_hold_pct - 10.0) * config.high_dev_hold_penalty_multiplier)
+    } else if ev.dev_hold_pct < 5.0 {
+        Some(config.low_dev_hold_bonus)
+    } else {
+        None
+    }
+}
+
+/// Liquidity: strong buy pressure indicator
+fn score_liquidity(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.has("liquidity")
+        .then(|| (ev.liquidity_usd / config.liquidity_bonus_divisor).min(25.0))
+}
+
+/// Market cap sweet spot
+fn score_market_cap(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    if !ev.has("market_cap") {
+        return None;
+    }
+    if ev.market_cap_usd >= 50_000.0 && ev.market_cap_usd <= 250_000.0 {
+        Some(config.market_cap_sweet_spot_bonus)
+    } else if ev.market_cap_usd > 250_000.0 && ev.market_cap_usd <= config.max_market_cap_usd {
+        Some(5.0) // Small bonus for near sweet spot
+    } else {
+        None
+    }
+}
+
+fn score_upgradeable(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.upgradeable.then_some(-config.upgradeable_penalty)
+}
+
+fn score_freeze_authority(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.freeze_authority
+        .then_some(-config.freeze_authority_penalty)
+}
+
+fn score_momentum(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.has_momentum(config).then_some(config.momentum_bonus)
+}
+
+fn score_graduation(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.graduation.then_some(config.graduation_bonus)
+}
+
+/// Share of `bonding_curve_progress_bonus` matching how far the curve has sold; listed even
+/// when it scores nothing, so the breakdown shows the progress was considered
+fn score_bonding_curve_progress(ev: &TokenEvent, config: &StrategyConfig) -> Option<f64> {
+    ev.bonding_curve_progress_pct
+        .map(|progress| progress.clamp(0.0, 100.0) / 100.0 * config.bonding_curve_progress_bonus)
+}
+
+/// UTC hour (0-23) of a Unix timestamp
+pub fn launch_hour(launched_at: Option<i64>) -> Option<u32> {
+    DateTime::from_timestamp(launched_at?, 0).map(|t| t.hour())
+}
+
+/// A failed entry filter, used to explain why a token was not bought
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRejection {
+    pub filter: String,
+    pub detail: String,
+}
+
+impl FilterRejection {
+    pub fn new(filter: &str, detail: impl Into<String>) -> Self {
+        Self {
+            filter: filter.to_string(),
+            detail: detail.into(),
         }
-        true
     }
 }
 
@@ -113,17 +440,37 @@ impl TokenEvent {
 pub struct TradeDecision {
     pub should_buy: bool,
     pub score: f64,
+    /// Entry filters that failed, empty when the token passed all of them
+    pub rejections: Vec<FilterRejection>,
+    /// Share of `max_sol_per_trade` the score sizes an entry at
+    pub size_fraction: f64,
+    /// The price the decision was made on, and when it was observed
+    pub price: f64,
+    pub priced_at_ms: Option<i64>,
 }
 
 pub fn decide(event: &TokenEvent, config: &StrategyConfig) -> TradeDecision {
-    let score = event.compute_score(config);
-    let basic = event.passes_basic_filters(config);
+    decide_with_score(event, config, event.compute_score(config))
+}
+
+/// Entry decision using a score from elsewhere (e.g. a scoring plugin)
+pub fn decide_with_score(event: &TokenEvent, config: &StrategyConfig, score: f64) -> TradeDecision {
+    let rejections = event.filter_rejections(config);
 
-    let should_buy = basic
+    let should_buy = rejections.is_empty()
         && score >= config.min_score_to_buy
-        && (!config.require_momentum_or_graduation || event.momentum || event.graduation);
+        && (!config.require_momentum_or_graduation
+            || event.has_momentum(config)
+            || event.graduation);
 
-    TradeDecision { should_buy, score }
+    TradeDecision {
+        should_buy,
+        score,
+        rejections,
+        size_fraction: config.size_fraction(score),
+        price: event.base_price,
+        priced_at_ms: event.priced_at_ms,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -132,33 +479,74 @@ pub struct ExitDecision {
     pub reason: String,
 }
 
-/// Determine if a position should be exited based on current token state
+/// Live market state of an open position's token, refreshed on its own
+/// rather than borrowed from whatever event the scanner is processing
+#[derive(Debug, Clone, Default)]
+pub struct LiveTokenState {
+    pub price_usd: f64,
+    pub liquidity_usd: f64,
+    pub volume_m5_usd: f64,
+    /// Token trades on a Raydium pool
+    pub raydium_lp_detected: bool,
+    /// Token has left the pump.fun bonding curve for a DEX pool
+    pub graduation: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl LiveTokenState {
+    /// Refresh from a DEX Screener pair, keeping previous values for fields the pair lacks
+    pub fn update_from_pair(&mut self, pair: &DexPairInfo) {
+        if let Some(price) = pair.price_usd.filter(|p| *p > 0.0) {
+            self.price_usd = price;
+        }
+        if let Some(liquidity) = pair.liquidity_usd {
+            self.liquidity_usd = liquidity;
+        }
+        if let Some(volume) = pair.volume_m5_usd {
+            self.volume_m5_usd = volume;
+        }
+        if let Some(dex_id) = pair.dex_id.as_deref() {
+            self.raydium_lp_detected = dex_id == "raydium";
+            self.graduation = dex_id != "pumpfun";
+        }
+        self.updated_at = Some(Utc::now());
+    }
+}
+
+/// Determine if a position should be exited based on its token's live state
+/// Stop loss and profit target are measured from the position's executed entry price
+/// against the latest observed price; price-based exits are skipped while no price is known
 pub fn should_exit(
-    event: &TokenEvent,
+    live: &LiveTokenState,
+    entry_price: f64,
     entry_liquidity: f64,
     config: &StrategyConfig,
 ) -> ExitDecision {
-    // Stop loss
-    if event.market_cap_usd < event.entry_market_cap * (1.0 - config.stop_loss_pct) {
-        return ExitDecision {
-            should_exit: true,
-            reason: "stop_loss".to_string(),
-        };
-    }
+    let current_price = live.price_usd;
+    if entry_price > 0.0 && current_price > 0.0 {
+        // Stop loss
+        if current_price < entry_price * (1.0 - config.stop_loss_pct) {
+            return ExitDecision {
+                should_exit: true,
+                reason: "stop_loss".to_string(),
+            };
+        }
 
-    // Profit target
-    let profit_pct = (event.market_cap_usd - event.entry_market_cap) / event.entry_market_cap;
-    if profit_pct >= config.min_profit_target_pct && profit_pct <= config.max_profit_target_pct {
-        return ExitDecision {
-            should_exit: true,
-            reason: "profit_target".to_string(),
-        };
+        // Profit target
+        let profit_pct = (current_price - entry_price) / entry_price;
+        if profit_pct >= config.min_profit_target_pct && profit_pct <= config.max_profit_target_pct
+        {
+            return ExitDecision {
+                should_exit: true,
+                reason: "profit_target".to_string(),
+            };
+        }
     }
 
     // Liquidity spike (Raydium LP detected)
-    if event.raydium_lp_detected
+    if live.raydium_lp_detected
         || (entry_liquidity > 0.0
-            && event.liquidity_usd > entry_liquidity * config.lp_spike_exit_multiplier)
+            && live.liquidity_usd > entry_liquidity * config.lp_spike_exit_multiplier)
     {
         return ExitDecision {
             should_exit: true,
@@ -166,8 +554,8 @@ pub fn should_exit(
         };
     }
 
-    // Graduation flag (legacy support)
-    if event.graduation {
+    // Graduated off the bonding curve
+    if live.graduation {
         return ExitDecision {
             should_exit: true,
             reason: "graduation".to_string(),
@@ -179,3 +567,239 @@ pub fn should_exit(
         reason: String::new(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PumpFunListing;
+
+    #[test]
+    fn scores_factors_in_order_with_their_weights() {
+        let mut ev: TokenEvent = PumpFunListing {
+            token_address: "mint".to_string(),
+            ..Default::default()
+        }
+        .into();
+        ev.missing_fields = vec!["holders".to_string(), "dev_hold".to_string()];
+        ev.liquidity_usd = 10_000.0;
+        ev.graduation = true;
+        ev.upgradeable = true;
+        let mut config = StrategyConfig::default();
+        let parts = ev.score_breakdown(&config);
+        let names: Vec<_> = parts.iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, ["base", "liquidity", "upgradeable", "graduation"]);
+        assert_eq!(ev.compute_score(&config), 65.0);
+
+        config
+            .set(
+                "score_factors",
+                r#"[{"name":"graduation","weight":3.0},{"name":"upgradeable","enabled":false},
+                    {"name":"liquidity"}]"#,
+            )
+            .unwrap();
+        assert_eq!(
+            ev.score_breakdown(&config),
+            [("base", 50.0), ("graduation", 75.0), ("liquidity", 10.0)]
+        );
+        // Clamped once every factor is in
+        assert_eq!(ev.compute_score(&config), 100.0);
+    }
+
+    #[test]
+    fn names_every_failed_filter() {
+        let mut ev: TokenEvent = PumpFunListing {
+            token_address: "mint".to_string(),
+            ..Default::default()
+        }
+        .into();
+        let config = StrategyConfig {
+            allowed_sources: vec!["signal".to_string()],
+            allowed_launch_hours_utc: vec![12],
+            min_bonding_curve_progress_pct: 10.0,
+            ..Default::default()
+        };
+        let filters = |ev: &TokenEvent| {
+            ev.filter_rejections(&config)
+                .into_iter()
+                .map(|r| r.filter)
+                .collect::<Vec<_>>()
+        };
+
+        // Missing data skips the filters for it but fails the required-fields check
+        ev.missing_fields = vec!["market_cap".to_string(), "holders".to_string()];
+        assert_eq!(filters(&ev), ["missing_data", "source"]);
+
+        ev.missing_fields.clear();
+        ev.source = "signal".to_string();
+        ev.is_dev_known_rugger = true;
+        ev.blacklisted = true;
+        ev.market_cap_usd = 1_000.0;
+        ev.holders = 1;
+        ev.dev_hold_pct = 50.0;
+        ev.upgradeable = true;
+        ev.freeze_authority = true;
+        ev.launched_at = Some(3 * 3600);
+        ev.round_trip_tax_pct = Some(20.0);
+        ev.bonding_curve_progress_pct = Some(5.0);
+        assert_eq!(
+            filters(&ev),
+            [
+                "known_rugger",
+                "blacklisted",
+                "market_cap",
+                "holders",
+                "dev_hold",
+                "upgradeable",
+                "freeze_authority",
+                "launch_hour",
+                "tax",
+                "bonding_curve_progress",
+            ]
+        );
+
+        ev.is_dev_known_rugger = false;
+        ev.blacklisted = false;
+        ev.market_cap_usd = 20_000.0;
+        ev.holders = 50;
+        ev.dev_hold_pct = 5.0;
+        ev.upgradeable = false;
+        ev.freeze_authority = false;
+        ev.launched_at = Some(12 * 3600);
+        ev.round_trip_tax_pct = Some(1.0);
+        ev.bonding_curve_progress_pct = Some(40.0);
+        assert!(filters(&ev).is_empty());
+        assert!(!decide_with_score(&ev, &config, 0.0).should_buy);
+    }
+
+    #[test]
+    fn scores_bonding_curve_progress_in_the_breakdown() {
+        let mut ev: TokenEvent = PumpFunListing::default().into();
+        let mut config = StrategyConfig::default();
+        let points = |ev: &TokenEvent, config: &StrategyConfig| {
+            ev.score_breakdown(config)
+                .into_iter()
+                .find(|(name, _)| *name == "bonding_curve_progress")
+                .map(|(_, points)| points)
+        };
+        assert_eq!(points(&ev, &config), None);
+        ev.bonding_curve_progress_pct = Some(40.0);
+        assert_eq!(points(&ev, &config), Some(0.0));
+        config.bonding_curve_progress_bonus = 20.0;
+        assert_eq!(points(&ev, &config), Some(8.0));
+    }
+
+    #[test]
+    fn creator_initial_buy_falls_back_to_the_listing_and_is_windowed() {
+        // 20M of the fixed 1B supply
+        let mut ev: TokenEvent = PumpFunListing {
+            initial_buy: Some("20000000".to_string()),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(ev.dev_initial_buy_pct, Some(2.0));
+
+        let mut config = StrategyConfig::default();
+        let rejected = |ev: &TokenEvent, config: &StrategyConfig| {
+            ev.filter_rejections(config)
+                .iter()
+                .any(|r| r.filter == "dev_initial_buy")
+        };
+        assert!(!rejected(&ev, &config));
+        config.min_dev_initial_buy_pct = 5.0;
+        assert!(rejected(&ev, &config));
+        config.min_dev_initial_buy_pct = 0.0;
+        config.max_dev_initial_buy_pct = 1.0;
+        assert!(rejected(&ev, &config));
+
+        // Unknown creator buys pass
+        ev.dev_initial_buy_pct = None;
+        assert!(!rejected(&ev, &config));
+    }
+
+    #[test]
+    fn stops_and_targets_measure_from_the_entry_price() {
+        let config = StrategyConfig::default();
+        let exit_at = |price_usd| {
+            let live = LiveTokenState {
+                price_usd,
+                ..Default::default()
+            };
+            let decision = should_exit(&live, 2.0, 0.0, &config);
+            decision.should_exit.then_some(decision.reason)
+        };
+        assert_eq!(exit_at(1.5).as_deref(), Some("stop_loss"));
+        assert_eq!(exit_at(1.7), None);
+        assert_eq!(exit_at(3.2).as_deref(), Some("profit_target"));
+        // Past the top of the profit band the position rides on
+        assert_eq!(exit_at(4.5), None);
+        // No price observed yet
+        assert_eq!(exit_at(0.0), None);
+    }
+
+    #[test]
+    fn exits_on_the_positions_own_live_state() {
+        let config = StrategyConfig::default();
+        let mut live = LiveTokenState {
+            price_usd: 1.0,
+            liquidity_usd: 1_000.0,
+            ..Default::default()
+        };
+        let pair = |dex_id: &str, price_usd, liquidity_usd| DexPairInfo {
+            dex_id: Some(dex_id.to_string()),
+            liquidity_usd,
+            price_usd,
+            volume_m5_usd: Some(500.0),
+            market_cap_usd: None,
+        };
+
+        // Fields the pair lacks keep their last value
+        live.update_from_pair(&pair("pumpfun", Some(1.1), None));
+        assert_eq!((live.price_usd, live.liquidity_usd), (1.1, 1_000.0));
+        assert!(live.updated_at.is_some());
+        assert!(!should_exit(&live, 1.0, 1_000.0, &config).should_exit);
+
+        live.update_from_pair(&pair("pumpfun", None, Some(2_500.0)));
+        assert_eq!(live.price_usd, 1.1);
+        assert_eq!(should_exit(&live, 1.0, 1_000.0, &config).reason, "lp_spike");
+
+        live.update_from_pair(&pair("meteora", Some(1.1), Some(1_000.0)));
+        assert_eq!(
+            should_exit(&live, 1.0, 1_000.0, &config).reason,
+            "graduation"
+        );
+        live.update_from_pair(&pair("raydium", Some(1.1), Some(1_000.0)));
+        assert_eq!(should_exit(&live, 1.0, 1_000.0, &config).reason, "lp_spike");
+    }
+
+    #[test]
+    fn token_age_window_has_no_maximum_by_default() {
+        let mut ev: TokenEvent = PumpFunListing::default().into();
+        let mut config = StrategyConfig {
+            min_token_age_secs: 30,
+            ..Default::default()
+        };
+        let age_rejection = |ev: &TokenEvent, config: &StrategyConfig| {
+            ev.filter_rejections(config)
+                .into_iter()
+                .find(|r| r.filter == "token_age")
+                .map(|r| r.detail)
+        };
+
+        // Unknown age is never held against a token
+        assert_eq!(age_rejection(&ev, &config), None);
+
+        ev.token_age_secs = Some(10);
+        assert_eq!(
+            age_rejection(&ev, &config).as_deref(),
+            Some("10s below the 30s minimum")
+        );
+        ev.token_age_secs = Some(30 * 86_400);
+        assert_eq!(age_rejection(&ev, &config), None);
+
+        config.max_token_age_secs = 300;
+        assert_eq!(
+            age_rejection(&ev, &config).as_deref(),
+            Some("2592000s above the 300s maximum")
+        );
+    }
+}
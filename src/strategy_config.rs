@@ -1,7 +1,20 @@
-/// Centralized configuration for all trading strategy parameters
-/// All filter thresholds and trading rules are defined here for easy tuning
+//! Centralized configuration for all trading strategy parameters
+//! All filter thresholds and trading rules are defined here; named presets live in
+//! `profiles.toml` and override these defaults
 
-#[derive(Debug, Clone)]
+use crate::strategy::{ENRICHMENT_FIELDS, SCORE_FACTORS};
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BUILTIN_PROFILES: &str = include_str!("../profiles.toml");
+
+/// Smallest entry worth sending; anything less is dust after fees
+pub const MIN_ENTRY_SOL: f64 = 0.01;
+
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct StrategyConfig {
     // === ENTRY FILTERS ===
     /// Minimum market cap in USD to consider buying
@@ -28,10 +41,59 @@ pub struct StrategyConfig {
     /// Minimum score required to buy (0-100)
     pub min_score_to_buy: f64,
 
-    /// Require momentum flag (liquidity > threshold)
+    /// Require momentum (see `momentum_window_secs`) or graduation
     pub require_momentum_or_graduation: bool,
 
+    /// Seconds of the trade stream, up to the last trade seen, that momentum is measured over
+    pub momentum_window_secs: u64,
+
+    /// Net SOL (buys less sells) that must flow in over the window for momentum
+    pub momentum_min_net_inflow_sol: f64,
+
+    /// Distinct wallets that must buy over the window for momentum
+    pub momentum_min_buyers: u32,
+
+    /// Minimum token age in seconds, measured from the mint's first on-chain signature
+    pub min_token_age_secs: u64,
+
+    /// Maximum token age in seconds, measured from the mint's first on-chain signature (0 = no
+    /// limit)
+    pub max_token_age_secs: u64,
+
+    /// Minimum percentage of supply the creator bought at launch
+    pub min_dev_initial_buy_pct: f64,
+
+    /// Maximum percentage of supply the creator bought at launch
+    pub max_dev_initial_buy_pct: f64,
+
+    /// Minimum bonding curve progress toward graduation (e.g., 40.0 = 40%)
+    pub min_bonding_curve_progress_pct: f64,
+
+    /// Maximum bonding curve progress toward graduation (e.g., 80.0 = 80%)
+    pub max_bonding_curve_progress_pct: f64,
+
+    /// Maximum round-trip tax (%) from transfer fees and pool taxes (e.g., 5.0 = 5%)
+    pub max_total_tax_pct: f64,
+
+    /// Enrichment fields a token must have before it can be bought (see `ENRICHMENT_FIELDS`);
+    /// missing optional fields are left out of the filters and the score instead
+    #[serde(deserialize_with = "enrichment_fields")]
+    pub required_fields: Vec<String>,
+
+    /// UTC hours (0-23) a token must have launched in to be bought; empty allows every hour
+    #[serde(deserialize_with = "utc_hours")]
+    pub allowed_launch_hours_utc: Vec<u32>,
+
+    /// Detection sources (e.g. "pumpfun", "volume_spike") a token must come from to be bought;
+    /// empty allows every source
+    pub allowed_sources: Vec<String>,
+
     // === SCORING WEIGHTS ===
+    /// Scoring factors (see `SCORE_FACTORS`) in the order they are evaluated; a factor left
+    /// out or disabled adds nothing to the score
+    #[serde(deserialize_with = "score_factors")]
+    pub score_factors: Vec<ScoreFactor>,
+
     /// Bonus points for low dev hold (< 5%)
     pub low_dev_hold_bonus: f64,
 
@@ -50,6 +112,9 @@ pub struct StrategyConfig {
     /// Graduation bonus points
     pub graduation_bonus: f64,
 
+    /// Points at a fully sold bonding curve, scaled by its progress (0 = no points)
+    pub bonding_curve_progress_bonus: f64,
+
     /// Penalty for upgradeable token
     pub upgradeable_penalty: f64,
 
@@ -69,6 +134,12 @@ pub struct StrategyConfig {
     /// Liquidity spike multiplier for exit (e.g., 2.0 = 2x increase)
     pub lp_spike_exit_multiplier: f64,
 
+    /// Watch held tokens' mint and metadata accounts for freezes, new supply, and rewrites
+    pub watch_mint: bool,
+
+    /// Sell a position when its mint changes; otherwise the change is only reported
+    pub exit_on_mint_change: bool,
+
     // === PORTFOLIO RULES ===
     /// Maximum number of concurrent positions
     pub max_positions: usize,
@@ -81,6 +152,152 @@ pub struct StrategyConfig {
 
     /// Assumed SOL/USD price for calculations
     pub sol_usd_price: f64,
+
+    /// SOL always kept back for transaction fees and token account rent
+    pub fee_reserve_sol: f64,
+
+    /// Extra SOL kept back per open position (including the one being sized) to pay its exit
+    pub exit_fee_reserve_sol: f64,
+
+    /// Cap on trading capital (free balance plus open positions); realized profits above it
+    /// are set aside instead of traded. Unset compounds without limit
+    pub compound_cap_sol: Option<f64>,
+
+    /// How the score sizes an entry from `max_sol_per_trade`: `flat` spends all of it,
+    /// `linear` scales from `min_size_fraction` of it at `min_score_to_buy` to all of it at
+    /// 100, and `tiers` spends the fraction of the highest `size_tiers` tier reached
+    #[serde(deserialize_with = "sizing_mode")]
+    pub position_sizing: String,
+
+    /// Share of `max_sol_per_trade` a `linear` entry spends at `min_score_to_buy`
+    pub min_size_fraction: f64,
+
+    /// `tiers` sizing; a score below every tier spends the lowest tier's fraction
+    #[serde(deserialize_with = "size_tiers")]
+    pub size_tiers: Vec<SizeTier>,
+
+    // === RISK LIMITS ===
+    /// Maximum total SOL committed across all open positions
+    pub max_total_sol_at_risk: f64,
+
+    /// Maximum SOL committed to tokens from the same creator wallet
+    pub max_sol_per_creator: f64,
+
+    /// Maximum SOL committed to tokens launched within the same window
+    pub max_sol_per_launch_window: f64,
+
+    /// Launch window length in seconds for launch-wave correlation
+    pub launch_window_secs: u64,
+
+    /// Pool liquidity required per unit of position, e.g. 10 for a $1,500 pool behind a $150
+    /// entry; larger entries are shrunk to fit, or skipped below the minimum entry. 0 disables
+    pub min_liquidity_multiple: f64,
+
+    /// Maximum price impact (%) of selling a graduated token's whole position back to SOL;
+    /// entries without a Jupiter sell route are always rejected
+    pub max_exit_price_impact_pct: f64,
+
+    /// Largest estimated price impact (%) of a live buy, which is what a sandwich extracts;
+    /// larger orders are shrunk to fit, or skipped below the minimum entry
+    pub max_entry_price_impact_pct: f64,
+
+    /// Live buys with a larger estimated impact (%), or into an unknown pool depth or a pool
+    /// with recent sandwiches, are sent through Jito instead of the routed relay
+    pub jito_entry_price_impact_pct: f64,
+
+    /// Skip live buys of tokens with more sandwiches than this among their last 50
+    /// transactions
+    pub max_recent_sandwiches: u32,
+
+    // === COOLDOWNS ===
+    /// Seconds to skip a token after it was rejected by the entry filters
+    pub rejection_cooldown_secs: u64,
+
+    /// Seconds to skip a token after a normal exit
+    pub exit_cooldown_secs: u64,
+
+    /// Seconds to skip a token after a stop-loss exit
+    pub stop_loss_cooldown_secs: u64,
+
+    /// Score improvement over the cooled-down score needed to re-enter early
+    pub cooldown_override_score_improvement: f64,
+
+    // === RE-ENTRY ===
+    /// Buy back exited winners that reclaim their prior high on renewed volume
+    pub enable_reentry: bool,
+
+    /// Maximum SOL to spend on a re-entry
+    pub reentry_max_sol: f64,
+
+    /// 5-minute volume must reach this multiple of the volume at exit
+    pub reentry_volume_multiplier: f64,
+
+    /// How long after an exit a token is watched for re-entry
+    pub reentry_window_secs: u64,
+
+    /// Maximum number of re-entries per token
+    pub max_reentries_per_token: u32,
+
+    // === ENTRY ORDERS ===
+    /// Wait for the price to retrace this far below the detection price before buying
+    /// (e.g., 10.0 = 10%; 0 buys at once)
+    pub entry_dip_pct: f64,
+
+    /// How long a dip order waits for its price before it is cancelled
+    pub entry_dip_window_secs: u64,
+
+    /// Equal buys a position is split into (1 buys it whole)
+    pub entry_slices: u32,
+
+    /// Seconds between the buys of a split position
+    pub entry_slice_interval_secs: u64,
+
+    /// Abort a buy whose price when it is sent is more than this (%) away from the price it
+    /// was decided at (0 disables)
+    pub max_entry_slippage_pct: f64,
+
+    /// Refresh the price before sending a buy when the one it was decided on is older than
+    /// this, in milliseconds (0 disables)
+    pub max_quote_age_ms: u64,
+
+    // === LOSING STREAKS ===
+    /// Pause entries after this many stop-losses in a row (0 disables)
+    pub pause_after_stop_losses: u32,
+
+    /// Pause entries when equity falls this far below its high within the window
+    /// (e.g., 0.15 = 15%; 0 disables)
+    pub pause_drawdown_pct: f64,
+
+    /// Rolling window in seconds for the drawdown pause
+    pub pause_window_secs: u64,
+
+    /// Seconds before paused entries resume on their own (0 waits for a manual resume)
+    pub pause_cooldown_secs: u64,
+
+    // === MARKET REGIME ===
+    /// Position size multiplier while the market is hot
+    pub hot_regime_size_multiplier: f64,
+
+    /// Position size multiplier while the market is dead (0 stands down)
+    pub dead_regime_size_multiplier: f64,
+
+    // === BACKTEST FILLS ===
+    /// Shortest delay between a backtest's buy or sell decision and its fill
+    pub min_fill_latency_ms: u64,
+
+    /// Longest fill delay; each fill waits a uniform draw between the two
+    pub max_fill_latency_ms: u64,
+
+    /// Chance (%) that each buyer recorded in a token's launch slot lands ahead of a
+    /// backtest's buy on the bonding curve (0 always fills first)
+    pub sniper_ahead_pct: f64,
+
+    /// SOL each sniper ahead spends, pushing up the bonding curve price the buy fills at
+    pub sniper_buy_sol: f64,
+
+    /// A buy whose price moved further than this (%) before it landed fails, as its
+    /// slippage limit would on-chain
+    pub sniper_max_slippage_pct: f64,
 }
 
 impl Default for StrategyConfig {
@@ -96,14 +313,32 @@ impl Default for StrategyConfig {
             reject_freeze_authority: true, // Reject tokens with freeze authority
             min_score_to_buy: 75.0,      // 75/100 minimum score
             require_momentum_or_graduation: true, // Require momentum OR graduation
+            momentum_window_secs: 30,    // Momentum over the last 30s of trades
+            momentum_min_net_inflow_sol: 1.0, // is at least 1 SOL net inflow
+            momentum_min_buyers: 3,      // from at least 3 buyers
+            min_token_age_secs: 0,       // No minimum age
+            max_token_age_secs: 0,       // No maximum age
+            min_dev_initial_buy_pct: 0.0, // No minimum creator buy
+            max_dev_initial_buy_pct: 100.0, // No maximum creator buy
+            min_bonding_curve_progress_pct: 0.0, // Brand new curves allowed
+            max_bonding_curve_progress_pct: 100.0, // Up to graduation
+            max_total_tax_pct: 5.0,      // 5% max lost to taxes buying and selling
+            // Reject rather than trust zeros for the dev bag and authorities
+            required_fields: ["market_cap", "holders", "dev_hold", "authorities"]
+                .map(String::from)
+                .to_vec(),
+            allowed_launch_hours_utc: Vec::new(), // Trade around the clock
+            allowed_sources: Vec::new(),          // Trade every source
 
             // === SCORING WEIGHTS ===
+            score_factors: ScoreFactor::defaults(),
             low_dev_hold_bonus: 10.0, // +10 points for dev hold < 5%
             high_dev_hold_penalty_multiplier: 4.0, // -4 points per % above 10%
             liquidity_bonus_divisor: 1_000.0, // liquidity_usd / 1000 = bonus (max 25)
             market_cap_sweet_spot_bonus: 15.0, // +15 points for $50k-$250k range
             momentum_bonus: 20.0,     // +20 points for momentum
             graduation_bonus: 25.0,   // +25 points for graduation
+            bonding_curve_progress_bonus: 0.0, // Curve progress shown but not scored
             upgradeable_penalty: 20.0, // -20 points if upgradeable
             freeze_authority_penalty: 15.0, // -15 points if freeze authority
 
@@ -112,50 +347,440 @@ impl Default for StrategyConfig {
             min_profit_target_pct: 0.5,    // +50% minimum profit target
             max_profit_target_pct: 1.0,    // +100% maximum profit target
             lp_spike_exit_multiplier: 2.0, // Exit if liquidity 2x
+            watch_mint: true,              // Check held mints every exit pass
+            exit_on_mint_change: true,     // and sell on a freeze, mint, or rewrite
 
             // === PORTFOLIO RULES ===
-            max_positions: 5,          // Max 5 concurrent positions
-            max_sol_per_trade: 0.5,    // 0.5 SOL per trade
-            starting_sol_balance: 3.0, // Start with 3 SOL
-            sol_usd_price: 30.0,       // Assume $30/SOL
+            max_positions: 5,            // Max 5 concurrent positions
+            max_sol_per_trade: 0.5,      // 0.5 SOL per trade
+            starting_sol_balance: 3.0,   // Start with 3 SOL
+            sol_usd_price: 30.0,         // Assume $30/SOL
+            fee_reserve_sol: 0.02,       // Fees and ATA rent (~0.002 SOL per account)
+            exit_fee_reserve_sol: 0.003, // One sell transaction with priority fee
+            compound_cap_sol: None,      // Roll all profits back into trading
+            // Every entry at max_sol_per_trade; linear sizing would start at half of it
+            position_sizing: "flat".to_string(),
+            min_size_fraction: 0.5,
+            size_tiers: SizeTier::defaults(),
+
+            // === RISK LIMITS ===
+            max_total_sol_at_risk: 2.5, // At most 2.5 SOL deployed at once
+            max_sol_per_creator: 0.5,   // One full position per creator
+            max_sol_per_launch_window: 1.0, // Two full positions per launch wave
+            launch_window_secs: 600,    // 10-minute launch waves
+            min_liquidity_multiple: 10.0, // Pool at least 10x the position
+            max_exit_price_impact_pct: 10.0, // Exit must cost at most 10% in impact
+            max_entry_price_impact_pct: 3.0, // Shrink buys that would move the pool over 3%
+            jito_entry_price_impact_pct: 1.0, // Protect buys moving it over 1%
+            max_recent_sandwiches: 2,        // Sandwich bots are working this pool
+
+            // === COOLDOWNS ===
+            rejection_cooldown_secs: 300, // 5 minutes after a rejection
+            exit_cooldown_secs: 1_800,    // 30 minutes after an exit
+            stop_loss_cooldown_secs: 3_600, // 1 hour after a stop loss
+            cooldown_override_score_improvement: 15.0, // +15 score to re-enter early
+
+            // === RE-ENTRY ===
+            enable_reentry: false,          // Off unless opted in
+            reentry_max_sol: 0.25,          // Half-size re-entries
+            reentry_volume_multiplier: 1.5, // 5m volume 1.5x the volume at exit
+            reentry_window_secs: 3_600,     // Watch exited winners for 1 hour
+            max_reentries_per_token: 1,     // Buy back at most once
+
+            // === ENTRY ORDERS ===
+            entry_dip_pct: 0.0,            // Buy on the decision
+            entry_dip_window_secs: 300,    // Dip orders wait 5 minutes
+            entry_slices: 1,               // in one buy
+            entry_slice_interval_secs: 30, // Slices 30 seconds apart
+            max_entry_slippage_pct: 0.0,   // Buy at whatever the price has become
+            max_quote_age_ms: 0,           // and however old the price is
+
+            // === LOSING STREAKS ===
+            pause_after_stop_losses: 0, // Off unless opted in
+            pause_drawdown_pct: 0.0,    // Off unless opted in
+            pause_window_secs: 3_600,   // Drawdown measured over the last hour
+            pause_cooldown_secs: 1_800, // Resume after 30 minutes
+
+            // === MARKET REGIME ===
+            hot_regime_size_multiplier: 1.0,  // Same size in every regime
+            dead_regime_size_multiplier: 1.0, // unless opted in
+
+            // === BACKTEST FILLS ===
+            min_fill_latency_ms: 0,        // Instant fills
+            max_fill_latency_ms: 0,        // at the decision price
+            sniper_ahead_pct: 0.0,         // First in every launch slot
+            sniper_buy_sol: 1.0,           // Snipers buy 1 SOL each
+            sniper_max_slippage_pct: 10.0, // The executor's slippage limit
         }
     }
 }
 
+/// One step of the scoring pipeline: a factor and the weight its points are multiplied by
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScoreFactor {
+    pub name: String,
+    #[serde(default = "full_weight")]
+    pub weight: f64,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+impl ScoreFactor {
+    /// Every factor in `SCORE_FACTORS`, in order, at full weight
+    pub fn defaults() -> Vec<Self> {
+        SCORE_FACTORS
+            .iter()
+            .map(|(name, _)| Self {
+                name: name.to_string(),
+                weight: full_weight(),
+                enabled: enabled(),
+            })
+            .collect()
+    }
+}
+
+/// A score from which `tiers` sizing spends `fraction` of `max_sol_per_trade`
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SizeTier {
+    pub min_score: f64,
+    pub fraction: f64,
+}
+
+impl SizeTier {
+    /// Half size from the default minimum score, three quarters from 85, full from 95
+    pub fn defaults() -> Vec<Self> {
+        [(75.0, 0.5), (85.0, 0.75), (95.0, 1.0)]
+            .into_iter()
+            .map(|(min_score, fraction)| Self {
+                min_score,
+                fraction,
+            })
+            .collect()
+    }
+}
+
+fn full_weight() -> f64 {
+    1.0
+}
+
+fn enabled() -> bool {
+    true
+}
+
 impl StrategyConfig {
-    /// Create config optimized for early sniping (catching tokens right at launch)
-    pub fn early_snipe() -> Self {
-        Self {
-            min_market_cap_usd: 1_000.0, // $1k minimum - catch very early
-            min_holders: 5,              // Only 5 holders needed
-            min_liquidity_usd: 500.0,    // $500 minimum liquidity
-            min_score_to_buy: 65.0,      // Lower score threshold
-            ..Default::default()
+    /// Look up a built-in profile by name
+    pub fn preset(name: &str) -> Option<Self> {
+        Profiles::builtin().get(name)
+    }
+
+    /// SOL held back for fees while `open_positions` positions plus a new one are open
+    pub fn fee_reserve(&self, open_positions: usize) -> f64 {
+        self.fee_reserve_sol + self.exit_fee_reserve_sol * (open_positions + 1) as f64
+    }
+
+    /// SOL a new entry may spend without dipping into the fee reserve
+    pub fn spendable_sol(&self, sol_balance: f64, open_positions: usize) -> f64 {
+        (sol_balance - self.fee_reserve(open_positions)).max(0.0)
+    }
+
+    /// How far (%) `send_price` moved from `decided_price`, when that is past
+    /// `max_entry_slippage_pct` and the buy should be aborted
+    pub fn entry_slippage_breach(&self, decided_price: f64, send_price: f64) -> Option<f64> {
+        if self.max_entry_slippage_pct <= 0.0 || decided_price <= 0.0 {
+            return None;
         }
+        let moved_pct = (send_price / decided_price - 1.0) * 100.0;
+        (moved_pct.abs() > self.max_entry_slippage_pct).then_some(moved_pct)
     }
 
-    /// Create config optimized for safer, established tokens
-    pub fn conservative() -> Self {
-        Self {
-            min_market_cap_usd: 50_000.0, // $50k minimum
-            min_holders: 200,             // 200 holders minimum
-            max_dev_hold_pct: 10.0,       // Stricter 10% max
-            min_liquidity_usd: 5_000.0,   // $5k minimum liquidity
-            min_score_to_buy: 80.0,       // Higher score threshold
-            ..Default::default()
+    /// Whether a price observed at `priced_at_ms` is too old to buy on at `now_ms`; a price
+    /// of unknown age is, once `max_quote_age_ms` is set
+    pub fn quote_is_stale(&self, priced_at_ms: Option<i64>, now_ms: i64) -> bool {
+        self.max_quote_age_ms > 0
+            && priced_at_ms.is_none_or(|at| now_ms - at > self.max_quote_age_ms as i64)
+    }
+
+    /// Share of `max_sol_per_trade` an entry scoring `score` spends under `position_sizing`
+    pub fn size_fraction(&self, score: f64) -> f64 {
+        match self.position_sizing.as_str() {
+            "linear" => {
+                let span = 100.0 - self.min_score_to_buy;
+                let min = self.min_size_fraction.clamp(0.0, 1.0);
+                if span <= 0.0 {
+                    return 1.0;
+                }
+                let t = ((score - self.min_score_to_buy) / span).clamp(0.0, 1.0);
+                min + (1.0 - min) * t
+            }
+            "tiers" => {
+                let by_score = |a: &&SizeTier, b: &&SizeTier| a.min_score.total_cmp(&b.min_score);
+                self.size_tiers
+                    .iter()
+                    .filter(|t| score >= t.min_score)
+                    .max_by(by_score)
+                    .or_else(|| self.size_tiers.iter().min_by(by_score))
+                    .map_or(1.0, |t| t.fraction)
+            }
+            _ => 1.0,
         }
     }
 
-    /// Create config optimized for aggressive trading
-    pub fn aggressive() -> Self {
-        Self {
-            min_market_cap_usd: 2_000.0, // $2k minimum
-            min_holders: 3,              // Only 3 holders
-            max_dev_hold_pct: 20.0,      // Allow higher dev hold
-            min_liquidity_usd: 300.0,    // $300 minimum
-            min_score_to_buy: 60.0,      // Lower threshold
-            max_positions: 10,           // More positions
-            ..Default::default()
+    /// Largest entry (SOL) `min_liquidity_multiple` allows into a pool of `liquidity_usd`;
+    /// unlimited when the multiple is off or the liquidity unknown
+    pub fn max_sol_for_liquidity(&self, liquidity_usd: f64) -> f64 {
+        if self.min_liquidity_multiple <= 0.0 || liquidity_usd <= 0.0 {
+            return f64::INFINITY;
         }
+        liquidity_usd / self.min_liquidity_multiple / self.sol_usd_price
+    }
+
+    /// Override one parameter by field name, e.g. `set("min_score_to_buy", "80")`
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let serde_json::Value::Object(mut fields) = serde_json::to_value(&*self)? else {
+            unreachable!("StrategyConfig serializes to an object");
+        };
+        if !fields.contains_key(key) {
+            bail!("unknown strategy parameter '{}'", key);
+        }
+        let value = serde_json::from_str(value)
+            .map_err(|_| anyhow!("invalid value '{}' for {}", value, key))?;
+        fields.insert(key.to_string(), value);
+        *self = serde_json::from_value(fields.into())
+            .map_err(|e| anyhow!("invalid value for {}: {}", key, e))?;
+        Ok(())
+    }
+}
+
+/// Deserialize a list of enrichment field names, rejecting unknown ones
+fn enrichment_fields<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    let fields = Vec::<String>::deserialize(d)?;
+    if let Some(unknown) = fields
+        .iter()
+        .find(|f| !ENRICHMENT_FIELDS.contains(&f.as_str()))
+    {
+        return Err(serde::de::Error::custom(format!(
+            "unknown enrichment field '{}' (expected one of {})",
+            unknown,
+            ENRICHMENT_FIELDS.join(", ")
+        )));
+    }
+    Ok(fields)
+}
+
+/// Deserialize the scoring pipeline, rejecting unknown and repeated factors
+fn score_factors<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<ScoreFactor>, D::Error> {
+    let factors = Vec::<ScoreFactor>::deserialize(d)?;
+    for (i, factor) in factors.iter().enumerate() {
+        if !SCORE_FACTORS.iter().any(|(name, _)| *name == factor.name) {
+            return Err(serde::de::Error::custom(format!(
+                "unknown score factor '{}' (expected one of {})",
+                factor.name,
+                SCORE_FACTORS.map(|(name, _)| name).join(", ")
+            )));
+        }
+        if factors[..i].iter().any(|f| f.name == factor.name) {
+            return Err(serde::de::Error::custom(format!(
+                "score factor '{}' is listed twice",
+                factor.name
+            )));
+        }
+    }
+    Ok(factors)
+}
+
+/// Deserialize a `position_sizing` mode, rejecting unknown ones
+fn sizing_mode<'de, D: serde::Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    let mode = String::deserialize(d)?;
+    if !["flat", "linear", "tiers"].contains(&mode.as_str()) {
+        return Err(serde::de::Error::custom(format!(
+            "unknown position sizing '{}' (expected flat, linear, or tiers)",
+            mode
+        )));
+    }
+    Ok(mode)
+}
+
+/// Deserialize sizing tiers, rejecting fractions outside 0-1
+fn size_tiers<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<SizeTier>, D::Error> {
+    let tiers = Vec::<SizeTier>::deserialize(d)?;
+    if let Some(tier) = tiers.iter().find(|t| !(0.0..=1.0).contains(&t.fraction)) {
+        return Err(serde::de::Error::custom(format!(
+            "size tier fraction {} is outside 0-1",
+            tier.fraction
+        )));
+    }
+    Ok(tiers)
+}
+
+/// Deserialize a list of UTC hours, rejecting any outside 0-23
+fn utc_hours<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<u32>, D::Error> {
+    let hours = Vec::<u32>::deserialize(d)?;
+    if let Some(hour) = hours.iter().find(|h| **h > 23) {
+        return Err(serde::de::Error::custom(format!(
+            "hour {} is not a UTC hour (0-23)",
+            hour
+        )));
+    }
+    Ok(hours)
+}
+
+/// Named strategy configs loaded from the `[profiles.<name>]` tables of a TOML file
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profiles {
+    profiles: BTreeMap<String, StrategyConfig>,
+}
+
+impl Profiles {
+    /// The profiles shipped in `profiles.toml`
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_PROFILES).expect("built-in profiles.toml is valid")
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read profiles {}: {}", path, e))?;
+        Self::from_toml(&text).map_err(|e| anyhow!("invalid profiles {}: {}", path, e))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<StrategyConfig> {
+        self.profiles.get(name).cloned()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_override_only_listed_fields() {
+        let profiles = Profiles::from_toml(
+            r#"
+            [profiles.tight]
+            min_score_to_buy = 90.0
+            max_positions = 2
+            "#,
+        )
+        .unwrap();
+        let tight = profiles.get("tight").unwrap();
+        assert_eq!(tight.min_score_to_buy, 90.0);
+        assert_eq!(tight.max_positions, 2);
+        assert_eq!(tight.stop_loss_pct, StrategyConfig::default().stop_loss_pct);
+
+        assert!(Profiles::from_toml("[profiles.typo]\nmin_scor_to_buy = 1.0").is_err());
+    }
+
+    #[test]
+    fn sizing_leaves_exit_fees_for_every_position() {
+        let config = StrategyConfig::default();
+        // 0.02 base + 0.003 for each of 4 open positions and the new one
+        assert!((config.spendable_sol(0.5, 4) - 0.465).abs() < 1e-9);
+        assert_eq!(config.spendable_sol(0.03, 4), 0.0);
+    }
+
+    #[test]
+    fn sizes_entries_by_score() {
+        let mut config = StrategyConfig::default();
+        assert_eq!(config.size_fraction(75.0), 1.0);
+
+        // Half size at the minimum score of 75, full size at 100
+        config.set("position_sizing", r#""linear""#).unwrap();
+        assert_eq!(config.size_fraction(75.0), 0.5);
+        assert_eq!(config.size_fraction(87.5), 0.75);
+        assert_eq!(config.size_fraction(120.0), 1.0);
+
+        config.set("position_sizing", r#""tiers""#).unwrap();
+        assert_eq!(config.size_fraction(70.0), 0.5);
+        assert_eq!(config.size_fraction(90.0), 0.75);
+        assert_eq!(config.size_fraction(95.0), 1.0);
+
+        assert!(config.set("position_sizing", r#""kelly""#).is_err());
+        let oversized = r#"[{"min_score":80,"fraction":1.5}]"#;
+        assert!(config.set("size_tiers", oversized).is_err());
+    }
+
+    #[test]
+    fn aborts_entries_past_the_slippage_limit() {
+        let mut config = StrategyConfig::default();
+        assert_eq!(config.entry_slippage_breach(1.0, 2.0), None);
+
+        config.max_entry_slippage_pct = 25.0;
+        assert_eq!(config.entry_slippage_breach(1.0, 1.2), None);
+        assert_eq!(config.entry_slippage_breach(1.0, 2.0), Some(100.0));
+        assert_eq!(config.entry_slippage_breach(1.0, 0.5), Some(-50.0));
+        assert_eq!(config.entry_slippage_breach(0.0, 2.0), None);
+    }
+
+    #[test]
+    fn flags_quotes_older_than_the_limit() {
+        let mut config = StrategyConfig::default();
+        assert!(!config.quote_is_stale(None, 10_000));
+
+        config.max_quote_age_ms = 500;
+        assert!(!config.quote_is_stale(Some(9_500), 10_000));
+        assert!(config.quote_is_stale(Some(9_400), 10_000));
+        assert!(config.quote_is_stale(None, 10_000));
+    }
+
+    #[test]
+    fn sets_parameters_by_name() {
+        let mut config = StrategyConfig::default();
+        config.set("min_score_to_buy", "80").unwrap();
+        config.set("max_positions", "3").unwrap();
+        config.set("enable_reentry", "true").unwrap();
+        assert_eq!(config.min_score_to_buy, 80.0);
+        assert_eq!(config.max_positions, 3);
+        assert!(config.enable_reentry);
+
+        assert!(config.set("max_positionz", "3").is_err());
+        assert!(config.set("max_positions", "lots").is_err());
+        assert!(config.set("max_positions", "-1").is_err());
+
+        config.set("required_fields", r#"["liquidity"]"#).unwrap();
+        assert_eq!(config.required_fields, ["liquidity"]);
+        assert!(config.set("required_fields", r#"["liquidty"]"#).is_err());
+
+        config.set("allowed_launch_hours_utc", "[22, 23, 0]").unwrap();
+        assert_eq!(config.allowed_launch_hours_utc, [22, 23, 0]);
+        assert!(config.set("allowed_launch_hours_utc", "[24]").is_err());
+
+        config
+            .set(
+                "score_factors",
+                r#"[{"name":"momentum","weight":2.0},{"name":"holders","enabled":false}]"#,
+            )
+            .unwrap();
+        assert_eq!(config.score_factors[0].weight, 2.0);
+        assert!(config.score_factors[0].enabled && !config.score_factors[1].enabled);
+        assert!(config.set("score_factors", r#"[{"name":"hype"}]"#).is_err());
+        let twice = r#"[{"name":"momentum"},{"name":"momentum"}]"#;
+        assert!(config.set("score_factors", twice).is_err());
+    }
+
+    #[test]
+    fn builtin_profiles_parse() {
+        let profiles = Profiles::builtin();
+        assert_eq!(
+            profiles.names().collect::<Vec<_>>(),
+            [
+                "aggressive",
+                "conservative",
+                "default",
+                "early_snipe",
+                "second_chance"
+            ]
+        );
+        assert_eq!(profiles.get("early_snipe").unwrap().max_token_age_secs, 300);
     }
 }
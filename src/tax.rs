@@ -0,0 +1,100 @@
+//! Taxes a token charges on every buy and sell, which come straight off the profit target
+//!
+//! Token-2022 mints can carry a transfer fee, read from the mint account with the other
+//! authorities. Graduated tokens can also be taxed by their pool, which the mint doesn't
+//! show, so enrichment quotes a `PROBE_LAMPORTS` buy on Jupiter and a sale of what it
+//! returns. What the round trip loses beyond both price impacts is the tax; it includes the
+//! pools' own swap fees, so an untaxed token measures about half a percent. A 10% tax each
+//! way turns a +50% move into a +21% exit, so `max_total_tax_pct` rejects taxed tokens.
+
+use crate::models::RoundTrip;
+use crate::scanner::TokenDataProvider;
+
+/// SOL quoted for the probe buy, small enough to leave price impact negligible
+pub const PROBE_LAMPORTS: u64 = 100_000_000;
+
+/// Quote buying `mint` for `PROBE_LAMPORTS` and selling the tokens straight back; `None`
+/// when either leg has no route
+pub async fn probe<P: TokenDataProvider>(scanner: &P, mint: &str) -> Option<RoundTrip> {
+    let buy = scanner
+        .query_buy_route(mint, PROBE_LAMPORTS)
+        .await
+        .ok()
+        .flatten()?;
+    let sell = scanner
+        .query_sell_route(mint, buy.out_lamports)
+        .await
+        .ok()
+        .flatten()?;
+    Some(RoundTrip {
+        in_lamports: PROBE_LAMPORTS,
+        out_lamports: sell.out_lamports,
+        price_impact_pct: buy.price_impact_pct + sell.price_impact_pct,
+    })
+}
+
+/// Share of a buy (%) a round trip loses to taxes: the transfer fee taken on the buy and
+/// again on the sale, or what the Jupiter round trip measured, whichever is higher
+pub fn round_trip_tax_pct(
+    transfer_fee_bps: Option<u16>,
+    round_trip: Option<&RoundTrip>,
+) -> Option<f64> {
+    let fee = transfer_fee_bps.map(|bps| {
+        let kept = 1.0 - bps as f64 / 10_000.0;
+        (1.0 - kept * kept) * 100.0
+    });
+    let measured = round_trip.filter(|r| r.in_lamports > 0).map(|r| {
+        let lost = (1.0 - r.out_lamports as f64 / r.in_lamports as f64) * 100.0;
+        (lost - r.price_impact_pct).max(0.0)
+    });
+    match (fee, measured) {
+        (Some(fee), Some(measured)) => Some(fee.max(measured)),
+        (fee, measured) => fee.or(measured),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner;
+
+    #[test]
+    fn combines_transfer_fees_and_measured_taxes() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":{"value":{
+            "owner":"TokenzQdBNbLqP5VEhdkAS6EPFLC1PhnBqCXEpPxuEb",
+            "data":{"parsed":{"info":{
+                "decimals":6,"mintAuthority":null,"freezeAuthority":null,"supply":"1000000",
+                "extensions":[
+                    {"extension":"immutableOwner"},
+                    {"extension":"transferFeeConfig","state":{
+                        "newerTransferFee":{"epoch":700,"maximumFee":1000000,"transferFeeBasisPoints":1000},
+                        "olderTransferFee":{"epoch":600,"maximumFee":1000000,"transferFeeBasisPoints":500}
+                    }}
+                ]
+            }}}
+        }}}"#;
+        let mint = scanner::parse_mint_account(body, "Taxed").unwrap().unwrap();
+        assert_eq!(mint.transfer_fee_bps, Some(1000));
+
+        // 10% each way keeps 81% of the buy
+        let fee = round_trip_tax_pct(mint.transfer_fee_bps, None).unwrap();
+        assert!((fee - 19.0).abs() < 1e-9);
+        assert_eq!(round_trip_tax_pct(Some(0), None), Some(0.0));
+        assert_eq!(round_trip_tax_pct(None, None), None);
+
+        // 12% lost, 2% of it to price impact
+        let taxed = RoundTrip {
+            in_lamports: 100,
+            out_lamports: 88,
+            price_impact_pct: 2.0,
+        };
+        let measured = round_trip_tax_pct(Some(0), Some(&taxed)).unwrap();
+        assert!((measured - 10.0).abs() < 1e-9);
+        assert!((round_trip_tax_pct(Some(1000), Some(&taxed)).unwrap() - 19.0).abs() < 1e-9);
+        let gained = RoundTrip {
+            out_lamports: 101,
+            ..taxed
+        };
+        assert_eq!(round_trip_tax_pct(None, Some(&gained)), Some(0.0));
+    }
+}
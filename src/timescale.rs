@@ -0,0 +1,222 @@
+//! Optional TimescaleDB storage for the time-series tables
+//!
+//! With the extension available (`TIMESCALE=auto`, the default) or required (`on`),
+//! `price_history` and `position_marks` become hypertables in one-day chunks. Chunks older
+//! than `TIMESCALE_COMPRESS_AFTER_HOURS` are compressed per token. One-minute candles of
+//! both tables, `price_candles_1m` and `mark_candles_1m`, are kept as continuous aggregates.
+//! They include rows not materialized yet, so they always match the raw tables.
+//!
+//! Converting is done once, when a table isn't a hypertable yet. It copies existing rows
+//! into chunks and locks the table while it does.
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::str::FromStr;
+
+/// Whether the time-series tables are stored in TimescaleDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Use TimescaleDB when the server has the extension
+    Auto,
+    /// Refuse to start without TimescaleDB
+    On,
+    Off,
+}
+
+impl FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Mode::Auto),
+            "on" | "true" => Ok(Mode::On),
+            "off" | "false" => Ok(Mode::Off),
+            other => bail!(
+                "unknown TimescaleDB mode '{}' (expected auto, on, or off)",
+                other
+            ),
+        }
+    }
+}
+
+/// A table converted to a hypertable
+struct Hypertable {
+    name: &'static str,
+    time_column: &'static str,
+    /// Columns compressed chunks are grouped by
+    segment_by: &'static str,
+    /// Run before converting, so the table meets hypertable constraints
+    prepare: &'static [&'static str],
+}
+
+const HYPERTABLES: [Hypertable; 2] = [
+    Hypertable {
+        name: "price_history",
+        time_column: "observed_at",
+        segment_by: "token_id",
+        prepare: &[],
+    },
+    Hypertable {
+        name: "position_marks",
+        time_column: "marked_at",
+        segment_by: "token_id, strategy_id",
+        // Unique indexes of a hypertable must include its time column
+        prepare: &[
+            "DELETE FROM position_marks WHERE marked_at IS NULL",
+            "ALTER TABLE position_marks ALTER COLUMN marked_at SET NOT NULL",
+            "ALTER TABLE position_marks DROP CONSTRAINT IF EXISTS position_marks_pkey",
+            "ALTER TABLE position_marks ADD PRIMARY KEY (id, marked_at)",
+        ],
+    },
+];
+
+const CONTINUOUS_AGGREGATES: [(&str, &str); 2] = [
+    (
+        "price_candles_1m",
+        "SELECT token_id, time_bucket(INTERVAL '1 minute', observed_at) AS bucket, \
+         first(price_usd, observed_at) AS open, MAX(price_usd) AS high, \
+         MIN(price_usd) AS low, last(price_usd, observed_at) AS close, COUNT(*) AS samples \
+         FROM price_history GROUP BY token_id, bucket",
+    ),
+    (
+        "mark_candles_1m",
+        "SELECT token_id, strategy_id, time_bucket(INTERVAL '1 minute', marked_at) AS bucket, \
+         MAX(price_usd) AS high, MIN(price_usd) AS low, last(price_usd, marked_at) AS close, \
+         last(unrealized_pnl, marked_at) AS unrealized_pnl, \
+         last(equity_usd, marked_at) AS equity_usd, MAX(drawdown_pct) AS drawdown_pct \
+         FROM position_marks GROUP BY token_id, strategy_id, bucket",
+    ),
+];
+
+/// Set up TimescaleDB storage as `mode` asks, after the migrations ran; returns whether the
+/// time-series tables are hypertables
+pub async fn setup(pool: &PgPool, mode: Mode, compress_after_hours: u64) -> Result<bool> {
+    if mode == Mode::Off {
+        return Ok(false);
+    }
+    let available: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'timescaledb')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !available {
+        if mode == Mode::On {
+            bail!("TIMESCALE=on but the database server doesn't have the timescaledb extension");
+        }
+        return Ok(false);
+    }
+    // Fails when the library isn't in shared_preload_libraries or the role can't create it
+    if let Err(e) = sqlx::query("CREATE EXTENSION IF NOT EXISTS timescaledb")
+        .execute(pool)
+        .await
+    {
+        if mode == Mode::On {
+            bail!("failed to enable TimescaleDB: {}", e);
+        }
+        println!("[timescale] Not using TimescaleDB: {}", e);
+        return Ok(false);
+    }
+
+    for table in &HYPERTABLES {
+        let converted: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM timescaledb_information.hypertables \
+             WHERE hypertable_schema = current_schema() AND hypertable_name = $1)",
+        )
+        .bind(table.name)
+        .fetch_one(pool)
+        .await?;
+        if converted {
+            continue;
+        }
+        println!("[timescale] Converting {} to a hypertable", table.name);
+        let mut tx = pool.begin().await?;
+        for stmt in table.prepare {
+            sqlx::query(stmt).execute(&mut *tx).await?;
+        }
+        sqlx::query(
+            "SELECT create_hypertable($1::REGCLASS, $2::NAME, \
+             chunk_time_interval => INTERVAL '1 day', migrate_data => TRUE)",
+        )
+        .bind(table.name)
+        .bind(table.time_column)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "ALTER TABLE {} SET (timescaledb.compress, \
+             timescaledb.compress_segmentby = '{}', timescaledb.compress_orderby = '{}')",
+            table.name, table.segment_by, table.time_column
+        ))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+    }
+    for table in &HYPERTABLES {
+        // Replaced, so a changed TIMESCALE_COMPRESS_AFTER_HOURS takes effect
+        sqlx::query("SELECT remove_compression_policy($1::REGCLASS, if_exists => TRUE)")
+            .bind(table.name)
+            .execute(pool)
+            .await?;
+        sqlx::query(
+            "SELECT add_compression_policy($1::REGCLASS, make_interval(hours => $2::INTEGER))",
+        )
+        .bind(table.name)
+        .bind(compress_after_hours as i32)
+        .execute(pool)
+        .await?;
+    }
+
+    for (name, query) in CONTINUOUS_AGGREGATES {
+        sqlx::query(&format!(
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS {} \
+             WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS {} \
+             WITH NO DATA",
+            name, query
+        ))
+        .execute(pool)
+        .await?;
+        // Refreshes every bucket that changed, including backfilled and archived ones
+        sqlx::query(
+            "SELECT add_continuous_aggregate_policy($1::REGCLASS, start_offset => NULL, \
+             end_offset => INTERVAL '1 minute', schedule_interval => INTERVAL '1 minute', \
+             if_not_exists => TRUE)",
+        )
+        .bind(name)
+        .execute(pool)
+        .await?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hypertables_keep_their_time_column_unique() {
+        assert_eq!("auto".parse::<Mode>().unwrap(), Mode::Auto);
+        assert_eq!("off".parse::<Mode>().unwrap(), Mode::Off);
+        assert!("yes".parse::<Mode>().is_err());
+
+        for table in &HYPERTABLES {
+            assert!(
+                table
+                    .prepare
+                    .iter()
+                    .filter(|stmt| stmt.contains("PRIMARY KEY"))
+                    .all(|stmt| stmt.contains(table.time_column))
+            );
+        }
+        for (name, query) in CONTINUOUS_AGGREGATES {
+            let table = HYPERTABLES
+                .iter()
+                .find(|t| query.contains(&format!("FROM {} ", t.name)))
+                .unwrap_or_else(|| panic!("{} reads no hypertable", name));
+            assert!(query.contains(&format!(
+                "time_bucket(INTERVAL '1 minute', {})",
+                table.time_column
+            )));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Associated token accounts opened by live buys
+//!
+//! Every buy leaves a token account holding about 0.002 SOL of rent. Accounts are recorded as
+//! `open` when a buy lands and `emptied` when the position is sold, and the executor closes
+//! emptied ones in batches to reclaim the rent. Over many snipes that rent adds up.
+
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+
+/// Most accounts looked up at once (the `getMultipleAccounts` limit)
+pub const MAX_PENDING: i64 = 100;
+
+pub async fn record_opened(pool: &PgPool, address: &str, wallet: &str, mint: &str) -> Result<()> {
+    // Buying a mint again reopens an account that was closed earlier
+    sqlx::query(
+        "INSERT INTO token_accounts (address, wallet, mint, status) VALUES ($1,$2,$3,'open') \
+         ON CONFLICT (address) DO UPDATE SET status='open', opened_at=NOW(), \
+         close_signature=NULL, rent_lamports=NULL, closed_at=NULL",
+    )
+    .bind(address)
+    .bind(wallet)
+    .bind(mint)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn record_emptied(pool: &PgPool, address: &str) -> Result<()> {
+    sqlx::query("UPDATE token_accounts SET status='emptied' WHERE address=$1 AND status='open'")
+        .bind(address)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Emptied accounts of `wallet` waiting to be closed
+pub async fn emptied(pool: &PgPool, wallet: &str) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        "SELECT address FROM token_accounts WHERE wallet=$1 AND status='emptied' \
+         ORDER BY opened_at LIMIT $2",
+    )
+    .bind(wallet)
+    .bind(MAX_PENDING)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(|r| r.get("address")).collect())
+}
+
+/// Mark an account closed; `signature` and `rent_lamports` are `None` when it was already
+/// gone on-chain
+pub async fn record_closed(
+    pool: &PgPool,
+    address: &str,
+    signature: Option<&str>,
+    rent_lamports: Option<u64>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE token_accounts SET status='closed', close_signature=$2, rent_lamports=$3, \
+         closed_at=NOW() WHERE address=$1",
+    )
+    .bind(address)
+    .bind(signature)
+    .bind(rent_lamports.map(|l| l as i64))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
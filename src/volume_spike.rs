@@ -0,0 +1,258 @@
+//! Volume spike detector for tokens that already graduated, as a second-chance entry source
+//! distinct from launch sniping
+//!
+//! Every `SPIKE_INTERVAL_SECS` the watchlist is rebuilt from `SPIKE_WATCHLIST` plus the most
+//! recently seen graduated tokens (up to `SPIKE_WATCHLIST_SIZE`, seen within
+//! `SPIKE_WATCHLIST_HOURS`). Each token's one-minute candles come from Birdeye
+//! (`BIRDEYE_API_KEY`). A token spikes when the average volume of its last few candles reaches
+//! `SPIKE_VOLUME_MULTIPLIER` times that of the candles before, while its price rose at least
+//! `SPIKE_MIN_PRICE_CHANGE_PCT` over the same candles. Spiking tokens go through the signal
+//! channel and are enriched and traded like any other token, with the `volume_spike` source.
+
+use crate::config::Config;
+use crate::signals::{ExternalSignal, SignalSender};
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+const BIRDEYE_OHLCV_URL: &str = "https://public-api.birdeye.so/defi/ohlcv";
+pub const SOURCE: &str = "volume_spike";
+/// Candles that make up the spike; the ones before them are the baseline
+const RECENT_CANDLES: usize = 3;
+const MIN_BASELINE_CANDLES: usize = 10;
+/// Minutes of candles requested per token
+const LOOKBACK_MINS: i64 = 30;
+/// Pause between Birdeye requests, within its public rate limit
+const BIRDEYE_DELAY: std::time::Duration = std::time::Duration::from_millis(1_100);
+/// A token isn't reported again this soon after a spike
+const RESIGNAL_AFTER_MINS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Candle {
+    #[serde(rename = "o")]
+    pub open: f64,
+    #[serde(rename = "c")]
+    pub close: f64,
+    #[serde(rename = "v")]
+    pub volume: f64,
+    #[serde(rename = "unixTime")]
+    pub at: i64,
+}
+
+#[derive(Deserialize)]
+struct OhlcvResponse {
+    data: Option<OhlcvData>,
+}
+
+#[derive(Deserialize)]
+struct OhlcvData {
+    #[serde(default)]
+    items: Vec<Candle>,
+}
+
+/// Parse a Birdeye OHLCV response into candles, oldest first
+pub fn parse_candles(body: &str) -> Result<Vec<Candle>> {
+    let resp: OhlcvResponse = serde_json::from_str(body)?;
+    let mut candles = resp.data.map(|d| d.items).unwrap_or_default();
+    candles.sort_by_key(|c| c.at);
+    Ok(candles)
+}
+
+/// Thresholds of the detector
+#[derive(Debug, Clone)]
+pub struct SpikeRules {
+    pub volume_multiplier: f64,
+    pub min_price_change_pct: f64,
+}
+
+impl SpikeRules {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            volume_multiplier: cfg.spike_volume_multiplier,
+            min_price_change_pct: cfg.spike_min_price_change_pct,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spike {
+    /// Average volume of the recent candles over that of the baseline
+    pub volume_multiple: f64,
+    /// Price change across the recent candles
+    pub price_change_pct: f64,
+}
+
+/// Whether the last candles spike in both volume and price
+pub fn detect(candles: &[Candle], rules: &SpikeRules) -> Option<Spike> {
+    if candles.len() < RECENT_CANDLES + MIN_BASELINE_CANDLES {
+        return None;
+    }
+    let (baseline, recent) = candles.split_at(candles.len() - RECENT_CANDLES);
+    let mean_volume = |c: &[Candle]| c.iter().map(|c| c.volume).sum::<f64>() / c.len() as f64;
+    let baseline_volume = mean_volume(baseline);
+    let start = recent[0].open;
+    if baseline_volume <= 0.0 || start <= 0.0 {
+        return None;
+    }
+    let spike = Spike {
+        volume_multiple: mean_volume(recent) / baseline_volume,
+        price_change_pct: (recent[RECENT_CANDLES - 1].close - start) / start * 100.0,
+    };
+    (spike.volume_multiple >= rules.volume_multiplier
+        && spike.price_change_pct >= rules.min_price_change_pct)
+        .then_some(spike)
+}
+
+pub struct SpikeDetector {
+    pool: PgPool,
+    client: Client,
+    birdeye_key: String,
+    rules: SpikeRules,
+    /// Mints always watched, on top of recently seen graduated tokens
+    pinned: Vec<String>,
+    watchlist_hours: u64,
+    watchlist_size: i64,
+    interval: std::time::Duration,
+    signals: SignalSender,
+    /// When each mint last spiked
+    signaled: HashMap<String, DateTime<Utc>>,
+}
+
+impl SpikeDetector {
+    pub fn new(pool: PgPool, cfg: &Config, birdeye_key: String, signals: SignalSender) -> Self {
+        Self {
+            pool,
+            client: Client::new(),
+            birdeye_key,
+            rules: SpikeRules::from_config(cfg),
+            pinned: cfg.spike_watchlist.clone(),
+            watchlist_hours: cfg.spike_watchlist_hours,
+            watchlist_size: cfg.spike_watchlist_size as i64,
+            interval: std::time::Duration::from_secs(cfg.spike_interval_secs),
+            signals,
+            signaled: HashMap::new(),
+        }
+    }
+
+    /// Scan the watchlist each interval until the process exits
+    pub async fn run(mut self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.scan().await {
+                println!("[spikes] Scan failed: {}", e);
+            }
+        }
+    }
+
+    async fn scan(&mut self) -> Result<()> {
+        let now = Utc::now();
+        self.signaled
+            .retain(|_, at| now - *at < Duration::minutes(RESIGNAL_AFTER_MINS));
+        for mint in self.watchlist().await? {
+            if self.signaled.contains_key(&mint) {
+                continue;
+            }
+            let candles = match self.candles(&mint, now).await {
+                Ok(candles) => candles,
+                Err(e) => {
+                    println!("[spikes] No candles for {}: {}", mint, e);
+                    continue;
+                }
+            };
+            tokio::time::sleep(BIRDEYE_DELAY).await;
+            let Some(spike) = detect(&candles, &self.rules) else {
+                continue;
+            };
+            println!(
+                "[spikes] {}: volume {:.1}x, price {:+.1}% over {} min",
+                mint, spike.volume_multiple, spike.price_change_pct, RECENT_CANDLES
+            );
+            self.signaled.insert(mint.clone(), now);
+            let signal = ExternalSignal {
+                mint,
+                confidence: None,
+                source: Some(SOURCE),
+            };
+            if self.signals.send(signal).await.is_err() {
+                bail!("signal channel closed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Pinned mints, then the most recently seen graduated tokens
+    async fn watchlist(&self) -> Result<Vec<String>> {
+        let graduated: Vec<(String,)> = sqlx::query_as(
+            "SELECT id FROM token_events \
+             WHERE graduation AND backfill_source IS NULL \
+             AND generated_at >= NOW() - make_interval(hours => $1) \
+             GROUP BY id ORDER BY MAX(generated_at) DESC LIMIT $2",
+        )
+        .bind(self.watchlist_hours as i32)
+        .bind(self.watchlist_size)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut watchlist = self.pinned.clone();
+        for (mint,) in graduated {
+            if !watchlist.contains(&mint) {
+                watchlist.push(mint);
+            }
+        }
+        Ok(watchlist)
+    }
+
+    async fn candles(&self, mint: &str, now: DateTime<Utc>) -> Result<Vec<Candle>> {
+        let from = now - Duration::minutes(LOOKBACK_MINS);
+        let resp = self
+            .client
+            .get(BIRDEYE_OHLCV_URL)
+            .query(&[
+                ("address", mint),
+                ("type", "1m"),
+                ("time_from", &from.timestamp().to_string()),
+                ("time_to", &now.timestamp().to_string()),
+            ])
+            .header("X-API-KEY", &self.birdeye_key)
+            .header("x-chain", "solana")
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("Birdeye returned {}", status);
+        }
+        parse_candles(&resp.text().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_volume_and_price_acceleration() {
+        let body = std::fs::read_to_string("tests/data/birdeye/ohlcv_spike.json").unwrap();
+        let candles = parse_candles(&body).unwrap();
+        assert_eq!(candles.len(), 20);
+        let rules = SpikeRules {
+            volume_multiplier: 5.0,
+            min_price_change_pct: 10.0,
+        };
+
+        let spike = detect(&candles, &rules).unwrap();
+        assert!((spike.volume_multiple - 9.91).abs() < 0.01);
+        assert!(spike.price_change_pct > 25.0);
+
+        // Before the spike, and with a volume surge that doesn't move the price
+        assert_eq!(detect(&candles[..17], &rules), None);
+        let mut flat = candles.clone();
+        let price = flat[17].open;
+        for c in &mut flat[17..] {
+            (c.open, c.close) = (price, price);
+        }
+        assert_eq!(detect(&flat, &rules), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Several funded trading wallets used in rotation
+//!
+//! Entries are spread over the wallets round-robin or pinned per strategy, which limits
+//! wallet-level fingerprinting and spreads nonce and token account contention. A position
+//! is always sold from the wallet that bought it.
+
+use crate::executor::LiveExecutor;
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How entries are assigned to wallets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WalletAssignment {
+    /// Each entry uses the next wallet in turn
+    RoundRobin,
+    /// Each strategy always uses the same wallet (by position in `--strategies`)
+    PerStrategy,
+}
+
+impl WalletAssignment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WalletAssignment::RoundRobin => "round-robin",
+            WalletAssignment::PerStrategy => "per-strategy",
+        }
+    }
+}
+
+impl FromStr for WalletAssignment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "round-robin" => Ok(WalletAssignment::RoundRobin),
+            "per-strategy" => Ok(WalletAssignment::PerStrategy),
+            other => bail!(
+                "unknown wallet assignment '{}' (expected round-robin or per-strategy)",
+                other
+            ),
+        }
+    }
+}
+
+pub struct WalletPool {
+    executors: Vec<LiveExecutor>,
+    assignment: WalletAssignment,
+    strategy_ids: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl WalletPool {
+    pub fn new(
+        executors: Vec<LiveExecutor>,
+        assignment: WalletAssignment,
+        strategy_ids: Vec<String>,
+    ) -> Result<Self> {
+        if executors.is_empty() {
+            bail!("no trading wallets configured");
+        }
+        Ok(Self {
+            executors,
+            assignment,
+            strategy_ids,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Wallet to open a new position for `strategy_id` with
+    pub fn for_entry(&self, strategy_id: &str) -> &LiveExecutor {
+        let idx = match self.assignment {
+            WalletAssignment::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            WalletAssignment::PerStrategy => self
+                .strategy_ids
+                .iter()
+                .position(|id| id == strategy_id)
+                .unwrap_or(0),
+        };
+        &self.executors[idx % self.executors.len()]
+    }
+
+    /// Wallet that holds a position, falling back to the primary wallet for unknown keys
+    pub fn for_exit(&self, pubkey: Option<&str>) -> &LiveExecutor {
+        pubkey
+            .and_then(|key| self.executors.iter().find(|e| e.pubkey() == key))
+            .unwrap_or_else(|| self.primary())
+    }
+
+    /// First configured wallet, used for sweeps
+    pub fn primary(&self) -> &LiveExecutor {
+        &self.executors[0]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LiveExecutor> {
+        self.executors.iter()
+    }
+
+    pub fn assignment(&self) -> WalletAssignment {
+        self.assignment
+    }
+}
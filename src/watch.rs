@@ -0,0 +1,126 @@
+//! `watch`: stream scored detections without trading or writing to the database
+//!
+//! Prints one line per enriched token with each strategy's decision, or one JSON object
+//! per line with `--json`, until interrupted.
+
+use crate::config::Config;
+use crate::portfolio_manager::PortfolioManager;
+use crate::queue::ListingQueue;
+use crate::scanner::{ListingSource, TokenDataProvider};
+use crate::simulator::enrich_listing;
+use crate::strategy::TokenEvent;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, future, stream};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct WatchLine<'a> {
+    at: DateTime<Utc>,
+    token: &'a TokenEvent,
+    decisions: Vec<WatchDecision<'a>>,
+}
+
+#[derive(Serialize)]
+struct WatchDecision<'a> {
+    strategy_id: &'a str,
+    should_buy: bool,
+    score: f64,
+    /// Share of `max_sol_per_trade` the score sizes an entry at
+    size_fraction: f64,
+    /// Entry filters that failed
+    rejections: Vec<String>,
+}
+
+pub async fn run<S: ListingSource + TokenDataProvider>(
+    scanner: &S,
+    cfg: &Config,
+    manager: &mut PortfolioManager,
+    json: bool,
+) -> Result<()> {
+    if !json {
+        println!(
+            "{:<8}  {:<44}  {:<10}  {:>9}  {:>9}  {:>7}  {:>6}  decisions",
+            "time", "mint", "symbol", "mcap", "liq", "holders", "dev%"
+        );
+    }
+    if !cfg.enable_pumpfun {
+        bail!("ENABLE_PUMPFUN is off, so there are no listings to watch");
+    }
+    let queue = ListingQueue::new(cfg.max_listing_age_secs, Default::default());
+    let steps = cfg.enrichment();
+    loop {
+        let mut listings = scanner.fetch_pumpfun_listings().await.unwrap_or_default();
+        queue.prioritize(&mut listings);
+        if let Err(e) = scanner.prefetch(&listings).await {
+            eprintln!("[scanner] batched prefetch failed: {}", e);
+        }
+        let events: Vec<TokenEvent> = stream::iter(listings)
+            .filter(|l| future::ready(queue.admit(l, Utc::now().timestamp())))
+            .map(|l| enrich_listing(scanner, l, &steps))
+            .buffered(cfg.scan_concurrency.max(1))
+            .collect()
+            .await;
+
+        for ev in &events {
+            let decisions = manager
+                .slots
+                .iter_mut()
+                .map(|slot| {
+                    let d = slot.decide(ev);
+                    (slot.id.clone(), d)
+                })
+                .collect::<Vec<_>>();
+            let at = Utc::now();
+            if json {
+                let line = WatchLine {
+                    at,
+                    token: ev,
+                    decisions: decisions
+                        .iter()
+                        .map(|(id, d)| WatchDecision {
+                            strategy_id: id,
+                            should_buy: d.should_buy,
+                            score: d.score,
+                            size_fraction: d.size_fraction,
+                            rejections: d.rejections.iter().map(|r| r.filter.clone()).collect(),
+                        })
+                        .collect(),
+                };
+                println!("{}", serde_json::to_string(&line)?);
+            } else {
+                let summary = decisions
+                    .iter()
+                    .map(|(id, d)| {
+                        let verdict = if d.should_buy { "BUY" } else { "skip" };
+                        let reasons = d
+                            .rejections
+                            .iter()
+                            .map(|r| r.filter.as_str())
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        if reasons.is_empty() {
+                            format!("{} {} {:.0}", id, verdict, d.score)
+                        } else {
+                            format!("{} {} {:.0} ({})", id, verdict, d.score, reasons)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                println!(
+                    "{:<8}  {:<44}  {:<10.10}  {:>9.0}  {:>9.0}  {:>7}  {:>6.1}  {}",
+                    at.format("%H:%M:%S"),
+                    ev.id,
+                    ev.token_type,
+                    ev.market_cap_usd,
+                    ev.liquidity_usd,
+                    ev.holders,
+                    ev.dev_hold_pct,
+                    summary
+                );
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(cfg.poll_interval_secs)).await;
+    }
+}
@@ -0,0 +1,417 @@
+//! Mints the operator wants tracked whether or not the bot holds them
+//!
+//! While the bot runs, every `WATCHLIST_INTERVAL_SECS` each watched mint's DexScreener price is
+//! stored in `price_history` (source `watchlist`). Crossing an entry's `alert_above_usd` or
+//! `alert_below_usd` is logged and sent through the notifier, once per crossing, as is every
+//! alert rule (see `alert_rules`) that fires.
+
+use crate::alert_rules::{AlertRule, Observation};
+use crate::blacklist::validate_address;
+use crate::notifier::Notifier;
+use crate::scanner::{Scanner, TokenDataProvider};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use structopt::StructOpt;
+
+const SOURCE: &str = "watchlist";
+
+#[derive(StructOpt, Debug)]
+pub enum WatchlistCommand {
+    /// Watch a mint, or change an existing entry's alerts and note
+    Add {
+        mint: String,
+        /// alert when the price rises to this many USD
+        #[structopt(long)]
+        above: Option<f64>,
+        /// alert when the price falls to this many USD
+        #[structopt(long)]
+        below: Option<f64>,
+        #[structopt(long)]
+        note: Option<String>,
+    },
+    /// Stop watching a mint
+    Remove { mint: String },
+    /// List watched mints with their last price
+    List,
+    /// Add an alert rule to a mint, watching it if needed (e.g. liquidity_drop_pct=30)
+    Alert { mint: String, rule: AlertRule },
+    /// List alert rules
+    Alerts,
+    /// Remove an alert rule by id
+    RemoveAlert { id: i64 },
+}
+
+/// A watched mint, as stored and as accepted by `POST /watchlist`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WatchlistEntry {
+    pub mint: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub alert_above_usd: Option<f64>,
+    #[serde(default)]
+    pub alert_below_usd: Option<f64>,
+    /// Price at the last check
+    #[serde(default, skip_deserializing)]
+    pub last_price_usd: Option<f64>,
+}
+
+impl WatchlistEntry {
+    pub fn validate(&self) -> Result<()> {
+        validate_address(&self.mint)?;
+        for price in [self.alert_above_usd, self.alert_below_usd]
+            .into_iter()
+            .flatten()
+        {
+            if !(price > 0.0 && price.is_finite()) {
+                bail!("alert price must be positive, got {}", price);
+            }
+        }
+        Ok(())
+    }
+
+    /// Alert for a move from `previous` to `price` across one of the thresholds
+    pub fn crossing(&self, previous: Option<f64>, price: f64) -> Option<String> {
+        if let Some(above) = self.alert_above_usd
+            && price >= above
+            && previous.is_none_or(|p| p < above)
+        {
+            return Some(format!(
+                "{} rose to ${} (alert above ${})",
+                self.mint, price, above
+            ));
+        }
+        if let Some(below) = self.alert_below_usd
+            && price <= below
+            && previous.is_none_or(|p| p > below)
+        {
+            return Some(format!(
+                "{} fell to ${} (alert below ${})",
+                self.mint, price, below
+            ));
+        }
+        None
+    }
+}
+
+/// Insert an entry, or update the alerts and note of an existing one
+pub async fn add(pool: &PgPool, entry: &WatchlistEntry) -> Result<()> {
+    entry.validate()?;
+    sqlx::query(
+        "INSERT INTO watchlist (mint, note, alert_above_usd, alert_below_usd) \
+         VALUES ($1, $2, $3, $4) ON CONFLICT (mint) DO UPDATE SET \
+         note = COALESCE(EXCLUDED.note, watchlist.note), \
+         alert_above_usd = EXCLUDED.alert_above_usd, \
+         alert_below_usd = EXCLUDED.alert_below_usd",
+    )
+    .bind(&entry.mint)
+    .bind(&entry.note)
+    .bind(entry.alert_above_usd)
+    .bind(entry.alert_below_usd)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Remove a mint; false if it wasn't watched
+pub async fn remove(pool: &PgPool, mint: &str) -> Result<bool> {
+    let removed = sqlx::query("DELETE FROM watchlist WHERE mint = $1")
+        .bind(mint)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(removed > 0)
+}
+
+pub async fn list(pool: &PgPool) -> Result<Vec<WatchlistEntry>> {
+    Ok(sqlx::query_as(
+        "SELECT mint, note, alert_above_usd, alert_below_usd, last_price_usd \
+         FROM watchlist ORDER BY added_at",
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// An alert rule on a watched mint
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AlertEntry {
+    pub id: i64,
+    pub mint: String,
+    pub rule: String,
+    /// Value the rule is measured from; unset before the first check
+    pub baseline: Option<f64>,
+}
+
+/// Add a rule to a mint, watching it first if needed; returns the rule's id
+pub async fn add_alert(pool: &PgPool, mint: &str, rule: &AlertRule) -> Result<i64> {
+    validate_address(mint)?;
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO watchlist (mint) VALUES ($1) ON CONFLICT (mint) DO NOTHING")
+        .bind(mint)
+        .execute(&mut *tx)
+        .await?;
+    let (id,): (i64,) =
+        sqlx::query_as("INSERT INTO watchlist_alerts (mint, rule) VALUES ($1, $2) RETURNING id")
+            .bind(mint)
+            .bind(rule.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Remove a rule; false if there was none with this id
+pub async fn remove_alert(pool: &PgPool, id: i64) -> Result<bool> {
+    let removed = sqlx::query("DELETE FROM watchlist_alerts WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(removed > 0)
+}
+
+pub async fn list_alerts(pool: &PgPool) -> Result<Vec<AlertEntry>> {
+    Ok(
+        sqlx::query_as("SELECT id, mint, rule, baseline FROM watchlist_alerts ORDER BY id")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+pub async fn run(pool: &PgPool, cmd: WatchlistCommand) -> Result<()> {
+    match cmd {
+        WatchlistCommand::Add {
+            mint,
+            above,
+            below,
+            note,
+        } => {
+            let entry = WatchlistEntry {
+                mint,
+                note,
+                alert_above_usd: above,
+                alert_below_usd: below,
+                last_price_usd: None,
+            };
+            add(pool, &entry).await?;
+            println!("Watching {}", entry.mint);
+        }
+        WatchlistCommand::Remove { mint } => {
+            if !remove(pool, &mint).await? {
+                bail!("{} is not watched", mint);
+            }
+            println!("Removed {}", mint);
+        }
+        WatchlistCommand::List => {
+            let entries = list(pool).await?;
+            let price = |p: Option<f64>| p.map(|p| format!("${}", p)).unwrap_or_default();
+            println!(
+                "{:<44} {:>14} {:>14} {:>14} note",
+                "mint", "last_price", "alert_above", "alert_below"
+            );
+            for e in &entries {
+                println!(
+                    "{:<44} {:>14} {:>14} {:>14} {}",
+                    e.mint,
+                    price(e.last_price_usd),
+                    price(e.alert_above_usd),
+                    price(e.alert_below_usd),
+                    e.note.as_deref().unwrap_or_default()
+                );
+            }
+            println!("{} entries", entries.len());
+        }
+        WatchlistCommand::Alert { mint, rule } => {
+            let id = add_alert(pool, &mint, &rule).await?;
+            println!("Alert {} on {}: {}", id, mint, rule);
+        }
+        WatchlistCommand::Alerts => {
+            let alerts = list_alerts(pool).await?;
+            println!("{:>5} {:<44} {:<28} baseline", "id", "mint", "rule");
+            for a in &alerts {
+                println!(
+                    "{:>5} {:<44} {:<28} {}",
+                    a.id,
+                    a.mint,
+                    a.rule,
+                    a.baseline.map(|b| b.to_string()).unwrap_or_default()
+                );
+            }
+            println!("{} alert rules", alerts.len());
+        }
+        WatchlistCommand::RemoveAlert { id } => {
+            if !remove_alert(pool, id).await? {
+                bail!("no alert rule {}", id);
+            }
+            println!("Removed alert rule {}", id);
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots watched mints' prices and raises their alerts, until the process exits
+pub struct Tracker {
+    pool: PgPool,
+    scanner: Scanner,
+    notifier: Option<Arc<Notifier>>,
+    interval: Duration,
+}
+
+impl Tracker {
+    pub fn new(
+        pool: PgPool,
+        scanner: Scanner,
+        notifier: Option<Arc<Notifier>>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            pool,
+            scanner,
+            notifier,
+            interval,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.check().await {
+                println!("[watchlist] Check failed: {}", e);
+            }
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        let alerts = list_alerts(&self.pool).await?;
+        for entry in list(&self.pool).await? {
+            let rules: Vec<(&AlertEntry, AlertRule)> = alerts
+                .iter()
+                .filter(|a| a.mint == entry.mint)
+                .filter_map(|a| match a.rule.parse() {
+                    Ok(rule) => Some((a, rule)),
+                    Err(e) => {
+                        println!("[watchlist] Skipping alert {}: {}", a.id, e);
+                        None
+                    }
+                })
+                .collect();
+            let obs = self
+                .observe(&entry.mint, rules.iter().any(|(_, r)| r.needs_holders()))
+                .await;
+
+            let mut fired = Vec::new();
+            let mut tx = self.pool.begin().await?;
+            if let Some(price) = obs.price_usd {
+                sqlx::query(
+                    "INSERT INTO price_history (token_id, observed_at, price_usd, source) \
+                     VALUES ($1, NOW(), $2, $3) ON CONFLICT DO NOTHING",
+                )
+                .bind(&entry.mint)
+                .bind(price)
+                .bind(SOURCE)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(
+                    "UPDATE watchlist SET last_price_usd = $2, last_checked_at = NOW() \
+                     WHERE mint = $1",
+                )
+                .bind(&entry.mint)
+                .bind(price)
+                .execute(&mut *tx)
+                .await?;
+                fired.extend(entry.crossing(entry.last_price_usd, price));
+            }
+            for (alert, rule) in rules {
+                let (baseline, message) = rule.check(alert.baseline, &obs);
+                sqlx::query(
+                    "UPDATE watchlist_alerts SET baseline = $2, \
+                     last_fired_at = CASE WHEN $3 THEN NOW() ELSE last_fired_at END \
+                     WHERE id = $1",
+                )
+                .bind(alert.id)
+                .bind(baseline)
+                .bind(message.is_some())
+                .execute(&mut *tx)
+                .await?;
+                fired.extend(message.map(|m| format!("{} {} ({})", entry.mint, m, rule)));
+            }
+            tx.commit().await?;
+
+            for alert in fired {
+                println!("[watchlist] {}", alert);
+                if let Some(notifier) = &self.notifier {
+                    notifier
+                        .send(&format!("memebot watchlist: {}", alert))
+                        .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up a mint's price and liquidity, and its holder count when a rule needs it
+    async fn observe(&self, mint: &str, holders: bool) -> Observation {
+        let pair = match self.scanner.query_dexscreener_pair(mint).await {
+            Ok(pair) => pair
+                .and_then(|d| d.pairs)
+                .and_then(|p| p.into_iter().next()),
+            Err(_) => None,
+        };
+        let holders = if holders {
+            match self.scanner.query_token_holder_stats(mint).await {
+                Ok(stats) => stats.and_then(|s| s.total),
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+        Observation {
+            price_usd: pair.as_ref().and_then(|p| p.price_usd).filter(|p| *p > 0.0),
+            liquidity_usd: pair.and_then(|p| p.liquidity_usd),
+            holders,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_once_per_threshold_crossing() {
+        let entry = WatchlistEntry {
+            mint: "7mGzzzwwZxgykTjNtmwgvWewdupxYe1WYJ5HDDNDpump".to_string(),
+            note: None,
+            alert_above_usd: Some(0.002),
+            alert_below_usd: Some(0.0005),
+            last_price_usd: None,
+        };
+        assert!(entry.validate().is_ok());
+
+        assert_eq!(entry.crossing(None, 0.001), None);
+        assert!(
+            entry
+                .crossing(Some(0.001), 0.0021)
+                .unwrap()
+                .contains("rose to $0.0021")
+        );
+        // Still above: no repeat
+        assert_eq!(entry.crossing(Some(0.0021), 0.0025), None);
+        assert!(
+            entry
+                .crossing(Some(0.0025), 0.0004)
+                .unwrap()
+                .contains("fell to $0.0004")
+        );
+
+        let bad = WatchlistEntry {
+            alert_below_usd: Some(-1.0),
+            ..entry
+        };
+        assert!(bad.validate().is_err());
+    }
+}
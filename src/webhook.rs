@@ -0,0 +1,200 @@
+//! Outgoing webhooks for buys, sells, rugs, dependency health changes, and mint changes on
+//! held tokens
+//!
+//! Each delivery is a JSON POST signed with HMAC-SHA256 over `"<timestamp>.<body>"`:
+//! - `X-Memebot-Event`: `buy`, `sell`, `rug`, `health`, or `mint_change`
+//! - `X-Memebot-Timestamp`: unix seconds
+//! - `X-Memebot-Signature`: `sha256=<hex digest>` (only when a secret is configured)
+
+use crate::control::{BotEvent, HealthEvent, MintChangeEvent, TradeEvent};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trade: Option<&'a TradeEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<&'a HealthEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mint_change: Option<&'a MintChangeEvent>,
+}
+
+pub struct WebhookNotifier {
+    client: Client,
+    urls: Vec<String>,
+    secret: Option<String>,
+    /// Event kinds to deliver
+    events: Vec<String>,
+    /// Sells losing at least this share of the position are reported as rugs
+    rug_loss_pct: f64,
+}
+
+impl WebhookNotifier {
+    pub fn new(
+        urls: Vec<String>,
+        secret: Option<String>,
+        events: Vec<String>,
+        rug_loss_pct: f64,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("sol-memebot/0.1")
+            .build()
+            .unwrap();
+        Self {
+            client,
+            urls,
+            secret,
+            events,
+            rug_loss_pct,
+        }
+    }
+
+    /// Deliver trade and health events until the sender side is dropped
+    pub async fn run(self, mut events: broadcast::Receiver<BotEvent>) {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    println!("[webhook] Fell behind, dropped {} events", n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let payload = match &event {
+                // Shadow trades never happened
+                BotEvent::Trade(trade) if !trade.shadow => Payload {
+                    event: self.classify(trade),
+                    trade: Some(trade),
+                    health: None,
+                    mint_change: None,
+                },
+                BotEvent::Health(health) => Payload {
+                    event: "health",
+                    trade: None,
+                    health: Some(health),
+                    mint_change: None,
+                },
+                BotEvent::MintChange(change) => Payload {
+                    event: "mint_change",
+                    trade: None,
+                    health: None,
+                    mint_change: Some(change),
+                },
+                _ => continue,
+            };
+            let kind = payload.event;
+            if !self.events.iter().any(|e| e == kind) {
+                continue;
+            }
+            let body = match serde_json::to_string(&payload) {
+                Ok(body) => body,
+                Err(e) => {
+                    println!("[webhook] Failed to serialize {} event: {}", kind, e);
+                    continue;
+                }
+            };
+            for url in &self.urls {
+                self.deliver(url, kind, &body).await;
+            }
+        }
+    }
+
+    fn classify(&self, trade: &TradeEvent) -> &'static str {
+        if trade.side == "buy" {
+            return "buy";
+        }
+        let cost = trade.usd - trade.pnl_usd.unwrap_or(0.0);
+        let loss_pct = if cost > 0.0 {
+            -trade.pnl_usd.unwrap_or(0.0) / cost * 100.0
+        } else {
+            0.0
+        };
+        if loss_pct >= self.rug_loss_pct {
+            "rug"
+        } else {
+            "sell"
+        }
+    }
+
+    /// POST with retries on network errors and 5xx responses
+    async fn deliver(&self, url: &str, kind: &str, body: &str) {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        for attempt in 1..=MAX_ATTEMPTS {
+            let mut req = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header("X-Memebot-Event", kind)
+                .header("X-Memebot-Timestamp", &timestamp)
+                .body(body.to_string());
+            if let Some(secret) = &self.secret {
+                req = req.header(
+                    "X-Memebot-Signature",
+                    format!("sha256={}", sign(secret, &timestamp, body)),
+                );
+            }
+            match req.send().await {
+                Ok(resp) if resp.status().is_success() => return,
+                Ok(resp) if resp.status().is_client_error() => {
+                    println!(
+                        "[webhook] {} rejected {} event: {}",
+                        url,
+                        kind,
+                        resp.status()
+                    );
+                    return;
+                }
+                Ok(resp) => println!(
+                    "[webhook] {} returned {} (attempt {}/{})",
+                    url,
+                    resp.status(),
+                    attempt,
+                    MAX_ATTEMPTS
+                ),
+                Err(e) => println!(
+                    "[webhook] {} failed: {} (attempt {}/{})",
+                    url, e, attempt, MAX_ATTEMPTS
+                ),
+            }
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+            }
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"`
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign("whsec_test", "1760600000", r#"{"event":"buy"}"#),
+            "4dad63f8017f41fb166152d10eb56de35c5bb7f03e4b82cf3df2313886951340"
+        );
+    }
+}
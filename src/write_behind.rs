@@ -0,0 +1,281 @@
+//! Write-behind queue for the rows the decision loop produces on every event
+//!
+//! Token events and position marks are handed to a background task and written with one
+//! INSERT per table once `WRITE_BATCH_SIZE` rows are waiting or `WRITE_FLUSH_INTERVAL_MS` has
+//! passed, so database latency stays out of decision latency. Timestamps are taken when a row
+//! is queued, not when it is written.
+//!
+//! The queue is bounded. When it is full, a token event waits for room (the loop slows down
+//! rather than losing detections) while a mark is dropped, since the next mark supersedes it.
+//! Rows of a failed batch are retried on the next tick, keeping at most a queue's worth.
+
+use crate::repo::Store;
+use crate::strategy::TokenEvent;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+/// Rows that may wait in the channel before token events block and marks are dropped
+const QUEUE_CAPACITY: usize = 10_000;
+
+pub struct EventRow {
+    pub event: TokenEvent,
+    pub score: f64,
+    /// `run_metadata` row of the run that observed the event
+    pub run_id: Option<i32>,
+    pub queued_at: Instant,
+}
+
+pub struct MarkRow {
+    pub strategy_id: String,
+    pub run_id: Option<i32>,
+    pub token_id: String,
+    pub price_usd: f64,
+    pub value_usd: f64,
+    pub unrealized_pnl: f64,
+    pub equity_usd: f64,
+    pub drawdown_pct: f64,
+    pub queued_at: Instant,
+}
+
+enum Write {
+    Event(Box<EventRow>),
+    Mark(MarkRow),
+    Flush(oneshot::Sender<()>),
+}
+
+#[derive(Debug, Default)]
+pub struct WriteCounters {
+    pub written: AtomicU64,
+    /// Token events that had to wait for room in the queue
+    pub blocked: AtomicU64,
+    /// Marks dropped because the queue was full, or rows dropped after failed writes
+    pub dropped: AtomicU64,
+    /// Batches that failed to write
+    pub failed: AtomicU64,
+}
+
+impl fmt::Display for WriteCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "written={} blocked={} dropped={} failed={}",
+            self.written.load(Ordering::Relaxed),
+            self.blocked.load(Ordering::Relaxed),
+            self.dropped.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// Handle to the background writer; dropping every handle flushes and stops it
+#[derive(Clone)]
+pub struct WriteBehind {
+    tx: mpsc::Sender<Write>,
+    pub counters: Arc<WriteCounters>,
+}
+
+impl WriteBehind {
+    /// Start a writer that adds to `counters`, which may outlive it
+    pub fn spawn(
+        store: Store,
+        batch_size: usize,
+        flush_interval: Duration,
+        counters: Arc<WriteCounters>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let writer = Writer {
+            store,
+            batch_size: batch_size.max(1),
+            events: Pending::new(QUEUE_CAPACITY),
+            marks: Pending::new(QUEUE_CAPACITY),
+            counters: counters.clone(),
+            failing: false,
+        };
+        tokio::spawn(writer.run(rx, flush_interval));
+        Self { tx, counters }
+    }
+
+    pub async fn token_event(&self, event: TokenEvent, score: f64, run_id: Option<i32>) {
+        let write = Write::Event(Box::new(EventRow {
+            event,
+            score,
+            run_id,
+            queued_at: Instant::now(),
+        }));
+        match self.tx.try_send(write) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(write)) => {
+                self.counters.blocked.fetch_add(1, Ordering::Relaxed);
+                let _ = self.tx.send(write).await;
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn mark(&self, mark: MarkRow) {
+        if self.tx.try_send(Write::Mark(mark)).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Wait until everything queued so far has been written (or has failed to be)
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.tx.send(Write::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+/// Rows waiting to be written, oldest first, holding at most `capacity`
+struct Pending<T> {
+    rows: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> Pending<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            rows: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Queue `row`; returns how many of the oldest rows were dropped to make room
+    fn push(&mut self, row: T) -> usize {
+        self.rows.push_back(row);
+        let excess = self.rows.len().saturating_sub(self.capacity);
+        self.rows.drain(..excess);
+        excess
+    }
+
+    /// Put a batch that failed to write back in front of newer rows, within capacity
+    fn requeue(&mut self, batch: Vec<T>) -> usize {
+        let room = self.capacity.saturating_sub(self.rows.len());
+        let dropped = batch.len().saturating_sub(room);
+        for row in batch.into_iter().skip(dropped).rev() {
+            self.rows.push_front(row);
+        }
+        dropped
+    }
+
+    fn take(&mut self) -> Vec<T> {
+        self.rows.drain(..).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+struct Writer {
+    store: Store,
+    batch_size: usize,
+    events: Pending<EventRow>,
+    marks: Pending<MarkRow>,
+    counters: Arc<WriteCounters>,
+    /// Set after a failed write; retries then wait for the next tick instead of the batch size
+    failing: bool,
+}
+
+impl Writer {
+    async fn run(mut self, mut rx: mpsc::Receiver<Write>, flush_interval: Duration) {
+        let mut ticker = tokio::time::interval(flush_interval);
+        loop {
+            tokio::select! {
+                write = rx.recv() => match write {
+                    Some(Write::Event(row)) => {
+                        let dropped = self.events.push(*row);
+                        self.counters.dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+                    }
+                    Some(Write::Mark(row)) => {
+                        let dropped = self.marks.push(row);
+                        self.counters.dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+                    }
+                    Some(Write::Flush(done)) => {
+                        self.flush().await;
+                        let _ = done.send(());
+                        continue;
+                    }
+                    None => {
+                        self.flush().await;
+                        return;
+                    }
+                },
+                _ = ticker.tick() => {
+                    self.flush().await;
+                    continue;
+                }
+            }
+            if !self.failing && self.events.len() + self.marks.len() >= self.batch_size {
+                self.flush().await;
+            }
+        }
+    }
+
+    async fn flush(&mut self) {
+        self.failing = false;
+        let events = self.events.take();
+        if !events.is_empty() {
+            match self.store.insert_events(&events).await {
+                Ok(()) => self.written(events.len()),
+                Err(e) => {
+                    println!("[db] failed to write {} token events: {}", events.len(), e);
+                    self.failing = true;
+                    self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    let dropped = self.events.requeue(events);
+                    self.counters
+                        .dropped
+                        .fetch_add(dropped as u64, Ordering::Relaxed);
+                }
+            }
+        }
+        let marks = self.marks.take();
+        if !marks.is_empty() {
+            match self.store.insert_marks(&marks).await {
+                Ok(()) => self.written(marks.len()),
+                Err(e) => {
+                    println!("[db] failed to write {} position marks: {}", marks.len(), e);
+                    self.failing = true;
+                    self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    let dropped = self.marks.requeue(marks);
+                    self.counters
+                        .dropped
+                        .fetch_add(dropped as u64, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    fn written(&self, rows: usize) {
+        self.counters
+            .written
+            .fetch_add(rows as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_rows_within_capacity() {
+        let mut pending = Pending::new(3);
+        for row in 1..=4 {
+            pending.push(row);
+        }
+        let batch = pending.take();
+        assert_eq!(batch, vec![2, 3, 4]);
+
+        // A failed batch goes back in front of rows queued meanwhile, oldest dropped first
+        pending.push(5);
+        assert_eq!(pending.requeue(batch), 1);
+        assert_eq!(pending.take(), vec![3, 4, 5]);
+    }
+}